@@ -0,0 +1,45 @@
+//! 编译期注入构建信息：git 提交与构建时间，供 /version 与启动日志使用。
+//! 不在 git 仓库中构建时（如源码包），可通过环境变量 SERVICEKIT_GIT_COMMIT 指定提交。
+
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    println!("cargo:rerun-if-env-changed=SERVICEKIT_GIT_COMMIT");
+
+    let commit = std::env::var("SERVICEKIT_GIT_COMMIT")
+        .ok()
+        .filter(|commit| !commit.trim().is_empty())
+        .or_else(git_commit)
+        .unwrap_or_else(|| "unknown".to_string());
+    let build_timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    println!("cargo:rustc-env=SERVICEKIT_GIT_COMMIT={}", commit.trim());
+    println!("cargo:rustc-env=SERVICEKIT_BUILD_TIMESTAMP={build_timestamp}");
+}
+
+fn git_commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let commit = String::from_utf8(output.stdout).ok()?;
+    let dirty = Command::new("git")
+        .args(["status", "--porcelain", "--untracked-files=no"])
+        .output()
+        .is_ok_and(|status| !status.stdout.is_empty());
+    Some(if dirty {
+        format!("{}-dirty", commit.trim())
+    } else {
+        commit.trim().to_string()
+    })
+}
@@ -0,0 +1,6 @@
+-- mss_push_result 增加单条推送的耗时、HTTP 状态码、请求次数和推送地址，用于按记录分析延迟与重试情况
+ALTER TABLE mss_push_result
+    ADD COLUMN duration_ms   BIGINT       NULL COMMENT '推送耗时（毫秒，含重试与休眠）',
+    ADD COLUMN http_status   INT          NULL COMMENT 'MSS 返回的 HTTP 状态码',
+    ADD COLUMN attempt_count INT          NULL COMMENT '实际发送请求的次数',
+    ADD COLUMN endpoint      VARCHAR(512) NULL COMMENT '推送的 MSS 地址';
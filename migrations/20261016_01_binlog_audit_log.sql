@@ -0,0 +1,13 @@
+-- binlog 同步落库后的变更审计，每条成功处理的 ModifyOperationLog 记录一次最终数据快照
+CREATE TABLE IF NOT EXISTS binlog_audit_log
+(
+    id               BIGINT       NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    entity_type      VARCHAR(32)  NOT NULL COMMENT '数据类型：org / user',
+    entity_id        VARCHAR(64)  NOT NULL COMMENT '实体ID（日志中的 cid）',
+    log_id           VARCHAR(64)  NOT NULL COMMENT 'ModifyOperationLog.id',
+    operation        VARCHAR(32)  NOT NULL COMMENT '变更操作',
+    data_modify_time BIGINT       NOT NULL COMMENT '源数据修改时间（毫秒时间戳）',
+    applied_at       DATETIME     NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '落库时间',
+    snapshot         JSON         NOT NULL COMMENT '写入 d_* 表的最终数据',
+    KEY idx_entity (entity_type, entity_id, data_modify_time)
+) COMMENT = 'binlog 变更审计';
@@ -0,0 +1,12 @@
+-- 每次推送任务按 hit_date 执行后的结果汇总，用于与同一 hit_date 的上一次执行比较
+CREATE TABLE IF NOT EXISTS mss_push_run
+(
+    id          BIGINT       NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    task_name   VARCHAR(64)  NOT NULL COMMENT '推送任务名称',
+    hit_date    VARCHAR(10)  NOT NULL COMMENT '推送的数据日期（yyyy-MM-dd）',
+    run_time    DATETIME     NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '执行时间',
+    success_ids JSON         NOT NULL COMMENT '本次推送成功的ID列表',
+    failed_ids  JSON         NOT NULL COMMENT '本次推送失败的ID列表',
+    diff        JSON         NULL COMMENT '与上一次执行相比的变化，首次执行为 NULL',
+    KEY idx_task_date (task_name, hit_date, id)
+) COMMENT = '推送任务执行汇总';
@@ -0,0 +1,11 @@
+-- 手动操作审计：记录通过管理接口触发的推送、binlog 同步等操作的调用方与参数
+CREATE TABLE IF NOT EXISTS admin_audit
+(
+    id         BIGINT       NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    action     VARCHAR(64)  NOT NULL COMMENT '操作，如 push_mss / binlog_sync',
+    caller     VARCHAR(128) NOT NULL COMMENT '调用方身份（API key 名称），未认证为 anonymous',
+    source_ip  VARCHAR(64)  NULL COMMENT '请求来源IP',
+    params     JSON         NOT NULL COMMENT '请求参数',
+    created_at DATETIME     NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '操作时间',
+    KEY idx_created_at (created_at)
+) COMMENT = '管理接口操作审计';
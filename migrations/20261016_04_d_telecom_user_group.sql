@@ -0,0 +1,9 @@
+-- 用户与用户组的关系表，由 binlog 用户同步维护（与 d_telecom_user 同一事务内先删后插）
+-- d_telecom_user.user_group_ids 仍保留逗号拼接的字符串，供现有查询使用
+CREATE TABLE IF NOT EXISTS d_telecom_user_group
+(
+    user_id  VARCHAR(64) NOT NULL COMMENT '网大用户ID，对应 d_telecom_user.id',
+    group_id VARCHAR(64) NOT NULL COMMENT '用户组ID',
+    PRIMARY KEY (user_id, group_id),
+    KEY idx_group_id (group_id)
+) COMMENT = '用户所属用户组';
@@ -0,0 +1,21 @@
+-- 任务执行编号：每次推送任务执行 / binlog 同步周期生成一条，自增 id 即 run_id
+CREATE TABLE IF NOT EXISTS task_run
+(
+    id         BIGINT UNSIGNED NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    task_name  VARCHAR(128)    NOT NULL COMMENT '任务名称',
+    started_at DATETIME        NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '开始时间',
+    KEY idx_task_name (task_name, started_at)
+) COMMENT = '任务执行记录';
+
+-- 各执行产物关联到产生它的 run_id，执行编号生成失败时为 NULL
+ALTER TABLE mss_push_result
+    ADD COLUMN run_id BIGINT UNSIGNED NULL COMMENT 'task_run.id',
+    ADD KEY idx_run_id (run_id);
+
+ALTER TABLE mss_push_run
+    ADD COLUMN run_id BIGINT UNSIGNED NULL COMMENT 'task_run.id',
+    ADD KEY idx_run_id (run_id);
+
+ALTER TABLE binlog_audit_log
+    ADD COLUMN run_id BIGINT UNSIGNED NULL COMMENT 'task_run.id',
+    ADD KEY idx_run_id (run_id);
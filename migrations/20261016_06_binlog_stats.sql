@@ -0,0 +1,38 @@
+-- binlog 同步统计：每处理完一批日志（同一数据类型、同一优先级）写入一条
+CREATE TABLE IF NOT EXISTS binlog_batch_stats
+(
+    id              BIGINT UNSIGNED NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    run_id          BIGINT UNSIGNED NULL COMMENT 'task_run.id',
+    data_type       VARCHAR(32)     NOT NULL COMMENT '数据类型：org / user',
+    priority        VARCHAR(16)     NOT NULL COMMENT '优先级：high / normal',
+    batch_size      INT UNSIGNED    NOT NULL COMMENT '本批日志数',
+    succeeded       INT UNSIGNED    NOT NULL COMMENT '处理成功的日志数',
+    failed          INT UNSIGNED    NOT NULL COMMENT '永久失败的日志数',
+    exhausted       INT UNSIGNED    NOT NULL COMMENT '重试次数用尽仍未处理的日志数',
+    failure_reasons JSON            NOT NULL COMMENT '永久失败原因 -> 次数',
+    avg_lag_ms      BIGINT          NOT NULL COMMENT '源数据修改到处理完成的平均延迟（毫秒）',
+    max_lag_ms      BIGINT          NOT NULL COMMENT '最大延迟（毫秒）',
+    save_failed     TINYINT(1)      NOT NULL DEFAULT 0 COMMENT 'd_* 表落库是否失败',
+    created_at      DATETIME        NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '记录时间',
+    KEY idx_created_at (created_at, data_type)
+) COMMENT = 'binlog 每批处理统计';
+
+-- 每日汇总，由 binlog 日报任务按天重算覆盖
+CREATE TABLE IF NOT EXISTS binlog_daily_digest
+(
+    stat_date       DATE            NOT NULL COMMENT '统计日期',
+    data_type       VARCHAR(32)     NOT NULL COMMENT '数据类型：org / user',
+    batches         INT UNSIGNED    NOT NULL COMMENT '批次数',
+    logs            INT UNSIGNED    NOT NULL COMMENT '日志总数',
+    succeeded       INT UNSIGNED    NOT NULL COMMENT '处理成功的日志数',
+    failed          INT UNSIGNED    NOT NULL COMMENT '永久失败的日志数',
+    exhausted       INT UNSIGNED    NOT NULL COMMENT '重试次数用尽的日志数',
+    save_failures   INT UNSIGNED    NOT NULL COMMENT '落库失败的批次数',
+    failure_reasons JSON            NOT NULL COMMENT '永久失败原因 -> 次数',
+    avg_lag_ms      BIGINT          NOT NULL COMMENT '按日志数加权的平均延迟（毫秒）',
+    max_lag_ms      BIGINT          NOT NULL COMMENT '最大延迟（毫秒）',
+    avg_batch_size  DOUBLE          NOT NULL COMMENT '平均批大小',
+    max_batch_size  INT UNSIGNED    NOT NULL COMMENT '最大批大小',
+    created_at      DATETIME        NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '生成时间',
+    PRIMARY KEY (stat_date, data_type)
+) COMMENT = 'binlog 同步每日汇总';
@@ -0,0 +1,14 @@
+-- 推送结果按省份汇总：每次推送任务执行后，每个省份写入一条
+CREATE TABLE IF NOT EXISTS mss_push_province_stats
+(
+    id         BIGINT UNSIGNED NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    run_id     BIGINT UNSIGNED NULL COMMENT 'task_run.id',
+    task_name  VARCHAR(64)     NOT NULL COMMENT '推送任务名称',
+    hit_date   VARCHAR(10)     NULL COMMENT '推送的数据日期（yyyy-MM-dd），按 ID 推送时为 NULL',
+    province   VARCHAR(64)     NOT NULL COMMENT '记录所属组织的省份，无法确定时为“未知”',
+    succeeded  INT UNSIGNED    NOT NULL COMMENT '推送成功的记录数',
+    failed     INT UNSIGNED    NOT NULL COMMENT '推送失败的记录数',
+    created_at DATETIME        NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '记录时间',
+    KEY idx_task_date (task_name, hit_date),
+    KEY idx_created_at (created_at, province)
+) COMMENT = '推送结果按省份统计';
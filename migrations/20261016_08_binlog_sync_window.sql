@@ -0,0 +1,13 @@
+-- binlog 已完整处理的时间窗口，相邻或重叠的窗口合并为一条，用于发现未覆盖的区间
+CREATE TABLE IF NOT EXISTS binlog_sync_window
+(
+    id              BIGINT UNSIGNED NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    run_id          BIGINT UNSIGNED NULL COMMENT 'task_run.id',
+    data_type       VARCHAR(32)     NOT NULL COMMENT '数据类型：org / user',
+    start_time      BIGINT          NOT NULL COMMENT '窗口起点（毫秒时间戳）',
+    end_time        BIGINT          NOT NULL COMMENT '窗口终点（毫秒时间戳）',
+    records         INT UNSIGNED    NOT NULL DEFAULT 0 COMMENT '窗口内处理的日志数',
+    created_at      DATETIME        NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '记录时间',
+    updated_at      DATETIME        NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '最后合并时间',
+    KEY idx_type_end (data_type, end_time)
+) COMMENT = 'binlog 已处理时间窗口';
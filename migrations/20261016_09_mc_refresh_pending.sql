@@ -0,0 +1,11 @@
+-- mc_* 表刷新 SQL 与目标表结构不兼容时，记录未能刷新的 ID，结构修复后重新刷新
+CREATE TABLE IF NOT EXISTS mc_refresh_pending
+(
+    id              BIGINT UNSIGNED NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    run_id          BIGINT UNSIGNED NULL COMMENT 'task_run.id',
+    target_table    VARCHAR(64)     NOT NULL COMMENT '刷新的目标表：mc_org_show / mc_user_ztk',
+    entity_ids      JSON            NOT NULL COMMENT '未能刷新的 ID',
+    error           TEXT            NOT NULL COMMENT '数据库返回的错误',
+    created_at      DATETIME        NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '记录时间',
+    KEY idx_table_created (target_table, created_at)
+) COMMENT = 'mc_* 表待刷新记录';
@@ -0,0 +1,4 @@
+-- 推送执行期间 MSS 返回需要休息（9019）的次数与累计等待时长
+ALTER TABLE mss_push_run
+    ADD COLUMN throttle_events  INT UNSIGNED    NOT NULL DEFAULT 0 COMMENT 'MSS 返回 9019 的次数',
+    ADD COLUMN throttle_wait_ms BIGINT UNSIGNED NOT NULL DEFAULT 0 COMMENT '因 9019 累计等待的毫秒数';
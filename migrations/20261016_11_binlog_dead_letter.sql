@@ -0,0 +1,14 @@
+-- 处理完成但多次落库失败的 binlog 日志，保存原始日志以便重新处理
+CREATE TABLE IF NOT EXISTS binlog_dead_letter
+(
+    id               BIGINT UNSIGNED NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    run_id           BIGINT UNSIGNED NULL COMMENT 'task_run.id',
+    data_type        VARCHAR(32)     NOT NULL COMMENT '数据类型：org / user',
+    priority         VARCHAR(16)     NOT NULL COMMENT '优先级：high / normal',
+    log_id           VARCHAR(64)     NOT NULL COMMENT 'binlog 日志 ID',
+    data_modify_time BIGINT          NOT NULL COMMENT '源数据修改时间（毫秒时间戳）',
+    log              JSON            NOT NULL COMMENT '原始日志',
+    error            TEXT            NOT NULL COMMENT '最后一次落库的错误',
+    created_at       DATETIME        NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '记录时间',
+    KEY idx_type_created (data_type, created_at)
+) COMMENT = 'binlog 落库失败的日志';
@@ -0,0 +1,9 @@
+-- binlog_dead_letter 同时保存永久失败的日志，并支持重新入队处理
+ALTER TABLE binlog_dead_letter
+    ADD COLUMN failure_kind VARCHAR(16)  NOT NULL DEFAULT 'save_failed' COMMENT '失败类型：permanent / save_failed' AFTER priority,
+    ADD COLUMN state        VARCHAR(32)  NULL COMMENT '永久失败时到达的处理状态' AFTER failure_kind,
+    ADD COLUMN retry_count  INT UNSIGNED NOT NULL DEFAULT 0 COMMENT '同一日志已重新入队的次数' AFTER error,
+    ADD COLUMN status       VARCHAR(16)  NOT NULL DEFAULT 'pending' COMMENT '状态：pending / requeued' AFTER retry_count,
+    ADD COLUMN requeued_at  DATETIME     NULL COMMENT '重新入队时间' AFTER status,
+    ADD KEY idx_status_created (status, created_at),
+    ADD KEY idx_log_id (log_id);
@@ -0,0 +1,22 @@
+-- 基准岗位，由 binlog 同步 standardstation 类型的变更写入
+CREATE TABLE IF NOT EXISTS d_telecom_standard_station
+(
+    id                   VARCHAR(64)  NOT NULL PRIMARY KEY COMMENT '岗位 ID',
+    code                 VARCHAR(64)  NULL COMMENT '岗位编码',
+    name                 VARCHAR(255) NULL COMMENT '岗位名称',
+    station_system       VARCHAR(64)  NULL COMMENT '岗位体系',
+    station_sequence     VARCHAR(64)  NULL COMMENT '岗位序列',
+    station_level        VARCHAR(64)  NULL COMMENT '岗位层级',
+    station_grade_system VARCHAR(64)  NULL COMMENT '职级体系',
+    station_grade        VARCHAR(64)  NULL COMMENT '职级',
+    weight               INT          NULL COMMENT '排序权重',
+    d_delete             VARCHAR(8)   NULL COMMENT '是否删除',
+    is_delete            VARCHAR(8)   NULL COMMENT '是否删除（源系统标记）',
+    datelastmodified     BIGINT       NULL COMMENT '源数据修改时间（毫秒时间戳）',
+    hitdate              VARCHAR(16)  NULL COMMENT '业务日期 yyyy-MM-dd',
+    intime               DATETIME     NULL COMMENT '写入时间',
+    year                 VARCHAR(4)   NULL COMMENT '年',
+    month                VARCHAR(2)   NULL COMMENT '月',
+    hitdate1             DATETIME     NULL COMMENT '写入时间',
+    KEY idx_code (code)
+) COMMENT = '基准岗位';
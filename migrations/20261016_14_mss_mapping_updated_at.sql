@@ -0,0 +1,7 @@
+-- d_mss_*_mapping 记录写入时间，binlog 处理时 mapping_cache.max_age 内写入的映射直接使用，不再调用网关
+ALTER TABLE d_mss_org_mapping
+    ADD COLUMN updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '映射写入时间';
+
+ALTER TABLE d_mss_user_mapping
+    ADD COLUMN updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '映射写入时间',
+    ADD KEY idx_userid_updated (userid, updated_at);
@@ -0,0 +1,16 @@
+-- task_run 同时记录执行结果：cron 触发、手动触发、依赖任务与组合任务的子任务都会登记，id 即各产物中的 run_id
+ALTER TABLE task_run
+    ADD COLUMN parent_id     BIGINT UNSIGNED NULL COMMENT '组合任务子任务所属的执行 task_run.id' AFTER id,
+    ADD COLUMN triggered_by  VARCHAR(32)     NOT NULL DEFAULT 'direct' COMMENT '触发方式：cron / manual / startup / dependent / subtask / direct' AFTER task_name,
+    ADD COLUMN status        VARCHAR(16)     NOT NULL DEFAULT 'running' COMMENT '状态：running / success / failed / unknown（迁移前登记的执行）' AFTER triggered_by,
+    ADD COLUMN error_message TEXT            NULL COMMENT '失败时的错误' AFTER status,
+    ADD COLUMN rows_fetched  BIGINT UNSIGNED NOT NULL DEFAULT 0 COMMENT '读取的记录数' AFTER error_message,
+    ADD COLUMN rows_pushed   BIGINT UNSIGNED NOT NULL DEFAULT 0 COMMENT '推送成功的记录数' AFTER rows_fetched,
+    ADD COLUMN rows_failed   BIGINT UNSIGNED NOT NULL DEFAULT 0 COMMENT '推送失败的记录数' AFTER rows_pushed,
+    ADD COLUMN finished_at   DATETIME        NULL COMMENT '结束时间，进程中途退出时为 NULL' AFTER started_at,
+    ADD COLUMN duration_ms   BIGINT UNSIGNED NULL COMMENT '执行耗时（毫秒）' AFTER finished_at,
+    ADD KEY idx_started (started_at),
+    ADD KEY idx_parent (parent_id);
+
+-- 此前登记的执行没有记录结果
+UPDATE task_run SET status = 'unknown' WHERE finished_at IS NULL;
@@ -0,0 +1,15 @@
+-- 组合推送每次执行结束后生成的归档清单，manifest 为签名的原文
+CREATE TABLE IF NOT EXISTS composite_run_manifest
+(
+    id            BIGINT UNSIGNED NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    run_id        BIGINT UNSIGNED NULL COMMENT '组合任务的执行编号 task_run.id，分配失败时为 NULL',
+    task_name     VARCHAR(128)    NOT NULL COMMENT '组合任务名称',
+    manifest_date DATE            NOT NULL COMMENT '清单日期：子任务的 hit_date，按 ID 推送时为执行日期',
+    location      VARCHAR(512)    NULL COMMENT '清单文件地址（file://... 或 s3://...），只记录在数据库时为 NULL',
+    sha256        CHAR(64)        NOT NULL COMMENT '清单原文的 SHA-256',
+    signature     CHAR(64)        NOT NULL COMMENT '清单原文的 HMAC-SHA256 签名',
+    manifest      MEDIUMTEXT      NOT NULL COMMENT '清单原文（JSON），保留签名时的原样以便验签',
+    created_at    DATETIME        NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    KEY idx_manifest_date (manifest_date),
+    KEY idx_run_id (run_id)
+) COMMENT = '组合推送归档清单';
@@ -0,0 +1,18 @@
+-- 影子推送到备用 MSS 环境的请求与响应，与主推送的 data_archiving_mss_record 分开记录
+CREATE TABLE IF NOT EXISTS mss_shadow_push_reply
+(
+    id             BIGINT UNSIGNED NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    run_id         BIGINT UNSIGNED NULL COMMENT '所属执行 task_run.id，不在任务执行中时为 NULL',
+    data_type      VARCHAR(32)     NOT NULL COMMENT '数据键名，如 classData',
+    record_ids     TEXT            NOT NULL COMMENT '本次请求的记录 ID，逗号分隔',
+    endpoint       VARCHAR(512)    NOT NULL COMMENT '影子环境地址',
+    outcome        VARCHAR(16)     NOT NULL COMMENT 'success / rejected / failed',
+    primary_ok     TINYINT(1)      NOT NULL COMMENT '同一批主推送是否成功',
+    http_status    SMALLINT        NULL COMMENT '影子环境返回的 HTTP 状态码，请求未完成时为 NULL',
+    duration_ms    BIGINT          NOT NULL COMMENT '请求耗时（毫秒）',
+    request_body   MEDIUMTEXT      NOT NULL COMMENT '请求 JSON',
+    response       MEDIUMTEXT      NULL COMMENT '响应体或错误信息',
+    created_at     DATETIME        NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    KEY idx_created_at (created_at),
+    KEY idx_run_id (run_id)
+) COMMENT = '影子推送记录';
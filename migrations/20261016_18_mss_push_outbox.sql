@@ -0,0 +1,15 @@
+-- 推送发件箱：推送前写入，trainNotifyMss 回写后删除，进程中断后启动时据此重推或补写状态
+CREATE TABLE IF NOT EXISTS mss_push_outbox
+(
+    id            BIGINT UNSIGNED NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    psn_data_kind VARCHAR(32)     NOT NULL COMMENT '数据种类，如 class、lecturer_sc',
+    record_id     VARCHAR(64)     NOT NULL COMMENT '记录 ID，与回写 trainNotifyMss 时的 ID 一致',
+    status        VARCHAR(16)     NOT NULL COMMENT 'pending：待推送，sent：已发出请求，acked：MSS 已接收，failed：推送失败',
+    record        MEDIUMTEXT      NOT NULL COMMENT '推送的记录（JSON），用于重推',
+    error         VARCHAR(1024)   NULL COMMENT '失败原因，回写 trainNotifyMssMessage 时使用',
+    run_id        BIGINT UNSIGNED NULL COMMENT '最后一次写入时的 task_run.id',
+    created_at    DATETIME        NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at    DATETIME        NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    UNIQUE KEY uk_kind_record (psn_data_kind, record_id),
+    KEY idx_status_updated_at (status, updated_at)
+) COMMENT = 'MSS 推送发件箱';
@@ -0,0 +1,14 @@
+-- ClickHouse 重放队列：语句在某个节点执行失败时写入，节点恢复后由后台 worker 按 id 顺序重放
+CREATE TABLE IF NOT EXISTS clickhouse_replay_queue
+(
+    id         BIGINT UNSIGNED NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    node       VARCHAR(255)    NOT NULL COMMENT '执行失败的节点，host:port',
+    statement  MEDIUMTEXT      NOT NULL COMMENT '需要重放的语句',
+    status     VARCHAR(16)     NOT NULL DEFAULT 'pending' COMMENT 'pending：待重放，replayed：已重放，abandoned：超过重试次数放弃',
+    attempts   INT UNSIGNED    NOT NULL DEFAULT 0 COMMENT '重放失败的次数',
+    error      VARCHAR(1024)   NULL COMMENT '最近一次失败的原因',
+    run_id     BIGINT UNSIGNED NULL COMMENT '写入时的 task_run.id',
+    created_at DATETIME        NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME        NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    KEY idx_status_node (status, node, id)
+) COMMENT = 'ClickHouse 失败语句重放队列';
@@ -0,0 +1,5 @@
+-- 推送发件箱不再使用 sent 状态：推送前写入 pending，请求后直接更新为 acked / failed
+UPDATE mss_push_outbox SET status = 'pending' WHERE status = 'sent';
+
+ALTER TABLE mss_push_outbox
+    MODIFY COLUMN status VARCHAR(16) NOT NULL COMMENT 'pending：已写入、推送结果未知，acked：MSS 已接收，failed：推送失败';
@@ -0,0 +1,6 @@
+-- d_mss_*_mapping 改为按 code / userid 覆盖写入，写入时间只在网关重新返回映射时刷新
+ALTER TABLE d_mss_org_mapping
+    ADD UNIQUE KEY uk_code (code);
+
+ALTER TABLE d_mss_user_mapping
+    ADD UNIQUE KEY uk_userid (userid);
@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::binlog::refresh::REFRESH_QUERIES;
+use crate::schedule::binlog_sync::DataType;
+
+// 推送失败率（按天）超过该值告警
+const PUSH_FAILURE_RATIO_THRESHOLD: f64 = 0.2;
+// binlog 处理延迟 P95 超过该秒数告警
+const BINLOG_LAG_THRESHOLD_SECONDS: u64 = 600;
+
+/// Prometheus 告警规则，序列化后与规则文件格式一致（JSON 也是合法的 YAML）
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertRule {
+    pub alert: String,
+    pub expr: String,
+    #[serde(rename = "for")]
+    pub for_duration: String,
+    pub labels: BTreeMap<String, String>,
+    pub annotations: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertRuleGroup {
+    pub name: String,
+    pub rules: Vec<AlertRule>,
+}
+
+/// 生成告警规则所需的任务信息，由 TaskSchedulerManager 在注册任务后填写
+#[derive(Debug, Default)]
+pub struct MonitoredTasks {
+    /// 推送任务名称（push_records_total 的 task 标签）
+    pub push_tasks: Vec<String>,
+    /// 通过 cron 调度的任务
+    pub cron_jobs: Vec<String>,
+    /// 持续运行的任务，以及超过多久没有成功视为停滞
+    pub continuous_jobs: Vec<(String, Duration)>,
+    pub binlog_data_types: Vec<DataType>,
+}
+
+fn rule(
+    alert: &str,
+    expr: String,
+    for_duration: &str,
+    severity: &str,
+    summary: String,
+) -> AlertRule {
+    AlertRule {
+        alert: alert.to_string(),
+        expr,
+        for_duration: for_duration.to_string(),
+        labels: BTreeMap::from([("severity".to_string(), severity.to_string())]),
+        annotations: BTreeMap::from([("summary".to_string(), summary)]),
+    }
+}
+
+pub fn generate_alert_rules(tasks: &MonitoredTasks) -> Vec<AlertRuleGroup> {
+    let mut push_rules: Vec<AlertRule> = tasks
+        .push_tasks
+        .iter()
+        .map(|task| {
+            rule(
+                "PushFailureRatioHigh",
+                format!(
+                    "sum(increase(push_records_total{{task=\"{task}\",outcome=\"failed\"}}[1d])) \
+                     / clamp_min(sum(increase(push_records_total{{task=\"{task}\"}}[1d])), 1) \
+                     > {PUSH_FAILURE_RATIO_THRESHOLD}"
+                ),
+                "0m",
+                "warning",
+                format!(
+                    "{task} failed to push more than {:.0}% of records in the last day",
+                    PUSH_FAILURE_RATIO_THRESHOLD * 100.0
+                ),
+            )
+        })
+        .collect();
+    push_rules.push(rule(
+        "PushStatusResidueHigh",
+        "sum by (store, table) (increase(push_status_residue_exceeded_total[1h])) > 0".to_string(),
+        "0m",
+        "warning",
+        "Rows pushed to MSS are still pending after the status write-back, they will be pushed again".to_string(),
+    ));
+    push_rules.push(rule(
+        "PushStatusMismatch",
+        "sum by (table) (increase(push_status_mismatch_total[1h])) > 0".to_string(),
+        "0m",
+        "warning",
+        "Push statuses in ClickHouse differ from MySQL after a push run".to_string(),
+    ));
+
+    let mut binlog_rules: Vec<AlertRule> = tasks
+        .binlog_data_types
+        .iter()
+        .map(|data_type| {
+            let label = data_type.as_label();
+            rule(
+                "BinlogLagHigh",
+                format!(
+                    "histogram_quantile(0.95, sum by (le) \
+                     (rate(binlog_processing_lag_seconds_bucket{{data_type=\"{label}\"}}[15m]))) \
+                     > {BINLOG_LAG_THRESHOLD_SECONDS}"
+                ),
+                "15m",
+                "warning",
+                format!("Binlog {label} changes are applied more than {BINLOG_LAG_THRESHOLD_SECONDS}s after they happen"),
+            )
+        })
+        .collect();
+    binlog_rules.extend(REFRESH_QUERIES.iter().map(|query| {
+        let table = query.table;
+        rule(
+            "BinlogRefreshIncompatible",
+            format!("increase(binlog_refresh_incompatible_total{{table=\"{table}\"}}[1h]) > 0"),
+            "0m",
+            "critical",
+            format!("Refresh query for {table} no longer matches the table schema, affected ids are kept in mc_refresh_pending"),
+        )
+    }));
+
+    let mut scheduler_rules: Vec<AlertRule> = tasks
+        .cron_jobs
+        .iter()
+        .chain(tasks.continuous_jobs.iter().map(|(job, _)| job))
+        .map(|job| {
+            rule(
+                "SchedulerJobAbsent",
+                format!("absent(scheduler_job_registered{{job=\"{job}\"}})"),
+                "10m",
+                "critical",
+                format!("Job '{job}' is not registered with the scheduler"),
+            )
+        })
+        .collect();
+    scheduler_rules.extend(tasks.continuous_jobs.iter().map(|(job, stale_after)| {
+        let seconds = stale_after.as_secs();
+        rule(
+            "SchedulerJobStalled",
+            format!(
+                "time() - scheduler_job_last_success_timestamp_seconds{{job=\"{job}\"}} > {seconds}"
+            ),
+            "0m",
+            "critical",
+            format!("Job '{job}' has not completed successfully for more than {seconds}s"),
+        )
+    }));
+
+    let db_rules = vec![rule(
+        "MysqlPoolExhausted",
+        "sum by (context) (increase(db_pool_exhausted_total[10m])) > 0".to_string(),
+        "0m",
+        "warning",
+        "MySQL connection pool was exhausted, affected tasks are retried later".to_string(),
+    )];
+
+    vec![
+        AlertRuleGroup {
+            name: "servicekit-push".to_string(),
+            rules: push_rules,
+        },
+        AlertRuleGroup {
+            name: "servicekit-binlog".to_string(),
+            rules: binlog_rules,
+        },
+        AlertRuleGroup {
+            name: "servicekit-scheduler".to_string(),
+            rules: scheduler_rules,
+        },
+        AlertRuleGroup {
+            name: "servicekit-db".to_string(),
+            rules: db_rules,
+        },
+    ]
+}
+
+#[test]
+fn test_generate_alert_rules() {
+    let tasks = MonitoredTasks {
+        push_tasks: vec!["PsnClassPushTask".to_string()],
+        cron_jobs: vec!["培训班数据归档到MSS定时任务".to_string()],
+        continuous_jobs: vec![("BinlogSyncTask".to_string(), Duration::from_secs(600))],
+        binlog_data_types: vec![DataType::Org, DataType::User],
+    };
+    let groups = generate_alert_rules(&tasks);
+    let counts: Vec<usize> = groups.iter().map(|group| group.rules.len()).collect();
+    assert_eq!(counts, vec![3, 4, 3, 1]);
+    assert!(
+        groups[0].rules[0]
+            .expr
+            .contains("task=\"PsnClassPushTask\"")
+    );
+    assert!(groups[1].rules[1].expr.contains("data_type=\"user\""));
+    assert!(groups[1].rules[3].expr.contains("table=\"mc_user_ztk\""));
+    assert_eq!(
+        groups[2].rules[2].expr,
+        "time() - scheduler_job_last_success_timestamp_seconds{job=\"BinlogSyncTask\"} > 600"
+    );
+}
@@ -0,0 +1,207 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sqlx::{MySql, MySqlPool, QueryBuilder, Row};
+
+use crate::db::query_runner::QueryRunner;
+use crate::models::task_run::current_run_id;
+use crate::schedule::binlog_sync::{DataType, ModifyOperationLog};
+
+/// 一条已落库的 binlog 变更，snapshot 为写入 d_* 表的最终数据
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub data_type: DataType,
+    pub entity_id: String,
+    pub log_id: String,
+    pub operation: String,
+    pub data_modify_time: i64,
+    pub snapshot: Value,
+    pub run_id: Option<u64>,
+}
+
+impl AuditEntry {
+    /// 没有 cid 的日志无法定位实体，不记录
+    pub fn from_log(
+        data_type: DataType,
+        log: &ModifyOperationLog,
+        snapshot: Value,
+    ) -> Option<Self> {
+        Some(AuditEntry {
+            data_type,
+            entity_id: log.cid.clone()?,
+            log_id: log.id.clone(),
+            operation: log.operation.clone(),
+            data_modify_time: log.data_modify_time,
+            snapshot,
+            run_id: current_run_id(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub from: Option<Value>,
+    pub to: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistoryEntry {
+    pub log_id: String,
+    pub operation: String,
+    pub data_modify_time: i64,
+    pub applied_at: NaiveDateTime,
+    pub run_id: Option<u64>,
+    /// 与上一条记录相比发生变化的字段，key 为字段路径（如 `[0].name`）
+    pub changes: Map<String, Value>,
+    pub snapshot: Value,
+}
+
+pub async fn record_audit_entries(mysql_pool: &MySqlPool, entries: &[AuditEntry]) -> Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let mut query_builder: QueryBuilder<MySql> = QueryBuilder::new(
+        "INSERT INTO binlog_audit_log (entity_type, entity_id, log_id, operation, data_modify_time, snapshot, run_id) ",
+    );
+    query_builder.push_values(entries, |mut b, entry| {
+        b.push_bind(entry.data_type.as_label())
+            .push_bind(&entry.entity_id)
+            .push_bind(&entry.log_id)
+            .push_bind(&entry.operation)
+            .push_bind(entry.data_modify_time)
+            .push_bind(entry.snapshot.to_string())
+            .push_bind(entry.run_id);
+    });
+    QueryRunner::new("batch_insert_audit_log")
+        .run(query_builder.build().execute(mysql_pool))
+        .await
+        .context("Failed to insert into binlog_audit_log table")?;
+    Ok(())
+}
+
+/// 按时间顺序返回实体的变更历史，最多 `limit` 条（取最近的）
+pub async fn load_history(
+    mysql_pool: &MySqlPool,
+    data_type: DataType,
+    entity_id: &str,
+    limit: u32,
+) -> Result<Vec<HistoryEntry>> {
+    // 多取一条更早的记录，用于计算第一条的 diff
+    let query = sqlx::query(
+        "SELECT log_id, operation, data_modify_time, applied_at, run_id, CAST(snapshot AS CHAR) AS snapshot \
+         FROM binlog_audit_log WHERE entity_type = ? AND entity_id = ? \
+         ORDER BY data_modify_time DESC, id DESC LIMIT ?",
+    )
+    .bind(data_type.as_label())
+    .bind(entity_id)
+    .bind(limit + 1);
+    let rows = QueryRunner::new("audit_history_by_entity")
+        .run(query.fetch_all(mysql_pool))
+        .await
+        .context("Failed to query binlog_audit_log")?;
+
+    let mut entries = Vec::with_capacity(rows.len());
+    for row in rows.into_iter().rev() {
+        let snapshot: String = row.try_get("snapshot")?;
+        entries.push(HistoryEntry {
+            log_id: row.try_get("log_id")?,
+            operation: row.try_get("operation")?,
+            data_modify_time: row.try_get("data_modify_time")?,
+            applied_at: row.try_get("applied_at")?,
+            run_id: row.try_get("run_id")?,
+            changes: Map::new(),
+            snapshot: serde_json::from_str(&snapshot).unwrap_or(Value::String(snapshot)),
+        });
+    }
+
+    for i in 1..entries.len() {
+        let changes = diff_snapshots(&entries[i - 1].snapshot, &entries[i].snapshot);
+        entries[i].changes = changes;
+    }
+    if entries.len() > limit as usize {
+        entries.remove(0);
+    } else if let Some(first) = entries.first_mut() {
+        first.changes = diff_snapshots(&Value::Null, &first.snapshot);
+    }
+    Ok(entries)
+}
+
+/// 按字段路径比较两个快照，返回 路径 -> {from, to}
+pub fn diff_snapshots(before: &Value, after: &Value) -> Map<String, Value> {
+    let mut before_fields = Map::new();
+    let mut after_fields = Map::new();
+    flatten("", before, &mut before_fields);
+    flatten("", after, &mut after_fields);
+
+    let mut changes = Map::new();
+    for (path, to) in &after_fields {
+        let from = before_fields.get(path);
+        if from != Some(to) {
+            let change = FieldChange {
+                from: from.cloned(),
+                to: Some(to.clone()),
+            };
+            changes.insert(
+                path.clone(),
+                serde_json::to_value(change).unwrap_or_default(),
+            );
+        }
+    }
+    for (path, from) in before_fields {
+        if !after_fields.contains_key(&path) {
+            let change = FieldChange {
+                from: Some(from),
+                to: None,
+            };
+            changes.insert(path, serde_json::to_value(change).unwrap_or_default());
+        }
+    }
+    changes
+}
+
+fn flatten(prefix: &str, value: &Value, out: &mut Map<String, Value>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                flatten(&path, child, out);
+            }
+        }
+        Value::Array(items) => {
+            for (i, child) in items.iter().enumerate() {
+                flatten(&format!("{prefix}[{i}]"), child, out);
+            }
+        }
+        Value::Null if prefix.is_empty() => {}
+        leaf => {
+            out.insert(prefix.to_string(), leaf.clone());
+        }
+    }
+}
+
+#[test]
+fn test_diff_snapshots() {
+    let before = serde_json::json!([{ "name": "盐城分公司", "sort": 1, "parent": "A" }]);
+    let after = serde_json::json!([{ "name": "盐城", "sort": 1, "code": "X1" }]);
+    let changes = diff_snapshots(&before, &after);
+
+    assert_eq!(changes.len(), 3);
+    assert_eq!(
+        changes["[0].name"],
+        serde_json::json!({ "from": "盐城分公司", "to": "盐城" })
+    );
+    assert_eq!(
+        changes["[0].code"],
+        serde_json::json!({ "from": null, "to": "X1" })
+    );
+    assert_eq!(
+        changes["[0].parent"],
+        serde_json::json!({ "from": "A", "to": null })
+    );
+    assert!(diff_snapshots(&after, &after).is_empty());
+}
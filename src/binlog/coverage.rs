@@ -0,0 +1,111 @@
+use anyhow::{Context, Result};
+use sqlx::{MySqlPool, Row};
+
+use crate::db::query_runner::QueryRunner;
+use crate::models::task_run::current_run_id;
+
+/// 记录一个已完整处理的窗口。与已有窗口重叠或相接时延长该窗口，否则新增一条
+pub async fn record_sync_window(
+    mysql_pool: &MySqlPool,
+    data_type: &str,
+    start_time: i64,
+    end_time: i64,
+    records: usize,
+) -> Result<()> {
+    let records = u32::try_from(records).unwrap_or(u32::MAX);
+    let query = sqlx::query(
+        "UPDATE binlog_sync_window SET end_time = GREATEST(end_time, ?), records = records + ? \
+         WHERE data_type = ? AND start_time <= ? AND end_time >= ? ORDER BY end_time DESC LIMIT 1",
+    )
+    .bind(end_time)
+    .bind(records)
+    .bind(data_type)
+    .bind(start_time)
+    .bind(start_time);
+    let extended = QueryRunner::new("binlog_sync_window_extend")
+        .run(query.execute(mysql_pool))
+        .await
+        .context("Failed to extend binlog_sync_window")?;
+    if extended.rows_affected() > 0 {
+        return Ok(());
+    }
+
+    let query = sqlx::query(
+        "INSERT INTO binlog_sync_window (run_id, data_type, start_time, end_time, records) VALUES (?, ?, ?, ?, ?)",
+    )
+    // 没有日志的周期不保留执行记录，不关联 run_id
+    .bind(current_run_id().filter(|_| records > 0))
+    .bind(data_type)
+    .bind(start_time)
+    .bind(end_time)
+    .bind(records);
+    QueryRunner::new("binlog_sync_window_insert")
+        .run(query.execute(mysql_pool))
+        .await
+        .context("Failed to insert into binlog_sync_window table")?;
+    Ok(())
+}
+
+/// 与 [from, to] 有交集的窗口，按起点升序
+pub async fn load_sync_windows(
+    mysql_pool: &MySqlPool,
+    data_type: &str,
+    from: i64,
+    to: i64,
+) -> Result<Vec<(i64, i64)>> {
+    let query = sqlx::query(
+        "SELECT start_time, end_time FROM binlog_sync_window \
+         WHERE data_type = ? AND end_time >= ? AND start_time <= ? ORDER BY start_time",
+    )
+    .bind(data_type)
+    .bind(from)
+    .bind(to);
+    let rows = QueryRunner::new("binlog_sync_windows_in_range")
+        .run(query.fetch_all(mysql_pool))
+        .await
+        .context("Failed to query binlog_sync_window")?;
+    rows.into_iter()
+        .map(|row| Ok((row.try_get("start_time")?, row.try_get("end_time")?)))
+        .collect()
+}
+
+/// 最早记录的窗口起点，开始记录之前的时间无法判断是否有缺口
+pub async fn earliest_window_start(mysql_pool: &MySqlPool, data_type: &str) -> Result<Option<i64>> {
+    let query = sqlx::query(
+        "SELECT MIN(start_time) AS start_time FROM binlog_sync_window WHERE data_type = ?",
+    )
+    .bind(data_type);
+    let row = QueryRunner::new("binlog_sync_window_earliest")
+        .run(query.fetch_one(mysql_pool))
+        .await
+        .context("Failed to query earliest binlog_sync_window")?;
+    Ok(row.try_get("start_time")?)
+}
+
+/// [from, to] 中没有被任何窗口覆盖的区间，windows 需按起点升序
+pub fn find_gaps(windows: &[(i64, i64)], from: i64, to: i64) -> Vec<(i64, i64)> {
+    let mut gaps = Vec::new();
+    let mut cursor = from;
+    for &(start, end) in windows {
+        if cursor >= to {
+            break;
+        }
+        if start > cursor {
+            gaps.push((cursor, start.min(to)));
+        }
+        cursor = cursor.max(end);
+    }
+    if cursor < to {
+        gaps.push((cursor, to));
+    }
+    gaps
+}
+
+#[test]
+fn test_find_gaps() {
+    let windows = [(0, 100), (50, 200), (300, 400), (380, 500), (700, 800)];
+    assert_eq!(find_gaps(&windows, 0, 600), vec![(200, 300), (500, 600)]);
+    assert_eq!(find_gaps(&windows, 100, 350), vec![(200, 300)]);
+    assert_eq!(find_gaps(&windows, 0, 150), vec![]);
+    assert_eq!(find_gaps(&[], 10, 20), vec![(10, 20)]);
+}
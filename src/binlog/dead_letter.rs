@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{MySql, MySqlPool, QueryBuilder, Row};
+
+use crate::db::query_runner::QueryRunner;
+use crate::models::task_run::current_run_id;
+use crate::schedule::binlog_sync::{DataType, LogPriority, ModifyOperationLog, PermanentFailure};
+
+/// 写入死信的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// 处理过程中发生不可重试的错误
+    Permanent,
+    /// 处理完成但多次落库失败
+    SaveFailed,
+}
+
+impl FailureKind {
+    pub fn as_label(&self) -> &'static str {
+        match self {
+            FailureKind::Permanent => "permanent",
+            FailureKind::SaveFailed => "save_failed",
+        }
+    }
+}
+
+/// binlog_dead_letter 中的一条记录
+#[derive(Debug, Serialize)]
+pub struct DeadLetterRecord {
+    pub id: u64,
+    pub run_id: Option<u64>,
+    pub data_type: String,
+    pub priority: String,
+    pub failure_kind: String,
+    pub state: Option<String>,
+    pub log_id: String,
+    pub data_modify_time: i64,
+    pub log: Value,
+    pub error: String,
+    pub retry_count: u32,
+    pub status: String,
+    pub created_at: NaiveDateTime,
+    pub requeued_at: Option<NaiveDateTime>,
+}
+
+/// 永久失败与落库失败的 binlog 日志，保存原始日志以便查看和重新入队处理
+#[derive(Clone)]
+pub struct DeadLetterStore {
+    mysql_pool: MySqlPool,
+}
+
+impl DeadLetterStore {
+    pub fn new(mysql_pool: MySqlPool) -> Self {
+        Self { mysql_pool }
+    }
+
+    /// 处理结果多次落库失败时保存本块的原始日志
+    pub async fn record_save_failures(
+        &self,
+        data_type: DataType,
+        priority: LogPriority,
+        logs: &[ModifyOperationLog],
+        error: &anyhow::Error,
+    ) -> Result<()> {
+        let error = format!("{error:#}");
+        let entries: Vec<_> = logs.iter().map(|log| (log, None, error.as_str())).collect();
+        self.insert(data_type, priority, FailureKind::SaveFailed, &entries)
+            .await
+    }
+
+    /// 保存永久失败的日志、失败原因与到达的处理状态
+    pub async fn record_permanent_failures(
+        &self,
+        data_type: DataType,
+        priority: LogPriority,
+        failures: &[PermanentFailure],
+    ) -> Result<()> {
+        let entries: Vec<_> = failures
+            .iter()
+            .map(|failure| (&failure.log, Some(failure.state), failure.reason.as_str()))
+            .collect();
+        self.insert(data_type, priority, FailureKind::Permanent, &entries)
+            .await
+    }
+
+    // 已有死信的日志（重新入队后）再次失败时，重试次数在之前的基础上加一
+    async fn insert(
+        &self,
+        data_type: DataType,
+        priority: LogPriority,
+        kind: FailureKind,
+        entries: &[(&ModifyOperationLog, Option<&'static str>, &str)],
+    ) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let retry_counts = self.retry_counts(entries).await?;
+
+        let mut query_builder: QueryBuilder<MySql> = QueryBuilder::new(
+            "INSERT INTO binlog_dead_letter \
+             (run_id, data_type, priority, failure_kind, state, log_id, data_modify_time, log, error, retry_count) ",
+        );
+        query_builder.push_values(entries, |mut b, (log, state, error)| {
+            b.push_bind(current_run_id())
+                .push_bind(data_type.as_label())
+                .push_bind(priority.as_label())
+                .push_bind(kind.as_label())
+                .push_bind(*state)
+                .push_bind(&log.id)
+                .push_bind(log.data_modify_time)
+                .push_bind(serde_json::to_string(log).unwrap_or_default())
+                .push_bind(*error)
+                .push_bind(retry_counts.get(&log.id).copied().unwrap_or(0));
+        });
+        QueryRunner::new("batch_insert_dead_letters")
+            .run(query_builder.build().execute(&self.mysql_pool))
+            .await
+            .context("Failed to insert into binlog_dead_letter table")?;
+        Ok(())
+    }
+
+    async fn retry_counts(
+        &self,
+        entries: &[(&ModifyOperationLog, Option<&'static str>, &str)],
+    ) -> Result<HashMap<String, u32>> {
+        let mut query_builder: QueryBuilder<MySql> = QueryBuilder::new(
+            "SELECT log_id, MAX(retry_count) AS retry_count FROM binlog_dead_letter WHERE log_id IN (",
+        );
+        let mut separated = query_builder.separated(", ");
+        for (log, _, _) in entries {
+            separated.push_bind(&log.id);
+        }
+        separated.push_unseparated(") GROUP BY log_id");
+        let rows = QueryRunner::new("dead_letter_retry_counts")
+            .run(query_builder.build().fetch_all(&self.mysql_pool))
+            .await
+            .context("Failed to query binlog_dead_letter retry counts")?;
+        rows.iter()
+            .map(|row| {
+                let retry_count: u32 = row.try_get("retry_count")?;
+                Ok((row.try_get("log_id")?, retry_count + 1))
+            })
+            .collect()
+    }
+
+    /// 按创建时间倒序列出死信，status / data_type 为 None 时不过滤
+    pub async fn list(
+        &self,
+        status: Option<&str>,
+        data_type: Option<DataType>,
+        limit: u32,
+    ) -> Result<Vec<DeadLetterRecord>> {
+        let mut query_builder: QueryBuilder<MySql> = QueryBuilder::new(
+            "SELECT id, run_id, data_type, priority, failure_kind, state, log_id, data_modify_time, \
+             CAST(log AS CHAR) AS log, error, retry_count, status, created_at, requeued_at \
+             FROM binlog_dead_letter WHERE 1 = 1",
+        );
+        if let Some(status) = status {
+            query_builder.push(" AND status = ").push_bind(status);
+        }
+        if let Some(data_type) = data_type {
+            query_builder
+                .push(" AND data_type = ")
+                .push_bind(data_type.as_label());
+        }
+        query_builder
+            .push(" ORDER BY id DESC LIMIT ")
+            .push_bind(limit);
+        let rows = QueryRunner::new("dead_letter_list")
+            .run(query_builder.build().fetch_all(&self.mysql_pool))
+            .await
+            .context("Failed to query binlog_dead_letter")?;
+        rows.iter()
+            .map(|row| {
+                let log: String = row.try_get("log")?;
+                Ok(DeadLetterRecord {
+                    id: row.try_get("id")?,
+                    run_id: row.try_get("run_id")?,
+                    data_type: row.try_get("data_type")?,
+                    priority: row.try_get("priority")?,
+                    failure_kind: row.try_get("failure_kind")?,
+                    state: row.try_get("state")?,
+                    log_id: row.try_get("log_id")?,
+                    data_modify_time: row.try_get("data_modify_time")?,
+                    log: serde_json::from_str(&log).unwrap_or(Value::String(log)),
+                    error: row.try_get("error")?,
+                    retry_count: row.try_get("retry_count")?,
+                    status: row.try_get("status")?,
+                    created_at: row.try_get("created_at")?,
+                    requeued_at: row.try_get("requeued_at")?,
+                })
+            })
+            .collect()
+    }
+
+    /// ids 中仍为 pending 的死信的原始日志，由调用方重新处理，处理成功后再调用 mark_requeued。
+    /// 处理前不改变状态，进程中断时这些死信仍可再次重新入队
+    pub async fn pending(&self, ids: &[u64]) -> Result<Vec<(u64, DataType, ModifyOperationLog)>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut query_builder: QueryBuilder<MySql> = QueryBuilder::new(
+            "SELECT id, data_type, CAST(log AS CHAR) AS log FROM binlog_dead_letter \
+             WHERE status = 'pending' AND id IN (",
+        );
+        let mut separated = query_builder.separated(", ");
+        for id in ids {
+            separated.push_bind(id);
+        }
+        separated.push_unseparated(")");
+        let rows = QueryRunner::new("dead_letter_pending")
+            .run(query_builder.build().fetch_all(&self.mysql_pool))
+            .await
+            .context("Failed to query pending binlog_dead_letter")?;
+
+        let mut logs = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let id: u64 = row.try_get("id")?;
+            let data_type: String = row.try_get("data_type")?;
+            let log: String = row.try_get("log")?;
+            let data_type: DataType = serde_json::from_value(Value::String(data_type))
+                .with_context(|| format!("Unknown data_type in dead letter {id}"))?;
+            let log: ModifyOperationLog = serde_json::from_str(&log)
+                .with_context(|| format!("Invalid log in dead letter {id}"))?;
+            logs.push((id, data_type, log));
+        }
+        Ok(logs)
+    }
+
+    /// 把重新处理完成的死信标记为 requeued，只更新仍为 pending 的记录，返回更新的条数。
+    /// 再次失败的日志已写入新的死信，原记录同样标记
+    pub async fn mark_requeued(&self, ids: &[u64]) -> Result<u64> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let mut query_builder: QueryBuilder<MySql> = QueryBuilder::new(
+            "UPDATE binlog_dead_letter SET status = 'requeued', retry_count = retry_count + 1, \
+             requeued_at = NOW() WHERE status = 'pending' AND id IN (",
+        );
+        let mut separated = query_builder.separated(", ");
+        for id in ids {
+            separated.push_bind(id);
+        }
+        separated.push_unseparated(")");
+        let result = QueryRunner::new("dead_letter_mark_requeued")
+            .run(query_builder.build().execute(&self.mysql_pool))
+            .await
+            .context("Failed to mark binlog_dead_letter as requeued")?;
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,393 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use sqlx::mysql::MySqlRow;
+use sqlx::{Column, MySqlPool, Row, TypeInfo};
+
+use crate::binlog::redact_excluded_columns;
+use crate::config::PersistencePolicyConfig;
+use crate::db::query_runner::QueryRunner;
+use crate::schedule::binlog_sync::DataType;
+use crate::utils::timefmt;
+
+// 每个实体导出的 d_* 表与查询条件，条件中的 ? 绑定实体 ID
+const USER_TABLES: [(&str, &str); 4] = [
+    ("d_telecom_user", "id = ?"),
+    ("d_telecom_user_group", "user_id = ?"),
+    ("d_mss_user_mapping", "userid = ?"),
+    (
+        "d_mss_user",
+        "HRCODE IN (SELECT mssuid FROM d_mss_user_mapping WHERE userid = ?)",
+    ),
+];
+
+const STATION_TABLES: [(&str, &str); 1] = [("d_telecom_standard_station", "id = ?")];
+
+const ORG_TABLES: [(&str, &str); 4] = [
+    ("d_telecom_org", "id = ?"),
+    ("d_telecom_org_tree", "id = ?"),
+    ("d_mss_org_mapping", "code = ?"),
+    (
+        "d_mss_org",
+        "hrcode IN (SELECT msscode FROM d_mss_org_mapping WHERE code = ?)",
+    ),
+];
+
+// 可以原样导出的字段（去掉下划线、转小写后比较）：ID、编码、状态、分类与时间。
+// 其余字段的字符串与数值一律替换，表结构或网关返回新增字段时默认脱敏
+const SAFE_KEYS: [&str; 77] = [
+    // ID 与编码
+    "id",
+    "cid",
+    "rid",
+    "logid",
+    "userid",
+    "groupid",
+    "usergroupids",
+    "code",
+    "msscode",
+    "mssuid",
+    "hrcode",
+    "hrid",
+    "mapid",
+    "no",
+    "org",
+    "parent",
+    "ancestors",
+    "fullpathid",
+    "companyid",
+    "companycode",
+    "organizationcode",
+    "namecardcompanyid",
+    "standardstation",
+    "ccode",
+    "pcode",
+    // 岗位、组织与账号的分类
+    "station",
+    "stationsequence",
+    "stationsystem",
+    "stationlevel",
+    "stationgrade",
+    "stationgradesystem",
+    "basestationcode",
+    "basestationsequence",
+    "basestationsystem",
+    "basestationlevel",
+    "basestationgrade",
+    "basestationgradesystem",
+    "jobstatus",
+    "jobcategory",
+    "jobtype",
+    "hrjobtype",
+    "extjobinfojobstatus",
+    "extjobinfojobcategory",
+    "extjobinfojobtype",
+    "extjobinfohrjobtype",
+    "userstatus",
+    "status",
+    "accounttype",
+    "certificatetype",
+    "orgtype",
+    "companytype",
+    "depttype",
+    "deptlevel",
+    "dlevel",
+    "leaf",
+    "iscorp",
+    "isdelete",
+    "ddelete",
+    "departmentinfoisclose",
+    "departmentinfoiscancel",
+    "weight",
+    "sort",
+    "province",
+    "city",
+    // binlog 日志与落库记录
+    "appid",
+    "domain",
+    "model",
+    "operation",
+    "type",
+    "priority",
+    "datamodifytime",
+    "datelastmodified",
+    "entitymetainfodatecreated",
+    "datecreated",
+    "appliedat",
+    "createdat",
+    "updatedat",
+];
+
+/// 附在问题报告中的单个实体数据：d_* 表中的行、MSS 映射、最近的落库快照与死信日志，个人信息已脱敏
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EntityFixture {
+    pub entity_type: String,
+    pub entity_id: String,
+    pub exported_at: String,
+    /// 表名 -> 行
+    pub tables: BTreeMap<String, Vec<Value>>,
+    /// binlog_audit_log 中最近的落库快照，按数据修改时间降序
+    pub audit_snapshots: Vec<Value>,
+    /// binlog_dead_letter 中该实体最近的原始日志
+    pub dead_letters: Vec<Value>,
+}
+
+/// 脱敏：只保留 SAFE_KEYS 中字段的值，同一次导出中相同的原值得到相同的替代值，不同导出之间不可关联
+pub struct Anonymizer {
+    salt: String,
+}
+
+impl Default for Anonymizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Anonymizer {
+    pub fn new() -> Self {
+        Self {
+            salt: uuid::Uuid::new_v4().to_string(),
+        }
+    }
+
+    fn is_safe_key(key: &str) -> bool {
+        let normalized = key.replace('_', "").to_lowercase();
+        SAFE_KEYS.contains(&normalized.as_str())
+    }
+
+    fn pseudonym(&self, value: &str) -> String {
+        let digest = Sha256::digest(format!("{}{value}", self.salt));
+        format!("anon-{}", &hex::encode(digest)[..12])
+    }
+
+    /// 递归替换不在 SAFE_KEYS 中的字段的值，空值与布尔值保持不变
+    pub fn anonymize(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, child) in map.iter_mut() {
+                    self.anonymize_field(Self::is_safe_key(key), child);
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.anonymize(item)),
+            _ => {}
+        }
+    }
+
+    /// 对象按各自的字段名判断，数组中的值沿用所在字段的判断
+    fn anonymize_field(&self, safe: bool, value: &mut Value) {
+        match value {
+            Value::Object(_) => self.anonymize(value),
+            Value::Array(items) => items
+                .iter_mut()
+                .for_each(|item| self.anonymize_field(safe, item)),
+            _ if safe => {}
+            Value::String(s) if s.is_empty() => {}
+            Value::String(s) => *s = self.pseudonym(s),
+            Value::Number(n) => *value = Value::String(self.pseudonym(&n.to_string())),
+            Value::Null | Value::Bool(_) => {}
+        }
+    }
+}
+
+/// 导出 entity_id 的数据并脱敏，limit 限制快照与死信的条数。
+/// 用户数据按 d_telecom_user 的落库策略去掉被排除的字段，包括策略生效前写入的快照
+pub async fn export_fixture(
+    mysql_pool: &MySqlPool,
+    persistence_policy: &PersistencePolicyConfig,
+    data_type: DataType,
+    entity_id: &str,
+    limit: u32,
+) -> Result<EntityFixture> {
+    let tables: &[(&str, &str)] = match data_type {
+        DataType::User => &USER_TABLES,
+        DataType::Org => &ORG_TABLES,
+        DataType::StandardStation => &STATION_TABLES,
+    };
+    let anonymizer = Anonymizer::new();
+    let label = data_type.as_label();
+
+    let mut fixture = EntityFixture {
+        entity_type: label.to_string(),
+        entity_id: entity_id.to_string(),
+        exported_at: timefmt::datetime(timefmt::now_local()),
+        tables: BTreeMap::new(),
+        audit_snapshots: Vec::new(),
+        dead_letters: Vec::new(),
+    };
+
+    for &(table, condition) in tables {
+        let rows = QueryRunner::new("fixture_entity_rows")
+            .run(
+                sqlx::query(&format!("SELECT * FROM {table} WHERE {condition}"))
+                    .bind(entity_id)
+                    .fetch_all(mysql_pool),
+            )
+            .await
+            .with_context(|| format!("Failed to query {table} for fixture"))?;
+        fixture
+            .tables
+            .insert(table.to_string(), rows.iter().map(row_to_json).collect());
+    }
+
+    let query = sqlx::query(
+        "SELECT log_id, operation, data_modify_time, applied_at, snapshot FROM binlog_audit_log \
+         WHERE entity_type = ? AND entity_id = ? ORDER BY data_modify_time DESC LIMIT ?",
+    )
+    .bind(label)
+    .bind(entity_id)
+    .bind(limit);
+    fixture.audit_snapshots = QueryRunner::new("fixture_audit_snapshots")
+        .run(query.fetch_all(mysql_pool))
+        .await
+        .context("Failed to query binlog_audit_log for fixture")?
+        .iter()
+        .map(row_to_json)
+        .collect();
+
+    let query = sqlx::query(
+        "SELECT log_id, priority, data_modify_time, log, error, created_at FROM binlog_dead_letter \
+         WHERE data_type = ? AND JSON_UNQUOTE(JSON_EXTRACT(log, '$.cid')) = ? ORDER BY id DESC LIMIT ?",
+    )
+    .bind(label)
+    .bind(entity_id)
+    .bind(limit);
+    fixture.dead_letters = QueryRunner::new("fixture_dead_letters")
+        .run(query.fetch_all(mysql_pool))
+        .await
+        .context("Failed to query binlog_dead_letter for fixture")?
+        .iter()
+        .map(row_to_json)
+        .collect();
+
+    if let (DataType::User, Some(policy)) = (data_type, persistence_policy.table("d_telecom_user"))
+    {
+        let snapshots = fixture
+            .audit_snapshots
+            .iter_mut()
+            .filter_map(|row| row.get_mut("snapshot"));
+        fixture
+            .tables
+            .values_mut()
+            .flatten()
+            .chain(snapshots)
+            .for_each(|value| redact_excluded_columns(policy, value));
+    }
+
+    for rows in fixture.tables.values_mut() {
+        rows.iter_mut().for_each(|row| anonymizer.anonymize(row));
+    }
+    fixture
+        .audit_snapshots
+        .iter_mut()
+        .chain(fixture.dead_letters.iter_mut())
+        .for_each(|row| anonymizer.anonymize(row));
+    Ok(fixture)
+}
+
+/// 把任意查询结果行转换为 JSON 对象，无法识别的类型记为 null
+fn row_to_json(row: &MySqlRow) -> Value {
+    let mut object = Map::new();
+    for column in row.columns() {
+        let index = column.ordinal();
+        let value = if column.type_info().name() == "JSON" {
+            row.try_get::<Option<String>, _>(index)
+                .ok()
+                .flatten()
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or(Value::Null)
+        } else {
+            column_value(row, index)
+        };
+        object.insert(column.name().to_string(), value);
+    }
+    Value::Object(object)
+}
+
+fn column_value(row: &MySqlRow, index: usize) -> Value {
+    if let Ok(v) = row.try_get::<Option<i64>, _>(index) {
+        return v.into();
+    }
+    if let Ok(v) = row.try_get::<Option<u64>, _>(index) {
+        return v.into();
+    }
+    if let Ok(v) = row.try_get::<Option<f64>, _>(index) {
+        return v.into();
+    }
+    if let Ok(v) = row.try_get::<Option<f32>, _>(index) {
+        return v.into();
+    }
+    if let Ok(v) = row.try_get::<Option<Decimal>, _>(index) {
+        return v.map(|d| d.to_string()).into();
+    }
+    if let Ok(v) = row.try_get::<Option<NaiveDateTime>, _>(index) {
+        return v.map(|t| t.to_string()).into();
+    }
+    if let Ok(v) = row.try_get::<Option<NaiveDate>, _>(index) {
+        return v.map(|d| d.to_string()).into();
+    }
+    row.try_get::<Option<String>, _>(index)
+        .ok()
+        .flatten()
+        .into()
+}
+
+#[test]
+fn test_anonymize_user_fields() {
+    let anonymizer = Anonymizer::new();
+    let mut value = serde_json::json!({
+        "id": "U1",
+        "status": 1,
+        "name_card_name": "张三",
+        "name_card_mobile": "13800000000",
+        "loginname": "zhangsan",
+        "name_card_folk": "汉族",
+        "gender": 1,
+        "archives_info_political": "群众",
+        "nameCard": { "name": "张三", "email": "" },
+        "d_mss_user": [{ "TELEPHONE": 1234, "IDENTITYCARD": null, "STATION": "S1", "SEX": "男" }],
+    });
+    anonymizer.anonymize(&mut value);
+
+    assert_eq!(value["id"], "U1");
+    assert_eq!(value["status"], 1);
+    assert_eq!(value["d_mss_user"][0]["STATION"], "S1");
+    for masked in [
+        &value["name_card_mobile"],
+        &value["loginname"],
+        &value["name_card_folk"],
+        &value["gender"],
+        &value["archives_info_political"],
+        &value["d_mss_user"][0]["TELEPHONE"],
+        &value["d_mss_user"][0]["SEX"],
+    ] {
+        assert!(masked.as_str().unwrap().starts_with("anon-"), "{masked}");
+    }
+    assert_eq!(value["d_mss_user"][0]["IDENTITYCARD"], Value::Null);
+    assert_eq!(value["nameCard"]["email"], "");
+    // 同一次导出中相同的原值替换结果一致
+    assert_eq!(value["name_card_name"], value["nameCard"]["name"]);
+}
+
+#[test]
+fn test_anonymize_org_fields() {
+    let mut org = serde_json::json!({
+        "id": "O1",
+        "full_path_id": "1,2,O1",
+        "name": "盐城分公司",
+        "legal": "李四",
+        "contact_info": "025-12345678",
+        "tags": ["a", "b"],
+    });
+    Anonymizer::new().anonymize(&mut org);
+    assert_eq!(org["id"], "O1");
+    assert_eq!(org["full_path_id"], "1,2,O1");
+    assert!(org["name"].as_str().unwrap().starts_with("anon-"));
+    assert!(org["legal"].as_str().unwrap().starts_with("anon-"));
+    assert!(org["contact_info"].as_str().unwrap().starts_with("anon-"));
+    assert!(org["tags"][0].as_str().unwrap().starts_with("anon-"));
+}
@@ -0,0 +1,108 @@
+//! d_mss_org_mapping / d_mss_user_mapping 作为 MSS 映射的本地缓存。
+//! 映射步骤先按 cid 查表，mapping_cache.max_age 内写入过的记录直接使用，
+//! 未命中、已过期或查询失败时由调用方继续调用网关 translate 服务。
+//! 命中的记录不再写回表中，写入时间只在网关重新返回映射时刷新，过期后一定会重新调用网关
+
+use anyhow::Result;
+use sqlx::{MySqlPool, Row};
+use tracing::warn;
+
+use crate::AppContext;
+use crate::binlog::{TelecomMssOrgMapping, TelecomMssUserMapping};
+use crate::db::query_runner::QueryRunner;
+use crate::metrics::MAPPING_CACHE_LOOKUPS;
+
+/// 组织编码对应的 MSS 组织映射，msscode 为空的记录视为未命中
+pub async fn cached_org_mapping(
+    app_context: &AppContext,
+    code: &str,
+) -> Option<TelecomMssOrgMapping> {
+    let max_age = max_age_secs(app_context)?;
+    let result = load_org_mapping(&app_context.mysql_pool, code, max_age).await;
+    record_lookup("d_mss_org_mapping", result)
+}
+
+/// 网大用户 ID 对应的 MSS 用户映射，mssuid 为空的记录视为未命中
+pub async fn cached_user_mapping(
+    app_context: &AppContext,
+    uid: &str,
+) -> Option<TelecomMssUserMapping> {
+    let max_age = max_age_secs(app_context)?;
+    let result = load_user_mapping(&app_context.mysql_pool, uid, max_age).await;
+    record_lookup("d_mss_user_mapping", result)
+}
+
+fn max_age_secs(app_context: &AppContext) -> Option<u64> {
+    let config = &app_context.mapping_cache;
+    config.enabled.then(|| config.max_age.as_secs())
+}
+
+fn record_lookup<T>(table: &str, result: Result<Option<T>>) -> Option<T> {
+    let (outcome, mapping) = match result {
+        Ok(Some(mapping)) => ("hit", Some(mapping)),
+        Ok(None) => ("miss", None),
+        Err(e) => {
+            warn!("Failed to look up {table}, falling back to gateway: {e:?}");
+            ("error", None)
+        }
+    };
+    MAPPING_CACHE_LOOKUPS
+        .with_label_values(&[table, outcome])
+        .inc();
+    mapping
+}
+
+async fn load_org_mapping(
+    pool: &MySqlPool,
+    code: &str,
+    max_age_secs: u64,
+) -> Result<Option<TelecomMssOrgMapping>> {
+    let query = sqlx::query(
+        "SELECT code, msscode FROM d_mss_org_mapping \
+         WHERE code = ? AND msscode IS NOT NULL AND updated_at >= NOW() - INTERVAL ? SECOND \
+         LIMIT 1",
+    )
+    .bind(code)
+    .bind(max_age_secs);
+    let row = QueryRunner::new("mapping_cache_org")
+        .run(query.fetch_optional(pool))
+        .await?;
+    row.map(|row| {
+        Ok(TelecomMssOrgMapping {
+            code: row.try_get("code")?,
+            mss_code: row.try_get("msscode")?,
+            from_cache: true,
+        })
+    })
+    .transpose()
+}
+
+async fn load_user_mapping(
+    pool: &MySqlPool,
+    uid: &str,
+    max_age_secs: u64,
+) -> Result<Option<TelecomMssUserMapping>> {
+    let query = sqlx::query(
+        "SELECT standardstation, userid, certificatecode, organization, name, mssuid \
+         FROM d_mss_user_mapping \
+         WHERE userid = ? AND mssuid IS NOT NULL AND updated_at >= NOW() - INTERVAL ? SECOND \
+         LIMIT 1",
+    )
+    .bind(uid)
+    .bind(max_age_secs);
+    let row = QueryRunner::new("mapping_cache_user")
+        .run(query.fetch_optional(pool))
+        .await?;
+    row.map(|row| {
+        Ok(TelecomMssUserMapping {
+            uid: row.try_get("userid")?,
+            hr_code: row.try_get("mssuid")?,
+            name: row.try_get("name")?,
+            certificate_code: row.try_get("certificatecode")?,
+            organization: row.try_get("organization")?,
+            standard_station: row.try_get("standardstation")?,
+            from_cache: true,
+        })
+    })
+    .transpose()
+}
@@ -1,5 +1,14 @@
+pub mod audit;
+pub mod coverage;
+pub mod dead_letter;
+pub mod fixture;
+mod mapping_cache;
 mod org_processor;
 pub(crate) mod processor;
+pub mod refresh;
+pub mod registry;
+mod station_processor;
+pub mod stats;
 mod user_processor;
 
 pub use org_processor::OrgDataProcessor;
@@ -7,8 +16,14 @@ pub use org_processor::TelecomMssOrg;
 pub use org_processor::TelecomMssOrgMapping;
 pub use org_processor::TelecomOrg;
 pub use org_processor::TelecomOrgTree;
+pub use processor::ProcessOutcome;
+pub use registry::{BinlogProcessor, ProcessorRegistry};
+pub use station_processor::StationDataProcessor;
+pub use station_processor::TelecomStandardStation;
 pub use user_processor::UserDataProcessor;
 
+pub(crate) use user_processor::TELECOM_USER_COLUMNS;
 pub use user_processor::TelecomMssUser;
 pub use user_processor::TelecomMssUserMapping;
 pub use user_processor::TelecomUser;
+pub use user_processor::redact_excluded_columns;
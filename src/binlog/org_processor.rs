@@ -1,21 +1,27 @@
+use crate::AppContext;
+use crate::binlog::mapping_cache::cached_org_mapping;
 use crate::binlog::processor::{
-    DataProcessorTrait, MergeableProcessedData, ProcessingState, Transition,
+    DataProcessorTrait, MergeableProcessedData, ProcessingState, Transition, Versioned,
+    dedup_newest,
+};
+use crate::binlog::refresh::{
+    MC_ORG_SHOW_REFRESH, is_schema_incompatible, record_incompatible_refresh,
 };
-use crate::schedule::binlog_sync::{EntityMetaInfo, ModifyOperationLog};
+use crate::db::query_runner::QueryRunner;
+use crate::schedule::binlog_sync::{DataType, EntityMetaInfo, ModifyOperationLog};
 use crate::utils::ProcessError;
-use crate::utils::{mysql_client, MapToProcessError};
-use crate::AppContext;
-use anyhow::Result;
+use crate::utils::lenient_number::lenient_number;
+use crate::utils::retry::RetryPolicy;
+use crate::utils::{Clock, MapToProcessError, mysql_client, timefmt};
+use anyhow::{Context, Result};
 use async_trait::async_trait;
 use chrono::NaiveDateTime;
-use itertools::Itertools;
-// 使用 itertools::Itertools::unique_by 来去重
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use sqlx::{Execute, MySql, QueryBuilder, Transaction};
+use sqlx::{MySql, MySqlPool, QueryBuilder, Transaction};
 use std::ops::DerefMut;
 use std::sync::{Arc, OnceLock};
-use tracing::info;
+use tracing::{info, warn};
 
 // 定义静态Regex（全局或模块级，确保只编译一次）
 static CITY_CLEAN_RE: OnceLock<Regex> = OnceLock::new();
@@ -128,6 +134,8 @@ pub struct TelecomMssOrgMapping {
     pub code: Option<String>,
     #[serde(rename = "mssCode")]
     pub mss_code: Option<String>,
+    #[serde(skip)]
+    pub from_cache: bool, // 取自本地 d_mss_org_mapping，落库时不重写，写入时间保持不变
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -137,6 +145,7 @@ pub struct TelecomMssOrg {
     pub company_type: Option<String>,
     #[serde(rename = "hrCode")]
     pub hr_code: Option<String>,
+    #[serde(default, deserialize_with = "lenient_number")]
     pub sort: Option<f32>,
     #[serde(rename = "type")]
     pub org_type: Option<String>, // 组织类型 例子:Z01
@@ -146,10 +155,12 @@ pub struct TelecomMssOrg {
     #[serde(rename = "parentDepartmentCode")]
     pub parent_department_code: Option<String>,
     pub id: Option<String>,
+    #[serde(default, deserialize_with = "lenient_number")]
     pub status: Option<u8>,
     pub identity: Option<String>,
     #[serde(rename = "departmentType")]
     pub department_type: Option<String>,
+    #[serde(default, deserialize_with = "lenient_number")]
     pub time: Option<i64>,
     #[serde(rename = "chiefLeader")]
     pub chief_leader: Option<String>,
@@ -165,18 +176,28 @@ pub struct TelecomMssOrg {
     pub hit_date1: Option<NaiveDateTime>,
 }
 
-// 用于在处理过程中聚合所有相关数据的结构体
+// 用于在处理过程中聚合所有相关数据的结构体，待插入的记录带版本，落库时按 ID 保留最新的版本
 #[derive(Default)]
 pub struct ProcessedOrgData {
-    pub telecom_orgs: Vec<TelecomOrg>,
-    pub telecom_org_trees: Vec<TelecomOrgTree>,
-    pub telecom_mss_org_mappings: Vec<TelecomMssOrgMapping>,
-    pub telecom_mss_orgs: Vec<TelecomMssOrg>,
+    pub telecom_orgs: Vec<Versioned<TelecomOrg>>,
+    pub telecom_org_trees: Vec<Versioned<TelecomOrgTree>>,
+    pub telecom_mss_org_mappings: Vec<Versioned<TelecomMssOrgMapping>>,
+    pub telecom_mss_orgs: Vec<Versioned<TelecomMssOrg>>,
 
     pub org_ids_to_delete: Vec<String>,
     pub org_tree_ids_to_delete: Vec<String>,
     pub org_mapping_codes_to_delete: Vec<String>,
     pub mss_org_codes_to_delete: Vec<String>,
+    pub deleted_org_ids: Vec<String>, // 删除日志的组织ID，合并时丢弃之前轮次中同一组织待写入的记录
+}
+
+impl ProcessedOrgData {
+    /// 删除日志之前同一组织待写入的记录不再写入：落库时先删后插，否则会把已删除的组织写回
+    fn drop_pending_org(&mut self, org_id: &str) {
+        self.telecom_orgs.retain(|org| org.record.id != org_id);
+        self.telecom_org_trees
+            .retain(|tree| tree.record.id != org_id);
+    }
 }
 
 pub struct OrgDataProcessor {
@@ -191,12 +212,14 @@ impl OrgDataProcessor {
     async fn transform_to_telecom_org(
         &self,
         log: &ModifyOperationLog,
-    ) -> Result<Option<TelecomOrg>, ProcessError> {
+    ) -> Result<TelecomOrg, ProcessError> {
         let cid = log
             .cid
             .as_deref()
             .ok_or_else(|| anyhow::anyhow!("CID is missing for log {}", log.id))?;
 
+        // 每条变更日志都意味着组织已变化，不能使用缓存中变更前的组织
+        self.app_context.gateway_client.invalidate_org(cid).await;
         self.app_context
             .gateway_client
             .org_loadbyid(cid)
@@ -207,7 +230,7 @@ impl OrgDataProcessor {
     async fn transform_to_org_tree(
         &self,
         log: &ModifyOperationLog,
-    ) -> Result<Option<TelecomOrgTree>, ProcessError> {
+    ) -> Result<TelecomOrgTree, ProcessError> {
         let cid = log.cid.as_deref().ok_or_else(|| {
             ProcessError::Permanent(anyhow::anyhow!("CID is missing for log {}", log.id))
         })?;
@@ -228,20 +251,19 @@ impl OrgDataProcessor {
             ProcessError::Permanent(anyhow::anyhow!("CID is missing for log {}", log.id))
         })?;
 
-        // 2. 处理网络调用：在这里区分超时错误和其它错误
-        let mapping_option = self
-            .app_context
-            .gateway_client
-            .mss_organization_translate(cid)
-            .await
-            .map_gateway_err()?;
-
-        // 3. 处理逻辑错误：如果 API 调用成功但没有返回数据，这是一个永久性错误
-        let mapping = mapping_option.ok_or_else(|| {
-            ProcessError::Permanent(anyhow::anyhow!("MSS organization not found for CID: {cid}",))
-        })?;
-
-        // 4. 处理逻辑错误：如果返回的数据缺少必要的 mss_code 字段，这也是一个永久性错误
+        // 2. 本地 d_mss_org_mapping 中有未过期的映射时直接使用，否则调用网关，区分超时错误和其它错误；
+        //    网关没有返回数据时 NotFound 是永久性错误
+        let mapping = match cached_org_mapping(&self.app_context, cid).await {
+            Some(mapping) => mapping,
+            None => self
+                .app_context
+                .gateway_client
+                .mss_organization_translate(cid)
+                .await
+                .map_gateway_err()?,
+        };
+
+        // 3. 处理逻辑错误：如果返回的数据缺少必要的 mss_code 字段，这也是一个永久性错误
         let mss_code = mapping.mss_code.clone().ok_or_else(|| {
             ProcessError::Permanent(anyhow::anyhow!("MSS code is missing for mapping"))
         })?;
@@ -252,7 +274,7 @@ impl OrgDataProcessor {
     async fn transform_to_mss_orgs(
         &self,
         mss_code: &str,
-    ) -> Result<Option<Vec<TelecomMssOrg>>, ProcessError> {
+    ) -> Result<Vec<TelecomMssOrg>, ProcessError> {
         self.app_context
             .gateway_client
             .mss_organization_query(mss_code)
@@ -260,6 +282,87 @@ impl OrgDataProcessor {
             .map_gateway_err()
     }
 
+    /// 先提交 d_telecom_*，失败时 d_mss_* 事务回滚，两组都未生效。
+    /// d_telecom_* 已提交而 d_mss_* 提交失败时执行补偿：d_mss_* 的写入是按主键先删后插或覆盖写入的幂等操作，
+    /// 在新事务中重做一次，使两组表重新一致
+    async fn commit_both(
+        &self,
+        telecom_tx: Transaction<'_, MySql>,
+        mss_tx: Transaction<'_, MySql>,
+        data: &ProcessedOrgData,
+    ) -> Result<()> {
+        telecom_tx
+            .commit()
+            .await
+            .context("Failed to commit d_telecom_* tables")?;
+        if let Err(e) = mss_tx.commit().await {
+            warn!(
+                "Failed to commit d_mss_* tables after d_telecom_* committed, compensating: {e:?}"
+            );
+            let mut tx = self.app_context.mysql_pool.begin().await?;
+            self.save_mss_tables(&mut tx, data).await?;
+            tx.commit()
+                .await
+                .context("Compensation for d_mss_* tables failed after d_telecom_* committed")?;
+            info!("Compensation for d_mss_* tables succeeded.");
+        }
+        Ok(())
+    }
+
+    /// d_telecom_org 与 d_telecom_org_tree：先删后插
+    async fn save_telecom_tables(
+        &self,
+        tx: &mut Transaction<'_, MySql>,
+        data: &ProcessedOrgData,
+    ) -> Result<()> {
+        mysql_client::batch_delete(tx, "d_telecom_org", "id", &data.org_ids_to_delete).await?;
+        mysql_client::batch_delete(tx, "d_telecom_org_tree", "id", &data.org_tree_ids_to_delete)
+            .await?;
+        // 1. 插入 TelecomOrg
+        let orgs_to_insert = dedup_newest(&data.telecom_orgs, |o| o.id.clone());
+        if !orgs_to_insert.is_empty() {
+            self.batch_insert_telecom_orgs(tx, orgs_to_insert).await?;
+        }
+        // 2. 插入 TelecomOrgTree
+        let org_trees_to_insert = dedup_newest(&data.telecom_org_trees, |o| o.id.clone());
+        if !org_trees_to_insert.is_empty() {
+            self.batch_insert_telecom_org_trees(tx, org_trees_to_insert)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// d_mss_org_mapping 按 code 覆盖写入，d_mss_org 先删后插
+    async fn save_mss_tables(
+        &self,
+        tx: &mut Transaction<'_, MySql>,
+        data: &ProcessedOrgData,
+    ) -> Result<()> {
+        mysql_client::batch_delete(
+            tx,
+            "d_mss_org_mapping",
+            "code",
+            &data.org_mapping_codes_to_delete,
+        )
+        .await?;
+        mysql_client::batch_delete(tx, "d_mss_org", "hrcode", &data.mss_org_codes_to_delete)
+            .await?;
+        // 3. 插入 TelecomMssOrgMapping
+        let mss_org_mappings_to_insert =
+            dedup_newest(&data.telecom_mss_org_mappings, |o| o.code.clone());
+        if !mss_org_mappings_to_insert.is_empty() {
+            self.batch_insert_telecom_mss_org_mappings(tx, mss_org_mappings_to_insert)
+                .await?;
+        }
+        // 4. 插入 TelecomMssOrg
+        let mss_orgs_to_insert = dedup_newest(&data.telecom_mss_orgs, |o| o.id.clone());
+        if !mss_orgs_to_insert.is_empty() {
+            self.batch_insert_telecom_mss_orgs(tx, mss_orgs_to_insert)
+                .await?
+        }
+        Ok(())
+    }
+
     async fn batch_insert_telecom_orgs(
         &self,
         tx: &mut Transaction<'_, MySql>,
@@ -315,7 +418,8 @@ impl OrgDataProcessor {
             let cleaned_name = org.name.map(|n| n.trim().replace('\u{200b}', ""));
 
             let mut p_code: Option<String> = None;
-            let mut province_name: Option<String> = None;
+            // 命中省份表时直接使用表中的 Arc<str>，不复制字符串
+            let mut province_name: Option<Arc<str>> = None;
             let mut c_code: Option<String> = None;
             let mut province_index: usize = 4; // 省份默认取第5个元素（索引4）
 
@@ -342,7 +446,7 @@ impl OrgDataProcessor {
             }
 
             if let Some(ref code) = p_code {
-                province_name = self.app_context.provinces.get(code.as_str()).cloned();
+                province_name = self.app_context.provinces.get(code.as_str());
             }
 
             let full_path_name_parts: Option<Vec<&str>> = org
@@ -352,7 +456,7 @@ impl OrgDataProcessor {
             if province_name.is_none() {
                 // 如果 province_name 仍为 None，则取 full_path_name 索引为4的名称
                 if let Some(parts) = &full_path_name_parts {
-                    province_name = parts.get(province_index).map(|name| name.to_string());
+                    province_name = parts.get(province_index).map(|name| Arc::from(*name));
                 }
             }
             let city_name = full_path_name_parts.as_ref().and_then(|parts| {
@@ -449,7 +553,9 @@ impl OrgDataProcessor {
                 .push_bind(org.full_path_name);
         });
         let query = query_builder.build();
-        query.execute(tx.deref_mut()).await?;
+        QueryRunner::new("batch_insert_telecom_orgs")
+            .run(query.execute(tx.deref_mut()))
+            .await?;
         Ok(())
     }
 
@@ -509,7 +615,9 @@ impl OrgDataProcessor {
                 .push_bind(org_tree.full_path_name);
         });
         let query = query_builder.build();
-        query.execute(&mut **tx).await?;
+        QueryRunner::new("batch_insert_telecom_org_trees")
+            .run(query.execute(&mut **tx))
+            .await?;
         Ok(())
     }
 
@@ -531,8 +639,13 @@ impl OrgDataProcessor {
             b.push_bind(mss_org_mapping.code)
                 .push_bind(mss_org_mapping.mss_code);
         });
+        // 网关重新返回的映射刷新写入时间，msscode 未变化时 ON UPDATE 不会生效，需显式写入
+        query_builder
+            .push(" ON DUPLICATE KEY UPDATE msscode = VALUES(msscode), updated_at = NOW()");
         let query = query_builder.build();
-        query.execute(&mut **tx).await?;
+        QueryRunner::new("batch_insert_telecom_mss_org_mappings")
+            .run(query.execute(&mut **tx))
+            .await?;
         Ok(())
     }
 
@@ -585,7 +698,9 @@ impl OrgDataProcessor {
                 .push_bind(None::<String>); // amount 设为 NULL
         });
         let query = query_builder.build();
-        query.execute(&mut **tx).await?;
+        QueryRunner::new("batch_insert_telecom_mss_orgs")
+            .run(query.execute(&mut **tx))
+            .await?;
         Ok(())
     }
 
@@ -594,31 +709,23 @@ impl OrgDataProcessor {
         &self,
         log: ModifyOperationLog,
     ) -> Result<Transition_, ProcessError> {
-        match self.transform_to_telecom_org(&log).await? {
-            // 成功获取，返回 Advanced 状态
-            Some(org) => Ok(Transition_::Advanced(Box::new(ProcessingState::GotStep1(
-                log,
-                Box::new(org),
-            )))),
-            None => Err(ProcessError::Permanent(anyhow::anyhow!(
-                "Unable to find corresponding TelecomOrg"
-            ))),
-        }
+        let org = self.transform_to_telecom_org(&log).await?;
+        // 成功获取，返回 Advanced 状态
+        Ok(Transition_::Advanced(Box::new(ProcessingState::GotStep1(
+            log,
+            Box::new(org),
+        ))))
     }
 
     async fn handle_got_telecom_org_state(
         &self,
         log: ModifyOperationLog,
     ) -> Result<Transition_, ProcessError> {
-        match self.transform_to_org_tree(&log).await? {
-            Some(tree) => Ok(Transition_::Advanced(Box::new(ProcessingState::GotStep2(
-                log,
-                Box::new(tree),
-            )))),
-            None => Err(ProcessError::Permanent(anyhow::anyhow!(
-                "Unable to generate OrgTree"
-            ))),
-        }
+        let tree = self.transform_to_org_tree(&log).await?;
+        Ok(Transition_::Advanced(Box::new(ProcessingState::GotStep2(
+            log,
+            Box::new(tree),
+        ))))
     }
 
     async fn handle_got_org_tree_state(
@@ -637,12 +744,7 @@ impl OrgDataProcessor {
         log: ModifyOperationLog,
         mss_code: String,
     ) -> Result<Transition_, ProcessError> {
-        let mss_orgs = self
-            .transform_to_mss_orgs(&mss_code)
-            .await?
-            .ok_or_else(|| {
-                ProcessError::Permanent(anyhow::anyhow!("Unable to find TelecomMssOrg"))
-            })?;
+        let mss_orgs = self.transform_to_mss_orgs(&mss_code).await?;
 
         // 这是最后一步，成功后返回 Completed 状态，并携带所有数据
         Ok(Transition_::Completed(Box::new(log), mss_orgs))
@@ -651,6 +753,10 @@ impl OrgDataProcessor {
 
 impl MergeableProcessedData for ProcessedOrgData {
     fn merge(&mut self, other: &mut Self) {
+        // other 来自之后的重试轮次，同一组织的日志按顺序推进，其中的删除晚于 self 中的写入
+        for org_id in &other.deleted_org_ids {
+            self.drop_pending_org(org_id);
+        }
         self.telecom_orgs.append(&mut other.telecom_orgs);
         self.telecom_org_trees.append(&mut other.telecom_org_trees);
         self.telecom_mss_org_mappings
@@ -664,6 +770,7 @@ impl MergeableProcessedData for ProcessedOrgData {
             .append(&mut other.org_mapping_codes_to_delete);
         self.mss_org_codes_to_delete
             .append(&mut other.mss_org_codes_to_delete);
+        self.deleted_org_ids.append(&mut other.deleted_org_ids);
     }
 }
 
@@ -675,6 +782,26 @@ impl DataProcessorTrait for OrgDataProcessor {
     type Mapping = TelecomMssOrgMapping;
     type Final = TelecomMssOrg;
 
+    fn data_type(&self) -> DataType {
+        DataType::Org
+    }
+
+    fn mysql_pool(&self) -> &MySqlPool {
+        &self.app_context.mysql_pool
+    }
+
+    fn retry_policy(&self) -> &RetryPolicy {
+        &self.app_context.retry.binlog
+    }
+
+    fn concurrency(&self) -> usize {
+        self.app_context.limits.binlog_concurrency
+    }
+
+    fn clock(&self) -> &dyn Clock {
+        self.app_context.clock.as_ref()
+    }
+
     async fn handle_initial(&self, log: &ModifyOperationLog) -> Result<Transition_, ProcessError> {
         self.handle_initial_state(log.clone()).await
     }
@@ -719,8 +846,13 @@ impl DataProcessorTrait for OrgDataProcessor {
                     org_to_insert.month = Some(month.to_string());
                     org_to_insert.in_time = Some(now);
                     org_to_insert.hit_date1 = Some(now);
-                    org_to_insert.hit_date = Some(now.format("%Y-%m-%d").to_string());
-                    data.telecom_orgs.push(org_to_insert);
+                    org_to_insert.hit_date = Some(timefmt::business_date(now.date()));
+                    let meta = org.entity_meta_info.as_ref();
+                    data.telecom_orgs
+                        .push(Versioned::new(org_to_insert, meta, log));
+                } else {
+                    data.drop_pending_org(&org.id);
+                    data.deleted_org_ids.push(org.id.clone());
                 }
             }
             ProcessingState::GotStep2(log, tree) => {
@@ -728,18 +860,23 @@ impl DataProcessorTrait for OrgDataProcessor {
                 let need_insert = log.type_ == 1 || log.type_ == 2;
                 data.org_tree_ids_to_delete.push(tree.id.clone());
                 if need_insert {
-                    data.telecom_org_trees.push((**tree).clone());
+                    let meta = tree.entity_meta_info.as_ref();
+                    data.telecom_org_trees
+                        .push(Versioned::new((**tree).clone(), meta, log));
                 }
             }
             ProcessingState::GotMapping(log, mapping, mss_code) => {
                 // 从 GotOrgTree -> GotMssMapping，处理 mapping 和 mss_code
                 let need_insert = log.type_ == 1 || log.type_ == 2;
-                if let Some(code) = &mapping.code {
-                    data.org_mapping_codes_to_delete.push(code.clone());
-                }
                 data.mss_org_codes_to_delete.push(mss_code.clone());
-                if need_insert {
-                    data.telecom_mss_org_mappings.push(mapping.clone());
+                if !need_insert {
+                    if let Some(code) = &mapping.code {
+                        data.org_mapping_codes_to_delete.push(code.clone());
+                    }
+                } else if !mapping.from_cache {
+                    // 取自本地映射表的记录不再写回，未过期的映射到期后重新调用网关
+                    data.telecom_mss_org_mappings
+                        .push(Versioned::new(mapping.clone(), None, log));
                 }
             }
             _ => {}
@@ -762,88 +899,34 @@ impl DataProcessorTrait for OrgDataProcessor {
                 mss_org.year = Some(year.to_string());
                 mss_org.month = Some(month.to_string());
                 mss_org.hit_date1 = Some(now);
-                mss_org.hit_date = Some(now.format("%Y-%m-%d %H:%M:%S").to_string());
-                data.telecom_mss_orgs.push(mss_org);
+                mss_org.hit_date = Some(timefmt::datetime(now));
+                data.telecom_mss_orgs
+                    .push(Versioned::new(mss_org, None, log));
             }
         }
     }
 
-    /// 保存处理好的数据到数据库
+    /// 保存处理好的数据到数据库。
+    /// d_telecom_* 与 d_mss_* 两组表互不依赖，各自在独立连接的事务中并发写入，
+    /// 全部写入成功后再依次提交，提交顺序与补偿见 commit_both
     async fn save_processed_data(&self, data: &ProcessedOrgData) -> Result<()> {
-        let mut tx = self.app_context.mysql_pool.begin().await?;
-        // --- 1. 执行批量刪除 ---
-        info!("Starting batch deletion organization of old data...");
-        mysql_client::batch_delete(&mut tx, "d_telecom_org", "id", &data.org_ids_to_delete).await?;
-        mysql_client::batch_delete(
-            &mut tx,
-            "d_telecom_org_tree",
-            "id",
-            &data.org_tree_ids_to_delete,
-        )
-        .await?;
-        mysql_client::batch_delete(
-            &mut tx,
-            "d_mss_org_mapping",
-            "code",
-            &data.org_mapping_codes_to_delete,
-        )
-        .await?;
-        mysql_client::batch_delete(
-            &mut tx,
-            "d_mss_org",
-            "hrcode",
-            &data.mss_org_codes_to_delete,
-        )
-        .await?;
-        // --- 2. 执行批量插入 ---
-        info!("Starting batch insertion of new data...");
-        // 1. 插入 TelecomOrg
-        let orgs_to_insert = data
-            .telecom_orgs
-            .iter()
-            .cloned()
-            .unique_by(|o| o.id.clone())
-            .collect::<Vec<_>>();
-        if !orgs_to_insert.is_empty() {
-            self.batch_insert_telecom_orgs(&mut tx, orgs_to_insert)
-                .await?;
-        }
-        // 2. 插入 TelecomOrgTree
-        let org_trees_to_insert = data
-            .telecom_org_trees
-            .iter()
-            .cloned()
-            .unique_by(|o| o.id.clone())
-            .collect::<Vec<_>>();
-        if !org_trees_to_insert.is_empty() {
-            self.batch_insert_telecom_org_trees(&mut tx, org_trees_to_insert)
-                .await?;
-        }
-
-        // 3. 插入 TelecomMssOrgMapping
-        let mss_org_mappings_to_insert = data
-            .telecom_mss_org_mappings
-            .iter()
-            .cloned()
-            .unique_by(|o| o.code.clone())
-            .collect::<Vec<_>>();
-        if !mss_org_mappings_to_insert.is_empty() {
-            self.batch_insert_telecom_mss_org_mappings(&mut tx, mss_org_mappings_to_insert)
-                .await?;
-        }
-
-        // 4. 插入 TelecomMssOrg
-        let mss_orgs_to_insert = data
-            .telecom_mss_orgs
-            .iter()
-            .cloned()
-            .unique_by(|o| o.id.clone())
-            .collect::<Vec<_>>();
-        if !mss_orgs_to_insert.is_empty() {
-            self.batch_insert_telecom_mss_orgs(&mut tx, mss_orgs_to_insert)
-                .await?
-        }
-        tx.commit().await?;
+        let mysql_pool = &self.app_context.mysql_pool;
+        info!("Starting concurrent save of organization data...");
+        // 任一组写入失败时另一组的事务随之被丢弃并回滚，两组都不落库
+        let (telecom_tx, mss_tx) = tokio::try_join!(
+            async {
+                let mut tx = mysql_pool.begin().await?;
+                self.save_telecom_tables(&mut tx, data).await?;
+                Ok::<_, anyhow::Error>(tx)
+            },
+            async {
+                let mut tx = mysql_pool.begin().await?;
+                self.save_mss_tables(&mut tx, data).await?;
+                Ok::<_, anyhow::Error>(tx)
+            },
+        )?;
+        self.commit_both(telecom_tx, mss_tx, data).await?;
+        info!("End concurrent save of organization data...");
         Ok(())
     }
 
@@ -856,7 +939,7 @@ impl DataProcessorTrait for OrgDataProcessor {
             .cloned()
             .collect::<std::collections::HashSet<_>>();
         for org in &data.telecom_orgs {
-            affected_ids.insert(org.id.clone());
+            affected_ids.insert(org.record.id.clone());
         }
         let unique_affected_ids: Vec<String> = affected_ids.into_iter().collect();
 
@@ -876,24 +959,35 @@ impl DataProcessorTrait for OrgDataProcessor {
 
         // 4. (Insert) 重新计算并插入需要存在的数据
         //    只为那些需要新增或更新的组织（即存在于 telecom_orgs 列表中的）执行插入
-        let ids_to_insert: Vec<String> = data.telecom_orgs.iter().map(|o| o.id.clone()).collect();
+        let ids_to_insert: Vec<String> = data
+            .telecom_orgs
+            .iter()
+            .map(|o| o.record.id.clone())
+            .collect();
 
         if !ids_to_insert.is_empty() {
-            // 4.1. 从 .sql 文件加载原始SQL
-            let raw_sql_query = sqlx::query_file!("queries/refresh_mc_org_show.sql");
-
-            // 4.2. 使用 QueryBuilder 附加动态的 WHERE IN 子句
-            let mut query_builder = QueryBuilder::new(raw_sql_query.sql());
-            query_builder.push(" WHERE TE.ID IN (");
-            let mut separated = query_builder.separated(", ");
-            for id in &ids_to_insert {
-                separated.push_bind(id);
-            }
-            separated.push_unseparated(")");
+            // 4.1. 从 .sql 文件加载原始SQL，附加动态的 WHERE IN 子句
+            let mut query_builder = MC_ORG_SHOW_REFRESH.builder(&ids_to_insert);
 
-            // 4.3. 构建并执行最终的查询
+            // 4.2. 构建并执行最终的查询。表结构不兼容时回滚本次刷新，记录待刷新的 ID
             let final_query = query_builder.build();
-            let result = final_query.execute(tx.deref_mut()).await?;
+            let result = match QueryRunner::new("refresh_mc_org_show")
+                .run(final_query.execute(tx.deref_mut()))
+                .await
+            {
+                Ok(result) => result,
+                Err(e) if is_schema_incompatible(&e) => {
+                    drop(tx);
+                    return record_incompatible_refresh(
+                        &self.app_context.mysql_pool,
+                        MC_ORG_SHOW_REFRESH.table,
+                        &unique_affected_ids,
+                        &e,
+                    )
+                    .await;
+                }
+                Err(e) => return Err(e.into()),
+            };
 
             info!(
                 "Inserted {} new records into mc_org_show",
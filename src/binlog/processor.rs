@@ -1,13 +1,39 @@
-use crate::schedule::binlog_sync::{ModifyOperationLog, PermanentFailure};
-use crate::utils::ProcessError;
-use anyhow::Result;
+use crate::binlog::audit::{AuditEntry, record_audit_entries};
+use crate::binlog::dead_letter::DeadLetterStore;
+use crate::binlog::stats::{BatchStats, record_batch_stats};
+use crate::metrics::{BINLOG_ADVANCE_DURATION, BINLOG_LOGS_ADVANCED, BINLOG_PROCESSING_LAG};
+use crate::schedule::binlog_sync::{
+    DataType, EntityMetaInfo, LogPriority, ModifyOperationLog, PermanentFailure,
+};
+use crate::utils::deadline::{deadline_exceeded, within_deadline};
+use crate::utils::retry::RetryPolicy;
+use crate::utils::{Clock, ProcessError, timefmt};
+use anyhow::{Context, Result};
 use async_trait::async_trait;
-use chrono::{Local, NaiveDateTime};
+use chrono::NaiveDateTime;
+use futures::StreamExt;
+use serde::Serialize;
+use sqlx::MySqlPool;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Debug;
-use tracing::{error, info};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
 
-// 最大重试次数
-const MAX_RETRIES: u32 = 10;
+// 每个分块的日志数，分块独立处理、落库，一块失败不影响其他块
+const SAVE_CHUNK_SIZE: usize = 500;
+// 一个分块落库的最大尝试次数
+const SAVE_ATTEMPTS: u32 = 3;
+// 落库重试的退避时间，按尝试次数递增
+const SAVE_RETRY_BACKOFF: Duration = Duration::from_secs(2);
+
+/// 分块多次落库失败且写入 binlog_dead_letter 也失败，日志既未落库也未保存，检查点不能推进
+#[derive(Debug, thiserror::Error)]
+#[error("{logs} {data_type:?} logs were neither saved nor dead-lettered")]
+pub struct SaveIncomplete {
+    pub data_type: DataType,
+    pub logs: usize,
+}
 
 pub fn clean_field(field: &mut Option<String>) {
     if let Some(s) = field.as_mut() {
@@ -20,11 +46,69 @@ pub fn clean_field(field: &mut Option<String>) {
     }
 }
 
+/// process 的结果：超过周期截止时间而未处理的日志由下个周期重新拉取
+#[derive(Debug, Default)]
+pub struct ProcessOutcome {
+    pub deferred: Vec<ModifyOperationLog>,
+}
+
+impl ProcessOutcome {
+    /// 最早一条未处理日志的时间，检查点不能超过它
+    pub fn resume_at(&self) -> Option<i64> {
+        self.deferred.iter().map(|log| log.data_modify_time).min()
+    }
+}
+
 // 共享 trait 用于 ProcessedData 的 merge
 pub trait MergeableProcessedData {
     fn merge(&mut self, other: &mut Self);
 }
 
+/// 处理结果中的一条记录及其版本（datelastmodified）。
+/// 多个重试轮次合并后同一实体可能有多个版本，且旧版本可能排在后面，落库前用 dedup_newest 去重
+#[derive(Debug, Clone)]
+pub struct Versioned<T> {
+    pub version: i64,
+    pub record: T,
+}
+
+impl<T> Versioned<T> {
+    /// 版本依次取记录自身的 datelastmodified、日志的 datelastmodified、日志的 data_modify_time
+    pub fn new(record: T, meta: Option<&EntityMetaInfo>, log: &ModifyOperationLog) -> Self {
+        let version = meta
+            .and_then(|meta| meta.date_last_modified)
+            .or_else(|| {
+                log.entity_meta_info
+                    .as_ref()
+                    .and_then(|meta| meta.date_last_modified)
+            })
+            .unwrap_or(log.data_modify_time);
+        Versioned { version, record }
+    }
+}
+
+/// 按 key 去重，每个 key 保留版本最新的记录，版本相同时保留先合并的一条。
+/// 结果按 key 排序，与合并顺序无关
+pub fn dedup_newest<T: Clone, K: Ord>(records: &[Versioned<T>], key: impl Fn(&T) -> K) -> Vec<T> {
+    let mut newest: BTreeMap<K, &Versioned<T>> = BTreeMap::new();
+    for versioned in records {
+        match newest.entry(key(&versioned.record)) {
+            Entry::Vacant(entry) => {
+                entry.insert(versioned);
+            }
+            Entry::Occupied(mut entry) => {
+                if versioned.version > entry.get().version {
+                    entry.insert(versioned);
+                }
+            }
+        }
+    }
+    newest
+        .into_values()
+        .map(|versioned| versioned.record.clone())
+        .collect()
+}
+
 /// 定义处理状态机，用于保存每个日志的处理进度
 // 泛型 ProcessingState：Intermediate1 (e.g., Org/User), Intermediate2 (e.g., Tree or ()), Mapping (e.g., MssMapping)
 #[derive(Debug)]
@@ -35,6 +119,39 @@ pub enum ProcessingState<I1, I2, M> {
     GotMapping(ModifyOperationLog, M, String), // String 为 mss_code 或者 hrCode
 }
 
+impl<I1, I2, M> ProcessingState<I1, I2, M> {
+    pub fn log(&self) -> &ModifyOperationLog {
+        match self {
+            ProcessingState::Initial(log)
+            | ProcessingState::GotStep1(log, _)
+            | ProcessingState::GotStep2(log, _)
+            | ProcessingState::GotMapping(log, _, _) => log,
+        }
+    }
+
+    /// 状态名称，写入死信记录
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProcessingState::Initial(_) => "initial",
+            ProcessingState::GotStep1(..) => "got_step1",
+            ProcessingState::GotStep2(..) => "got_step2",
+            ProcessingState::GotMapping(..) => "got_mapping",
+        }
+    }
+}
+
+/// 一组日志推进后的结果，D 为处理后的数据，S 为需要重试的状态
+pub struct AdvanceOutput<D, S> {
+    pub data: D,
+    pub retry: Vec<S>,
+    pub permanent_failures: Vec<PermanentFailure>,
+    pub audit_entries: Vec<AuditEntry>,
+    /// 因网关服务被关闭而跳过剩余步骤的日志：(服务名, 失败记录)，与永久失败一样写入死信，
+    /// 服务恢复后重新入队补齐
+    pub degraded: Vec<(String, PermanentFailure)>,
+    pub completed: usize,
+}
+
 // 泛型 Transition 表示状态转换的结果
 #[derive(Debug)]
 pub enum Transition<I1, I2, M, F> {
@@ -48,7 +165,22 @@ pub trait DataProcessorTrait: Send + Sync {
     type Intermediate1: Clone + Send + Debug; // e.g., TelecomOrg
     type Intermediate2: Clone + Send + Debug; // e.g., TelecomOrgTree or ()
     type Mapping: Clone + Send + Debug; // e.g., TelecomMssOrgMapping
-    type Final: Clone + Send + Debug; // e.g., TelecomMssOrg
+    type Final: Clone + Send + Debug + Serialize; // e.g., TelecomMssOrg
+
+    // 处理器对应的数据类型，写入 binlog_audit_log.entity_type
+    fn data_type(&self) -> DataType;
+
+    // 写入变更审计记录使用的连接池
+    fn mysql_pool(&self) -> &MySqlPool;
+
+    // 暂时性失败的日志重新处理的轮数与间隔
+    fn retry_policy(&self) -> &RetryPolicy;
+
+    // 同时推进的实体数
+    fn concurrency(&self) -> usize;
+
+    // 计算 hit_date、分区年月与处理延迟使用的时钟
+    fn clock(&self) -> &dyn Clock;
 
     // 每个步骤的 handle 函数，由具体处理器实现
     async fn handle_initial(
@@ -105,7 +237,13 @@ pub trait DataProcessorTrait: Send + Sync {
         now: NaiveDateTime,
     );
 
-    // 共享的 advance_states 函数（可作为 trait 方法调用）
+    // 钩子：Completed 时写入审计表的快照，快照可通过历史接口查询，落库策略排除的字段须在这里去掉
+    fn audit_snapshot(&self, final_data: &[Self::Final]) -> serde_json::Value {
+        serde_json::to_value(final_data).unwrap_or_default()
+    }
+
+    // 共享的 advance_states 函数（可作为 trait 方法调用）。
+    // 同一实体（cid）的日志按顺序在同一组内处理，不同实体的组最多 concurrency 个并发推进
     async fn advance_states(
         &self,
         states: Vec<ProcessingState<Self::Intermediate1, Self::Intermediate2, Self::Mapping>>,
@@ -113,16 +251,109 @@ pub trait DataProcessorTrait: Send + Sync {
         Self::ProcessedData,
         Vec<ProcessingState<Self::Intermediate1, Self::Intermediate2, Self::Mapping>>,
         Vec<PermanentFailure>,
+        Vec<AuditEntry>,
     ) {
+        let started = Instant::now();
+        let data_type = self.data_type().as_label();
+        let now = self.clock().now_local();
+        let (year, month) = timefmt::year_month(now);
+
+        let total = states.len();
+        let groups = group_by_entity(states);
+        // buffered 按分组顺序返回结果，合并顺序与输入一致
+        let outputs: Vec<_> = futures::stream::iter(groups)
+            .map(|group| self.advance_group(group, &year, &month, now))
+            .buffered(self.concurrency().max(1))
+            .collect()
+            .await;
+
         let mut processed_data = Self::ProcessedData::default();
         let mut states_for_retry = Vec::new();
         let mut permanent_failures = Vec::new();
+        let mut audit_entries = Vec::new();
+        // 因网关服务被关闭而停在中间状态的日志：服务名 -> 日志 ID
+        let mut degraded: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut degraded_failures = Vec::new();
+        let mut completed = 0;
+        for mut output in outputs {
+            processed_data.merge(&mut output.data);
+            states_for_retry.extend(output.retry);
+            permanent_failures.extend(output.permanent_failures);
+            audit_entries.extend(output.audit_entries);
+            completed += output.completed;
+            for (service, failure) in output.degraded {
+                degraded
+                    .entry(service)
+                    .or_default()
+                    .push(failure.log.id.clone());
+                degraded_failures.push(failure);
+            }
+        }
 
-        let now = Local::now().naive_local();
-        let year = now.format("%Y").to_string();
-        let month = now.format("%m").to_string();
+        for (outcome, count) in [
+            ("completed", completed),
+            ("retry", states_for_retry.len()),
+            ("permanent", permanent_failures.len()),
+            ("degraded", degraded_failures.len()),
+        ] {
+            BINLOG_LOGS_ADVANCED
+                .with_label_values(&[data_type, outcome])
+                .inc_by(count as u64);
+        }
+        BINLOG_ADVANCE_DURATION
+            .with_label_values(&[data_type])
+            .observe(started.elapsed().as_secs_f64());
 
-        for state in states {
+        for (service, log_ids) in &degraded {
+            warn!(
+                "Gateway service {service} is disabled, {} {:?} logs saved with partial data and dead-lettered for requeue: {log_ids:?}",
+                log_ids.len(),
+                self.data_type()
+            );
+        }
+        // checkpoint 照常推进，跳过的步骤靠死信重新入队补齐
+        permanent_failures.extend(degraded_failures);
+        info!(
+            "Advanced {total} {:?} logs in {:?}, completed {completed}, states_for_retry: {:?} len: {}",
+            self.data_type(),
+            started.elapsed(),
+            states_for_retry,
+            states_for_retry.len()
+        );
+        (
+            processed_data,
+            states_for_retry,
+            permanent_failures,
+            audit_entries,
+        )
+    }
+
+    // 按顺序推进同一实体的日志。前面的日志需要重试时，后面的日志也留到下一轮，保证按修改顺序应用
+    async fn advance_group(
+        &self,
+        group: Vec<ProcessingState<Self::Intermediate1, Self::Intermediate2, Self::Mapping>>,
+        year: &str,
+        month: &str,
+        now: NaiveDateTime,
+    ) -> AdvanceOutput<
+        Self::ProcessedData,
+        ProcessingState<Self::Intermediate1, Self::Intermediate2, Self::Mapping>,
+    > {
+        let mut output = AdvanceOutput {
+            data: Self::ProcessedData::default(),
+            retry: Vec::new(),
+            permanent_failures: Vec::new(),
+            audit_entries: Vec::new(),
+            degraded: Vec::new(),
+            completed: 0,
+        };
+
+        for state in group {
+            // 超过截止时间后不再发起新的调用，剩余状态原样返回
+            if !output.retry.is_empty() || deadline_exceeded() {
+                output.retry.push(state);
+                continue;
+            }
             let mut current_state = state;
             // 使用 loop 来驱动单个日志的状态流转，直到成功、需要重试或永久失败
             loop {
@@ -141,47 +372,60 @@ pub trait DataProcessorTrait: Send + Sync {
                     Ok(Transition::Advanced(next_state_box)) => {
                         // 调用钩子处理数据
                         // 核心逻辑：立即处理上一个状态的数据
-                        self.post_advance(&mut processed_data, &next_state_box, &year, &month, now);
+                        self.post_advance(&mut output.data, &next_state_box, year, month, now);
                         // 更新状态，继续循环
                         // 更新状态，从 Box 中移出值
                         current_state = *next_state_box;
                     }
                     // 所有步骤都已成功完成
                     Ok(Transition::Completed(log, final_data)) => {
-                        // 调用钩子处理最终数据
-                        self.post_complete(
-                            &mut processed_data,
+                        // 记录最终数据快照，保存成功后写入审计表
+                        let snapshot = self.audit_snapshot(&final_data);
+                        output.audit_entries.extend(AuditEntry::from_log(
+                            self.data_type(),
                             &log,
-                            final_data,
-                            &year,
-                            &month,
-                            now,
-                        );
+                            snapshot,
+                        ));
+                        // 调用钩子处理最终数据
+                        self.post_complete(&mut output.data, &log, final_data, year, month, now);
+                        output.completed += 1;
                         break; // 此日志处理完成，跳出 loop
                     }
-                    Err(ProcessError::GatewayTimeout(_)) => {
-                        // 发生超时，将当前状态加入重试列表
-                        states_for_retry.push(current_state);
+                    Err(
+                        ProcessError::GatewayTimeout(_)
+                        | ProcessError::GatewayUnavailable(_)
+                        | ProcessError::DeadlineExceeded
+                        | ProcessError::CircuitOpen(_),
+                    ) => {
+                        // 发生超时、网关暂时不可用或熔断，将当前状态加入重试列表，同组剩余状态不再调用
+                        output.retry.push(current_state);
+                        break;
+                    }
+                    Err(ProcessError::ServiceDisabled(service)) => {
+                        // 已推进的步骤数据保留在 processed_data 中正常落库，剩余步骤跳过，日志写入死信
+                        let state = current_state.name();
+                        let log = extract_log_from_state(current_state);
+                        let reason = ProcessError::ServiceDisabled(service.clone()).to_string();
+                        output
+                            .degraded
+                            .push((service, PermanentFailure { log, reason, state }));
                         break;
                     }
                     Err(ProcessError::Permanent(e)) => {
                         // 发生永久性错误，记录并放弃
+                        let state = current_state.name();
                         let log = extract_log_from_state(current_state);
-                        permanent_failures.push(PermanentFailure {
+                        output.permanent_failures.push(PermanentFailure {
                             log,
                             reason: e.to_string(),
+                            state,
                         });
                         break;
                     }
                 }
             }
         }
-        info!(
-            "states_for_retry: {:?} len: {}",
-            states_for_retry,
-            states_for_retry.len()
-        );
-        (processed_data, states_for_retry, permanent_failures)
+        output
     }
 
     // 新增：保存处理数据的抽象方法
@@ -190,45 +434,143 @@ pub trait DataProcessorTrait: Send + Sync {
     // 新增：刷新表的抽象方法
     async fn refresh_table(&self, data: &Self::ProcessedData) -> Result<()>;
 
-    // 默认实现的 process 方法，主入口函数，包含了重试逻辑
-    async fn process(&self, logs: Vec<ModifyOperationLog>) -> Result<()> {
+    // 落库失败时重试，先删后插的写入可以安全重做
+    async fn save_with_retry(&self, data: &Self::ProcessedData) -> Result<()> {
+        let mut attempt = 1;
+        loop {
+            match self.save_processed_data(data).await {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt < SAVE_ATTEMPTS => {
+                    warn!(
+                        "Failed to save {:?} data (attempt {attempt}/{SAVE_ATTEMPTS}), retrying: {e:?}",
+                        self.data_type()
+                    );
+                    tokio::time::sleep(SAVE_RETRY_BACKOFF * attempt).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    // 默认实现的 process 方法，主入口函数：按优先级分道，高优先级的日志先处理并落库，同一实体的日志在同一道中。
+    // 每道按 SAVE_CHUNK_SIZE 分块处理。超过周期截止时间后不再开始新的分块，未处理的日志在结果中返回
+    async fn process(&self, logs: Vec<ModifyOperationLog>) -> Result<ProcessOutcome> {
+        let (high, normal) = split_lanes(logs);
+        let mut outcome = ProcessOutcome::default();
+        for (priority, lane) in [(LogPriority::High, high), (LogPriority::Normal, normal)] {
+            let mut remaining = lane;
+            while !remaining.is_empty() {
+                if deadline_exceeded() {
+                    outcome.deferred.append(&mut remaining);
+                    break;
+                }
+                let rest = remaining.split_off(remaining.len().min(SAVE_CHUNK_SIZE));
+                info!(
+                    "Processing {} {} priority logs for {:?}",
+                    remaining.len(),
+                    priority.as_label(),
+                    self.data_type()
+                );
+                let deferred = self.process_lane(priority, remaining).await?;
+                outcome.deferred.extend(deferred);
+                remaining = rest;
+            }
+        }
+        if !outcome.deferred.is_empty() {
+            warn!(
+                "Cycle deadline exceeded, {} {:?} logs deferred to the next cycle",
+                outcome.deferred.len(),
+                self.data_type()
+            );
+        }
+        Ok(outcome)
+    }
+
+    // 处理同一优先级的日志，包含了重试逻辑。已完成的部分照常落库，
+    // 超过截止时间而未完成的日志不计入本批统计，返回给调用方顺延
+    async fn process_lane(
+        &self,
+        priority: LogPriority,
+        logs: Vec<ModifyOperationLog>,
+    ) -> Result<Vec<ModifyOperationLog>> {
+        let mut modify_times: Vec<i64> = logs.iter().map(|log| log.data_modify_time).collect();
+        // 落库失败时写入 binlog_dead_letter 的原始日志
+        let mut chunk_logs = logs.clone();
         // 初始化状态机
         let mut states_to_process: Vec<
             ProcessingState<Self::Intermediate1, Self::Intermediate2, Self::Mapping>,
         > = logs.into_iter().map(ProcessingState::Initial).collect();
 
         let mut final_processed_data = Self::ProcessedData::default();
+        let mut final_audit_entries = Vec::new();
+        let mut final_permanent_failures = Vec::new();
+        let dead_letters = DeadLetterStore::new(self.mysql_pool().clone());
+        let mut stats = BatchStats {
+            data_type: self.data_type().as_label().to_string(),
+            priority: priority.as_label().to_string(),
+            batch_size: modify_times.len() as u32,
+            ..Default::default()
+        };
 
-        for i in 0..MAX_RETRIES {
+        let retry_policy = self.retry_policy();
+        for attempt in 1..=retry_policy.max_attempts {
             if states_to_process.is_empty() {
                 info!("All data has been successfully processed.");
                 break;
             }
+            // 重新处理前按策略退避，截止时间到达时不再等待
+            if attempt > 1 {
+                let _ =
+                    within_deadline(tokio::time::sleep(retry_policy.backoff(attempt - 1))).await;
+            }
+            if deadline_exceeded() {
+                break;
+            }
             info!(
                 "Processing data, {} retry attempts remaining. Pending count: {}",
-                MAX_RETRIES - i,
+                retry_policy.max_attempts - attempt + 1,
                 states_to_process.len()
             );
 
-            let (mut processed_data_chunk, next_states, permanent_failures) =
+            let pending = states_to_process.len();
+            let (mut processed_data_chunk, next_states, permanent_failures, audit_entries) =
                 self.advance_states(states_to_process).await;
+            // 本轮既没有失败也不需要重试的日志即处理成功
+            stats.succeeded +=
+                pending.saturating_sub(next_states.len() + permanent_failures.len()) as u32;
 
             // 合并当轮成功的数据
             final_processed_data.merge(&mut processed_data_chunk);
+            final_audit_entries.extend(audit_entries);
 
-            // 记录永久失败的日志
-            if !permanent_failures.is_empty() {
-                for failure in permanent_failures {
-                    error!(
-                        "Processing permanently failed, will not retry. Reason: {}. Log: {:?}",
-                        failure.reason, failure.log
-                    );
-                }
+            // 记录永久失败的日志，本块结束后写入 binlog_dead_letter
+            for failure in &permanent_failures {
+                stats.add_failure(&failure.reason);
+                error!(
+                    "Processing permanently failed at state {}, will not retry. Reason: {}. Log: {:?}",
+                    failure.state, failure.reason, failure.log
+                );
             }
+            final_permanent_failures.extend(permanent_failures);
             // 更新待处理列表，用于下一轮重试
             states_to_process = next_states;
         }
 
+        let mut deferred = Vec::new();
+        if deadline_exceeded() {
+            deferred.extend(states_to_process.drain(..).map(extract_log_from_state));
+            // 顺延的日志不计入本批统计与处理延迟
+            for log in &deferred {
+                if let Some(pos) = modify_times
+                    .iter()
+                    .position(|modify_time| *modify_time == log.data_modify_time)
+                {
+                    modify_times.swap_remove(pos);
+                }
+            }
+            stats.batch_size = modify_times.len() as u32;
+        }
         // 重试次数用尽后，如果仍有未处理的状态，则记录错误
         if !states_to_process.is_empty() {
             error!(
@@ -236,20 +578,118 @@ pub trait DataProcessorTrait: Send + Sync {
                 states_to_process.len()
             );
         }
+        stats.exhausted = states_to_process.len() as u32;
 
-        // 所有轮次结束后，一次性保存所有成功的数据
-        match self.save_processed_data(&final_processed_data).await {
-            Ok(_) => info!("All batches of data successfully saved to database."),
-            Err(e) => error!("Failed to save data: {e:?}"),
+        // 所有轮次结束后，一次性保存本块所有成功的数据，多次失败后把本块日志写入 binlog_dead_letter
+        let mut incomplete = None;
+        match self.save_with_retry(&final_processed_data).await {
+            Ok(_) => {
+                info!("All batches of data successfully saved to database.");
+                // 只有数据落库后才记录审计，审计失败不影响主流程
+                if let Err(e) = record_audit_entries(self.mysql_pool(), &final_audit_entries).await
+                {
+                    warn!("Failed to record binlog audit entries: {e:?}");
+                }
+                // 在 d_* 表更新成功后，刷新 mc_user_ztk 或者 mc_org_show 表
+                if let Err(e) = self.refresh_table(&final_processed_data).await {
+                    error!("Failed to refresh table: {e:?}");
+                }
+            }
+            Err(e) => {
+                stats.save_failed = true;
+                // 顺延的日志下个周期重新处理，永久失败的日志单独记录，都不作为落库失败写入死信
+                let skipped_ids: HashSet<&str> = deferred
+                    .iter()
+                    .chain(final_permanent_failures.iter().map(|failure| &failure.log))
+                    .map(|log| log.id.as_str())
+                    .collect();
+                chunk_logs.retain(|log| !skipped_ids.contains(log.id.as_str()));
+                error!(
+                    "Failed to save data after {SAVE_ATTEMPTS} attempts, dead-lettering {} logs: {e:?}",
+                    chunk_logs.len()
+                );
+                incomplete = dead_letters
+                    .record_save_failures(self.data_type(), priority, &chunk_logs, &e)
+                    .await
+                    .context(SaveIncomplete {
+                        data_type: self.data_type(),
+                        logs: chunk_logs.len(),
+                    })
+                    .err();
+            }
+        }
+
+        // 永久失败的日志不会被重新拉取，写入失败只能告警
+        if let Err(e) = dead_letters
+            .record_permanent_failures(self.data_type(), priority, &final_permanent_failures)
+            .await
+        {
+            error!(
+                "Failed to dead-letter {} permanently failed {:?} logs: {e:?}",
+                final_permanent_failures.len(),
+                self.data_type()
+            );
+        }
+
+        let lag = BINLOG_PROCESSING_LAG
+            .with_label_values(&[self.data_type().as_label(), priority.as_label()]);
+        let now_ms = self.clock().timestamp_ms();
+        let lags_ms: Vec<i64> = modify_times
+            .iter()
+            .map(|modify_time| (now_ms - modify_time).max(0))
+            .collect();
+        for lag_ms in &lags_ms {
+            lag.observe(*lag_ms as f64 / 1000.0);
         }
 
-        // 在 d_* 表更新成功后，刷新 mc_user_ztk 或者 mc_org_show 表
-        if let Err(e) = self.refresh_table(&final_processed_data).await {
-            error!("Failed to refresh table: {e:?}");
+        // 批次统计供 binlog 日报汇总，写入失败不影响主流程
+        stats.set_lags(&lags_ms);
+        if let Err(e) = record_batch_stats(self.mysql_pool(), &stats).await {
+            warn!("Failed to record binlog batch stats: {e:?}");
         }
 
-        Ok(())
+        if let Some(e) = incomplete {
+            return Err(e);
+        }
+        Ok(deferred)
+    }
+}
+
+// 按优先级分道，同一 cid 的日志取其中最高的优先级并放在同一道，保持原有顺序，
+// 否则后到的删除可能先于之前的新增落库，新增又把已删除的行写回
+fn split_lanes(
+    logs: Vec<ModifyOperationLog>,
+) -> (Vec<ModifyOperationLog>, Vec<ModifyOperationLog>) {
+    let urgent: HashSet<String> = logs
+        .iter()
+        .filter(|log| log.priority() == LogPriority::High)
+        .filter_map(|log| log.cid.clone())
+        .collect();
+    logs.into_iter().partition(|log| {
+        log.priority() == LogPriority::High
+            || log.cid.as_ref().is_some_and(|cid| urgent.contains(cid))
+    })
+}
+
+// 按 cid 分组，组的顺序与组内顺序都保持首次出现的顺序。没有 cid 的日志各自成组
+fn group_by_entity<I1, I2, M>(
+    states: Vec<ProcessingState<I1, I2, M>>,
+) -> Vec<Vec<ProcessingState<I1, I2, M>>> {
+    let mut groups: Vec<Vec<ProcessingState<I1, I2, M>>> = Vec::new();
+    let mut index_by_cid: HashMap<String, usize> = HashMap::new();
+    for state in states {
+        match state.log().cid.clone() {
+            Some(cid) => match index_by_cid.get(&cid) {
+                Some(&index) => groups[index].push(state),
+                None => {
+                    index_by_cid.insert(cid, groups.len());
+                    groups.push(vec![state]);
+                }
+            },
+            None => groups.push(vec![state]),
+        }
     }
+    groups
 }
 
 // 辅助函数：提取 log（共享）
@@ -261,3 +701,101 @@ fn extract_log_from_state<I1, I2, M>(state: ProcessingState<I1, I2, M>) -> Modif
         ProcessingState::GotMapping(log, _, _) => log,
     }
 }
+
+#[test]
+fn test_group_by_entity_keeps_order() {
+    let state = |id: &str, cid: Option<&str>| {
+        ProcessingState::<(), (), ()>::Initial(ModifyOperationLog {
+            id: id.to_string(),
+            cid: cid.map(String::from),
+            ..Default::default()
+        })
+    };
+    let groups = group_by_entity(vec![
+        state("1", Some("a")),
+        state("2", Some("b")),
+        state("3", None),
+        state("4", Some("a")),
+        state("5", None),
+    ]);
+    let ids: Vec<Vec<&str>> = groups
+        .iter()
+        .map(|group| group.iter().map(|state| state.log().id.as_str()).collect())
+        .collect();
+    assert_eq!(ids, vec![vec!["1", "4"], vec!["2"], vec!["3"], vec!["5"]]);
+}
+
+#[test]
+fn test_split_lanes_keeps_entity_in_one_lane() {
+    let log = |id: &str, cid: &str, type_: u8| ModifyOperationLog {
+        id: id.to_string(),
+        cid: Some(cid.to_string()),
+        type_,
+        ..Default::default()
+    };
+    let (high, normal) = split_lanes(vec![
+        log("1", "a", 1),
+        log("2", "b", 2),
+        log("3", "a", 3),
+        log("4", "c", 3),
+    ]);
+    let ids =
+        |logs: &[ModifyOperationLog]| logs.iter().map(|log| log.id.clone()).collect::<Vec<_>>();
+    // a 的新增随删除进入高优先级道，并排在删除之前
+    assert_eq!(ids(&high), vec!["1", "3", "4"]);
+    assert_eq!(ids(&normal), vec!["2"]);
+}
+
+#[test]
+fn test_dedup_newest() {
+    let log = |data_modify_time: i64, date_last_modified: Option<i64>| ModifyOperationLog {
+        data_modify_time,
+        entity_meta_info: date_last_modified.map(|date_last_modified| EntityMetaInfo {
+            date_last_modified: Some(date_last_modified),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    // 版本来源：记录自身 > 日志的 datelastmodified > data_modify_time
+    let record_meta = EntityMetaInfo {
+        date_last_modified: Some(30),
+        ..Default::default()
+    };
+    assert_eq!(
+        Versioned::new((), Some(&record_meta), &log(10, Some(20))).version,
+        30
+    );
+    assert_eq!(Versioned::new((), None, &log(10, Some(20))).version, 20);
+    assert_eq!(Versioned::new((), None, &log(10, None)).version, 10);
+
+    let versioned = |id: &str, name: &str, version: i64| Versioned {
+        version,
+        record: (id.to_string(), name.to_string()),
+    };
+    // 新版本在第一轮合并、旧版本在重试轮次中合并，以及相反的顺序，结果相同
+    let newer_first = [
+        versioned("b", "b-new", 2),
+        versioned("a", "a-new", 5),
+        versioned("b", "b-old", 1),
+        versioned("a", "a-old", 3),
+    ];
+    let older_first = [
+        versioned("a", "a-old", 3),
+        versioned("b", "b-old", 1),
+        versioned("a", "a-new", 5),
+        versioned("b", "b-new", 2),
+    ];
+    let expected = vec![
+        ("a".to_string(), "a-new".to_string()),
+        ("b".to_string(), "b-new".to_string()),
+    ];
+    assert_eq!(dedup_newest(&newer_first, |(id, _)| id.clone()), expected);
+    assert_eq!(dedup_newest(&older_first, |(id, _)| id.clone()), expected);
+
+    // 版本相同时保留先合并的一条
+    let tied = [versioned("a", "first", 1), versioned("a", "second", 1)];
+    assert_eq!(
+        dedup_newest(&tied, |(id, _)| id.clone()),
+        vec![("a".to_string(), "first".to_string())]
+    );
+}
@@ -0,0 +1,109 @@
+use anyhow::{Context, Result};
+use sqlx::{Execute, MySql, MySqlPool, QueryBuilder};
+use tracing::{error, info};
+
+use crate::db::query_runner::QueryRunner;
+use crate::metrics::BINLOG_REFRESH_INCOMPATIBLE;
+use crate::models::task_run::current_run_id;
+
+// 目标表结构变化导致刷新 SQL 失效时 MySQL 返回的 SQLSTATE：表不存在、列不存在、列数不匹配
+const INCOMPATIBLE_SQLSTATES: [&str; 3] = ["42S02", "42S22", "21S01"];
+
+/// queries/refresh_mc_*.sql 中的刷新语句，执行时在末尾追加 `WHERE id_column IN (...)`
+pub struct RefreshQuery {
+    pub table: &'static str,
+    pub id_column: &'static str,
+    sql: fn() -> &'static str,
+}
+
+pub const MC_ORG_SHOW_REFRESH: RefreshQuery = RefreshQuery {
+    table: "mc_org_show",
+    id_column: "TE.ID",
+    sql: || sqlx::query_file!("queries/refresh_mc_org_show.sql").sql(),
+};
+
+pub const MC_USER_ZTK_REFRESH: RefreshQuery = RefreshQuery {
+    table: "mc_user_ztk",
+    id_column: "TU.ID",
+    sql: || sqlx::query_file!("queries/refresh_mc_user_ztk.sql").sql(),
+};
+
+pub const REFRESH_QUERIES: [RefreshQuery; 2] = [MC_ORG_SHOW_REFRESH, MC_USER_ZTK_REFRESH];
+
+impl RefreshQuery {
+    /// 只刷新 ids 对应记录的语句
+    pub fn builder<'a>(&self, ids: &'a [String]) -> QueryBuilder<'a, MySql> {
+        let mut query_builder = QueryBuilder::new((self.sql)());
+        query_builder.push(format!(" WHERE {} IN (", self.id_column));
+        let mut separated = query_builder.separated(", ");
+        for id in ids {
+            separated.push_bind(id);
+        }
+        separated.push_unseparated(")");
+        query_builder
+    }
+
+    /// EXPLAIN 刷新语句，目标表或源表结构不兼容时返回错误
+    pub async fn explain(&self, mysql_pool: &MySqlPool) -> Result<()> {
+        let sql = format!("EXPLAIN {} WHERE {} IN ('')", (self.sql)(), self.id_column);
+        QueryRunner::new("refresh_query_explain")
+            .run(sqlx::query(&sql).fetch_all(mysql_pool))
+            .await
+            .with_context(|| format!("Refresh query for {} failed EXPLAIN", self.table))?;
+        Ok(())
+    }
+}
+
+/// 启动时逐个 EXPLAIN 刷新语句，不兼容只告警不阻止启动
+pub async fn validate_refresh_queries(mysql_pool: &MySqlPool) {
+    for query in &REFRESH_QUERIES {
+        match query.explain(mysql_pool).await {
+            Ok(()) => info!("Refresh query for {} is compatible.", query.table),
+            Err(e) => {
+                error!(
+                    "Refresh query for {} is incompatible with the current schema, \
+                     refreshes will be recorded in mc_refresh_pending: {e:?}",
+                    query.table
+                );
+                BINLOG_REFRESH_INCOMPATIBLE
+                    .with_label_values(&[query.table])
+                    .inc();
+            }
+        }
+    }
+}
+
+/// 数据库错误是否由表结构不兼容引起
+pub fn is_schema_incompatible(e: &sqlx::Error) -> bool {
+    e.as_database_error()
+        .and_then(|db_err| db_err.code())
+        .is_some_and(|code| INCOMPATIBLE_SQLSTATES.contains(&code.as_ref()))
+}
+
+/// 刷新因表结构不兼容失败时记录未刷新的 ID 并告警，不影响本批 d_* 表的落库
+pub async fn record_incompatible_refresh(
+    mysql_pool: &MySqlPool,
+    table: &str,
+    ids: &[String],
+    e: &sqlx::Error,
+) -> Result<()> {
+    error!(
+        "Refresh of {table} is incompatible with the current schema, recorded {} ids in mc_refresh_pending: {e}",
+        ids.len()
+    );
+    BINLOG_REFRESH_INCOMPATIBLE
+        .with_label_values(&[table])
+        .inc();
+    let query = sqlx::query(
+        "INSERT INTO mc_refresh_pending (run_id, target_table, entity_ids, error) VALUES (?, ?, ?, ?)",
+    )
+    .bind(current_run_id())
+    .bind(table)
+    .bind(serde_json::to_string(ids)?)
+    .bind(e.to_string());
+    QueryRunner::new("refresh_pending_insert")
+        .run(query.execute(mysql_pool))
+        .await
+        .context("Failed to insert into mc_refresh_pending table")?;
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::AppContext;
+use crate::binlog::processor::{DataProcessorTrait, ProcessOutcome};
+use crate::binlog::{OrgDataProcessor, StationDataProcessor, UserDataProcessor};
+use crate::schedule::binlog_sync::{DataType, ModifyOperationLog};
+
+/// DataProcessorTrait 带有关联类型，无法直接作为 trait object，
+/// 这里包一层只暴露 process 的对象安全接口
+#[async_trait]
+pub trait BinlogProcessor: Send + Sync {
+    async fn process_logs(&self, logs: Vec<ModifyOperationLog>) -> Result<ProcessOutcome>;
+}
+
+#[async_trait]
+impl<T: DataProcessorTrait> BinlogProcessor for T {
+    async fn process_logs(&self, logs: Vec<ModifyOperationLog>) -> Result<ProcessOutcome> {
+        self.process(logs).await
+    }
+}
+
+pub type ProcessorFactory = fn(Arc<AppContext>) -> Arc<dyn BinlogProcessor>;
+
+/// DataType 到处理器的映射，连续同步任务与手动同步接口共用。
+/// 新增数据类型时只需在 `with_defaults` 中注册
+pub struct ProcessorRegistry {
+    factories: Vec<(DataType, ProcessorFactory)>,
+}
+
+impl ProcessorRegistry {
+    pub fn new() -> Self {
+        Self {
+            factories: Vec::new(),
+        }
+    }
+
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        registry.register(DataType::Org, |app_context| {
+            Arc::new(OrgDataProcessor::new(app_context))
+        });
+        registry.register(DataType::User, |app_context| {
+            Arc::new(UserDataProcessor::new(app_context))
+        });
+        registry.register(DataType::StandardStation, |app_context| {
+            Arc::new(StationDataProcessor::new(app_context))
+        });
+        registry
+    }
+
+    /// 注册处理器，同一 DataType 重复注册时覆盖之前的
+    pub fn register(&mut self, data_type: DataType, factory: ProcessorFactory) {
+        self.factories
+            .retain(|(registered, _)| *registered != data_type);
+        self.factories.push((data_type, factory));
+    }
+
+    /// 按注册顺序返回已注册的数据类型
+    pub fn data_types(&self) -> impl Iterator<Item = DataType> + '_ {
+        self.factories.iter().map(|(data_type, _)| *data_type)
+    }
+
+    pub fn get(
+        &self,
+        data_type: DataType,
+        app_context: Arc<AppContext>,
+    ) -> Option<Arc<dyn BinlogProcessor>> {
+        self.factories
+            .iter()
+            .find(|(registered, _)| *registered == data_type)
+            .map(|(_, factory)| factory(app_context))
+    }
+}
+
+impl Default for ProcessorRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
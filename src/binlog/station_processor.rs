@@ -0,0 +1,294 @@
+use crate::AppContext;
+use crate::binlog::processor::{
+    DataProcessorTrait, MergeableProcessedData, ProcessingState, Transition, clean_field,
+};
+use crate::db::query_runner::QueryRunner;
+use crate::schedule::binlog_sync::{DataType, EntityMetaInfo, ModifyOperationLog};
+use crate::utils::ProcessError;
+use crate::utils::lenient_number::lenient_number;
+use crate::utils::retry::RetryPolicy;
+use crate::utils::{Clock, MapToProcessError, mysql_client, timefmt};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use sqlx::{MySql, MySqlPool, QueryBuilder, Transaction};
+use std::sync::Arc;
+use tracing::info;
+
+// 基准岗位只需一次网关调用，initial 之后直接完成，中间状态不携带数据
+type Transition_ = Transition<(), (), (), TelecomStandardStation>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelecomStandardStation {
+    pub id: String,
+    pub code: Option<String>,
+    pub name: Option<String>,
+    pub system: Option<String>,   // 岗位体系
+    pub sequence: Option<String>, // 岗位序列
+    pub level: Option<String>,    // 岗位层级
+    #[serde(rename = "gradeSystem")]
+    pub grade_system: Option<String>,
+    pub grade: Option<String>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub weight: Option<i32>,
+    pub delete: Option<bool>,
+    pub is_delete: Option<bool>,
+    pub hit_date: Option<String>, // yyyy-MM-dd 格式的日期字符串
+    pub in_time: Option<NaiveDateTime>,
+    pub year: Option<String>,
+    pub month: Option<String>,
+    pub hit_date1: Option<NaiveDateTime>,
+    #[serde(rename = "entityMetaInfo")]
+    pub entity_meta_info: Option<EntityMetaInfo>,
+}
+
+#[derive(Default)]
+pub struct ProcessedStationData {
+    pub stations: Vec<TelecomStandardStation>,
+    pub station_ids_to_delete: Vec<String>,
+    pub deleted_station_ids: Vec<String>, // 删除日志的岗位ID，合并时丢弃之前轮次中同一岗位待写入的记录
+}
+
+impl MergeableProcessedData for ProcessedStationData {
+    fn merge(&mut self, other: &mut Self) {
+        // other 来自之后的重试轮次，其中的删除晚于 self 中同一岗位的写入，落库时先删后插，不能再写回
+        self.stations
+            .retain(|station| !other.deleted_station_ids.contains(&station.id));
+        self.stations.append(&mut other.stations);
+        self.station_ids_to_delete
+            .append(&mut other.station_ids_to_delete);
+        self.deleted_station_ids
+            .append(&mut other.deleted_station_ids);
+    }
+}
+
+pub struct StationDataProcessor {
+    app_context: Arc<AppContext>,
+}
+
+impl StationDataProcessor {
+    pub fn new(app_context: Arc<AppContext>) -> Self {
+        Self { app_context }
+    }
+
+    async fn transform_to_station(
+        &self,
+        log: &ModifyOperationLog,
+    ) -> Result<TelecomStandardStation, ProcessError> {
+        let cid = log.cid.as_deref().ok_or_else(|| {
+            ProcessError::Permanent(anyhow::anyhow!("CID is missing for log {}", log.id))
+        })?;
+
+        self.app_context
+            .gateway_client
+            .standard_station_loadbyid(cid)
+            .await
+            .map_gateway_err()
+    }
+
+    fn unsupported_step(log: &ModifyOperationLog, step: &str) -> ProcessError {
+        ProcessError::Permanent(anyhow::anyhow!(
+            "Step {step} is not supported for standard station log {}",
+            log.id
+        ))
+    }
+
+    async fn batch_insert_telecom_standard_stations(
+        &self,
+        tx: &mut Transaction<'_, MySql>,
+        stations: Vec<TelecomStandardStation>,
+    ) -> Result<()> {
+        if stations.is_empty() {
+            return Ok(());
+        }
+        let mut query_builder = QueryBuilder::new(
+            "INSERT INTO d_telecom_standard_station (
+            id,
+            code,
+            name,
+            station_system,
+            station_sequence,
+            station_level,
+            station_grade_system,
+            station_grade,
+            weight,
+            d_delete,
+            is_delete,
+            datelastmodified,
+            hitdate,
+            intime,
+            year,
+            month,
+            hitdate1
+        ) ",
+        );
+        query_builder.push_values(stations, |mut b, mut station| {
+            clean_field(&mut station.name);
+            let delete_str = station.delete.map(|b| b.to_string());
+            let is_delete_str = station.is_delete.map(|b| b.to_string());
+
+            b.push_bind(station.id)
+                .push_bind(station.code)
+                .push_bind(station.name)
+                .push_bind(station.system)
+                .push_bind(station.sequence)
+                .push_bind(station.level)
+                .push_bind(station.grade_system)
+                .push_bind(station.grade)
+                .push_bind(station.weight)
+                .push_bind(delete_str)
+                .push_bind(is_delete_str)
+                .push_bind(station.entity_meta_info.and_then(|e| e.date_last_modified))
+                .push_bind(station.hit_date)
+                .push_bind(station.in_time)
+                .push_bind(station.year)
+                .push_bind(station.month)
+                .push_bind(station.hit_date1);
+        });
+        let query = query_builder.build();
+        QueryRunner::new("batch_insert_telecom_standard_stations")
+            .run(query.execute(&mut **tx))
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DataProcessorTrait for StationDataProcessor {
+    type ProcessedData = ProcessedStationData;
+    type Intermediate1 = ();
+    type Intermediate2 = ();
+    type Mapping = ();
+    type Final = TelecomStandardStation;
+
+    fn data_type(&self) -> DataType {
+        DataType::StandardStation
+    }
+
+    fn mysql_pool(&self) -> &MySqlPool {
+        &self.app_context.mysql_pool
+    }
+
+    fn retry_policy(&self) -> &RetryPolicy {
+        &self.app_context.retry.binlog
+    }
+
+    fn concurrency(&self) -> usize {
+        self.app_context.limits.binlog_concurrency
+    }
+
+    fn clock(&self) -> &dyn Clock {
+        self.app_context.clock.as_ref()
+    }
+
+    async fn handle_initial(&self, log: &ModifyOperationLog) -> Result<Transition_, ProcessError> {
+        let station = self.transform_to_station(log).await?;
+        Ok(Transition_::Completed(Box::new(log.clone()), vec![station]))
+    }
+
+    async fn handle_step1(&self, log: &ModifyOperationLog) -> Result<Transition_, ProcessError> {
+        Err(Self::unsupported_step(log, "step1"))
+    }
+
+    async fn handle_step2(&self, log: &ModifyOperationLog) -> Result<Transition_, ProcessError> {
+        Err(Self::unsupported_step(log, "step2"))
+    }
+
+    async fn handle_mapping(
+        &self,
+        log: &ModifyOperationLog,
+        _mss_code: &str,
+    ) -> Result<Transition_, ProcessError> {
+        Err(Self::unsupported_step(log, "mapping"))
+    }
+
+    fn post_advance(
+        &self,
+        _data: &mut Self::ProcessedData,
+        _state: &ProcessingState<Self::Intermediate1, Self::Intermediate2, Self::Mapping>,
+        _year: &str,
+        _month: &str,
+        _now: NaiveDateTime,
+    ) {
+        // 基准岗位没有中间状态，数据全部在 post_complete 中累积
+    }
+
+    fn post_complete(
+        &self,
+        data: &mut Self::ProcessedData,
+        log: &ModifyOperationLog,
+        final_data: Vec<Self::Final>,
+        year: &str,
+        month: &str,
+        now: NaiveDateTime,
+    ) {
+        let need_insert = log.type_ == 1 || log.type_ == 2;
+        for mut station in final_data {
+            data.station_ids_to_delete.push(station.id.clone());
+            if need_insert {
+                station.year = Some(year.to_string());
+                station.month = Some(month.to_string());
+                station.in_time = Some(now);
+                station.hit_date1 = Some(now);
+                station.hit_date = Some(timefmt::business_date(now.date()));
+                data.stations.push(station);
+            } else {
+                data.stations.retain(|pending| pending.id != station.id);
+                data.deleted_station_ids.push(station.id.clone());
+            }
+        }
+    }
+
+    /// d_telecom_standard_station：先删后插
+    async fn save_processed_data(&self, data: &ProcessedStationData) -> Result<()> {
+        info!("Starting save of standard station data...");
+        let mut tx = self.app_context.mysql_pool.begin().await?;
+        mysql_client::batch_delete(
+            &mut tx,
+            "d_telecom_standard_station",
+            "id",
+            &data.station_ids_to_delete,
+        )
+        .await?;
+        let stations_to_insert = data
+            .stations
+            .iter()
+            .cloned()
+            .unique_by(|s| s.id.clone())
+            .collect::<Vec<_>>();
+        self.batch_insert_telecom_standard_stations(&mut tx, stations_to_insert)
+            .await?;
+        tx.commit()
+            .await
+            .context("Failed to commit d_telecom_standard_station")?;
+        info!("End save of standard station data...");
+        Ok(())
+    }
+
+    /// 没有依赖基准岗位的 mc_* 表，无需刷新
+    async fn refresh_table(&self, _data: &ProcessedStationData) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_station_from_gateway_payload() {
+    let payload = serde_json::json!({
+        "id": "st-001",
+        "code": "GW0001",
+        "name": " 网络运维岗 ",
+        "gradeSystem": "T",
+        "weight": "12",
+        "is_delete": false,
+        "entityMetaInfo": {"dateCreated": 1700000000000_i64, "dateLastModified": 1700000001000_i64}
+    });
+    let station: TelecomStandardStation = serde_json::from_value(payload).unwrap();
+    assert_eq!(station.grade_system.as_deref(), Some("T"));
+    assert_eq!(station.weight, Some(12));
+    assert_eq!(
+        station.entity_meta_info.and_then(|e| e.date_last_modified),
+        Some(1_700_000_001_000)
+    );
+}
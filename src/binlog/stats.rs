@@ -0,0 +1,279 @@
+use anyhow::{Context, Result};
+use chrono::{Days, NaiveDate};
+use sqlx::{MySqlPool, Row};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use crate::db::query_runner::QueryRunner;
+use crate::messages::{Language, Message};
+use crate::models::task_run::current_run_id;
+
+/// 失败原因分组时保留的最大长度
+const MAX_REASON_LEN: usize = 128;
+
+/// 一批日志（同一数据类型、同一优先级）的处理统计
+#[derive(Debug, Clone, Default)]
+pub struct BatchStats {
+    pub data_type: String,
+    pub priority: String,
+    pub batch_size: u32,
+    pub succeeded: u32,
+    pub failed: u32,
+    pub exhausted: u32,
+    /// 归一化后的失败原因 -> 次数
+    pub failure_reasons: BTreeMap<String, u32>,
+    pub avg_lag_ms: i64,
+    pub max_lag_ms: i64,
+    pub save_failed: bool,
+}
+
+impl BatchStats {
+    pub fn add_failure(&mut self, reason: &str) {
+        self.failed += 1;
+        *self.failure_reasons.entry(reason_key(reason)).or_default() += 1;
+    }
+
+    pub fn set_lags(&mut self, lags_ms: &[i64]) {
+        if lags_ms.is_empty() {
+            return;
+        }
+        self.avg_lag_ms = lags_ms.iter().sum::<i64>() / lags_ms.len() as i64;
+        self.max_lag_ms = lags_ms.iter().copied().max().unwrap_or_default();
+    }
+}
+
+/// 失败原因中冒号之后通常是 cid 等具体值，按冒号前的部分分组
+pub fn reason_key(reason: &str) -> String {
+    let key = reason.split(':').next().unwrap_or_default().trim();
+    key.chars().take(MAX_REASON_LEN).collect()
+}
+
+pub async fn record_batch_stats(mysql_pool: &MySqlPool, stats: &BatchStats) -> Result<()> {
+    let query = sqlx::query(
+        "INSERT INTO binlog_batch_stats (run_id, data_type, priority, batch_size, succeeded, failed, exhausted, failure_reasons, avg_lag_ms, max_lag_ms, save_failed) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(current_run_id())
+    .bind(&stats.data_type)
+    .bind(&stats.priority)
+    .bind(stats.batch_size)
+    .bind(stats.succeeded)
+    .bind(stats.failed)
+    .bind(stats.exhausted)
+    .bind(serde_json::to_string(&stats.failure_reasons)?)
+    .bind(stats.avg_lag_ms)
+    .bind(stats.max_lag_ms)
+    .bind(stats.save_failed);
+    QueryRunner::new("binlog_batch_stats_insert")
+        .run(query.execute(mysql_pool))
+        .await
+        .context("Failed to insert into binlog_batch_stats table")?;
+    Ok(())
+}
+
+/// 某一天某个数据类型的汇总
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DailyDigest {
+    pub data_type: String,
+    pub batches: u32,
+    pub logs: u32,
+    pub succeeded: u32,
+    pub failed: u32,
+    pub exhausted: u32,
+    pub save_failures: u32,
+    pub failure_reasons: BTreeMap<String, u32>,
+    pub avg_lag_ms: i64,
+    pub max_lag_ms: i64,
+    pub avg_batch_size: f64,
+    pub max_batch_size: u32,
+}
+
+/// 按数据类型汇总批次统计，平均延迟按批大小加权
+pub fn summarize(batches: &[BatchStats]) -> Vec<DailyDigest> {
+    let mut digests: BTreeMap<&str, DailyDigest> = BTreeMap::new();
+    let mut lag_totals: BTreeMap<&str, i64> = BTreeMap::new();
+    for batch in batches {
+        let digest = digests
+            .entry(&batch.data_type)
+            .or_insert_with(|| DailyDigest {
+                data_type: batch.data_type.clone(),
+                ..Default::default()
+            });
+        digest.batches += 1;
+        digest.logs += batch.batch_size;
+        digest.succeeded += batch.succeeded;
+        digest.failed += batch.failed;
+        digest.exhausted += batch.exhausted;
+        digest.save_failures += u32::from(batch.save_failed);
+        for (reason, count) in &batch.failure_reasons {
+            *digest.failure_reasons.entry(reason.clone()).or_default() += count;
+        }
+        digest.max_lag_ms = digest.max_lag_ms.max(batch.max_lag_ms);
+        digest.max_batch_size = digest.max_batch_size.max(batch.batch_size);
+        *lag_totals.entry(&batch.data_type).or_default() +=
+            batch.avg_lag_ms * i64::from(batch.batch_size);
+    }
+    digests
+        .into_iter()
+        .map(|(data_type, mut digest)| {
+            if digest.logs > 0 {
+                digest.avg_lag_ms = lag_totals[data_type] / i64::from(digest.logs);
+            }
+            digest.avg_batch_size = f64::from(digest.logs) / f64::from(digest.batches);
+            digest
+        })
+        .collect()
+}
+
+/// 读取某一天的批次统计
+pub async fn load_batch_stats(mysql_pool: &MySqlPool, date: NaiveDate) -> Result<Vec<BatchStats>> {
+    let start = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    let end = (date + Days::new(1))
+        .and_hms_opt(0, 0, 0)
+        .unwrap_or_default();
+    let query = sqlx::query(
+        "SELECT data_type, priority, batch_size, succeeded, failed, exhausted, CAST(failure_reasons AS CHAR) AS failure_reasons, avg_lag_ms, max_lag_ms, save_failed FROM binlog_batch_stats WHERE created_at >= ? AND created_at < ?",
+    )
+    .bind(start)
+    .bind(end);
+    let rows = QueryRunner::new("binlog_batch_stats_by_date")
+        .run(query.fetch_all(mysql_pool))
+        .await
+        .context("Failed to query binlog_batch_stats table")?;
+
+    let mut batches = Vec::with_capacity(rows.len());
+    for row in rows {
+        let failure_reasons: String = row.try_get("failure_reasons")?;
+        batches.push(BatchStats {
+            data_type: row.try_get("data_type")?,
+            priority: row.try_get("priority")?,
+            batch_size: row.try_get("batch_size")?,
+            succeeded: row.try_get("succeeded")?,
+            failed: row.try_get("failed")?,
+            exhausted: row.try_get("exhausted")?,
+            failure_reasons: serde_json::from_str(&failure_reasons).unwrap_or_default(),
+            avg_lag_ms: row.try_get("avg_lag_ms")?,
+            max_lag_ms: row.try_get("max_lag_ms")?,
+            save_failed: row.try_get("save_failed")?,
+        });
+    }
+    Ok(batches)
+}
+
+/// 写入日报，重复生成同一天时覆盖
+pub async fn upsert_daily_digest(
+    mysql_pool: &MySqlPool,
+    date: NaiveDate,
+    digest: &DailyDigest,
+) -> Result<()> {
+    let query = sqlx::query(
+        "INSERT INTO binlog_daily_digest (stat_date, data_type, batches, logs, succeeded, failed, exhausted, save_failures, failure_reasons, avg_lag_ms, max_lag_ms, avg_batch_size, max_batch_size) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) ON DUPLICATE KEY UPDATE batches = VALUES(batches), logs = VALUES(logs), succeeded = VALUES(succeeded), failed = VALUES(failed), exhausted = VALUES(exhausted), save_failures = VALUES(save_failures), failure_reasons = VALUES(failure_reasons), avg_lag_ms = VALUES(avg_lag_ms), max_lag_ms = VALUES(max_lag_ms), avg_batch_size = VALUES(avg_batch_size), max_batch_size = VALUES(max_batch_size)",
+    )
+    .bind(date)
+    .bind(&digest.data_type)
+    .bind(digest.batches)
+    .bind(digest.logs)
+    .bind(digest.succeeded)
+    .bind(digest.failed)
+    .bind(digest.exhausted)
+    .bind(digest.save_failures)
+    .bind(serde_json::to_string(&digest.failure_reasons)?)
+    .bind(digest.avg_lag_ms)
+    .bind(digest.max_lag_ms)
+    .bind(digest.avg_batch_size)
+    .bind(digest.max_batch_size);
+    QueryRunner::new("binlog_daily_digest_upsert")
+        .run(query.execute(mysql_pool))
+        .await
+        .context("Failed to upsert binlog_daily_digest table")?;
+    Ok(())
+}
+
+/// 日报的文本摘要，按 language 输出，失败原因按次数降序
+pub fn format_digest(date: NaiveDate, digests: &[DailyDigest], language: Language) -> String {
+    let mut text = Message::DigestTitle {
+        date: date.to_string(),
+    }
+    .describe(language);
+    text.push('\n');
+    if digests.is_empty() {
+        let _ = writeln!(text, "{}", Message::DigestEmpty.describe(language));
+        return text;
+    }
+    for digest in digests {
+        let entry = Message::DigestEntry {
+            data_type: digest.data_type.clone(),
+            logs: digest.logs,
+            succeeded: digest.succeeded,
+            failed: digest.failed,
+            exhausted: digest.exhausted,
+            batches: digest.batches,
+            avg_batch_size: digest.avg_batch_size,
+            max_batch_size: digest.max_batch_size,
+            save_failures: digest.save_failures,
+            avg_lag_secs: digest.avg_lag_ms as f64 / 1000.0,
+            max_lag_secs: digest.max_lag_ms as f64 / 1000.0,
+        };
+        let _ = writeln!(text, "{}", entry.describe(language));
+        let mut reasons: Vec<_> = digest.failure_reasons.iter().collect();
+        reasons.sort_by(|a, b| b.1.cmp(a.1));
+        for (reason, count) in reasons {
+            let _ = writeln!(text, "  - {reason}: {count}");
+        }
+    }
+    text
+}
+
+#[test]
+fn test_summarize_batch_stats() {
+    let mut first = BatchStats {
+        data_type: "user".to_string(),
+        priority: "normal".to_string(),
+        batch_size: 10,
+        succeeded: 8,
+        ..Default::default()
+    };
+    first.add_failure("MSS organization not found for CID: 1001");
+    first.add_failure("MSS organization not found for CID: 1002");
+    first.set_lags(&[1000, 3000]);
+    let second = BatchStats {
+        data_type: "user".to_string(),
+        priority: "high".to_string(),
+        batch_size: 30,
+        succeeded: 29,
+        exhausted: 1,
+        avg_lag_ms: 6000,
+        max_lag_ms: 9000,
+        save_failed: true,
+        ..Default::default()
+    };
+
+    let digests = summarize(&[first, second]);
+    assert_eq!(digests.len(), 1);
+    let digest = &digests[0];
+    assert_eq!(digest.logs, 40);
+    assert_eq!(digest.failed, 2);
+    assert_eq!(digest.save_failures, 1);
+    assert_eq!(
+        digest
+            .failure_reasons
+            .get("MSS organization not found for CID"),
+        Some(&2)
+    );
+    // (2000 * 10 + 6000 * 30) / 40
+    assert_eq!(digest.avg_lag_ms, 5000);
+    assert_eq!(digest.max_lag_ms, 9000);
+    assert_eq!(digest.avg_batch_size, 20.0);
+}
+
+#[test]
+fn test_format_digest_language() {
+    let date = NaiveDate::from_ymd_opt(2026, 10, 15).unwrap();
+    assert_eq!(
+        format_digest(date, &[], Language::En),
+        "binlog sync digest 2026-10-15\nno sync records\n"
+    );
+    assert_eq!(
+        format_digest(date, &[], Language::Zh),
+        "binlog 同步日报 2026-10-15\n无同步记录\n"
+    );
+}
@@ -1,21 +1,31 @@
 use crate::AppContext;
+use crate::binlog::mapping_cache::cached_user_mapping;
 use crate::binlog::processor::{
-    DataProcessorTrait, MergeableProcessedData, ProcessingState, Transition, clean_field,
+    DataProcessorTrait, MergeableProcessedData, ProcessingState, Transition, Versioned,
+    clean_field, dedup_newest,
 };
-use crate::schedule::binlog_sync::{EntityMetaInfo, ModifyOperationLog};
-use crate::utils::{MapToProcessError, ProcessError, mysql_client};
+use crate::binlog::refresh::{
+    MC_USER_ZTK_REFRESH, is_schema_incompatible, record_incompatible_refresh,
+};
+use crate::config::ColumnPolicy;
+use crate::db::query_runner::QueryRunner;
+use crate::schedule::binlog_sync::{DataType, EntityMetaInfo, ModifyOperationLog};
+use crate::utils::lenient_number::lenient_number;
+use crate::utils::retry::RetryPolicy;
+use crate::utils::{Clock, MapToProcessError, ProcessError, mysql_client, timefmt};
 use anyhow::{Result, anyhow};
 use async_trait::async_trait;
 use chrono::NaiveDateTime;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use sqlx::{Execute, MySql, QueryBuilder, Transaction};
+use sqlx::query_builder::Separated;
+use sqlx::{MySql, MySqlPool, QueryBuilder, Transaction};
 use std::cmp::Ordering;
 use std::hash::{Hash, Hasher};
 use std::ops::DerefMut;
 use std::sync::Arc;
-use tracing::info;
+use tracing::{info, warn};
 
 type Transition_ = Transition<TelecomUser, (), TelecomMssUserMapping, TelecomMssUser>;
 
@@ -28,21 +38,27 @@ pub struct TelecomUser {
     pub delete: Option<bool>,
     pub loginname: Option<String>,
     pub name: Option<String>,
+    #[serde(default, deserialize_with = "lenient_number")]
     pub gender: Option<i32>,
     pub photo: Option<String>,
     pub no: Option<String>,
+    #[serde(default, deserialize_with = "lenient_number")]
     pub certificate_type: Option<i32>,
     pub certificate_code: Option<String>,
     pub is_ehr_sync: Option<bool>,
     pub org: Option<String>,
+    #[serde(default, deserialize_with = "lenient_number")]
     pub status: Option<i32>,
     pub contact_info: Option<ContactInfo>,
     pub job_info: Option<JobInfo>,
+    #[serde(default, deserialize_with = "lenient_number")]
     pub effective_time_start: Option<i64>,
+    #[serde(default, deserialize_with = "lenient_number")]
     pub effective_time_end: Option<i64>,
     pub archives_info: Option<ArchivesInfo>,
     pub is_outter: Option<bool>,
     pub user_group_ids: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "lenient_number")]
     pub account_type: Option<i32>,
     pub ext: Option<UserExt>,
     #[serde(rename = "encryptCertificate_code")]
@@ -91,14 +107,17 @@ impl ContactInfo {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ArchivesInfo {
+    #[serde(default, deserialize_with = "lenient_number")]
     pub birthday: Option<i64>,
     #[serde(rename = "isonlychild")]
     pub is_only_child: Option<bool>,
     pub is_union_members: Option<bool>,
     pub major: Option<String>,
     pub folk: Option<String>,
+    #[serde(default, deserialize_with = "lenient_number")]
     pub join_union_date: Option<i64>,
     pub political: Option<String>,
+    #[serde(default, deserialize_with = "lenient_number")]
     pub party_date: Option<i64>,
     pub academy: Option<String>,
 }
@@ -113,14 +132,23 @@ impl ArchivesInfo {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JobInfo {
+    #[serde(default, deserialize_with = "lenient_number")]
     pub positive_date: Option<i32>,
+    #[serde(default, deserialize_with = "lenient_number")]
     pub special_job_years: Option<i32>,
+    #[serde(default, deserialize_with = "lenient_number")]
     pub work_date: Option<i64>,
+    #[serde(default, deserialize_with = "lenient_number")]
     pub leave_date: Option<i32>,
     pub is_special_job: Option<bool>,
+    #[serde(default, deserialize_with = "lenient_number")]
     pub work_age: Option<i32>,
     pub is_core_staff: Option<bool>,
-    #[serde(rename = "enterunit_date")]
+    #[serde(
+        rename = "enterunit_date",
+        default,
+        deserialize_with = "lenient_number"
+    )]
     pub enter_unit_date: Option<i64>,
 }
 
@@ -132,10 +160,12 @@ pub struct UserExt {
     pub pro_info: Option<Vec<Value>>,
     pub job_info: Option<ExtJobInfo>,
     pub name_card: Option<NameCard>,
+    #[serde(default, deserialize_with = "lenient_number")]
     pub weight: Option<f32>,
     pub is_activated: Option<bool>,
     pub authorize_info: Option<AuthorizeInfo>,
     pub password_reset: Option<bool>,
+    #[serde(default, deserialize_with = "lenient_number")]
     pub activated_time: Option<i64>,
 }
 
@@ -211,7 +241,11 @@ impl BaseStation {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthorizeInfo {
-    #[serde(rename = "expirationDate")]
+    #[serde(
+        rename = "expirationDate",
+        default,
+        deserialize_with = "lenient_number"
+    )]
     pub expiration_date: Option<i64>,
     #[serde(rename = "mobileVague")]
     pub mobile_vague: Option<String>,
@@ -240,76 +274,177 @@ pub struct AuthorizeInfo {
     pub identity_card_vague: Option<String>,
 }
 
-/// 一个平铺的结构体，专门用于批量插入 d_telecom_user 表
-struct InsertTelecomUser {
-    base_station_sequence: Option<String>,
-    base_station_code: Option<String>,
-    base_station_system: Option<String>,
-    base_station_gradesystem: Option<String>,
-    base_station_level: Option<String>,
-    base_station_grade: Option<String>,
-    base_station_name: Option<String>,
-    password_reset: Option<String>,
-    ext_job_info_jobstatus: Option<String>,
-    ext_job_info_jobcategory: Option<String>,
-    ext_job_info_hrjobtype: Option<String>,
-    ext_job_info_jobtype: Option<String>,
-    name_card_company_id: Option<String>,
-    name_card_gender: Option<String>,
-    name_card_companyphone: Option<String>,
-    name_card_organization: Option<String>,
-    name_card_name: Option<String>,
-    name_card_station: Option<String>,
-    name_card_mobile: Option<String>,
-    name_card_folk: Option<String>,
-    name_card_company: Option<String>,
-    name_card_email: Option<String>,
-    weight: Option<f32>,
-    no: Option<String>,
-    account_type: Option<i32>,
-    datelastmodified: Option<i64>,
-    certificate_code: Option<String>,
-    gender: Option<i32>,
-    loginname: Option<String>,
-    org: Option<String>,
-    job_info_positive_date: Option<i32>,
-    job_info_special_job_years: Option<i32>,
-    job_info_work_date: Option<i64>,
-    job_info_is_special_job: Option<String>,
-    job_info_leave_date: Option<i32>,
-    job_info_work_age: Option<i32>,
-    job_info_is_core_staff: Option<String>,
-    job_info_enterunit_date: Option<i64>,
-    is_ehr_sync: Option<String>,
-    photo: Option<String>,
-    effective_time_end: Option<i64>,
-    contact_info_phone: Option<String>,
-    contact_info_mobile: Option<String>,
-    contact_info_email: Option<String>,
-    user_group_ids: Option<String>,
-    d_delete: Option<String>,
-    is_delete: Option<String>,
-    effective_time_start: Option<i64>,
-    encryptcertificate_code: Option<String>,
-    name: Option<String>,
-    id: String,
-    certificate_type: Option<i32>,
-    status: Option<i32>,
-    archives_info_birthday: Option<i64>,
-    archives_info_isonlychild: Option<String>,
-    archives_info_is_union_members: Option<String>,
-    archives_info_major: Option<String>,
-    archives_info_folk: Option<String>,
-    archives_info_join_union_date: Option<i64>,
-    archives_info_political: Option<String>,
-    archives_info_party_date: Option<i64>,
-    archives_info_academy: Option<String>,
-    hit_date: Option<String>,
-    in_time: Option<NaiveDateTime>,
-    year: Option<String>,
-    month: Option<String>,
-    archived_batches: Option<String>,
-    hit_date1: Option<NaiveDateTime>,
+/// d_telecom_user 的列表：字段、类型与列名只在这里声明一次，
+/// 结构体定义、INSERT 列清单和绑定顺序都由它生成，三者不会不一致
+macro_rules! insert_telecom_user_columns {
+    ($($field:ident: $ty:ty => $column:literal,)*) => {
+        /// 一个平铺的结构体，专门用于批量插入 d_telecom_user 表
+        struct InsertTelecomUser {
+            $($field: $ty,)*
+        }
+
+        impl InsertTelecomUser {
+            const COLUMNS: &'static [&'static str] = &[$($column),*];
+
+            /// 按 COLUMNS 的顺序绑定一行的值
+            fn push_binds<'args>(&'args self, b: &mut Separated<'_, 'args, MySql, &'static str>) {
+                $(b.push_bind(&self.$field);)*
+            }
+
+            /// 按列名把字段置为 NULL，未知列与主键 id 忽略
+            fn clear_column(&mut self, column: &str) {
+                match column {
+                    $($column => self.$field.set_null(),)*
+                    _ => {}
+                }
+            }
+        }
+    };
+}
+
+insert_telecom_user_columns! {
+    base_station_sequence: Option<String> => "base_station_sequence",
+    base_station_code: Option<String> => "base_station_code",
+    base_station_system: Option<String> => "base_station_system",
+    base_station_gradesystem: Option<String> => "base_station_gradesystem",
+    base_station_level: Option<String> => "base_station_level",
+    base_station_grade: Option<String> => "base_station_grade",
+    base_station_name: Option<String> => "base_station_name",
+    password_reset: Option<String> => "password_reset",
+    ext_job_info_jobstatus: Option<String> => "ext_job_info_jobstatus",
+    ext_job_info_jobcategory: Option<String> => "ext_job_info_jobcategory",
+    ext_job_info_hrjobtype: Option<String> => "ext_job_info_hrjobtype",
+    ext_job_info_jobtype: Option<String> => "ext_job_info_jobtype",
+    name_card_company_id: Option<String> => "name_card_company_id",
+    name_card_gender: Option<String> => "name_card_gender",
+    name_card_companyphone: Option<String> => "name_card_companyphone",
+    name_card_organization: Option<String> => "name_card_organization",
+    name_card_name: Option<String> => "name_card_name",
+    name_card_station: Option<String> => "name_card_station",
+    name_card_mobile: Option<String> => "name_card_mobile",
+    name_card_folk: Option<String> => "name_card_folk",
+    name_card_company: Option<String> => "name_card_company",
+    name_card_email: Option<String> => "name_card_email",
+    weight: Option<f32> => "weight",
+    no: Option<String> => "no",
+    account_type: Option<i32> => "account_type",
+    datelastmodified: Option<i64> => "datelastmodified",
+    certificate_code: Option<String> => "certificate_code",
+    gender: Option<i32> => "gender",
+    loginname: Option<String> => "loginname",
+    org: Option<String> => "org",
+    job_info_positive_date: Option<i32> => "job_info_positive_date",
+    job_info_special_job_years: Option<i32> => "job_info_special_job_years",
+    job_info_work_date: Option<i64> => "job_info_work_date",
+    job_info_is_special_job: Option<String> => "job_info_is_special_job",
+    job_info_leave_date: Option<i32> => "job_info_leave_date",
+    job_info_work_age: Option<i32> => "job_info_work_age",
+    job_info_is_core_staff: Option<String> => "job_info_is_core_staff",
+    job_info_enterunit_date: Option<i64> => "job_info_enterunit_date",
+    is_ehr_sync: Option<String> => "is_ehr_sync",
+    photo: Option<String> => "photo",
+    effective_time_end: Option<i64> => "effective_time_end",
+    contact_info_phone: Option<String> => "contact_info_phone",
+    contact_info_mobile: Option<String> => "contact_info_mobile",
+    contact_info_email: Option<String> => "contact_info_email",
+    user_group_ids: Option<String> => "user_group_ids",
+    d_delete: Option<String> => "d_delete",
+    is_delete: Option<String> => "is_delete",
+    effective_time_start: Option<i64> => "effective_time_start",
+    encryptcertificate_code: Option<String> => "encryptcertificate_code",
+    name: Option<String> => "name",
+    id: String => "id",
+    certificate_type: Option<i32> => "certificate_type",
+    status: Option<i32> => "status",
+    archives_info_birthday: Option<i64> => "archives_info_birthday",
+    archives_info_isonlychild: Option<String> => "archives_info_isonlychild",
+    archives_info_is_union_members: Option<String> => "archives_info_is_union_members",
+    archives_info_major: Option<String> => "archives_info_major",
+    archives_info_folk: Option<String> => "archives_info_folk",
+    archives_info_join_union_date: Option<i64> => "archives_info_join_union_date",
+    archives_info_political: Option<String> => "archives_info_political",
+    archives_info_party_date: Option<i64> => "archives_info_party_date",
+    archives_info_academy: Option<String> => "archives_info_academy",
+    hit_date: Option<String> => "hitdate",
+    in_time: Option<NaiveDateTime> => "intime",
+    year: Option<String> => "year",
+    month: Option<String> => "month",
+    archived_batches: Option<String> => "archived_batches",
+    hit_date1: Option<NaiveDateTime> => "hitdate1",
+}
+
+/// 落库策略置空列时使用，主键（非 Option 字段）保持不变
+trait ClearColumn {
+    fn set_null(&mut self);
+}
+
+impl<T> ClearColumn for Option<T> {
+    fn set_null(&mut self) {
+        *self = None;
+    }
+}
+
+impl ClearColumn for String {
+    fn set_null(&mut self) {}
+}
+
+/// d_telecom_user 写入的列，落库策略中的列名按它校验
+pub(crate) const TELECOM_USER_COLUMNS: &[&str] = InsertTelecomUser::COLUMNS;
+
+impl InsertTelecomUser {
+    fn insert_sql() -> String {
+        format!("INSERT INTO d_telecom_user ({}) ", Self::COLUMNS.join(", "))
+    }
+
+    /// 策略排除的列，主键 id 不在其中
+    fn excluded_columns(policy: &ColumnPolicy) -> Vec<&'static str> {
+        Self::COLUMNS
+            .iter()
+            .copied()
+            .filter(|column| *column != "id" && !policy.permits(column))
+            .collect()
+    }
+}
+
+/// 按 d_telecom_user 的落库策略删除 JSON 中被排除列对应的字段，用于审计快照、网关缓存、
+/// 问题报告等数据库之外保存的副本。字段路径按列名的规则拼接（archivesInfo.political
+/// 对应 archives_info_political，ext 下的字段省略 ext 前缀），比较时忽略大小写与下划线
+pub fn redact_excluded_columns(policy: &ColumnPolicy, value: &mut Value) {
+    let excluded: Vec<String> = InsertTelecomUser::excluded_columns(policy)
+        .into_iter()
+        .map(normalize_column)
+        .collect();
+    if !excluded.is_empty() {
+        redact_path(&excluded, "", value);
+    }
+}
+
+fn normalize_column(name: &str) -> String {
+    name.replace('_', "").to_lowercase()
+}
+
+fn redact_path(excluded: &[String], prefix: &str, value: &mut Value) {
+    match value {
+        Value::Object(map) => map.retain(|key, child| {
+            let path = format!("{prefix}{}", normalize_column(key));
+            let column = path
+                .strip_prefix("ext")
+                .filter(|_| prefix.starts_with("ext"));
+            if excluded
+                .iter()
+                .any(|c| *c == path || Some(c.as_str()) == column)
+            {
+                return false;
+            }
+            redact_path(excluded, &path, child);
+            true
+        }),
+        // 数组中的元素沿用所在字段的路径
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| redact_path(excluded, prefix, item)),
+        _ => {}
+    }
 }
 
 impl From<TelecomUser> for InsertTelecomUser {
@@ -420,6 +555,7 @@ impl From<TelecomUser> for InsertTelecomUser {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TelecomMssUser {
     pub id: Option<String>,
+    #[serde(default, deserialize_with = "lenient_number")]
     pub time: Option<i64>,
     pub identity: Option<String>,
     pub code: Option<String>,
@@ -436,14 +572,17 @@ pub struct TelecomMssUser {
     pub organization_code: Option<String>,
     #[serde(rename = "companyCode")]
     pub company_code: Option<String>,
+    #[serde(default, deserialize_with = "lenient_number")]
     pub sex: Option<i32>,
     #[serde(rename = "identityCard")]
     pub identity_card: Option<String>,
+    #[serde(default, deserialize_with = "lenient_number")]
     pub birthday: Option<i64>,
     #[serde(rename = "firstMobile")]
     pub first_mobile: Option<String>,
-    #[serde(rename = "userStatus")]
+    #[serde(rename = "userStatus", default, deserialize_with = "lenient_number")]
     pub user_status: Option<i32>,
+    #[serde(default, deserialize_with = "lenient_number")]
     pub sort: Option<f32>,
     #[serde(rename = "jobNumber")]
     pub job_number: Option<String>,
@@ -540,6 +679,8 @@ pub struct TelecomMssUserMapping {
     pub organization: Option<String>,
     #[serde(rename = "standardStation")]
     pub standard_station: Option<String>,
+    #[serde(skip)]
+    pub from_cache: bool, // 取自本地 d_mss_user_mapping，落库时不重写，写入时间保持不变
 }
 
 impl PartialEq for TelecomMssUserMapping {
@@ -564,26 +705,42 @@ impl Hash for TelecomMssUserMapping {
     }
 }
 
-// 用于在处理过程中聚合所有相关数据的结构体
+// 用于在处理过程中聚合所有相关数据的结构体，待插入的记录带版本，落库时按 ID 保留最新的版本
 #[derive(Default)]
 pub struct ProcessedUserData {
-    pub telecom_users: Vec<TelecomUser>,
-    pub mss_user_mappings: Vec<TelecomMssUserMapping>,
-    pub mss_users: Vec<TelecomMssUser>,
+    pub telecom_users: Vec<Versioned<TelecomUser>>,
+    pub mss_user_mappings: Vec<Versioned<TelecomMssUserMapping>>,
+    pub mss_users: Vec<Versioned<TelecomMssUser>>,
 
-    pub user_ids_to_delete: Vec<String>, // 根据网大ID删除d_telecom_user表以及d_mss_user_mapping表数据
+    pub user_ids_to_delete: Vec<String>, // 根据网大ID删除d_telecom_user表数据
+    pub mapping_user_ids_to_delete: Vec<String>, // 删除日志根据网大ID删除d_mss_user_mapping表数据
     pub job_numbers_to_delete: Vec<String>, // 根据job_number删除d_mss_user表数据
     pub hr_codes_to_delete: Vec<String>, // 根据hr_code删除d_mss_user表数据
 }
 
+impl ProcessedUserData {
+    /// 删除日志之前同一用户待写入的记录不再写入：落库时先删后插，否则会把已删除的用户写回
+    fn drop_pending_user(&mut self, user_id: &str) {
+        self.telecom_users.retain(|user| user.record.id != user_id);
+        self.mss_user_mappings
+            .retain(|mapping| mapping.record.uid.as_deref() != Some(user_id));
+    }
+}
+
 impl MergeableProcessedData for ProcessedUserData {
     fn merge(&mut self, other: &mut Self) {
+        // other 来自之后的重试轮次，同一用户的日志按顺序推进，其中的删除晚于 self 中的写入
+        for user_id in &other.mapping_user_ids_to_delete {
+            self.drop_pending_user(user_id);
+        }
         self.telecom_users.append(&mut other.telecom_users);
         self.mss_user_mappings.append(&mut other.mss_user_mappings);
         self.mss_users.append(&mut other.mss_users);
 
         self.user_ids_to_delete
             .append(&mut other.user_ids_to_delete);
+        self.mapping_user_ids_to_delete
+            .append(&mut other.mapping_user_ids_to_delete);
         self.job_numbers_to_delete
             .append(&mut other.job_numbers_to_delete);
         self.hr_codes_to_delete
@@ -605,16 +762,12 @@ impl UserDataProcessor {
         &self,
         log: ModifyOperationLog,
     ) -> Result<Transition_, ProcessError> {
-        match self.transform_to_telecom_user(&log).await? {
-            // 成功获取，返回 Advanced 状态
-            Some(user) => Ok(Transition_::Advanced(Box::new(ProcessingState::GotStep1(
-                log,
-                Box::new(user),
-            )))),
-            None => Err(ProcessError::Permanent(anyhow::anyhow!(
-                "Unable to find corresponding TelecomUser"
-            ))),
-        }
+        let user = self.transform_to_telecom_user(&log).await?;
+        // 成功获取，返回 Advanced 状态
+        Ok(Transition_::Advanced(Box::new(ProcessingState::GotStep1(
+            log,
+            Box::new(user),
+        ))))
     }
 
     async fn handle_got_telecom_user_state(
@@ -634,12 +787,7 @@ impl UserDataProcessor {
         hr_code: String,
     ) -> Result<Transition_, ProcessError> {
         // 1. 获取 mss_users 列表
-        let mss_users = self
-            .transform_to_mss_users(&hr_code)
-            .await?
-            .ok_or_else(|| {
-                ProcessError::Permanent(anyhow::anyhow!("Unable to find TelecomMssUser"))
-            })?;
+        let mss_users = self.transform_to_mss_users(&hr_code).await?;
 
         // mss_users 接口返回的只有一个值，所以这里取最小没有意义了，但还是保留吧
         // 2. 使用 .iter().min() 找到优先级最高（最小）的用户
@@ -658,12 +806,14 @@ impl UserDataProcessor {
     async fn transform_to_telecom_user(
         &self,
         log: &ModifyOperationLog,
-    ) -> Result<Option<TelecomUser>, ProcessError> {
+    ) -> Result<TelecomUser, ProcessError> {
         let cid = log
             .cid
             .as_deref()
             .ok_or_else(|| anyhow::anyhow!("CID is missing for log {}", log.id))?;
 
+        // 每条变更日志都意味着用户已变化，不能使用缓存中变更前的用户
+        self.app_context.gateway_client.invalidate_user(cid).await;
         self.app_context
             .gateway_client
             .user_loadbyid(cid)
@@ -680,20 +830,19 @@ impl UserDataProcessor {
             ProcessError::Permanent(anyhow::anyhow!("CID is missing for log {}", log.id))
         })?;
 
-        // 2. 处理网络调用：在这里区分超时错误和其它错误
-        let mapping_option = self
-            .app_context
-            .gateway_client
-            .mss_user_translate(cid)
-            .await
-            .map_gateway_err()?;
-
-        // 3. 处理逻辑错误：如果 API 调用成功但没有返回数据，这是一个永久性错误
-        let mapping = mapping_option.ok_or_else(|| {
-            ProcessError::Permanent(anyhow::anyhow!("MSS user not found for CID: {cid}"))
-        })?;
+        // 2. 本地 d_mss_user_mapping 中有未过期的映射时直接使用，否则调用网关，区分超时错误和其它错误；
+        //    网关没有返回数据时 NotFound 是永久性错误
+        let mapping = match cached_user_mapping(&self.app_context, cid).await {
+            Some(mapping) => mapping,
+            None => self
+                .app_context
+                .gateway_client
+                .mss_user_translate(cid)
+                .await
+                .map_gateway_err()?,
+        };
 
-        // 4. 处理逻辑错误：如果返回的数据缺少必要的 mss_code 字段，这也是一个永久性错误
+        // 3. 处理逻辑错误：如果返回的数据缺少必要的 mss_code 字段，这也是一个永久性错误
         let hr_code = mapping.hr_code.clone().ok_or_else(|| {
             ProcessError::Permanent(anyhow::anyhow!("MSS hr_code is missing for mapping"))
         })?;
@@ -704,7 +853,7 @@ impl UserDataProcessor {
     async fn transform_to_mss_users(
         &self,
         hr_code: &str,
-    ) -> Result<Option<Vec<TelecomMssUser>>, ProcessError> {
+    ) -> Result<Vec<TelecomMssUser>, ProcessError> {
         self.app_context
             .gateway_client
             .mss_user_queryorder(hr_code)
@@ -712,6 +861,34 @@ impl UserDataProcessor {
             .map_gateway_err()
     }
 
+    /// 按落库策略把 d_telecom_user 中被排除的列置为 NULL，并记录应用的策略版本
+    fn apply_persistence_policy(&self, users: &mut [InsertTelecomUser]) {
+        let policy_config = &self.app_context.persistence_policy;
+        let Some(policy) = policy_config.table("d_telecom_user") else {
+            return;
+        };
+        if !policy.permits("id") {
+            warn!(
+                "Persistence policy {} excludes primary key d_telecom_user.id, ignored",
+                policy_config.version
+            );
+        }
+        let excluded = InsertTelecomUser::excluded_columns(policy);
+        if excluded.is_empty() {
+            return;
+        }
+        for user in users.iter_mut() {
+            for column in &excluded {
+                user.clear_column(column);
+            }
+        }
+        info!(
+            "Applied persistence policy {} to {} d_telecom_user rows, nulled columns: {excluded:?}",
+            policy_config.version,
+            users.len()
+        );
+    }
+
     async fn batch_insert_telecom_users(
         &self,
         tx: &mut Transaction<'_, MySql>,
@@ -721,154 +898,49 @@ impl UserDataProcessor {
             return Ok(());
         }
         // 预转换：O(n) 开销，但逻辑分离
-        let insert_users: Vec<InsertTelecomUser> = users.into_iter().map(Into::into).collect();
+        let mut insert_users: Vec<InsertTelecomUser> = users.into_iter().map(Into::into).collect();
+        self.apply_persistence_policy(&mut insert_users);
 
-        // 使用 QueryBuilder 安全地构建批量插入语句
-        let mut query_builder = QueryBuilder::new(
-            "INSERT INTO d_telecom_user (
-            base_station_sequence,
-            base_station_code,
-            base_station_system,
-            base_station_gradesystem,
-            base_station_level,
-            base_station_grade,
-            base_station_name,
-            password_reset,
-            ext_job_info_jobstatus,
-            ext_job_info_jobcategory,
-            ext_job_info_hrjobtype,
-            ext_job_info_jobtype,
-            name_card_company_id,
-            name_card_gender,
-            name_card_companyphone,
-            name_card_organization,
-            name_card_name,
-            name_card_station,
-            name_card_mobile,
-            name_card_folk,
-            name_card_company,
-            name_card_email,
-            weight,
-            no,
-            account_type,
-            datelastmodified,
-            certificate_code,
-            gender,
-            loginname,
-            org,
-            job_info_positive_date,
-            job_info_special_job_years,
-            job_info_work_date,
-            job_info_is_special_job,
-            job_info_leave_date,
-            job_info_work_age,
-            job_info_is_core_staff,
-            job_info_enterunit_date,
-            is_ehr_sync,
-            photo,
-            effective_time_end,
-            contact_info_phone,
-            contact_info_mobile,
-            contact_info_email,
-            user_group_ids,
-            d_delete,
-            is_delete,
-            effective_time_start,
-            encryptcertificate_code,
-            name,
-            id,
-            certificate_type,
-            status,
-            archives_info_birthday,
-            archives_info_isonlychild,
-            archives_info_is_union_members,
-            archives_info_major,
-            archives_info_folk,
-            archives_info_join_union_date,
-            archives_info_political,
-            archives_info_party_date,
-            archives_info_academy,
-            hitdate,
-            intime,
-            year,
-            month,
-            archived_batches,
-            hitdate1
-        ) ",
-        );
-        query_builder.push_values(&insert_users, |mut b, user| {
-            // 按照SQL语句中列的顺序绑定值
-            b.push_bind(&user.base_station_sequence)
-                .push_bind(&user.base_station_code)
-                .push_bind(&user.base_station_system)
-                .push_bind(&user.base_station_gradesystem)
-                .push_bind(&user.base_station_level)
-                .push_bind(&user.base_station_grade)
-                .push_bind(&user.base_station_name)
-                .push_bind(&user.password_reset)
-                .push_bind(&user.ext_job_info_jobstatus)
-                .push_bind(&user.ext_job_info_jobcategory)
-                .push_bind(&user.ext_job_info_hrjobtype)
-                .push_bind(&user.ext_job_info_jobtype)
-                .push_bind(&user.name_card_company_id)
-                .push_bind(&user.name_card_gender)
-                .push_bind(&user.name_card_companyphone)
-                .push_bind(&user.name_card_organization)
-                .push_bind(&user.name_card_name)
-                .push_bind(&user.name_card_station)
-                .push_bind(&user.name_card_mobile)
-                .push_bind(&user.name_card_folk)
-                .push_bind(&user.name_card_company)
-                .push_bind(&user.name_card_email)
-                .push_bind(user.weight)
-                .push_bind(&user.no)
-                .push_bind(user.account_type)
-                .push_bind(user.datelastmodified) // 假设 entity_meta_info 中有 datelastmodified
-                .push_bind(&user.certificate_code)
-                .push_bind(user.gender)
-                .push_bind(&user.loginname)
-                .push_bind(&user.org)
-                .push_bind(user.job_info_positive_date)
-                .push_bind(user.job_info_special_job_years)
-                .push_bind(user.job_info_work_date)
-                .push_bind(&user.job_info_is_special_job)
-                .push_bind(user.job_info_leave_date)
-                .push_bind(user.job_info_work_age)
-                .push_bind(&user.job_info_is_core_staff)
-                .push_bind(user.job_info_enterunit_date)
-                .push_bind(&user.is_ehr_sync)
-                .push_bind(&user.photo)
-                .push_bind(user.effective_time_end)
-                .push_bind(&user.contact_info_phone)
-                .push_bind(&user.contact_info_mobile)
-                .push_bind(&user.contact_info_email)
-                .push_bind(&user.user_group_ids) // 将Vec<String>转换为字符串
-                .push_bind(&user.d_delete)
-                .push_bind(&user.is_delete)
-                .push_bind(user.effective_time_start)
-                .push_bind(&user.encryptcertificate_code)
-                .push_bind(&user.name)
-                .push_bind(&user.id)
-                .push_bind(user.certificate_type)
-                .push_bind(user.status)
-                .push_bind(user.archives_info_birthday)
-                .push_bind(&user.archives_info_isonlychild)
-                .push_bind(&user.archives_info_is_union_members)
-                .push_bind(&user.archives_info_major)
-                .push_bind(&user.archives_info_folk)
-                .push_bind(user.archives_info_join_union_date)
-                .push_bind(&user.archives_info_political)
-                .push_bind(user.archives_info_party_date)
-                .push_bind(&user.archives_info_academy)
-                .push_bind(&user.hit_date)
-                .push_bind(user.in_time)
-                .push_bind(&user.year)
-                .push_bind(&user.month)
-                .push_bind(&user.archived_batches)
-                .push_bind(user.hit_date1);
+        // 列清单与绑定顺序都来自 insert_telecom_user_columns!
+        let mut query_builder = QueryBuilder::new(InsertTelecomUser::insert_sql());
+        query_builder.push_values(&insert_users, |mut b, user| user.push_binds(&mut b));
+        let query = query_builder.build();
+        QueryRunner::new("batch_insert_telecom_users")
+            .run(query.execute(tx.deref_mut()))
+            .await?;
+        Ok(())
+    }
+
+    /// 将 user_group_ids 展开为 d_telecom_user_group 中的 (user_id, group_id) 行
+    async fn batch_insert_telecom_user_groups(
+        &self,
+        tx: &mut Transaction<'_, MySql>,
+        users: &[TelecomUser],
+    ) -> Result<()> {
+        let memberships = users
+            .iter()
+            .flat_map(|user| {
+                user.user_group_ids
+                    .iter()
+                    .flatten()
+                    .map(|group_id| group_id.trim())
+                    .filter(|group_id| !group_id.is_empty())
+                    .map(move |group_id| (user.id.as_str(), group_id))
+            })
+            .unique()
+            .collect::<Vec<_>>();
+        if memberships.is_empty() {
+            return Ok(());
+        }
+        let mut query_builder =
+            QueryBuilder::new("INSERT INTO d_telecom_user_group (user_id, group_id) ");
+        query_builder.push_values(memberships, |mut b, (user_id, group_id)| {
+            b.push_bind(user_id).push_bind(group_id);
         });
         let query = query_builder.build();
-        query.execute(tx.deref_mut()).await?;
+        QueryRunner::new("batch_insert_telecom_user_groups")
+            .run(query.execute(&mut **tx))
+            .await?;
         Ok(())
     }
 
@@ -898,8 +970,16 @@ impl UserDataProcessor {
                 .push_bind(mss_org_mapping.name)
                 .push_bind(mss_org_mapping.hr_code);
         });
+        // 网关重新返回的映射刷新写入时间，字段未变化时 ON UPDATE 不会生效，需显式写入
+        query_builder.push(
+            " ON DUPLICATE KEY UPDATE standardstation = VALUES(standardstation), \
+             certificatecode = VALUES(certificatecode), organization = VALUES(organization), \
+             name = VALUES(name), mssuid = VALUES(mssuid), updated_at = NOW()",
+        );
         let query = query_builder.build();
-        query.execute(&mut **tx).await?;
+        QueryRunner::new("batch_insert_telecom_mss_user_mappings")
+            .run(query.execute(&mut **tx))
+            .await?;
         Ok(())
     }
 
@@ -984,7 +1064,9 @@ impl UserDataProcessor {
                 .push_bind(mss_user.hr_code);
         });
         let query = query_builder.build();
-        query.execute(&mut **tx).await?;
+        QueryRunner::new("batch_insert_telecom_mss_users")
+            .run(query.execute(&mut **tx))
+            .await?;
         Ok(())
     }
 }
@@ -997,6 +1079,26 @@ impl DataProcessorTrait for UserDataProcessor {
     type Mapping = TelecomMssUserMapping;
     type Final = TelecomMssUser;
 
+    fn data_type(&self) -> DataType {
+        DataType::User
+    }
+
+    fn mysql_pool(&self) -> &MySqlPool {
+        &self.app_context.mysql_pool
+    }
+
+    fn retry_policy(&self) -> &RetryPolicy {
+        &self.app_context.retry.binlog
+    }
+
+    fn concurrency(&self) -> usize {
+        self.app_context.limits.binlog_concurrency
+    }
+
+    fn clock(&self) -> &dyn Clock {
+        self.app_context.clock.as_ref()
+    }
+
     async fn handle_initial(&self, log: &ModifyOperationLog) -> Result<Transition_, ProcessError> {
         self.handle_initial_state(log.clone()).await
     }
@@ -1034,6 +1136,11 @@ impl DataProcessorTrait for UserDataProcessor {
                 let need_insert = log.type_ == 1 || log.type_ == 2;
                 // user 是 &Box<TelecomUser>，使用 .id 会自动解引用
                 data.user_ids_to_delete.push(user.id.clone());
+                // 新增和修改时映射按 userid 覆盖写入，只有删除日志删除映射
+                if !need_insert {
+                    data.drop_pending_user(&user.id);
+                    data.mapping_user_ids_to_delete.push(user.id.clone());
+                }
                 if let Some(job_number) = user
                     .ext
                     .as_ref()
@@ -1049,16 +1156,20 @@ impl DataProcessorTrait for UserDataProcessor {
                     user_to_insert.month = Some(month.to_string());
                     user_to_insert.in_time = Some(now);
                     user_to_insert.hit_date1 = Some(now);
-                    user_to_insert.hit_date = Some(now.format("%Y-%m-%d").to_string());
-                    data.telecom_users.push(user_to_insert);
+                    user_to_insert.hit_date = Some(timefmt::business_date(now.date()));
+                    let meta = user.entity_meta_info.as_ref();
+                    data.telecom_users
+                        .push(Versioned::new(user_to_insert, meta, log));
                 }
             }
             ProcessingState::GotMapping(log, mapping, hr_code) => {
                 // 从 GotTelecomUser -> GotMssMapping，处理 mapping 和 hr_code
                 let need_insert = log.type_ == 1 || log.type_ == 2;
                 data.hr_codes_to_delete.push(hr_code.clone());
-                if need_insert {
-                    data.mss_user_mappings.push(mapping.clone());
+                // 取自本地映射表的记录不再写回，未过期的映射到期后重新调用网关
+                if need_insert && !mapping.from_cache {
+                    data.mss_user_mappings
+                        .push(Versioned::new(mapping.clone(), None, log));
                 }
             }
             _ => {}
@@ -1078,11 +1189,19 @@ impl DataProcessorTrait for UserDataProcessor {
         let need_insert = log.type_ == 1 || log.type_ == 2;
         if need_insert {
             for mss_user in final_data {
-                data.mss_users.push(mss_user);
+                data.mss_users.push(Versioned::new(mss_user, None, log));
             }
         }
     }
 
+    fn audit_snapshot(&self, final_data: &[TelecomMssUser]) -> Value {
+        let mut snapshot = serde_json::to_value(final_data).unwrap_or_default();
+        if let Some(policy) = self.app_context.persistence_policy.table("d_telecom_user") {
+            redact_excluded_columns(policy, &mut snapshot);
+        }
+        snapshot
+    }
+
     /// 保存处理好的数据到数据库
     async fn save_processed_data(&self, data: &ProcessedUserData) -> Result<()> {
         let mut tx = self.app_context.mysql_pool.begin().await?;
@@ -1094,6 +1213,13 @@ impl DataProcessorTrait for UserDataProcessor {
             &mut tx,
             "d_mss_user_mapping",
             "USERID",
+            &data.mapping_user_ids_to_delete,
+        )
+        .await?;
+        mysql_client::batch_delete(
+            &mut tx,
+            "d_telecom_user_group",
+            "user_id",
             &data.user_ids_to_delete,
         )
         .await?;
@@ -1109,34 +1235,21 @@ impl DataProcessorTrait for UserDataProcessor {
         // --- 2. 执行批量插入 ---
         info!("Starting batch insertion user of new data...");
         // 1. 插入 TelecomUser
-        let users_to_insert = data
-            .telecom_users
-            .iter()
-            .cloned()
-            .unique_by(|o| o.id.clone())
-            .collect::<Vec<_>>();
+        let users_to_insert = dedup_newest(&data.telecom_users, |o| o.id.clone());
         if !users_to_insert.is_empty() {
+            self.batch_insert_telecom_user_groups(&mut tx, &users_to_insert)
+                .await?;
             self.batch_insert_telecom_users(&mut tx, users_to_insert)
                 .await?;
         }
         // 2. 插入 TelecomMssUserMapping
-        let mss_user_mappings_to_insert = data
-            .mss_user_mappings
-            .iter()
-            .cloned()
-            .unique_by(|o| o.uid.clone())
-            .collect::<Vec<_>>();
+        let mss_user_mappings_to_insert = dedup_newest(&data.mss_user_mappings, |o| o.uid.clone());
         if !mss_user_mappings_to_insert.is_empty() {
             self.batch_insert_telecom_mss_user_mappings(&mut tx, mss_user_mappings_to_insert)
                 .await?;
         }
         // 3. 插入 TelecomMssUser
-        let mss_users_to_insert = data
-            .mss_users
-            .iter()
-            .cloned()
-            .unique_by(|o| o.id.clone())
-            .collect::<Vec<_>>();
+        let mss_users_to_insert = dedup_newest(&data.mss_users, |o| o.id.clone());
         if !mss_users_to_insert.is_empty() {
             self.batch_insert_telecom_mss_users(&mut tx, mss_users_to_insert)
                 .await?
@@ -1155,7 +1268,7 @@ impl DataProcessorTrait for UserDataProcessor {
             .cloned()
             .collect::<std::collections::HashSet<_>>();
         for user in &data.telecom_users {
-            affected_ids.insert(user.id.clone());
+            affected_ids.insert(user.record.id.clone());
         }
         let unique_affected_ids: Vec<String> = affected_ids.into_iter().collect();
 
@@ -1175,24 +1288,35 @@ impl DataProcessorTrait for UserDataProcessor {
 
         // 4. (Insert) 重新计算并插入需要存在的数据
         //    只为那些需要新增或更新的组织（即存在于 telecom_users 列表中的）执行插入
-        let ids_to_insert: Vec<String> = data.telecom_users.iter().map(|o| o.id.clone()).collect();
+        let ids_to_insert: Vec<String> = data
+            .telecom_users
+            .iter()
+            .map(|o| o.record.id.clone())
+            .collect();
 
         if !ids_to_insert.is_empty() {
-            // 4.1. 从 .sql 文件加载原始SQL
-            let raw_sql_query = sqlx::query_file!("queries/refresh_mc_user_ztk.sql");
-
-            // 4.2. 使用 QueryBuilder 附加动态的 WHERE IN 子句
-            let mut query_builder = QueryBuilder::new(raw_sql_query.sql());
-            query_builder.push(" WHERE TU.ID IN (");
-            let mut separated = query_builder.separated(", ");
-            for id in &ids_to_insert {
-                separated.push_bind(id);
-            }
-            separated.push_unseparated(")");
+            // 4.1. 从 .sql 文件加载原始SQL，附加动态的 WHERE IN 子句
+            let mut query_builder = MC_USER_ZTK_REFRESH.builder(&ids_to_insert);
 
-            // 4.3. 构建并执行最终的查询
+            // 4.2. 构建并执行最终的查询。表结构不兼容时回滚本次刷新，记录待刷新的 ID
             let final_query = query_builder.build();
-            let result = final_query.execute(tx.deref_mut()).await?;
+            let result = match QueryRunner::new("refresh_mc_user_ztk")
+                .run(final_query.execute(tx.deref_mut()))
+                .await
+            {
+                Ok(result) => result,
+                Err(e) if is_schema_incompatible(&e) => {
+                    drop(tx);
+                    return record_incompatible_refresh(
+                        &self.app_context.mysql_pool,
+                        MC_USER_ZTK_REFRESH.table,
+                        &unique_affected_ids,
+                        &e,
+                    )
+                    .await;
+                }
+                Err(e) => return Err(e.into()),
+            };
 
             info!(
                 "Inserted {} new records into mc_user_ztk",
@@ -1206,3 +1330,120 @@ impl DataProcessorTrait for UserDataProcessor {
         Ok(())
     }
 }
+
+#[test]
+fn test_processed_user_data_keeps_newest_across_retry_rounds() {
+    let log = |id: &str, date_last_modified: i64| ModifyOperationLog {
+        id: id.to_string(),
+        cid: Some("u1".to_string()),
+        type_: 2,
+        entity_meta_info: Some(EntityMetaInfo {
+            date_last_modified: Some(date_last_modified),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let chunk = |log: &ModifyOperationLog, name: &str| {
+        let user: TelecomUser =
+            serde_json::from_value(serde_json::json!({"id": "u1", "name": name})).unwrap();
+        let mapping: TelecomMssUserMapping =
+            serde_json::from_value(serde_json::json!({"uid": "u1", "name": name})).unwrap();
+        let mss_user: TelecomMssUser =
+            serde_json::from_value(serde_json::json!({"id": "m1", "name": name})).unwrap();
+        ProcessedUserData {
+            telecom_users: vec![Versioned::new(user, None, log)],
+            mss_user_mappings: vec![Versioned::new(mapping, None, log)],
+            mss_users: vec![Versioned::new(mss_user, None, log)],
+            ..Default::default()
+        }
+    };
+    let (old_log, new_log) = (log("1", 100), log("2", 200));
+    // 新版本在第一轮完成、旧版本在重试轮次中完成，以及相反的顺序
+    for (first, second) in [
+        (chunk(&new_log, "new"), chunk(&old_log, "old")),
+        (chunk(&old_log, "old"), chunk(&new_log, "new")),
+    ] {
+        let (mut merged, mut second) = (first, second);
+        merged.merge(&mut second);
+        let users = dedup_newest(&merged.telecom_users, |o| o.id.clone());
+        let mappings = dedup_newest(&merged.mss_user_mappings, |o| o.uid.clone());
+        let mss_users = dedup_newest(&merged.mss_users, |o| o.id.clone());
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].name.as_deref(), Some("new"));
+        assert_eq!(mappings[0].name.as_deref(), Some("new"));
+        assert_eq!(mss_users[0].name.as_deref(), Some("new"));
+    }
+}
+
+#[test]
+fn test_redact_excluded_columns_from_audit_snapshot() {
+    use std::collections::HashSet;
+
+    let policy = ColumnPolicy {
+        deny: HashSet::from([
+            "archives_info_political".to_string(),
+            "NAME_CARD_FOLK".to_string(),
+            "id".to_string(),
+        ]),
+        ..Default::default()
+    };
+    let user: TelecomUser = serde_json::from_value(serde_json::json!({
+        "id": "u1",
+        "name": "张三",
+        "archives_info": {"political": "群众", "folk": "汉族"},
+        "ext": {"name_card": {"name": "张三", "folk": "汉族"}},
+    }))
+    .unwrap();
+    let mut snapshot = serde_json::to_value(vec![user]).unwrap();
+    redact_excluded_columns(&policy, &mut snapshot);
+
+    let user = &snapshot[0];
+    assert!(user["archives_info"].get("political").is_none());
+    assert_eq!(user["archives_info"]["folk"], "汉族");
+    assert!(user["ext"]["name_card"].get("folk").is_none());
+    assert_eq!(user["ext"]["name_card"]["name"], "张三");
+    // 主键不受策略影响
+    assert_eq!(user["id"], "u1");
+    assert_eq!(user["name"], "张三");
+}
+
+#[test]
+fn test_processed_user_data_delete_after_insert_leaves_no_row() {
+    let log = |id: &str, type_: u8| ModifyOperationLog {
+        id: id.to_string(),
+        cid: Some("u1".to_string()),
+        type_,
+        ..Default::default()
+    };
+    let (insert_log, delete_log) = (log("1", 1), log("2", 3));
+    let user: TelecomUser = serde_json::from_value(serde_json::json!({"id": "u1"})).unwrap();
+    let mapping: TelecomMssUserMapping =
+        serde_json::from_value(serde_json::json!({"uid": "u1"})).unwrap();
+    let inserted = || ProcessedUserData {
+        telecom_users: vec![Versioned::new(user.clone(), None, &insert_log)],
+        mss_user_mappings: vec![Versioned::new(mapping.clone(), None, &insert_log)],
+        user_ids_to_delete: vec!["u1".to_string()],
+        ..Default::default()
+    };
+    let mut deleted = ProcessedUserData {
+        user_ids_to_delete: vec!["u1".to_string()],
+        mapping_user_ids_to_delete: vec!["u1".to_string()],
+        ..Default::default()
+    };
+    let assert_absent = |data: &ProcessedUserData| {
+        assert!(dedup_newest(&data.telecom_users, |o| o.id.clone()).is_empty());
+        assert!(data.mss_user_mappings.is_empty());
+        // 已有的行仍按删除列表删除
+        assert!(data.user_ids_to_delete.contains(&"u1".to_string()));
+    };
+
+    // 删除在之后的重试轮次中完成
+    let mut merged = inserted();
+    merged.merge(&mut deleted);
+    assert_absent(&merged);
+
+    // 新增与删除在同一轮次中先后推进
+    let mut same_round = inserted();
+    same_round.drop_pending_user(delete_log.cid.as_deref().unwrap());
+    assert_absent(&same_round);
+}
@@ -0,0 +1,49 @@
+//! 构建信息，由 build.rs 在编译期注入
+
+use chrono::DateTime;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    pub git_commit: &'static str,
+    /// RFC 3339 格式的构建时间（UTC）
+    pub build_timestamp: String,
+}
+
+impl BuildInfo {
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            git_commit: env!("SERVICEKIT_GIT_COMMIT"),
+            build_timestamp: format_timestamp(env!("SERVICEKIT_BUILD_TIMESTAMP")),
+        }
+    }
+
+    /// 启动日志中的一行摘要
+    pub fn banner(&self) -> String {
+        format!(
+            "servicekit v{} (commit {}, built {})",
+            self.version, self.git_commit, self.build_timestamp
+        )
+    }
+}
+
+fn format_timestamp(epoch_secs: &str) -> String {
+    epoch_secs
+        .parse()
+        .ok()
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+        .map(|time| time.to_rfc3339())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[test]
+fn test_build_info_banner() {
+    assert_eq!(format_timestamp("0"), "1970-01-01T00:00:00+00:00");
+    assert_eq!(format_timestamp(""), "unknown");
+
+    let info = BuildInfo::current();
+    assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+    assert!(info.banner().contains(info.git_commit));
+}
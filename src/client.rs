@@ -0,0 +1,150 @@
+//! 本服务 HTTP 接口的 Rust 客户端，供其他内部服务调用 pushMss、binlog 同步等接口。
+//! 请求与响应直接使用 handler 的模型，接口变化时编译即可发现不一致。
+//! 需要启用 client feature
+
+use std::time::Duration;
+
+use reqwest::{RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+
+use crate::web::{
+    API_KEY_HEADER, ApiResponse, BinlogParams, IDEMPOTENCY_KEY_HEADER, JobAccepted, JobStatus,
+    PushDataParams, PushJobAccepted, PushResultPage, PushResultParams,
+};
+
+// 未指定时的请求超时
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("Request to servicekit failed: {0}")]
+    Http(#[from] reqwest::Error),
+    /// 接口返回了失败，message 为响应中的错误信息
+    #[error("servicekit returned {status}: {message}")]
+    Api { status: StatusCode, message: String },
+}
+
+pub type ClientResult<T> = Result<T, ClientError>;
+
+/// 本服务接口的客户端，base_url 为服务地址（如 http://127.0.0.1:8080），接口位于其下的 /api
+#[derive(Debug, Clone)]
+pub struct ServicekitClient {
+    http_client: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl ServicekitClient {
+    pub fn new(base_url: &str) -> ClientResult<Self> {
+        let http_client = reqwest::Client::builder()
+            .timeout(DEFAULT_TIMEOUT)
+            .build()?;
+        Ok(Self::with_http_client(http_client, base_url))
+    }
+
+    /// 使用调用方已配置好的 reqwest 客户端，如需要自定义超时或代理
+    pub fn with_http_client(http_client: reqwest::Client, base_url: &str) -> Self {
+        ServicekitClient {
+            http_client,
+            base_url: format!("{}/api", base_url.trim_end_matches('/')),
+            api_key: None,
+        }
+    }
+
+    /// 每个请求携带 admin_config.api_keys 中配置的 key，用于审计与管理接口鉴权
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// 异步推送指定日期范围或培训 ID 的数据，返回作业编号，进度通过 job_status 查询。
+    /// idempotency_key 相同的重复提交返回首次的响应
+    pub async fn push_mss(
+        &self,
+        params: &PushDataParams,
+        idempotency_key: Option<&str>,
+    ) -> ClientResult<PushJobAccepted> {
+        let request = self.post("/pxb/pushMss", idempotency_key).json(params);
+        self.send(request).await
+    }
+
+    /// 异步同步指定用户、机构或基准岗位的数据，返回作业编号
+    pub async fn binlog_sync(
+        &self,
+        params: &BinlogParams,
+        idempotency_key: Option<&str>,
+    ) -> ClientResult<JobAccepted> {
+        let request = self.post("/v2/binlog/sync", idempotency_key).json(params);
+        self.send(request).await
+    }
+
+    /// 查询 pushMss 作业的进度，作业不存在（或已被清理）时返回 None
+    pub async fn job_status(&self, job_id: &str) -> ClientResult<Option<JobStatus>> {
+        let request = self.get(&format!("/pxb/jobs/{job_id}"));
+        match self.send(request).await {
+            Ok(status) => Ok(Some(status)),
+            Err(ClientError::Api {
+                status: StatusCode::NOT_FOUND,
+                ..
+            }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 分页查询推送结果
+    pub async fn push_results(&self, params: &PushResultParams) -> ClientResult<PushResultPage> {
+        let request = self.get("/pxb/pushResults").query(params);
+        self.send(request).await
+    }
+
+    fn get(&self, path: &str) -> RequestBuilder {
+        self.authorized(self.http_client.get(format!("{}{path}", self.base_url)))
+    }
+
+    fn post(&self, path: &str, idempotency_key: Option<&str>) -> RequestBuilder {
+        let mut request =
+            self.authorized(self.http_client.post(format!("{}{path}", self.base_url)));
+        if let Some(idempotency_key) = idempotency_key {
+            request = request.header(IDEMPOTENCY_KEY_HEADER, idempotency_key);
+        }
+        request
+    }
+
+    fn authorized(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.api_key {
+            Some(api_key) => request.header(API_KEY_HEADER, api_key),
+            None => request,
+        }
+    }
+
+    // 解析 ApiResponse，success 为 false 或 HTTP 状态不成功时返回 ClientError::Api
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> ClientResult<T> {
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+        let parsed = serde_json::from_str::<ApiResponse<T>>(&body);
+        match parsed {
+            Ok(ApiResponse {
+                success: true,
+                data: Some(data),
+                ..
+            }) if status.is_success() => Ok(data),
+            Ok(api_response) => Err(ClientError::Api {
+                status,
+                message: api_response
+                    .message
+                    .unwrap_or_else(|| "missing data in response".to_string()),
+            }),
+            Err(e) => Err(ClientError::Api {
+                status,
+                message: format!("unexpected response ({e}): {body}"),
+            }),
+        }
+    }
+}
+
+#[test]
+fn test_base_url() {
+    let client = ServicekitClient::with_http_client(reqwest::Client::new(), "http://svc:8080/");
+    assert_eq!(client.base_url, "http://svc:8080/api");
+}
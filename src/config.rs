@@ -1,14 +1,29 @@
-use config::{Config, ConfigError, Environment, File};
-use serde::Deserialize;
-use std::collections::HashMap;
+use crate::PsnDataKind;
+use crate::messages::Language;
+use crate::schedule::status_updates::NotifyStatus;
+use crate::utils::retry::{RetryClass, RetryPolicy};
+use config::builder::DefaultState;
+use config::{Config, ConfigBuilder, ConfigError, Environment, File};
+use serde::{Deserialize, Deserializer};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::net::IpAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tracing::info;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct AppConfig {
-    pub database_url: String,
+    pub database_url: SecretString,
+    pub web_server_host: String, // Web 服务器监听地址
     pub web_server_port: u16,
     pub tasks: TasksConfig, // 包含所有任务的配置
+    #[serde(skip)]
+    pub logging: Arc<LoggingConfig>, // 日志级别、按模块过滤与输出格式
+    #[serde(skip)]
+    pub config_watch: Arc<ConfigWatchConfig>, // 监听配置文件，修改后热加载部分配置
+    #[serde(skip)]
+    pub messages: Arc<MessagesConfig>, // 提示信息的语言
     #[serde(skip)] // 序列化/反序列化时跳过，因为我们会在 new 方法中手动处理 Arc 包装
     pub mss_info_config: Arc<MssInfoConfig>,
     #[serde(skip)]
@@ -17,72 +32,1276 @@ pub struct AppConfig {
     pub clickhouse_config: Arc<ClickhouseConfig>, // ClickHouse配置
     #[serde(skip)]
     pub redis_config: Arc<RedisConfig>,
+    #[serde(skip)]
+    pub reply_archive_config: Arc<ReplyArchiveConfig>, // MSS 报文归档配置
+    #[serde(skip)]
+    pub admin_config: Arc<AdminConfig>, // 管理接口配置
+    #[serde(skip)]
+    pub timeouts: Arc<TimeoutsConfig>, // 超时与锁过期时间
+    #[serde(skip)]
+    pub limits: Arc<LimitsConfig>, // 批量大小等数量限制
+    #[serde(skip)]
+    pub binlog_polling: Arc<BinlogPollingConfig>, // binlog 自适应轮询间隔
+    #[serde(skip)]
+    pub mss_retry_queue: Arc<RetryQueueConfig>, // MSS 暂时性失败的延迟重试
+    #[serde(skip)]
+    pub clickhouse_replay: Arc<ClickHouseReplayConfig>, // 在恢复的节点上重放失败的 ClickHouse 语句
+    #[serde(skip)]
+    pub retry: Arc<RetryConfig>, // 网关、MSS 与 binlog 处理的重试策略
+    #[serde(skip)]
+    pub gateway_cache: Arc<GatewayCacheConfig>, // 网关组织查询的 Redis 缓存
+    #[serde(skip)]
+    pub mapping_cache: Arc<MappingCacheConfig>, // d_mss_*_mapping 作为 MSS 映射的本地缓存
+    #[serde(skip)]
+    pub binlog_sharding: Arc<BinlogShardingConfig>, // 多实例按 cid 分片处理 User binlog
+    #[serde(skip)]
+    pub notify_status: Arc<NotifyStatusConfig>, // trainNotifyMss 各状态写入的值
+    #[serde(skip)]
+    pub train_status: Arc<TrainStatusConfig>, // 删除通知按培训班状态筛选
+    #[serde(skip)]
+    pub analytics_export: Arc<AnalyticsExportConfig>, // 执行与推送汇总导出到 ClickHouse
+    #[serde(skip)]
+    pub push_verification: Arc<PushVerificationConfig>, // 状态回写后核对仍未更新的记录数
+    #[serde(skip)]
+    pub push_pipeline: Arc<PushPipelineConfig>, // 查询与推送之间的队列
+    #[serde(skip)]
+    pub shadow_push: Arc<ShadowPushConfig>, // 按比例把推送镜像到备用 MSS 环境
+    #[serde(skip)]
+    pub push_outbox: Arc<PushOutboxConfig>, // 推送发件箱，进程中断后恢复未完成的推送
+    #[serde(skip)]
+    pub legacy_import: Arc<LegacyImportConfig>, // 从旧 Java 系统导入历史推送结果
+    #[serde(skip)]
+    pub run_manifest: Arc<RunManifestConfig>, // 组合推送结束时生成签名的归档清单
+    #[serde(skip)]
+    pub rate_limits: Arc<RateLimitsConfig>, // MSS 与网关调用的令牌桶限流
+    #[serde(skip)]
+    pub persistence_policy: Arc<PersistencePolicyConfig>, // 按表排除不允许落库的列
     pub provinces: HashMap<String, String>, // 省份配置
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct TasksConfig {
     pub psn_push: PsnPushTaskConfig,
+    #[serde(default)]
+    pub clickhouse_schema_check: Option<CronTaskConfig>, // ClickHouse 表结构巡检，未配置时只在启动时检查
+    #[serde(default)]
+    pub gateway_cache_warmup: Option<CronTaskConfig>, // 网关缓存预热，未配置时只在启动时预热
+    #[serde(default)]
+    pub binlog_digest: Option<CronTaskConfig>, // binlog 同步日报，未配置时不生成
+    #[serde(default)]
+    pub binlog_gap_replay: Option<CronTaskConfig>, // binlog 未覆盖区间补拉，未配置时不检测
+    #[serde(default)]
+    pub composite_groups: Vec<CompositeGroupConfig>, // 额外的推送组合任务
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct CronTaskConfig {
+    pub cron_schedule: String,
+    pub task_name: String,
+}
+
+/// 推送数据的地区：main 为默认数据，sc 为四川（*_SC_* 表）
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PushRegion {
+    #[default]
+    All,
+    Main,
+    Sc,
+}
+
+impl PushRegion {
+    pub fn includes(&self, kind: PsnDataKind) -> bool {
+        match self {
+            PushRegion::All => true,
+            PushRegion::Main => !kind.is_sc(),
+            PushRegion::Sc => kind.is_sc(),
+        }
+    }
+}
+
+/// 按配置组合的推送任务，如只在中午推送讲师与档案。包含所选数据的删除通知
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct CompositeGroupConfig {
+    pub cron_schedule: String,
+    pub task_name: String,
+    /// 数据种类：class、lecturer、training、archive
+    pub kinds: Vec<String>,
+    #[serde(default)]
+    pub region: PushRegion,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct PsnPushTaskConfig {
     pub cron_schedule: String,
     pub task_name: String, // 任务名称
 }
 
-#[derive(Debug, Deserialize, Clone, Default)]
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
 pub struct MssInfoConfig {
     pub app_id: String,
-    pub app_key: String,
+    pub app_key: SecretString,
     pub app_url: String,
+    #[serde(default)]
+    pub encoding: MssEncoding, // 请求体编码方式
+    #[serde(default)]
+    pub batch_size: usize, // 每次请求推送的记录数，json-single 编码时固定为 1
+}
+
+impl MssInfoConfig {
+    /// 实际每次请求的记录数
+    pub fn push_batch_size(&self) -> usize {
+        match self.encoding {
+            MssEncoding::JsonSingle => 1,
+            MssEncoding::JsonBatch | MssEncoding::NdjsonGzip => self.batch_size.max(1),
+        }
+    }
 }
 
-#[derive(Debug, Deserialize, Clone, Default)]
+/// MSS 请求体编码：json-single（默认）、json-batch、ndjson-gzip
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum MssEncoding {
+    #[default]
+    JsonSingle,
+    JsonBatch,
+    NdjsonGzip,
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
 pub struct Targets {
     pub newtca: u32,
     pub basedata: u32,
     pub mss: u32,
 }
 
-#[derive(Debug, Deserialize, Clone, Default)]
+/// 网关上的目标应用，对应 telecom_config.targets 中的一项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GatewayTarget {
+    Newtca,
+    Basedata,
+    Mss,
+}
+
+impl GatewayTarget {
+    pub const ALL: [GatewayTarget; 3] = [
+        GatewayTarget::Newtca,
+        GatewayTarget::Basedata,
+        GatewayTarget::Mss,
+    ];
+
+    /// 与 telecom_config.targets 中的键一致
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GatewayTarget::Newtca => "newtca",
+            GatewayTarget::Basedata => "basedata",
+            GatewayTarget::Mss => "mss",
+        }
+    }
+}
+
+impl Targets {
+    /// 目标应用在网关上的 app id
+    pub fn app_id(&self, target: GatewayTarget) -> u32 {
+        match target {
+            GatewayTarget::Newtca => self.newtca,
+            GatewayTarget::Basedata => self.basedata,
+            GatewayTarget::Mss => self.mss,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
 pub struct TelecomConfig {
     pub gateway_url: String,
     pub source_app_id: u32,
     pub mode: i32,
     pub is_sync: bool,
     pub targets: Targets,
+    /// 网关服务目录，key 为服务名（如 mss.user.queryorder），未配置的服务使用默认值
+    #[serde(default)]
+    pub services: HashMap<String, GatewayServiceConfig>,
+    /// 网关调用的熔断器，每个目标应用各一个
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+}
+
+/// 熔断器配置：连续 failure_threshold 次调用失败后打开，cool_down 后进入半开，
+/// 半开状态最多放行 half_open_max_calls 个探测调用
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct CircuitBreakerConfig {
+    pub enabled: bool,
+    pub failure_threshold: u32,
+    #[serde(with = "humantime_serde")]
+    pub cool_down: Duration,
+    pub half_open_max_calls: u32,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        CircuitBreakerConfig {
+            enabled: true,
+            failure_threshold: 5,
+            cool_down: Duration::from_secs(30),
+            half_open_max_calls: 1,
+        }
+    }
+}
+
+/// 单个网关服务的配置
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct GatewayServiceConfig {
+    /// 关闭后不再调用该服务（如上游故障期间），处理器按部分结果降级
+    pub enabled: bool,
+    /// 覆盖代码中的默认目标应用 ID
+    pub target: Option<u32>,
+    /// 覆盖 HTTP 客户端的请求超时
+    #[serde(with = "humantime_serde")]
+    pub timeout: Option<Duration>,
+    /// 覆盖 [retry.gateway] 的重试次数（不含第一次请求）
+    pub retry: Option<u32>,
+}
+
+impl Default for GatewayServiceConfig {
+    fn default() -> Self {
+        GatewayServiceConfig {
+            enabled: true,
+            target: None,
+            timeout: None,
+            retry: None,
+        }
+    }
 }
 
-#[derive(Debug, Deserialize, Clone, Default)]
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
 pub struct ClickhouseConfig {
     pub hosts: Vec<String>,
     pub ports: Vec<u16>,
     pub user: String,
-    pub password: String,
+    pub password: SecretString,
     pub database: String,
+    /// 数据种类（class、lecturer 等）-> 回写 trainNotifyMss 的表，不同环境的库名不同，启动时校验
+    #[serde(default)]
+    pub tables: HashMap<String, String>,
 }
 
 // 添加一个临时的结构体用于初始反序列化
 #[derive(Debug, Deserialize)]
 struct RawAppConfig {
-    pub database_url: String,
+    pub database_url: SecretString,
+    #[serde(default = "default_web_server_host")]
+    pub web_server_host: String,
     pub web_server_port: u16,
     pub tasks: TasksConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    #[serde(default)]
+    pub config_watch: ConfigWatchConfig,
+    #[serde(default)]
+    pub messages: MessagesConfig,
     pub mss_info_config: MssInfoConfig,
     pub telecom_config: TelecomConfig,
     pub clickhouse_config: ClickhouseConfig,
     pub redis_config: RedisConfig,
+    #[serde(default)]
+    pub reply_archive_config: ReplyArchiveConfig,
+    #[serde(default)]
+    pub admin_config: AdminConfig,
+    #[serde(default)]
+    pub timeouts: TimeoutsConfig,
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    #[serde(default)]
+    pub binlog_polling: BinlogPollingConfig,
+    #[serde(default)]
+    pub mss_retry_queue: RetryQueueConfig,
+    #[serde(default)]
+    pub clickhouse_replay: ClickHouseReplayConfig,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    #[serde(default)]
+    pub gateway_cache: GatewayCacheConfig,
+    #[serde(default)]
+    pub mapping_cache: MappingCacheConfig,
+    #[serde(default)]
+    pub binlog_sharding: BinlogShardingConfig,
+    #[serde(default)]
+    pub notify_status: NotifyStatusConfig,
+    #[serde(default)]
+    pub train_status: TrainStatusConfig,
+    #[serde(default)]
+    pub analytics_export: AnalyticsExportConfig,
+    #[serde(default)]
+    pub push_verification: PushVerificationConfig,
+    #[serde(default)]
+    pub push_pipeline: PushPipelineConfig,
+    #[serde(default)]
+    pub shadow_push: ShadowPushConfig,
+    #[serde(default)]
+    pub push_outbox: PushOutboxConfig,
+    #[serde(default)]
+    pub legacy_import: LegacyImportConfig,
+    #[serde(default)]
+    pub run_manifest: RunManifestConfig,
+    #[serde(default)]
+    pub rate_limits: RateLimitsConfig,
+    #[serde(default)]
+    pub persistence_policy: PersistencePolicyConfig,
     provinces: HashMap<String, String>,
 }
 
-#[derive(Debug, Deserialize, Clone, Default)]
+impl RawAppConfig {
+    // 取值不合法的配置在启动或热加载时被拒绝，而不是在运行中才暴露
+    fn validate(&self) -> Result<(), ConfigError> {
+        self.retry.validate().map_err(ConfigError::Message)?;
+        self.notify_status
+            .validate()
+            .map_err(ConfigError::Message)?;
+        self.persistence_policy
+            .validate()
+            .map_err(ConfigError::Message)?;
+        Ok(())
+    }
+}
+
+// 未配置时只监听本机，与之前的行为一致
+fn default_web_server_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
 pub struct RedisConfig {
-    pub url: String,
+    pub url: SecretString, // 包含口令
+}
+
+/// MSS 报文的存储位置
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplyArchiveBackend {
+    /// 报文直接写入 data_archiving_mss_record
+    #[default]
+    Mysql,
+    /// 写入本地目录
+    Local,
+    /// 写入兼容 S3 协议的对象存储
+    S3,
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ReplyArchiveConfig {
+    pub backend: ReplyArchiveBackend,
+    pub local_dir: String,
+    pub s3: S3Config,
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct S3Config {
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    pub access_key: SecretString,
+    pub secret_key: SecretString,
+    pub prefix: String,
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct AdminConfig {
+    /// 允许通过 /admin/tables/{name}/columns 查询结构的表
+    pub schema_tables: Vec<String>,
+    /// API key 名称 -> key，调用方通过 X-API-Key 请求头携带，名称写入操作审计。
+    /// 也可以通过 admin_config.api_keys_file 从文件读取，见 resolve_secret_files
+    pub api_keys: HashMap<String, SecretString>,
+    /// 受信任的反向代理地址，只有来自这些地址的请求才按 X-Forwarded-For 记录调用方 IP，
+    /// 其余请求记录连接的对端地址，调用方无法伪造
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+/// 时间类配置，使用 humantime 格式（如 "500ms"、"30s"、"5m"、"1h"）
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct TimeoutsConfig {
+    #[serde(with = "humantime_serde")]
+    pub http_connect: Duration,
+    #[serde(with = "humantime_serde")]
+    pub http_read: Duration,
+    #[serde(with = "humantime_serde")]
+    pub http_request: Duration,
+    /// 两次调用 MSS 接口之间的最小间隔
+    #[serde(with = "humantime_serde")]
+    pub mss_min_interval: Duration,
+    #[serde(with = "humantime_serde")]
+    pub mysql_acquire: Duration,
+    #[serde(with = "humantime_serde")]
+    pub binlog_lock_ttl: Duration,
+    /// 一个 binlog 同步周期的总时长上限，超过后保存已完成的部分，其余顺延到下个周期
+    #[serde(with = "humantime_serde")]
+    pub binlog_cycle_deadline: Duration,
+    /// 携带 Idempotency-Key 的请求保存首次响应的时长
+    #[serde(with = "humantime_serde")]
+    pub idempotency_ttl: Duration,
+    /// binlog 缺口检测向前查找的范围
+    #[serde(with = "humantime_serde")]
+    pub binlog_gap_lookback: Duration,
+    /// 收到关闭信号后等待运行中任务结束的时长
+    #[serde(with = "humantime_serde")]
+    pub shutdown_drain: Duration,
+}
+
+impl Default for TimeoutsConfig {
+    fn default() -> Self {
+        Self {
+            http_connect: Duration::from_secs(5),
+            http_read: Duration::from_secs(5),
+            http_request: Duration::from_secs(10),
+            mss_min_interval: Duration::from_millis(20),
+            mysql_acquire: Duration::from_secs(3),
+            binlog_lock_ttl: Duration::from_secs(3600),
+            binlog_cycle_deadline: Duration::from_secs(600),
+            idempotency_ttl: Duration::from_secs(24 * 3600),
+            binlog_gap_lookback: Duration::from_secs(24 * 3600),
+            shutdown_drain: Duration::from_secs(60),
+        }
+    }
+}
+
+/// binlog 连续同步的自适应轮询间隔（AIMD）：
+/// 空闲周期间隔线性增加 increase_step，繁忙周期间隔乘以 decrease_factor，
+/// 出错时从 error_interval 开始按连续出错次数翻倍，结果都限制在 [min_interval, max_interval]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct BinlogPollingConfig {
+    #[serde(with = "humantime_serde")]
+    pub min_interval: Duration,
+    #[serde(with = "humantime_serde")]
+    pub max_interval: Duration,
+    #[serde(with = "humantime_serde")]
+    pub increase_step: Duration,
+    pub decrease_factor: f64,
+    /// 单个周期拉取的记录数达到该值视为繁忙
+    pub busy_records: usize,
+    #[serde(with = "humantime_serde")]
+    pub error_interval: Duration,
+}
+
+impl Default for BinlogPollingConfig {
+    fn default() -> Self {
+        Self {
+            min_interval: Duration::from_secs(1),
+            max_interval: Duration::from_secs(120),
+            increase_step: Duration::from_secs(5),
+            decrease_factor: 0.5,
+            busy_records: 100,
+            error_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct LimitsConfig {
+    /// 推送完成后回写 trainNotifyMss 时每批的 ID 数量
+    pub push_update_batch_size: usize,
+    /// 推送结果后台写入每批的记录数
+    pub push_result_batch_size: usize,
+    /// 推送任务每页查询的记录数，按页推送并回写状态
+    pub push_fetch_page_size: usize,
+    /// 单个周期内每种 binlog 类型最多拉取的页数
+    pub binlog_max_pages_per_cycle: u32,
+    /// binlog.find 每页的条数，不超过网关上限
+    pub binlog_page_size: u32,
+    /// 单个周期内每种 binlog 类型最多处理的新日志条数，超出部分顺延到下个周期
+    pub binlog_max_records_per_cycle: usize,
+    /// binlog 处理时同时推进的实体数，同一实体的日志仍按顺序处理
+    pub binlog_concurrency: usize,
+    /// pushMss 按日期范围补推时同时处理的日期数，每个日期仍是独立的组合任务
+    pub push_date_concurrency: usize,
+    /// 出站 HTTP 响应体的最大长度，如 "16MB"
+    pub http_max_response_body: ByteSize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            push_update_batch_size: 1000,
+            push_result_batch_size: 100,
+            push_fetch_page_size: 5000,
+            binlog_max_pages_per_cycle: 500,
+            binlog_page_size: 20,
+            binlog_max_records_per_cycle: 10_000,
+            binlog_concurrency: 16,
+            push_date_concurrency: 4,
+            http_max_response_body: ByteSize(16 * 1024 * 1024),
+        }
+    }
+}
+
+/// MSS 延迟重试队列配置，第 n 次重试的等待时间为 base_delay * 2^(n-1)，不超过 max_delay
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RetryQueueConfig {
+    pub enabled: bool,
+    /// 进入队列后最多重试的次数，超过后按失败回写
+    pub max_attempts: u32,
+    #[serde(with = "humantime_serde")]
+    pub base_delay: Duration,
+    #[serde(with = "humantime_serde")]
+    pub max_delay: Duration,
+    /// worker 检查到期记录的间隔
+    #[serde(with = "humantime_serde")]
+    pub poll_interval: Duration,
+    /// worker 每次最多取出的记录数
+    pub batch_size: usize,
+    /// 取出的记录在此时间内不会被其他节点取出，应大于处理一批记录的时间；
+    /// 处理节点崩溃时记录在租约到期后重新重试
+    #[serde(with = "humantime_serde")]
+    pub lease: Duration,
+}
+
+impl Default for RetryQueueConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_attempts: 6,
+            base_delay: Duration::from_secs(60),
+            max_delay: Duration::from_secs(3600),
+            poll_interval: Duration::from_secs(30),
+            batch_size: 100,
+            lease: Duration::from_secs(600),
+        }
+    }
+}
+
+/// ClickHouse 重放队列：状态回写在某个节点执行失败时，语句写入 clickhouse_replay_queue，
+/// 后台 worker 在该节点恢复后按写入顺序重放，避免副本之间的状态不一致
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ClickHouseReplayConfig {
+    pub enabled: bool,
+    /// worker 检查待重放语句的间隔
+    #[serde(with = "humantime_serde")]
+    pub poll_interval: Duration,
+    /// worker 每次最多取出的语句数
+    pub batch_size: usize,
+    /// 重放失败达到该次数后放弃，需要人工对账修复
+    pub max_attempts: u32,
+}
+
+impl Default for ClickHouseReplayConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            poll_interval: Duration::from_secs(60),
+            batch_size: 100,
+            max_attempts: 30,
+        }
+    }
+}
+
+/// 监听配置文件的修改并热加载：任务 cron 表达式、日志过滤规则、限流与提示信息语言立即生效，
+/// 数据库地址等需要重启的配置只记录日志，不生效
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ConfigWatchConfig {
+    pub enabled: bool,
+    /// 文件变化后等待该时长再重新加载，合并编辑器保存时的多次写入
+    #[serde(with = "humantime_serde")]
+    pub debounce: Duration,
+}
+
+impl Default for ConfigWatchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            debounce: Duration::from_secs(2),
+        }
+    }
+}
+
+/// 日志与接口返回的提示信息：编码固定为英文，描述按 language 输出，见 messages 模块
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct MessagesConfig {
+    pub language: Language,
+}
+
+/// 各类外部调用的重试策略
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// 网关服务调用，服务目录中的 retry 可覆盖次数
+    pub gateway: RetryPolicy,
+    /// MSS 推送，启用延迟重试队列时本次推送不重试
+    pub mss: RetryPolicy,
+    /// binlog 处理中暂时性失败的日志重新处理的轮数与间隔
+    pub binlog: RetryPolicy,
+}
+
+impl RetryConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, policy) in [
+            ("gateway", &self.gateway),
+            ("mss", &self.mss),
+            ("binlog", &self.binlog),
+        ] {
+            policy
+                .validate()
+                .map_err(|e| format!("retry.{name}: {e}"))?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            gateway: RetryPolicy {
+                max_attempts: 1,
+                initial_backoff: Duration::from_millis(500),
+                max_backoff: Duration::from_secs(5),
+                ..RetryPolicy::default()
+            },
+            // MSS 返回 9019 时要求等待一分钟
+            mss: RetryPolicy {
+                max_attempts: 5,
+                initial_backoff: Duration::from_secs(60),
+                max_backoff: Duration::from_secs(60),
+                multiplier: 1.0,
+                jitter: 0.0,
+                retry_on: vec![RetryClass::Throttled],
+            },
+            binlog: RetryPolicy {
+                max_attempts: 10,
+                initial_backoff: Duration::from_secs(1),
+                max_backoff: Duration::from_secs(10),
+                ..RetryPolicy::default()
+            },
+        }
+    }
+}
+
+/// 网关 MSS 组织查询（mss.organization.translate / query）的 Redis 读穿缓存。
+/// 启动时及按 tasks.gateway_cache_warmup 定时从 d_* 表预热被引用最多的 preload_top_n 个组织
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct GatewayCacheConfig {
+    pub enabled: bool,
+    /// 缓存条目的过期时间。处理组织变更日志前清除该组织的条目，没有映射的查询结果不缓存
+    #[serde(with = "humantime_serde")]
+    pub ttl: Duration,
+    /// 预热的组织数，只写入 ttl 内更新过的映射
+    pub preload_top_n: usize,
+    /// 同时缓存 org.loadbyid / user.loadbyid，binlog 集中到达时同一 cid 只查询一次网关。
+    /// 处理变更日志前清除对应缓存
+    pub lookups: bool,
+    #[serde(with = "humantime_serde")]
+    pub lookup_ttl: Duration,
+}
+
+impl Default for GatewayCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl: Duration::from_secs(600),
+            preload_top_n: 500,
+            lookups: false,
+            lookup_ttl: Duration::from_secs(60),
+        }
+    }
+}
+
+/// binlog 处理 MSS 映射步骤时先查 d_mss_org_mapping / d_mss_user_mapping，
+/// 记录在 max_age 内写入过的直接使用，未命中或已过期时再调用 mss.*.translate
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct MappingCacheConfig {
+    pub enabled: bool,
+    /// 映射记录的有效期，网关侧映射变更最多延迟该时间生效
+    #[serde(with = "humantime_serde")]
+    pub max_age: Duration,
+}
+
+impl Default for MappingCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_age: Duration::from_secs(6 * 3600),
+        }
+    }
+}
+
+/// User binlog 分片处理：每个实例在 Redis 中登记为成员，按成员 ID 排序后的位置作为分片号，
+/// 只处理 hash(cid) % 成员数 落在自己分片的用户日志，成员加入或离开后下个周期自动重新分配。
+/// 其他类型的 binlog 仍由持有全局锁的实例处理
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct BinlogShardingConfig {
+    pub enabled: bool,
+    /// 成员心跳的有效期，超过该时间未续期的实例视为已离开，它的分片由其余实例接管
+    #[serde(with = "humantime_serde")]
+    pub member_ttl: Duration,
+}
+
+impl Default for BinlogShardingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            member_ttl: Duration::from_secs(30),
+        }
+    }
+}
+
+/// 日志输出格式
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    /// 每行一个 JSON 对象，便于日志平台采集
+    Json,
+}
+
+/// 日志配置。控制台与文件分别过滤，未单独配置级别时使用 level；
+/// modules 按模块覆盖级别（如 sqlx = "warn"），同时作用于控制台与文件。
+/// 输出格式也分别配置，通常控制台保持文本，文件输出 JSON 供 ELK 采集
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct LoggingConfig {
+    pub level: String,
+    pub console_level: Option<String>,
+    pub file_level: Option<String>,
+    pub modules: BTreeMap<String, String>,
+    pub console_format: LogFormat,
+    pub file_format: LogFormat,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            console_level: Some("debug".to_string()),
+            file_level: None,
+            modules: BTreeMap::new(),
+            console_format: LogFormat::Text,
+            file_format: LogFormat::Text,
+        }
+    }
+}
+
+impl LoggingConfig {
+    /// 控制台层的过滤规则，EnvFilter 语法
+    pub fn console_directives(&self) -> String {
+        self.directives(self.console_level.as_deref())
+    }
+
+    /// 文件层的过滤规则，EnvFilter 语法
+    pub fn file_directives(&self) -> String {
+        self.directives(self.file_level.as_deref())
+    }
+
+    fn directives(&self, level: Option<&str>) -> String {
+        std::iter::once(level.unwrap_or(&self.level).to_string())
+            .chain(
+                self.modules
+                    .iter()
+                    .map(|(module, level)| format!("{module}={level}")),
+            )
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// trainNotifyMss 各状态在 ClickHouse 与 MySQL 中写入的值，默认 0 / 1 / 2，
+/// 部分省份使用 SUCCESS / FAIL 等取值
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct NotifyStatusConfig {
+    pub pending: String,
+    pub success: String,
+    pub failed: String,
+}
+
+impl Default for NotifyStatusConfig {
+    fn default() -> Self {
+        Self {
+            pending: "0".to_string(),
+            success: "1".to_string(),
+            failed: "2".to_string(),
+        }
+    }
+}
+
+impl NotifyStatusConfig {
+    /// 状态写入数据库的值
+    pub fn value(&self, status: NotifyStatus) -> &str {
+        match status {
+            NotifyStatus::Pending => &self.pending,
+            NotifyStatus::Success => &self.success,
+            NotifyStatus::Failed => &self.failed,
+        }
+    }
+
+    /// 各状态的值不能为空且互不相同，否则回写与对账无法区分状态
+    pub fn validate(&self) -> Result<(), String> {
+        for status in NotifyStatus::ALL {
+            if self.value(status).trim().is_empty() {
+                return Err(format!("notify_status.{status} must not be empty"));
+            }
+        }
+        for (i, a) in NotifyStatus::ALL.into_iter().enumerate() {
+            for b in NotifyStatus::ALL.into_iter().skip(i + 1) {
+                if self.value(a) == self.value(b) {
+                    return Err(format!(
+                        "notify_status.{a} and notify_status.{b} must differ, both are '{}'",
+                        self.value(a)
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 按数据库中的值或状态名（pending / success / failed）解析状态
+    pub fn parse(&self, value: &str) -> Option<NotifyStatus> {
+        NotifyStatus::ALL
+            .into_iter()
+            .find(|status| self.value(*status) == value)
+            .or_else(|| value.parse().ok())
+    }
+}
+
+/// fz_train_trainstatus 中的状态名称。培训班取消后推送任务不再推送其记录，改由删除通知任务通知 MSS 删除
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct TrainStatusConfig {
+    pub cancelled: String,
+}
+
+impl Default for TrainStatusConfig {
+    fn default() -> Self {
+        Self {
+            cancelled: "已取消".to_string(),
+        }
+    }
+}
+
+/// 任务执行与推送汇总导出到 ClickHouse，供 Grafana 查询长期历史，不占用业务 MySQL
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct AnalyticsExportConfig {
+    pub enabled: bool,
+    /// 每次任务执行一行（task_run 的汇总）
+    pub task_run_table: String,
+    /// 每次按日期推送一行（成功、失败、延后与限流计数）
+    pub push_run_table: String,
+}
+
+impl Default for AnalyticsExportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            task_run_table: "DXXY_LOCAL.servicekit_task_run".to_string(),
+            push_run_table: "DXXY_LOCAL.servicekit_push_run".to_string(),
+        }
+    }
+}
+
+/// 推送结束后的核对：统计本次查询到的全部记录（按日期推送时即该日期的全部源数据）在 MySQL 与 ClickHouse 中
+/// trainNotifyMss 仍为 pending 或 NULL 的行数，超过 residue_threshold 时记录告警指标；
+/// ClickHouse 中与 MySQL 状态不一致的行数同样记录告警指标。用于发现只执行了一部分的回写
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PushVerificationConfig {
+    pub enabled: bool,
+    pub residue_threshold: u64,
+    /// ClickHouse 的 ALTER ... UPDATE 异步执行，核对前等待该时长
+    #[serde(with = "humantime_serde")]
+    pub clickhouse_settle_delay: Duration,
+}
+
+impl Default for PushVerificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            residue_threshold: 0,
+            clickhouse_settle_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+/// 查询与推送之间的队列
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PushQueueBackend {
+    /// 进程内的有界 channel
+    #[default]
+    Memory,
+    /// Redis 列表，以后可由单独部署的推送进程消费
+    Redis,
+}
+
+/// 推送任务的查询与推送解耦：查询出的记录按 MSS 批量大小入队，由 workers 个推送 worker 并发推送。
+/// 未启用时在查询后按批顺序推送
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PushPipelineConfig {
+    pub enabled: bool,
+    pub backend: PushQueueBackend,
+    /// 队列中最多等待推送的批数，队列满时查询阶段等待
+    pub queue_capacity: usize,
+    pub workers: usize,
+    /// Redis 列表的键前缀，每次执行使用 {prefix}:{任务名}:{uuid}
+    pub redis_key_prefix: String,
+    /// Redis 队列为空或已满时的轮询间隔
+    #[serde(with = "humantime_serde")]
+    pub redis_poll_interval: Duration,
+    /// Redis 队列持续为空或已满超过该时间时停止等待并报错，另一端可能已异常退出
+    #[serde(with = "humantime_serde")]
+    pub redis_idle_timeout: Duration,
+    /// 每次写入后重设 Redis 列表的过期时间，进程异常退出未清理的队列到期后自动删除
+    #[serde(with = "humantime_serde")]
+    pub redis_key_ttl: Duration,
+}
+
+impl Default for PushPipelineConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: PushQueueBackend::Memory,
+            queue_capacity: 16,
+            workers: 2,
+            redis_key_prefix: "mss:push:queue".to_string(),
+            redis_poll_interval: Duration::from_millis(200),
+            redis_idle_timeout: Duration::from_secs(600),
+            redis_key_ttl: Duration::from_secs(3600),
+        }
+    }
+}
+
+/// 推送发件箱：每批记录推送前写入 mss_push_outbox（pending），请求前后更新为 sent、acked / failed，
+/// trainNotifyMss 回写后删除。进程中断后，启动时处理超过 stale_after 未更新的行：
+/// pending / sent 的重新推送，acked / failed 的补写状态
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PushOutboxConfig {
+    pub enabled: bool,
+    /// 多实例部署时，只恢复超过该时长未更新的行，避免处理其他实例正在推送的记录
+    #[serde(with = "humantime_serde")]
+    pub stale_after: Duration,
+}
+
+impl Default for PushOutboxConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            stale_after: Duration::from_secs(600),
+        }
+    }
+}
+
+/// 旧 Java 归档系统的推送结果表，由 import-legacy-results 子命令导入 mss_push_result / mss_push_result_detail。
+/// 旧表与 mss_push_result 的基础字段相同，可以写成 库名.表名
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct LegacyImportConfig {
+    /// 导入时必填
+    pub result_table: String,
+    /// 导入时必填
+    pub detail_table: String,
+    /// 每批读取与写入的记录数
+    pub batch_size: usize,
+}
+
+impl Default for LegacyImportConfig {
+    fn default() -> Self {
+        Self {
+            result_table: String::new(),
+            detail_table: String::new(),
+            batch_size: 1000,
+        }
+    }
+}
+
+/// 影子推送：按 sample_percent 抽样，把推送的请求体另外发送到备用的 MSS 环境（如新环境上线前的验证）。
+/// 影子请求的结果只记录在 mss_shadow_push_reply，不影响主推送的结果与状态回写
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ShadowPushConfig {
+    pub enabled: bool,
+    /// 启用时必填
+    pub app_url: String,
+    /// 为空时使用 mss_info_config 的 app_id / app_key
+    pub app_id: String,
+    pub app_key: SecretString,
+    /// 抽样比例（0-100），按记录 ID 抽样，同一条记录每次的抽样结果相同
+    pub sample_percent: f64,
+    /// 单次影子请求的超时，不重试
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+}
+
+impl Default for ShadowPushConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            app_url: String::new(),
+            app_id: String::new(),
+            app_key: SecretString::default(),
+            sample_percent: 10.0,
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// 组合推送的归档清单：每次执行结束后生成清单（run_id、日期、各类数据的计数与已推送 ID 的校验和），
+/// 用 signing_key 做 HMAC-SHA256 签名后写入 storage，哈希与签名记录在 MySQL
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct RunManifestConfig {
+    pub enabled: bool,
+    /// 启用时必填
+    pub signing_key: SecretString,
+    /// 清单文件的存储位置，backend 为 mysql 时只记录在 MySQL
+    pub storage: ReplyArchiveConfig,
+}
+
+/// 出站调用的令牌桶限流，同一目标的所有任务共用一个桶
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct RateLimitsConfig {
+    pub mss: RateLimitConfig,
+    pub gateway: RateLimitConfig,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// 每秒请求数，0 表示不限流
+    pub requests_per_second: f64,
+    /// 空闲后允许连续发出的请求数
+    pub burst: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_second: 0.0,
+            burst: 1,
+        }
+    }
+}
+
+/// 落库字段策略，key 为表名。部分地区不允许保存政治面貌、民族等档案字段，
+/// 被排除的列在 Insert* 转换时置为 NULL，每次应用都会记录策略版本。
+/// 审计快照、网关缓存与导出的问题报告中对应的字段同样去掉
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct PersistencePolicyConfig {
+    /// 策略版本，修改策略时同步修改
+    pub version: String,
+    pub tables: HashMap<String, ColumnPolicy>,
+}
+
+impl PersistencePolicyConfig {
+    pub fn table(&self, table: &str) -> Option<&ColumnPolicy> {
+        self.tables.get(table)
+    }
+
+    /// 表与列须是落库时实际写入的，拼错的列名会让策略静默失效
+    pub fn validate(&self) -> Result<(), String> {
+        let mut tables: Vec<_> = self.tables.iter().collect();
+        tables.sort_by(|a, b| a.0.cmp(b.0));
+        for (table, policy) in tables {
+            let columns = match table.as_str() {
+                "d_telecom_user" => crate::binlog::TELECOM_USER_COLUMNS,
+                _ => return Err(format!("persistence_policy.tables.{table}: unknown table")),
+            };
+            let mut listed: Vec<&String> = policy
+                .deny
+                .iter()
+                .chain(policy.allow.iter().flatten())
+                .collect();
+            listed.sort();
+            if let Some(unknown) = listed.into_iter().find(|name| {
+                !columns
+                    .iter()
+                    .any(|column| column.eq_ignore_ascii_case(name))
+            }) {
+                return Err(format!(
+                    "persistence_policy.tables.{table}: unknown column {unknown:?}"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 单表的列策略，列名不区分大小写
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ColumnPolicy {
+    /// 配置后只写入列出的列
+    pub allow: Option<HashSet<String>>,
+    /// 不写入的列，优先于 allow
+    pub deny: HashSet<String>,
+}
+
+impl ColumnPolicy {
+    pub fn permits(&self, column: &str) -> bool {
+        let listed = |columns: &HashSet<String>| {
+            columns
+                .iter()
+                .any(|listed| listed.eq_ignore_ascii_case(column))
+        };
+        !listed(&self.deny) && self.allow.as_ref().is_none_or(listed)
+    }
+}
+
+/// 字节数配置，支持整数或带单位的字符串（B、KB、MB、GB，按 1024 进制）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    pub fn as_usize(&self) -> usize {
+        usize::try_from(self.0).unwrap_or(usize::MAX)
+    }
+
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let trimmed = input.trim();
+        let split = trimmed
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(trimmed.len());
+        let (number, unit) = trimmed.split_at(split);
+        let number: u64 = number
+            .parse()
+            .map_err(|_| format!("Invalid byte size: '{input}'"))?;
+        let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+            "" | "B" => 1,
+            "K" | "KB" | "KIB" => 1024,
+            "M" | "MB" | "MIB" => 1024 * 1024,
+            "G" | "GB" | "GIB" => 1024 * 1024 * 1024,
+            _ => return Err(format!("Unknown byte size unit in '{input}'")),
+        };
+        number
+            .checked_mul(multiplier)
+            .map(ByteSize)
+            .ok_or_else(|| format!("Byte size overflows: '{input}'"))
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteSize {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Bytes(u64),
+            Text(String),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Bytes(bytes) => Ok(ByteSize(bytes)),
+            Raw::Text(text) => ByteSize::parse(&text).map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// 口令、密钥等敏感配置，Debug 输出不包含明文，避免启动时打印配置泄露凭据。
+/// 需要明文时调用 expose
+#[derive(Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new(secret: impl Into<String>) -> Self {
+        SecretString(secret.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            f.write_str("\"\"")
+        } else {
+            f.write_str("[REDACTED]")
+        }
+    }
+}
+
+// 可以从文件读取的敏感配置：设置了 <key>_file 时读取该文件的内容作为 <key> 的值，
+// 如 mss_info_config.app_key_file = "/run/secrets/mss_key"
+const SECRET_KEYS: [&str; 10] = [
+    "database_url",
+    "mss_info_config.app_key",
+    "clickhouse_config.password",
+    "redis_config.url",
+    "shadow_push.app_key",
+    "reply_archive_config.s3.access_key",
+    "reply_archive_config.s3.secret_key",
+    "run_manifest.signing_key",
+    "run_manifest.storage.s3.access_key",
+    "run_manifest.storage.s3.secret_key",
+];
+
+// 管理接口的 API key 文件，内容为 TOML 格式的 名称 = "key"，覆盖配置文件中的同名 key
+const API_KEYS_FILE_KEY: &str = "admin_config.api_keys_file";
+
+/// 把 SECRET_KEYS 中设置了 <key>_file 的配置替换为文件内容，去掉末尾的换行；
+/// 设置了 admin_config.api_keys_file 时读取其中的 API key
+fn resolve_secret_files(
+    mut builder: ConfigBuilder<DefaultState>,
+) -> Result<ConfigBuilder<DefaultState>, ConfigError> {
+    let config = builder.build_cloned()?;
+    for key in SECRET_KEYS {
+        let file_key = format!("{key}_file");
+        let path = match config.get_string(&file_key) {
+            Ok(path) => path,
+            Err(ConfigError::NotFound(_)) => continue,
+            Err(e) => return Err(e),
+        };
+        let secret = std::fs::read_to_string(&path).map_err(|e| {
+            ConfigError::Message(format!("Failed to read {file_key} '{path}': {e}"))
+        })?;
+        info!("Loaded {key} from {path}.");
+        builder = builder.set_override(key, secret.trim_end_matches(['\r', '\n']))?;
+    }
+
+    let path = match config.get_string(API_KEYS_FILE_KEY) {
+        Ok(path) => path,
+        Err(ConfigError::NotFound(_)) => return Ok(builder),
+        Err(e) => return Err(e),
+    };
+    let api_keys: HashMap<String, String> = Config::builder()
+        .add_source(File::new(&path, config::FileFormat::Toml))
+        .build()
+        .and_then(Config::try_deserialize)
+        .map_err(|e| {
+            ConfigError::Message(format!("Failed to read {API_KEYS_FILE_KEY} '{path}': {e}"))
+        })?;
+    info!("Loaded {} admin API keys from {path}.", api_keys.len());
+    for (name, key) in api_keys {
+        builder = builder.set_override(format!("admin_config.api_keys.{name}"), key)?;
+    }
+    Ok(builder)
 }
 
 impl AppConfig {
     pub fn new() -> Result<Self, ConfigError> {
+        Self::load(&Self::config_file())
+    }
+
+    /// 当前环境的配置文件路径
+    pub fn config_file() -> String {
         // 检测环境：dev 或 release
         // 支持环境变量覆盖启动 RUST_ENV=staging cargo run
         let env = std::env::var("RUST_ENV").unwrap_or_else(|_| {
@@ -92,25 +1311,258 @@ impl AppConfig {
                 "release".to_string()
             }
         });
-        let config_file = format!("config/{}.toml", env);
+        format!("config/{}.toml", env)
+    }
+
+    /// 从指定文件加载配置，环境变量可覆盖文件中的值（如 APP__MSS_INFO_CONFIG__APP_KEY），
+    /// 敏感配置可以通过 <key>_file 从文件读取，见 SECRET_KEYS
+    pub fn load(config_file: &str) -> Result<Self, ConfigError> {
         info!("Loading configuration from: {}", config_file);
 
         let builder = Config::builder()
-            .add_source(File::with_name(&config_file))
+            .add_source(File::with_name(config_file))
             .add_source(Environment::with_prefix("APP").separator("__")); // 允许环境变量覆盖 (例如: APP__TASKS__PSN_TRAIN_PUSH__CRON_SCHEDULE)
+        let builder = resolve_secret_files(builder)?;
 
         // 使用 try_deserialize 来直接反序列化为 RawAppConfig
         // 在反序列化后手动将相关字段包装到 Arc 中，并返回 AppConfig
         let raw_config: RawAppConfig = builder.build()?.try_deserialize()?;
+        raw_config.validate()?;
         Ok(AppConfig {
             database_url: raw_config.database_url,
+            web_server_host: raw_config.web_server_host,
             web_server_port: raw_config.web_server_port,
             tasks: raw_config.tasks,
+            logging: Arc::new(raw_config.logging),
+            config_watch: Arc::new(raw_config.config_watch),
+            messages: Arc::new(raw_config.messages),
             mss_info_config: Arc::new(raw_config.mss_info_config),
             telecom_config: Arc::new(raw_config.telecom_config),
             clickhouse_config: Arc::new(raw_config.clickhouse_config),
             redis_config: Arc::new(raw_config.redis_config),
+            reply_archive_config: Arc::new(raw_config.reply_archive_config),
+            admin_config: Arc::new(raw_config.admin_config),
+            timeouts: Arc::new(raw_config.timeouts),
+            limits: Arc::new(raw_config.limits),
+            binlog_polling: Arc::new(raw_config.binlog_polling),
+            mss_retry_queue: Arc::new(raw_config.mss_retry_queue),
+            clickhouse_replay: Arc::new(raw_config.clickhouse_replay),
+            retry: Arc::new(raw_config.retry),
+            gateway_cache: Arc::new(raw_config.gateway_cache),
+            mapping_cache: Arc::new(raw_config.mapping_cache),
+            binlog_sharding: Arc::new(raw_config.binlog_sharding),
+            notify_status: Arc::new(raw_config.notify_status),
+            train_status: Arc::new(raw_config.train_status),
+            analytics_export: Arc::new(raw_config.analytics_export),
+            push_verification: Arc::new(raw_config.push_verification),
+            push_pipeline: Arc::new(raw_config.push_pipeline),
+            shadow_push: Arc::new(raw_config.shadow_push),
+            push_outbox: Arc::new(raw_config.push_outbox),
+            legacy_import: Arc::new(raw_config.legacy_import),
+            run_manifest: Arc::new(raw_config.run_manifest),
+            rate_limits: Arc::new(raw_config.rate_limits),
+            persistence_policy: Arc::new(raw_config.persistence_policy),
             provinces: raw_config.provinces,
         })
     }
 }
+
+#[test]
+fn test_byte_size_parse() {
+    assert_eq!(ByteSize::parse("512"), Ok(ByteSize(512)));
+    assert_eq!(ByteSize::parse("16MB"), Ok(ByteSize(16 * 1024 * 1024)));
+    assert_eq!(ByteSize::parse("2 kb"), Ok(ByteSize(2048)));
+    assert_eq!(ByteSize::parse("1GiB"), Ok(ByteSize(1024 * 1024 * 1024)));
+    assert!(ByteSize::parse("16XB").is_err());
+    assert!(ByteSize::parse("MB").is_err());
+}
+
+#[test]
+fn test_column_policy_permits() {
+    let deny_only = ColumnPolicy {
+        allow: None,
+        deny: HashSet::from(["archives_info_political".to_string()]),
+    };
+    assert!(!deny_only.permits("ARCHIVES_INFO_POLITICAL"));
+    assert!(deny_only.permits("archives_info_folk"));
+
+    let allow_list = ColumnPolicy {
+        allow: Some(HashSet::from(["id".to_string(), "name".to_string()])),
+        deny: HashSet::from(["name".to_string()]),
+    };
+    assert!(allow_list.permits("id"));
+    assert!(!allow_list.permits("name"));
+    assert!(!allow_list.permits("org"));
+}
+
+#[test]
+fn test_notify_status_validate() {
+    assert!(NotifyStatusConfig::default().validate().is_ok());
+    let custom = NotifyStatusConfig {
+        pending: "WAIT".to_string(),
+        success: "SUCCESS".to_string(),
+        failed: "FAIL".to_string(),
+    };
+    assert!(custom.validate().is_ok());
+    let empty = NotifyStatusConfig {
+        failed: " ".to_string(),
+        ..NotifyStatusConfig::default()
+    };
+    assert!(
+        empty
+            .validate()
+            .unwrap_err()
+            .contains("notify_status.failed")
+    );
+    let duplicate = NotifyStatusConfig {
+        failed: "0".to_string(),
+        ..NotifyStatusConfig::default()
+    };
+    assert!(
+        duplicate
+            .validate()
+            .unwrap_err()
+            .contains("notify_status.pending")
+    );
+}
+
+#[test]
+fn test_persistence_policy_validate() {
+    let policy = |deny: &[&str]| PersistencePolicyConfig {
+        version: "test".to_string(),
+        tables: HashMap::from([(
+            "d_telecom_user".to_string(),
+            ColumnPolicy {
+                allow: None,
+                deny: deny.iter().map(|column| column.to_string()).collect(),
+            },
+        )]),
+    };
+    assert!(PersistencePolicyConfig::default().validate().is_ok());
+    assert!(
+        policy(&["archives_info_political", "NAME_CARD_FOLK"])
+            .validate()
+            .is_ok()
+    );
+    assert!(
+        policy(&["archives_info_politics"])
+            .validate()
+            .unwrap_err()
+            .contains("archives_info_politics")
+    );
+
+    let unknown_table = PersistencePolicyConfig {
+        version: "test".to_string(),
+        tables: HashMap::from([("d_telecom_org".to_string(), ColumnPolicy::default())]),
+    };
+    assert!(unknown_table.validate().is_err());
+}
+
+#[test]
+fn test_logging_directives() {
+    let mut logging = LoggingConfig::default();
+    assert_eq!(logging.console_directives(), "debug");
+    assert_eq!(logging.file_directives(), "info");
+    logging
+        .modules
+        .insert("sqlx".to_string(), "warn".to_string());
+    logging
+        .modules
+        .insert("hyper".to_string(), "error".to_string());
+    logging.file_level = Some("warn".to_string());
+    assert_eq!(logging.console_directives(), "debug,hyper=error,sqlx=warn");
+    assert_eq!(logging.file_directives(), "warn,hyper=error,sqlx=warn");
+}
+
+#[test]
+fn test_secret_string_redacted() {
+    let redis = RedisConfig {
+        url: SecretString::new("redis://:p%40ss@127.0.0.1:6379/0"),
+    };
+    assert_eq!(format!("{redis:?}"), "RedisConfig { url: [REDACTED] }");
+    assert_eq!(redis.url.expose(), "redis://:p%40ss@127.0.0.1:6379/0");
+    assert_eq!(format!("{:?}", SecretString::default()), "\"\"");
+}
+
+#[test]
+fn test_app_config_debug_redacts_secrets() {
+    let toml = include_str!("../config/dev.toml")
+        .replace(
+            "signing_key = \"\"",
+            "signing_key = \"manifest-signing-key\"",
+        )
+        .replace(
+            "[admin_config.api_keys]\n",
+            "[admin_config.api_keys]\nops = \"admin-api-key\"\n",
+        )
+        + "\n[reply_archive_config.s3]\naccess_key = \"s3-access-key\"\nsecret_key = \"s3-secret-key\"\n";
+    let path = std::env::temp_dir().join(format!(
+        "servicekit-debug-config-{}.toml",
+        std::process::id()
+    ));
+    std::fs::write(&path, toml).unwrap();
+    let config = AppConfig::load(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+    let config = config.unwrap();
+    assert_eq!(
+        config.admin_config.api_keys["ops"].expose(),
+        "admin-api-key"
+    );
+
+    let debug = format!("{config:?}");
+    let secrets = [
+        config.database_url.expose(),
+        config.mss_info_config.app_key.expose(),
+        config.clickhouse_config.password.expose(),
+        config.redis_config.url.expose(),
+        "manifest-signing-key",
+        "admin-api-key",
+        "s3-access-key",
+        "s3-secret-key",
+    ];
+    for secret in secrets.into_iter().filter(|secret| !secret.is_empty()) {
+        assert!(!debug.contains(secret), "Debug output contains {secret}");
+    }
+}
+
+#[test]
+fn test_resolve_secret_files() {
+    let path = std::env::temp_dir().join(format!("servicekit-mss-key-{}", std::process::id()));
+    std::fs::write(&path, "file-key\n").unwrap();
+    let toml = format!(
+        "[mss_info_config]\napp_key = \"inline-key\"\napp_key_file = {:?}\n\n[redis_config]\nurl = \"redis://inline\"\n",
+        path.display().to_string()
+    );
+    let builder = Config::builder().add_source(File::from_str(&toml, config::FileFormat::Toml));
+    let config = resolve_secret_files(builder).unwrap().build().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(
+        config.get_string("mss_info_config.app_key").unwrap(),
+        "file-key"
+    );
+    assert_eq!(
+        config.get_string("redis_config.url").unwrap(),
+        "redis://inline"
+    );
+
+    let keys_path =
+        std::env::temp_dir().join(format!("servicekit-api-keys-{}", std::process::id()));
+    std::fs::write(&keys_path, "ops = \"file-api-key\"\n").unwrap();
+    let toml = format!(
+        "[admin_config]\napi_keys_file = {:?}\n\n[admin_config.api_keys]\nops = \"inline-api-key\"\n",
+        keys_path.display().to_string()
+    );
+    let builder = Config::builder().add_source(File::from_str(&toml, config::FileFormat::Toml));
+    let config = resolve_secret_files(builder).unwrap().build().unwrap();
+    std::fs::remove_file(&keys_path).unwrap();
+    assert_eq!(
+        config.get_string("admin_config.api_keys.ops").unwrap(),
+        "file-api-key"
+    );
+
+    let missing = Config::builder().add_source(File::from_str(
+        "database_url_file = \"/nonexistent/servicekit-db-url\"",
+        config::FileFormat::Toml,
+    ));
+    assert!(resolve_secret_files(missing).is_err());
+}
@@ -0,0 +1,427 @@
+//! 配置热加载：监听配置文件，文件变化后重新加载并校验 AppConfig。
+//! 任务 cron 表达式（通过任务注册表修改调度）、日志过滤规则、限流与提示信息语言立即生效；
+//! 数据库地址、端口、凭据等需要重启的配置修改只记录日志，不生效。
+//! 新配置无法解析或 cron 表达式、日志过滤规则无效时整体拒绝，继续使用当前配置
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use notify::{Event, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+use tracing_subscriber::EnvFilter;
+
+use crate::AppContext;
+use crate::config::{AppConfig, LoggingConfig, TasksConfig};
+use crate::messages;
+use crate::metrics::CONFIG_RELOADS;
+use crate::schedule::cron_calendar::validate_cron;
+
+/// 新旧配置之间的差异
+#[derive(Debug, Default, PartialEq)]
+pub struct ConfigChanges {
+    /// 修改了 cron 表达式的任务：任务名 -> 新的表达式
+    pub schedules: BTreeMap<String, String>,
+    /// 日志级别或按模块过滤的规则有变化
+    pub log_filters: bool,
+    pub rate_limits: bool,
+    pub messages: bool,
+    /// 修改了但需要重启才能生效的配置
+    pub restart_required: Vec<&'static str>,
+}
+
+impl ConfigChanges {
+    pub fn between(current: &AppConfig, new: &AppConfig) -> Self {
+        let current_schedules = cron_schedules(&current.tasks);
+        let schedules = cron_schedules(&new.tasks)
+            .into_iter()
+            .filter(|(name, cron_schedule)| {
+                current_schedules
+                    .get(name)
+                    .is_some_and(|current| current != cron_schedule)
+            })
+            .collect();
+
+        let mut restart_required = Vec::new();
+        let mut check = |section: &'static str, changed: bool| {
+            if changed {
+                restart_required.push(section);
+            }
+        };
+        check("database_url", current.database_url != new.database_url);
+        check(
+            "web_server_host",
+            current.web_server_host != new.web_server_host,
+        );
+        check(
+            "web_server_port",
+            current.web_server_port != new.web_server_port,
+        );
+        // 增删任务或修改组合任务的数据种类需要重新注册
+        check(
+            "tasks",
+            without_schedules(&current.tasks) != without_schedules(&new.tasks),
+        );
+        check(
+            "logging.console_format",
+            current.logging.console_format != new.logging.console_format,
+        );
+        check(
+            "logging.file_format",
+            current.logging.file_format != new.logging.file_format,
+        );
+        check("config_watch", current.config_watch != new.config_watch);
+        check(
+            "mss_info_config",
+            current.mss_info_config != new.mss_info_config,
+        );
+        check(
+            "telecom_config",
+            current.telecom_config != new.telecom_config,
+        );
+        check(
+            "clickhouse_config",
+            current.clickhouse_config != new.clickhouse_config,
+        );
+        check("redis_config", current.redis_config != new.redis_config);
+        check(
+            "reply_archive_config",
+            current.reply_archive_config != new.reply_archive_config,
+        );
+        check("admin_config", current.admin_config != new.admin_config);
+        check("timeouts", current.timeouts != new.timeouts);
+        check("limits", current.limits != new.limits);
+        check(
+            "binlog_polling",
+            current.binlog_polling != new.binlog_polling,
+        );
+        check(
+            "mss_retry_queue",
+            current.mss_retry_queue != new.mss_retry_queue,
+        );
+        check(
+            "clickhouse_replay",
+            current.clickhouse_replay != new.clickhouse_replay,
+        );
+        check("retry", current.retry != new.retry);
+        check("gateway_cache", current.gateway_cache != new.gateway_cache);
+        check("mapping_cache", current.mapping_cache != new.mapping_cache);
+        check(
+            "binlog_sharding",
+            current.binlog_sharding != new.binlog_sharding,
+        );
+        check("notify_status", current.notify_status != new.notify_status);
+        check("train_status", current.train_status != new.train_status);
+        check(
+            "analytics_export",
+            current.analytics_export != new.analytics_export,
+        );
+        check(
+            "push_verification",
+            current.push_verification != new.push_verification,
+        );
+        check("push_pipeline", current.push_pipeline != new.push_pipeline);
+        check("shadow_push", current.shadow_push != new.shadow_push);
+        check("push_outbox", current.push_outbox != new.push_outbox);
+        check("legacy_import", current.legacy_import != new.legacy_import);
+        check("run_manifest", current.run_manifest != new.run_manifest);
+        check(
+            "persistence_policy",
+            current.persistence_policy != new.persistence_policy,
+        );
+        check("provinces", current.provinces != new.provinces);
+
+        ConfigChanges {
+            schedules,
+            log_filters: log_directives(&current.logging) != log_directives(&new.logging),
+            rate_limits: current.rate_limits != new.rate_limits,
+            messages: current.messages != new.messages,
+            restart_required,
+        }
+    }
+
+    /// 是否有可以热加载的修改
+    pub fn has_hot_changes(&self) -> bool {
+        !self.schedules.is_empty() || self.log_filters || self.rate_limits || self.messages
+    }
+
+    /// 校验将要生效的修改，任一无效时整体拒绝
+    fn validate(&self, new: &AppConfig) -> Result<()> {
+        for (name, cron_schedule) in &self.schedules {
+            validate_cron(cron_schedule)
+                .with_context(|| format!("Invalid cron schedule for task '{name}'"))?;
+        }
+        if self.log_filters {
+            let (console, file) = log_directives(&new.logging);
+            for directives in [console, file] {
+                EnvFilter::try_new(&directives)
+                    .with_context(|| format!("Invalid log filter: {directives}"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// 每个 cron 任务的名称与 cron 表达式
+fn schedule_entries(tasks: &mut TasksConfig) -> Vec<(&str, &mut String)> {
+    let psn_push = &mut tasks.psn_push;
+    let mut entries = vec![(psn_push.task_name.as_str(), &mut psn_push.cron_schedule)];
+    for config in [
+        &mut tasks.clickhouse_schema_check,
+        &mut tasks.gateway_cache_warmup,
+        &mut tasks.binlog_digest,
+        &mut tasks.binlog_gap_replay,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        entries.push((config.task_name.as_str(), &mut config.cron_schedule));
+    }
+    for group in &mut tasks.composite_groups {
+        entries.push((group.task_name.as_str(), &mut group.cron_schedule));
+    }
+    entries
+}
+
+/// 任务名 -> cron 表达式
+fn cron_schedules(tasks: &TasksConfig) -> BTreeMap<String, String> {
+    schedule_entries(&mut tasks.clone())
+        .into_iter()
+        .map(|(name, cron_schedule)| (name.to_string(), cron_schedule.clone()))
+        .collect()
+}
+
+// 去掉 cron 表达式后的任务配置，用于判断是否有需要重启的修改
+fn without_schedules(tasks: &TasksConfig) -> TasksConfig {
+    let mut tasks = tasks.clone();
+    for (_, cron_schedule) in schedule_entries(&mut tasks) {
+        cron_schedule.clear();
+    }
+    tasks
+}
+
+fn log_directives(logging: &LoggingConfig) -> (String, String) {
+    (logging.console_directives(), logging.file_directives())
+}
+
+/// 持有当前生效的配置，文件变化时与新配置比较并应用可以热加载的部分
+struct ConfigReloader {
+    app_context: Arc<AppContext>,
+    config_file: String,
+    current: AppConfig,
+}
+
+impl ConfigReloader {
+    async fn reload(&mut self) {
+        let new = match AppConfig::load(&self.config_file) {
+            Ok(new) => new,
+            Err(e) => {
+                error!(
+                    "Rejected configuration reload from {}, keeping the current configuration: {e}",
+                    self.config_file
+                );
+                CONFIG_RELOADS.with_label_values(&["rejected"]).inc();
+                return;
+            }
+        };
+        let changes = ConfigChanges::between(&self.current, &new);
+        if let Err(e) = changes.validate(&new) {
+            error!(
+                "Rejected configuration reload from {}, keeping the current configuration: {e:#}",
+                self.config_file
+            );
+            CONFIG_RELOADS.with_label_values(&["rejected"]).inc();
+            return;
+        }
+        for section in &changes.restart_required {
+            warn!(
+                "Configuration '{section}' changed in {}, it requires a restart and was not applied.",
+                self.config_file
+            );
+        }
+        if !changes.has_hot_changes() {
+            info!(
+                "No hot-reloadable configuration changed in {}.",
+                self.config_file
+            );
+            CONFIG_RELOADS.with_label_values(&["unchanged"]).inc();
+            return;
+        }
+        self.apply(&changes, &new).await;
+        CONFIG_RELOADS.with_label_values(&["applied"]).inc();
+    }
+
+    // 只更新生效了的部分，需要重启的配置保持当前值
+    async fn apply(&mut self, changes: &ConfigChanges, new: &AppConfig) {
+        let mut tasks = self.current.tasks.clone();
+        if !changes.schedules.is_empty() {
+            match self.app_context.task_registry.get() {
+                Some(registry) => {
+                    for (name, cron_schedule) in schedule_entries(&mut tasks) {
+                        let Some(new_schedule) = changes.schedules.get(name) else {
+                            continue;
+                        };
+                        match registry.reschedule(name, new_schedule).await {
+                            Ok(_) => *cron_schedule = new_schedule.clone(),
+                            Err(e) => error!("Failed to apply cron schedule for '{name}': {e}"),
+                        }
+                    }
+                }
+                None => {
+                    warn!("Task registry is not ready, cron schedule changes were not applied.")
+                }
+            }
+        }
+
+        let mut logging = self.current.logging.as_ref().clone();
+        if changes.log_filters {
+            let (console, file) = log_directives(&new.logging);
+            match self.app_context.log_filter.get() {
+                Some(handle) => match handle.reload(Some(&console), Some(&file)) {
+                    Ok(filters) => {
+                        info!(
+                            "Log filters reloaded from configuration: console '{}', file '{}'.",
+                            filters.console, filters.file
+                        );
+                        logging = LoggingConfig {
+                            console_format: logging.console_format,
+                            file_format: logging.file_format,
+                            ..new.logging.as_ref().clone()
+                        };
+                    }
+                    Err(e) => error!("Failed to reload log filters: {e:#}"),
+                },
+                None => warn!("Log filters are not reloadable in this process."),
+            }
+        }
+
+        if changes.rate_limits {
+            self.app_context.rate_limiters.update(&new.rate_limits);
+        }
+
+        if changes.messages {
+            messages::set_language(new.messages.language);
+            info!("Message language changed to {:?}.", new.messages.language);
+        }
+
+        self.current = AppConfig {
+            tasks,
+            logging: Arc::new(logging),
+            rate_limits: Arc::clone(&new.rate_limits),
+            messages: Arc::clone(&new.messages),
+            ..self.current.clone()
+        };
+        info!("Configuration reloaded from {}.", self.config_file);
+    }
+}
+
+/// 监听 config_file 所在目录，配置文件变化且 debounce 内没有再变化后重新加载，关闭时退出。
+/// app_config 为启动时加载的配置
+pub fn spawn_config_watcher(
+    app_context: Arc<AppContext>,
+    app_config: AppConfig,
+    config_file: String,
+) -> Result<()> {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let path = PathBuf::from(&config_file);
+    let file_name = path.file_name().map(ToOwned::to_owned);
+    let mut watcher =
+        notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+            Ok(event)
+                if (event.kind.is_modify() || event.kind.is_create())
+                    && event
+                        .paths
+                        .iter()
+                        .any(|path| path.file_name() == file_name.as_deref()) =>
+            {
+                let _ = sender.send(());
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Config file watcher error: {e:?}"),
+        })
+        .context("Failed to create config file watcher")?;
+    // 监听目录而不是文件，编辑器保存时可能以新文件替换原文件
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    watcher
+        .watch(dir, RecursiveMode::NonRecursive)
+        .with_context(|| format!("Failed to watch {}", dir.display()))?;
+
+    let debounce = app_config.config_watch.debounce;
+    let shutdown = Arc::clone(&app_context.shutdown);
+    let mut reloader = ConfigReloader {
+        app_context,
+        config_file,
+        current: app_config,
+    };
+    tokio::spawn(async move {
+        // watcher 释放后停止监听
+        let _watcher = watcher;
+        info!(
+            "Watching {} for configuration changes.",
+            reloader.config_file
+        );
+        loop {
+            tokio::select! {
+                received = receiver.recv() => {
+                    if received.is_none() {
+                        break;
+                    }
+                }
+                _ = shutdown.cancelled() => break,
+            }
+            tokio::time::sleep(debounce).await;
+            while receiver.try_recv().is_ok() {}
+            reloader.reload().await;
+        }
+        info!("Config file watcher stopped.");
+    });
+    Ok(())
+}
+
+#[test]
+fn test_config_changes() {
+    let current = AppConfig::load("config/dev.toml").unwrap();
+    assert_eq!(
+        ConfigChanges::between(&current, &current),
+        ConfigChanges::default()
+    );
+
+    let mut new = current.clone();
+    new.tasks.psn_push.cron_schedule = "0 0 6 * * *".to_string();
+    new.database_url = crate::config::SecretString::new("mysql://other/db");
+    let mut logging = new.logging.as_ref().clone();
+    logging
+        .modules
+        .insert("sqlx".to_string(), "warn".to_string());
+    new.logging = Arc::new(logging);
+    let changes = ConfigChanges::between(&current, &new);
+    assert_eq!(
+        changes.schedules,
+        BTreeMap::from([(
+            current.tasks.psn_push.task_name.clone(),
+            "0 0 6 * * *".to_string()
+        )])
+    );
+    assert!(changes.log_filters);
+    assert!(!changes.rate_limits);
+    assert!(!changes.messages);
+    assert_eq!(changes.restart_required, vec!["database_url"]);
+    assert!(changes.validate(&new).is_ok());
+
+    // 修改任务名视为增删任务，需要重启
+    let mut renamed = current.clone();
+    renamed.tasks.psn_push.task_name = "renamed".to_string();
+    let changes = ConfigChanges::between(&current, &renamed);
+    assert!(changes.schedules.is_empty());
+    assert_eq!(changes.restart_required, vec!["tasks"]);
+
+    let mut invalid = current.clone();
+    invalid.tasks.psn_push.cron_schedule = "not a cron".to_string();
+    let changes = ConfigChanges::between(&current, &invalid);
+    assert!(changes.validate(&invalid).is_err());
+}
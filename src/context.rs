@@ -1,11 +1,34 @@
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
-use crate::config::{MssInfoConfig, RedisConfig, TelecomConfig};
+use crate::alert_rules::AlertRuleGroup;
+use crate::binlog::ProcessorRegistry;
+use crate::config::{
+    AdminConfig, AppConfig, BinlogPollingConfig, BinlogShardingConfig, GatewayCacheConfig,
+    LimitsConfig, MappingCacheConfig, MssInfoConfig, NotifyStatusConfig, PersistencePolicyConfig,
+    RedisConfig, RetryConfig, TimeoutsConfig, TrainStatusConfig,
+};
 use crate::db::mysql_pool;
-use crate::utils::redis::{init_redis, RedisMgr};
-use crate::utils::{ClickHouseClient, GatewayClient};
-use crate::ClickhouseConfig;
+use crate::logging::LogFilterHandle;
+use crate::mappers::reply_store::{ReplyBodyStore, build_reply_store};
+use crate::models::analytics_export::AnalyticsExporter;
+use crate::models::push_result::PushResultWriter;
+use crate::schedule::clickhouse_replay::ClickHouseReplayQueue;
+use crate::schedule::job_tracker::JobTracker;
+use crate::schedule::mss_retry_queue::MssRetryQueue;
+use crate::schedule::push_executor::ClickhouseTables;
+use crate::schedule::push_outbox::PushOutbox;
+use crate::schedule::push_pipeline::PushPipeline;
+use crate::schedule::push_verification::PushVerifier;
+use crate::schedule::run_manifest::RunManifestWriter;
+use crate::schedule::running_tasks::RunningTaskRegistry;
+use crate::schedule::shadow_push::ShadowPusher;
+use crate::schedule::task_registry::TaskRegistry;
+use crate::shutdown::ShutdownController;
+use crate::utils::redis::{RedisMgr, init_redis};
+use crate::utils::{
+    ClickHouseClient, Clock, GatewayCache, GatewayClient, InstrumentedClient, LookupCache,
+    RateLimiters, SystemClock,
+};
 use anyhow::{Context as _, Result};
 use reqwest::Client;
 use sqlx::MySqlPool;
@@ -15,63 +38,294 @@ use tracing::info;
 #[derive(Clone)]
 pub struct AppContext {
     pub mysql_pool: MySqlPool,
-    pub http_client: Client,
+    pub mss_http_client: Arc<InstrumentedClient>,
     pub mss_info_config: Arc<MssInfoConfig>,
+    /// MSS 与网关调用的令牌桶限流器，所有任务共用
+    pub rate_limiters: Arc<RateLimiters>,
     pub gateway_client: Arc<GatewayClient>,
     pub clickhouse_client: Arc<ClickHouseClient>,
+    /// 各类数据的 ClickHouse 表，按 [clickhouse_config.tables] 配置
+    pub clickhouse_tables: Arc<ClickhouseTables>,
     pub redis_mgr: RedisMgr,
-    pub provinces: Arc<HashMap<String, String>>,
+    pub push_result_writer: Arc<PushResultWriter>,
+    /// 执行与推送汇总导出到 ClickHouse，未启用时为 None
+    pub analytics_exporter: Option<Arc<AnalyticsExporter>>,
+    /// 推送状态回写后的残留核对，未启用时为 None
+    pub push_verifier: Option<Arc<PushVerifier>>,
+    /// 查询与推送之间的队列，未启用时为 None
+    pub push_pipeline: Option<Arc<PushPipeline>>,
+    /// 抽样镜像到备用 MSS 环境的影子推送，未启用时为 None
+    pub shadow_pusher: Option<Arc<ShadowPusher>>,
+    /// 推送发件箱，进程中断后启动时恢复未完成的推送，未启用时为 None
+    pub push_outbox: Option<Arc<PushOutbox>>,
+    /// 组合推送的归档清单，未启用时为 None
+    pub run_manifest_writer: Option<Arc<RunManifestWriter>>,
+    pub processor_registry: Arc<ProcessorRegistry>,
+    pub mss_retry_queue: Option<Arc<MssRetryQueue>>,
+    /// 状态回写在部分 ClickHouse 节点失败时的重放队列，未启用时为 None
+    pub clickhouse_replay: Option<Arc<ClickHouseReplayQueue>>,
+    pub reply_store: Option<Arc<dyn ReplyBodyStore>>,
+    pub admin_config: Arc<AdminConfig>,
+    pub timeouts: Arc<TimeoutsConfig>,
+    pub limits: Arc<LimitsConfig>,
+    pub retry: Arc<RetryConfig>,
+    pub binlog_polling: Arc<BinlogPollingConfig>,
+    pub gateway_cache: Arc<GatewayCacheConfig>,
+    pub mapping_cache: Arc<MappingCacheConfig>,
+    pub binlog_sharding: Arc<BinlogShardingConfig>,
+    pub notify_status: Arc<NotifyStatusConfig>,
+    /// 推送与删除通知按培训班状态筛选
+    pub train_status: Arc<TrainStatusConfig>,
+    pub persistence_policy: Arc<PersistencePolicyConfig>,
+    /// 省份编码 -> 省份名称
+    pub provinces: Arc<LookupCache>,
+    /// 调度器注册任务后生成的告警规则
+    pub alert_rules: Arc<OnceLock<Vec<AlertRuleGroup>>>,
+    /// 调度器注册的 cron 任务，注册完成后设置，供任务查询与控制接口使用
+    pub task_registry: Arc<OnceLock<Arc<TaskRegistry>>>,
+    /// 日志过滤规则的热更新句柄，日志初始化后设置
+    pub log_filter: Arc<OnceLock<LogFilterHandle>>,
+    /// 进程关闭信号，调度器与推送任务据此停止开始新的工作
+    pub shutdown: Arc<ShutdownController>,
+    /// 手动触发的推送作业的进度，供作业查询接口使用
+    pub job_tracker: Arc<JobTracker>,
+    /// 正在运行的推送任务实例，供诊断接口查看
+    pub running_tasks: Arc<RunningTaskRegistry>,
+    /// 计算昨天、hit_date 与 binlog 同步窗口使用的时钟，测试与补数据时可替换
+    pub clock: Arc<dyn Clock>,
 }
 
 impl AppContext {
-    pub async fn new(
-        database_url: &str,
-        mss_info_config: Arc<MssInfoConfig>,
-        telecom_config: Arc<TelecomConfig>,
-        clickhouse_config: Arc<ClickhouseConfig>,
-        redis_config: Arc<RedisConfig>,
-        provinces: HashMap<String, String>,
-    ) -> Result<Self> {
+    pub async fn new(app_config: &AppConfig) -> Result<Self> {
+        let mss_info_config = Arc::clone(&app_config.mss_info_config);
+        let telecom_config = Arc::clone(&app_config.telecom_config);
+        let clickhouse_config = Arc::clone(&app_config.clickhouse_config);
+        let redis_config = Arc::clone(&app_config.redis_config);
+        let reply_archive_config = Arc::clone(&app_config.reply_archive_config);
+        let admin_config = Arc::clone(&app_config.admin_config);
+        let timeouts = Arc::clone(&app_config.timeouts);
+        let limits = Arc::clone(&app_config.limits);
+        let gateway_cache = Arc::clone(&app_config.gateway_cache);
+
         // --- Initialize MYSQL POOL ---
-        let mysql_pool = mysql_pool::create_mysql_pool(database_url)
-            .await
-            .context("Failed to create database connection mysql_pool")?;
+        let mysql_pool =
+            mysql_pool::create_mysql_pool(app_config.database_url.expose(), timeouts.mysql_acquire)
+                .await
+                .context("Failed to create database connection mysql_pool")?;
         info!("Database connection mysql_pool created.");
 
         // --- Initialize HTTP ---
-        // 自定义 HTTP 客户端，设置超时
+        // 自定义 HTTP 客户端，超时由 [timeouts] 配置
         let http_client = Client::builder()
-            .connect_timeout(Duration::from_secs(5)) // TCP连接最多等5秒
-            .read_timeout(Duration::from_secs(5)) // 读取响应最多等5秒
-            .timeout(Duration::from_secs(10)) // 整个请求最多10秒
+            .connect_timeout(timeouts.http_connect) // TCP连接超时
+            .read_timeout(timeouts.http_read) // 读取响应超时
+            .timeout(timeouts.http_request) // 整个请求超时
             .build()
             .expect("Failed to build reqwest client");
+        let max_body_size = limits.http_max_response_body.as_usize();
+        // MSS 与网关各自持有一个带观测能力的客户端，共享底层连接池
+        // MSS 接口要求两次调用之间有最小间隔（默认20毫秒）
+        let mss_http_client = Arc::new(
+            InstrumentedClient::new(http_client.clone(), "mss", timeouts.mss_min_interval)
+                .with_max_body_size(max_body_size),
+        );
         info!("HTTP Client initialized.");
 
-        // --- Initialize GatewayClient ---
-        let gateway_client = Arc::new(GatewayClient::new(http_client.clone(), telecom_config));
-        info!("GatewayClient initialized.");
+        let shutdown = Arc::new(ShutdownController::new());
+
+        // 影子推送使用单独的客户端，不受 MSS 最小调用间隔约束，也不计入 mss 的请求指标
+        let shadow_pusher = ShadowPusher::new(
+            Arc::new(
+                InstrumentedClient::new(http_client.clone(), "mss_shadow", Duration::ZERO)
+                    .with_max_body_size(max_body_size),
+            ),
+            mysql_pool.clone(),
+            Arc::clone(&mss_info_config),
+            Arc::clone(&app_config.shadow_push),
+            Arc::clone(&shutdown),
+        )
+        .context("Failed to initialize shadow push")?
+        .map(Arc::new);
+        info!(
+            "Shadow push enabled: {}, sample: {}%",
+            app_config.shadow_push.enabled, app_config.shadow_push.sample_percent
+        );
+
+        // 清单与推送任务使用同一个时钟
+        let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+
+        // --- Initialize RunManifestWriter ---
+        let run_manifest_writer = RunManifestWriter::new(
+            mysql_pool.clone(),
+            &app_config.run_manifest,
+            http_client.clone(),
+            Arc::clone(&clock),
+        )
+        .context("Failed to initialize run manifest writer")?
+        .map(Arc::new);
+        info!("Run manifest enabled: {}", app_config.run_manifest.enabled);
+
+        // --- Initialize ReplyBodyStore ---
+        let reply_store = build_reply_store(&reply_archive_config, http_client.clone())
+            .context("Failed to initialize MSS reply archive store")?;
+        info!(
+            "MSS reply archive backend: {:?}",
+            reply_archive_config.backend
+        );
 
         // --- Initialize ClickHouseClient ---
         let clickhouse_client = Arc::new(
             ClickHouseClient::new(clickhouse_config)
                 .context("Failed to initialize ClickHouseClient")?,
         );
+        let clickhouse_tables = Arc::new(
+            ClickhouseTables::from_config(&app_config.clickhouse_config.tables)
+                .context("Invalid [clickhouse_config.tables]")?,
+        );
         info!("ClickHouseClient initialized.");
 
-        let redis_mgr: RedisMgr = init_redis(&redis_config.url)
+        let redis_mgr: RedisMgr = init_redis(redis_config.url.expose())
             .await
             .context("Failed to initialize Redis ConnectionManager")?;
 
         info!("Redis ConnectionManager initialized.");
+
+        // --- Initialize RateLimiters ---
+        let rate_limiters = Arc::new(RateLimiters::new(&app_config.rate_limits));
+        info!("Rate limits: {:?}", app_config.rate_limits);
+
+        // --- Initialize GatewayClient ---
+        let mut gateway_client = GatewayClient::new(
+            InstrumentedClient::new(http_client, "gateway", Duration::ZERO)
+                .with_max_body_size(max_body_size),
+            telecom_config,
+            app_config.retry.gateway.clone(),
+        )
+        .with_rate_limiter(Arc::clone(&rate_limiters.gateway))
+        .with_user_policy(
+            app_config
+                .persistence_policy
+                .table("d_telecom_user")
+                .cloned(),
+        );
+        if gateway_cache.enabled {
+            let mut cache = GatewayCache::new(redis_mgr.clone(), gateway_cache.ttl);
+            if gateway_cache.lookups {
+                cache = cache.with_lookups(gateway_cache.lookup_ttl);
+            }
+            gateway_client = gateway_client.with_cache(cache);
+        }
+        let gateway_client = Arc::new(gateway_client);
+        info!(
+            "GatewayClient initialized, cache enabled: {}",
+            gateway_cache.enabled
+        );
+
+        // --- Initialize MssRetryQueue ---
+        let mss_retry_queue = app_config.mss_retry_queue.enabled.then(|| {
+            Arc::new(MssRetryQueue::new(
+                redis_mgr.clone(),
+                Arc::clone(&app_config.mss_retry_queue),
+            ))
+        });
+        info!(
+            "MSS retry queue enabled: {}",
+            app_config.mss_retry_queue.enabled
+        );
+
+        // --- Initialize ClickHouseReplayQueue ---
+        let clickhouse_replay = ClickHouseReplayQueue::new(
+            mysql_pool.clone(),
+            Arc::clone(&clickhouse_client),
+            Arc::clone(&app_config.clickhouse_replay),
+        )
+        .map(Arc::new);
+        info!(
+            "ClickHouse replay queue enabled: {}",
+            app_config.clickhouse_replay.enabled
+        );
+
+        // --- Initialize PushResultWriter ---
+        let push_result_writer = Arc::new(PushResultWriter::spawn(
+            mysql_pool.clone(),
+            limits.push_result_batch_size,
+        ));
+        info!("PushResultWriter initialized.");
+
+        let analytics_exporter = AnalyticsExporter::new(
+            Arc::clone(&clickhouse_client),
+            Arc::clone(&app_config.analytics_export),
+        )
+        .map(Arc::new);
+        info!(
+            "Analytics export enabled: {}",
+            app_config.analytics_export.enabled
+        );
+
+        let push_verifier = PushVerifier::new(
+            mysql_pool.clone(),
+            Arc::clone(&clickhouse_client),
+            Arc::clone(&app_config.notify_status),
+            Arc::clone(&app_config.push_verification),
+            limits.push_update_batch_size,
+        )
+        .map(Arc::new);
+        info!(
+            "Push verification enabled: {}",
+            app_config.push_verification.enabled
+        );
+
+        let push_pipeline =
+            PushPipeline::new(Arc::clone(&app_config.push_pipeline), redis_mgr.clone())
+                .map(Arc::new);
+        info!(
+            "Push pipeline enabled: {}, backend: {:?}",
+            app_config.push_pipeline.enabled, app_config.push_pipeline.backend
+        );
+
+        let push_outbox =
+            PushOutbox::new(mysql_pool.clone(), Arc::clone(&app_config.push_outbox)).map(Arc::new);
+        info!("Push outbox enabled: {}", app_config.push_outbox.enabled);
+
         Ok(Self {
             mysql_pool,
-            http_client,
+            mss_http_client,
             mss_info_config,
+            rate_limiters,
             gateway_client,
             clickhouse_client,
+            clickhouse_tables,
             redis_mgr,
-            provinces: Arc::new(provinces),
+            push_result_writer,
+            analytics_exporter,
+            push_verifier,
+            push_pipeline,
+            shadow_pusher,
+            push_outbox,
+            run_manifest_writer,
+            processor_registry: Arc::new(ProcessorRegistry::with_defaults()),
+            mss_retry_queue,
+            clickhouse_replay,
+            reply_store,
+            admin_config,
+            timeouts,
+            limits,
+            retry: Arc::clone(&app_config.retry),
+            binlog_polling: Arc::clone(&app_config.binlog_polling),
+            gateway_cache,
+            mapping_cache: Arc::clone(&app_config.mapping_cache),
+            binlog_sharding: Arc::clone(&app_config.binlog_sharding),
+            notify_status: Arc::clone(&app_config.notify_status),
+            train_status: Arc::clone(&app_config.train_status),
+            persistence_policy: Arc::clone(&app_config.persistence_policy),
+            provinces: Arc::new(LookupCache::from_map("provinces", &app_config.provinces)),
+            alert_rules: Arc::new(OnceLock::new()),
+            task_registry: Arc::new(OnceLock::new()),
+            log_filter: Arc::new(OnceLock::new()),
+            shutdown,
+            job_tracker: Arc::new(JobTracker::default()),
+            running_tasks: Arc::new(RunningTaskRegistry::default()),
+            clock,
         })
     }
 }
@@ -83,7 +337,7 @@ pub struct RedisContext {
 
 impl RedisContext {
     pub async fn new(redis_config: Arc<RedisConfig>) -> Result<Self> {
-        let redis_mgr: RedisMgr = init_redis(&redis_config.url)
+        let redis_mgr: RedisMgr = init_redis(redis_config.url.expose())
             .await
             .context("Failed to initialize Redis ConnectionManager")?;
         info!("Redis ConnectionManager initialized.");
@@ -1 +1,2 @@
 pub mod mysql_pool;
+pub mod query_runner;
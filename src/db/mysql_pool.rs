@@ -1,8 +1,16 @@
+use sqlx::MySql;
 use sqlx::mysql::{MySqlConnectOptions, MySqlPool, MySqlPoolOptions};
+use sqlx::pool::PoolConnection;
 use std::str::FromStr;
 use std::time::Duration;
+use tracing::warn;
 
-pub async fn create_mysql_pool(database_url: &str) -> Result<MySqlPool, sqlx::Error> {
+use crate::metrics::DB_POOL_EXHAUSTED;
+
+pub async fn create_mysql_pool(
+    database_url: &str,
+    acquire_timeout: Duration,
+) -> Result<MySqlPool, sqlx::Error> {
     // 1. MySqlConnectOptions only holds connection details.
     let connect_options = MySqlConnectOptions::from_str(database_url)?;
 
@@ -11,10 +19,76 @@ pub async fn create_mysql_pool(database_url: &str) -> Result<MySqlPool, sqlx::Er
         .max_connections(10)
         .min_connections(2)
         // Set the timeout for creating a new connection HERE.
-        .acquire_timeout(Duration::from_secs(3))
+        .acquire_timeout(acquire_timeout)
         // Finally, build the mysql_pool using the connection details.
         .connect_with(connect_options)
         .await?;
 
     Ok(mysql_pool)
 }
+
+/// 连接池在超时时间内没有可用连接，调用方应稍后重试而不是按失败处理
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "MySQL pool exhausted: no connection for {context} within {timeout:?} (size {size}, idle {idle})"
+)]
+pub struct PoolExhausted {
+    pub context: String,
+    pub timeout: Duration,
+    pub size: u32,
+    pub idle: usize,
+}
+
+/// 在 timeout 内从连接池取出连接，超时返回 `PoolExhausted` 并记录指标
+pub async fn acquire_with_timeout(
+    mysql_pool: &MySqlPool,
+    context: &str,
+    timeout: Duration,
+) -> anyhow::Result<PoolConnection<MySql>> {
+    match tokio::time::timeout(timeout, mysql_pool.acquire()).await {
+        Ok(Ok(conn)) => Ok(conn),
+        Ok(Err(sqlx::Error::PoolTimedOut)) | Err(_) => {
+            let exhausted = PoolExhausted {
+                context: context.to_string(),
+                timeout,
+                size: mysql_pool.size(),
+                idle: mysql_pool.num_idle(),
+            };
+            warn!("{exhausted}");
+            DB_POOL_EXHAUSTED.with_label_values(&[context]).inc();
+            Err(exhausted.into())
+        }
+        Ok(Err(e)) => Err(anyhow::Error::new(e)
+            .context(format!("Failed to acquire MySQL connection for {context}"))),
+    }
+}
+
+/// 错误链中是否有连接池耗尽（包括未经 `acquire_with_timeout` 的 sqlx 超时）
+pub fn is_pool_exhausted(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause.is::<PoolExhausted>()
+            || matches!(
+                cause.downcast_ref::<sqlx::Error>(),
+                Some(sqlx::Error::PoolTimedOut)
+            )
+    })
+}
+
+#[test]
+fn test_is_pool_exhausted() {
+    use anyhow::Context;
+
+    let exhausted: anyhow::Result<()> = Err(PoolExhausted {
+        context: "push".to_string(),
+        timeout: Duration::from_secs(3),
+        size: 10,
+        idle: 0,
+    }
+    .into());
+    assert!(is_pool_exhausted(
+        &exhausted.context("Failed to fetch").unwrap_err()
+    ));
+    let timed_out: anyhow::Result<()> = Err(sqlx::Error::PoolTimedOut.into());
+    assert!(is_pool_exhausted(&timed_out.unwrap_err()));
+    assert!(!is_pool_exhausted(&anyhow::anyhow!("other")));
+}
@@ -0,0 +1,184 @@
+//! 具名查询执行入口。每条 MySQL 语句带一个逻辑名称（如 classes_by_date、batch_insert_users），
+//! 按名称记录执行次数、耗时与行数，导出到 Prometheus 指标并供 /admin/queries 查看热点。
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use sqlx::mysql::{MySqlQueryResult, MySqlRow};
+
+use crate::metrics::{DB_QUERY_DURATION, DB_QUERY_ROWS};
+
+static QUERY_STATS: LazyLock<Mutex<HashMap<String, QueryStats>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// 查询结果的行数：写入语句为影响行数，读取语句为返回行数
+pub trait RowCount {
+    fn row_count(&self) -> u64;
+}
+
+impl RowCount for MySqlQueryResult {
+    fn row_count(&self) -> u64 {
+        self.rows_affected()
+    }
+}
+
+impl RowCount for MySqlRow {
+    fn row_count(&self) -> u64 {
+        1
+    }
+}
+
+impl<T> RowCount for Vec<T> {
+    fn row_count(&self) -> u64 {
+        self.len() as u64
+    }
+}
+
+impl<T> RowCount for Option<T> {
+    fn row_count(&self) -> u64 {
+        u64::from(self.is_some())
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct QueryStats {
+    calls: u64,
+    errors: u64,
+    rows: u64,
+    total: Duration,
+    max: Duration,
+}
+
+impl QueryStats {
+    fn record(&mut self, elapsed: Duration, rows: Option<u64>) {
+        self.calls += 1;
+        match rows {
+            Some(rows) => self.rows += rows,
+            None => self.errors += 1,
+        }
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+    }
+}
+
+/// 单个查询名称的累计统计
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryStatsEntry {
+    pub name: String,
+    pub calls: u64,
+    pub errors: u64,
+    pub rows: u64,
+    pub total_ms: u64,
+    pub avg_ms: u64,
+    pub max_ms: u64,
+}
+
+impl QueryStatsEntry {
+    fn new(name: &str, stats: &QueryStats) -> Self {
+        let total_ms = millis(stats.total);
+        Self {
+            name: name.to_string(),
+            calls: stats.calls,
+            errors: stats.errors,
+            rows: stats.rows,
+            total_ms,
+            avg_ms: total_ms.checked_div(stats.calls).unwrap_or_default(),
+            max_ms: millis(stats.max),
+        }
+    }
+}
+
+/// 为一条语句绑定逻辑名称，用法：
+/// `QueryRunner::new("task_run_start").run(query.execute(pool)).await?`
+#[derive(Debug, Clone, Copy)]
+pub struct QueryRunner<'a> {
+    name: &'a str,
+}
+
+impl<'a> QueryRunner<'a> {
+    pub fn new(name: &'a str) -> Self {
+        Self { name }
+    }
+
+    /// 执行查询并按名称记录耗时与行数，错误原样返回
+    pub async fn run<T, F>(self, query: F) -> Result<T, sqlx::Error>
+    where
+        T: RowCount,
+        F: Future<Output = Result<T, sqlx::Error>>,
+    {
+        let started = Instant::now();
+        let result = query.await;
+        self.record(
+            started.elapsed(),
+            result.as_ref().ok().map(RowCount::row_count),
+        );
+        result
+    }
+
+    fn record(&self, elapsed: Duration, rows: Option<u64>) {
+        let outcome = if rows.is_some() { "ok" } else { "error" };
+        DB_QUERY_DURATION
+            .with_label_values(&[self.name, outcome])
+            .observe(elapsed.as_secs_f64());
+        if let Some(rows) = rows {
+            DB_QUERY_ROWS.with_label_values(&[self.name]).inc_by(rows);
+        }
+
+        let mut stats = QUERY_STATS.lock().unwrap_or_else(|e| e.into_inner());
+        match stats.get_mut(self.name) {
+            Some(entry) => entry.record(elapsed, rows),
+            None => {
+                let mut entry = QueryStats::default();
+                entry.record(elapsed, rows);
+                stats.insert(self.name.to_string(), entry);
+            }
+        }
+    }
+}
+
+/// 所有查询的累计统计，按总耗时降序
+pub fn query_stats() -> Vec<QueryStatsEntry> {
+    let stats = QUERY_STATS.lock().unwrap_or_else(|e| e.into_inner());
+    let mut entries: Vec<QueryStatsEntry> = stats
+        .iter()
+        .map(|(name, stats)| QueryStatsEntry::new(name, stats))
+        .collect();
+    entries.sort_by(|a, b| {
+        b.total_ms
+            .cmp(&a.total_ms)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    entries
+}
+
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+#[tokio::test]
+async fn test_query_runner_records_stats() {
+    let name = "test_query_runner_records_stats";
+    let rows = QueryRunner::new(name)
+        .run(async { Ok(vec![1, 2, 3]) })
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 3);
+    let missing = QueryRunner::new(name)
+        .run(async { Ok(None::<u8>) })
+        .await
+        .unwrap();
+    assert!(missing.is_none());
+    let failed = QueryRunner::new(name)
+        .run(async { Err::<Vec<u8>, _>(sqlx::Error::RowNotFound) })
+        .await;
+    assert!(failed.is_err());
+
+    let entry = query_stats()
+        .into_iter()
+        .find(|entry| entry.name == name)
+        .unwrap();
+    assert_eq!((entry.calls, entry.errors, entry.rows), (3, 1, 3));
+}
@@ -1,8 +1,14 @@
+//! 服务端（调度任务、binlog 同步、HTTP 服务）需要默认启用的 server feature；
+//! 只引用 HTTP 客户端时使用 default-features = false, features = ["client"]
+
+#[cfg(feature = "server")]
 use anyhow::Result;
+#[cfg(feature = "server")]
 use std::any::type_name;
 // 定义一个 trait，用于所有可以被调度器执行的任务
 // 它们必须是 Send + Sync + 'static (线程安全，可在线程间移动，且生命周期静态)
 // 并且提供一个返回 Result<()> 的异步执行方法
+#[cfg(feature = "server")]
 #[async_trait::async_trait]
 pub trait TaskExecutor: Send + Sync + 'static {
     // 获取任务名称
@@ -13,25 +19,56 @@ pub trait TaskExecutor: Send + Sync + 'static {
     async fn execute(&self) -> Result<()>;
 }
 
+#[cfg(feature = "server")]
+pub mod alert_rules;
+#[cfg(feature = "server")]
 pub mod binlog;
+#[cfg(feature = "server")]
+pub mod build_info;
+#[cfg(feature = "client")]
+pub mod client;
+#[cfg(feature = "server")]
 pub mod config;
+#[cfg(feature = "server")]
+pub mod config_watcher;
+#[cfg(feature = "server")]
 pub mod context;
+#[cfg(feature = "server")]
 pub mod db;
+#[cfg(feature = "server")]
 pub mod logging;
+#[cfg(feature = "server")]
 pub mod mappers;
+pub mod messages;
+#[cfg(feature = "server")]
+pub mod metrics;
+#[cfg(feature = "server")]
 pub mod models;
+#[cfg(feature = "server")]
 pub mod parsers;
+#[cfg(feature = "server")]
 pub mod schedule;
+#[cfg(feature = "server")]
+pub mod shutdown;
+#[cfg(feature = "server")]
 pub mod utils;
 pub mod web;
 
-pub use models::train::{ClassData, DynamicPsnData, LecturerData, PsnDataKind};
+#[cfg(feature = "server")]
+pub use models::train::{ClassData, DynamicPsnData, LecturerData, PsnDataKind, PsnRecord};
+#[cfg(feature = "server")]
 pub use web::WebServer;
 
+#[cfg(feature = "server")]
 pub use config::{AppConfig, ClickhouseConfig, MssInfoConfig};
+#[cfg(feature = "server")]
 pub use mappers::archiving_mss_mapper::{ArchivingMssMapper, RecordMssReply};
+#[cfg(feature = "server")]
 pub use parsers::push_result_parser::PushResultParser;
 
+#[cfg(feature = "server")]
 pub use context::AppContext;
+#[cfg(feature = "server")]
 pub use context::RedisContext;
-pub use utils::mss_client::psn_dos_push;
+#[cfg(feature = "server")]
+pub use utils::mss_client::{psn_dos_push, psn_dos_push_batch};
@@ -0,0 +1,90 @@
+//! JSON 日志格式：每个事件输出一行 JSON，包含时间、级别、target、源码位置、线程、所在 span 和字段。
+//! span 从外到内排列，每个 span 带名称与格式化后的字段
+
+use std::fmt;
+
+use chrono::Local;
+use serde_json::{Map, Value, json};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::utils::timefmt;
+
+pub struct JsonFormat;
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        let mut fields = JsonFields::default();
+        event.record(&mut fields);
+        let spans: Vec<Value> = ctx
+            .event_scope()
+            .into_iter()
+            .flat_map(|scope| scope.from_root())
+            .map(|span| {
+                let fields = span
+                    .extensions()
+                    .get::<FormattedFields<N>>()
+                    .map(|fields| fields.fields.clone())
+                    .filter(|fields| !fields.is_empty());
+                json!({ "name": span.name(), "fields": fields })
+            })
+            .collect();
+        let thread = std::thread::current();
+        let line = json!({
+            "timestamp": Local::now().format(timefmt::LOG_DATETIME_FORMAT).to_string(),
+            "level": metadata.level().to_string(),
+            "target": metadata.target(),
+            "file": metadata.file(),
+            "line": metadata.line(),
+            "thread_id": format!("{:?}", thread.id()),
+            "thread_name": thread.name(),
+            "spans": spans,
+            "fields": fields.0,
+        });
+        writeln!(writer, "{line}")
+    }
+}
+
+/// 把事件字段收集为 JSON 对象，message 与其他字段并列
+#[derive(Default)]
+struct JsonFields(Map<String, Value>);
+
+impl Visit for JsonFields {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), json!(format!("{value:?}")));
+    }
+}
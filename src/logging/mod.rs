@@ -1,20 +1,105 @@
+mod json;
+
 use anyhow::{Context, Result};
 use chrono::Local;
 use logroller::{Compression, LogRollerBuilder, Rotation, RotationAge, TimeZone};
+use serde::Serialize;
 use std::fs::{self};
 use std::path::PathBuf;
 
 use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::fmt::MakeWriter;
 use tracing_subscriber::fmt::format::Writer;
 use tracing_subscriber::fmt::time::FormatTime;
-use tracing_subscriber::{self, filter::EnvFilter, fmt, prelude::*, util::SubscriberInitExt};
+use tracing_subscriber::{
+    self, Layer, Registry, filter::EnvFilter, fmt, prelude::*, reload, util::SubscriberInitExt,
+};
+
+use crate::config::{LogFormat, LoggingConfig};
+use crate::utils::timefmt;
+use json::JsonFormat;
 
 // 自定义本地时间格式
 pub struct LocalTimer;
 
 impl FormatTime for LocalTimer {
     fn format_time(&self, w: &mut Writer<'_>) -> std::fmt::Result {
-        write!(w, "{}", Local::now().format("%Y-%m-%d %H:%M:%S%.3f"))
+        write!(w, "{}", Local::now().format(timefmt::LOG_DATETIME_FORMAT))
+    }
+}
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// 当前生效的过滤规则，EnvFilter 语法
+#[derive(Debug, Clone, Serialize)]
+pub struct LogFilters {
+    pub console: String,
+    pub file: String,
+}
+
+/// 运行中修改控制台与文件层过滤规则的句柄
+#[derive(Clone)]
+pub struct LogFilterHandle {
+    console: reload::Handle<EnvFilter, Registry>,
+    file: reload::Handle<EnvFilter, Registry>,
+}
+
+impl LogFilterHandle {
+    pub fn current(&self) -> Result<LogFilters> {
+        Ok(LogFilters {
+            console: self
+                .console
+                .with_current(ToString::to_string)
+                .context("Failed to read console log filter")?,
+            file: self
+                .file
+                .with_current(ToString::to_string)
+                .context("Failed to read file log filter")?,
+        })
+    }
+
+    /// 替换过滤规则，None 的一侧保持不变。规则无效时两侧都不修改，重启后恢复为配置中的规则
+    pub fn reload(&self, console: Option<&str>, file: Option<&str>) -> Result<LogFilters> {
+        let parse = |directives: Option<&str>| {
+            directives
+                .map(|directives| {
+                    EnvFilter::try_new(directives)
+                        .with_context(|| format!("Invalid log filter: {directives}"))
+                })
+                .transpose()
+        };
+        let (console, file) = (parse(console)?, parse(file)?);
+        if let Some(filter) = console {
+            self.console
+                .reload(filter)
+                .context("Failed to reload console log filter")?;
+        }
+        if let Some(filter) = file {
+            self.file
+                .reload(filter)
+                .context("Failed to reload file log filter")?;
+        }
+        self.current()
+    }
+}
+
+// 按配置的格式创建输出层，JSON 格式不输出 ANSI 颜色
+fn format_layer<W>(format: LogFormat, ansi: bool, writer: W) -> BoxedLayer
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = fmt::layer()
+        .with_writer(writer)
+        .with_target(true)
+        .with_timer(LocalTimer) // 使用定义的本地时间格式
+        .with_thread_ids(true)
+        .with_thread_names(true)
+        .with_line_number(true)
+        .with_file(true)
+        .with_level(true);
+    match format {
+        LogFormat::Text => layer.with_ansi(ansi).boxed(),
+        LogFormat::Json => layer.with_ansi(false).event_format(JsonFormat).boxed(),
     }
 }
 
@@ -28,7 +113,8 @@ impl FormatTime for LocalTimer {
 /// - 控制台输出层，使用本地时间、线程ID/名称、文件名/行号和日志级别。
 /// - 文件输出层，使用 tracing-appender 按天轮转（文件名如 app.YYYY-MM-DD.log），并在初始化时压缩旧日志文件。
 /// - 注意：压缩使用 Gz 格式，仅在初始化时执行（不实时）。
-pub fn init_logging() -> Result<WorkerGuard> {
+/// - 两个输出层的级别、按模块过滤与格式来自 `LoggingConfig`，过滤规则可通过返回的句柄在运行中修改。
+pub fn init_logging(config: &LoggingConfig) -> Result<(WorkerGuard, LogFilterHandle)> {
     let log_dir = PathBuf::from("logs");
     fs::create_dir_all(&log_dir).context(format!("Failed to create log directory: {log_dir:?}"))?;
 
@@ -45,35 +131,33 @@ pub fn init_logging() -> Result<WorkerGuard> {
     // 创建非阻塞 writer（异步写入）
     let (non_blocking, guard) = tracing_appender::non_blocking(appender);
 
-    // 创建一个 fmt 层用于文件输出
-    let file_layer = fmt::layer()
-        .with_ansi(false) // 文件输出通常不需要 ANSI 颜色
-        .with_writer(non_blocking) // 使用 tracing-appender 的 writer
-        .with_target(true)
-        .with_timer(LocalTimer) // 使用定义的本地时间格式
-        .with_thread_ids(true)
-        .with_thread_names(true)
-        .with_line_number(true)
-        .with_file(true)
-        .with_level(true)
-        .with_filter(EnvFilter::new("info")); // 文件日志通常使用 info 级别
+    let file_filter = EnvFilter::try_new(config.file_directives())
+        .context("Invalid file log filter in logging config")?;
+    let console_filter = EnvFilter::try_new(config.console_directives())
+        .context("Invalid console log filter in logging config")?;
+    let (file_filter, file_handle) = reload::Layer::new(file_filter);
+    let (console_filter, console_handle) = reload::Layer::new(console_filter);
 
-    // 创建一个 fmt 层用于控制台输出
-    let stdout_layer = fmt::layer()
-        .with_ansi(true) // 控制台输出可以有颜色
-        .with_timer(LocalTimer)
-        .with_thread_ids(true)
-        .with_thread_names(true)
-        .with_line_number(true)
-        .with_file(true)
-        .with_level(true)
-        .with_filter(EnvFilter::new("debug")); // 控制台日志通常使用 debug 级别
+    // 文件输出层，文件输出不需要 ANSI 颜色
+    let file_layer = format_layer(config.file_format, false, non_blocking)
+        .with_filter(file_filter)
+        .boxed();
+
+    // 控制台输出层，控制台输出可以有颜色
+    let stdout_layer = format_layer(config.console_format, true, std::io::stdout)
+        .with_filter(console_filter)
+        .boxed();
 
     // 将两个层组合起来并初始化全局订阅者
     tracing_subscriber::registry()
-        .with(stdout_layer)
-        .with(file_layer)
+        .with(vec![stdout_layer, file_layer])
         .init();
 
-    Ok(guard)
+    Ok((
+        guard,
+        LogFilterHandle {
+            console: console_handle,
+            file: file_handle,
+        },
+    ))
 }
@@ -1,44 +1,181 @@
-use anyhow::{Context, Result};
-use servicekit::{logging, schedule::TaskSchedulerManager, AppConfig, AppContext, WebServer};
+use anyhow::{Context, Result, bail};
+use servicekit::binlog::fixture::export_fixture;
+use servicekit::build_info::BuildInfo;
+use servicekit::config_watcher::spawn_config_watcher;
+use servicekit::db::mysql_pool;
+use servicekit::models::legacy_import::import_legacy_results;
+use servicekit::models::task_run_history::TaskRunRecorder;
+use servicekit::schedule::binlog_sync::DataType;
+use servicekit::utils::redis::close_redis;
+use servicekit::utils::timefmt;
+use servicekit::{
+    AppConfig, AppContext, WebServer, logging, messages, schedule::TaskSchedulerManager, shutdown,
+};
 //servicekit是crate 名称（在 Cargo.toml 中定义），代表了库。logging,  WebServer 这些都是从 lib.rs 中 pub use 或 pub mod 导出的项。如果 lib.rs 不存在或者没有正确地导出这些模块，main.rs 将无法直接通过 servicekit:: 路径来访问它们
 use std::sync::Arc;
-use tracing::info;
+use tracing::{info, warn};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // 1. 初始化日志系统
-    // 主线程需持有guard，不然guard会在init_logging调用完后drop掉导致 worker 线程立即停止（不会写日志到文件中）
-    let _guard = logging::init_logging().context("Failed to initialize logging")?;
-    info!("Application starting...");
-
-    // 2. 加载应用程序配置
+    // 1. 加载应用程序配置，日志级别与格式来自配置
     let app_config = AppConfig::new().context("Failed to load application configuration")?;
+    messages::set_language(app_config.messages.language);
+
+    // 2. 初始化日志系统
+    // 主线程需持有guard，不然guard会在init_logging调用完后drop掉导致 worker 线程立即停止（不会写日志到文件中）
+    let (log_guard, log_filter) =
+        logging::init_logging(&app_config.logging).context("Failed to initialize logging")?;
+    info!("Application starting: {}", BuildInfo::current().banner());
     info!("Application configuration loaded successfully: {app_config:?}");
 
+    // 子命令：导出脱敏的实体数据、导入旧系统的推送结果后退出，不启动调度器与 Web 服务器
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("export-fixture") => return run_export_fixture(&app_config, &args[1..]).await,
+        Some("import-legacy-results") => {
+            return run_import_legacy_results(&app_config, &args[1..]).await;
+        }
+        _ => {}
+    }
+
     // 3. 创建AppContext实例
-    let app_context = AppContext::new(
-        &app_config.database_url,
-        Arc::clone(&app_config.mss_info_config),
-        Arc::clone(&app_config.telecom_config),
-        Arc::clone(&app_config.clickhouse_config),
-        Arc::clone(&app_config.redis_config),
-        app_config.provinces,
-    )
-    .await?;
+    let app_context = AppContext::new(&app_config).await?;
+    let _ = app_context.log_filter.set(log_filter);
     let app_context_arc = Arc::new(app_context);
 
     // 4. 初始化和启动任务调度器
-    let scheduler = TaskSchedulerManager::new().await?;
+    let mut scheduler = TaskSchedulerManager::new(
+        Arc::clone(&app_context_arc.shutdown),
+        TaskRunRecorder::new(app_context_arc.mysql_pool.clone())
+            .with_analytics(app_context_arc.analytics_exporter.clone()),
+    )
+    .await?;
     scheduler
         .initialize_tasks(Arc::clone(&app_context_arc), &app_config.tasks)
         .await?;
     scheduler.start().await;
 
-    // 5.启动 Web 服务器
-    let server = WebServer::new(app_config.web_server_port, Arc::clone(&app_context_arc));
-    server.start().await.context("Failed to start web server")?;
+    // 监听配置文件，热加载任务计划、日志过滤规则与限流。监听失败不影响启动
+    if app_config.config_watch.enabled
+        && let Err(e) = spawn_config_watcher(
+            Arc::clone(&app_context_arc),
+            app_config.clone(),
+            AppConfig::config_file(),
+        )
+    {
+        warn!("Config hot-reload is disabled: {e:?}");
+    }
+
+    // 5. 监听 SIGINT/SIGTERM，触发关闭
+    let shutdown_controller = Arc::clone(&app_context_arc.shutdown);
+    tokio::spawn(async move {
+        shutdown::wait_for_signal().await;
+        shutdown_controller.trigger();
+    });
+
+    // 6.启动 Web 服务器，关闭触发后停止
+    let server = WebServer::new(
+        app_config.web_server_host.as_str(),
+        app_config.web_server_port,
+        Arc::clone(&app_context_arc),
+    );
+    let server_result = server.start().await.context("Failed to start web server");
+    // Web 服务器异常退出时同样走关闭流程
+    app_context_arc.shutdown.trigger();
+
+    // 7. 停止触发新任务，等待运行中的任务结束
+    scheduler.shutdown().await;
+    let drain_timeout = app_config.timeouts.shutdown_drain;
+    if !app_context_arc.shutdown.drain(drain_timeout).await {
+        warn!("Some tasks did not finish within {drain_timeout:?}, shutting down anyway.");
+    }
+
+    // 8. 刷新尚未写入的推送结果，关闭 MySQL 连接池
+    app_context_arc.push_result_writer.shutdown().await;
+    app_context_arc.mysql_pool.close().await;
+
+    // 9. 关闭 ClickHouse 连接池与 Redis 连接，运行中的任务已在上一步结束
+    app_context_arc.clickhouse_client.close();
+    if let Err(e) = close_redis(&app_context_arc.redis_mgr).await {
+        warn!("Failed to close Redis connection: {e:?}");
+    }
 
     info!("Application shut down cleanly.");
+    // 10. 最后释放日志 guard，确保关闭过程的日志写入文件
+    drop(log_guard);
+
+    server_result
+}
+
+// 每个实体导出的落库快照与死信日志条数
+const FIXTURE_HISTORY_LIMIT: u32 = 5;
+
+/// servicekit export-fixture <org|user> <实体ID> [输出文件]
+async fn run_export_fixture(app_config: &AppConfig, args: &[String]) -> Result<()> {
+    let (data_type, entity_id) = match args {
+        [data_type, entity_id, ..] => (data_type, entity_id),
+        _ => bail!("Usage: servicekit export-fixture <org|user> <entity_id> [output.json]"),
+    };
+    let data_type: DataType = serde_json::from_value(serde_json::Value::String(data_type.clone()))
+        .with_context(|| format!("Unknown data type: {data_type}"))?;
+    let output = args
+        .get(2)
+        .cloned()
+        .unwrap_or_else(|| format!("fixture-{}-{entity_id}.json", data_type.as_label()));
+
+    let pool = mysql_pool::create_mysql_pool(
+        app_config.database_url.expose(),
+        app_config.timeouts.mysql_acquire,
+    )
+    .await
+    .context("Failed to create database connection mysql_pool")?;
+    let fixture = export_fixture(
+        &pool,
+        &app_config.persistence_policy,
+        data_type,
+        entity_id,
+        FIXTURE_HISTORY_LIMIT,
+    )
+    .await?;
+    pool.close().await;
+
+    let json = serde_json::to_string_pretty(&fixture).context("Failed to serialize fixture")?;
+    std::fs::write(&output, json)
+        .with_context(|| format!("Failed to write fixture to {output}"))?;
+    info!(
+        "Exported anonymized {} fixture for {entity_id} to {output} ({} tables).",
+        data_type.as_label(),
+        fixture.tables.len()
+    );
+    Ok(())
+}
+
+/// servicekit import-legacy-results <开始日期> <结束日期>，日期为 yyyy-MM-dd，均包含
+async fn run_import_legacy_results(app_config: &AppConfig, args: &[String]) -> Result<()> {
+    let (begin_date, end_date) = match args {
+        [begin_date, end_date, ..] => (
+            timefmt::parse_business_date(begin_date)
+                .with_context(|| format!("Invalid begin_date: {begin_date}"))?,
+            timefmt::parse_business_date(end_date)
+                .with_context(|| format!("Invalid end_date: {end_date}"))?,
+        ),
+        _ => bail!("Usage: servicekit import-legacy-results <begin_date> <end_date>"),
+    };
+
+    let pool = mysql_pool::create_mysql_pool(
+        app_config.database_url.expose(),
+        app_config.timeouts.mysql_acquire,
+    )
+    .await
+    .context("Failed to create database connection mysql_pool")?;
+    let result =
+        import_legacy_results(&pool, &app_config.legacy_import, begin_date, end_date).await;
+    pool.close().await;
 
+    let summary = result?;
+    info!(
+        "Imported legacy push results from {begin_date} to {end_date}: scanned {}, imported {} ({} details), skipped {} existing.",
+        summary.scanned, summary.imported, summary.details, summary.skipped
+    );
     Ok(())
 }
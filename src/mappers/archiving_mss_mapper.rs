@@ -1,7 +1,15 @@
+use std::sync::Arc;
+
 use anyhow::{Context, Result};
 use serde::Serialize;
 use sqlx::MySqlPool;
-use tracing::info;
+use tracing::{info, warn};
+
+use crate::db::query_runner::QueryRunner;
+use crate::utils::clock::Clock;
+use crate::utils::timefmt;
+
+use super::reply_store::{ReplyBodyStore, sha256_hex};
 
 #[derive(Debug, Clone, Serialize)] // Serialize for eventual logging/db storage if needed
 pub struct RecordMssReply {
@@ -14,18 +22,64 @@ pub struct RecordMssReply {
 // 模拟数据库 mapper
 pub struct ArchivingMssMapper {
     mysql_pool: MySqlPool, // ArchivingMssMapper 现在持有数据库连接池
+    reply_store: Option<Arc<dyn ReplyBodyStore>>, // 配置了外部存储时报文写到这里
+    clock: Arc<dyn Clock>, // 报文的发送时间与存储路径中的日期按它计算
 }
 
 impl ArchivingMssMapper {
-    pub fn new(mysql_pool: MySqlPool) -> Self {
-        ArchivingMssMapper { mysql_pool }
+    pub fn new(
+        mysql_pool: MySqlPool,
+        reply_store: Option<Arc<dyn ReplyBodyStore>>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        ArchivingMssMapper {
+            mysql_pool,
+            reply_store,
+            clock,
+        }
+    }
+
+    pub fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
     }
 
     pub async fn record_mss_reply(&self, reply: &RecordMssReply) -> Result<()> {
+        let Some(store) = &self.reply_store else {
+            return self.insert_record(reply).await;
+        };
+        // 完整报文写到外部存储，数据库中 datas 保存报文地址，msg 保存报文哈希
+        let body = serde_json::to_vec(reply).context("Failed to serialize RecordMssReply")?;
+        let key = format!(
+            "{}/{}.json",
+            timefmt::business_date(self.clock.today()),
+            reply.id
+        );
+        match store.put(&key, &body).await {
+            Ok(location) => {
+                let pointer = RecordMssReply {
+                    id: reply.id.clone(),
+                    datas: location,
+                    send_time: reply.send_time.clone(),
+                    msg: format!("sha256:{}", sha256_hex(&body)),
+                };
+                self.insert_record(&pointer).await
+            }
+            Err(e) => {
+                // 外部存储不可用时退回到直接写数据库，避免丢失报文
+                warn!(
+                    "Failed to archive MSS reply {} externally, storing inline: {e:?}",
+                    reply.id
+                );
+                self.insert_record(reply).await
+            }
+        }
+    }
+
+    async fn insert_record(&self, reply: &RecordMssReply) -> Result<()> {
         info!("Recording MSS reply to DB, ID: {:?}", reply.id);
         // 使用 sqlx::query! 或 sqlx::query_as! 进行插入
         // 这里是关键：明确指定数据库列名
-        sqlx::query!(
+        let query = sqlx::query!(
             r#"
             INSERT INTO data_archiving_mss_record (id, msg, datas, sendTime)
             VALUES (?, ?, ?, ?)
@@ -34,10 +88,11 @@ impl ArchivingMssMapper {
             reply.msg,
             reply.datas,
             reply.send_time
-        )
-        .execute(&self.mysql_pool)
-        .await
-        .context("Failed to insert RecordMssReply into data_archiving_mss_record")?;
+        );
+        QueryRunner::new("archiving_mss_record_insert")
+            .run(query.execute(&self.mysql_pool))
+            .await
+            .context("Failed to insert RecordMssReply into data_archiving_mss_record")?;
 
         Ok(())
     }
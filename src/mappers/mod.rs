@@ -1 +1,2 @@
 pub mod archiving_mss_mapper;
+pub mod reply_store;
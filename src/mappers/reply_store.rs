@@ -0,0 +1,177 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result, anyhow};
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Url};
+use sha2::{Digest, Sha256};
+
+use crate::config::{ReplyArchiveBackend, ReplyArchiveConfig, S3Config};
+
+/// MSS 请求/响应报文的外部存储。
+/// 配置了外部存储时，data_archiving_mss_record 只保留报文位置与哈希
+#[async_trait]
+pub trait ReplyBodyStore: Send + Sync {
+    /// 保存报文，返回可定位该报文的地址（file://... 或 s3://...）
+    async fn put(&self, key: &str, body: &[u8]) -> Result<String>;
+}
+
+/// 根据配置创建外部存储，backend 为 mysql 时返回 None（报文直接写入数据库）
+pub fn build_reply_store(
+    config: &ReplyArchiveConfig,
+    http_client: Client,
+) -> Result<Option<Arc<dyn ReplyBodyStore>>> {
+    let store: Arc<dyn ReplyBodyStore> = match config.backend {
+        ReplyArchiveBackend::Mysql => return Ok(None),
+        ReplyArchiveBackend::Local => {
+            if config.local_dir.is_empty() {
+                return Err(anyhow!(
+                    "reply_archive_config.local_dir is required for local backend"
+                ));
+            }
+            Arc::new(LocalDirStore::new(PathBuf::from(&config.local_dir)))
+        }
+        ReplyArchiveBackend::S3 => Arc::new(S3Store::new(http_client, config.s3.clone())?),
+    };
+    Ok(Some(store))
+}
+
+pub fn sha256_hex(body: &[u8]) -> String {
+    hex::encode(Sha256::digest(body))
+}
+
+pub struct LocalDirStore {
+    root: PathBuf,
+}
+
+impl LocalDirStore {
+    pub fn new(root: PathBuf) -> Self {
+        LocalDirStore { root }
+    }
+}
+
+#[async_trait]
+impl ReplyBodyStore for LocalDirStore {
+    async fn put(&self, key: &str, body: &[u8]) -> Result<String> {
+        let path = self.root.join(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .context(format!("Failed to create directory {}", parent.display()))?;
+        }
+        tokio::fs::write(&path, body)
+            .await
+            .context(format!("Failed to write reply body to {}", path.display()))?;
+        Ok(format!("file://{}", path.display()))
+    }
+}
+
+/// 兼容 S3 协议的对象存储（path-style 地址，AWS SigV4 签名）
+pub struct S3Store {
+    http_client: Client,
+    config: S3Config,
+    host: String,
+}
+
+impl S3Store {
+    pub fn new(http_client: Client, config: S3Config) -> Result<Self> {
+        let url = Url::parse(&config.endpoint)
+            .context(format!("Invalid S3 endpoint: {}", config.endpoint))?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err(anyhow!("S3 endpoint has no host: {}", config.endpoint)),
+        };
+        if config.bucket.is_empty() {
+            return Err(anyhow!(
+                "reply_archive_config.s3.bucket is required for s3 backend"
+            ));
+        }
+        Ok(S3Store {
+            http_client,
+            config,
+            host,
+        })
+    }
+
+    fn object_key(&self, key: &str) -> String {
+        let prefix = self.config.prefix.trim_matches('/');
+        if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{prefix}/{key}")
+        }
+    }
+
+    fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+        mac.update(data.as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    /// 计算 SigV4 的 Authorization 头
+    fn authorization(&self, path: &str, amz_date: &str, payload_hash: &str) -> String {
+        let date = &amz_date[..8];
+        let region = &self.config.region;
+        let scope = format!("{date}/{region}/s3/aws4_request");
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "PUT\n{path}\n\nhost:{}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n\n{signed_headers}\n{payload_hash}",
+            self.host
+        );
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            sha256_hex(canonical_request.as_bytes())
+        );
+
+        let k_date = Self::hmac(
+            format!("AWS4{}", self.config.secret_key.expose()).as_bytes(),
+            date,
+        );
+        let k_region = Self::hmac(&k_date, region);
+        let k_service = Self::hmac(&k_region, "s3");
+        let k_signing = Self::hmac(&k_service, "aws4_request");
+        let signature = hex::encode(Self::hmac(&k_signing, &string_to_sign));
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+            self.config.access_key.expose()
+        )
+    }
+}
+
+#[async_trait]
+impl ReplyBodyStore for S3Store {
+    async fn put(&self, key: &str, body: &[u8]) -> Result<String> {
+        let object_key = self.object_key(key);
+        let path = format!("/{}/{object_key}", self.config.bucket);
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = sha256_hex(body);
+
+        let response = self
+            .http_client
+            .put(format!(
+                "{}{path}",
+                self.config.endpoint.trim_end_matches('/')
+            ))
+            .header("x-amz-date", &amz_date)
+            .header("x-amz-content-sha256", &payload_hash)
+            .header(
+                "Authorization",
+                self.authorization(&path, &amz_date, &payload_hash),
+            )
+            .body(body.to_vec())
+            .send()
+            .await
+            .context("Failed to send reply body to S3")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("S3 put failed: Status={status}, Body={body}"));
+        }
+        Ok(format!("s3://{}/{object_key}", self.config.bucket))
+    }
+}
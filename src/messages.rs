@@ -0,0 +1,421 @@
+//! 面向运维与接口调用方的提示信息目录。每条信息有固定的英文编码，日志告警与调用方按编码匹配，
+//! 不受语言影响；描述按 [messages] language 输出英文或中文，默认英文。
+//! 输出形式为 `CODE: 描述`，如 `DATE_BEGIN_AFTER_END: begin date 2024-05-02 is after end date 2024-05-01`
+
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use serde::Deserialize;
+
+/// 描述使用的语言
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    Zh,
+}
+
+// 当前语言，启动时与配置热加载时设置
+static LANGUAGE: AtomicU8 = AtomicU8::new(Language::En as u8);
+
+pub fn set_language(language: Language) {
+    LANGUAGE.store(language as u8, Ordering::Relaxed);
+}
+
+pub fn language() -> Language {
+    match LANGUAGE.load(Ordering::Relaxed) {
+        value if value == Language::Zh as u8 => Language::Zh,
+        _ => Language::En,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    DateInvalidFormat {
+        value: String,
+    },
+    DateBeginAfterEnd {
+        begin: String,
+        end: String,
+    },
+    DateSpecialMonthMismatch {
+        begin: String,
+        end: String,
+    },
+    DateMixedSpecialMonth {
+        begin: String,
+        end: String,
+    },
+    PushParamsConflict,
+    PushParamsMissing,
+    PushDateRangeIncomplete,
+    /// rejected 为被拒绝的 ID 及原因
+    NoValidTrainIds {
+        rejected: String,
+    },
+    NoDatesToProcess,
+    /// 进程正在关闭，不再接受新的后台作业
+    ShuttingDown,
+    UnknownDataKind {
+        kind: String,
+    },
+    // binlog 接口，error 为失败原因
+    BinlogDeadLettersQueryFailed {
+        error: String,
+    },
+    BinlogSyncRunning,
+    BinlogLockFailed {
+        error: String,
+    },
+    BinlogRequeueFailed {
+        error: String,
+    },
+    // 管理接口
+    SchemaTableNotAllowed {
+        table: String,
+    },
+    SchemaQueryFailed {
+        table: String,
+    },
+    SchemaTableNotFound {
+        table: String,
+    },
+    AlertRulesNotReady,
+    CronSchedulesNotReady,
+    CronExpandFailed {
+        error: String,
+    },
+    AuditQueryFailed {
+        error: String,
+    },
+    LockInspectFailed {
+        key: String,
+        error: String,
+    },
+    LockUnknown {
+        key: String,
+    },
+    LockNotHeld {
+        key: String,
+    },
+    LockClearFailed {
+        key: String,
+        error: String,
+    },
+    ReconcileDateInvalid {
+        value: String,
+    },
+    ReconcileFailed {
+        error: String,
+    },
+    NotifyStatusUnsupported {
+        kind: String,
+    },
+    /// expected 为可选的状态值
+    NotifyStatusInvalid {
+        value: String,
+        expected: String,
+    },
+    NotifyStatusIdsOutOfRange {
+        max: usize,
+    },
+    NotifyStatusSetFailed {
+        error: String,
+    },
+    DigestTitle {
+        date: String,
+    },
+    DigestEmpty,
+    /// binlog 日报中一种数据类型的统计，延迟单位为秒
+    DigestEntry {
+        data_type: String,
+        logs: u32,
+        succeeded: u32,
+        failed: u32,
+        exhausted: u32,
+        batches: u32,
+        avg_batch_size: f64,
+        max_batch_size: u32,
+        save_failures: u32,
+        avg_lag_secs: f64,
+        max_lag_secs: f64,
+    },
+}
+
+impl Message {
+    /// 固定的英文编码
+    pub fn code(&self) -> &'static str {
+        match self {
+            Message::DateInvalidFormat { .. } => "DATE_INVALID_FORMAT",
+            Message::DateBeginAfterEnd { .. } => "DATE_BEGIN_AFTER_END",
+            Message::DateSpecialMonthMismatch { .. } => "DATE_SPECIAL_MONTH_MISMATCH",
+            Message::DateMixedSpecialMonth { .. } => "DATE_MIXED_SPECIAL_MONTH",
+            Message::PushParamsConflict => "PUSH_PARAMS_CONFLICT",
+            Message::PushParamsMissing => "PUSH_PARAMS_MISSING",
+            Message::PushDateRangeIncomplete => "PUSH_DATE_RANGE_INCOMPLETE",
+            Message::NoValidTrainIds { .. } => "NO_VALID_TRAIN_IDS",
+            Message::NoDatesToProcess => "NO_DATES_TO_PROCESS",
+            Message::ShuttingDown => "SHUTTING_DOWN",
+            Message::UnknownDataKind { .. } => "UNKNOWN_DATA_KIND",
+            Message::BinlogDeadLettersQueryFailed { .. } => "BINLOG_DEAD_LETTERS_QUERY_FAILED",
+            Message::BinlogSyncRunning => "BINLOG_SYNC_RUNNING",
+            Message::BinlogLockFailed { .. } => "BINLOG_LOCK_FAILED",
+            Message::BinlogRequeueFailed { .. } => "BINLOG_REQUEUE_FAILED",
+            Message::SchemaTableNotAllowed { .. } => "SCHEMA_TABLE_NOT_ALLOWED",
+            Message::SchemaQueryFailed { .. } => "SCHEMA_QUERY_FAILED",
+            Message::SchemaTableNotFound { .. } => "SCHEMA_TABLE_NOT_FOUND",
+            Message::AlertRulesNotReady => "ALERT_RULES_NOT_READY",
+            Message::CronSchedulesNotReady => "CRON_SCHEDULES_NOT_READY",
+            Message::CronExpandFailed { .. } => "CRON_EXPAND_FAILED",
+            Message::AuditQueryFailed { .. } => "AUDIT_QUERY_FAILED",
+            Message::LockInspectFailed { .. } => "LOCK_INSPECT_FAILED",
+            Message::LockUnknown { .. } => "LOCK_UNKNOWN",
+            Message::LockNotHeld { .. } => "LOCK_NOT_HELD",
+            Message::LockClearFailed { .. } => "LOCK_CLEAR_FAILED",
+            Message::ReconcileDateInvalid { .. } => "RECONCILE_DATE_INVALID",
+            Message::ReconcileFailed { .. } => "RECONCILE_FAILED",
+            Message::NotifyStatusUnsupported { .. } => "NOTIFY_STATUS_UNSUPPORTED",
+            Message::NotifyStatusInvalid { .. } => "NOTIFY_STATUS_INVALID",
+            Message::NotifyStatusIdsOutOfRange { .. } => "NOTIFY_STATUS_IDS_OUT_OF_RANGE",
+            Message::NotifyStatusSetFailed { .. } => "NOTIFY_STATUS_SET_FAILED",
+            Message::DigestTitle { .. } => "BINLOG_DIGEST_TITLE",
+            Message::DigestEmpty => "BINLOG_DIGEST_EMPTY",
+            Message::DigestEntry { .. } => "BINLOG_DIGEST_ENTRY",
+        }
+    }
+
+    /// 指定语言的描述，不含编码
+    pub fn describe(&self, language: Language) -> String {
+        match language {
+            Language::En => self.describe_en(),
+            Language::Zh => self.describe_zh(),
+        }
+    }
+
+    /// 当前语言的描述，不含编码
+    pub fn text(&self) -> String {
+        self.describe(language())
+    }
+
+    fn describe_en(&self) -> String {
+        match self {
+            Message::DateInvalidFormat { value } => {
+                format!("invalid date {value}, expected YYYY-MM-DD or YYYY-MM")
+            }
+            Message::DateBeginAfterEnd { begin, end } => {
+                format!("begin date {begin} is after end date {end}")
+            }
+            Message::DateSpecialMonthMismatch { begin, end } => {
+                format!("special month dates must be in the same year and month: {begin} or {end}")
+            }
+            Message::DateMixedSpecialMonth { begin, end } => {
+                format!("special month dates cannot be mixed with regular dates: {begin} or {end}")
+            }
+            Message::PushParamsConflict => {
+                "cannot provide both date range (begin_date/end_date) and train_ids".to_string()
+            }
+            Message::PushParamsMissing => {
+                "must provide either a date range (begin_date/end_date) or train_ids".to_string()
+            }
+            Message::PushDateRangeIncomplete => {
+                "both begin_date and end_date must be provided if using date range".to_string()
+            }
+            Message::NoValidTrainIds { rejected } => {
+                format!("no valid train_ids provided, rejected: {rejected}")
+            }
+            Message::NoDatesToProcess => "no dates to process in the date range".to_string(),
+            Message::ShuttingDown => "service is shutting down, try again later".to_string(),
+            Message::UnknownDataKind { kind } => format!("unknown data kind {kind}"),
+            Message::BinlogDeadLettersQueryFailed { error } => {
+                format!("failed to query binlog dead letters: {error}")
+            }
+            Message::BinlogSyncRunning => {
+                "binlog sync is running, retry the requeue later".to_string()
+            }
+            Message::BinlogLockFailed { error } => {
+                format!("failed to acquire binlog sync lock: {error}")
+            }
+            Message::BinlogRequeueFailed { error } => {
+                format!("failed to requeue dead letters: {error}")
+            }
+            Message::SchemaTableNotAllowed { table } => {
+                format!("table {table} is not in the schema allowlist")
+            }
+            Message::SchemaQueryFailed { table } => {
+                format!("failed to query columns of table {table}")
+            }
+            Message::SchemaTableNotFound { table } => format!("table {table} does not exist"),
+            Message::AlertRulesNotReady => {
+                "alert rules are generated after the scheduler registers its tasks".to_string()
+            }
+            Message::CronSchedulesNotReady => {
+                "cron schedules are available after the scheduler registers its tasks".to_string()
+            }
+            Message::CronExpandFailed { error } => {
+                format!("failed to expand cron schedules: {error}")
+            }
+            Message::AuditQueryFailed { error } => format!("failed to query admin audit: {error}"),
+            Message::LockInspectFailed { key, error } => {
+                format!("failed to inspect lock {key}: {error}")
+            }
+            Message::LockUnknown { key } => format!("lock {key} is not a known service lock"),
+            Message::LockNotHeld { key } => {
+                format!("lock {key} is not held with the given token")
+            }
+            Message::LockClearFailed { key, error } => {
+                format!("failed to clear lock {key}: {error}")
+            }
+            Message::ReconcileDateInvalid { value } => {
+                format!("invalid date {value}, expected YYYY-MM-DD")
+            }
+            Message::ReconcileFailed { error } => {
+                format!("failed to reconcile ClickHouse statuses: {error}")
+            }
+            Message::NotifyStatusUnsupported { kind } => {
+                format!("data kind {kind} has no ClickHouse notify status")
+            }
+            Message::NotifyStatusInvalid { value, expected } => {
+                format!("invalid status {value}, expected one of {expected}")
+            }
+            Message::NotifyStatusIdsOutOfRange { max } => {
+                format!("ids must contain 1 to {max} non-empty IDs")
+            }
+            Message::NotifyStatusSetFailed { error } => {
+                format!("failed to set ClickHouse notify status: {error}")
+            }
+            Message::DigestTitle { date } => format!("binlog sync digest {date}"),
+            Message::DigestEmpty => "no sync records".to_string(),
+            Message::DigestEntry {
+                data_type,
+                logs,
+                succeeded,
+                failed,
+                exhausted,
+                batches,
+                avg_batch_size,
+                max_batch_size,
+                save_failures,
+                avg_lag_secs,
+                max_lag_secs,
+            } => format!(
+                "[{data_type}] {logs} logs ({succeeded} succeeded, {failed} failed, {exhausted} exhausted), \
+                 {batches} batches (avg {avg_batch_size:.1}, max {max_batch_size}), \
+                 {save_failures} failed saves, avg lag {avg_lag_secs:.1}s, max lag {max_lag_secs:.1}s"
+            ),
+        }
+    }
+
+    fn describe_zh(&self) -> String {
+        match self {
+            Message::DateInvalidFormat { value } => {
+                format!("日期格式无效：{value}，应为 YYYY-MM-DD 或 YYYY-MM")
+            }
+            Message::DateBeginAfterEnd { begin, end } => {
+                format!("起始日期 {begin} 晚于结束日期 {end}")
+            }
+            Message::DateSpecialMonthMismatch { begin, end } => {
+                format!("特殊月份的起止日期必须属于同一年同一月：{begin} 或 {end}")
+            }
+            Message::DateMixedSpecialMonth { begin, end } => {
+                format!("特殊月份日期与普通日期不能混用：{begin} 或 {end}")
+            }
+            Message::PushParamsConflict => {
+                "不能同时提供日期范围（begin_date/end_date）与 train_ids".to_string()
+            }
+            Message::PushParamsMissing => {
+                "必须提供日期范围（begin_date/end_date）或 train_ids".to_string()
+            }
+            Message::PushDateRangeIncomplete => {
+                "按日期范围推送时 begin_date 与 end_date 都必须提供".to_string()
+            }
+            Message::NoValidTrainIds { rejected } => {
+                format!("没有有效的 train_ids，被拒绝的 ID：{rejected}")
+            }
+            Message::NoDatesToProcess => "日期范围内没有要处理的日期".to_string(),
+            Message::ShuttingDown => "服务正在关闭，请稍后重试".to_string(),
+            Message::UnknownDataKind { kind } => format!("未知的数据类型：{kind}"),
+            Message::BinlogDeadLettersQueryFailed { error } => {
+                format!("查询 binlog 死信失败：{error}")
+            }
+            Message::BinlogSyncRunning => "binlog 同步正在执行，请稍后重新入队".to_string(),
+            Message::BinlogLockFailed { error } => format!("获取 binlog 同步锁失败：{error}"),
+            Message::BinlogRequeueFailed { error } => format!("死信重新入队失败：{error}"),
+            Message::SchemaTableNotAllowed { table } => format!("表 {table} 不在允许查询的列表中"),
+            Message::SchemaQueryFailed { table } => format!("查询表 {table} 的字段失败"),
+            Message::SchemaTableNotFound { table } => format!("表 {table} 不存在"),
+            Message::AlertRulesNotReady => "调度器注册任务后才会生成告警规则".to_string(),
+            Message::CronSchedulesNotReady => "调度器注册任务后才能查看执行计划".to_string(),
+            Message::CronExpandFailed { error } => format!("展开执行计划失败：{error}"),
+            Message::AuditQueryFailed { error } => format!("查询操作审计失败：{error}"),
+            Message::LockInspectFailed { key, error } => format!("查看锁 {key} 失败：{error}"),
+            Message::LockUnknown { key } => format!("{key} 不是本服务使用的锁"),
+            Message::LockNotHeld { key } => format!("锁 {key} 未被指定的 token 持有"),
+            Message::LockClearFailed { key, error } => format!("清除锁 {key} 失败：{error}"),
+            Message::ReconcileDateInvalid { value } => {
+                format!("日期格式无效：{value}，应为 YYYY-MM-DD")
+            }
+            Message::ReconcileFailed { error } => format!("对账 ClickHouse 状态失败：{error}"),
+            Message::NotifyStatusUnsupported { kind } => {
+                format!("数据类型 {kind} 没有 ClickHouse 推送状态")
+            }
+            Message::NotifyStatusInvalid { value, expected } => {
+                format!("状态值无效：{value}，应为 {expected} 之一")
+            }
+            Message::NotifyStatusIdsOutOfRange { max } => {
+                format!("ids 必须包含 1 到 {max} 个非空 ID")
+            }
+            Message::NotifyStatusSetFailed { error } => {
+                format!("设置 ClickHouse 推送状态失败：{error}")
+            }
+            Message::DigestTitle { date } => format!("binlog 同步日报 {date}"),
+            Message::DigestEmpty => "无同步记录".to_string(),
+            Message::DigestEntry {
+                data_type,
+                logs,
+                succeeded,
+                failed,
+                exhausted,
+                batches,
+                avg_batch_size,
+                max_batch_size,
+                save_failures,
+                avg_lag_secs,
+                max_lag_secs,
+            } => format!(
+                "[{data_type}] 日志 {logs} 条（成功 {succeeded}，失败 {failed}，重试用尽 {exhausted}），\
+                 批次 {batches}（平均 {avg_batch_size:.1}，最大 {max_batch_size}），落库失败 {save_failures} 批，\
+                 平均延迟 {avg_lag_secs:.1}s，最大延迟 {max_lag_secs:.1}s"
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code(), self.text())
+    }
+}
+
+#[test]
+fn test_message_catalog() {
+    let message = Message::DateBeginAfterEnd {
+        begin: "2024-05-02".to_string(),
+        end: "2024-05-01".to_string(),
+    };
+    assert_eq!(message.code(), "DATE_BEGIN_AFTER_END");
+    assert_eq!(
+        message.describe(Language::En),
+        "begin date 2024-05-02 is after end date 2024-05-01"
+    );
+    assert_eq!(
+        message.describe(Language::Zh),
+        "起始日期 2024-05-02 晚于结束日期 2024-05-01"
+    );
+    // 默认英文
+    assert_eq!(language(), Language::En);
+    assert_eq!(
+        message.to_string(),
+        "DATE_BEGIN_AFTER_END: begin date 2024-05-02 is after end date 2024-05-01"
+    );
+}
@@ -0,0 +1,460 @@
+use std::sync::LazyLock;
+
+use prometheus::{
+    HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+
+/// 进程内唯一的指标注册表
+pub static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::new);
+
+/// 出站 HTTP 请求耗时，按目标和状态码区分
+pub static HTTP_CLIENT_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(HistogramVec::new(
+        HistogramOpts::new(
+            "http_client_request_duration_seconds",
+            "Latency of outbound HTTP requests",
+        )
+        .buckets(vec![0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]),
+        &["target", "status"],
+    ))
+});
+
+/// 出站请求因限流等待的时长，只记录需要等待的请求
+pub static RATE_LIMIT_WAIT_SECONDS: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(HistogramVec::new(
+        HistogramOpts::new(
+            "rate_limit_wait_seconds",
+            "Time outbound requests waited for a rate limiter token",
+        )
+        .buckets(vec![0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 30.0, 60.0]),
+        &["target"],
+    ))
+});
+
+/// 出站 HTTP 请求次数，outcome 为 ok / http_error / transport_error
+pub static HTTP_CLIENT_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new("http_client_requests_total", "Outbound HTTP requests"),
+        &["target", "outcome"],
+    ))
+});
+
+/// binlog 分页非正常结束次数，reason 为 fetch_failed / repeated_page / empty_pages / page_cap
+pub static BINLOG_PAGINATION_ABORTED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "binlog_pagination_aborted_total",
+            "Binlog pagination loops that terminated abnormally",
+        ),
+        &["data_type", "reason"],
+    ))
+});
+
+/// binlog 缺口补拉重新处理的窗口数
+pub static BINLOG_GAPS_REPLAYED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "binlog_gaps_replayed_total",
+            "Binlog windows replayed by the gap detector",
+        ),
+        &["data_type"],
+    ))
+});
+
+/// mc_* 表刷新语句与表结构不兼容的次数，按目标表区分
+pub static BINLOG_REFRESH_INCOMPATIBLE: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "binlog_refresh_incompatible_total",
+            "mc_* refresh queries rejected because of schema incompatibility",
+        ),
+        &["table"],
+    ))
+});
+
+/// binlog 从源数据修改到处理完成的延迟，按数据类型和优先级区分
+pub static BINLOG_PROCESSING_LAG: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(HistogramVec::new(
+        HistogramOpts::new(
+            "binlog_processing_lag_seconds",
+            "Delay between a binlog modification and the end of its processing",
+        )
+        .buckets(vec![
+            1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0,
+        ]),
+        &["data_type", "priority"],
+    ))
+});
+
+/// binlog 日志推进的条数，outcome 为 completed / retry / permanent / degraded
+pub static BINLOG_LOGS_ADVANCED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "binlog_logs_advanced_total",
+            "Binlog logs advanced through the processing state machine",
+        ),
+        &["data_type", "outcome"],
+    ))
+});
+
+/// 一轮并发推进一批 binlog 日志的耗时
+pub static BINLOG_ADVANCE_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(HistogramVec::new(
+        HistogramOpts::new(
+            "binlog_advance_duration_seconds",
+            "Time spent advancing one round of binlog logs",
+        )
+        .buckets(vec![
+            0.1, 0.5, 1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0,
+        ]),
+        &["data_type"],
+    ))
+});
+
+/// 熔断器当前状态：0 关闭，1 打开，2 半开
+pub static CIRCUIT_BREAKER_STATE: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register(IntGaugeVec::new(
+        Opts::new(
+            "circuit_breaker_state",
+            "Circuit breaker state: 0 closed, 1 open, 2 half-open",
+        ),
+        &["name"],
+    ))
+});
+
+/// 熔断器状态切换次数，state 为切换后的状态
+pub static CIRCUIT_BREAKER_TRANSITIONS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "circuit_breaker_transitions_total",
+            "Circuit breaker state transitions",
+        ),
+        &["name", "state"],
+    ))
+});
+
+/// ClickHouse 节点表结构与预期不一致时为 1，巡检通过后恢复为 0
+pub static CLICKHOUSE_SCHEMA_DIVERGENT: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register(IntGaugeVec::new(
+        Opts::new(
+            "clickhouse_schema_divergent",
+            "ClickHouse nodes whose table is missing expected columns",
+        ),
+        &["node", "table"],
+    ))
+});
+
+/// 推送记录数，outcome 为 success / failed / deferred
+pub static PUSH_RECORDS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new("push_records_total", "Records pushed to MSS by push tasks"),
+        &["task", "outcome"],
+    ))
+});
+
+/// 最近一次回写核对时 trainNotifyMss 仍为 pending / NULL 的行数，store 为 mysql / clickhouse
+pub static PUSH_STATUS_RESIDUE: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register(IntGaugeVec::new(
+        Opts::new(
+            "push_status_residue",
+            "Pushed rows whose trainNotifyMss is still pending after the status write-back",
+        ),
+        &["store", "table"],
+    ))
+});
+
+/// 回写核对的残留行数超过阈值的次数
+pub static PUSH_STATUS_RESIDUE_EXCEEDED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "push_status_residue_exceeded_total",
+            "Status write-back verifications whose residue exceeded the threshold",
+        ),
+        &["store", "table"],
+    ))
+});
+
+/// 推送结束后核对时 ClickHouse 中状态与 MySQL 不一致的行数
+pub static PUSH_STATUS_MISMATCH: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "push_status_mismatch_total",
+            "Rows whose trainNotifyMss in ClickHouse differs from MySQL after a push",
+        ),
+        &["table"],
+    ))
+});
+
+/// 按省份统计的推送记录数，outcome 为 success / failed
+pub static PUSH_PROVINCE_RECORDS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "push_province_records_total",
+            "Records pushed to MSS by push tasks, by province",
+        ),
+        &["task", "province", "outcome"],
+    ))
+});
+
+/// 推送时 MSS 返回需要休息（9019）的次数
+pub static MSS_THROTTLE_EVENTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "mss_throttle_events_total",
+            "MSS responses asking push tasks to rest (code 9019)",
+        ),
+        &["task"],
+    ))
+});
+
+/// 推送因 MSS 限流累计等待的秒数
+pub static MSS_THROTTLE_WAIT_SECONDS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "mss_throttle_wait_seconds_total",
+            "Seconds push tasks spent waiting after MSS throttling",
+        ),
+        &["task"],
+    ))
+});
+
+/// 已注册到调度器的任务，值恒为 1
+pub static SCHEDULER_JOB_REGISTERED: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register(IntGaugeVec::new(
+        Opts::new(
+            "scheduler_job_registered",
+            "Jobs registered with the scheduler",
+        ),
+        &["job"],
+    ))
+});
+
+/// 任务最近一次成功完成的时间（Unix 秒）
+pub static SCHEDULER_JOB_LAST_SUCCESS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register(IntGaugeVec::new(
+        Opts::new(
+            "scheduler_job_last_success_timestamp_seconds",
+            "Unix time of the last successful run of a job",
+        ),
+        &["job"],
+    ))
+});
+
+/// 查找缓存的访问次数，result 为 hit / miss
+pub static LOOKUP_CACHE_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "lookup_cache_requests_total",
+            "Lookups against in-memory caches",
+        ),
+        &["cache", "result"],
+    ))
+});
+
+/// 查找缓存当前的条目数
+pub static LOOKUP_CACHE_ENTRIES: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register(IntGaugeVec::new(
+        Opts::new("lookup_cache_entries", "Entries held by in-memory caches"),
+        &["cache"],
+    ))
+});
+
+/// 当前实例的 binlog 分片，value 为 index（分片号）/ count（存活成员数）
+pub static BINLOG_SHARD: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register(IntGaugeVec::new(
+        Opts::new(
+            "binlog_shard",
+            "Shard claimed by this instance for sharded binlog processing",
+        ),
+        &["data_type", "value"],
+    ))
+});
+
+/// binlog 映射步骤查本地 d_mss_*_mapping 的次数，result 为 hit / miss / error
+pub static MAPPING_CACHE_LOOKUPS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "mapping_cache_lookups_total",
+            "Lookups against local MSS mapping tables before calling gateway translate services",
+        ),
+        &["table", "result"],
+    ))
+});
+
+/// 网关 Redis 缓存的访问次数，result 为 hit / miss / error / invalidated / invalidate_error
+pub static GATEWAY_CACHE_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "gateway_cache_requests_total",
+            "Lookups against the Redis cache in front of gateway services",
+        ),
+        &["service", "result"],
+    ))
+});
+
+/// 网关数值字段的宽松解析次数，kind 为 string / empty / invalid / clamped / truncated
+pub static GATEWAY_NUMERIC_COERCIONS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "gateway_numeric_coercions_total",
+            "Numeric fields from the gateway that were coerced instead of rejected",
+        ),
+        &["type", "kind"],
+    ))
+});
+
+/// 定时与连续任务的运行次数，outcome 为 success / failed / skipped
+pub static TASK_RUNS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new("task_runs_total", "Scheduled and continuous task runs"),
+        &["task", "outcome"],
+    ))
+});
+
+/// 定时与连续任务单次运行的耗时
+pub static TASK_RUN_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(HistogramVec::new(
+        HistogramOpts::new("task_run_duration_seconds", "Duration of a single task run").buckets(
+            vec![
+                0.5, 1.0, 5.0, 15.0, 30.0, 60.0, 300.0, 900.0, 1800.0, 3600.0,
+            ],
+        ),
+        &["task"],
+    ))
+});
+
+/// 单条数据推送到 MSS 的耗时（包含重试），data 为数据键名，outcome 为 success / failed
+pub static MSS_PUSH_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(HistogramVec::new(
+        HistogramOpts::new(
+            "mss_push_duration_seconds",
+            "Latency of pushing a single record to MSS, including retries",
+        )
+        .buckets(vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 60.0, 300.0]),
+        &["data", "outcome"],
+    ))
+});
+
+/// 影子推送的请求数，outcome 为 success / rejected / failed / dropped，primary 为同一批主推送的结果
+pub static MSS_SHADOW_PUSH: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "mss_shadow_push_total",
+            "Sampled push requests mirrored to the secondary MSS endpoint",
+        ),
+        &["data", "outcome", "primary"],
+    ))
+});
+
+/// 启动时从推送发件箱恢复的记录数，status 为恢复前的状态 pending / sent / acked / failed
+pub static PUSH_OUTBOX_RECOVERED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "push_outbox_recovered_total",
+            "Outbox rows left unfinished by a previous process and recovered at startup",
+        ),
+        &["kind", "status"],
+    ))
+});
+
+/// ClickHouse 重放队列中的语句数，按节点统计，outcome 为 queued / replayed / failed / abandoned
+pub static CLICKHOUSE_REPLAY: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "clickhouse_replay_total",
+            "ClickHouse statements queued after a node failed and replayed once it recovered",
+        ),
+        &["node", "outcome"],
+    ))
+});
+
+/// 配置文件热加载的次数，outcome 为 applied / unchanged / rejected
+pub static CONFIG_RELOADS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "config_reloads_total",
+            "Configuration file reloads triggered by the file watcher",
+        ),
+        &["outcome"],
+    ))
+});
+
+/// 网关服务调用的耗时（包含重试），outcome 为 success / failed / disabled
+pub static GATEWAY_CALL_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(HistogramVec::new(
+        HistogramOpts::new(
+            "gateway_call_duration_seconds",
+            "Latency of gateway service calls, including retries",
+        )
+        .buckets(vec![0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0]),
+        &["service", "outcome"],
+    ))
+});
+
+/// MySQL 与 ClickHouse 批量写入每批的记录数，backend 为 mysql / clickhouse
+pub static DB_BATCH_SIZE: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(HistogramVec::new(
+        HistogramOpts::new(
+            "db_batch_size",
+            "Rows written per MySQL or ClickHouse batch",
+        )
+        .buckets(vec![
+            1.0, 10.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0,
+        ]),
+        &["backend", "table"],
+    ))
+});
+
+/// 在超时时间内没有取到 MySQL 连接的次数，context 为取连接的调用方
+pub static DB_POOL_EXHAUSTED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "db_pool_exhausted_total",
+            "MySQL connection acquisitions that timed out because the pool was exhausted",
+        ),
+        &["context"],
+    ))
+});
+
+/// 具名 MySQL 查询耗时，outcome 为 ok / error
+pub static DB_QUERY_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(HistogramVec::new(
+        HistogramOpts::new(
+            "db_query_duration_seconds",
+            "Latency of named MySQL queries",
+        )
+        .buckets(vec![
+            0.001, 0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+        ]),
+        &["query", "outcome"],
+    ))
+});
+
+/// 具名 MySQL 查询返回或影响的行数
+pub static DB_QUERY_ROWS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "db_query_rows_total",
+            "Rows returned or affected by named MySQL queries",
+        ),
+        &["query"],
+    ))
+});
+
+/// 以 Prometheus 文本格式导出注册表中的所有指标
+pub fn gather_text() -> prometheus::Result<String> {
+    let mut buffer = String::new();
+    TextEncoder::new().encode_utf8(&REGISTRY.gather(), &mut buffer)?;
+    Ok(buffer)
+}
+
+fn register<M>(metric: prometheus::Result<M>) -> M
+where
+    M: prometheus::core::Collector + Clone + 'static,
+{
+    let metric = metric.expect("metric definition is valid");
+    REGISTRY
+        .register(Box::new(metric.clone()))
+        .expect("metric is registered only once");
+    metric
+}
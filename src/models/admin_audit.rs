@@ -0,0 +1,79 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{MySqlPool, Row};
+
+use crate::db::query_runner::QueryRunner;
+
+#[derive(Debug, Serialize)]
+pub struct AdminAuditEntry {
+    pub id: i64,
+    pub action: String,
+    pub caller: String,
+    pub source_ip: Option<String>,
+    pub params: Value,
+    pub created_at: NaiveDateTime,
+}
+
+pub async fn record_admin_action<P: Serialize>(
+    mysql_pool: &MySqlPool,
+    action: &str,
+    caller: &str,
+    source_ip: Option<&str>,
+    params: &P,
+) -> Result<()> {
+    let params = serde_json::to_string(params).context("Failed to serialize audit params")?;
+    QueryRunner::new("admin_audit_insert")
+        .run(
+            sqlx::query(
+                "INSERT INTO admin_audit (action, caller, source_ip, params) VALUES (?, ?, ?, ?)",
+            )
+            .bind(action)
+            .bind(caller)
+            .bind(source_ip)
+            .bind(params)
+            .execute(mysql_pool),
+        )
+        .await
+        .context("Failed to insert into admin_audit table")?;
+    Ok(())
+}
+
+/// 按时间倒序分页查询，返回 (当前页记录, 总数)，page 从 1 开始
+pub async fn list_admin_actions(
+    mysql_pool: &MySqlPool,
+    page: u32,
+    page_size: u32,
+) -> Result<(Vec<AdminAuditEntry>, i64)> {
+    let total: i64 = QueryRunner::new("admin_audit_count")
+        .run(sqlx::query("SELECT COUNT(*) AS total FROM admin_audit").fetch_one(mysql_pool))
+        .await
+        .context("Failed to count admin_audit")?
+        .try_get("total")?;
+
+    let query = sqlx::query(
+        "SELECT id, action, caller, source_ip, CAST(params AS CHAR) AS params, created_at \
+         FROM admin_audit ORDER BY id DESC LIMIT ? OFFSET ?",
+    )
+    .bind(page_size)
+    .bind(u64::from(page.saturating_sub(1)) * u64::from(page_size));
+    let rows = QueryRunner::new("admin_audit_page")
+        .run(query.fetch_all(mysql_pool))
+        .await
+        .context("Failed to query admin_audit")?;
+
+    let mut entries = Vec::with_capacity(rows.len());
+    for row in rows {
+        let params: String = row.try_get("params")?;
+        entries.push(AdminAuditEntry {
+            id: row.try_get("id")?,
+            action: row.try_get("action")?,
+            caller: row.try_get("caller")?,
+            source_ip: row.try_get("source_ip")?,
+            params: serde_json::from_str(&params).unwrap_or(Value::String(params)),
+            created_at: row.try_get("created_at")?,
+        });
+    }
+    Ok((entries, total))
+}
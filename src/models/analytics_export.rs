@@ -0,0 +1,190 @@
+//! 任务执行与推送汇总导出到 ClickHouse，供 Grafana 查询几个月的历史而不占用业务 MySQL。
+//! 导出失败只告警，不影响任务。表需预先创建：
+//!
+//! ```sql
+//! CREATE TABLE DXXY_LOCAL.servicekit_task_run (
+//!     task_name String, triggered_by String, status LowCardinality(String),
+//!     error_message Nullable(String), rows_fetched UInt64, rows_pushed UInt64, rows_failed UInt64,
+//!     started_at DateTime, finished_at DateTime, duration_ms UInt64
+//! ) ENGINE = MergeTree PARTITION BY toYYYYMM(started_at) ORDER BY (task_name, started_at);
+//!
+//! CREATE TABLE DXXY_LOCAL.servicekit_push_run (
+//!     task_name String, hit_date Nullable(String), success UInt64, failed UInt64, deferred UInt64,
+//!     throttle_events UInt32, throttle_wait_ms UInt64, finished_at DateTime, duration_ms UInt64
+//! ) ENGINE = MergeTree PARTITION BY toYYYYMM(finished_at) ORDER BY (task_name, finished_at);
+//! ```
+
+use std::sync::Arc;
+
+use chrono::{Local, NaiveDateTime};
+use clickhouse_rs::Block;
+use tracing::{debug, warn};
+
+use crate::config::AnalyticsExportConfig;
+use crate::utils::ClickHouseClient;
+
+/// 一次任务执行的汇总，对应 task_run 中结束的一行
+#[derive(Debug, Clone)]
+pub struct TaskRunSummary {
+    pub task_name: String,
+    pub triggered_by: String,
+    pub status: &'static str,
+    pub error_message: Option<String>,
+    pub rows_fetched: u64,
+    pub rows_pushed: u64,
+    pub rows_failed: u64,
+    pub started_at: NaiveDateTime,
+    pub finished_at: NaiveDateTime,
+    pub duration_ms: u64,
+}
+
+impl TaskRunSummary {
+    fn block(&self) -> Block {
+        Block::new()
+            .column("task_name", vec![self.task_name.clone()])
+            .column("triggered_by", vec![self.triggered_by.clone()])
+            .column("status", vec![self.status.to_string()])
+            .column("error_message", vec![self.error_message.clone()])
+            .column("rows_fetched", vec![self.rows_fetched])
+            .column("rows_pushed", vec![self.rows_pushed])
+            .column("rows_failed", vec![self.rows_failed])
+            .column("started_at", vec![epoch_seconds(self.started_at)])
+            .column("finished_at", vec![epoch_seconds(self.finished_at)])
+            .column("duration_ms", vec![self.duration_ms])
+    }
+}
+
+/// 一次推送执行的汇总，按 ID 推送时 hit_date 为 None
+#[derive(Debug, Clone)]
+pub struct PushRunSummary {
+    pub task_name: String,
+    pub hit_date: Option<String>,
+    pub success: u64,
+    pub failed: u64,
+    pub deferred: u64,
+    pub throttle_events: u32,
+    pub throttle_wait_ms: u64,
+    pub finished_at: NaiveDateTime,
+    pub duration_ms: u64,
+}
+
+impl PushRunSummary {
+    fn block(&self) -> Block {
+        Block::new()
+            .column("task_name", vec![self.task_name.clone()])
+            .column("hit_date", vec![self.hit_date.clone()])
+            .column("success", vec![self.success])
+            .column("failed", vec![self.failed])
+            .column("deferred", vec![self.deferred])
+            .column("throttle_events", vec![self.throttle_events])
+            .column("throttle_wait_ms", vec![self.throttle_wait_ms])
+            .column("finished_at", vec![epoch_seconds(self.finished_at)])
+            .column("duration_ms", vec![self.duration_ms])
+    }
+}
+
+/// 本地时间转为 DateTime 列的秒级时间戳
+fn epoch_seconds(at: NaiveDateTime) -> u32 {
+    let timestamp = at
+        .and_local_timezone(Local)
+        .earliest()
+        .map_or_else(|| at.and_utc().timestamp(), |local| local.timestamp());
+    u32::try_from(timestamp).unwrap_or_default()
+}
+
+/// 把汇总写入每个 ClickHouse 节点的本地表，至少一个节点写入成功即可，其余节点的失败只告警
+pub struct AnalyticsExporter {
+    clickhouse_client: Arc<ClickHouseClient>,
+    config: Arc<AnalyticsExportConfig>,
+}
+
+impl AnalyticsExporter {
+    /// 未启用时返回 None
+    pub fn new(
+        clickhouse_client: Arc<ClickHouseClient>,
+        config: Arc<AnalyticsExportConfig>,
+    ) -> Option<Self> {
+        config.enabled.then_some(Self {
+            clickhouse_client,
+            config,
+        })
+    }
+
+    pub async fn export_task_run(&self, summary: &TaskRunSummary) {
+        self.insert(&self.config.task_run_table, summary.block())
+            .await;
+    }
+
+    pub async fn export_push_run(&self, summary: &PushRunSummary) {
+        self.insert(&self.config.push_run_table, summary.block())
+            .await;
+    }
+
+    async fn insert(&self, table: &str, block: Block) {
+        let execution = match self
+            .clickhouse_client
+            .insert_on_all_nodes(table, &block)
+            .await
+        {
+            Ok(execution) => execution,
+            Err(e) => {
+                warn!("Failed to export run summary into {table}: {e:?}");
+                return;
+            }
+        };
+        let failed: Vec<&str> = execution.failed_nodes().map(|(node, _)| node).collect();
+        if failed.len() == execution.results.len() {
+            warn!("Failed to export run summary into {table} on any ClickHouse node.");
+        } else if failed.is_empty() {
+            debug!("Exported run summary into {table} on all ClickHouse nodes.");
+        } else {
+            warn!("Exported run summary into {table}, but not on ClickHouse nodes {failed:?}.");
+        }
+    }
+}
+
+#[test]
+fn test_summary_blocks() {
+    use crate::utils::timefmt;
+
+    let at = timefmt::parse_datetime("2026-10-16 02:00:00").unwrap();
+    let task_run = TaskRunSummary {
+        task_name: "培训推送".to_string(),
+        triggered_by: "cron".to_string(),
+        status: "failed",
+        error_message: Some("it's down".to_string()),
+        rows_fetched: 10,
+        rows_pushed: 8,
+        rows_failed: 2,
+        started_at: at,
+        finished_at: at,
+        duration_ms: 1500,
+    };
+    let block = task_run.block();
+    assert_eq!(block.row_count(), 1);
+    assert_eq!(block.column_count(), 10);
+
+    let push_run = PushRunSummary {
+        task_name: "培训推送".to_string(),
+        hit_date: None,
+        success: 8,
+        failed: 2,
+        deferred: 0,
+        throttle_events: 1,
+        throttle_wait_ms: 3000,
+        finished_at: at,
+        duration_ms: 1500,
+    };
+    let block = push_run.block();
+    assert_eq!(block.row_count(), 1);
+    assert_eq!(block.column_count(), 9);
+}
+
+#[test]
+fn test_epoch_seconds() {
+    use crate::utils::timefmt;
+
+    let at = timefmt::parse_datetime("2026-10-16 02:00:00").unwrap();
+    let expected = at.and_local_timezone(Local).unwrap().timestamp();
+    assert_eq!(i64::from(epoch_seconds(at)), expected);
+}
@@ -0,0 +1,201 @@
+//! 从旧 Java 归档系统的推送结果表导入历史记录，切换前后的推送结果都在 mss_push_result 中，
+//! 推送结果查询、对账等功能可以跨越切换时间。按 ID 去重，重复执行只导入尚未存在的记录
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Context, Result, bail};
+use chrono::{Days, NaiveDate, NaiveTime};
+use serde::Serialize;
+use sqlx::{MySql, MySqlPool, QueryBuilder, Row};
+use tracing::info;
+
+use crate::config::LegacyImportConfig;
+use crate::db::query_runner::QueryRunner;
+use crate::models::push_result::{
+    MssPushResult, MssPushResultDetail, PushResultRecord, PushResultService,
+};
+
+// 导入记录的 endpoint，用于区分来自旧系统的结果
+const LEGACY_ENDPOINT: &str = "legacy-java";
+
+/// 一次导入的计数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct LegacyImportSummary {
+    /// 旧表中日期范围内的记录数
+    pub scanned: usize,
+    pub imported: usize,
+    /// mss_push_result 中已存在而跳过的记录数
+    pub skipped: usize,
+    pub details: usize,
+}
+
+/// 按 ID 分页读取旧表中 push_time 在 [begin_date, end_date] 内的记录，旧表没有的调用信息字段为 NULL
+fn legacy_page_query(
+    table: &str,
+    begin_date: NaiveDate,
+    end_date: NaiveDate,
+    after_id: Option<&str>,
+    batch_size: usize,
+) -> QueryBuilder<'static, MySql> {
+    let mut query_builder: QueryBuilder<MySql> = QueryBuilder::new(format!(
+        "SELECT id, push_time, train_id, course_id, user_id, type AS data_type, error_msg, error_code, \
+         NULL AS duration_ms, NULL AS http_status, NULL AS attempt_count, NULL AS endpoint, NULL AS run_id \
+         FROM {table} WHERE push_time >= "
+    ));
+    query_builder
+        .push_bind(begin_date.and_time(NaiveTime::MIN))
+        .push(" AND push_time < ")
+        .push_bind((end_date + Days::new(1)).and_time(NaiveTime::MIN));
+    if let Some(after_id) = after_id {
+        query_builder
+            .push(" AND id > ")
+            .push_bind(after_id.to_string());
+    }
+    query_builder
+        .push(" ORDER BY id LIMIT ")
+        .push_bind(batch_size as u64);
+    query_builder
+}
+
+fn push_id_list(query_builder: &mut QueryBuilder<'_, MySql>, ids: &[String]) {
+    query_builder.push(" IN (");
+    let mut separated = query_builder.separated(", ");
+    for id in ids {
+        separated.push_bind(id.clone());
+    }
+    separated.push_unseparated(")");
+}
+
+/// 导入 push_time 在 [begin_date, end_date] 内的旧推送结果及其详情
+pub async fn import_legacy_results(
+    mysql_pool: &MySqlPool,
+    config: &LegacyImportConfig,
+    begin_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<LegacyImportSummary> {
+    if config.result_table.trim().is_empty() || config.detail_table.trim().is_empty() {
+        bail!("legacy_import.result_table and legacy_import.detail_table are required");
+    }
+    if begin_date > end_date {
+        bail!("begin_date {begin_date} is after end_date {end_date}");
+    }
+    let batch_size = config.batch_size.max(1);
+    let service = PushResultService::new(mysql_pool.clone());
+    let mut summary = LegacyImportSummary::default();
+    let mut after_id: Option<String> = None;
+    loop {
+        let mut query_builder = legacy_page_query(
+            &config.result_table,
+            begin_date,
+            end_date,
+            after_id.as_deref(),
+            batch_size,
+        );
+        let results: Vec<MssPushResult> = QueryRunner::new("legacy_import_results")
+            .run(
+                query_builder
+                    .build_query_as::<MssPushResult>()
+                    .fetch_all(mysql_pool),
+            )
+            .await
+            .with_context(|| {
+                format!("Failed to read legacy results from {}", config.result_table)
+            })?;
+        let Some(last) = results.last() else {
+            break;
+        };
+        after_id = Some(last.id.clone());
+        let page_len = results.len();
+        summary.scanned += page_len;
+
+        let ids: Vec<String> = results.iter().map(|result| result.id.clone()).collect();
+        let existing = existing_ids(mysql_pool, &ids).await?;
+        let new_results: Vec<MssPushResult> = results
+            .into_iter()
+            .filter(|result| !existing.contains(&result.id))
+            .collect();
+        summary.skipped += page_len - new_results.len();
+
+        if !new_results.is_empty() {
+            let new_ids: Vec<String> = new_results.iter().map(|result| result.id.clone()).collect();
+            let mut details = legacy_details(mysql_pool, &config.detail_table, &new_ids).await?;
+            let records: Vec<PushResultRecord> = new_results
+                .into_iter()
+                .map(|mut result| {
+                    result.endpoint = Some(LEGACY_ENDPOINT.to_string());
+                    let details = details.remove(&result.id).unwrap_or_default();
+                    PushResultRecord { result, details }
+                })
+                .collect();
+            summary.imported += records.len();
+            summary.details += records
+                .iter()
+                .map(|record| record.details.len())
+                .sum::<usize>();
+            service.record_batch(&records).await?;
+        }
+        info!(
+            "Legacy import progress: scanned {}, imported {}, skipped {}.",
+            summary.scanned, summary.imported, summary.skipped
+        );
+
+        if page_len < batch_size {
+            break;
+        }
+    }
+    Ok(summary)
+}
+
+// mss_push_result 中已存在的 ID
+async fn existing_ids(mysql_pool: &MySqlPool, ids: &[String]) -> Result<HashSet<String>> {
+    let mut query_builder: QueryBuilder<MySql> =
+        QueryBuilder::new("SELECT id FROM mss_push_result WHERE id");
+    push_id_list(&mut query_builder, ids);
+    let rows = QueryRunner::new("legacy_import_existing")
+        .run(query_builder.build().fetch_all(mysql_pool))
+        .await
+        .context("Failed to query existing mss_push_result IDs")?;
+    rows.iter()
+        .map(|row| row.try_get::<String, _>("id").map_err(Into::into))
+        .collect()
+}
+
+// 旧详情表中 data_id 属于 ids 的详情，按 data_id 分组
+async fn legacy_details(
+    mysql_pool: &MySqlPool,
+    detail_table: &str,
+    ids: &[String],
+) -> Result<HashMap<String, Vec<MssPushResultDetail>>> {
+    let mut query_builder: QueryBuilder<MySql> = QueryBuilder::new(format!(
+        "SELECT data_id, result_id FROM {detail_table} WHERE data_id"
+    ));
+    push_id_list(&mut query_builder, ids);
+    let rows = QueryRunner::new("legacy_import_details")
+        .run(query_builder.build().fetch_all(mysql_pool))
+        .await
+        .with_context(|| format!("Failed to read legacy details from {detail_table}"))?;
+    let mut details: HashMap<String, Vec<MssPushResultDetail>> = HashMap::new();
+    for row in rows {
+        let detail = MssPushResultDetail {
+            data_id: row.try_get("data_id")?,
+            result_id: row.try_get("result_id")?,
+        };
+        details
+            .entry(detail.data_id.clone())
+            .or_default()
+            .push(detail);
+    }
+    Ok(details)
+}
+
+#[test]
+fn test_legacy_page_query() {
+    let date = NaiveDate::from_ymd_opt(2026, 10, 1).unwrap();
+    let query_builder = legacy_page_query("legacy.mss_push_result", date, date, Some("abc"), 100);
+    assert_eq!(
+        query_builder.sql(),
+        "SELECT id, push_time, train_id, course_id, user_id, type AS data_type, error_msg, error_code, \
+         NULL AS duration_ms, NULL AS http_status, NULL AS attempt_count, NULL AS endpoint, NULL AS run_id \
+         FROM legacy.mss_push_result WHERE push_time >= ? AND push_time < ? AND id > ? ORDER BY id LIMIT ?"
+    );
+}
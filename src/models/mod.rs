@@ -1,3 +1,11 @@
+pub mod admin_audit;
+pub mod analytics_export;
+pub mod legacy_import;
 pub mod org;
+pub mod push_province_stats;
 pub mod push_result;
+pub mod push_run;
+pub mod run_manifest;
+pub mod task_run;
+pub mod task_run_history;
 pub mod train;
@@ -0,0 +1,142 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{Context, Result};
+use sqlx::{MySqlPool, QueryBuilder, Row};
+
+use crate::db::query_runner::QueryRunner;
+use crate::models::task_run::current_run_id;
+
+/// 记录没有组织或组织无法对应到省份时使用的省份名称
+pub const UNKNOWN_PROVINCE: &str = "未知";
+
+/// 一次推送任务执行中某个省份的结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProvinceStats {
+    pub province: String,
+    pub succeeded: u32,
+    pub failed: u32,
+}
+
+/// MSS 组织编码对应的省份：d_mss_org_mapping.msscode -> d_telecom_org.PROVINCE
+pub async fn load_org_provinces(
+    mysql_pool: &MySqlPool,
+    org_ids: &[&str],
+) -> Result<HashMap<String, String>> {
+    let mut provinces = HashMap::new();
+    if org_ids.is_empty() {
+        return Ok(provinces);
+    }
+    let mut query_builder = QueryBuilder::new(
+        "SELECT m.msscode, o.PROVINCE AS province FROM d_mss_org_mapping m
+        JOIN d_telecom_org o ON o.id = m.code
+        WHERE o.PROVINCE IS NOT NULL AND o.PROVINCE <> '' AND m.msscode IN (",
+    );
+    let mut separated = query_builder.separated(", ");
+    for org_id in org_ids {
+        separated.push_bind(*org_id);
+    }
+    separated.push_unseparated(")");
+    let rows = QueryRunner::new("org_provinces_by_mss_codes")
+        .run(query_builder.build().fetch_all(mysql_pool))
+        .await
+        .context("Failed to query provinces of MSS orgs")?;
+    for row in rows {
+        let org_id: String = row.try_get("msscode")?;
+        let province: String = row.try_get("province")?;
+        provinces.entry(org_id).or_insert(province);
+    }
+    Ok(provinces)
+}
+
+/// 按省份汇总推送结果，结果按省份名称排序
+pub fn tally_by_province(
+    org_by_id: &HashMap<String, String>,
+    provinces: &HashMap<String, String>,
+    success_ids: &[String],
+    failed_ids: &[String],
+) -> Vec<ProvinceStats> {
+    let province_of = |id: &String| -> String {
+        org_by_id
+            .get(id)
+            .and_then(|org_id| provinces.get(org_id))
+            .map_or(UNKNOWN_PROVINCE, String::as_str)
+            .to_string()
+    };
+    let mut stats: BTreeMap<String, ProvinceStats> = BTreeMap::new();
+    for id in success_ids {
+        let province = province_of(id);
+        stats
+            .entry(province.clone())
+            .or_insert_with(|| ProvinceStats {
+                province,
+                ..Default::default()
+            })
+            .succeeded += 1;
+    }
+    for id in failed_ids {
+        let province = province_of(id);
+        stats
+            .entry(province.clone())
+            .or_insert_with(|| ProvinceStats {
+                province,
+                ..Default::default()
+            })
+            .failed += 1;
+    }
+    stats.into_values().collect()
+}
+
+pub async fn record_province_stats(
+    mysql_pool: &MySqlPool,
+    task_name: &str,
+    hit_date: Option<&str>,
+    stats: &[ProvinceStats],
+) -> Result<()> {
+    if stats.is_empty() {
+        return Ok(());
+    }
+    let run_id = current_run_id();
+    let mut query_builder = QueryBuilder::new(
+        "INSERT INTO mss_push_province_stats (run_id, task_name, hit_date, province, succeeded, failed) ",
+    );
+    query_builder.push_values(stats, |mut b, stat| {
+        b.push_bind(run_id)
+            .push_bind(task_name)
+            .push_bind(hit_date)
+            .push_bind(&stat.province)
+            .push_bind(stat.succeeded)
+            .push_bind(stat.failed);
+    });
+    QueryRunner::new("batch_insert_province_stats")
+        .run(query_builder.build().execute(mysql_pool))
+        .await
+        .context("Failed to insert into mss_push_province_stats table")?;
+    Ok(())
+}
+
+#[test]
+fn test_tally_by_province() {
+    let org_by_id: HashMap<String, String> = [("a", "org1"), ("b", "org2"), ("c", "org1")]
+        .into_iter()
+        .map(|(id, org)| (id.to_string(), org.to_string()))
+        .collect();
+    let provinces: HashMap<String, String> = [("org1".to_string(), "北京".to_string())].into();
+    let ids = |ids: &[&str]| -> Vec<String> { ids.iter().map(|id| id.to_string()).collect() };
+
+    let stats = tally_by_province(&org_by_id, &provinces, &ids(&["a", "b", "d"]), &ids(&["c"]));
+    assert_eq!(
+        stats,
+        vec![
+            ProvinceStats {
+                province: "北京".to_string(),
+                succeeded: 1,
+                failed: 1,
+            },
+            ProvinceStats {
+                province: UNKNOWN_PROVINCE.to_string(),
+                succeeded: 2,
+                failed: 0,
+            },
+        ]
+    );
+}
@@ -1,18 +1,32 @@
+use std::time::Duration;
+
 use anyhow::{Context, Result};
-use chrono::NaiveDateTime;
+use chrono::{Days, NaiveDate, NaiveTime};
 use serde::{Deserialize, Serialize};
-use sqlx::MySqlPool;
+use sqlx::{MySql, MySqlPool, QueryBuilder, Row};
+use tokio::sync::{Mutex, mpsc};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MssPushResult {
-    pub id: String, // 数据库中存储为 VARCHAR(36)
-    pub push_time: NaiveDateTime,
-    pub train_id: Option<String>,
-    pub course_id: Option<String>,
-    pub user_id: Option<String>,
-    pub data_type: Option<i32>, // `type` 是 SQL 关键字，我们使用 `data_type`
-    pub error_msg: Option<String>,
-    pub error_code: Option<String>,
+use crate::db::query_runner::QueryRunner;
+use crate::metrics::DB_BATCH_SIZE;
+use crate::parsers::mss_response::SUCCESS_CODE;
+
+// 写入通道容量，写入任务落后时推送方在此处等待
+const WRITER_CHANNEL_CAPACITY: usize = 1024;
+// 未攒满一批时的最长等待时间
+const WRITER_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+// 推送结果也是 pushResults 接口的响应，定义在 web::models 中
+pub use crate::web::{DailyPushSummary, MssPushResult};
+
+/// 一次推送请求的调用信息，由 psn_dos_push 采集后交给 PushResultParser 写入 mss_push_result
+#[derive(Debug, Clone, Default)]
+pub struct PushTelemetry {
+    pub duration_ms: i64,
+    pub http_status: Option<u16>,
+    pub attempt_count: u32,
+    pub endpoint: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +35,58 @@ pub struct MssPushResultDetail {
     pub result_id: Option<String>, // 可以是 trainingId, course_id, userId 等
 }
 
+/// 一条待写入的推送结果及其详情
+#[derive(Debug, Clone)]
+pub struct PushResultRecord {
+    pub result: MssPushResult,
+    pub details: Vec<MssPushResultDetail>,
+}
+
+/// 推送结果的查询条件，None 表示不过滤。起止日期按 push_time 计算，均包含
+#[derive(Debug, Clone, Default)]
+pub struct PushResultFilter {
+    pub begin_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub data_type: Option<i32>,
+    pub error_code: Option<String>,
+    pub train_id: Option<String>,
+    pub user_id: Option<String>,
+}
+
+impl PushResultFilter {
+    fn push_conditions(&self, query_builder: &mut QueryBuilder<'_, MySql>) {
+        query_builder.push(" WHERE 1 = 1");
+        if let Some(begin) = self.begin_date {
+            query_builder
+                .push(" AND push_time >= ")
+                .push_bind(begin.and_time(NaiveTime::MIN));
+        }
+        if let Some(end) = self.end_date {
+            query_builder
+                .push(" AND push_time < ")
+                .push_bind((end + Days::new(1)).and_time(NaiveTime::MIN));
+        }
+        if let Some(data_type) = self.data_type {
+            query_builder.push(" AND type = ").push_bind(data_type);
+        }
+        if let Some(error_code) = &self.error_code {
+            query_builder
+                .push(" AND error_code = ")
+                .push_bind(error_code.clone());
+        }
+        if let Some(train_id) = &self.train_id {
+            query_builder
+                .push(" AND train_id = ")
+                .push_bind(train_id.clone());
+        }
+        if let Some(user_id) = &self.user_id {
+            query_builder
+                .push(" AND user_id = ")
+                .push_bind(user_id.clone());
+        }
+    }
+}
+
 pub struct PushResultService {
     mysql_pool: MySqlPool,
 }
@@ -30,45 +96,269 @@ impl PushResultService {
         PushResultService { mysql_pool }
     }
 
-    pub async fn record(
-        &self,
-        mss_push_result: &MssPushResult,
-        result_details: &[MssPushResultDetail],
-    ) -> Result<()> {
+    /// 在一个事务中批量插入主记录与详情记录
+    pub async fn record_batch(&self, records: &[PushResultRecord]) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let mut tx = self
+            .mysql_pool
+            .begin()
+            .await
+            .context("Failed to begin push result transaction")?;
+
         // 插入 MssPushResult 主记录
-        sqlx::query!(
-            r#"
-            INSERT INTO mss_push_result (id, push_time, train_id, course_id, user_id, type, error_msg, error_code)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
-            "#,
-            mss_push_result.id,
-            mss_push_result.push_time,
-            mss_push_result.train_id,
-            mss_push_result.course_id,
-            mss_push_result.user_id,
-            mss_push_result.data_type,
-            mss_push_result.error_msg,
-            mss_push_result.error_code,
-        )
-        .execute(&self.mysql_pool)
-        .await
-        .context("Failed to insert into mss_push_result table")?;
+        let mut query_builder: QueryBuilder<MySql> = QueryBuilder::new(
+            "INSERT INTO mss_push_result (id, push_time, train_id, course_id, user_id, type, error_msg, error_code, \
+             duration_ms, http_status, attempt_count, endpoint, run_id) ",
+        );
+        query_builder.push_values(records, |mut b, record| {
+            let r = &record.result;
+            b.push_bind(&r.id)
+                .push_bind(r.push_time)
+                .push_bind(&r.train_id)
+                .push_bind(&r.course_id)
+                .push_bind(&r.user_id)
+                .push_bind(r.data_type)
+                .push_bind(&r.error_msg)
+                .push_bind(&r.error_code)
+                .push_bind(r.duration_ms)
+                .push_bind(r.http_status)
+                .push_bind(r.attempt_count)
+                .push_bind(&r.endpoint)
+                .push_bind(r.run_id);
+        });
+        QueryRunner::new("batch_insert_push_results")
+            .run(query_builder.build().execute(&mut *tx))
+            .await
+            .context("Failed to insert into mss_push_result table")?;
 
         // 插入 MssPushResultDetail 详情记录
-        for detail in result_details {
-            sqlx::query!(
-                r#"
-                INSERT INTO mss_push_result_detail (data_id, result_id)
-                VALUES (?, ?)
-                "#,
-                detail.data_id,
-                detail.result_id,
+        let details: Vec<&MssPushResultDetail> =
+            records.iter().flat_map(|r| r.details.iter()).collect();
+        if !details.is_empty() {
+            let mut query_builder: QueryBuilder<MySql> =
+                QueryBuilder::new("INSERT INTO mss_push_result_detail (data_id, result_id) ");
+            query_builder.push_values(details, |mut b, detail| {
+                b.push_bind(&detail.data_id).push_bind(&detail.result_id);
+            });
+            QueryRunner::new("batch_insert_push_result_details")
+                .run(query_builder.build().execute(&mut *tx))
+                .await
+                .context("Failed to insert into mss_push_result_detail table")?;
+        }
+
+        tx.commit()
+            .await
+            .context("Failed to commit push result transaction")?;
+        Ok(())
+    }
+
+    /// 按推送时间倒序分页查询，返回 (当前页记录, 总数)，page 从 1 开始
+    pub async fn query(
+        &self,
+        filter: &PushResultFilter,
+        page: u32,
+        page_size: u32,
+    ) -> Result<(Vec<MssPushResult>, i64)> {
+        let mut count_builder: QueryBuilder<MySql> =
+            QueryBuilder::new("SELECT COUNT(*) AS total FROM mss_push_result");
+        filter.push_conditions(&mut count_builder);
+        let total: i64 = QueryRunner::new("push_results_count")
+            .run(count_builder.build().fetch_one(&self.mysql_pool))
+            .await
+            .context("Failed to count mss_push_result")?
+            .try_get("total")?;
+
+        let mut query_builder: QueryBuilder<MySql> = QueryBuilder::new(
+            "SELECT id, push_time, train_id, course_id, user_id, type AS data_type, error_msg, error_code, \
+             duration_ms, http_status, attempt_count, endpoint, run_id FROM mss_push_result",
+        );
+        filter.push_conditions(&mut query_builder);
+        query_builder
+            .push(" ORDER BY push_time DESC, id LIMIT ")
+            .push_bind(page_size)
+            .push(" OFFSET ")
+            .push_bind(u64::from(page.saturating_sub(1)) * u64::from(page_size));
+        let results = QueryRunner::new("push_results_page")
+            .run(
+                query_builder
+                    .build_query_as::<MssPushResult>()
+                    .fetch_all(&self.mysql_pool),
             )
-            .execute(&self.mysql_pool)
             .await
-            .context("Failed to insert into mss_push_result_detail table")?;
+            .context("Failed to query mss_push_result")?;
+        Ok((results, total))
+    }
+
+    /// 按天统计满足条件的推送成功与失败记录数，成功与 clickhouse 对账的判定一致：
+    /// error_msg 为空且 error_code 为成功码
+    pub async fn daily_summary(&self, filter: &PushResultFilter) -> Result<Vec<DailyPushSummary>> {
+        let mut query_builder = daily_summary_query(filter);
+        let rows = QueryRunner::new("push_results_daily_summary")
+            .run(query_builder.build().fetch_all(&self.mysql_pool))
+            .await
+            .context("Failed to summarize mss_push_result")?;
+        rows.iter()
+            .map(|row| {
+                let total: i64 = row.try_get("total")?;
+                let succeeded: i64 = row.try_get("succeeded")?;
+                Ok(DailyPushSummary {
+                    date: row.try_get("day")?,
+                    succeeded,
+                    failed: total - succeeded,
+                })
+            })
+            .collect()
+    }
+}
+
+/// 按天汇总的查询。每行结果按其详情数计为多条记录（旧系统导入的结果一行对应一次请求），
+/// 没有详情的结果计为一条
+fn daily_summary_query(filter: &PushResultFilter) -> QueryBuilder<'static, MySql> {
+    let mut query_builder: QueryBuilder<MySql> = QueryBuilder::new(
+        "SELECT DATE(push_time) AS day, CAST(SUM(records) AS SIGNED) AS total, \
+         CAST(SUM(IF(succeeded, records, 0)) AS SIGNED) AS succeeded FROM (\
+         SELECT push_time, error_msg IS NULL AND error_code = ",
+    );
+    query_builder.push_bind(SUCCESS_CODE).push(
+        " AS succeeded, GREATEST((SELECT COUNT(*) FROM mss_push_result_detail \
+         WHERE mss_push_result_detail.data_id = mss_push_result.id), 1) AS records \
+         FROM mss_push_result",
+    );
+    filter.push_conditions(&mut query_builder);
+    query_builder.push(") AS results GROUP BY day ORDER BY day");
+    query_builder
+}
+
+enum WriterCommand {
+    Record(Box<PushResultRecord>),
+    Shutdown,
+}
+
+/// 推送结果的后台写入器：推送路径只负责入队，由独立任务批量写入 MySQL
+pub struct PushResultWriter {
+    sender: mpsc::Sender<WriterCommand>,
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl PushResultWriter {
+    /// `batch_size` 为每次批量写入的最大记录数
+    pub fn spawn(mysql_pool: MySqlPool, batch_size: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(WRITER_CHANNEL_CAPACITY);
+        let service = PushResultService::new(mysql_pool);
+        let handle = tokio::spawn(run_writer(service, receiver, batch_size.max(1)));
+        PushResultWriter {
+            sender,
+            handle: Mutex::new(Some(handle)),
         }
+    }
 
-        Ok(())
+    /// 将结果加入写入队列，队列满时等待写入任务消费
+    pub async fn record(&self, result: MssPushResult, details: Vec<MssPushResultDetail>) {
+        let record = Box::new(PushResultRecord { result, details });
+        if let Err(e) = self.sender.send(WriterCommand::Record(record)).await {
+            error!("Push result writer is closed, dropping result: {e}");
+        }
     }
+
+    /// 通知写入任务刷新剩余记录并退出，等待其完成
+    pub async fn shutdown(&self) {
+        let Some(handle) = self.handle.lock().await.take() else {
+            return;
+        };
+        if self.sender.send(WriterCommand::Shutdown).await.is_err() {
+            warn!("Push result writer already stopped before shutdown");
+        }
+        if let Err(e) = handle.await {
+            error!("Push result writer task failed: {e:?}");
+        }
+    }
+}
+
+async fn run_writer(
+    service: PushResultService,
+    mut receiver: mpsc::Receiver<WriterCommand>,
+    batch_size: usize,
+) {
+    info!("Push result writer started");
+    let mut buffer: Vec<PushResultRecord> = Vec::with_capacity(batch_size);
+    let mut ticker = tokio::time::interval(WRITER_FLUSH_INTERVAL);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            command = receiver.recv() => match command {
+                Some(WriterCommand::Record(record)) => {
+                    buffer.push(*record);
+                    if buffer.len() >= batch_size {
+                        flush(&service, &mut buffer, batch_size).await;
+                    }
+                }
+                Some(WriterCommand::Shutdown) | None => {
+                    // 关闭前把通道中已入队的记录一并写入
+                    receiver.close();
+                    while let Some(command) = receiver.recv().await {
+                        if let WriterCommand::Record(record) = command {
+                            buffer.push(*record);
+                        }
+                    }
+                    flush(&service, &mut buffer, batch_size).await;
+                    break;
+                }
+            },
+            _ = ticker.tick() => flush(&service, &mut buffer, batch_size).await,
+        }
+    }
+    info!("Push result writer stopped");
+}
+
+async fn flush(service: &PushResultService, buffer: &mut Vec<PushResultRecord>, batch_size: usize) {
+    if buffer.is_empty() {
+        return;
+    }
+    for chunk in buffer.chunks(batch_size) {
+        DB_BATCH_SIZE
+            .with_label_values(&["mysql", "mss_push_result"])
+            .observe(chunk.len() as f64);
+        if let Err(e) = service.record_batch(chunk).await {
+            error!("Failed to record {} push results: {e:?}", chunk.len());
+        }
+    }
+    buffer.clear();
+}
+
+#[test]
+fn test_push_result_filter_conditions() {
+    let filter = PushResultFilter {
+        begin_date: NaiveDate::from_ymd_opt(2026, 10, 1),
+        end_date: NaiveDate::from_ymd_opt(2026, 10, 15),
+        error_code: Some("500".to_string()),
+        ..Default::default()
+    };
+    let mut query_builder: QueryBuilder<MySql> =
+        QueryBuilder::new("SELECT id FROM mss_push_result");
+    filter.push_conditions(&mut query_builder);
+    assert_eq!(
+        query_builder.sql(),
+        "SELECT id FROM mss_push_result WHERE 1 = 1 AND push_time >= ? AND push_time < ? AND error_code = ?"
+    );
+}
+
+#[test]
+fn test_daily_summary_counts_records() {
+    let filter = PushResultFilter {
+        data_type: Some(2),
+        ..Default::default()
+    };
+    let sql = daily_summary_query(&filter).into_sql();
+    assert!(
+        sql.starts_with("SELECT DATE(push_time) AS day, CAST(SUM(records) AS SIGNED) AS total")
+    );
+    assert!(
+        sql.contains("WHERE mss_push_result_detail.data_id = mss_push_result.id), 1) AS records")
+    );
+    assert!(sql.ends_with(
+        "FROM mss_push_result WHERE 1 = 1 AND type = ?) AS results GROUP BY day ORDER BY day"
+    ));
 }
@@ -0,0 +1,138 @@
+use std::collections::HashSet;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sqlx::{MySqlPool, Row};
+
+use crate::db::query_runner::QueryRunner;
+use crate::models::task_run::current_run_id;
+
+/// 一次推送任务执行后各ID的结果
+#[derive(Debug, Clone, Default)]
+pub struct PushRunOutcome {
+    pub success_ids: Vec<String>,
+    pub failed_ids: Vec<String>,
+    /// MSS 返回需要休息（9019）的次数
+    pub throttle_events: u32,
+    /// 因 9019 累计等待的毫秒数
+    pub throttle_wait_ms: u64,
+}
+
+/// 与同一 hit_date 上一次执行相比的变化
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PushRunDiff {
+    /// 上次未推送、本次成功
+    pub newly_succeeded: Vec<String>,
+    /// 上次未推送、本次失败
+    pub newly_failed: Vec<String>,
+    /// 上次失败、本次成功
+    pub recovered: Vec<String>,
+    /// 上次成功、本次失败
+    pub regressed: Vec<String>,
+}
+
+impl PushRunDiff {
+    pub fn summary(&self) -> String {
+        format!(
+            "newly_succeeded={}, newly_failed={}, recovered={}, regressed={}",
+            self.newly_succeeded.len(),
+            self.newly_failed.len(),
+            self.recovered.len(),
+            self.regressed.len()
+        )
+    }
+}
+
+pub fn diff_runs(previous: &PushRunOutcome, current: &PushRunOutcome) -> PushRunDiff {
+    let previous_success: HashSet<&String> = previous.success_ids.iter().collect();
+    let previous_failed: HashSet<&String> = previous.failed_ids.iter().collect();
+
+    let mut diff = PushRunDiff::default();
+    for id in &current.success_ids {
+        if previous_failed.contains(id) {
+            diff.recovered.push(id.clone());
+        } else if !previous_success.contains(id) {
+            diff.newly_succeeded.push(id.clone());
+        }
+    }
+    for id in &current.failed_ids {
+        if previous_success.contains(id) {
+            diff.regressed.push(id.clone());
+        } else if !previous_failed.contains(id) {
+            diff.newly_failed.push(id.clone());
+        }
+    }
+    diff
+}
+
+/// 保存本次执行结果，并返回与同一任务、同一 hit_date 上一次执行的差异（首次执行返回 None）
+pub async fn record_push_run(
+    mysql_pool: &MySqlPool,
+    task_name: &str,
+    hit_date: &str,
+    outcome: &PushRunOutcome,
+) -> Result<Option<PushRunDiff>> {
+    let query = sqlx::query(
+        "SELECT CAST(success_ids AS CHAR) AS success_ids, CAST(failed_ids AS CHAR) AS failed_ids \
+         FROM mss_push_run WHERE task_name = ? AND hit_date = ? ORDER BY id DESC LIMIT 1",
+    )
+    .bind(task_name)
+    .bind(hit_date);
+    let previous = QueryRunner::new("push_run_previous")
+        .run(query.fetch_optional(mysql_pool))
+        .await
+        .context("Failed to query mss_push_run")?;
+
+    let diff = match previous {
+        Some(row) => {
+            let success_ids: String = row.try_get("success_ids")?;
+            let failed_ids: String = row.try_get("failed_ids")?;
+            let previous = PushRunOutcome {
+                success_ids: serde_json::from_str(&success_ids).unwrap_or_default(),
+                failed_ids: serde_json::from_str(&failed_ids).unwrap_or_default(),
+                ..Default::default()
+            };
+            Some(diff_runs(&previous, outcome))
+        }
+        None => None,
+    };
+
+    let query = sqlx::query(
+        "INSERT INTO mss_push_run (task_name, hit_date, success_ids, failed_ids, diff, run_id, throttle_events, throttle_wait_ms) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(task_name)
+    .bind(hit_date)
+    .bind(serde_json::to_string(&outcome.success_ids)?)
+    .bind(serde_json::to_string(&outcome.failed_ids)?)
+    .bind(diff.as_ref().map(serde_json::to_string).transpose()?)
+    .bind(current_run_id())
+    .bind(outcome.throttle_events)
+    .bind(outcome.throttle_wait_ms);
+    QueryRunner::new("push_run_insert")
+        .run(query.execute(mysql_pool))
+        .await
+        .context("Failed to insert into mss_push_run table")?;
+
+    Ok(diff)
+}
+
+#[test]
+fn test_diff_runs() {
+    let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+    let previous = PushRunOutcome {
+        success_ids: ids(&["a", "b"]),
+        failed_ids: ids(&["c", "d"]),
+        ..Default::default()
+    };
+    let current = PushRunOutcome {
+        success_ids: ids(&["a", "c", "e"]),
+        failed_ids: ids(&["b", "d", "f"]),
+        ..Default::default()
+    };
+
+    let diff = diff_runs(&previous, &current);
+    assert_eq!(diff.newly_succeeded, ids(&["e"]));
+    assert_eq!(diff.newly_failed, ids(&["f"]));
+    assert_eq!(diff.recovered, ids(&["c"]));
+    assert_eq!(diff.regressed, ids(&["b"]));
+}
@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+
+use crate::db::query_runner::QueryRunner;
+
+/// 组合推送一次执行的归档清单，序列化后的原文即签名内容
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunManifest {
+    pub run_id: Option<u64>,
+    pub task_name: String,
+    pub manifest_date: NaiveDate,
+    pub generated_at: String,
+    /// 子任务名称 -> 该类数据的计数
+    pub kinds: BTreeMap<String, KindManifest>,
+    /// 全部已推送 ID 的校验和
+    pub pushed_ids_sha256: String,
+}
+
+/// 一类数据在本次执行中的计数
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KindManifest {
+    /// 涉及的 hit_date，按 ID 推送时为空
+    pub hit_dates: Vec<String>,
+    pub fetched: u64,
+    pub pushed: u64,
+    pub failed: u64,
+    /// 已推送 ID 去重排序后按行拼接的 SHA-256
+    pub pushed_ids_sha256: String,
+}
+
+/// composite_run_manifest 中的一行
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct RunManifestRecord {
+    pub id: u64,
+    pub run_id: Option<u64>,
+    pub task_name: String,
+    pub manifest_date: NaiveDate,
+    pub location: Option<String>,
+    pub sha256: String,
+    pub signature: String,
+    /// 签名的原文，验签时直接对该字符串计算
+    pub manifest: String,
+    pub created_at: NaiveDateTime,
+}
+
+/// 记录清单的位置、哈希与签名，manifest 为签名的原文
+pub async fn record_run_manifest(
+    mysql_pool: &MySqlPool,
+    manifest: &RunManifest,
+    body: &[u8],
+    location: Option<&str>,
+    sha256: &str,
+    signature: &str,
+) -> Result<u64> {
+    let query = sqlx::query(
+        "INSERT INTO composite_run_manifest (run_id, task_name, manifest_date, location, sha256, signature, manifest) \
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(manifest.run_id)
+    .bind(&manifest.task_name)
+    .bind(manifest.manifest_date)
+    .bind(location)
+    .bind(sha256)
+    .bind(signature)
+    .bind(String::from_utf8_lossy(body).into_owned());
+    let result = QueryRunner::new("run_manifest_insert")
+        .run(query.execute(mysql_pool))
+        .await
+        .context("Failed to insert into composite_run_manifest table")?;
+    Ok(result.last_insert_id())
+}
+
+/// 按清单日期查询，按 id 排序
+pub async fn run_manifests_by_date(
+    mysql_pool: &MySqlPool,
+    date: NaiveDate,
+) -> Result<Vec<RunManifestRecord>> {
+    let query = sqlx::query_as::<_, RunManifestRecord>(
+        "SELECT id, run_id, task_name, manifest_date, location, sha256, signature, \
+         manifest, created_at FROM composite_run_manifest \
+         WHERE manifest_date = ? ORDER BY id",
+    )
+    .bind(date);
+    QueryRunner::new("run_manifest_by_date")
+        .run(query.fetch_all(mysql_pool))
+        .await
+        .context("Failed to query composite_run_manifest")
+}
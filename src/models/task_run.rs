@@ -0,0 +1,101 @@
+use std::future::Future;
+
+use anyhow::{Context, Result};
+use sqlx::MySqlPool;
+use tracing::{Instrument, info_span, warn};
+
+use crate::db::query_runner::QueryRunner;
+use crate::models::task_run_history::TaskRunRecorder;
+
+tokio::task_local! {
+    static CURRENT_RUN_ID: u64;
+}
+
+/// 在 task_run 表中登记一次执行，返回自增的 run_id。
+/// parent_id 为组合任务子任务所属的执行，triggered_by 为触发方式
+pub async fn start_task_run(
+    mysql_pool: &MySqlPool,
+    task_name: &str,
+    triggered_by: &str,
+    parent_id: Option<u64>,
+) -> Result<u64> {
+    let result = QueryRunner::new("task_run_start")
+        .run(
+            sqlx::query(
+                "INSERT INTO task_run (parent_id, task_name, triggered_by) VALUES (?, ?, ?)",
+            )
+            .bind(parent_id)
+            .bind(task_name)
+            .bind(triggered_by)
+            .execute(mysql_pool),
+        )
+        .await
+        .context("Failed to insert into task_run table")?;
+    Ok(result.last_insert_id())
+}
+
+/// 当前执行的 run_id，不在 `TaskRunRecorder` 或 `with_task_run` 范围内时为 None。
+/// 只在同一个 tokio 任务内可见，需要写入其他任务的数据应在入队前读取
+pub fn current_run_id() -> Option<u64> {
+    CURRENT_RUN_ID.try_with(|run_id| *run_id).ok()
+}
+
+/// 执行期间把 run_id 附加到 tracing span 与 `current_run_id`
+pub(crate) async fn scope_run_id<F: Future>(run_id: u64, task_name: &str, fut: F) -> F::Output {
+    let span = info_span!("task_run", run_id, task = task_name);
+    CURRENT_RUN_ID.scope(run_id, fut.instrument(span)).await
+}
+
+/// 在一次登记的执行中运行 fut。已在调度器等登记的执行范围内时沿用当前 run_id，
+/// 否则（如 binlog 缺口重放）登记一次新的执行，结束时写入状态与行数。
+/// 登记失败不影响执行，此时产物中的 run_id 为 NULL
+pub async fn with_task_run<T, F: Future<Output = Result<T>>>(
+    mysql_pool: &MySqlPool,
+    task_name: &str,
+    fut: F,
+) -> Result<T> {
+    if current_run_id().is_some() {
+        return fut.await;
+    }
+    TaskRunRecorder::new(mysql_pool.clone())
+        .record(task_name, "direct", fut)
+        .await
+        .0
+}
+
+/// 同 `with_task_run`，但成功且 `idle` 返回 true 的执行不保留记录，结束后删除登记的 task_run 行。
+/// binlog 连续同步约每秒一个周期，空闲周期不写入任何带 run_id 的数据，全部保留每天会新增约 8 万行；
+/// 处理了日志或失败的周期照常保留
+pub async fn with_task_run_discarding_idle<T, F: Future<Output = Result<T>>>(
+    mysql_pool: &MySqlPool,
+    task_name: &str,
+    fut: F,
+    idle: impl FnOnce(&T) -> bool,
+) -> Result<T> {
+    if current_run_id().is_some() {
+        return fut.await;
+    }
+    let (value, run_id) = with_task_run(mysql_pool, task_name, async {
+        let value = fut.await?;
+        Ok::<_, anyhow::Error>((value, current_run_id()))
+    })
+    .await?;
+    if let Some(run_id) = run_id.filter(|_| idle(&value)) {
+        if let Err(e) = delete_task_run(mysql_pool, run_id).await {
+            warn!("Failed to discard idle run {run_id} of {task_name}: {e:?}");
+        }
+    }
+    Ok(value)
+}
+
+async fn delete_task_run(mysql_pool: &MySqlPool, run_id: u64) -> Result<()> {
+    QueryRunner::new("task_run_discard")
+        .run(
+            sqlx::query("DELETE FROM task_run WHERE id = ?")
+                .bind(run_id)
+                .execute(mysql_pool),
+        )
+        .await
+        .context("Failed to delete from task_run table")?;
+    Ok(())
+}
@@ -0,0 +1,314 @@
+//! 执行历史：每次 TaskExecutor 执行在 task_run 中登记一行，id 即 run_id，
+//! 结束时写入状态、错误和行数计数。组合任务的子任务在父任务的记录范围内执行，记录 parent_id，行数同时累加到父任务
+
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+use tracing::warn;
+
+use crate::TaskExecutor;
+use crate::db::query_runner::QueryRunner;
+use crate::models::analytics_export::{AnalyticsExporter, TaskRunSummary};
+use crate::models::task_run::{scope_run_id, start_task_run};
+use crate::utils::timefmt;
+
+tokio::task_local! {
+    static CURRENT_RUN: Arc<RunScope>;
+}
+
+/// 执行中累加的行数计数
+#[derive(Debug, Clone, Copy)]
+pub enum RowCounter {
+    Fetched,
+    Pushed,
+    Failed,
+}
+
+#[derive(Debug, Default)]
+struct RunCounters {
+    fetched: AtomicU64,
+    pushed: AtomicU64,
+    failed: AtomicU64,
+    /// 上级执行的计数，累加时一并累加
+    parent: Option<Arc<RunCounters>>,
+}
+
+impl RunCounters {
+    fn add(&self, counter: RowCounter, rows: u64) {
+        let mut current = Some(self);
+        while let Some(counters) = current {
+            counters.get(counter).fetch_add(rows, Ordering::Relaxed);
+            current = counters.parent.as_deref();
+        }
+    }
+
+    fn get(&self, counter: RowCounter) -> &AtomicU64 {
+        match counter {
+            RowCounter::Fetched => &self.fetched,
+            RowCounter::Pushed => &self.pushed,
+            RowCounter::Failed => &self.failed,
+        }
+    }
+
+    fn load(&self, counter: RowCounter) -> u64 {
+        self.get(counter).load(Ordering::Relaxed)
+    }
+}
+
+// 行数计数也出现在作业状态接口的响应中，定义在 web::models 中
+pub use crate::web::RunTotals;
+
+impl RunTotals {
+    fn of(counters: &RunCounters) -> Self {
+        RunTotals {
+            fetched: counters.load(RowCounter::Fetched),
+            pushed: counters.load(RowCounter::Pushed),
+            failed: counters.load(RowCounter::Failed),
+        }
+    }
+}
+
+/// 一次正在记录的执行
+struct RunScope {
+    recorder: TaskRunRecorder,
+    /// task_run.id，登记失败时为 None，执行照常进行
+    run_id: Option<u64>,
+    counters: Arc<RunCounters>,
+}
+
+/// 为当前执行及其上级执行累加行数，不在记录范围内时忽略。
+/// 只在同一个 tokio 任务内可见
+pub fn count_rows(counter: RowCounter, rows: usize) {
+    let rows = u64::try_from(rows).unwrap_or(u64::MAX);
+    let _ = CURRENT_RUN.try_with(|scope| scope.counters.add(counter, rows));
+}
+
+/// 包装 TaskExecutor::execute，把每次执行登记到 task_run 并设置 current_run_id。记录失败只告警，不影响执行
+#[derive(Clone)]
+pub struct TaskRunRecorder {
+    mysql_pool: MySqlPool,
+    analytics: Option<Arc<AnalyticsExporter>>,
+}
+
+impl TaskRunRecorder {
+    pub fn new(mysql_pool: MySqlPool) -> Self {
+        Self {
+            mysql_pool,
+            analytics: None,
+        }
+    }
+
+    /// 执行结束后同时把汇总导出到 ClickHouse，None 时不导出
+    pub fn with_analytics(mut self, analytics: Option<Arc<AnalyticsExporter>>) -> Self {
+        self.analytics = analytics;
+        self
+    }
+
+    /// 执行任务并记录结果，在其他记录范围内调用时作为其子执行
+    pub async fn run(&self, task: &dyn TaskExecutor, triggered_by: &str) -> Result<()> {
+        self.run_counted(task, triggered_by).await.0
+    }
+
+    /// 同 `run`，同时返回本次执行的行数计数
+    pub async fn run_counted(
+        &self,
+        task: &dyn TaskExecutor,
+        triggered_by: &str,
+    ) -> (Result<()>, RunTotals) {
+        self.record(task.name(), triggered_by, task.execute()).await
+    }
+
+    /// 登记一次执行并在其范围内运行 fut，结束后记录结果，返回结果与行数计数
+    pub async fn record<T, F: Future<Output = Result<T>>>(
+        &self,
+        task_name: &str,
+        triggered_by: &str,
+        fut: F,
+    ) -> (Result<T>, RunTotals) {
+        let parent = CURRENT_RUN.try_with(Arc::clone).ok();
+        let parent_id = parent.as_ref().and_then(|parent| parent.run_id);
+        let counters = Arc::new(RunCounters {
+            parent: parent.map(|parent| Arc::clone(&parent.counters)),
+            ..Default::default()
+        });
+        let run_id =
+            match start_task_run(&self.mysql_pool, task_name, triggered_by, parent_id).await {
+                Ok(run_id) => Some(run_id),
+                Err(e) => {
+                    warn!("Failed to record run history for {task_name}: {e:?}");
+                    None
+                }
+            };
+        let scope = Arc::new(RunScope {
+            recorder: self.clone(),
+            run_id,
+            counters,
+        });
+
+        let started_at = timefmt::now_local();
+        let started = Instant::now();
+        let scoped = CURRENT_RUN.scope(Arc::clone(&scope), fut);
+        let result = match run_id {
+            Some(run_id) => scope_run_id(run_id, task_name, scoped).await,
+            None => scoped.await,
+        };
+        let elapsed = started.elapsed();
+        let (status, error_message) = run_status(&result);
+        if let Some(run_id) = run_id {
+            if let Err(e) = self
+                .finish(
+                    run_id,
+                    elapsed,
+                    status,
+                    error_message.as_deref(),
+                    &scope.counters,
+                )
+                .await
+            {
+                warn!("Failed to record run history for {task_name}: {e:?}");
+            }
+        }
+        let totals = RunTotals::of(&scope.counters);
+        if let Some(analytics) = &self.analytics {
+            let summary = TaskRunSummary {
+                task_name: task_name.to_string(),
+                triggered_by: triggered_by.to_string(),
+                status,
+                error_message,
+                rows_fetched: totals.fetched,
+                rows_pushed: totals.pushed,
+                rows_failed: totals.failed,
+                started_at,
+                finished_at: timefmt::now_local(),
+                duration_ms: elapsed_ms(elapsed),
+            };
+            analytics.export_task_run(&summary).await;
+        }
+        (result, totals)
+    }
+
+    /// 确认能从连接池取得连接，连接池耗尽时返回错误。调度器在开始运行前调用
+    pub async fn check_pool(&self) -> Result<()> {
+        self.mysql_pool
+            .acquire()
+            .await
+            .context("Failed to acquire MySQL connection before run")?;
+        Ok(())
+    }
+
+    /// 在当前记录的执行下运行子任务，不在记录范围内时直接执行
+    pub async fn run_nested(task: &dyn TaskExecutor, triggered_by: &str) -> Result<()> {
+        match CURRENT_RUN.try_with(|scope| scope.recorder.clone()) {
+            Ok(recorder) => recorder.run(task, triggered_by).await,
+            Err(_) => task.execute().await,
+        }
+    }
+
+    async fn finish(
+        &self,
+        run_id: u64,
+        elapsed: Duration,
+        status: &str,
+        error_message: Option<&str>,
+        counters: &RunCounters,
+    ) -> Result<()> {
+        let query = sqlx::query(
+            "UPDATE task_run SET status = ?, error_message = ?, rows_fetched = ?, rows_pushed = ?, \
+             rows_failed = ?, finished_at = NOW(), duration_ms = ? WHERE id = ?",
+        )
+        .bind(status)
+        .bind(error_message)
+        .bind(counters.load(RowCounter::Fetched))
+        .bind(counters.load(RowCounter::Pushed))
+        .bind(counters.load(RowCounter::Failed))
+        .bind(elapsed_ms(elapsed))
+        .bind(run_id);
+        QueryRunner::new("task_run_finish")
+            .run(query.execute(&self.mysql_pool))
+            .await
+            .context("Failed to update task_run table")?;
+        Ok(())
+    }
+}
+
+/// 执行结果对应的状态与错误信息
+fn run_status<T>(result: &Result<T>) -> (&'static str, Option<String>) {
+    match result {
+        Ok(_) => ("success", None),
+        Err(e) => ("failed", Some(format!("{e:#}"))),
+    }
+}
+
+fn elapsed_ms(elapsed: Duration) -> u64 {
+    u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// task_run 中的一次执行
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct TaskRunHistory {
+    pub id: u64,
+    pub parent_id: Option<u64>,
+    pub task_name: String,
+    pub triggered_by: String,
+    pub status: String,
+    pub error_message: Option<String>,
+    pub rows_fetched: u64,
+    pub rows_pushed: u64,
+    pub rows_failed: u64,
+    pub started_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+    pub duration_ms: Option<u64>,
+}
+
+/// 按开始时间倒序列出最近的执行，task_name / status 为 None 时不过滤
+pub async fn recent_runs(
+    mysql_pool: &MySqlPool,
+    task_name: Option<&str>,
+    status: Option<&str>,
+    limit: u32,
+) -> Result<Vec<TaskRunHistory>> {
+    let mut query_builder: QueryBuilder<MySql> = QueryBuilder::new(
+        "SELECT id, parent_id, task_name, triggered_by, status, error_message, rows_fetched, \
+         rows_pushed, rows_failed, started_at, finished_at, duration_ms FROM task_run WHERE 1 = 1",
+    );
+    if let Some(task_name) = task_name {
+        query_builder.push(" AND task_name = ").push_bind(task_name);
+    }
+    if let Some(status) = status {
+        query_builder.push(" AND status = ").push_bind(status);
+    }
+    query_builder
+        .push(" ORDER BY id DESC LIMIT ")
+        .push_bind(limit);
+    QueryRunner::new("task_run_recent")
+        .run(
+            query_builder
+                .build_query_as::<TaskRunHistory>()
+                .fetch_all(mysql_pool),
+        )
+        .await
+        .context("Failed to query task_run")
+}
+
+#[test]
+fn test_run_counters_propagate_to_parent() {
+    let parent = Arc::new(RunCounters::default());
+    let child = RunCounters {
+        parent: Some(Arc::clone(&parent)),
+        ..Default::default()
+    };
+    child.add(RowCounter::Fetched, 3);
+    child.add(RowCounter::Pushed, 2);
+    parent.add(RowCounter::Failed, 1);
+    assert_eq!(child.load(RowCounter::Fetched), 3);
+    assert_eq!(child.load(RowCounter::Failed), 0);
+    assert_eq!(parent.load(RowCounter::Fetched), 3);
+    assert_eq!(parent.load(RowCounter::Pushed), 2);
+    assert_eq!(parent.load(RowCounter::Failed), 1);
+}
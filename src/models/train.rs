@@ -206,6 +206,72 @@ pub struct ArchiveData {
     pub psn_archive_status: Option<String>,
 }
 
+/// 推送记录的 ID、MSS 报文中的键名与数据种类，各数据结构与 DynamicPsnData 统一实现。
+/// kind 不区分地区，四川数据与全国数据结构相同
+pub trait PsnRecord {
+    /// 业务主键，推送结果、状态回写与重试队列都按它标识记录
+    fn id(&self) -> &str;
+    /// MSS 报文中包裹记录的键名
+    fn key_name(&self) -> &'static str;
+    fn kind(&self) -> PsnDataKind;
+}
+
+impl PsnRecord for ClassData {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn key_name(&self) -> &'static str {
+        "classData"
+    }
+
+    fn kind(&self) -> PsnDataKind {
+        PsnDataKind::Class
+    }
+}
+
+impl PsnRecord for LecturerData {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn key_name(&self) -> &'static str {
+        "lecturerData"
+    }
+
+    fn kind(&self) -> PsnDataKind {
+        PsnDataKind::Lecturer
+    }
+}
+
+impl PsnRecord for TrainingData {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn key_name(&self) -> &'static str {
+        "psnTrainingData"
+    }
+
+    fn kind(&self) -> PsnDataKind {
+        PsnDataKind::Training
+    }
+}
+
+impl PsnRecord for ArchiveData {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn key_name(&self) -> &'static str {
+        "psnArchiveData"
+    }
+
+    fn kind(&self) -> PsnDataKind {
+        PsnDataKind::Archive
+    }
+}
+
 // 定义一个枚举来封装所有可能的数据类型
 #[derive(Debug, Serialize, Clone)] // Enum 也需要 Serialize
 #[serde(untagged)] // 使用 untagged 序列化，这样它不会在 JSON 中添加额外的标签
@@ -216,29 +282,81 @@ pub enum DynamicPsnData {
     Archive(ArchiveData),
 }
 
+impl PsnRecord for DynamicPsnData {
+    fn id(&self) -> &str {
+        self.record().id()
+    }
+
+    fn key_name(&self) -> &'static str {
+        self.record().key_name()
+    }
+
+    fn kind(&self) -> PsnDataKind {
+        self.record().kind()
+    }
+}
+
 impl DynamicPsnData {
-    // 辅助方法，根据枚举变体返回对应的动态键名
-    pub fn get_key_name(&self) -> &'static str {
+    fn record(&self) -> &dyn PsnRecord {
+        match self {
+            DynamicPsnData::Class(data) => data,
+            DynamicPsnData::Lecturer(data) => data,
+            DynamicPsnData::Training(data) => data,
+            DynamicPsnData::Archive(data) => data,
+        }
+    }
+
+    // 删除通知复用同一数据结构，只修改 operation
+    pub fn set_operation(&mut self, operation: &str) {
+        let target = match self {
+            DynamicPsnData::Class(data) => &mut data.operation,
+            DynamicPsnData::Lecturer(data) => &mut data.operation,
+            DynamicPsnData::Training(data) => &mut data.operation,
+            DynamicPsnData::Archive(data) => &mut data.operation,
+        };
+        *target = operation.to_string();
+    }
+
+    /// 按数据种类从 JSON 还原（untagged 枚举无法直接反序列化），供重试队列使用
+    pub fn from_value(kind: PsnDataKind, value: serde_json::Value) -> serde_json::Result<Self> {
+        Ok(match kind {
+            PsnDataKind::Class | PsnDataKind::ClassSc => {
+                DynamicPsnData::Class(serde_json::from_value(value)?)
+            }
+            PsnDataKind::Lecturer | PsnDataKind::LecturerSc => {
+                DynamicPsnData::Lecturer(serde_json::from_value(value)?)
+            }
+            PsnDataKind::Training | PsnDataKind::TrainingSc => {
+                DynamicPsnData::Training(serde_json::from_value(value)?)
+            }
+            PsnDataKind::Archive | PsnDataKind::ArchiveSc => {
+                DynamicPsnData::Archive(serde_json::from_value(value)?)
+            }
+        })
+    }
+
+    // 记录所属的 MSS 组织编码，用于按省份统计推送结果；讲师与人员清单不带组织
+    pub fn get_org_id(&self) -> Option<&str> {
         match self {
-            DynamicPsnData::Class(_) => "classData",
-            DynamicPsnData::Lecturer(_) => "lecturerData",
-            DynamicPsnData::Training(_) => "psnTrainingData",
-            DynamicPsnData::Archive(_) => "psnArchiveData",
+            DynamicPsnData::Class(data) => data.org_id.as_deref(),
+            DynamicPsnData::Archive(data) => data.user_org_id.as_deref(),
+            DynamicPsnData::Lecturer(_) | DynamicPsnData::Training(_) => None,
         }
     }
 
-    pub fn get_data_id(&self) -> &str {
+    // MSS 返回结果中用于标识记录的字段值（与 PushResultParser 中的 id 字段一致）
+    pub fn get_result_id(&self) -> Option<&str> {
         match self {
-            DynamicPsnData::Class(data) => &data.id,
-            DynamicPsnData::Lecturer(data) => &data.id,
-            DynamicPsnData::Training(data) => &data.id,
-            DynamicPsnData::Archive(data) => &data.id,
+            DynamicPsnData::Class(data) => Some(&data.training_id),
+            DynamicPsnData::Lecturer(data) => data.course_id.as_deref(),
+            DynamicPsnData::Training(data) => data.user_id.as_deref(),
+            DynamicPsnData::Archive(data) => data.user_id.as_deref(),
         }
     }
 }
 
 // 新增：表示 DynamicPsnData 的种类，不包含实际数据
-#[derive(Debug, Clone, Copy, PartialEq, Eq)] // 需要 Copy trait 方便传递
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)] // 需要 Copy trait 方便传递
 pub enum PsnDataKind {
     Class,
     Lecturer,
@@ -264,4 +382,78 @@ impl PsnDataKind {
             PsnDataKind::ArchiveSc => "PsnArchiveScPushTask",
         }
     }
+
+    /// 是否为四川数据（*_SC_* 表）
+    pub fn is_sc(&self) -> bool {
+        matches!(
+            self,
+            PsnDataKind::ClassSc
+                | PsnDataKind::LecturerSc
+                | PsnDataKind::TrainingSc
+                | PsnDataKind::ArchiveSc
+        )
+    }
+
+    /// 去掉地区后的数据种类，如 ClassSc -> Class
+    pub fn base_kind(&self) -> Self {
+        match self {
+            PsnDataKind::ClassSc => PsnDataKind::Class,
+            PsnDataKind::LecturerSc => PsnDataKind::Lecturer,
+            PsnDataKind::TrainingSc => PsnDataKind::Training,
+            PsnDataKind::ArchiveSc => PsnDataKind::Archive,
+            kind => *kind,
+        }
+    }
+
+    /// 接口路径与落库时使用的名称，与 from_name 互逆
+    pub fn as_name(&self) -> &'static str {
+        match self {
+            PsnDataKind::Class => "class",
+            PsnDataKind::Lecturer => "lecturer",
+            PsnDataKind::Training => "training",
+            PsnDataKind::Archive => "archive",
+            PsnDataKind::ClassSc => "class_sc",
+            PsnDataKind::LecturerSc => "lecturer_sc",
+            PsnDataKind::TrainingSc => "training_sc",
+            PsnDataKind::ArchiveSc => "archive_sc",
+        }
+    }
+
+    // 从接口路径中的名称解析，如 class、lecturer_sc，不区分大小写
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "class" => Some(PsnDataKind::Class),
+            "lecturer" => Some(PsnDataKind::Lecturer),
+            "training" => Some(PsnDataKind::Training),
+            "archive" => Some(PsnDataKind::Archive),
+            "class_sc" => Some(PsnDataKind::ClassSc),
+            "lecturer_sc" => Some(PsnDataKind::LecturerSc),
+            "training_sc" => Some(PsnDataKind::TrainingSc),
+            "archive_sc" => Some(PsnDataKind::ArchiveSc),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn test_psn_record() {
+    let lecturer = LecturerData {
+        id: "lec-1".to_string(),
+        ..Default::default()
+    };
+    let archive = DynamicPsnData::Archive(ArchiveData {
+        id: "arc-1".to_string(),
+        ..Default::default()
+    });
+    assert_eq!(
+        (lecturer.id(), lecturer.key_name(), lecturer.kind()),
+        ("lec-1", "lecturerData", PsnDataKind::Lecturer)
+    );
+    assert_eq!(
+        (archive.id(), archive.key_name(), archive.kind()),
+        ("arc-1", "psnArchiveData", PsnDataKind::Archive)
+    );
+    // 四川数据与全国数据共用结构，kind 与去掉地区后的种类一致
+    let training = DynamicPsnData::Training(TrainingData::default());
+    assert_eq!(training.kind(), PsnDataKind::TrainingSc.base_kind());
 }
@@ -1 +1,2 @@
+pub mod mss_response;
 pub mod push_result_parser;
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+/// MSS 推送接口成功时返回的 descCode
+pub const SUCCESS_CODE: &str = "200";
+
+/// MSS 推送接口的响应外层
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MssEnvelope {
+    #[serde(rename = "descCode", deserialize_with = "lenient_string")]
+    pub desc_code: Option<String>,
+    #[serde(rename = "descMsg", deserialize_with = "lenient_string")]
+    pub desc_msg: Option<String>,
+    /// dcoos 网关层返回的 code（例如 9019 表示需要休息）
+    #[serde(deserialize_with = "lenient_string")]
+    pub code: Option<String>,
+    /// 失败时为 JSON 字符串形式的 MssErrorData，部分环境直接返回对象
+    pub data: Option<Value>,
+}
+
+impl MssEnvelope {
+    pub fn is_success(&self) -> bool {
+        self.desc_code.as_deref() == Some(SUCCESS_CODE)
+    }
+
+    /// 解析 data 字段中的错误明细
+    pub fn error_data(&self) -> Result<MssErrorData, String> {
+        match &self.data {
+            Some(Value::String(raw)) => serde_json::from_str(raw)
+                .map_err(|e| format!("Failed to parse JSON: {e:?}, Input: {raw}")),
+            Some(value @ Value::Object(_)) => serde_json::from_value(value.clone())
+                .map_err(|e| format!("Failed to parse JSON: {e:?}, Input: {value}")),
+            _ => Err(format!("Missing 'data' field in result JSON: {self:?}")),
+        }
+    }
+}
+
+/// 推送数据的种类，对应请求/响应中的数组键名
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MssRecordKind {
+    Class,
+    Lecturer,
+    PsnTraining,
+    PsnArchive,
+}
+
+impl MssRecordKind {
+    pub const ALL: [MssRecordKind; 4] = [
+        MssRecordKind::Class,
+        MssRecordKind::Lecturer,
+        MssRecordKind::PsnTraining,
+        MssRecordKind::PsnArchive,
+    ];
+
+    pub fn key_name(&self) -> &'static str {
+        match self {
+            MssRecordKind::Class => "classData",
+            MssRecordKind::Lecturer => "lecturerData",
+            MssRecordKind::PsnTraining => "psnTrainingData",
+            MssRecordKind::PsnArchive => "psnArchiveData",
+        }
+    }
+
+    /// mss_push_result.type 列的取值
+    pub fn data_type(&self) -> i32 {
+        match self {
+            MssRecordKind::Class => 1,
+            MssRecordKind::Lecturer => 2,
+            MssRecordKind::PsnTraining => 3,
+            MssRecordKind::PsnArchive => 4,
+        }
+    }
+
+    /// 该种类记录的标识字段值（写入 mss_push_result_detail.result_id）
+    pub fn record_id<'a>(&self, record: &'a MssRecord) -> Option<&'a str> {
+        match self {
+            MssRecordKind::Class => record.training_id.as_deref(),
+            MssRecordKind::Lecturer => record.course_id.as_deref(),
+            MssRecordKind::PsnTraining | MssRecordKind::PsnArchive => record.user_id.as_deref(),
+        }
+    }
+}
+
+/// 请求或错误明细中的单条记录，只保留解析结果需要的字段
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MssRecord {
+    #[serde(rename = "trainingId", deserialize_with = "lenient_string")]
+    pub training_id: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    pub course_id: Option<String>,
+    #[serde(rename = "userId", deserialize_with = "lenient_string")]
+    pub user_id: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    pub errormsg: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    pub errorcode: Option<String>,
+}
+
+/// 按种类分组的记录集合，请求体与 MSS 返回的错误明细结构相同
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MssRecordSet {
+    #[serde(rename = "classData")]
+    pub class_data: Vec<MssRecord>,
+    #[serde(rename = "lecturerData")]
+    pub lecturer_data: Vec<MssRecord>,
+    #[serde(rename = "psnTrainingData")]
+    pub psn_training_data: Vec<MssRecord>,
+    #[serde(rename = "psnArchiveData")]
+    pub psn_archive_data: Vec<MssRecord>,
+}
+
+/// 推送给 MSS 的请求体
+pub type MssPushRequest = MssRecordSet;
+/// MSS 拒绝时 data 字段中的错误明细
+pub type MssErrorData = MssRecordSet;
+
+impl MssRecordSet {
+    pub fn records_of(&self, kind: MssRecordKind) -> &[MssRecord] {
+        match kind {
+            MssRecordKind::Class => &self.class_data,
+            MssRecordKind::Lecturer => &self.lecturer_data,
+            MssRecordKind::PsnTraining => &self.psn_training_data,
+            MssRecordKind::PsnArchive => &self.psn_archive_data,
+        }
+    }
+
+    /// 按 classData、lecturerData、psnTrainingData、psnArchiveData 的顺序遍历所有记录
+    pub fn iter(&self) -> impl Iterator<Item = (MssRecordKind, &MssRecord)> {
+        MssRecordKind::ALL
+            .into_iter()
+            .flat_map(move |kind| self.records_of(kind).iter().map(move |r| (kind, r)))
+    }
+}
+
+/// MSS 的编码字段有时是数字有时是字符串，统一按字符串处理
+fn lenient_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Option::<Value>::deserialize(deserializer)? {
+        Some(Value::String(s)) => Some(s),
+        Some(Value::Number(n)) => Some(n.to_string()),
+        Some(Value::Bool(b)) => Some(b.to_string()),
+        _ => None,
+    })
+}
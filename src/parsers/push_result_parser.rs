@@ -1,258 +1,340 @@
-use chrono::Local;
-use serde_json::Value;
-use sqlx::MySqlPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::NaiveDateTime;
+use serde::de::DeserializeOwned;
 use tracing::{error, info};
 use uuid::Uuid;
 
-use crate::models::push_result::{MssPushResult, MssPushResultDetail, PushResultService};
-
-const SUCCESS_CODE: &str = "200";
+use crate::models::push_result::{
+    MssPushResult, MssPushResultDetail, PushResultWriter, PushTelemetry,
+};
+use crate::models::task_run::current_run_id;
+use crate::parsers::mss_response::{
+    MssEnvelope, MssErrorData, MssPushRequest, MssRecordKind, SUCCESS_CODE,
+};
+use crate::utils::clock::Clock;
 
-const REQUEST_KEYS: [(&str, i32, &str, &str); 4] = [
-    ("classData", 1, "trainingId", "train_id"),
-    ("lecturerData", 2, "course_id", "course_id"),
-    ("psnTrainingData", 3, "userId", "user_id"),
-    ("psnArchiveData", 4, "userId", "user_id"),
-];
+/// MSS 拒绝推送时返回给调用方的错误，`record_errors` 为 被拒绝记录的ID -> 错误信息
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{message}")]
+pub struct PushRejection {
+    pub message: String,
+    pub record_errors: HashMap<String, String>,
+}
 
-const ERROR_KEYS: [(&str, i32, &str); 4] = [
-    ("classData", 1, "trainingId"),
-    ("lecturerData", 2, "course_id"),
-    ("psnTrainingData", 3, "userId"),
-    ("psnArchiveData", 4, "userId"),
-];
+impl From<String> for PushRejection {
+    fn from(message: String) -> Self {
+        PushRejection {
+            message,
+            record_errors: HashMap::new(),
+        }
+    }
+}
 
 pub struct PushResultParser {
-    push_result_service: PushResultService,
+    push_result_writer: Arc<PushResultWriter>,
+    clock: Arc<dyn Clock>, // 结果的 push_time 按它记录
 }
 
 impl PushResultParser {
-    pub fn new(mysql_pool: MySqlPool) -> Self {
+    pub fn new(push_result_writer: Arc<PushResultWriter>, clock: Arc<dyn Clock>) -> Self {
         PushResultParser {
-            push_result_service: PushResultService::new(mysql_pool),
+            push_result_writer,
+            clock,
         }
     }
-    pub async fn parse(&self, data: &str, result: &str) -> Result<(), String> {
-        info!("Parsing push result beginning");
 
-        let mut push_result = MssPushResult {
-            id: Uuid::new_v4().to_string(),
-            push_time: Local::now().naive_local(),
-            train_id: None,
-            course_id: None,
-            user_id: None,
-            data_type: None,
-            error_msg: None,
-            error_code: None,
-        };
-        let mut result_details = Vec::new();
+    /// 解析一次推送的响应并记录结果，请求中的每条记录各记一条结果，批量推送时按条区分成功与失败
+    pub async fn parse(
+        &self,
+        data: &str,
+        result: &str,
+        telemetry: &PushTelemetry,
+    ) -> Result<(), PushRejection> {
+        info!("Parsing push result beginning");
 
         // 1. 解析 'result' JSON
-        let result_data = match self.parse_json(result) {
+        let envelope: MssEnvelope = match parse_json(result) {
             Ok(val) => val,
             Err(e) => {
-                push_result.error_code = Some("500".into());
-                return self
-                    .handle_parse_error(&mut push_result, &result_details, e)
-                    .await;
+                return Err(self
+                    .record_failure(data, Some("500".into()), e, telemetry)
+                    .await
+                    .into());
             }
         };
 
-        push_result.error_code = result_data
-            .get("descCode")
-            .and_then(Value::as_str)
-            .map(ToString::to_string);
-
-        // 2. 解析请求数据JSON
-        let request_data = match self.parse_json(data) {
+        // 2. 解析请求数据JSON，每条记录对应一条结果
+        let request: MssPushRequest = match parse_json(data) {
             Ok(val) => val,
             Err(e) => {
-                return self
-                    .handle_parse_error(&mut push_result, &result_details, e)
-                    .await;
+                return Err(self
+                    .record_failure(data, envelope.desc_code.clone(), e, telemetry)
+                    .await
+                    .into());
             }
         };
+        let mut results = record_results(&request, telemetry, self.clock.now_local());
 
-        // 3. 从请求数据中提取信息
-        Self::extract_request_info(&request_data, &mut push_result, &mut result_details);
-
-        // 4. 处理成功情况
-        if push_result.error_code.as_deref() == Some(SUCCESS_CODE) {
-            self.record_result(&push_result, &result_details).await;
+        // 3. 处理成功情况
+        if envelope.is_success() {
+            for (push_result, _) in &mut results {
+                push_result.error_code = envelope.desc_code.clone();
+            }
             info!(
-                "Parsing push result completed successfully. Result ID: {}",
-                push_result.id
+                "Parsing push result completed successfully for {} records.",
+                results.len()
             );
+            self.record_results(results).await;
             return Ok(());
         }
 
-        // 5. 处理失败情况
-        if let Err(e) = self
-            .handle_failure(&result_data, &mut push_result, &mut result_details)
-            .await
-        {
-            self.record_result(&push_result, &result_details).await;
-            return Err(e);
+        // 4. 处理失败情况
+        let error_data = match envelope.error_data() {
+            Ok(error_data) => error_data,
+            Err(e) => {
+                error!("{e}");
+                for (push_result, _) in &mut results {
+                    push_result.error_code = envelope.desc_code.clone();
+                    push_result.error_msg = Some(e.clone());
+                }
+                self.record_results(results).await;
+                return Err(e.into());
+            }
+        };
+        let rejected = extract_error_info(&error_data);
+        if rejected.len() > 1 {
+            info!("MSS rejected {} records in one response.", rejected.len());
         }
 
-        // 6. 记录失败结果
-        self.record_result(&push_result, &result_details).await;
+        // 5. 错误明细中的记录失败；有明细时其余记录视为已接收，没有明细时整批失败
+        for (push_result, details) in &mut results {
+            let result_id = details
+                .first()
+                .and_then(|detail| detail.result_id.as_deref());
+            match result_id.and_then(|id| rejected.get(id)) {
+                Some(record_error) => {
+                    push_result.error_code = record_error
+                        .errorcode
+                        .clone()
+                        .or_else(|| envelope.desc_code.clone());
+                    push_result.error_msg = record_error.errormsg.clone();
+                }
+                None if !rejected.is_empty() => {
+                    push_result.error_code = Some(SUCCESS_CODE.to_string());
+                }
+                None => push_result.error_code = envelope.desc_code.clone(),
+            }
+        }
         info!(
-            "Parsing push result completed with error. Result ID: {}",
-            push_result.id
+            "Parsing push result completed with error: {} of {} records rejected.",
+            rejected.len(),
+            results.len()
         );
+        self.record_results(results).await;
 
-        // 7.返回错误信息
-        Err(push_result.error_msg.clone().unwrap_or_else(|| {
-            format!(
-                "Push failed with code: {}",
-                push_result.error_code.as_deref().unwrap_or("UNKNOWN")
-            )
-        }))
+        // 6.返回错误信息以及每条被拒绝记录的错误，错误信息取第一条被拒绝的记录
+        let first_error = error_data
+            .iter()
+            .find_map(|(kind, record)| kind.record_id(record).and(Some(record)));
+        let message = first_error
+            .and_then(|record| record.errormsg.clone())
+            .unwrap_or_else(|| {
+                format!(
+                    "Push failed with code: {}",
+                    first_error
+                        .and_then(|record| record.errorcode.as_deref())
+                        .or(envelope.desc_code.as_deref())
+                        .unwrap_or("UNKNOWN")
+                )
+            });
+        Err(PushRejection {
+            message,
+            record_errors: rejected
+                .into_iter()
+                .map(|(id, record)| (id, record_error_reason(&record)))
+                .collect(),
+        })
     }
 
-    /// 从请求数据中提取信息
-    fn extract_request_info(
-        request_data: &Value,
-        push_result: &mut MssPushResult,
-        result_details: &mut Vec<MssPushResultDetail>,
-    ) {
-        for &(key, data_type_val, id_field, result_field) in &REQUEST_KEYS {
-            if let Some(array) = request_data.get(key).and_then(Value::as_array)
-                && let Some(obj) = array.first().and_then(Value::as_object)
-                && let Some(id_val) = obj.get(id_field).and_then(Value::as_str)
-            {
-                push_result.data_type = Some(data_type_val);
-
-                match result_field {
-                    "train_id" => push_result.train_id = Some(id_val.to_string()),
-                    "course_id" => {
-                        push_result.course_id = Some(id_val.to_string());
-                        push_result.train_id = obj
-                            .get("trainingId")
-                            .and_then(Value::as_str)
-                            .map(ToString::to_string);
-                    }
-                    "user_id" => {
-                        push_result.user_id = Some(id_val.to_string());
-                        push_result.train_id = obj
-                            .get("trainingId")
-                            .and_then(Value::as_str)
-                            .map(ToString::to_string);
-                    }
-                    _ => (),
-                }
-
-                result_details.push(MssPushResultDetail {
-                    data_id: push_result.id.clone(),
-                    result_id: Some(id_val.to_string()),
-                });
-            }
+    /// 响应或请求无法解析时记录失败：请求可以解析时每条记录各记一条，否则只记一条没有详情的结果
+    async fn record_failure(
+        &self,
+        data: &str,
+        error_code: Option<String>,
+        error: String,
+        telemetry: &PushTelemetry,
+    ) -> String {
+        error!("{}", error);
+        let push_time = self.clock.now_local();
+        let mut results = parse_json::<MssPushRequest>(data)
+            .map(|request| record_results(&request, telemetry, push_time))
+            .unwrap_or_default();
+        if results.is_empty() {
+            results.push((new_push_result(telemetry, push_time), Vec::new()));
+        }
+        for (push_result, _) in &mut results {
+            push_result.error_code = error_code.clone();
+            push_result.error_msg = Some(error.clone());
         }
+        self.record_results(results).await;
+        error
     }
 
-    /// 解析JSON字符串
-    fn parse_json(&self, input: &str) -> Result<Value, String> {
-        serde_json::from_str(input)
-            .map_err(|e| format!("Failed to parse JSON: {e:?}, Input: {input}"))
+    /// 将结果交给后台写入器，不在推送路径上等待数据库写入
+    async fn record_results(&self, results: Vec<(MssPushResult, Vec<MssPushResultDetail>)>) {
+        for (push_result, result_details) in results {
+            self.push_result_writer
+                .record(push_result, result_details)
+                .await;
+        }
     }
+}
 
-    /// 处理结果解析错误
-    async fn handle_parse_error(
-        &self,
-        push_result: &mut MssPushResult,
-        result_details: &[MssPushResultDetail],
-        error: String,
-    ) -> Result<(), String> {
-        error!("{}", error);
-        push_result.error_msg = Some(error.clone());
-        self.record_result(push_result, result_details).await;
-        Err(error)
+fn new_push_result(telemetry: &PushTelemetry, push_time: NaiveDateTime) -> MssPushResult {
+    MssPushResult {
+        id: Uuid::new_v4().to_string(),
+        push_time,
+        train_id: None,
+        course_id: None,
+        user_id: None,
+        data_type: None,
+        error_msg: None,
+        error_code: None,
+        duration_ms: Some(telemetry.duration_ms),
+        http_status: telemetry.http_status.map(i32::from),
+        attempt_count: Some(telemetry.attempt_count as i32),
+        endpoint: Some(telemetry.endpoint.clone()),
+        run_id: current_run_id(),
     }
+}
 
-    /// 处理失败响应
-    async fn handle_failure(
-        &self,
-        result_data: &Value,
-        push_result: &mut MssPushResult,
-        result_details: &mut Vec<MssPushResultDetail>,
-    ) -> Result<(), String> {
-        let raw_data_str = result_data
-            .get("data")
-            .and_then(Value::as_str)
-            .ok_or_else(|| {
-                let err = format!("Missing 'data' field in result JSON: {result_data:?}");
-                error!("{err}");
-                push_result.error_msg = Some(err.clone());
-                err
-            })?;
+/// 解析JSON字符串
+fn parse_json<T: DeserializeOwned>(input: &str) -> Result<T, String> {
+    serde_json::from_str(input).map_err(|e| format!("Failed to parse JSON: {e:?}, Input: {input}"))
+}
+
+/// 请求中的每条记录生成一条结果及指向该记录的详情，结果码由调用方按响应填写。
+/// 没有标识字段的记录无法对应到数据，跳过
+fn record_results(
+    request: &MssPushRequest,
+    telemetry: &PushTelemetry,
+    push_time: NaiveDateTime,
+) -> Vec<(MssPushResult, Vec<MssPushResultDetail>)> {
+    request
+        .iter()
+        .filter_map(|(kind, record)| {
+            let id_val = kind.record_id(record)?;
+            let mut push_result = new_push_result(telemetry, push_time);
+            push_result.data_type = Some(kind.data_type());
+            match kind {
+                MssRecordKind::Class => push_result.train_id = Some(id_val.to_string()),
+                MssRecordKind::Lecturer => {
+                    push_result.course_id = Some(id_val.to_string());
+                    push_result.train_id = record.training_id.clone();
+                }
+                MssRecordKind::PsnTraining | MssRecordKind::PsnArchive => {
+                    push_result.user_id = Some(id_val.to_string());
+                    push_result.train_id = record.training_id.clone();
+                }
+            }
+            let detail = MssPushResultDetail {
+                data_id: push_result.id.clone(),
+                result_id: Some(id_val.to_string()),
+            };
+            Some((push_result, vec![detail]))
+        })
+        .collect()
+}
 
-        let error_data = self.parse_json(raw_data_str).map_err(|e| {
-            error!("{e}");
-            push_result.error_msg = Some(e.clone());
-            e
-        })?;
+/// 从失败响应的错误明细中提取被拒绝的记录：记录 ID -> 记录（含 errormsg / errorcode）
+fn extract_error_info(error_data: &MssErrorData) -> HashMap<String, MssRecord> {
+    error_data
+        .iter()
+        .filter_map(|(kind, record)| {
+            let id_val = kind.record_id(record)?;
+            Some((id_val.to_string(), record.clone()))
+        })
+        .collect()
+}
 
-        // 重置结果详情
-        result_details.clear();
-        push_result.train_id = None;
-        push_result.course_id = None;
-        push_result.user_id = None;
-        push_result.data_type = None;
+/// 被拒绝记录的错误信息，没有 errormsg 时使用 errorcode
+fn record_error_reason(record: &MssRecord) -> String {
+    record
+        .errormsg
+        .as_deref()
+        .or(record.errorcode.as_deref())
+        .unwrap_or("UNKNOWN")
+        .to_string()
+}
 
-        // 从错误数据中提取信息
-        if let Some(error_data_obj) = error_data.as_object() {
-            for &(key, data_type_val, id_field) in &ERROR_KEYS {
-                if let Some(array) = error_data_obj.get(key).and_then(Value::as_array)
-                    && let Some(obj) = array.first().and_then(Value::as_object)
-                {
-                    push_result.data_type = Some(data_type_val);
+#[test]
+fn test_parse_mss_responses() {
+    let success: MssEnvelope =
+        parse_json(include_str!("../../tests/fixtures/mss/success.json")).unwrap();
+    assert!(success.is_success());
 
-                    // 提取ID字段
-                    if let Some(id_val) = obj.get(id_field).and_then(Value::as_str) {
-                        result_details.push(MssPushResultDetail {
-                            data_id: push_result.id.clone(),
-                            result_id: Some(id_val.to_string()),
-                        });
-                    }
+    let missing: MssEnvelope =
+        parse_json(include_str!("../../tests/fixtures/mss/missing_data.json")).unwrap();
+    assert!(!missing.is_success());
+    assert!(missing.error_data().is_err());
 
-                    // 提取错误信息
-                    if let Some(msg) = obj.get("errormsg").and_then(Value::as_str) {
-                        push_result.error_msg = Some(msg.to_string());
-                    }
-                    if let Some(code) = obj.get("errorcode").and_then(Value::as_str) {
-                        push_result.error_code = Some(code.to_string());
-                    }
+    let archive: MssEnvelope = parse_json(include_str!(
+        "../../tests/fixtures/mss/archive_rejected.json"
+    ))
+    .unwrap();
+    let rejected = extract_error_info(&archive.error_data().unwrap());
+    assert_eq!(rejected["U1001"].errorcode.as_deref(), Some("3001"));
+    assert_eq!(record_error_reason(&rejected["U1001"]), "3001");
+}
 
-                    // 提取其他可能存在的ID
-                    if let Some(train_id) = obj.get("trainingId").and_then(Value::as_str) {
-                        push_result.train_id = Some(train_id.to_string());
-                    }
-                    if let Some(course_id) = obj.get("course_id").and_then(Value::as_str) {
-                        push_result.course_id = Some(course_id.to_string());
-                    }
-                    if let Some(user_id) = obj.get("userId").and_then(Value::as_str) {
-                        push_result.user_id = Some(user_id.to_string());
-                    }
-                }
-            }
-        }
+#[test]
+fn test_extract_rejected_records() {
+    let class: MssEnvelope =
+        parse_json(include_str!("../../tests/fixtures/mss/class_rejected.json")).unwrap();
+    let rejected = extract_error_info(&class.error_data().unwrap());
+    assert_eq!(rejected.len(), 1);
+    assert_eq!(
+        rejected["T2024001"].errormsg.as_deref(),
+        Some("培训班编号已存在")
+    );
 
-        Ok(())
-    }
+    let lecturer: MssEnvelope = parse_json(include_str!(
+        "../../tests/fixtures/mss/lecturer_batch_rejected.json"
+    ))
+    .unwrap();
+    let rejected = extract_error_info(&lecturer.error_data().unwrap());
+    assert_eq!(rejected.len(), 2);
+    assert_eq!(
+        record_error_reason(&rejected["C001"]),
+        "讲师身份证号格式错误"
+    );
+    assert_eq!(record_error_reason(&rejected["C002"]), "E2002");
+}
 
-    /// 记录结果到数据库
-    async fn record_result(
-        &self,
-        push_result: &MssPushResult,
-        result_details: &[MssPushResultDetail],
-    ) {
-        if let Err(e) = self
-            .push_result_service
-            .record(push_result, result_details)
-            .await
-        {
-            error!("Failed to record push result: {e:?}");
-        }
+#[test]
+fn test_record_results() {
+    use crate::utils::clock::FixedClock;
+    use crate::utils::timefmt;
+
+    let request: MssPushRequest = parse_json(include_str!(
+        "../../tests/fixtures/mss/lecturer_request.json"
+    ))
+    .unwrap();
+    let push_time = timefmt::parse_datetime("2026-10-16 02:00:00").unwrap();
+    let clock = FixedClock::at_local(push_time);
+    let results = record_results(&request, &PushTelemetry::default(), clock.now_local());
+    assert_eq!(results.len(), 2);
+    // 每条记录一条结果，标识字段与详情都指向该记录
+    for ((push_result, details), course_id) in results.iter().zip(["C001", "C002"]) {
+        assert_eq!(push_result.push_time, push_time);
+        assert_eq!(push_result.data_type, Some(2));
+        assert_eq!(push_result.course_id.as_deref(), Some(course_id));
+        assert_eq!(push_result.train_id.as_deref(), Some("T2024001"));
+        assert_eq!(details.len(), 1);
+        assert_eq!(details[0].data_id, push_result.id);
+        assert_eq!(details[0].result_id.as_deref(), Some(course_id));
     }
+    assert_ne!(results[0].0.id, results[1].0.id);
 }
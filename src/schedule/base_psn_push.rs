@@ -1,24 +1,53 @@
 use std::sync::Arc;
+use std::time::Duration;
 
-use crate::config::MssInfoConfig;
+use crate::AppContext;
+use crate::config::{MssInfoConfig, NotifyStatusConfig, TrainStatusConfig};
 use crate::mappers::archiving_mss_mapper::ArchivingMssMapper;
+use crate::models::analytics_export::AnalyticsExporter;
 use crate::parsers::push_result_parser::PushResultParser;
-use crate::utils::{ClickHouseClient, GatewayClient};
-use crate::AppContext;
-use reqwest::Client;
+use crate::schedule::clickhouse_replay::ClickHouseReplayQueue;
+use crate::schedule::mss_retry_queue::MssRetryQueue;
+use crate::schedule::push_executor::ClickhouseTables;
+use crate::schedule::push_outbox::PushOutbox;
+use crate::schedule::push_pipeline::PushPipeline;
+use crate::schedule::push_verification::PushVerifier;
+use crate::schedule::running_tasks::RunningTaskRegistry;
+use crate::schedule::shadow_push::ShadowPusher;
+use crate::shutdown::ShutdownController;
+use crate::utils::retry::RetryPolicy;
+use crate::utils::{ClickHouseClient, Clock, GatewayClient, InstrumentedClient, RateLimiter};
 use sqlx::MySqlPool;
 
 // 封装所有任务共享的字段
 pub struct BasePsnPushTask {
     pub mysql_pool: MySqlPool,
-    pub http_client: Client,
+    pub mysql_acquire_timeout: Duration, // 查询推送数据时等待连接的上限，超时按连接池耗尽结束本次运行
+    pub http_client: Arc<InstrumentedClient>,
     pub mss_info_config: Arc<MssInfoConfig>,
     pub archiving_mapper: ArchivingMssMapper,
     pub push_result_parser: PushResultParser,
     pub gateway_client: Arc<GatewayClient>,
     pub clickhouse_client: Arc<ClickHouseClient>, // 添加 ClickHouse 客户端
+    pub clickhouse_tables: Arc<ClickhouseTables>, // 各类数据回写状态的 ClickHouse 表
     pub hit_date: Option<String>,                 // 存储可选的 hit_date
     pub train_ids: Option<Vec<String>>,           // 存储可选的 train_ids
+    pub update_batch_size: usize,                 // 回写推送状态时每批的 ID 数量
+    pub fetch_page_size: usize,                   // 每页查询的记录数，按页推送并回写状态
+    pub notify_status: Arc<NotifyStatusConfig>,   // 回写推送状态时各状态写入的值
+    pub train_status: Arc<TrainStatusConfig>,     // 推送排除已取消的培训班，删除通知只查询它们
+    pub retry_queue: Option<Arc<MssRetryQueue>>,  // 暂时性失败的延迟重试队列，未启用时为 None
+    pub retry_policy: RetryPolicy,                // 单次推送内的重试策略
+    pub mss_rate_limiter: Arc<RateLimiter>,       // 所有任务共用的 MSS 限流器
+    pub shutdown: Arc<ShutdownController>,        // 关闭时在两条记录之间停止推送
+    pub clock: Arc<dyn Clock>,                    // 未指定 hit_date 时按它计算昨天
+    pub analytics_exporter: Option<Arc<AnalyticsExporter>>, // 推送汇总导出到 ClickHouse，未启用时为 None
+    pub push_verifier: Option<Arc<PushVerifier>>,           // 状态回写后核对残留，未启用时为 None
+    pub push_pipeline: Option<Arc<PushPipeline>>,           // 查询与推送之间的队列，未启用时为 None
+    pub shadow_pusher: Option<Arc<ShadowPusher>>, // 抽样镜像到备用 MSS 环境，未启用时为 None
+    pub push_outbox: Option<Arc<PushOutbox>>,     // 推送发件箱，未启用时为 None
+    pub running_tasks: Arc<RunningTaskRegistry>,  // 运行期间登记任务参数，供诊断接口查看
+    pub clickhouse_replay: Option<Arc<ClickHouseReplayQueue>>, // ClickHouse 节点失败时的重放队列，未启用时为 None
 }
 
 impl BasePsnPushTask {
@@ -29,18 +58,42 @@ impl BasePsnPushTask {
     ) -> Self {
         // MySqlPool 是 Arc 包装的，所以可以安全克隆
         let pool_clone_for_mapper = app_context.mysql_pool.clone();
-        let pool_clone_for_parser = app_context.mysql_pool.clone();
 
         BasePsnPushTask {
             mysql_pool: app_context.mysql_pool.clone(),
-            http_client: app_context.http_client.clone(),
+            mysql_acquire_timeout: app_context.timeouts.mysql_acquire,
+            http_client: Arc::clone(&app_context.mss_http_client),
             mss_info_config: Arc::clone(&app_context.mss_info_config),
-            archiving_mapper: ArchivingMssMapper::new(pool_clone_for_mapper),
-            push_result_parser: PushResultParser::new(pool_clone_for_parser),
+            archiving_mapper: ArchivingMssMapper::new(
+                pool_clone_for_mapper,
+                app_context.reply_store.clone(),
+                Arc::clone(&app_context.clock),
+            ),
+            push_result_parser: PushResultParser::new(
+                Arc::clone(&app_context.push_result_writer),
+                Arc::clone(&app_context.clock),
+            ),
             gateway_client: Arc::clone(&app_context.gateway_client),
             clickhouse_client: Arc::clone(&app_context.clickhouse_client),
+            clickhouse_tables: Arc::clone(&app_context.clickhouse_tables),
             hit_date,
             train_ids,
+            update_batch_size: app_context.limits.push_update_batch_size.max(1),
+            fetch_page_size: app_context.limits.push_fetch_page_size.max(1),
+            notify_status: Arc::clone(&app_context.notify_status),
+            train_status: Arc::clone(&app_context.train_status),
+            retry_queue: app_context.mss_retry_queue.clone(),
+            retry_policy: app_context.retry.mss.clone(),
+            mss_rate_limiter: Arc::clone(&app_context.rate_limiters.mss),
+            shutdown: Arc::clone(&app_context.shutdown),
+            clock: Arc::clone(&app_context.clock),
+            analytics_exporter: app_context.analytics_exporter.clone(),
+            push_verifier: app_context.push_verifier.clone(),
+            push_pipeline: app_context.push_pipeline.clone(),
+            shadow_pusher: app_context.shadow_pusher.clone(),
+            push_outbox: app_context.push_outbox.clone(),
+            running_tasks: Arc::clone(&app_context.running_tasks),
+            clickhouse_replay: app_context.clickhouse_replay.clone(),
         }
     }
 }
@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use sqlx::MySqlPool;
+use tracing::info;
+
+use crate::TaskExecutor;
+use crate::binlog::stats::{format_digest, load_batch_stats, summarize, upsert_daily_digest};
+use crate::messages;
+use crate::utils::Clock;
+
+/// 汇总前一天的 binlog 批次统计写入 binlog_daily_digest，并把文本摘要输出到日志
+pub struct BinlogDigestTask {
+    mysql_pool: MySqlPool,
+    task_name: String,
+    clock: Arc<dyn Clock>,
+}
+
+impl BinlogDigestTask {
+    pub fn new(mysql_pool: MySqlPool, task_name: String, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            mysql_pool,
+            task_name,
+            clock,
+        }
+    }
+
+    /// 生成指定日期的日报，返回文本摘要
+    pub async fn digest(&self, date: NaiveDate) -> Result<String> {
+        let batches = load_batch_stats(&self.mysql_pool, date).await?;
+        let digests = summarize(&batches);
+        for digest in &digests {
+            upsert_daily_digest(&self.mysql_pool, date, digest).await?;
+        }
+        Ok(format_digest(date, &digests, messages::language()))
+    }
+}
+
+#[async_trait::async_trait]
+impl TaskExecutor for BinlogDigestTask {
+    fn name(&self) -> &str {
+        &self.task_name
+    }
+
+    async fn execute(&self) -> Result<()> {
+        let yesterday = self.clock.yesterday();
+        let summary = self.digest(yesterday).await?;
+        info!("{summary}");
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use tracing::{error, info, warn};
+
+use crate::binlog::coverage::{earliest_window_start, find_gaps, load_sync_windows};
+use crate::metrics::BINLOG_GAPS_REPLAYED;
+use crate::models::task_run::with_task_run;
+use crate::schedule::binlog_shard::SHARDED_DATA_TYPE;
+use crate::schedule::binlog_sync::{BINLOG_SYNC_LOCK_KEY, BinlogSyncTask};
+use crate::utils::deadline::{CycleDeadline, deadline_exceeded, with_cycle_deadline};
+use crate::utils::redis::RedisLock;
+use crate::{AppContext, TaskExecutor};
+
+// 每次补拉的最大窗口，与同步周期一致
+const REPLAY_WINDOW_MS: i64 = 300_000;
+
+/// 查找检查点之前没有被完整处理的 binlog 区间并重新拉取处理。
+/// 与 binlog 同步共用一把锁，补拉期间同步周期跳过
+pub struct BinlogGapReplayTask {
+    app_context: Arc<AppContext>,
+    sync_task: BinlogSyncTask,
+    task_name: String,
+}
+
+impl BinlogGapReplayTask {
+    pub fn new(app_context: Arc<AppContext>, task_name: String) -> Self {
+        Self {
+            sync_task: BinlogSyncTask::new(Arc::clone(&app_context)),
+            app_context,
+            task_name,
+        }
+    }
+
+    /// 补拉所有数据类型的缺口，返回重新处理的窗口数
+    async fn replay_gaps(&self) -> Result<usize> {
+        let checkpoint = self.sync_task.checkpoint().await?;
+        let lookback_ms = i64::try_from(self.app_context.timeouts.binlog_gap_lookback.as_millis())
+            .unwrap_or(i64::MAX);
+        let lookback_from = self
+            .app_context
+            .clock
+            .timestamp_ms()
+            .saturating_sub(lookback_ms);
+
+        let mut replayed = 0;
+        let data_types: Vec<_> = self.app_context.processor_registry.data_types().collect();
+        for data_type in data_types {
+            // 分片处理的日志按分片检查点推进，不记录覆盖窗口
+            if self.app_context.binlog_sharding.enabled && data_type == SHARDED_DATA_TYPE {
+                continue;
+            }
+            let label = data_type.as_label();
+            let Some(earliest) = earliest_window_start(&self.app_context.mysql_pool, label).await?
+            else {
+                continue;
+            };
+            let from = lookback_from.max(earliest);
+            let windows =
+                load_sync_windows(&self.app_context.mysql_pool, label, from, checkpoint).await?;
+            for (gap_start, gap_end) in find_gaps(&windows, from, checkpoint) {
+                warn!(
+                    "Found uncovered binlog window for type {data_type:?}: {gap_start}..{gap_end}, replaying."
+                );
+                let mut cursor = gap_start;
+                while cursor < gap_end {
+                    if deadline_exceeded() {
+                        warn!(
+                            "Binlog gap replay deadline exceeded, remaining gaps are left to the next run."
+                        );
+                        return Ok(replayed);
+                    }
+                    let window_end = gap_end.min(cursor + REPLAY_WINDOW_MS);
+                    match self
+                        .sync_task
+                        .replay_window(data_type, cursor, window_end)
+                        .await
+                    {
+                        Ok(covered_end) => {
+                            replayed += 1;
+                            BINLOG_GAPS_REPLAYED.with_label_values(&[label]).inc();
+                            cursor = covered_end;
+                        }
+                        Err(e) => {
+                            error!(
+                                "Failed to replay binlog window {cursor}..{window_end} for type {data_type:?}: {e:?}"
+                            );
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(replayed)
+    }
+}
+
+#[async_trait::async_trait]
+impl TaskExecutor for BinlogGapReplayTask {
+    fn name(&self) -> &str {
+        &self.task_name
+    }
+
+    async fn execute(&self) -> Result<()> {
+        let ttl_ms = u64::try_from(self.app_context.timeouts.binlog_lock_ttl.as_millis())
+            .unwrap_or(u64::MAX);
+        let Some(lock) =
+            RedisLock::try_acquire(&self.app_context.redis_mgr, BINLOG_SYNC_LOCK_KEY, ttl_ms)
+                .await?
+        else {
+            info!("Binlog sync is running, skipping gap replay.");
+            return Ok(());
+        };
+
+        let deadline = CycleDeadline::after(self.app_context.timeouts.binlog_cycle_deadline);
+        let result = with_task_run(
+            &self.app_context.mysql_pool,
+            &self.task_name,
+            with_cycle_deadline(deadline, self.replay_gaps()),
+        )
+        .await;
+        if let Err(e) = lock.release(&self.app_context.redis_mgr).await {
+            error!("Failed to release redis lock after binlog gap replay: {e:?}");
+        }
+
+        let replayed = result?;
+        if replayed > 0 {
+            info!("Binlog gap replay reprocessed {replayed} windows.");
+        }
+        Ok(())
+    }
+}
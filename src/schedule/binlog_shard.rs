@@ -0,0 +1,242 @@
+//! User binlog 分片处理。实例在 Redis 有序集合中登记心跳，按成员 ID 排序后的位置作为分片号，
+//! 只处理 hash(cid) % 成员数 落在自己分片的日志。
+//! 每个分片的检查点按 "分片号/成员数" 保存在 Redis 中；成员数变化后，没有检查点的分片从所有已有检查点中
+//! 最早的一个开始，重叠的日志会重复处理（落库为先删后插，可以重复执行）
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use redis::AsyncCommands;
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::metrics::BINLOG_SHARD;
+use crate::schedule::binlog_sync::{DataType, ModifyOperationLog};
+use crate::shutdown::ShutdownController;
+use crate::utils::redis::RedisMgr;
+use crate::utils::timefmt;
+
+/// 按实例分片处理的数据类型
+pub const SHARDED_DATA_TYPE: DataType = DataType::User;
+const MEMBERS_KEY: &str = "binlog:shard:user:members";
+const CHECKPOINTS_KEY: &str = "binlog:shard:user:checkpoints";
+
+/// 当前实例负责的分片
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShardAssignment {
+    pub index: usize,
+    pub count: usize,
+}
+
+impl ShardAssignment {
+    /// 日志的 cid 是否落在本分片，没有 cid 的日志固定归属同一个分片
+    pub fn owns(&self, log: &ModifyOperationLog) -> bool {
+        shard_of(log.cid.as_deref().unwrap_or_default(), self.count) == self.index
+    }
+
+    fn field(&self) -> String {
+        format!("{}/{}", self.index, self.count)
+    }
+}
+
+/// cid 所属的分片，不依赖进程内的随机哈希种子，各实例计算结果一致
+fn shard_of(cid: &str, count: usize) -> usize {
+    let digest = Sha256::digest(cid.as_bytes());
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest[..8]);
+    let count = u64::try_from(count.max(1)).unwrap_or(u64::MAX);
+    usize::try_from(u64::from_be_bytes(prefix) % count).unwrap_or_default()
+}
+
+/// 分片的起始检查点：同一分片布局下自己的检查点优先；布局变化后取所有检查点中最早的一个
+fn resolve_checkpoint(
+    checkpoints: &HashMap<String, i64>,
+    assignment: ShardAssignment,
+) -> Option<i64> {
+    checkpoints
+        .get(&assignment.field())
+        .or_else(|| checkpoints.values().min())
+        .copied()
+}
+
+/// 当前布局的所有分片都写入检查点后，其他布局的检查点不再需要
+fn stale_fields(fields: &[String], count: usize) -> Vec<String> {
+    let suffix = format!("/{count}");
+    let (current, stale): (Vec<_>, Vec<_>) = fields
+        .iter()
+        .cloned()
+        .partition(|field| field.ends_with(&suffix));
+    if current.len() < count {
+        return Vec::new();
+    }
+    stale
+}
+
+/// 本实例在 Redis 中的分片成员身份
+pub struct ShardMembership {
+    redis_mgr: RedisMgr,
+    member_id: String,
+    member_ttl: Duration,
+    /// 上个周期的分片，变化时记录日志
+    last: Mutex<Option<ShardAssignment>>,
+}
+
+impl ShardMembership {
+    pub fn new(redis_mgr: RedisMgr, member_ttl: Duration) -> Self {
+        Self {
+            redis_mgr,
+            member_id: Uuid::new_v4().to_string(),
+            member_ttl,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// 续期心跳、清理过期成员，并按当前存活成员计算本实例的分片
+    pub async fn assignment(&self) -> Result<ShardAssignment> {
+        let now = timefmt::timestamp_ms();
+        let ttl_ms = i64::try_from(self.member_ttl.as_millis()).unwrap_or(i64::MAX);
+        let mut conn = self.redis_mgr.clone();
+        let (mut members,): (Vec<String>,) = redis::pipe()
+            .atomic()
+            .zadd(MEMBERS_KEY, &self.member_id, now)
+            .ignore()
+            .zrembyscore(MEMBERS_KEY, "-inf", now.saturating_sub(ttl_ms))
+            .ignore()
+            .zrange(MEMBERS_KEY, 0, -1)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to refresh binlog shard membership")?;
+        members.sort();
+        let index = members
+            .iter()
+            .position(|member| *member == self.member_id)
+            .context("Binlog shard member expired before it could claim a shard")?;
+        let assignment = ShardAssignment {
+            index,
+            count: members.len(),
+        };
+        self.observe(assignment);
+        Ok(assignment)
+    }
+
+    fn observe(&self, assignment: ShardAssignment) {
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        if *last != Some(assignment) {
+            info!(
+                "Binlog member {} claimed {SHARDED_DATA_TYPE:?} shard {} of {} (previously {:?}).",
+                self.member_id, assignment.index, assignment.count, *last
+            );
+            *last = Some(assignment);
+        }
+        let label = SHARDED_DATA_TYPE.as_label();
+        BINLOG_SHARD
+            .with_label_values(&[label, "index"])
+            .set(i64::try_from(assignment.index).unwrap_or(i64::MAX));
+        BINLOG_SHARD
+            .with_label_values(&[label, "count"])
+            .set(i64::try_from(assignment.count).unwrap_or(i64::MAX));
+    }
+
+    async fn heartbeat(&self) -> Result<()> {
+        let mut conn = self.redis_mgr.clone();
+        let _: () = conn
+            .zadd(MEMBERS_KEY, &self.member_id, timefmt::timestamp_ms())
+            .await
+            .context("Failed to renew binlog shard membership")?;
+        Ok(())
+    }
+
+    async fn leave(&self) -> Result<()> {
+        let mut conn = self.redis_mgr.clone();
+        let _: () = conn
+            .zrem(MEMBERS_KEY, &self.member_id)
+            .await
+            .context("Failed to leave binlog shard membership")?;
+        Ok(())
+    }
+
+    /// 同步周期可能长于 member_ttl，心跳在后台按 member_ttl 的三分之一续期；
+    /// 进程关闭时退出成员，其余实例在下个周期重新分配分片
+    pub fn spawn_heartbeat(self: Arc<Self>, shutdown: Arc<ShutdownController>) {
+        let interval = self.member_ttl / 3;
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep(interval) => {}
+                    _ = shutdown.cancelled() => break,
+                }
+                if let Err(e) = self.heartbeat().await {
+                    warn!("{e:?}");
+                }
+            }
+            match self.leave().await {
+                Ok(()) => info!("Binlog member {} left shard membership.", self.member_id),
+                Err(e) => warn!("{e:?}"),
+            }
+        });
+    }
+
+    /// 分片的起始检查点，还没有任何分片检查点时（首次开启分片）使用全局检查点
+    pub async fn load_checkpoint(&self, assignment: ShardAssignment, global: i64) -> Result<i64> {
+        let mut conn = self.redis_mgr.clone();
+        let checkpoints: HashMap<String, i64> = conn
+            .hgetall(CHECKPOINTS_KEY)
+            .await
+            .context("Failed to load binlog shard checkpoints")?;
+        Ok(resolve_checkpoint(&checkpoints, assignment).unwrap_or(global))
+    }
+
+    /// 保存分片检查点，当前布局完整后清理其他布局的检查点
+    pub async fn save_checkpoint(&self, assignment: ShardAssignment, timestamp: i64) -> Result<()> {
+        let mut conn = self.redis_mgr.clone();
+        let _: () = conn
+            .hset(CHECKPOINTS_KEY, assignment.field(), timestamp)
+            .await
+            .context("Failed to save binlog shard checkpoint")?;
+        let fields: Vec<String> = conn
+            .hkeys(CHECKPOINTS_KEY)
+            .await
+            .context("Failed to list binlog shard checkpoints")?;
+        let stale = stale_fields(&fields, assignment.count);
+        if !stale.is_empty() {
+            let _: () = conn
+                .hdel(CHECKPOINTS_KEY, &stale)
+                .await
+                .context("Failed to remove stale binlog shard checkpoints")?;
+            info!("Removed binlog shard checkpoints of previous layouts: {stale:?}");
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_shard_assignment() {
+    let shards = |count| {
+        (0..count)
+            .map(|index| ShardAssignment { index, count })
+            .collect::<Vec<_>>()
+    };
+    for cid in ["u-1", "u-2", "8a7f3e", ""] {
+        let log = ModifyOperationLog {
+            cid: Some(cid.to_string()).filter(|cid| !cid.is_empty()),
+            ..Default::default()
+        };
+        assert_eq!(shards(3).iter().filter(|s| s.owns(&log)).count(), 1);
+        assert!(shards(1)[0].owns(&log));
+    }
+    assert_eq!(shard_of("u-1", 5), shard_of("u-1", 5));
+
+    let checkpoints = HashMap::from([("0/2".to_string(), 300), ("1/2".to_string(), 100)]);
+    let own = ShardAssignment { index: 0, count: 2 };
+    let rebalanced = ShardAssignment { index: 2, count: 3 };
+    assert_eq!(resolve_checkpoint(&checkpoints, own), Some(300));
+    assert_eq!(resolve_checkpoint(&checkpoints, rebalanced), Some(100));
+    assert_eq!(resolve_checkpoint(&HashMap::new(), own), None);
+
+    let fields = ["0/2", "1/2", "0/3", "1/3"].map(String::from);
+    assert!(stale_fields(&fields, 3).is_empty());
+    assert_eq!(stale_fields(&fields, 2), ["0/3", "1/3"]);
+}
@@ -1,33 +1,36 @@
-use crate::binlog::processor::DataProcessorTrait;
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use futures::FutureExt;
 use serde::{Deserialize, Serialize};
 use sqlx::{MySqlPool, Row};
 use std::future::Future;
 use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 use tracing::{error, info, warn};
 
-use crate::binlog::{OrgDataProcessor, UserDataProcessor};
-use crate::utils::redis::{RedisLock, RedisMgr};
 use crate::AppContext;
+use crate::binlog::coverage::record_sync_window;
+use crate::binlog::processor::SaveIncomplete;
+use crate::db::query_runner::QueryRunner;
+use crate::metrics::BINLOG_PAGINATION_ABORTED;
+use crate::models::task_run::with_task_run_discarding_idle;
+use crate::models::task_run_history::{RowCounter, count_rows};
+use crate::schedule::binlog_shard::{SHARDED_DATA_TYPE, ShardAssignment, ShardMembership};
+use crate::shutdown::ShutdownController;
+use crate::utils::deadline::{
+    CycleDeadline, DeadlineExceeded, deadline_exceeded, with_cycle_deadline,
+};
+use crate::utils::gateway_client::{BINLOG_FIND_MAX_PAGE_SIZE, GatewayError};
+use crate::utils::redis::{RedisLock, RedisMgr};
 
 // 定义常量
-const BINLOG_SYNC_LOCK_KEY: &str = "binlog:sync:lock";
-
-// 定义binlog类型枚举
-/// 数据类型
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum DataType {
-    /// 基准岗位
-    StandardStation,
-    /// 机构
-    Org,
-    /// 用户
-    User,
-}
+pub const BINLOG_SYNC_LOCK_KEY: &str = "binlog:sync:lock";
+// 连续空页达到该数量时停止翻页
+const MAX_CONSECUTIVE_EMPTY_PAGES: u32 = 3;
+
+// 数据类型也是 HTTP 接口的参数，定义在 web::models 中
+pub use crate::web::DataType;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ResultSet {
@@ -93,6 +96,35 @@ pub struct ModifyOperationLog {
     pub entity_meta_info: Option<EntityMetaInfo>,
 }
 
+/// binlog 处理优先级，同一周期内高优先级的日志先处理并落库
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogPriority {
+    /// 删除
+    High,
+    /// 新增与修改
+    Normal,
+}
+
+impl LogPriority {
+    pub fn as_label(&self) -> &'static str {
+        match self {
+            LogPriority::High => "high",
+            LogPriority::Normal => "normal",
+        }
+    }
+}
+
+impl ModifyOperationLog {
+    /// 按 type 判断优先级，与处理器判断是否写入的规则一致：1 新增、2 修改，其余为删除
+    pub fn priority(&self) -> LogPriority {
+        if self.type_ == 1 || self.type_ == 2 {
+            LogPriority::Normal
+        } else {
+            LogPriority::High
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct EntityMetaInfo {
     #[serde(rename = "dateCreated")]
@@ -105,28 +137,32 @@ pub struct EntityMetaInfo {
 pub struct PermanentFailure {
     pub log: ModifyOperationLog,
     pub reason: String,
+    pub state: &'static str, // 失败时到达的处理状态
 }
 
 pub struct BinlogSyncTimestampHolder {
     mysql_pool: MySqlPool,
     redis_mgr: RedisMgr,
+    lock_ttl: Duration,
     /// 如果成功获取锁就把 RedisLock 放到这里，save_timestamp 会读取并释放它
     lock_holder: Mutex<Option<RedisLock>>,
 }
 
 impl BinlogSyncTimestampHolder {
-    pub fn new(mysql_pool: MySqlPool, redis_mgr: RedisMgr) -> Self {
+    pub fn new(mysql_pool: MySqlPool, redis_mgr: RedisMgr, lock_ttl: Duration) -> Self {
         Self {
             mysql_pool,
             redis_mgr,
+            lock_ttl,
             lock_holder: Mutex::new(None),
         }
     }
 
     /// 获取锁
     async fn acquire_lock(&self) -> Result<bool> {
-        // 设置1小时后锁失效，4小时太长
-        match RedisLock::try_acquire(&self.redis_mgr, BINLOG_SYNC_LOCK_KEY, 3_600_000).await? {
+        // 锁过期时间由 timeouts.binlog_lock_ttl 配置，默认1小时
+        let ttl_ms = u64::try_from(self.lock_ttl.as_millis()).unwrap_or(u64::MAX);
+        match RedisLock::try_acquire(&self.redis_mgr, BINLOG_SYNC_LOCK_KEY, ttl_ms).await? {
             Some(lock) => {
                 // 成功获取锁，将lock存入 holder，在以后释放
                 let mut guard = self.lock_holder.lock().await;
@@ -142,8 +178,11 @@ impl BinlogSyncTimestampHolder {
         }
     }
     async fn get_timestamp(&self) -> Result<i64> {
-        let row = sqlx::query("SELECT timestamp FROM binlog_sync_timestamp")
-            .fetch_one(&self.mysql_pool)
+        let row = QueryRunner::new("binlog_timestamp_get")
+            .run(
+                sqlx::query("SELECT timestamp FROM binlog_sync_timestamp")
+                    .fetch_one(&self.mysql_pool),
+            )
             .await
             .context("Failed to get timestamp")?;
 
@@ -151,9 +190,12 @@ impl BinlogSyncTimestampHolder {
     }
 
     async fn save_timestamp(&self, timestamp: i64) -> Result<()> {
-        sqlx::query("UPDATE binlog_sync_timestamp SET timestamp = ?")
-            .bind(timestamp)
-            .execute(&self.mysql_pool)
+        QueryRunner::new("binlog_timestamp_save")
+            .run(
+                sqlx::query("UPDATE binlog_sync_timestamp SET timestamp = ?")
+                    .bind(timestamp)
+                    .execute(&self.mysql_pool),
+            )
             .await
             .context("Failed to update timestamp")?;
 
@@ -179,18 +221,19 @@ impl BinlogSyncTimestampHolder {
 
     /// "受保护的作用域执行"
     /// 接收一个异步闭包，安全地执行它，并确保锁总是被释放。
-    pub async fn run_scoped_sync<F, Fut>(&self, operation: F) -> Result<bool>
+    pub async fn run_scoped_sync<F, Fut, T>(&self, operation: F) -> Result<T>
     where
         // 闭包接收 i64 (start_time)，返回一个 Future
         F: FnOnce(i64) -> Fut,
-        // Future 的输出是 Result<i64>，其中 i64 是新的 end_time
-        Fut: Future<Output = Result<(i64, bool)>>,
+        // Future 的输出是 Result<(i64, T)>，其中 i64 是新的 end_time，T 为本次执行的结果
+        Fut: Future<Output = Result<(i64, T)>>,
+        T: Default,
     {
         // 1. 先尝试获取锁
         if !self.acquire_lock().await? {
             // 如果获取锁失败，直接返回，不再执行后续逻辑
             warn!("Current task acquire lock is not acquired.");
-            return Ok(false);
+            return Ok(T::default());
         }
 
         // 2. 将所有获取锁之后的操作，全部放入一个新的 async 块中
@@ -198,9 +241,9 @@ impl BinlogSyncTimestampHolder {
             // 2.1. 在安全区域内获取时间戳
             let start_timestamp = self.get_timestamp().await?;
             // 2.2. 执行传入的业务逻辑
-            let (end_time, is_caught_up) = operation(start_timestamp).await?;
+            let (end_time, output) = operation(start_timestamp).await?;
             self.save_timestamp(end_time).await?;
-            Ok(is_caught_up) // 如果所有步骤都成功，返回 Ok
+            Ok(output) // 如果所有步骤都成功，返回 Ok
         };
 
         // 3. 将业务逻辑（Future）包装在 AssertUnwindSafe 和 catch_unwind 中
@@ -216,9 +259,9 @@ impl BinlogSyncTimestampHolder {
         // 4. 根据执行结果进行处理
         match future_result {
             // 1: 所有工作都成功完成
-            Ok(Ok(is_caught_up)) => {
+            Ok(Ok(output)) => {
                 info!("Scoped operation and cleanup completed successfully.");
-                Ok(is_caught_up)
+                Ok(output)
             }
             // 2: 工作中发生了可恢复的错误 (Err)
             Ok(Err(e)) => {
@@ -237,9 +280,56 @@ impl BinlogSyncTimestampHolder {
     }
 }
 
+/// 一个同步周期的结果，用于调整下一次的轮询间隔
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SyncCycle {
+    /// 是否已追上当前时间；未获取到锁时为 false
+    pub caught_up: bool,
+    /// 本周期拉取的日志条数
+    pub records: usize,
+}
+
 pub struct BinlogSyncTask {
     app_context: Arc<AppContext>,
     timestamp_holder: BinlogSyncTimestampHolder,
+    /// binlog.find 每页条数，已限制在网关上限内
+    page_size: u32,
+    /// 开启 binlog_sharding 时本实例的分片成员身份
+    shard_membership: Option<Arc<ShardMembership>>,
+}
+
+/// 单个数据类型一个周期的拉取结果
+struct TypeSyncOutcome {
+    records: usize,
+    /// 达到条数上限时最后一条已处理日志的时间，下个周期从这里继续
+    resume_at: Option<i64>,
+    /// 分页正常结束，resume_at 之前的日志都已拉取
+    complete: bool,
+}
+
+/// 从检查点开始的同步窗口：向前重叠 30 秒，最多向后 5 分钟且不超过当前时间 now_ms。
+/// 返回 (start_time, end_time, 是否已追上当前时间)
+fn sync_window(timestamp: i64, now_ms: i64) -> (i64, i64, bool) {
+    let start_time = timestamp - 30_000; // 30 秒前
+    let five_minutes_later = timestamp + 300_000; // 5 分钟后
+    let end_time = std::cmp::min(five_minutes_later, now_ms);
+    // 如果 end_time < five_minutes_later，说明我们被 now 限制了，已经追上了。
+    (start_time, end_time, end_time < five_minutes_later)
+}
+
+/// 第 max_records 条新日志（data_modify_time 晚于 checkpoint）的下标。
+/// 窗口开头与上个周期重叠的日志不计入上限，保证每个周期都能推进
+fn record_cap_index(
+    items: &[ModifyOperationLog],
+    checkpoint: i64,
+    max_records: usize,
+) -> Option<usize> {
+    items
+        .iter()
+        .enumerate()
+        .filter(|(_, log)| log.data_modify_time > checkpoint)
+        .nth(max_records.saturating_sub(1))
+        .map(|(index, _)| index)
 }
 
 impl BinlogSyncTask {
@@ -247,10 +337,31 @@ impl BinlogSyncTask {
         let timestamp_holder = BinlogSyncTimestampHolder::new(
             app_context.mysql_pool.clone(),
             app_context.redis_mgr.clone(),
+            app_context.timeouts.binlog_lock_ttl,
         );
+        let configured_page_size = app_context.limits.binlog_page_size;
+        let page_size = configured_page_size.clamp(1, BINLOG_FIND_MAX_PAGE_SIZE);
+        if page_size != configured_page_size {
+            warn!(
+                "limits.binlog_page_size {configured_page_size} is out of range 1..={BINLOG_FIND_MAX_PAGE_SIZE}, using {page_size}"
+            );
+        }
+        let sharded = app_context.binlog_sharding.enabled
+            && app_context
+                .processor_registry
+                .data_types()
+                .any(|data_type| data_type == SHARDED_DATA_TYPE);
+        let shard_membership = sharded.then(|| {
+            Arc::new(ShardMembership::new(
+                app_context.redis_mgr.clone(),
+                app_context.binlog_sharding.member_ttl,
+            ))
+        });
         Self {
             app_context,
             timestamp_holder,
+            page_size,
+            shard_membership,
         }
     }
 
@@ -258,105 +369,391 @@ impl BinlogSyncTask {
         "BinlogSyncTask"
     }
 
-    /// 辅助函数：为指定的数据类型获取并处理所有 binlog 数据。
+    /// 开启分片时在后台续期本实例的成员心跳
+    pub fn spawn_shard_heartbeat(&self, shutdown: Arc<ShutdownController>) {
+        if let Some(membership) = &self.shard_membership {
+            Arc::clone(membership).spawn_heartbeat(shutdown);
+        }
+    }
+
+    /// 辅助函数：为指定的数据类型获取并处理所有 binlog 数据，指定 shard 时只处理该分片的日志。
+    /// 新日志超过 limits.binlog_max_records_per_cycle 或超过周期截止时间时只处理前面的部分，其余顺延
     async fn process_data_for_type(
         &self,
         data_type: DataType,
+        checkpoint: i64,
         start_time: i64,
         end_time: i64,
-    ) -> Result<()> {
-        let mut current_page = None;
+        shard: Option<ShardAssignment>,
+    ) -> Result<TypeSyncOutcome> {
+        let max_records = self.app_context.limits.binlog_max_records_per_cycle;
+        let mut current_page = Page::new(1, self.page_size);
+        let mut new_records: usize = 0;
+        // 达到条数上限或截止时间时网关是否还有未拉取的页
+        let mut has_more_pages = false;
         let mut all_items_for_type = Vec::new();
+        let mut pages_fetched: u32 = 0;
+        let mut consecutive_empty_pages: u32 = 0;
+        let mut last_page_ids: Vec<String> = Vec::new();
+        // 分页非正常结束的原因，None 表示正常翻完
+        let mut abort_reason: Option<&'static str> = None;
+        // 第一页失败时无法区分无数据和网关异常，窗口不记为已处理
+        let mut first_page_failed = false;
 
         // 1. 获取当前类型的所有分页数据
-        while let Some(result_set) = self
-            .app_context
-            .gateway_client
-            .binlog_find(data_type, start_time, end_time, current_page)
-            .await?
-        {
+        loop {
+            if deadline_exceeded() {
+                has_more_pages = true;
+                break;
+            }
+            let fetched = self
+                .app_context
+                .gateway_client
+                .binlog_find(data_type, start_time, end_time, current_page)
+                .await;
+            let result_set = match fetched {
+                Ok(Some(result_set)) => result_set,
+                Err(GatewayError::Transport(e)) if e.is::<DeadlineExceeded>() => {
+                    has_more_pages = true;
+                    break;
+                }
+                Ok(None)
+                | Err(GatewayError::BadMessageCode { .. } | GatewayError::Deserialize { .. }) => {
+                    // 第一页就失败视为无数据，中途失败则已取到的数据不完整
+                    if pages_fetched > 0 {
+                        abort_reason = Some("fetch_failed");
+                    } else {
+                        first_page_failed = true;
+                    }
+                    break;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            pages_fetched += 1;
+
             // 处理当前页的数据
-            if let Some(mut items) = result_set.items {
-                // 处理日志项
-                all_items_for_type.append(&mut items);
+            let items = result_set.items.unwrap_or_default();
+            let page_ids: Vec<String> = items.iter().map(|log| log.id.clone()).collect();
+            // 网关忽略页码时会反复返回同一页
+            if !page_ids.is_empty() && page_ids == last_page_ids {
+                abort_reason = Some("repeated_page");
+                break;
+            }
+            if items.is_empty() {
+                consecutive_empty_pages += 1;
+            } else {
+                consecutive_empty_pages = 0;
             }
+            // 空页与重复页按网关返回的整页判断，之后只保留本分片的日志
+            let items: Vec<_> = match shard {
+                Some(shard) => items.into_iter().filter(|log| shard.owns(log)).collect(),
+                None => items,
+            };
+            new_records += items
+                .iter()
+                .filter(|log| log.data_modify_time > checkpoint)
+                .count();
+            all_items_for_type.extend(items);
+            last_page_ids = page_ids;
+
             // 检查是否还有下一页
             if !result_set.page.has_next_page() {
                 break;
             }
-            current_page = Some(result_set.page.next_page());
+            if new_records >= max_records {
+                has_more_pages = true;
+                break;
+            }
+            if consecutive_empty_pages >= MAX_CONSECUTIVE_EMPTY_PAGES {
+                abort_reason = Some("empty_pages");
+                break;
+            }
+            // 防止网关返回异常 total_page 时无限翻页
+            if pages_fetched >= self.app_context.limits.binlog_max_pages_per_cycle {
+                abort_reason = Some("page_cap");
+                break;
+            }
+            current_page = result_set.page.next_page();
+        }
+
+        if let Some(reason) = abort_reason {
+            warn!(
+                "Binlog pagination for type {data_type:?} terminated abnormally ({reason}) after {pages_fetched} pages \
+                 and {} records. Window {start_time}..{end_time} may have gaps.",
+                all_items_for_type.len()
+            );
+            BINLOG_PAGINATION_ABORTED
+                .with_label_values(&[data_type.as_label(), reason])
+                .inc();
+        }
+
+        // 超出条数上限的日志顺延到下个周期，检查点只推进到最后一条处理的日志
+        let mut resume_at = None;
+        if let Some(cap_index) = record_cap_index(&all_items_for_type, checkpoint, max_records) {
+            if has_more_pages || cap_index + 1 < all_items_for_type.len() {
+                all_items_for_type.truncate(cap_index + 1);
+                let last_time = all_items_for_type[cap_index].data_modify_time;
+                info!(
+                    "Binlog type {data_type:?} reached the cap of {max_records} records per cycle, \
+                     deferring logs after {last_time} to the next cycle."
+                );
+                resume_at = Some(last_time);
+            }
+        } else if has_more_pages {
+            // 截止时间到达时停止翻页，已拉取的部分照常处理
+            let last_time = all_items_for_type
+                .last()
+                .map_or(checkpoint, |log| log.data_modify_time);
+            warn!(
+                "Cycle deadline exceeded while paging type {data_type:?} after {pages_fetched} pages, \
+                 deferring logs after {last_time} to the next cycle."
+            );
+            resume_at = Some(last_time);
         }
 
         // 2. 获取完所有数据后，分发给对应的处理器
+        let items_len = all_items_for_type.len();
         if all_items_for_type.is_empty() {
             warn!("No results set for type {data_type:?}");
         } else {
-            let items_len = all_items_for_type.len();
             info!("Retrieved {items_len} records for type {data_type:?}, starting processing...");
-            match data_type {
-                DataType::Org => {
-                    let org_processor = OrgDataProcessor::new(self.app_context.clone());
-                    // 返回Result，让上层决定如何处理错误
-                    org_processor.process(all_items_for_type).await?;
-                }
-                DataType::User => {
-                    let user_processor = UserDataProcessor::new(self.app_context.clone());
-                    user_processor.process(all_items_for_type).await?;
+            match self
+                .app_context
+                .processor_registry
+                .get(data_type, Arc::clone(&self.app_context))
+            {
+                // 返回Result，让上层决定如何处理错误
+                Some(processor) => {
+                    let outcome = processor.process_logs(all_items_for_type).await?;
+                    if let Some(deferred_at) = outcome.resume_at() {
+                        resume_at = Some(resume_at.map_or(deferred_at, |at| at.min(deferred_at)));
+                    }
                 }
-                _ => {
+                None => {
                     warn!("Unknown or unsupported DataType for processing: {data_type:?}");
                 }
             }
         }
-        Ok(())
+        Ok(TypeSyncOutcome {
+            records: items_len,
+            resume_at,
+            complete: abort_reason.is_none() && !first_page_failed,
+        })
+    }
+
+    /// 当前检查点，之前的窗口都已经同步过（可能有缺口）
+    pub(crate) async fn checkpoint(&self) -> Result<i64> {
+        self.timestamp_holder.get_timestamp().await
+    }
+
+    /// 重新拉取并处理某个数据类型 [start_time, end_time] 内的日志，记录为已处理窗口。
+    /// 达到条数上限或截止时间时只处理前面的部分，返回实际覆盖到的时间
+    pub(crate) async fn replay_window(
+        &self,
+        data_type: DataType,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<i64> {
+        let outcome = self
+            .process_data_for_type(data_type, start_time, start_time, end_time, None)
+            .await?;
+        if !outcome.complete {
+            bail!(
+                "Binlog pagination for type {data_type:?} did not complete in window {start_time}..{end_time}"
+            );
+        }
+        let covered_end = outcome.resume_at.unwrap_or(end_time);
+        if covered_end <= start_time {
+            bail!("No progress replaying type {data_type:?} window {start_time}..{end_time}");
+        }
+        record_sync_window(
+            &self.app_context.mysql_pool,
+            data_type.as_label(),
+            start_time,
+            covered_end,
+            outcome.records,
+        )
+        .await?;
+        Ok(covered_end)
+    }
+
+    /// 执行一个同步周期，不在登记的执行范围内时每个周期登记一次执行，没有拉取到日志的周期不保留执行记录，
+    /// 并受 timeouts.binlog_cycle_deadline 限制。开启分片时，全局周期之后再处理本实例分片的 User 日志
+    pub async fn sync_data(&self) -> Result<SyncCycle> {
+        let deadline = CycleDeadline::after(self.app_context.timeouts.binlog_cycle_deadline);
+        let cycle = async {
+            let global = self.sync_cycle().await;
+            let Some(membership) = &self.shard_membership else {
+                return global;
+            };
+            // 未获取到全局锁或全局周期出错时，本实例的分片照常处理
+            let shard = self.sync_shard(membership).await;
+            let (global, shard) = (global?, shard?);
+            Ok(SyncCycle {
+                caught_up: global.caught_up && shard.caught_up,
+                records: global.records + shard.records,
+            })
+        };
+        let counted = async {
+            let cycle = with_cycle_deadline(deadline, cycle).await?;
+            count_rows(RowCounter::Fetched, cycle.records);
+            Ok::<_, anyhow::Error>(cycle)
+        };
+        with_task_run_discarding_idle(
+            &self.app_context.mysql_pool,
+            self.name(),
+            counted,
+            |cycle: &SyncCycle| cycle.records == 0,
+        )
+        .await
     }
 
-    pub async fn sync_data(&self) -> Result<bool> {
+    /// 处理本实例分片内的 User 日志，检查点按分片保存在 Redis 中，不受全局锁限制。
+    /// 分片窗口不记录到 binlog_sync_window，未完整处理的窗口不推进检查点，下个周期重新处理
+    async fn sync_shard(&self, membership: &ShardMembership) -> Result<SyncCycle> {
+        let data_type = SHARDED_DATA_TYPE;
+        let assignment = membership.assignment().await?;
+        let timestamp = membership
+            .load_checkpoint(assignment, self.checkpoint().await?)
+            .await?;
+        let (start_time, end_time, is_caught_up) =
+            sync_window(timestamp, self.app_context.clock.timestamp_ms());
+        info!(
+            "Executing {data_type:?} shard {}/{} sync logic with start_timestamp: {timestamp}",
+            assignment.index, assignment.count
+        );
+
+        let outcome = self
+            .process_data_for_type(data_type, timestamp, start_time, end_time, Some(assignment))
+            .await?;
+        let next_timestamp = if outcome.complete {
+            outcome.resume_at.unwrap_or(end_time).max(timestamp)
+        } else {
+            warn!(
+                "{data_type:?} shard window {start_time}..{end_time} was not fully fetched, holding checkpoint at {timestamp}."
+            );
+            timestamp
+        };
+        membership
+            .save_checkpoint(assignment, next_timestamp)
+            .await?;
+        Ok(SyncCycle {
+            caught_up: is_caught_up && next_timestamp >= end_time,
+            records: outcome.records,
+        })
+    }
+
+    async fn sync_cycle(&self) -> Result<SyncCycle> {
         // 一个业务逻辑的闭包
         let business_logic = |timestamp: i64| async move {
             info!("Executing sync logic with start_timestamp: {}", timestamp);
-            let start_time = timestamp - 30_000; // 30 秒前
-            let five_minutes_later = timestamp + 300_000; // 5 分钟后
-            let end_time = std::cmp::min(
-                five_minutes_later,
-                chrono::Utc::now().timestamp_millis(), // 时间戳全球统一不区分时区
-            );
-
-            // 如果 end_time < five_minutes_later，说明我们被 now 限制了，已经追上了。
-            let is_caught_up = end_time < five_minutes_later;
+            let (start_time, end_time, is_caught_up) =
+                sync_window(timestamp, self.app_context.clock.timestamp_ms());
             if is_caught_up {
                 info!("Binlog sync is caught up to the current time.");
             } else {
                 info!("Binlog sync is processing historical data.");
             }
 
-            // 1. 为 Org 和 User 分别创建一个异步任务 Future
-            let org_processing_future =
-                self.process_data_for_type(DataType::Org, start_time, end_time);
-            let user_processing_future =
-                self.process_data_for_type(DataType::User, start_time, end_time);
-
-            // 2. 使用 tokio::join! 并发地执行这两个 Future
-            info!("Starting concurrent processing for Org and User data...");
-            let (org_result, user_result) =
-                tokio::join!(org_processing_future, user_processing_future);
-
-            // 3. 分别处理两个任务的结果
-            if let Err(e) = org_result {
-                error!("Error occurred while processing organization data: {e:?}");
-            } else {
-                info!("Organization data processing completed.");
+            // 1. 为每个已注册的数据类型创建一个异步任务 Future，分片处理的类型由各实例自行处理
+            let data_types: Vec<DataType> = self
+                .app_context
+                .processor_registry
+                .data_types()
+                .filter(|&data_type| {
+                    self.shard_membership.is_none() || data_type != SHARDED_DATA_TYPE
+                })
+                .collect();
+            let processing_futures = data_types.iter().map(|&data_type| {
+                self.process_data_for_type(data_type, timestamp, start_time, end_time, None)
+            });
+
+            // 2. 使用 join_all 并发地执行这些 Future
+            info!("Starting concurrent processing for {data_types:?} data...");
+            let results = futures::future::join_all(processing_futures).await;
+
+            // 3. 分别处理每个任务的结果
+            let mut records = 0;
+            let mut next_timestamp = end_time;
+            for (data_type, result) in data_types.iter().zip(results) {
+                match result {
+                    Ok(outcome) => {
+                        records += outcome.records;
+                        if let Some(resume_at) = outcome.resume_at {
+                            next_timestamp = next_timestamp.min(resume_at);
+                        }
+                        // 分页中途结束的窗口不记录，由缺口补拉任务重新处理
+                        if outcome.complete {
+                            let covered_end = outcome.resume_at.unwrap_or(end_time);
+                            if let Err(e) = record_sync_window(
+                                &self.app_context.mysql_pool,
+                                data_type.as_label(),
+                                start_time,
+                                covered_end,
+                                outcome.records,
+                            )
+                            .await
+                            {
+                                warn!("Failed to record {data_type:?} sync window: {e:?}");
+                            }
+                        }
+                        info!("{data_type:?} data processing completed.");
+                    }
+                    // 本周期的日志既未落库也未写入死信，检查点不推进，下个周期重新处理
+                    Err(e) if e.is::<SaveIncomplete>() => {
+                        next_timestamp = next_timestamp.min(timestamp);
+                        error!("Failed to persist {data_type:?} data, holding checkpoint: {e:?}");
+                    }
+                    Err(e) => error!("Error occurred while processing {data_type:?} data: {e:?}"),
+                }
             }
-
-            if let Err(e) = user_result {
-                error!("Error occurred while processing user data: {e:?}");
-            } else {
-                info!("User data processing completed.");
+            // 顺延的日志可能落在与上个周期重叠的窗口内，检查点不回退
+            let next_timestamp = next_timestamp.max(timestamp);
+            if deadline_exceeded() {
+                warn!(
+                    "Binlog cycle deadline exceeded, saved completed work and advanced checkpoint to {next_timestamp}."
+                );
             }
-            // 业务逻辑成功完成，返回新的时间戳以及"是否追上"的标志
-            Ok((end_time, is_caught_up))
+            // 有类型被顺延时还没有追上
+            let deferred = next_timestamp < end_time;
+            // 业务逻辑成功完成，返回新的时间戳以及本周期的结果
+            Ok((
+                next_timestamp,
+                SyncCycle {
+                    caught_up: is_caught_up && !deferred,
+                    records,
+                },
+            ))
         };
         // 调用“受保护的执行”
         self.timestamp_holder.run_scoped_sync(business_logic).await
     }
 }
+
+#[test]
+fn test_log_priority() {
+    let log = |type_: u8, operation: &str| ModifyOperationLog {
+        type_,
+        operation: operation.to_string(),
+        ..Default::default()
+    };
+    assert_eq!(log(3, "DELETE").priority(), LogPriority::High);
+    assert_eq!(log(1, "CREATE").priority(), LogPriority::Normal);
+    assert_eq!(log(2, "UPDATE").priority(), LogPriority::Normal);
+    // 不按名称匹配：unlock、block 等修改不是删除
+    assert_eq!(log(2, "unlock").priority(), LogPriority::Normal);
+    assert_eq!(log(2, "block").priority(), LogPriority::Normal);
+}
+
+#[test]
+fn test_record_cap_index() {
+    let log = |data_modify_time: i64| ModifyOperationLog {
+        data_modify_time,
+        ..Default::default()
+    };
+    // 前两条与上个周期重叠，不计入上限
+    let items = vec![log(90), log(100), log(110), log(120), log(130)];
+    assert_eq!(record_cap_index(&items, 100, 2), Some(3));
+    assert_eq!(record_cap_index(&items, 100, 3), Some(4));
+    assert_eq!(record_cap_index(&items, 100, 4), None);
+}
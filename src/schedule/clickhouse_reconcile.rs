@@ -0,0 +1,243 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{Context, Result, anyhow};
+use chrono::{Days, NaiveDate};
+use serde::Serialize;
+use sqlx::{MySqlPool, Row};
+use tracing::{error, info, warn};
+
+use crate::db::query_runner::QueryRunner;
+use crate::parsers::mss_response::{MssRecordKind, SUCCESS_CODE};
+use crate::schedule::push_executor::{clickhouse_status_table, get_clickhouse_result_id_column};
+use crate::schedule::status_updates::NotifyStatus;
+use crate::{AppContext, PsnDataKind};
+
+/// 一个 ClickHouse 节点的对账结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NodeReconcileReport {
+    pub node: String,
+    /// 在该节点上找到的 ID 数
+    pub checked: usize,
+    /// trainNotifyMss 与推送结果不一致的 ID 数
+    pub mismatched: usize,
+    /// 修正语句执行成功的 ID 数
+    pub repaired: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+fn mss_record_kind(kind: PsnDataKind) -> Option<MssRecordKind> {
+    match kind {
+        PsnDataKind::Class => Some(MssRecordKind::Class),
+        PsnDataKind::Lecturer => Some(MssRecordKind::Lecturer),
+        _ => None,
+    }
+}
+
+/// 某天每个 ID 最后一次推送的结果对应的 trainNotifyMss 状态：成功或失败
+pub async fn load_push_outcomes(
+    mysql_pool: &MySqlPool,
+    record_kind: MssRecordKind,
+    date: NaiveDate,
+) -> Result<BTreeMap<String, NotifyStatus>> {
+    let start = date.and_time(chrono::NaiveTime::MIN);
+    let end = start + Days::new(1);
+    let query = sqlx::query(
+        "SELECT d.result_id, IF(r.error_msg IS NULL AND r.error_code = ?, 1, 0) AS succeeded \
+         FROM mss_push_result r JOIN mss_push_result_detail d ON d.data_id = r.id \
+         WHERE r.type = ? AND r.push_time >= ? AND r.push_time < ? AND d.result_id IS NOT NULL \
+         ORDER BY r.push_time, succeeded, r.id",
+    )
+    .bind(SUCCESS_CODE)
+    .bind(record_kind.data_type())
+    .bind(start)
+    .bind(end);
+    let rows = QueryRunner::new("push_outcomes_by_date")
+        .run(query.fetch_all(mysql_pool))
+        .await
+        .context("Failed to query push outcomes from mss_push_result")?;
+
+    // 按推送时间升序，同一 ID 以最后一次结果为准；推送时间相同时成功的结果排在后面，顺序固定
+    let mut outcomes = BTreeMap::new();
+    for row in rows {
+        let result_id: String = row.try_get("result_id")?;
+        let succeeded: i64 = row.try_get("succeeded")?;
+        let status = if succeeded == 1 {
+            NotifyStatus::Success
+        } else {
+            NotifyStatus::Failed
+        };
+        outcomes.insert(result_id, status);
+    }
+    Ok(outcomes)
+}
+
+/// 节点上状态与推送结果不一致的 ID，按应修正成的状态分组。节点上没有的 ID 不处理
+pub fn find_mismatches<'a>(
+    outcomes: &BTreeMap<String, &'a str>,
+    flags: &HashMap<String, Vec<String>>,
+) -> BTreeMap<&'a str, Vec<String>> {
+    let mut mismatches: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for (id, &status) in outcomes {
+        let Some(node_flags) = flags.get(id) else {
+            continue;
+        };
+        if node_flags.iter().any(|flag| flag != status) {
+            mismatches.entry(status).or_default().push(id.clone());
+        }
+    }
+    mismatches
+}
+
+/// 对比某天某种数据在 ClickHouse 各节点上的 trainNotifyMss 与 mss_push_result 中的推送结果，
+/// 对不一致的 ID 逐批在对应节点上重新执行 ALTER ... UPDATE
+pub async fn reconcile_clickhouse_statuses(
+    app_context: &AppContext,
+    kind: PsnDataKind,
+    date: NaiveDate,
+) -> Result<Vec<NodeReconcileReport>> {
+    let (Some(record_kind), Some(id_column)) =
+        (mss_record_kind(kind), get_clickhouse_result_id_column(kind))
+    else {
+        return Err(anyhow!(
+            "Data kind {kind:?} has no ClickHouse status to reconcile"
+        ));
+    };
+    let table = app_context.clickhouse_tables.get(kind)?;
+    let outcomes = load_push_outcomes(&app_context.mysql_pool, record_kind, date).await?;
+    info!(
+        "Reconciling {} {kind:?} push outcomes of {date} against ClickHouse table '{table}'.",
+        outcomes.len()
+    );
+
+    let batch_size = app_context.limits.push_update_batch_size.max(1);
+    let ids: Vec<String> = outcomes.keys().cloned().collect();
+    let mut reports: BTreeMap<String, NodeReconcileReport> = BTreeMap::new();
+    for chunk in ids.chunks(batch_size) {
+        let chunk_outcomes: BTreeMap<String, &str> = chunk
+            .iter()
+            .map(|id| (id.clone(), app_context.notify_status.value(outcomes[id])))
+            .collect();
+        for (node, result) in app_context
+            .clickhouse_client
+            .notify_flags_on_all_nodes(table, id_column, chunk)
+            .await
+        {
+            let report = reports
+                .entry(node.clone())
+                .or_insert_with(|| NodeReconcileReport {
+                    node: node.clone(),
+                    ..Default::default()
+                });
+            let flags = match result {
+                Ok(flags) => flags,
+                Err(e) => {
+                    error!("Failed to query trainNotifyMss on {node}: {e:?}");
+                    report.errors.push(format!("query failed: {e}"));
+                    continue;
+                }
+            };
+            report.checked += flags.len();
+            for (status, mismatched_ids) in find_mismatches(&chunk_outcomes, &flags) {
+                report.mismatched += mismatched_ids.len();
+                match app_context
+                    .clickhouse_client
+                    .update_notify_status_on_node(&node, table, id_column, status, &mismatched_ids)
+                    .await
+                {
+                    Ok(()) => report.repaired += mismatched_ids.len(),
+                    Err(e) => {
+                        warn!("Failed to repair trainNotifyMss on {node}: {e:?}");
+                        report.errors.push(format!("repair failed: {e}"));
+                    }
+                }
+            }
+        }
+    }
+
+    for report in reports.values() {
+        info!(
+            "ClickHouse reconcile on {}: checked {}, mismatched {}, repaired {}.",
+            report.node, report.checked, report.mismatched, report.repaired
+        );
+    }
+    Ok(reports.into_values().collect())
+}
+
+/// 一个 ClickHouse 节点上手动更新状态的结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NodeStatusUpdateReport {
+    pub node: String,
+    /// 更新语句执行成功的 ID 数
+    pub updated: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+/// 把 ids 的 trainNotifyMss 设置为 status 对应的值，每 push_update_batch_size 个 ID 在所有节点上执行一次 ALTER ... UPDATE，
+/// 用于带外修复后的人工修正
+pub async fn set_clickhouse_statuses(
+    app_context: &AppContext,
+    kind: PsnDataKind,
+    ids: &[String],
+    status: NotifyStatus,
+) -> Result<Vec<NodeStatusUpdateReport>> {
+    let Some((table, id_column)) = clickhouse_status_table(&app_context.clickhouse_tables, kind)?
+    else {
+        return Err(anyhow!("Data kind {kind:?} has no ClickHouse status"));
+    };
+    let status = app_context.notify_status.value(status);
+    info!(
+        "Setting trainNotifyMss = '{status}' for {} {kind:?} IDs in ClickHouse table '{table}'.",
+        ids.len()
+    );
+
+    let batch_size = app_context.limits.push_update_batch_size.max(1);
+    let mut reports: BTreeMap<String, NodeStatusUpdateReport> = BTreeMap::new();
+    for chunk in ids.chunks(batch_size) {
+        for (node, result) in app_context
+            .clickhouse_client
+            .update_notify_status(table, id_column, status, chunk)
+            .await?
+            .results
+        {
+            let report = reports
+                .entry(node.clone())
+                .or_insert_with(|| NodeStatusUpdateReport {
+                    node: node.clone(),
+                    ..Default::default()
+                });
+            match result {
+                Ok(()) => report.updated += chunk.len(),
+                Err(e) => {
+                    warn!("Failed to set trainNotifyMss on {node}: {e:?}");
+                    report.errors.push(format!("update failed: {e}"));
+                }
+            }
+        }
+    }
+    Ok(reports.into_values().collect())
+}
+
+#[test]
+fn test_find_mismatches() {
+    let outcomes: BTreeMap<String, &str> = [("a", "1"), ("b", "2"), ("c", "1"), ("d", "1")]
+        .into_iter()
+        .map(|(id, status)| (id.to_string(), status))
+        .collect();
+    let flags: HashMap<String, Vec<String>> =
+        [("a", vec!["1"]), ("b", vec!["1"]), ("c", vec!["1", "0"])]
+            .into_iter()
+            .map(|(id, flags)| {
+                (
+                    id.to_string(),
+                    flags.into_iter().map(String::from).collect(),
+                )
+            })
+            .collect();
+
+    let mismatches = find_mismatches(&outcomes, &flags);
+    assert_eq!(mismatches.len(), 2);
+    assert_eq!(mismatches["1"], vec!["c".to_string()]);
+    assert_eq!(mismatches["2"], vec!["b".to_string()]);
+}
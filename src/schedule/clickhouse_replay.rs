@@ -0,0 +1,226 @@
+//! ClickHouse 重放队列：状态回写的语句在某个节点执行失败时写入 clickhouse_replay_queue，
+//! 后台 worker 在该节点恢复（ping 成功）后按写入顺序重放，避免副本之间静默不一致。
+//! 同一节点的语句严格按 id 顺序执行，前一条失败时该节点本轮不再继续
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use sqlx::{MySqlPool, Row};
+use tracing::{error, info, warn};
+
+use crate::config::ClickHouseReplayConfig;
+use crate::db::query_runner::QueryRunner;
+use crate::metrics::CLICKHOUSE_REPLAY;
+use crate::models::task_run::current_run_id;
+use crate::shutdown::ShutdownController;
+use crate::utils::ClickHouseClient;
+use crate::utils::clickhouse_client::NodeExecution;
+
+// error 列的长度上限
+const MAX_ERROR_LEN: usize = 1024;
+
+/// 待重放的一条语句
+struct ReplayRow {
+    id: u64,
+    statement: String,
+    attempts: u32,
+}
+
+pub struct ClickHouseReplayQueue {
+    mysql_pool: MySqlPool,
+    clickhouse_client: Arc<ClickHouseClient>,
+    config: Arc<ClickHouseReplayConfig>,
+}
+
+impl ClickHouseReplayQueue {
+    /// 未启用时返回 None
+    pub fn new(
+        mysql_pool: MySqlPool,
+        clickhouse_client: Arc<ClickHouseClient>,
+        config: Arc<ClickHouseReplayConfig>,
+    ) -> Option<Self> {
+        config.enabled.then_some(ClickHouseReplayQueue {
+            mysql_pool,
+            clickhouse_client,
+            config,
+        })
+    }
+
+    /// 把执行失败的节点写入队列，写入失败记录日志。全部失败节点都已入队时返回 true
+    pub async fn enqueue_failures(&self, execution: &NodeExecution) -> bool {
+        let mut all_queued = true;
+        for (node, e) in execution.failed_nodes() {
+            let queued = QueryRunner::new("clickhouse_replay_enqueue")
+                .run(
+                    sqlx::query(
+                        "INSERT INTO clickhouse_replay_queue (node, statement, error, run_id) \
+                         VALUES (?, ?, ?, ?)",
+                    )
+                    .bind(node)
+                    .bind(&execution.statement)
+                    .bind(truncate_error(&format!("{e:#}")))
+                    .bind(current_run_id())
+                    .execute(&self.mysql_pool),
+                )
+                .await;
+            match queued {
+                Ok(_) => {
+                    warn!("Queued ClickHouse statement for replay on {node}.");
+                    CLICKHOUSE_REPLAY.with_label_values(&[node, "queued"]).inc();
+                }
+                Err(e) => {
+                    all_queued = false;
+                    error!(
+                        "Failed to queue ClickHouse statement for replay on {node}: {e:?}. Statement: {}",
+                        execution.statement
+                    );
+                }
+            }
+        }
+        all_queued
+    }
+
+    /// 取出各节点最早的待重放语句，按节点分组、按 id 排序
+    async fn pending(&self) -> Result<BTreeMap<String, Vec<ReplayRow>>> {
+        let rows = QueryRunner::new("clickhouse_replay_pending")
+            .run(
+                sqlx::query(
+                    "SELECT id, node, statement, attempts FROM clickhouse_replay_queue \
+                     WHERE status = 'pending' ORDER BY id LIMIT ?",
+                )
+                .bind(self.config.batch_size.max(1) as u64)
+                .fetch_all(&self.mysql_pool),
+            )
+            .await
+            .context("Failed to query clickhouse_replay_queue")?;
+        let mut by_node: BTreeMap<String, Vec<ReplayRow>> = BTreeMap::new();
+        for row in rows {
+            let node: String = row.try_get("node")?;
+            by_node.entry(node).or_default().push(ReplayRow {
+                id: row.try_get("id")?,
+                statement: row.try_get("statement")?,
+                attempts: row.try_get("attempts")?,
+            });
+        }
+        Ok(by_node)
+    }
+
+    async fn replay_once(&self) -> Result<()> {
+        for (node, rows) in self.pending().await? {
+            if let Err(e) = self.clickhouse_client.ping_node(&node).await {
+                info!(
+                    "ClickHouse node {node} still unavailable, {} statements waiting: {e}",
+                    rows.len()
+                );
+                continue;
+            }
+            for row in rows {
+                match self
+                    .clickhouse_client
+                    .execute_on_node(&node, &row.statement)
+                    .await
+                {
+                    Ok(()) => {
+                        self.set_status(row.id, "replayed", None).await?;
+                        CLICKHOUSE_REPLAY
+                            .with_label_values(&[&node, "replayed"])
+                            .inc();
+                    }
+                    Err(e) => {
+                        let attempts = row.attempts + 1;
+                        let error = truncate_error(&format!("{e:#}"));
+                        if attempts >= self.config.max_attempts {
+                            error!(
+                                "Abandoned ClickHouse replay {} on {node} after {attempts} attempts: {e:?}",
+                                row.id
+                            );
+                            self.set_status(row.id, "abandoned", Some(&error)).await?;
+                            CLICKHOUSE_REPLAY
+                                .with_label_values(&[&node, "abandoned"])
+                                .inc();
+                        } else {
+                            warn!(
+                                "Failed to replay ClickHouse statement {} on {node}: {e:?}",
+                                row.id
+                            );
+                            self.record_failure(row.id, attempts, &error).await?;
+                            CLICKHOUSE_REPLAY
+                                .with_label_values(&[&node, "failed"])
+                                .inc();
+                            // 保持同一节点的执行顺序，后面的语句等下一轮
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn set_status(&self, id: u64, status: &str, error: Option<&str>) -> Result<()> {
+        QueryRunner::new("clickhouse_replay_set_status")
+            .run(
+                sqlx::query(
+                    "UPDATE clickhouse_replay_queue SET status = ?, error = COALESCE(?, error) \
+                     WHERE id = ?",
+                )
+                .bind(status)
+                .bind(error)
+                .bind(id)
+                .execute(&self.mysql_pool),
+            )
+            .await
+            .context("Failed to update clickhouse_replay_queue")?;
+        Ok(())
+    }
+
+    async fn record_failure(&self, id: u64, attempts: u32, error: &str) -> Result<()> {
+        QueryRunner::new("clickhouse_replay_record_failure")
+            .run(
+                sqlx::query(
+                    "UPDATE clickhouse_replay_queue SET attempts = ?, error = ? WHERE id = ?",
+                )
+                .bind(attempts)
+                .bind(error)
+                .bind(id)
+                .execute(&self.mysql_pool),
+            )
+            .await
+            .context("Failed to update clickhouse_replay_queue")?;
+        Ok(())
+    }
+}
+
+fn truncate_error(error: &str) -> String {
+    error.chars().take(MAX_ERROR_LEN).collect()
+}
+
+/// 启动后台 worker，按 poll_interval 重放已恢复节点上的语句，关闭时退出
+pub fn spawn_replay_worker(
+    replay_queue: Arc<ClickHouseReplayQueue>,
+    shutdown: Arc<ShutdownController>,
+) {
+    tokio::spawn(async move {
+        info!("ClickHouse replay worker started.");
+        let mut ticker = tokio::time::interval(replay_queue.config.poll_interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.cancelled() => break,
+            }
+            if let Err(e) = replay_queue.replay_once().await {
+                error!("Failed to replay ClickHouse statements: {e:?}");
+            }
+        }
+        info!("ClickHouse replay worker stopped.");
+    });
+}
+
+#[test]
+fn test_truncate_error() {
+    let long = "错".repeat(MAX_ERROR_LEN + 10);
+    assert_eq!(truncate_error(&long).chars().count(), MAX_ERROR_LEN);
+    assert_eq!(truncate_error("timeout"), "timeout");
+}
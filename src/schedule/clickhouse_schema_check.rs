@@ -0,0 +1,110 @@
+use anyhow::{Result, anyhow};
+use std::sync::Arc;
+use tracing::{error, info};
+
+use crate::TaskExecutor;
+use crate::metrics::CLICKHOUSE_SCHEMA_DIVERGENT;
+use crate::schedule::push_executor::{
+    CLICKHOUSE_STATUS_KINDS, ClickhouseTables, get_clickhouse_id_column,
+    get_clickhouse_result_id_column,
+};
+use crate::utils::ClickHouseClient;
+
+/// 推送任务回写 trainNotifyMss 依赖的 ClickHouse 表及列
+pub fn expected_columns(tables: &ClickhouseTables) -> Result<Vec<(&str, Vec<&'static str>)>> {
+    CLICKHOUSE_STATUS_KINDS
+        .iter()
+        .map(|&kind| {
+            let mut columns = vec![get_clickhouse_id_column(kind), "trainNotifyMss"];
+            // 状态对账按 MSS 返回的 ID 查询
+            if let Some(column) =
+                get_clickhouse_result_id_column(kind).filter(|column| !columns.contains(column))
+            {
+                columns.push(column);
+            }
+            Ok((tables.get(kind)?, columns))
+        })
+        .collect()
+}
+
+/// 巡检每个 ClickHouse 节点的表结构。某个节点缺列时 ALTER ... UPDATE 只会在该节点失败，
+/// 每次推送都会静默重复，所以这里把不一致的节点记录到 clickhouse_schema_divergent 指标并报错。
+pub struct ClickhouseSchemaCheckTask {
+    clickhouse_client: Arc<ClickHouseClient>,
+    clickhouse_tables: Arc<ClickhouseTables>,
+    task_name: String,
+}
+
+impl ClickhouseSchemaCheckTask {
+    pub fn new(
+        clickhouse_client: Arc<ClickHouseClient>,
+        clickhouse_tables: Arc<ClickhouseTables>,
+        task_name: String,
+    ) -> Self {
+        Self {
+            clickhouse_client,
+            clickhouse_tables,
+            task_name,
+        }
+    }
+
+    /// 返回不一致的节点描述，全部一致时为空
+    pub async fn check(&self) -> Vec<String> {
+        let expected = match expected_columns(&self.clickhouse_tables) {
+            Ok(expected) => expected,
+            Err(e) => return vec![format!("{e:#}")],
+        };
+        let mut divergent = Vec::new();
+        for (table, columns) in expected {
+            for (node, result) in self
+                .clickhouse_client
+                .column_names_on_all_nodes(table)
+                .await
+            {
+                let problem = match result {
+                    Ok(existing) => {
+                        let missing: Vec<&str> = columns
+                            .iter()
+                            .copied()
+                            .filter(|column| !existing.contains(*column))
+                            .collect();
+                        (!missing.is_empty()).then(|| format!("missing columns {missing:?}"))
+                    }
+                    Err(e) => Some(format!("check failed: {e}")),
+                };
+                let gauge = CLICKHOUSE_SCHEMA_DIVERGENT.with_label_values(&[node.as_str(), table]);
+                match problem {
+                    Some(problem) => {
+                        gauge.set(1);
+                        divergent.push(format!("{node} {table}: {problem}"));
+                    }
+                    None => gauge.set(0),
+                }
+            }
+        }
+        divergent
+    }
+}
+
+#[async_trait::async_trait]
+impl TaskExecutor for ClickhouseSchemaCheckTask {
+    fn name(&self) -> &str {
+        &self.task_name
+    }
+
+    async fn execute(&self) -> Result<()> {
+        let divergent = self.check().await;
+        if divergent.is_empty() {
+            info!("ClickHouse schema check passed on all nodes.");
+            return Ok(());
+        }
+        for node in &divergent {
+            error!("ClickHouse schema divergence: {node}");
+        }
+        Err(anyhow!(
+            "ClickHouse schema diverged on {} node/table pairs: {}",
+            divergent.len(),
+            divergent.join("; ")
+        ))
+    }
+}
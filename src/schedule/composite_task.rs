@@ -1,10 +1,19 @@
 use crate::TaskExecutor;
+use crate::db::mysql_pool::is_pool_exhausted;
+use crate::models::task_run::with_task_run;
+use crate::models::task_run_history::TaskRunRecorder;
+use crate::schedule::run_manifest::{RunManifestCollector, RunManifestWriter};
+use crate::schedule::status_updates::StatusUpdateCollector;
 use std::sync::Arc;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 pub struct CompositeTask {
     tasks: Vec<Arc<dyn TaskExecutor + Send + Sync + 'static>>,
     pub task_name: String,
+    // 设置后子任务的状态回写在全部子任务结束后合并执行
+    status_collector: Option<Arc<StatusUpdateCollector>>,
+    // 设置后每次执行结束时生成签名的归档清单
+    manifest_writer: Option<Arc<RunManifestWriter>>,
 }
 
 impl CompositeTask {
@@ -12,7 +21,52 @@ impl CompositeTask {
         tasks: Vec<Arc<dyn TaskExecutor + Send + Sync + 'static>>,
         task_name: String,
     ) -> Self {
-        Self { tasks, task_name }
+        Self {
+            tasks,
+            task_name,
+            status_collector: None,
+            manifest_writer: None,
+        }
+    }
+
+    /// 合并子任务的状态回写，在组合任务结束时统一执行
+    pub fn with_status_batching(mut self, collector: StatusUpdateCollector) -> Self {
+        self.status_collector = Some(Arc::new(collector));
+        self
+    }
+
+    /// 每次执行结束时生成归档清单，None 时不生成
+    pub fn with_manifest(mut self, writer: Option<Arc<RunManifestWriter>>) -> Self {
+        self.manifest_writer = writer;
+        self
+    }
+
+    async fn run_batched(&self) -> anyhow::Result<()> {
+        match &self.status_collector {
+            Some(collector) => Arc::clone(collector).scope(self.run_subtasks()).await,
+            None => self.run_subtasks().await,
+        }
+    }
+
+    /// 子任务失败只记录日志；连接池耗尽时不再启动其余子任务并返回错误，调度器不会重跑已开始的运行
+    async fn run_subtasks(&self) -> anyhow::Result<()> {
+        let tasks_len = self.tasks.len();
+        for (idx, subtask) in self.tasks.iter().enumerate() {
+            let sub_name = subtask.name();
+            info!("Starting subtask {}/{tasks_len}: '{sub_name}'.", idx + 1);
+            match TaskRunRecorder::run_nested(subtask.as_ref(), "subtask").await {
+                Ok(_) => info!("Subtask '{sub_name}' completed successfully."),
+                Err(e) if is_pool_exhausted(&e) => {
+                    warn!(
+                        "Subtask '{sub_name}' hit MySQL pool exhaustion, skipping remaining {} subtasks.",
+                        tasks_len - idx - 1
+                    );
+                    return Err(e);
+                }
+                Err(e) => error!("Subtask '{sub_name}' failed: {e:?}"),
+            }
+        }
+        Ok(())
     }
 }
 
@@ -27,15 +81,22 @@ impl TaskExecutor for CompositeTask {
         let tasks_len = self.tasks.len();
 
         info!("Composite task '{task_name}' started. Containing {tasks_len} subtasks.");
-        for (idx, subtask) in self.tasks.iter().enumerate() {
-            let sub_name = subtask.name();
-            info!("Starting subtask {}/{tasks_len}: '{sub_name}'.", idx + 1);
-            match subtask.execute().await {
-                Ok(_) => info!("Subtask '{sub_name}' completed successfully."),
-                Err(e) => error!("Subtask '{sub_name}' failed: {e:?}"),
+        let result = match &self.manifest_writer {
+            Some(writer) => {
+                // 每次执行登记一个 run_id 并使用新的收集器，清单记录该 run_id；清单失败只告警
+                let run = async {
+                    let collector = Arc::new(RunManifestCollector::default());
+                    let result = Arc::clone(&collector).scope(self.run_batched()).await;
+                    if let Err(e) = writer.write(task_name, &collector).await {
+                        warn!("Failed to write run manifest of '{task_name}': {e:?}");
+                    }
+                    result
+                };
+                with_task_run(writer.mysql_pool(), task_name, run).await
             }
-        }
+            None => self.run_batched().await,
+        };
         info!("Composite task '{task_name}' finished.");
-        Ok(())
+        result
     }
 }
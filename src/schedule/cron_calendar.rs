@@ -0,0 +1,133 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Days};
+use chrono_tz::Tz;
+use croner::Cron;
+use croner::parser::{CronParser, Seconds};
+use serde::Serialize;
+
+use crate::utils::timefmt;
+
+/// 所有 cron 任务使用的时区
+pub const SCHEDULER_TIMEZONE: Tz = chrono_tz::Asia::Shanghai;
+/// 单个任务最多展开的触发次数，避免高频任务撑大响应
+pub const MAX_FIRE_TIMES_PER_JOB: usize = 5000;
+
+/// 调度器中注册的一个 cron 任务
+#[derive(Debug, Clone, Serialize)]
+pub struct CronSchedule {
+    pub task_name: String,
+    pub cron_schedule: String,
+}
+
+/// 一个 cron 任务在查询范围内的触发时间
+#[derive(Debug, Clone, Serialize)]
+pub struct UpcomingFires {
+    pub task_name: String,
+    pub cron_schedule: String,
+    pub timezone: String,
+    pub fire_times: Vec<String>,
+    /// 超过 MAX_FIRE_TIMES_PER_JOB 被截断
+    pub truncated: bool,
+}
+
+fn parse_cron(cron_schedule: &str) -> Result<Cron> {
+    CronParser::builder()
+        .seconds(Seconds::Optional)
+        .build()
+        .parse(cron_schedule)
+        .with_context(|| format!("Invalid cron expression '{cron_schedule}'"))
+}
+
+/// 校验 cron 表达式，解析规则与调度器一致
+pub fn validate_cron(cron_schedule: &str) -> Result<()> {
+    parse_cron(cron_schedule).map(|_| ())
+}
+
+/// from 之后的下一次触发时间，不存在（如 2 月 30 日）时为 None
+pub fn next_fire_time(cron_schedule: &str, from: DateTime<Tz>) -> Result<Option<DateTime<Tz>>> {
+    let cron = parse_cron(cron_schedule)?;
+    Ok(cron.find_next_occurrence(&from, false).ok())
+}
+
+/// 展开 (from, until] 范围内的触发时间，最多 limit 个
+pub fn fire_times_between(
+    cron_schedule: &str,
+    from: DateTime<Tz>,
+    until: DateTime<Tz>,
+    limit: usize,
+) -> Result<Vec<DateTime<Tz>>> {
+    let cron = parse_cron(cron_schedule)?;
+    let mut fire_times = Vec::new();
+    let mut cursor = from;
+    while fire_times.len() < limit {
+        // 找不到下一次触发时间（如 2 月 30 日）视为范围内不触发
+        let Ok(next) = cron.find_next_occurrence(&cursor, false) else {
+            break;
+        };
+        if next > until {
+            break;
+        }
+        fire_times.push(next);
+        cursor = next;
+    }
+    Ok(fire_times)
+}
+
+/// 从 from 开始展开未来 days 天内每个任务的触发时间
+pub fn upcoming_fires(
+    schedules: &[CronSchedule],
+    from: DateTime<Tz>,
+    days: u32,
+) -> Result<Vec<UpcomingFires>> {
+    let until = from + Days::new(u64::from(days));
+    schedules
+        .iter()
+        .map(|schedule| {
+            let mut fire_times = fire_times_between(
+                &schedule.cron_schedule,
+                from,
+                until,
+                MAX_FIRE_TIMES_PER_JOB + 1,
+            )?;
+            let truncated = fire_times.len() > MAX_FIRE_TIMES_PER_JOB;
+            fire_times.truncate(MAX_FIRE_TIMES_PER_JOB);
+            Ok(UpcomingFires {
+                task_name: schedule.task_name.clone(),
+                cron_schedule: schedule.cron_schedule.clone(),
+                timezone: from.timezone().name().to_string(),
+                fire_times: fire_times
+                    .into_iter()
+                    .map(|time| timefmt::datetime(time.naive_local()))
+                    .collect(),
+                truncated,
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn test_fire_times_between() {
+    use chrono::TimeZone;
+
+    let from = SCHEDULER_TIMEZONE
+        .with_ymd_and_hms(2026, 3, 5, 0, 0, 0)
+        .unwrap();
+    let until = from + Days::new(2);
+    let fire_times = fire_times_between("0 10 0 * * *", from, until, 10).unwrap();
+    let fire_times: Vec<String> = fire_times
+        .into_iter()
+        .map(|time| timefmt::datetime(time.naive_local()))
+        .collect();
+    assert_eq!(
+        fire_times,
+        vec!["2026-03-05 00:10:00", "2026-03-06 00:10:00"]
+    );
+
+    // 不存在的日期不会触发
+    assert!(
+        fire_times_between("0 0 0 30 2 *", from, until, 10)
+            .unwrap()
+            .is_empty()
+    );
+    assert!(fire_times_between("not a cron", from, until, 10).is_err());
+}
@@ -0,0 +1,211 @@
+use anyhow::{Context, Result, anyhow};
+use sqlx::{MySqlPool, QueryBuilder, Row};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::binlog::{TelecomMssOrg, TelecomMssOrgMapping};
+use crate::db::query_runner::QueryRunner;
+use crate::utils::gateway_client::{MSS_ORG_QUERY_SERVICE, MSS_ORG_TRANSLATE_SERVICE};
+use crate::{AppContext, TaskExecutor};
+
+/// 从 d_* 表把被用户引用最多的组织及其 MSS 映射写入网关缓存，
+/// 避免部署后第一个同步周期集中调用 mss.organization.translate / query。
+/// 只预热 gateway_cache.ttl 内写入的映射，过期时间扣除映射的存在时间，且不覆盖已有的缓存条目
+pub struct GatewayCacheWarmupTask {
+    app_context: Arc<AppContext>,
+    task_name: String,
+}
+
+impl GatewayCacheWarmupTask {
+    pub fn new(app_context: Arc<AppContext>, task_name: String) -> Self {
+        Self {
+            app_context,
+            task_name,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl TaskExecutor for GatewayCacheWarmupTask {
+    fn name(&self) -> &str {
+        &self.task_name
+    }
+
+    async fn execute(&self) -> Result<()> {
+        let Some(cache) = &self.app_context.gateway_client.cache else {
+            info!("Gateway cache disabled, skipping warmup.");
+            return Ok(());
+        };
+        let pool = &self.app_context.mysql_pool;
+
+        let org_codes = top_referenced_orgs(pool, self.app_context.gateway_cache.preload_top_n)
+            .await
+            .context("Failed to load top referenced orgs")?;
+        let mappings = load_mss_org_mappings(pool, &org_codes, self.app_context.gateway_cache.ttl)
+            .await
+            .context("Failed to load d_mss_org_mapping")?;
+        // d_mss_org 没有写入时间，按指向它的映射中最旧的一条计算
+        let mut mss_code_ages: BTreeMap<String, Duration> = BTreeMap::new();
+        for (mapping, age) in &mappings {
+            if let Some(mss_code) = &mapping.mss_code {
+                let oldest = mss_code_ages.entry(mss_code.clone()).or_default();
+                *oldest = (*oldest).max(*age);
+            }
+        }
+        let mss_codes: Vec<String> = mss_code_ages.keys().cloned().collect();
+        let mss_orgs = load_mss_orgs(pool, &mss_codes)
+            .await
+            .context("Failed to load d_mss_org")?;
+
+        let mut warmed = 0;
+        let mut failed = 0;
+        for (mapping, age) in &mappings {
+            let Some(code) = &mapping.code else {
+                continue;
+            };
+            match cache
+                .try_put_aged(MSS_ORG_TRANSLATE_SERVICE, code, mapping, *age)
+                .await
+            {
+                Ok(written) => warmed += usize::from(written),
+                Err(e) => {
+                    warn!("Failed to warm {MSS_ORG_TRANSLATE_SERVICE} for {code}: {e:?}");
+                    failed += 1;
+                }
+            }
+        }
+        for (mss_code, orgs) in &mss_orgs {
+            let age = mss_code_ages[mss_code];
+            match cache
+                .try_put_aged(MSS_ORG_QUERY_SERVICE, mss_code, orgs, age)
+                .await
+            {
+                Ok(written) => warmed += usize::from(written),
+                Err(e) => {
+                    warn!("Failed to warm {MSS_ORG_QUERY_SERVICE} for {mss_code}: {e:?}");
+                    failed += 1;
+                }
+            }
+        }
+        info!(
+            "Gateway cache warmed: {} orgs, {} fresh mappings, {} mss orgs, {warmed} entries written, {failed} failed",
+            org_codes.len(),
+            mappings.len(),
+            mss_orgs.len()
+        );
+        if failed > 0 {
+            return Err(anyhow!("Gateway cache warmup failed for {failed} entries"));
+        }
+        Ok(())
+    }
+}
+
+/// d_telecom_user 中引用次数最多的 top_n 个组织编码
+async fn top_referenced_orgs(pool: &MySqlPool, top_n: usize) -> Result<Vec<String>> {
+    let query = sqlx::query_scalar(
+        "SELECT org FROM d_telecom_user WHERE org IS NOT NULL AND org <> ''
+        GROUP BY org ORDER BY COUNT(*) DESC LIMIT ?",
+    )
+    .bind(top_n as u64);
+    let codes = QueryRunner::new("top_referenced_orgs")
+        .run(query.fetch_all(pool))
+        .await?;
+    Ok(codes)
+}
+
+/// 组织的 MSS 映射及其写入至今的时间，只取 max_age 内写入且有 MSS 编码的记录
+async fn load_mss_org_mappings(
+    pool: &MySqlPool,
+    codes: &[String],
+    max_age: Duration,
+) -> Result<Vec<(TelecomMssOrgMapping, Duration)>> {
+    if codes.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut query_builder = QueryBuilder::new(
+        "SELECT code, msscode, TIMESTAMPDIFF(SECOND, updated_at, NOW()) AS age_secs
+        FROM d_mss_org_mapping
+        WHERE msscode IS NOT NULL AND updated_at >= NOW() - INTERVAL ",
+    );
+    query_builder.push_bind(max_age.as_secs());
+    query_builder.push(" SECOND AND code IN (");
+    let mut separated = query_builder.separated(", ");
+    for code in codes {
+        separated.push_bind(code);
+    }
+    separated.push_unseparated(")");
+    let rows = QueryRunner::new("mss_org_mappings_by_codes")
+        .run(query_builder.build().fetch_all(pool))
+        .await?;
+    rows.iter()
+        .map(|row| {
+            let age_secs: i64 = row.try_get("age_secs")?;
+            let mapping = TelecomMssOrgMapping {
+                code: row.try_get("code")?,
+                mss_code: row.try_get("msscode")?,
+                from_cache: false,
+            };
+            Ok((mapping, Duration::from_secs(age_secs.max(0) as u64)))
+        })
+        .collect()
+}
+
+/// 按 hrcode 分组，与 mss.organization.query 的返回一致。
+/// d_mss_org 未保存的字段（departmentType 等）为 None
+async fn load_mss_orgs(
+    pool: &MySqlPool,
+    mss_codes: &[String],
+) -> Result<BTreeMap<String, Vec<TelecomMssOrg>>> {
+    let mut grouped: BTreeMap<String, Vec<TelecomMssOrg>> = BTreeMap::new();
+    if mss_codes.is_empty() {
+        return Ok(grouped);
+    }
+    let mut query_builder = QueryBuilder::new(
+        "SELECT code, companytype, hrcode, CAST(sort AS DOUBLE) AS sort, type,
+        parentcompanycode, identity, name, parentdepartmentcode, id,
+        CAST(time AS SIGNED) AS time, CAST(status AS UNSIGNED) AS status,
+        hitdate1, hitdate, year, month
+        FROM d_mss_org WHERE hrcode IN (",
+    );
+    let mut separated = query_builder.separated(", ");
+    for mss_code in mss_codes {
+        separated.push_bind(mss_code);
+    }
+    separated.push_unseparated(")");
+    let rows = QueryRunner::new("mss_orgs_by_hr_codes")
+        .run(query_builder.build().fetch_all(pool))
+        .await?;
+    for row in rows {
+        let hr_code: Option<String> = row.try_get("hrcode")?;
+        let Some(key) = hr_code.clone() else {
+            continue;
+        };
+        let sort: Option<f64> = row.try_get("sort")?;
+        let status: Option<u64> = row.try_get("status")?;
+        grouped.entry(key).or_default().push(TelecomMssOrg {
+            code: row.try_get("code")?,
+            company_type: row.try_get("companytype")?,
+            hr_code,
+            sort: sort.map(|sort| sort as f32),
+            org_type: row.try_get("type")?,
+            parent_company_code: row.try_get("parentcompanycode")?,
+            name: row.try_get("name")?,
+            parent_department_code: row.try_get("parentdepartmentcode")?,
+            id: row.try_get("id")?,
+            status: status.and_then(|status| u8::try_from(status).ok()),
+            identity: row.try_get("identity")?,
+            department_type: None,
+            time: row.try_get("time")?,
+            chief_leader: None,
+            deputy_leader: None,
+            department_level: None,
+            hit_date: row.try_get("hitdate")?,
+            year: row.try_get("year")?,
+            month: row.try_get("month")?,
+            hit_date1: row.try_get("hitdate1")?,
+        });
+    }
+    Ok(grouped)
+}
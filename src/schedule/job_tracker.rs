@@ -0,0 +1,264 @@
+//! 手动触发的后台作业的进度：每个作业由若干项（如补推的每个日期）组成，逐项记录状态，
+//! 结束后汇总为报告。只保存在内存中，超过上限时丢弃最早结束的作业。
+//! MSS 返回 9019 休息期间，正在处理的项标记为 throttled 并记录恢复时间，与真正卡住区分开
+
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tracing::info;
+
+use crate::models::task_run_history::RunTotals;
+use crate::utils::timefmt;
+
+// 内存中最多保留的已结束作业数
+const MAX_FINISHED_JOBS: usize = 200;
+
+// 作业状态也是接口的响应，定义在 web::models 中
+pub use crate::web::{JobItemStatus, JobReport, JobState, JobStatus};
+
+impl JobStatus {
+    fn report(&self) -> JobReport {
+        let mut report = JobReport {
+            items: self.items.len(),
+            ..Default::default()
+        };
+        for (name, item) in &self.items {
+            report.totals += item.totals;
+            match item.state {
+                JobState::Succeeded => report.succeeded += 1,
+                JobState::Failed => {
+                    report.failed += 1;
+                    report.failed_items.push(name.clone());
+                }
+                JobState::Pending | JobState::Running | JobState::Throttled => {}
+            }
+        }
+        report
+    }
+}
+
+#[derive(Debug, Default)]
+struct Jobs {
+    jobs: BTreeMap<String, JobStatus>,
+    /// 已结束作业的 ID，按结束顺序
+    finished: VecDeque<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct JobTracker {
+    jobs: Mutex<Jobs>,
+}
+
+impl JobTracker {
+    /// 登记作业及其全部项，各项初始为 pending
+    pub fn start(&self, job_id: &str, kind: &str, items: &[String]) {
+        let status = JobStatus {
+            job_id: job_id.to_string(),
+            kind: kind.to_string(),
+            state: JobState::Running,
+            started_at: timefmt::datetime(timefmt::now_local()),
+            finished_at: None,
+            items: items
+                .iter()
+                .map(|item| {
+                    let status = JobItemStatus {
+                        state: JobState::Pending,
+                        started_at: None,
+                        finished_at: None,
+                        throttled_until: None,
+                        totals: RunTotals::default(),
+                        error: None,
+                        throttled: 0,
+                    };
+                    (item.clone(), status)
+                })
+                .collect(),
+            report: None,
+        };
+        self.lock().jobs.insert(job_id.to_string(), status);
+    }
+
+    pub fn item_started(&self, job_id: &str, item: &str) {
+        self.update_item(job_id, item, |status| {
+            status.state = JobState::Running;
+            status.started_at = Some(timefmt::datetime(timefmt::now_local()));
+        });
+    }
+
+    /// 该项的请求遇到 9019，休息 wait 后继续
+    pub fn item_throttled(&self, job_id: &str, item: &str, wait: Duration) {
+        let until = timefmt::now_local()
+            + chrono::Duration::from_std(wait).unwrap_or(chrono::Duration::zero());
+        let until = timefmt::datetime(until);
+        info!("Job {job_id} item {item} throttled by MSS until {until}");
+        self.update_item(job_id, item, |status| {
+            status.state = JobState::Throttled;
+            status.throttled_until = Some(until);
+            status.throttled += 1;
+        });
+    }
+
+    /// 休息结束，该项所有请求都恢复后回到 running
+    pub fn item_resumed(&self, job_id: &str, item: &str) {
+        self.update_item(job_id, item, |status| {
+            status.throttled = status.throttled.saturating_sub(1);
+            if status.throttled == 0 && status.state == JobState::Throttled {
+                status.state = JobState::Running;
+                status.throttled_until = None;
+            }
+        });
+    }
+
+    /// 记录一项的结果，error 为 None 表示成功
+    pub fn item_finished(
+        &self,
+        job_id: &str,
+        item: &str,
+        totals: RunTotals,
+        error: Option<String>,
+    ) {
+        self.update_item(job_id, item, |status| {
+            status.state = if error.is_some() {
+                JobState::Failed
+            } else {
+                JobState::Succeeded
+            };
+            status.finished_at = Some(timefmt::datetime(timefmt::now_local()));
+            status.throttled_until = None;
+            status.throttled = 0;
+            status.totals = totals;
+            status.error = error;
+        });
+    }
+
+    /// 结束作业并返回汇总，有失败项时作业为 failed
+    pub fn finish(&self, job_id: &str) -> Option<JobReport> {
+        let mut jobs = self.lock();
+        let status = jobs.jobs.get_mut(job_id)?;
+        let report = status.report();
+        status.state = if report.failed > 0 {
+            JobState::Failed
+        } else {
+            JobState::Succeeded
+        };
+        status.finished_at = Some(timefmt::datetime(timefmt::now_local()));
+        status.report = Some(report.clone());
+        jobs.finished.push_back(job_id.to_string());
+        while jobs.finished.len() > MAX_FINISHED_JOBS {
+            if let Some(oldest) = jobs.finished.pop_front() {
+                jobs.jobs.remove(&oldest);
+            }
+        }
+        Some(report)
+    }
+
+    pub fn get(&self, job_id: &str) -> Option<JobStatus> {
+        self.lock().jobs.get(job_id).cloned()
+    }
+
+    fn update_item(&self, job_id: &str, item: &str, update: impl FnOnce(&mut JobItemStatus)) {
+        if let Some(status) = self
+            .lock()
+            .jobs
+            .get_mut(job_id)
+            .and_then(|job| job.items.get_mut(item))
+        {
+            update(status);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Jobs> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// 当前任务正在处理的作业项，MSS 客户端休息时通过它更新作业状态
+struct CurrentJobItem {
+    tracker: Arc<JobTracker>,
+    job_id: String,
+    item: String,
+}
+
+tokio::task_local! {
+    static CURRENT_JOB_ITEM: Arc<CurrentJobItem>;
+}
+
+/// 在 fut 执行期间把遇到的 9019 休息记到作业 job_id 的 item 上
+pub async fn with_job_item<F: Future>(
+    tracker: Arc<JobTracker>,
+    job_id: &str,
+    item: &str,
+    fut: F,
+) -> F::Output {
+    let current = Arc::new(CurrentJobItem {
+        tracker,
+        job_id: job_id.to_string(),
+        item: item.to_string(),
+    });
+    CURRENT_JOB_ITEM.scope(current, fut).await
+}
+
+/// 当前作业项开始休息，不在作业中时不做处理
+pub fn mark_throttled(wait: Duration) {
+    let _ = CURRENT_JOB_ITEM.try_with(|current| {
+        current
+            .tracker
+            .item_throttled(&current.job_id, &current.item, wait)
+    });
+}
+
+/// 当前作业项休息结束
+pub fn mark_resumed() {
+    let _ = CURRENT_JOB_ITEM
+        .try_with(|current| current.tracker.item_resumed(&current.job_id, &current.item));
+}
+
+#[test]
+fn test_job_tracker_report() {
+    let tracker = JobTracker::default();
+    let dates = vec!["2026-10-01".to_string(), "2026-10-02".to_string()];
+    tracker.start("job", "push_mss", &dates);
+    tracker.item_started("job", "2026-10-01");
+    assert_eq!(
+        tracker.get("job").unwrap().items["2026-10-01"].state,
+        JobState::Running
+    );
+    let totals = RunTotals {
+        fetched: 3,
+        pushed: 2,
+        failed: 1,
+    };
+    tracker.item_finished("job", "2026-10-01", totals, None);
+    tracker.item_finished("job", "2026-10-02", totals, Some("boom".to_string()));
+
+    let report = tracker.finish("job").unwrap();
+    assert_eq!((report.succeeded, report.failed), (1, 1));
+    assert_eq!(report.totals.pushed, 4);
+    assert_eq!(report.failed_items, vec!["2026-10-02".to_string()]);
+    assert_eq!(tracker.get("job").unwrap().state, JobState::Failed);
+    assert!(tracker.finish("missing").is_none());
+}
+
+#[test]
+fn test_job_tracker_throttled() {
+    let tracker = JobTracker::default();
+    tracker.start("job", "push_mss", &["2026-10-01".to_string()]);
+    tracker.item_started("job", "2026-10-01");
+    // 两个 worker 同时休息，都恢复后才回到 running
+    tracker.item_throttled("job", "2026-10-01", Duration::from_secs(60));
+    tracker.item_throttled("job", "2026-10-01", Duration::from_secs(60));
+    let item = &tracker.get("job").unwrap().items["2026-10-01"];
+    assert_eq!(item.state, JobState::Throttled);
+    assert!(item.throttled_until.is_some());
+    tracker.item_resumed("job", "2026-10-01");
+    assert_eq!(
+        tracker.get("job").unwrap().items["2026-10-01"].state,
+        JobState::Throttled
+    );
+    tracker.item_resumed("job", "2026-10-01");
+    let item = &tracker.get("job").unwrap().items["2026-10-01"];
+    assert_eq!(item.state, JobState::Running);
+    assert!(item.throttled_until.is_none());
+}
@@ -1,23 +1,48 @@
 pub mod base_psn_push;
+pub mod binlog_digest;
+pub mod binlog_gap_replay;
+pub mod binlog_shard;
 pub mod binlog_sync;
+pub mod clickhouse_reconcile;
+pub mod clickhouse_replay;
+pub mod clickhouse_schema_check;
 pub mod composite_task;
+pub mod cron_calendar;
+pub mod gateway_cache_warmup;
+pub mod job_tracker;
+pub mod mss_retry_queue;
+pub mod poll_interval;
 pub mod psn_archive_push;
 pub mod psn_archive_sc_push;
 pub mod psn_class_push;
 pub mod psn_class_sc_push;
+pub mod psn_delete_push;
 pub mod psn_lecturer_push;
 pub mod psn_lecturer_sc_push;
 pub mod psn_training_push;
 pub mod psn_training_sc_push;
 pub mod push_executor;
+pub mod push_outbox;
+pub mod push_pipeline;
+pub mod push_verification;
+pub mod run_manifest;
+pub mod running_tasks;
+pub mod shadow_push;
+pub mod status_updates;
+pub mod task_registry;
 pub mod task_scheduler_manager;
 
 pub use base_psn_push::BasePsnPushTask;
+pub use binlog_digest::BinlogDigestTask;
+pub use binlog_gap_replay::BinlogGapReplayTask;
+pub use clickhouse_schema_check::ClickhouseSchemaCheckTask;
 pub use composite_task::CompositeTask;
+pub use gateway_cache_warmup::GatewayCacheWarmupTask;
 pub use psn_archive_push::PsnArchivePushTask;
 pub use psn_archive_sc_push::PsnArchiveScPushTask;
 pub use psn_class_push::PsnClassPushTask;
 pub use psn_class_sc_push::PsnClassScPushTask;
+pub use psn_delete_push::PsnDeletePushTask;
 pub use psn_lecturer_push::PsnLecturerPushTask;
 pub use psn_lecturer_sc_push::PsnLecturerScPushTask;
 pub use psn_training_push::PsnTrainingPushTask;
@@ -0,0 +1,240 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use redis::{AsyncCommands, Script};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{error, info, warn};
+
+use crate::config::RetryQueueConfig;
+use crate::schedule::BasePsnPushTask;
+use crate::schedule::push_executor::{
+    failure_reason, is_transient, push_record, write_back_statuses,
+};
+use crate::shutdown::ShutdownController;
+use crate::utils::redis::RedisMgr;
+use crate::utils::timefmt;
+use crate::{AppContext, DynamicPsnData, PsnDataKind, PsnRecord};
+
+// 有序集合，score 为下次重试的毫秒时间戳；取出处理中的记录 score 为租约到期时间
+const RETRY_QUEUE_KEY: &str = "mss:retry:queue";
+
+// 记录仍然到期时把 score 改为租约到期时间，返回 1 表示取得。
+// 取得后其他节点在租约内看不到这条记录，处理节点崩溃时租约到期后重新可见
+const CLAIM_SCRIPT: &str = r#"
+    local score = redis.call("ZSCORE", KEYS[1], ARGV[1])
+    if score and tonumber(score) <= tonumber(ARGV[2]) then
+        redis.call("ZADD", KEYS[1], ARGV[3], ARGV[1])
+        return 1
+    end
+    return 0
+"#;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RetryEntry {
+    kind: PsnDataKind,
+    data_id: String,
+    /// 本条是第几次重试，从 1 开始
+    attempt: u32,
+    record: Value,
+}
+
+/// 第 attempt 次重试前的等待时间：base_delay * 2^(attempt-1)，不超过 max_delay
+pub fn retry_delay(config: &RetryQueueConfig, attempt: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+    config
+        .base_delay
+        .saturating_mul(factor)
+        .min(config.max_delay)
+}
+
+/// MSS 暂时性失败记录的延迟重试队列（Redis 有序集合），推送任务只负责入队，
+/// 由 `spawn_retry_worker` 启动的 worker 按到期时间重试并回写状态
+pub struct MssRetryQueue {
+    redis_mgr: RedisMgr,
+    config: Arc<RetryQueueConfig>,
+}
+
+impl MssRetryQueue {
+    pub fn new(redis_mgr: RedisMgr, config: Arc<RetryQueueConfig>) -> Self {
+        Self { redis_mgr, config }
+    }
+
+    pub async fn enqueue(
+        &self,
+        kind: PsnDataKind,
+        psn_data: &DynamicPsnData,
+        attempt: u32,
+    ) -> Result<()> {
+        let entry = RetryEntry {
+            kind,
+            data_id: psn_data.id().to_string(),
+            attempt,
+            record: serde_json::to_value(psn_data).context("Failed to serialize retry record")?,
+        };
+        let delay = retry_delay(&self.config, attempt);
+        let due_at = timefmt::timestamp_ms() + delay.as_millis() as i64;
+        let member = serde_json::to_string(&entry).context("Failed to serialize retry entry")?;
+
+        let mut conn = self.redis_mgr.clone();
+        let _: () = conn
+            .zadd(RETRY_QUEUE_KEY, member, due_at)
+            .await
+            .context("redis ZADD failed")?;
+        info!(
+            "Queued {kind:?} record {} for retry #{attempt} in {delay:?}",
+            entry.data_id
+        );
+        Ok(())
+    }
+
+    /// 取得已到期的记录，返回原始成员与解析后的记录。取得只是续租，记录仍在队列中，
+    /// 重试成功、重新入队或放弃后再调用 `remove` 删除；无法解析的记录直接删除
+    async fn take_due(&self) -> Result<Vec<(String, RetryEntry)>> {
+        let now = timefmt::timestamp_ms();
+        let lease_until = now + self.config.lease.as_millis() as i64;
+        let claim = Script::new(CLAIM_SCRIPT);
+        let mut conn = self.redis_mgr.clone();
+        let members: Vec<String> = conn
+            .zrangebyscore_limit(
+                RETRY_QUEUE_KEY,
+                "-inf",
+                now,
+                0,
+                self.config.batch_size as isize,
+            )
+            .await
+            .context("redis ZRANGEBYSCORE failed")?;
+
+        let mut entries = Vec::with_capacity(members.len());
+        for member in members {
+            let claimed: i32 = claim
+                .key(RETRY_QUEUE_KEY)
+                .arg(&member)
+                .arg(now)
+                .arg(lease_until)
+                .invoke_async(&mut conn)
+                .await
+                .context("redis claim script failed")?;
+            if claimed == 0 {
+                continue;
+            }
+            match serde_json::from_str(&member) {
+                Ok(entry) => entries.push((member, entry)),
+                Err(e) => {
+                    error!("Dropping malformed retry entry: {e}. Entry: {member}");
+                    self.remove(&member).await;
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// 删除已处理完的记录，失败时记录在租约到期后会被再次重试
+    async fn remove(&self, member: &str) {
+        let mut conn = self.redis_mgr.clone();
+        let result: redis::RedisResult<usize> = conn.zrem(RETRY_QUEUE_KEY, member).await;
+        if let Err(e) = result {
+            error!("Failed to remove retry entry, it will be retried after the lease expires: {e}");
+        }
+    }
+}
+
+/// 启动后台 worker，按 poll_interval 检查到期记录。
+/// 每轮重试登记为运行中的任务，关闭时处理完已取出的记录后退出
+pub fn spawn_retry_worker(
+    app_context: Arc<AppContext>,
+    retry_queue: Arc<MssRetryQueue>,
+    shutdown: Arc<ShutdownController>,
+) {
+    tokio::spawn(async move {
+        info!("MSS retry worker started.");
+        // 与推送任务共用 HTTP 客户端、报文归档和结果记录
+        let base_task = BasePsnPushTask::new(app_context, None, None);
+        let mut ticker = tokio::time::interval(retry_queue.config.poll_interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.cancelled() => break,
+            }
+            let Some(_guard) = shutdown.track() else {
+                break;
+            };
+            match retry_queue.take_due().await {
+                Ok(entries) => {
+                    for (member, entry) in entries {
+                        if retry_entry(&base_task, &retry_queue, entry).await {
+                            retry_queue.remove(&member).await;
+                        }
+                    }
+                }
+                Err(e) => error!("Failed to read MSS retry queue: {e:?}"),
+            }
+        }
+        info!("MSS retry worker stopped.");
+    });
+}
+
+/// 重试一条记录，返回 true 表示已处理完（成功、重新入队或放弃），可以从队列中删除
+async fn retry_entry(
+    base_task: &BasePsnPushTask,
+    retry_queue: &MssRetryQueue,
+    entry: RetryEntry,
+) -> bool {
+    let RetryEntry {
+        kind,
+        data_id,
+        attempt,
+        record,
+    } = entry;
+    let psn_data = match DynamicPsnData::from_value(kind, record) {
+        Ok(psn_data) => psn_data,
+        Err(e) => {
+            error!("Dropping retry entry {data_id} of {kind:?}: {e}");
+            return true;
+        }
+    };
+
+    // 队列本身负责重试，单次推送不再等待
+    let retry_policy = base_task.retry_policy.with_max_attempts(1);
+    let error = match push_record(base_task, &psn_data, &retry_policy).await {
+        Ok(()) => {
+            info!("Retry #{attempt} of {kind:?} record {data_id} succeeded.");
+            write_back_statuses(base_task, kind, &[data_id], &[]).await;
+            return true;
+        }
+        Err(e) => e,
+    };
+
+    if is_transient(&error) && attempt < retry_queue.config.max_attempts {
+        warn!("Retry #{attempt} of {kind:?} record {data_id} failed: {error:?}");
+        return match retry_queue.enqueue(kind, &psn_data, attempt + 1).await {
+            Ok(()) => true,
+            Err(e) => {
+                // 保留原记录，租约到期后按本次的重试次数再试
+                error!("Failed to requeue {data_id}, keeping the claimed entry: {e:?}");
+                false
+            }
+        };
+    }
+    error!("Giving up on {kind:?} record {data_id} after {attempt} retries: {error:?}");
+    let reason = failure_reason(&psn_data, &error);
+    write_back_statuses(base_task, kind, &[], &[(data_id, reason)]).await;
+    true
+}
+
+#[test]
+fn test_retry_delay() {
+    let config = RetryQueueConfig {
+        base_delay: Duration::from_secs(60),
+        max_delay: Duration::from_secs(600),
+        ..Default::default()
+    };
+    assert_eq!(retry_delay(&config, 1), Duration::from_secs(60));
+    assert_eq!(retry_delay(&config, 2), Duration::from_secs(120));
+    assert_eq!(retry_delay(&config, 4), Duration::from_secs(480));
+    assert_eq!(retry_delay(&config, 5), Duration::from_secs(600));
+    assert_eq!(retry_delay(&config, 40), Duration::from_secs(600));
+}
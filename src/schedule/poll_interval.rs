@@ -0,0 +1,93 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::BinlogPollingConfig;
+use crate::schedule::binlog_sync::SyncCycle;
+
+/// binlog 连续同步的自适应轮询间隔（AIMD），参数见 BinlogPollingConfig
+pub struct AdaptivePollInterval {
+    config: Arc<BinlogPollingConfig>,
+    current: Duration,
+    error_streak: u32,
+}
+
+impl AdaptivePollInterval {
+    pub fn new(config: Arc<BinlogPollingConfig>) -> Self {
+        let current = config.min_interval;
+        AdaptivePollInterval {
+            config,
+            current,
+            error_streak: 0,
+        }
+    }
+
+    /// 周期成功后的休眠时间：未追上或拉取记录较多时乘性减小，否则加性增大
+    pub fn on_success(&mut self, cycle: SyncCycle) -> Duration {
+        self.error_streak = 0;
+        let busy = !cycle.caught_up || cycle.records >= self.config.busy_records;
+        let next = if busy {
+            self.current
+                .mul_f64(self.config.decrease_factor.clamp(0.0, 1.0))
+        } else {
+            self.current + self.config.increase_step
+        };
+        self.current = self.clamp(next);
+        self.current
+    }
+
+    /// 周期出错后的休眠时间：按连续出错次数指数退避，不改变成功时的间隔
+    pub fn on_error(&mut self) -> Duration {
+        self.error_streak = self.error_streak.saturating_add(1);
+        let factor = 2u32.saturating_pow(self.error_streak - 1);
+        self.clamp(self.config.error_interval.saturating_mul(factor))
+    }
+
+    fn clamp(&self, interval: Duration) -> Duration {
+        interval.clamp(
+            self.config.min_interval,
+            self.config.max_interval.max(self.config.min_interval),
+        )
+    }
+}
+
+#[test]
+fn test_adaptive_poll_interval() {
+    let mut poll = AdaptivePollInterval::new(Arc::new(BinlogPollingConfig::default()));
+    let quiet = SyncCycle {
+        caught_up: true,
+        records: 3,
+    };
+    let busy = SyncCycle {
+        caught_up: true,
+        records: 500,
+    };
+
+    assert_eq!(poll.on_success(quiet), Duration::from_secs(6));
+    assert_eq!(poll.on_success(quiet), Duration::from_secs(11));
+    assert_eq!(poll.on_success(busy), Duration::from_millis(5500));
+    for _ in 0..100 {
+        poll.on_success(quiet);
+    }
+    assert_eq!(poll.on_success(quiet), Duration::from_secs(120));
+
+    assert_eq!(poll.on_error(), Duration::from_secs(10));
+    assert_eq!(poll.on_error(), Duration::from_secs(20));
+    assert_eq!(poll.on_error(), Duration::from_secs(40));
+    for _ in 0..40 {
+        poll.on_error();
+    }
+    assert_eq!(poll.on_error(), Duration::from_secs(120));
+
+    // 出错不影响成功时的间隔，追赶历史数据时持续加速到下限
+    assert_eq!(
+        poll.on_success(SyncCycle::default()),
+        Duration::from_secs(60)
+    );
+    for _ in 0..10 {
+        poll.on_success(SyncCycle::default());
+    }
+    assert_eq!(
+        poll.on_success(SyncCycle::default()),
+        Duration::from_secs(1)
+    );
+}
@@ -14,6 +14,7 @@ pub struct PsnArchivePushTask {
 
 impl PsnDataWrapper for PsnArchivePushTask {
     type DataType = ArchiveData;
+    const TRAIN_TABLE_ALIAS: &'static str = "c";
     fn wrap_data(data: Self::DataType) -> DynamicPsnData {
         DynamicPsnData::Archive(data)
     }
@@ -14,6 +14,7 @@ pub struct PsnArchiveScPushTask {
 
 impl PsnDataWrapper for PsnArchiveScPushTask {
     type DataType = ArchiveData;
+    const TRAIN_TABLE_ALIAS: &'static str = "c";
     fn wrap_data(data: Self::DataType) -> DynamicPsnData {
         DynamicPsnData::Archive(data)
     }
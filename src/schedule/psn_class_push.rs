@@ -13,6 +13,7 @@ pub struct PsnClassPushTask {
 
 impl PsnDataWrapper for PsnClassPushTask {
     type DataType = ClassData;
+    const TRAIN_TABLE_ALIAS: &'static str = "a";
     fn wrap_data(data: Self::DataType) -> DynamicPsnData {
         DynamicPsnData::Class(data)
     }
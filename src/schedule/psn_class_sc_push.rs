@@ -13,6 +13,7 @@ pub struct PsnClassScPushTask {
 
 impl PsnDataWrapper for PsnClassScPushTask {
     type DataType = ClassData;
+    const TRAIN_TABLE_ALIAS: &'static str = "a";
     fn wrap_data(data: Self::DataType) -> DynamicPsnData {
         DynamicPsnData::Class(data)
     }
@@ -0,0 +1,111 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use anyhow::Result;
+use sqlx::{MySql, QueryBuilder};
+
+use crate::schedule::push_executor::{PsnDataWrapper, QueryType, execute_push_task_logic};
+use crate::schedule::{
+    BasePsnPushTask, PsnArchivePushTask, PsnArchiveScPushTask, PsnClassPushTask,
+    PsnClassScPushTask, PsnLecturerPushTask, PsnLecturerScPushTask, PsnTrainingPushTask,
+    PsnTrainingScPushTask,
+};
+use crate::{AppContext, DynamicPsnData, PsnDataKind, TaskExecutor};
+
+/// 删除通知中 operation 字段的取值
+pub const DELETE_OPERATION: &str = "delete";
+
+/// 可以发送删除通知的数据种类
+pub trait PsnDeletable: PsnDataWrapper {
+    /// 删除通知任务的名称
+    const DELETE_TASK_NAME: &'static str;
+}
+
+/// 复用 W 的查询，按 [train_status] 只保留所属培训班已取消的记录，并把 operation 置为 delete
+pub struct PsnDeletion<W>(PhantomData<W>);
+
+impl<W: PsnDeletable> PsnDataWrapper for PsnDeletion<W> {
+    type DataType = W::DataType;
+    const TRAIN_TABLE_ALIAS: &'static str = W::TRAIN_TABLE_ALIAS;
+    const CANCELLED_ONLY: bool = true;
+
+    fn wrap_data(data: Self::DataType) -> DynamicPsnData {
+        let mut data = W::wrap_data(data);
+        data.set_operation(DELETE_OPERATION);
+        data
+    }
+
+    fn get_query_builder(query_type: QueryType) -> QueryBuilder<'static, MySql> {
+        W::get_query_builder(query_type)
+    }
+
+    fn get_psn_data_kind_for_wrapper() -> PsnDataKind {
+        W::get_psn_data_kind_for_wrapper()
+    }
+
+    fn task_display_name() -> &'static str {
+        W::DELETE_TASK_NAME
+    }
+}
+
+/// 培训班被取消后通知 MSS 删除对应数据，推送、回写与结果记录与 W 的推送任务相同
+pub struct PsnDeletePushTask<W> {
+    base: BasePsnPushTask,
+    _wrapper: PhantomData<W>,
+}
+
+impl<W: PsnDeletable> PsnDeletePushTask<W> {
+    pub fn new(
+        app_context: Arc<AppContext>,
+        hit_date: Option<String>,
+        train_ids: Option<Vec<String>>,
+    ) -> Self {
+        Self {
+            base: BasePsnPushTask::new(app_context, hit_date, train_ids),
+            _wrapper: PhantomData,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<W: PsnDeletable> TaskExecutor for PsnDeletePushTask<W> {
+    fn name(&self) -> &str {
+        W::DELETE_TASK_NAME
+    }
+
+    async fn execute(&self) -> Result<()> {
+        execute_push_task_logic::<PsnDeletion<W>>(&self.base).await
+    }
+}
+
+impl PsnDeletable for PsnClassPushTask {
+    const DELETE_TASK_NAME: &'static str = "PsnClassDeletePushTask";
+}
+
+impl PsnDeletable for PsnLecturerPushTask {
+    const DELETE_TASK_NAME: &'static str = "PsnLecturerDeletePushTask";
+}
+
+impl PsnDeletable for PsnTrainingPushTask {
+    const DELETE_TASK_NAME: &'static str = "PsnTrainingDeletePushTask";
+}
+
+impl PsnDeletable for PsnArchivePushTask {
+    const DELETE_TASK_NAME: &'static str = "PsnArchiveDeletePushTask";
+}
+
+impl PsnDeletable for PsnClassScPushTask {
+    const DELETE_TASK_NAME: &'static str = "PsnClassScDeletePushTask";
+}
+
+impl PsnDeletable for PsnLecturerScPushTask {
+    const DELETE_TASK_NAME: &'static str = "PsnLecturerScDeletePushTask";
+}
+
+impl PsnDeletable for PsnTrainingScPushTask {
+    const DELETE_TASK_NAME: &'static str = "PsnTrainingScDeletePushTask";
+}
+
+impl PsnDeletable for PsnArchiveScPushTask {
+    const DELETE_TASK_NAME: &'static str = "PsnArchiveScDeletePushTask";
+}
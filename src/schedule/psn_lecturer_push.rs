@@ -13,6 +13,7 @@ pub struct PsnLecturerPushTask {
 
 impl PsnDataWrapper for PsnLecturerPushTask {
     type DataType = LecturerData;
+    const TRAIN_TABLE_ALIAS: &'static str = "T";
     fn wrap_data(data: Self::DataType) -> crate::DynamicPsnData {
         crate::DynamicPsnData::Lecturer(data)
     }
@@ -14,6 +14,7 @@ pub struct PsnTrainingScPushTask {
 
 impl PsnDataWrapper for PsnTrainingScPushTask {
     type DataType = TrainingData;
+    const TRAIN_TABLE_ALIAS: &'static str = "c";
     fn wrap_data(data: Self::DataType) -> DynamicPsnData {
         DynamicPsnData::Training(data)
     }
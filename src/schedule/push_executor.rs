@@ -1,18 +1,49 @@
-use anyhow::{Context, Result};
-use chrono::{Duration, Local};
+use anyhow::{Context, Result, anyhow, bail};
+use itertools::Itertools;
 use sqlx::{Database, Execute, FromRow, MySql, MySqlPool, QueryBuilder};
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::marker::Unpin;
 use std::sync::Arc;
-use tracing::{error, info};
+use std::time::Instant;
+use tracing::{error, info, warn};
 
-use crate::schedule::BasePsnPushTask;
-use crate::utils::mss_client::psn_dos_push;
-use crate::{DynamicPsnData, PsnDataKind};
-
-pub const BATCH_SIZE: usize = 1000;
+use crate::config::NotifyStatusConfig;
+use crate::db::mysql_pool::acquire_with_timeout;
+use crate::db::query_runner::QueryRunner;
+use crate::metrics::{
+    MSS_PUSH_DURATION, MSS_THROTTLE_EVENTS, MSS_THROTTLE_WAIT_SECONDS, PUSH_PROVINCE_RECORDS,
+    PUSH_RECORDS,
+};
+use crate::models::analytics_export::PushRunSummary;
+use crate::models::push_province_stats::{
+    load_org_provinces, record_province_stats, tally_by_province,
+};
+use crate::models::push_run::{PushRunOutcome, record_push_run};
+use crate::models::task_run::{current_run_id, with_task_run};
+use crate::models::task_run_history::{RowCounter, count_rows};
+use crate::parsers::push_result_parser::PushRejection;
+use crate::schedule::psn_delete_push::PsnDeletion;
+use crate::schedule::push_outbox::OutboxStatus;
+use crate::schedule::push_verification::residue_summary;
+use crate::schedule::run_manifest::RunManifestCollector;
+use crate::schedule::running_tasks::RunningTask;
+use crate::schedule::status_updates::{
+    NotifyStatus, StatusStore, StatusTarget, StatusUpdateCollector, StatusUpdates,
+};
+use crate::schedule::{
+    BasePsnPushTask, PsnArchivePushTask, PsnArchiveScPushTask, PsnClassPushTask,
+    PsnClassScPushTask, PsnLecturerPushTask, PsnLecturerScPushTask, PsnTrainingPushTask,
+    PsnTrainingScPushTask,
+};
+use crate::utils::mss_client::{ThrottleStats, psn_dos_push_batch, with_throttle_stats};
+use crate::utils::mss_encoder::{EncodedPayload, MssEncoder};
+use crate::utils::retry::RetryPolicy;
+use crate::utils::timefmt;
+use crate::{DynamicPsnData, PsnDataKind, PsnRecord};
 
 // 定义查询类型枚举
+#[derive(Clone)]
 pub enum QueryType {
     ByDate(String),
     ByIds(Vec<String>),
@@ -21,12 +52,26 @@ pub enum QueryType {
 pub trait PsnDataWrapper: Send + Sync + 'static {
     // 修正：在 DataType 的 trait bound 中添加 Unpin
     type DataType: for<'r> FromRow<'r, <MySql as Database>::Row> + Debug + Send + Sync + Unpin;
+    // 查询中培训班表（NU_TRAINSOURCEDATA_*）的别名，按培训班状态筛选时使用
+    const TRAIN_TABLE_ALIAS: &'static str;
+    // 删除通知只查询已取消培训班的记录，推送任务则排除这些记录
+    const CANCELLED_ONLY: bool = false;
     fn wrap_data(data: Self::DataType) -> DynamicPsnData;
     fn get_query_builder(query_type: QueryType) -> QueryBuilder<'static, MySql>;
 
     // 新增：获取此 Wrapper 处理的 DynamicPsnData 的种类
     fn get_psn_data_kind_for_wrapper() -> PsnDataKind;
 
+    // 分页查询时按该列做 keyset 分页，各查询的主表别名均为 a，a.ID 即记录 ID
+    fn keyset_column() -> &'static str {
+        "a.ID"
+    }
+
+    // 任务名称，用于日志、指标与推送执行记录；同一数据种类的删除通知需要区分
+    fn task_display_name() -> &'static str {
+        Self::get_psn_data_kind_for_wrapper().to_task_display_name()
+    }
+
     fn apply_query_filters<'a>(
         mut query_builder: QueryBuilder<'a, MySql>,
         query_type: QueryType,
@@ -55,19 +100,50 @@ pub trait PsnDataWrapper: Send + Sync + 'static {
     }
 }
 
-// 辅助函数：根据 PsnDataKind 类型获取 ClickHouse 表名
-fn get_clickhouse_table_name(kind: PsnDataKind) -> &'static str {
-    match kind {
-        PsnDataKind::Class => "DXXY_LOCAL.TRAIN_SOURCE_DATA_ZTK_ALL",
-        PsnDataKind::Lecturer => "DXXY_LOCAL.TRAIN_COURSE_DATA_ZTK_ALL",
-        PsnDataKind::Archive => "DXXY_LOCAL.TRAIN_USER_DATA_ZTK_ALL",
-        PsnDataKind::Training => "UNKNOWN_TABLE_FOR_TRAINING",
-        _ => "UNKNOWN_TABLE",
+// 推送任务回写 ClickHouse 状态的数据种类
+pub(crate) const CLICKHOUSE_STATUS_KINDS: [PsnDataKind; 3] = [
+    PsnDataKind::Class,
+    PsnDataKind::Lecturer,
+    PsnDataKind::Archive,
+];
+
+/// 各类数据的 ClickHouse 表，按 [clickhouse_config.tables] 配置，启动时构建一次放入 AppContext
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClickhouseTables(HashMap<PsnDataKind, String>);
+
+impl ClickhouseTables {
+    /// 校验配置：数据种类必须可识别，回写状态的数据种类必须配置非空的表名
+    pub fn from_config(config: &HashMap<String, String>) -> Result<Self> {
+        let mut tables = HashMap::new();
+        for (name, table) in config {
+            let kind = PsnDataKind::from_name(name)
+                .with_context(|| format!("Unknown data kind in ClickHouse tables: {name}"))?;
+            let table = table.trim();
+            if !table.is_empty() {
+                tables.insert(kind, table.to_string());
+            }
+        }
+        let missing: Vec<PsnDataKind> = CLICKHOUSE_STATUS_KINDS
+            .into_iter()
+            .filter(|kind| !tables.contains_key(kind))
+            .collect();
+        if !missing.is_empty() {
+            bail!("ClickHouse tables are not configured for {missing:?}");
+        }
+        Ok(Self(tables))
+    }
+
+    /// 数据种类的 ClickHouse 表，未配置时返回错误
+    pub fn get(&self, kind: PsnDataKind) -> Result<&str> {
+        self.0
+            .get(&kind)
+            .map(String::as_str)
+            .ok_or_else(|| anyhow!("No ClickHouse table is configured for {kind:?}"))
     }
 }
 
 // 辅助函数：根据 PsnDataKind 类型获取 ID 字段名
-fn get_clickhouse_id_column(kind: PsnDataKind) -> &'static str {
+pub(crate) fn get_clickhouse_id_column(kind: PsnDataKind) -> &'static str {
     match kind {
         PsnDataKind::Class => "T_TRAINID",
         PsnDataKind::Lecturer => "id",
@@ -77,6 +153,16 @@ fn get_clickhouse_id_column(kind: PsnDataKind) -> &'static str {
     }
 }
 
+/// mss_push_result_detail.result_id 在 ClickHouse 表中对应的字段。
+/// 人员档案记录的是 MSS 人员编码，ClickHouse 表中没有对应字段，返回 None
+pub(crate) fn get_clickhouse_result_id_column(kind: PsnDataKind) -> Option<&'static str> {
+    match kind {
+        PsnDataKind::Class => Some("T_TRAINID"),
+        PsnDataKind::Lecturer => Some("COURSEID"),
+        _ => None,
+    }
+}
+
 // 新增辅助函数：根据 PsnDataKind 类型获取 MySQL 表名
 fn get_mysql_table_name(kind: PsnDataKind) -> &'static str {
     match kind {
@@ -101,14 +187,244 @@ fn get_mysql_id_column(kind: PsnDataKind) -> &'static str {
     }
 }
 
-// 核心的通用执行逻辑函数
+/// 推送状态回写的 ClickHouse 表与 ID 字段，不回写 ClickHouse 的种类返回 None，
+/// 回写的种类没有配置表时返回错误
+pub(crate) fn clickhouse_status_table(
+    tables: &ClickhouseTables,
+    kind: PsnDataKind,
+) -> Result<Option<(&str, &'static str)>> {
+    if !CLICKHOUSE_STATUS_KINDS.contains(&kind) {
+        return Ok(None);
+    }
+    Ok(Some((tables.get(kind)?, get_clickhouse_id_column(kind))))
+}
+
+/// 推送状态回写的 MySQL 表与 ID 字段，不回写 MySQL 的种类返回 None
+fn mysql_status_table(kind: PsnDataKind) -> Option<(&'static str, &'static str)> {
+    match kind {
+        PsnDataKind::Training | PsnDataKind::TrainingSc => None,
+        _ => Some((get_mysql_table_name(kind), get_mysql_id_column(kind))),
+    }
+}
+
+/// 本次查询到的全部记录在各状态表中的核对目标，MySQL 在前，ClickHouse 与其逐条比较
+pub(crate) fn source_targets(
+    tables: &ClickhouseTables,
+    kind: PsnDataKind,
+    ids: &[String],
+) -> Result<Vec<StatusTarget>> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mysql = mysql_status_table(kind).map(|(table, id_column)| StatusTarget {
+        store: StatusStore::Mysql,
+        table: table.to_string(),
+        id_column,
+        ids: ids.to_vec(),
+    });
+    let clickhouse =
+        clickhouse_status_table(tables, kind)?.map(|(table, id_column)| StatusTarget {
+            store: StatusStore::Clickhouse,
+            table: table.to_string(),
+            id_column,
+            ids: ids.to_vec(),
+        });
+    Ok(mysql.into_iter().chain(clickhouse).collect())
+}
+
+/// 启动时校验查询拼接：分别以 ByDate 和 ByIds 模式构建查询，并在 MySQL 上执行 EXPLAIN。
+/// `apply_query_filters` 假定 .sql 文件以 WHERE 子句结尾，文件被改动后在这里而不是凌晨的任务中失败。
+pub async fn audit_query_builder<W: PsnDataWrapper>(mysql_pool: &MySqlPool) -> Result<()> {
+    let task_display_name = W::task_display_name();
+    let modes = [
+        ("ByDate", QueryType::ByDate("1970-01-01".to_string())),
+        (
+            "ByIds",
+            QueryType::ByIds(vec!["__query_audit__".to_string()]),
+        ),
+    ];
+    for (mode, query_type) in modes {
+        // 两种模式都只绑定一个参数，之后依次是培训班状态、起始 ID 与条数
+        let mut query_builder = W::get_query_builder(query_type);
+        push_train_status_filter::<W>(&mut query_builder, "__query_audit__");
+        push_keyset_page::<W>(&mut query_builder, Some("__query_audit__".to_string()), 1);
+        let explain_sql = format!("EXPLAIN {}", query_builder.sql());
+        QueryRunner::new("push_query_audit")
+            .run(
+                sqlx::query(&explain_sql)
+                    .bind("__query_audit__")
+                    .bind("__query_audit__")
+                    .bind("__query_audit__")
+                    .bind(1u64)
+                    .fetch_all(mysql_pool),
+            )
+            .await
+            .with_context(|| {
+                format!("Query audit failed for {task_display_name} in {mode} mode: {explain_sql}")
+            })?;
+    }
+    info!("Query audit passed for {task_display_name}");
+    Ok(())
+}
+
+/// 按培训班状态筛选：删除通知只保留已取消的培训班，推送任务排除它们，
+/// 同一次运行中已取消培训班的记录不会先推送新增再推送删除。trainstatus 为空的培训班照常推送
+fn push_train_status_filter<W: PsnDataWrapper>(
+    query_builder: &mut QueryBuilder<'static, MySql>,
+    cancelled_status: &str,
+) {
+    let exists = if W::CANCELLED_ONLY {
+        "EXISTS"
+    } else {
+        "NOT EXISTS"
+    };
+    query_builder.push(format!(
+        " AND {exists} (SELECT 1 FROM fz_train_trainstatus ts_cancelled WHERE ts_cancelled.`CODE` = {}.trainstatus AND ts_cancelled.`NAME` = ",
+        W::TRAIN_TABLE_ALIAS
+    ));
+    query_builder.push_bind(cancelled_status.to_string());
+    query_builder.push(")");
+}
+
+/// 按 keyset 分页：只取 ID 大于 after_id 的 page_size 条，按 ID 排序
+fn push_keyset_page<W: PsnDataWrapper>(
+    query_builder: &mut QueryBuilder<'static, MySql>,
+    after_id: Option<String>,
+    page_size: usize,
+) {
+    let column = W::keyset_column();
+    if let Some(after_id) = after_id {
+        query_builder.push(format!(" AND {column} > "));
+        query_builder.push_bind(after_id);
+    }
+    query_builder.push(format!(" ORDER BY {column} LIMIT "));
+    query_builder.push_bind(page_size as u64);
+}
+
+// 推送查询在 QueryRunner 中的名称，如 PsnClassPushTask_by_date
+fn push_query_name<W: PsnDataWrapper>(query_type: &QueryType) -> String {
+    let mode = match query_type {
+        QueryType::ByDate(_) => "by_date",
+        QueryType::ByIds(_) => "by_ids",
+    };
+    format!("{}_{mode}", W::task_display_name())
+}
+
+/// 校验所有推送任务的查询，任意一个失败即返回错误
+pub async fn audit_push_queries(mysql_pool: &MySqlPool) -> Result<()> {
+    audit_query_builder::<PsnClassPushTask>(mysql_pool).await?;
+    audit_query_builder::<PsnLecturerPushTask>(mysql_pool).await?;
+    audit_query_builder::<PsnArchivePushTask>(mysql_pool).await?;
+    audit_query_builder::<PsnTrainingPushTask>(mysql_pool).await?;
+    audit_query_builder::<PsnClassScPushTask>(mysql_pool).await?;
+    audit_query_builder::<PsnLecturerScPushTask>(mysql_pool).await?;
+    audit_query_builder::<PsnArchiveScPushTask>(mysql_pool).await?;
+    audit_query_builder::<PsnTrainingScPushTask>(mysql_pool).await?;
+    audit_query_builder::<PsnDeletion<PsnClassPushTask>>(mysql_pool).await?;
+    audit_query_builder::<PsnDeletion<PsnLecturerPushTask>>(mysql_pool).await?;
+    audit_query_builder::<PsnDeletion<PsnArchivePushTask>>(mysql_pool).await?;
+    audit_query_builder::<PsnDeletion<PsnTrainingPushTask>>(mysql_pool).await?;
+    audit_query_builder::<PsnDeletion<PsnClassScPushTask>>(mysql_pool).await?;
+    audit_query_builder::<PsnDeletion<PsnLecturerScPushTask>>(mysql_pool).await?;
+    audit_query_builder::<PsnDeletion<PsnArchiveScPushTask>>(mysql_pool).await?;
+    audit_query_builder::<PsnDeletion<PsnTrainingScPushTask>>(mysql_pool).await?;
+    Ok(())
+}
+
+/// 按培训班 ID 执行任务的查询，并将每条记录编码为推送时发送给 MSS 的请求，不发送。
+/// 推送时每条记录单独请求，所以每条记录对应一个请求
+pub async fn preview_payload<W: PsnDataWrapper>(
+    mysql_pool: &MySqlPool,
+    encoder: &dyn MssEncoder,
+    cancelled_status: &str,
+    id: &str,
+) -> Result<Vec<EncodedPayload>> {
+    let task_display_name = W::task_display_name();
+    let query_type = QueryType::ByIds(vec![id.to_string()]);
+    let query_name = push_query_name::<W>(&query_type);
+    let mut query_builder = W::get_query_builder(query_type);
+    push_train_status_filter::<W>(&mut query_builder, cancelled_status);
+    let datas = QueryRunner::new(&query_name)
+        .run(
+            query_builder
+                .build_query_as::<W::DataType>()
+                .fetch_all(mysql_pool),
+        )
+        .await
+        .context(format!(
+            "Failed to fetch {task_display_name} data from database"
+        ))?;
+    datas
+        .into_iter()
+        .map(|data| encoder.encode(&[&W::wrap_data(data)]))
+        .collect()
+}
+
+/// 按数据种类分派到对应任务的查询
+pub async fn preview_push_payload(
+    kind: PsnDataKind,
+    mysql_pool: &MySqlPool,
+    encoder: &dyn MssEncoder,
+    cancelled_status: &str,
+    id: &str,
+) -> Result<Vec<EncodedPayload>> {
+    match kind {
+        PsnDataKind::Class => {
+            preview_payload::<PsnClassPushTask>(mysql_pool, encoder, cancelled_status, id).await
+        }
+        PsnDataKind::Lecturer => {
+            preview_payload::<PsnLecturerPushTask>(mysql_pool, encoder, cancelled_status, id).await
+        }
+        PsnDataKind::Training => {
+            preview_payload::<PsnTrainingPushTask>(mysql_pool, encoder, cancelled_status, id).await
+        }
+        PsnDataKind::Archive => {
+            preview_payload::<PsnArchivePushTask>(mysql_pool, encoder, cancelled_status, id).await
+        }
+        PsnDataKind::ClassSc => {
+            preview_payload::<PsnClassScPushTask>(mysql_pool, encoder, cancelled_status, id).await
+        }
+        PsnDataKind::LecturerSc => {
+            preview_payload::<PsnLecturerScPushTask>(mysql_pool, encoder, cancelled_status, id)
+                .await
+        }
+        PsnDataKind::TrainingSc => {
+            preview_payload::<PsnTrainingScPushTask>(mysql_pool, encoder, cancelled_status, id)
+                .await
+        }
+        PsnDataKind::ArchiveSc => {
+            preview_payload::<PsnArchiveScPushTask>(mysql_pool, encoder, cancelled_status, id).await
+        }
+    }
+}
+
+// 核心的通用执行逻辑函数，在 task_run 登记的执行中运行，不经调度器直接执行时登记新的执行
 pub async fn execute_push_task_logic<W: PsnDataWrapper>(base_task: &BasePsnPushTask) -> Result<()> {
+    let task_display_name = W::task_display_name();
+    with_task_run(
+        &base_task.mysql_pool,
+        task_display_name,
+        execute_push_task_run::<W>(base_task),
+    )
+    .await
+}
+
+async fn execute_push_task_run<W: PsnDataWrapper>(base_task: &BasePsnPushTask) -> Result<()> {
     let psn_data_kind = W::get_psn_data_kind_for_wrapper(); // 获取当前任务处理的数据类型种类
-    let task_display_name = psn_data_kind.to_task_display_name(); // 获取任务名称
+    let task_display_name = W::task_display_name(); // 获取任务名称
+    let started = Instant::now();
     info!(
         "Running {task_display_name} via execute_push_task_logic at: {}",
-        Local::now().format("%Y-%m-%d %H:%M:%S")
+        timefmt::datetime(base_task.clock.now_local())
     );
+    // 运行期间持有，结束后自动从注册表移出
+    let _running = base_task.running_tasks.register(RunningTask::new(
+        task_display_name,
+        psn_data_kind,
+        base_task.hit_date.clone(),
+        base_task.train_ids.clone(),
+        current_run_id(),
+    ));
 
     let query_type = if let Some(date_str) = &base_task.hit_date {
         // <--- 克隆 String 以便 QueryType 拥有
@@ -120,196 +436,683 @@ pub async fn execute_push_task_logic<W: PsnDataWrapper>(base_task: &BasePsnPushT
         QueryType::ByIds(ids.clone()) // <--- 传递拥有所有权的 Vec<String>
     } else {
         // 如果没有提供 train_ids 和 hit_date，则回退到计算“昨天”的日期
-        let today = Local::now().date_naive();
-        let yesterday = today - Duration::days(1);
-        let hit_date_calculated = yesterday.format("%Y-%m-%d").to_string(); // <--- 创建拥有所有权的 String
+        let hit_date_calculated = timefmt::business_date(base_task.clock.yesterday()); // <--- 创建拥有所有权的 String
         info!("Processing data for calculated hit_date: {hit_date_calculated}");
         QueryType::ByDate(hit_date_calculated) // <--- 传递拥有所有权的 String
     };
+    // 按日期推送时记录执行结果，用于与同一日期的上一次执行比较
+    let run_hit_date = match &query_type {
+        QueryType::ByDate(hit_date) => Some(hit_date.clone()),
+        QueryType::ByIds(_) => None,
+    };
 
-    let datas = W::get_query_builder(query_type)
-        .build_query_as::<W::DataType>()
-        .fetch_all(&base_task.mysql_pool)
+    let query_name = push_query_name::<W>(&query_type);
+    let page_size = base_task.fetch_page_size;
+    let manifest = RunManifestCollector::current();
+    // 按页查询、推送并回写状态，内存中只保留一页记录，中途失败时已完成的页状态已回写
+    let mut totals = PushBatchOutcome::default();
+    // 本次查询到的全部记录 ID，推送结束后核对其状态，未启用核对时不收集
+    let mut source_ids: Vec<String> = Vec::new();
+    let mut fetched = 0;
+    let mut after_id: Option<String> = None;
+    loop {
+        let mut query_builder = W::get_query_builder(query_type.clone());
+        push_train_status_filter::<W>(&mut query_builder, &base_task.train_status.cancelled);
+        push_keyset_page::<W>(&mut query_builder, after_id.take(), page_size);
+        // 连接池耗尽时不排队等待，返回 PoolExhausted 结束本次运行
+        let mut conn = acquire_with_timeout(
+            &base_task.mysql_pool,
+            &query_name,
+            base_task.mysql_acquire_timeout,
+        )
         .await
         .context(format!(
             "Failed to fetch {task_display_name} data from database"
         ))?;
+        let datas = QueryRunner::new(&query_name)
+            .run(
+                query_builder
+                    .build_query_as::<W::DataType>()
+                    .fetch_all(&mut *conn),
+            )
+            .await
+            .context(format!(
+                "Failed to fetch {task_display_name} data from database"
+            ))?;
+        drop(conn);
+        count_rows(RowCounter::Fetched, datas.len());
+        let page_len = datas.len();
+        fetched += page_len;
+        let records: Vec<DynamicPsnData> = datas.into_iter().map(wrap_found::<W>).collect();
+        let Some(last) = records.last() else {
+            break;
+        };
+        after_id = Some(last.id().to_string());
+        if base_task.push_verifier.is_some() {
+            source_ids.extend(records.iter().map(|record| record.id().to_string()));
+        }
+        if page_len == page_size {
+            info!("Fetched a page of {page_len} {task_display_name} records, {fetched} so far.");
+        }
+
+        let mut outcome = match &base_task.push_pipeline {
+            // 查询与推送解耦：记录按批入队，由多个推送 worker 并发推送
+            Some(pipeline) => {
+                pipeline
+                    .run(base_task, psn_data_kind, task_display_name, records)
+                    .await
+            }
+            None => {
+                push_records_in_batches(base_task, psn_data_kind, task_display_name, records).await
+            }
+        };
+        write_back_statuses(
+            base_task,
+            psn_data_kind,
+            &outcome.success_ids,
+            &outcome.failed_ids,
+        )
+        .await;
+        // 发件箱不可用时停止推送，已推送记录的状态已回写，其余记录下次运行再推
+        if let Some(e) = outcome.aborted.take() {
+            return Err(e.context(format!("Aborted {task_display_name} push")));
+        }
+        totals.merge(outcome);
 
-    // 存储成功和失败的 ID
-    let mut success_ids: Vec<String> = Vec::new();
-    let mut failed_ids: Vec<(String, Option<String>)> = Vec::new();
+        if page_len < page_size {
+            break;
+        }
+        if base_task.shutdown.is_shutting_down() {
+            warn!("Shutting down, stopped fetching {task_display_name} after {fetched} records.");
+            break;
+        }
+    }
 
-    if datas.is_empty() {
+    if fetched == 0 {
         info!("No data found for task: {task_display_name}");
+        if let Some(manifest) = &manifest {
+            manifest.record(psn_data_kind, run_hit_date.as_deref(), 0, &[], 0);
+        }
         return Ok(());
     }
-    for data in datas {
-        info!("Found {task_display_name}: {data:?}");
-        let psn_data_enum = W::wrap_data(data);
+    let PushBatchOutcome {
+        success_ids,
+        failed_ids,
+        deferred_count,
+        org_by_id,
+        throttle_events,
+        throttle_wait_ms,
+    } = totals;
+    count_rows(RowCounter::Pushed, success_ids.len());
+    count_rows(RowCounter::Failed, failed_ids.len());
+    if let Some(manifest) = &manifest {
+        manifest.record(
+            psn_data_kind,
+            run_hit_date.as_deref(),
+            fetched,
+            &success_ids,
+            failed_ids.len(),
+        );
+    }
+
+    // 核对本次查询到的全部记录（按日期推送时即该日期的全部源数据），由组合任务合并回写时在其回写后核对
+    let residues = match &base_task.push_verifier {
+        Some(verifier) => {
+            let targets = source_targets(&base_task.clickhouse_tables, psn_data_kind, &source_ids)?;
+            match StatusUpdateCollector::current() {
+                Some(collector) => {
+                    collector.verify_after_flush(targets);
+                    None
+                }
+                None => Some(verifier.verify(&targets).await),
+            }
+        }
+        None => None,
+    };
+    record_push_by_province(
+        base_task,
+        task_display_name,
+        run_hit_date.as_deref(),
+        &org_by_id,
+        &success_ids,
+        &failed_ids,
+    )
+    .await;
+
+    for (outcome, count) in [
+        ("success", success_ids.len()),
+        ("failed", failed_ids.len()),
+        ("deferred", deferred_count),
+    ] {
+        PUSH_RECORDS
+            .with_label_values(&[task_display_name, outcome])
+            .inc_by(count as u64);
+    }
+    MSS_THROTTLE_EVENTS
+        .with_label_values(&[task_display_name])
+        .inc_by(u64::from(throttle_events));
+    MSS_THROTTLE_WAIT_SECONDS
+        .with_label_values(&[task_display_name])
+        .inc_by(throttle_wait_ms / 1000);
 
-        let current_id = psn_data_enum.get_data_id().to_string();
+    if let Some(analytics) = &base_task.analytics_exporter {
+        let summary = PushRunSummary {
+            task_name: task_display_name.to_string(),
+            hit_date: run_hit_date.clone(),
+            success: success_ids.len() as u64,
+            failed: failed_ids.len() as u64,
+            deferred: deferred_count as u64,
+            throttle_events,
+            throttle_wait_ms,
+            finished_at: base_task.clock.now_local(),
+            duration_ms: u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+        };
+        analytics.export_push_run(&summary).await;
+    }
 
-        if let Err(e) = psn_dos_push(
-            &base_task.http_client,
-            Arc::clone(&base_task.mss_info_config),
-            &base_task.archiving_mapper,
-            &base_task.push_result_parser,
-            &psn_data_enum,
+    let mut run_summary = format!(
+        "success={}, failed={}, deferred={deferred_count}, throttled={throttle_events} ({}s waiting)",
+        success_ids.len(),
+        failed_ids.len(),
+        throttle_wait_ms / 1000
+    );
+    if let Some(residues) = &residues {
+        run_summary = format!("{run_summary}, residue: {}", residue_summary(residues));
+    }
+    if let Some(hit_date) = run_hit_date {
+        let outcome = PushRunOutcome {
+            success_ids,
+            failed_ids: failed_ids.into_iter().map(|(id, _)| id).collect(),
+            throttle_events,
+            throttle_wait_ms,
+        };
+        match record_push_run(
+            &base_task.mysql_pool,
+            task_display_name,
+            &hit_date,
+            &outcome,
         )
         .await
         {
-            if matches!(psn_data_enum, DynamicPsnData::Lecturer(_)) {
-                failed_ids.push((current_id, Some(e.to_string())));
-            } else {
-                failed_ids.push((current_id, None));
-            }
-        } else {
-            let psn_data_enum_name = psn_data_enum.get_key_name();
-            info!(
-                "Successfully sent data of type '{psn_data_enum_name}' to third party. Task: {task_display_name}"
-            );
-            success_ids.push(current_id);
-            // 成功后调用小助手接口，写入归档成功的班级
-            if let DynamicPsnData::Class(class_data) = psn_data_enum {
-                let _ = base_task
-                    .gateway_client
-                    .update_newtca_train_status(
-                        &class_data.training_id,
-                        class_data.training_status.as_deref(),
-                    )
-                    .await;
-            } else {
-                info!(
-                    "Skipping gateway service invocation for data of type '{psn_data_enum_name}'. Only 'Class' data is processed by gateway."
+            Ok(Some(diff)) => {
+                run_summary = format!(
+                    "{run_summary}, vs previous run of {hit_date}: {}",
+                    diff.summary()
                 );
+                if !diff.regressed.is_empty() {
+                    warn!(
+                        "{task_display_name} IDs that succeeded last run but failed now for {hit_date}: {:?}",
+                        diff.regressed
+                    );
+                }
             }
+            Ok(None) => run_summary = format!("{run_summary}, first run of {hit_date}"),
+            Err(e) => warn!("Failed to record push run for {task_display_name}: {e:?}"),
         }
     }
 
-    // --- ClickHouse Updates ---
-    if matches!(
-        psn_data_kind,
-        PsnDataKind::Training
-            | PsnDataKind::ClassSc
-            | PsnDataKind::LecturerSc
-            | PsnDataKind::TrainingSc
-            | PsnDataKind::ArchiveSc
-    ) {
-        // 不更新 ClickHouse
-        info!("Skipping ClickHouse updates for PsnDataKind: {psn_data_kind:?}.");
+    info!("{task_display_name} completed successfully. Summary: {run_summary}");
+
+    Ok(())
+}
+
+/// 按记录所属组织的省份统计推送结果，更新指标并写入 mss_push_province_stats。
+/// 统计失败只告警，不影响推送任务
+async fn record_push_by_province(
+    base_task: &BasePsnPushTask,
+    task_display_name: &str,
+    hit_date: Option<&str>,
+    org_by_id: &HashMap<String, String>,
+    success_ids: &[String],
+    failed_ids: &[(String, Option<String>)],
+) {
+    let org_ids: Vec<&str> = org_by_id.values().map(String::as_str).unique().collect();
+    let provinces = load_org_provinces(&base_task.mysql_pool, &org_ids)
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Failed to resolve provinces for {task_display_name}: {e:?}");
+            HashMap::new()
+        });
+    let failed_ids: Vec<String> = failed_ids.iter().map(|(id, _)| id.clone()).collect();
+    let stats = tally_by_province(org_by_id, &provinces, success_ids, &failed_ids);
+    for stat in &stats {
+        for (outcome, count) in [("success", stat.succeeded), ("failed", stat.failed)] {
+            PUSH_PROVINCE_RECORDS
+                .with_label_values(&[task_display_name, &stat.province, outcome])
+                .inc_by(u64::from(count));
+        }
+    }
+    if let Err(e) =
+        record_province_stats(&base_task.mysql_pool, task_display_name, hit_date, &stats).await
+    {
+        warn!("Failed to record province stats for {task_display_name}: {e:?}");
+    }
+}
+
+/// 一批记录的推送结果
+#[derive(Debug, Default)]
+pub struct PushBatchOutcome {
+    pub success_ids: Vec<String>,
+    pub failed_ids: Vec<(String, Option<String>)>,
+    /// 进入重试队列的记录数
+    pub deferred_count: usize,
+    /// 记录 ID -> 所属 MSS 组织编码，用于按省份统计
+    pub org_by_id: HashMap<String, String>,
+    /// MSS 返回需要休息（9019）的次数
+    pub throttle_events: u32,
+    /// 因 9019 累计等待的毫秒数
+    pub throttle_wait_ms: u64,
+    /// 记录写入发件箱失败而停止推送时的错误，未推送的记录保持原状态
+    pub aborted: Option<anyhow::Error>,
+}
+
+impl PushBatchOutcome {
+    pub fn merge(&mut self, other: PushBatchOutcome) {
+        self.success_ids.extend(other.success_ids);
+        self.failed_ids.extend(other.failed_ids);
+        self.deferred_count += other.deferred_count;
+        self.org_by_id.extend(other.org_by_id);
+        self.throttle_events += other.throttle_events;
+        self.throttle_wait_ms += other.throttle_wait_ms;
+        if self.aborted.is_none() {
+            self.aborted = other.aborted;
+        }
+    }
+}
+
+/// 按 mss_info_config 的批量大小推送已查询出的记录，不回写状态。压测工具直接用合成数据调用
+pub async fn push_datas<W: PsnDataWrapper>(
+    base_task: &BasePsnPushTask,
+    datas: Vec<W::DataType>,
+) -> PushBatchOutcome {
+    let records = datas.into_iter().map(wrap_found::<W>).collect();
+    push_records_in_batches(
+        base_task,
+        W::get_psn_data_kind_for_wrapper(),
+        W::task_display_name(),
+        records,
+    )
+    .await
+}
+
+/// 按 mss_info_config 的批量大小依次推送，不回写状态
+async fn push_records_in_batches(
+    base_task: &BasePsnPushTask,
+    psn_data_kind: PsnDataKind,
+    task_display_name: &str,
+    records: Vec<DynamicPsnData>,
+) -> PushBatchOutcome {
+    let mut outcome = PushBatchOutcome::default();
+    let throttle = Arc::new(ThrottleStats::default());
+    let retry_policy = push_retry_policy(base_task);
+
+    let total = records.len();
+    let mut pushed_count = 0;
+    for batch in records.chunks(base_task.mss_info_config.push_batch_size()) {
+        // 关闭时停在两次请求之间，未推送的记录保持原状态，下次运行再推
+        if base_task.shutdown.is_shutting_down() {
+            warn!(
+                "Shutting down, stopped {task_display_name} push with {} records left.",
+                total - pushed_count
+            );
+            break;
+        }
+        pushed_count += batch.len();
+        if let Err(e) = push_batch(
+            base_task,
+            psn_data_kind,
+            batch,
+            &retry_policy,
+            &throttle,
+            &mut outcome,
+        )
+        .await
+        {
+            error!(
+                "Stopped {task_display_name} push with {} records left: {e:?}",
+                total - pushed_count + batch.len()
+            );
+            outcome.aborted = Some(e);
+            break;
+        }
+    }
+    outcome.throttle_events = throttle.events();
+    outcome.throttle_wait_ms = throttle.wait_ms();
+    outcome
+}
+
+fn wrap_found<W: PsnDataWrapper>(data: W::DataType) -> DynamicPsnData {
+    info!("Found {}: {data:?}", W::task_display_name());
+    W::wrap_data(data)
+}
+
+/// 推送时使用的重试策略。启用重试队列时不在本次运行中等待 MSS 限流，直接交给重试队列
+pub(crate) fn push_retry_policy(base_task: &BasePsnPushTask) -> RetryPolicy {
+    if base_task.retry_queue.is_some() {
+        base_task.retry_policy.with_max_attempts(1)
     } else {
-        // 在数据处理前，直接从 PsnDataWrapper 获取 ClickHouse 的表和ID字段
-        let clickhouse_table = get_clickhouse_table_name(psn_data_kind);
-        let clickhouse_id_column = get_clickhouse_id_column(psn_data_kind);
+        base_task.retry_policy.clone()
+    }
+}
+
+/// 一次请求推送一批记录，结果记入 outcome：成功、失败，或启用重试队列时暂时性失败入队。
+/// 启用发件箱时记录写入发件箱失败则不推送，返回错误
+pub(crate) async fn push_batch(
+    base_task: &BasePsnPushTask,
+    psn_data_kind: PsnDataKind,
+    records: &[DynamicPsnData],
+    retry_policy: &RetryPolicy,
+    throttle: &Arc<ThrottleStats>,
+    outcome: &mut PushBatchOutcome,
+) -> Result<()> {
+    for psn_data_enum in records {
+        if let Some(org_id) = psn_data_enum.get_org_id() {
+            outcome
+                .org_by_id
+                .insert(psn_data_enum.id().to_string(), org_id.to_string());
+        }
+    }
+
+    // 发件箱：请求前写入 pending，进程在请求过程中中断时启动后重新推送。
+    // 写入失败时不推送，否则中断后无法恢复
+    let outbox = base_task.push_outbox.as_deref();
+    if let Some(outbox) = outbox {
+        outbox
+            .claim(psn_data_kind, records)
+            .await
+            .with_context(|| format!("Failed to record {psn_data_kind:?} batch in push outbox"))?;
+    }
+
+    let results = with_throttle_stats(
+        Arc::clone(throttle),
+        push_records(base_task, records, retry_policy),
+    )
+    .await;
+    let mut acked = Vec::new();
+    let mut failed = Vec::new();
+    let mut deferred = Vec::new();
+    for (psn_data_enum, pushed) in records.iter().zip(results) {
+        let current_id = psn_data_enum.id().to_string();
+        match pushed {
+            Ok(()) => acked.push((current_id, None)),
+            Err(e) => match &base_task.retry_queue {
+                // 暂时性失败进入延迟重试队列，由重试 worker 回写状态
+                Some(retry_queue) if is_transient(&e) => {
+                    match retry_queue.enqueue(psn_data_kind, psn_data_enum, 1).await {
+                        Ok(()) => deferred.push(current_id),
+                        Err(queue_err) => {
+                            error!("Failed to enqueue {current_id} for retry: {queue_err:?}");
+                            failed.push((current_id, failure_reason(psn_data_enum, &e)));
+                        }
+                    }
+                }
+                _ => failed.push((current_id, failure_reason(psn_data_enum, &e))),
+            },
+        }
+    }
+
+    if let Some(outbox) = outbox {
+        let marked = async {
+            outbox
+                .mark(psn_data_kind, OutboxStatus::Acked, &acked)
+                .await?;
+            outbox
+                .mark(psn_data_kind, OutboxStatus::Failed, &failed)
+                .await?;
+            // 进入重试队列的记录由重试队列负责，不再留在发件箱
+            outbox.release(psn_data_kind, &deferred).await
+        };
+        if let Err(e) = marked.await {
+            error!("Failed to update {psn_data_kind:?} batch in push outbox: {e:?}");
+        }
+    }
+
+    outcome.deferred_count += deferred.len();
+    outcome
+        .success_ids
+        .extend(acked.into_iter().map(|(id, _)| id));
+    outcome.failed_ids.extend(failed);
+    Ok(())
+}
+
+/// 推送单条记录，成功后通知网关更新培训班状态
+pub(crate) async fn push_record(
+    base_task: &BasePsnPushTask,
+    psn_data_enum: &DynamicPsnData,
+    retry_policy: &RetryPolicy,
+) -> RecordResult {
+    push_records(base_task, std::slice::from_ref(psn_data_enum), retry_policy)
+        .await
+        .pop()
+        .unwrap_or(Ok(()))
+}
+
+/// 一次请求推送多条记录，返回与 records 顺序一致的每条记录的结果。
+/// 成功的培训班记录通知网关更新培训班状态
+pub(crate) async fn push_records(
+    base_task: &BasePsnPushTask,
+    records: &[DynamicPsnData],
+    retry_policy: &RetryPolicy,
+) -> Vec<RecordResult> {
+    let Some(first) = records.first() else {
+        return Vec::new();
+    };
+    let psn_data_enum_name = first.key_name();
+    let started = Instant::now();
+    let pushed = psn_dos_push_batch(
+        &base_task.http_client,
+        Arc::clone(&base_task.mss_info_config),
+        &base_task.archiving_mapper,
+        &base_task.push_result_parser,
+        &records.iter().collect::<Vec<_>>(),
+        retry_policy,
+        &base_task.mss_rate_limiter,
+    )
+    .await;
+    // 影子推送在后台进行，不影响本批的结果
+    if let Some(shadow_pusher) = &base_task.shadow_pusher {
+        shadow_pusher.mirror(records, pushed.is_ok());
+    }
+    let outcome = if pushed.is_ok() { "success" } else { "failed" };
+    MSS_PUSH_DURATION
+        .with_label_values(&[psn_data_enum_name, outcome])
+        .observe(started.elapsed().as_secs_f64());
+
+    let results = split_batch_result(records, pushed);
+    for (psn_data_enum, result) in records.iter().zip(&results) {
+        if result.is_err() {
+            continue;
+        }
         info!(
-            "Processing data for ClickHouse table: '{clickhouse_table}' using ID column: '{clickhouse_id_column}' for task: {task_display_name}"
+            "Successfully sent data of type '{}' to third party.",
+            psn_data_enum.key_name()
         );
+        // 成功后调用小助手接口，写入归档成功的班级
+        if let DynamicPsnData::Class(class_data) = psn_data_enum {
+            let _ = base_task
+                .gateway_client
+                .update_newtca_train_status(
+                    &class_data.training_id,
+                    class_data.training_status.as_deref(),
+                )
+                .await;
+        }
+    }
+    results
+}
 
-        if !success_ids.is_empty() {
-            for chunk in success_ids.chunks(BATCH_SIZE) {
-                let ids_for_query = chunk
-                    .iter()
-                    .map(|id| format!("'{id}'"))
-                    .collect::<Vec<String>>()
-                    .join(",");
-
-                let status = "1"; // Success status
-                let query_sql = format!(
-                    "ALTER TABLE {clickhouse_table} UPDATE trainNotifyMss = '{status}' WHERE {clickhouse_id_column} IN ({ids_for_query})"
-                );
-                info!("Attempting to update success status in ClickHouse.");
-                base_task
-                    .clickhouse_client
-                    .execute_on_all_nodes(&query_sql)
-                    .await;
+/// 一条记录的推送结果。同一请求中整批失败的记录共享同一个错误，保留完整的错误链
+pub(crate) type RecordResult = std::result::Result<(), Arc<anyhow::Error>>;
+
+/// 把一次请求的结果拆分到每条记录。MSS 按条返回错误明细时，明细中的记录失败、其余记录视为已接收；
+/// 请求本身失败、没有错误明细或明细与请求中的记录对不上时整批失败
+fn split_batch_result(records: &[DynamicPsnData], pushed: Result<()>) -> Vec<RecordResult> {
+    let error = match pushed {
+        Ok(()) => return records.iter().map(|_| Ok(())).collect(),
+        Err(error) => Arc::new(error),
+    };
+    let rejection = error.downcast_ref::<PushRejection>();
+    let rejected_reason = |record: &DynamicPsnData| {
+        let result_id = record.get_result_id()?;
+        let reason = rejection?.record_errors.get(result_id)?;
+        Some((result_id.to_string(), reason.clone()))
+    };
+    let any_matched = records.len() > 1
+        && records
+            .iter()
+            .any(|record| rejected_reason(record).is_some());
+    records
+        .iter()
+        .map(|record| match rejected_reason(record) {
+            Some((result_id, reason)) if any_matched => {
+                Err(Arc::new(anyhow::Error::new(PushRejection {
+                    message: reason.clone(),
+                    record_errors: HashMap::from([(result_id, reason)]),
+                })))
             }
+            None if any_matched => Ok(()),
+            _ => Err(Arc::clone(&error)),
+        })
+        .collect()
+}
+
+/// MSS 明确拒绝的记录重试也不会成功，其余错误（网络、HTTP 状态码、限流）视为暂时性失败
+pub(crate) fn is_transient(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<PushRejection>().is_none()
+}
+
+/// 失败原因，只有讲师数据需要写入 trainNotifyMssMessage
+pub(crate) fn failure_reason(psn_data_enum: &DynamicPsnData, e: &anyhow::Error) -> Option<String> {
+    if !matches!(psn_data_enum, DynamicPsnData::Lecturer(_)) {
+        return None;
+    }
+    // 优先使用 MSS 针对该条记录返回的错误信息
+    let reason = e
+        .downcast_ref::<PushRejection>()
+        .and_then(|rejection| {
+            psn_data_enum
+                .get_result_id()
+                .and_then(|result_id| rejection.record_errors.get(result_id))
+                .cloned()
+        })
+        .unwrap_or_else(|| format!("{e:#}"));
+    Some(reason)
+}
+
+/// 将推送结果回写到 ClickHouse 与 MySQL 的 trainNotifyMss 字段。
+/// 在组合任务的 StatusUpdateCollector 范围内时只做合并，由组合任务结束时统一回写
+pub(crate) async fn write_back_statuses(
+    base_task: &BasePsnPushTask,
+    psn_data_kind: PsnDataKind,
+    success_ids: &[String],
+    failed_ids: &[(String, Option<String>)],
+) {
+    let task_display_name = psn_data_kind.to_task_display_name();
+    let mut updates = StatusUpdates::default();
+    // 回写的表，都回写成功后才从发件箱删除
+    let mut tables = Vec::new();
+    let mut settle = base_task.push_outbox.is_some();
+    // --- ClickHouse Updates ---
+    match clickhouse_status_table(&base_task.clickhouse_tables, psn_data_kind) {
+        Ok(None) => {
+            // 不更新 ClickHouse
+            info!("Skipping ClickHouse updates for PsnDataKind: {psn_data_kind:?}.");
         }
-        // Process error IDs
-        if !failed_ids.is_empty() {
-            for chunk in failed_ids.chunks(BATCH_SIZE) {
-                let ids_for_query = chunk
-                    .iter()
-                    .map(|(id, _)| format!("'{id}'"))
-                    .collect::<Vec<String>>()
-                    .join(",");
-                let status = "2"; // Error status
-
-                // Log detailed error reasons for this batch
-                for (id, reason_opt) in chunk.iter() {
-                    if let Some(reason) = reason_opt {
-                        error!("Failed Lecturer ID: {id}, Reason: {reason}");
-                    } else {
-                        error!("Failed ID (other type): {id}");
-                    }
+        Err(e) => {
+            // 启动时已校验，不应出现；MySQL 照常回写，记录留在发件箱中待补写
+            error!("Skipping ClickHouse updates for {task_display_name}: {e:#}");
+            settle = false;
+        }
+        Ok(Some((clickhouse_table, clickhouse_id_column))) => {
+            tables.push(clickhouse_table);
+            info!(
+                "Processing data for ClickHouse table: '{clickhouse_table}' using ID column: '{clickhouse_id_column}' for task: {task_display_name}"
+            );
+            // Log detailed error reasons
+            for (id, reason_opt) in failed_ids {
+                if let Some(reason) = reason_opt {
+                    error!("Failed Lecturer ID: {id}, Reason: {reason}");
+                } else {
+                    error!("Failed ID (other type): {id}");
                 }
-                let query_sql = format!(
-                    "ALTER TABLE {clickhouse_table} UPDATE trainNotifyMss = '{status}' WHERE {clickhouse_id_column} IN ({ids_for_query})"
-                );
-                info!("Attempting to update error status in ClickHouse.");
-                base_task
-                    .clickhouse_client
-                    .execute_on_all_nodes(&query_sql)
-                    .await;
             }
+            updates.add_clickhouse(
+                clickhouse_table,
+                clickhouse_id_column,
+                NotifyStatus::Success,
+                success_ids,
+            );
+            updates.add_clickhouse(
+                clickhouse_table,
+                clickhouse_id_column,
+                NotifyStatus::Failed,
+                failed_ids.iter().map(|(id, _)| id),
+            );
         }
     }
 
     // --- MySQL Updates ---
-    if matches!(
-        psn_data_kind,
-        PsnDataKind::Training | PsnDataKind::TrainingSc
-    ) {
-        // 不更新 MySQL
-        info!("Skipping MySQL updates for PsnDataKind: {psn_data_kind:?}.");
-    } else {
-        let mysql_table = get_mysql_table_name(psn_data_kind);
-        let mysql_id_column = get_mysql_id_column(psn_data_kind);
+    match mysql_status_table(psn_data_kind) {
+        None => {
+            // 不更新 MySQL
+            info!("Skipping MySQL updates for PsnDataKind: {psn_data_kind:?}.");
+        }
+        Some((mysql_table, mysql_id_column)) => {
+            tables.push(mysql_table);
 
-        // 只有 PsnDataKind::Lecturer 类型需要更新 trainNotifyMssMessage 字段
-        let update_message_field = psn_data_kind == PsnDataKind::Lecturer; // <--- 根据类型设置此标志
-        info!(
-            "Attempting MySQL updates for PsnDataKind::{psn_data_kind:?} (Table: '{mysql_table}', ID Column: '{mysql_id_column}', Update message field: {update_message_field})."
-        );
+            // 只有 PsnDataKind::Lecturer 类型需要更新 trainNotifyMssMessage 字段
+            let update_message_field = psn_data_kind == PsnDataKind::Lecturer; // <--- 根据类型设置此标志
+            info!(
+                "Attempting MySQL updates for PsnDataKind::{psn_data_kind:?} (Table: '{mysql_table}', ID Column: '{mysql_id_column}', Update message field: {update_message_field})."
+            );
 
-        // 处理成功 ID 的 MySQL 更新
-        if !success_ids.is_empty() {
-            // 将成功 ID 转换为 (String, Option<String>) 格式，消息为 None
+            // 成功 ID 的消息为 None
             let success_items: Vec<(String, Option<String>)> =
                 success_ids.iter().map(|id| (id.clone(), None)).collect();
-
-            for chunk in success_items.chunks(BATCH_SIZE) {
-                update_notify_mss_mysql(
-                    &base_task.mysql_pool,
-                    mysql_table,
-                    mysql_id_column,
-                    "1",
-                    chunk,
-                    update_message_field,
-                )
-                .await;
-            }
+            updates.add_mysql(
+                mysql_table,
+                mysql_id_column,
+                update_message_field,
+                NotifyStatus::Success,
+                &success_items,
+            );
+            updates.add_mysql(
+                mysql_table,
+                mysql_id_column,
+                update_message_field,
+                NotifyStatus::Failed,
+                failed_ids,
+            );
         }
+    }
+
+    if settle {
+        updates.add_settled(
+            psn_data_kind,
+            &tables,
+            success_ids
+                .iter()
+                .chain(failed_ids.iter().map(|(id, _)| id)),
+        );
+    }
 
-        // 处理失败 ID 的 MySQL 更新
-        if !failed_ids.is_empty() {
-            // failed_ids 已经是 Vec<(String, Option<String>)>，可以直接使用
-            for chunk in failed_ids.chunks(BATCH_SIZE) {
-                update_notify_mss_mysql(
+    match StatusUpdateCollector::current() {
+        Some(collector) => collector.add(updates),
+        None => {
+            let written = updates
+                .flush(
+                    &base_task.clickhouse_client,
                     &base_task.mysql_pool,
-                    mysql_table,
-                    mysql_id_column,
-                    "2",
-                    chunk,
-                    update_message_field,
+                    base_task.update_batch_size,
+                    &base_task.notify_status,
+                    base_task.clickhouse_replay.as_deref(),
                 )
                 .await;
+            if let Some(outbox) = &base_task.push_outbox {
+                outbox.settle_all(written).await;
             }
         }
     }
-
-    info!("{task_display_name} completed successfully.");
-
-    Ok(())
 }
 
 // 更新 MySQL 表的 `trainNotifyMss` 字段和可选的 `trainNotifyMssMessage` 字段。
@@ -317,16 +1120,18 @@ pub async fn execute_push_task_logic<W: PsnDataWrapper>(base_task: &BasePsnPushT
 /// 根据传入的 `table_name` 和 `id_column` 来构建更新语句。
 /// `items` 参数是 `(ID, Option<Message>)` 的元组列表。
 /// `update_message_field` 参数指示是否应更新 `trainNotifyMssMessage` 字段。
+/// `status` 按 `statuses` 转换为写入的值。更新失败时返回错误
 pub async fn update_notify_mss_mysql(
     mysql_pool: &MySqlPool,
     table_name: &str,
     id_column: &str,
-    status: &str,
+    status: NotifyStatus,
+    statuses: &NotifyStatusConfig,
     items: &[(String, Option<String>)],
     update_message_field: bool,
-) {
+) -> Result<()> {
     if items.is_empty() {
-        return;
+        return Ok(());
     }
 
     // 构建 UPDATE ... SET trainNotifyMss = CASE <id_column> WHEN <id_value> THEN <status> ... END
@@ -334,12 +1139,13 @@ pub async fn update_notify_mss_mysql(
         "UPDATE {table_name} SET trainNotifyMss = CASE {id_column} "
     ));
 
+    let value = statuses.value(status);
     // 为每个 item 构建 WHEN ... THEN ... 部分
     for (id, _) in items {
         query_builder.push(" WHEN "); // 推送 SQL 关键字
         query_builder.push_bind(id.clone()); // 绑定 ID 值，sqlx 会为其生成一个 ?
         query_builder.push(" THEN "); // 推送 SQL 关键字
-        query_builder.push_bind(status); // 绑定状态值，sqlx 会为其生成一个 ?
+        query_builder.push_bind(value); // 绑定状态值，sqlx 会为其生成一个 ?
     }
     query_builder.push(" END"); // 结束 CASE 语句
 
@@ -351,7 +1157,7 @@ pub async fn update_notify_mss_mysql(
             query_builder.push_bind(id.clone()); // 绑定 ID 值
             query_builder.push(" THEN "); // 推送 SQL 关键字
 
-            if status == "2" {
+            if status == NotifyStatus::Failed {
                 // 失败状态，绑定消息
                 query_builder.push_bind(msg_opt.clone()); // 绑定消息值
             } else {
@@ -379,17 +1185,135 @@ pub async fn update_notify_mss_mysql(
     // 打印构建的 SQL 语句和绑定参数，便于调试验证
     info!("Built MySQL update query: {}", query.sql());
 
-    match query.execute(mysql_pool).await {
-        Ok(result) => {
-            info!(
-                "MySQL update for table '{table_name}' completed. Rows affected: {}",
-                result.rows_affected()
-            );
-        }
-        Err(e) => {
-            error!(
-                "Failed to update MySQL table '{table_name}' (status: {status}, items: {items:?}): {e:?}"
-            );
-        }
-    }
+    let result = QueryRunner::new("push_status_update")
+        .run(query.execute(mysql_pool))
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to update MySQL table '{table_name}' (status: {status}, items: {items:?})"
+            )
+        })?;
+    info!(
+        "MySQL update for table '{table_name}' completed. Rows affected: {}",
+        result.rows_affected()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_split_batch_result() {
+    use crate::LecturerData;
+
+    let lecturer = |course_id: &str| {
+        DynamicPsnData::Lecturer(LecturerData {
+            id: course_id.to_string(),
+            course_id: Some(course_id.to_string()),
+            ..Default::default()
+        })
+    };
+    let records = [lecturer("C001"), lecturer("C002"), lecturer("C003")];
+
+    let all_ok = split_batch_result(&records, Ok(()));
+    assert!(all_ok.iter().all(Result::is_ok));
+
+    let partial = split_batch_result(
+        &records,
+        Err(anyhow::Error::new(PushRejection {
+            message: "rejected".to_string(),
+            record_errors: HashMap::from([("C002".to_string(), "E2002".to_string())]),
+        })),
+    );
+    assert!(partial[0].is_ok() && partial[2].is_ok());
+    let rejected = partial[1].as_ref().unwrap_err();
+    assert!(!is_transient(rejected));
+    assert_eq!(
+        failure_reason(&records[1], rejected).as_deref(),
+        Some("E2002")
+    );
+
+    let unmatched = split_batch_result(
+        &records,
+        Err(anyhow::Error::new(PushRejection::from(
+            "denied".to_string(),
+        ))),
+    );
+    assert!(
+        unmatched
+            .iter()
+            .all(|r| r.as_ref().is_err_and(|e| !is_transient(e)))
+    );
+
+    let network = split_batch_result(
+        &records,
+        Err(anyhow::anyhow!("connection reset").context("Failed to push lecturer batch")),
+    );
+    assert!(
+        network
+            .iter()
+            .all(|r| r.as_ref().is_err_and(|e| is_transient(e)))
+    );
+    // 整批失败时每条记录共享原始错误，错误链不被展开成字符串
+    let error = network[0].as_ref().unwrap_err();
+    assert_eq!(error.chain().count(), 2);
+    assert!(Arc::ptr_eq(error, network[2].as_ref().unwrap_err()));
+}
+
+#[test]
+fn test_parse_clickhouse_tables() {
+    let mut config: HashMap<String, String> = [
+        ("class", "STAGING.TRAIN_SOURCE_DATA_ZTK_ALL"),
+        ("Lecturer", " STAGING.TRAIN_COURSE_DATA_ZTK_ALL "),
+        ("archive", "STAGING.TRAIN_USER_DATA_ZTK_ALL"),
+    ]
+    .into_iter()
+    .map(|(kind, table)| (kind.to_string(), table.to_string()))
+    .collect();
+    let tables = ClickhouseTables::from_config(&config).unwrap();
+    assert_eq!(
+        tables.get(PsnDataKind::Lecturer).unwrap(),
+        "STAGING.TRAIN_COURSE_DATA_ZTK_ALL"
+    );
+    assert!(tables.get(PsnDataKind::Training).is_err());
+    assert_eq!(
+        clickhouse_status_table(&tables, PsnDataKind::Class).unwrap(),
+        Some(("STAGING.TRAIN_SOURCE_DATA_ZTK_ALL", "T_TRAINID"))
+    );
+    assert_eq!(
+        clickhouse_status_table(&tables, PsnDataKind::ClassSc).unwrap(),
+        None
+    );
+
+    config.insert("archive".to_string(), " ".to_string());
+    assert!(ClickhouseTables::from_config(&config).is_err());
+    config.insert("archive".to_string(), "T".to_string());
+    config.insert("unknown".to_string(), "T".to_string());
+    assert!(ClickhouseTables::from_config(&config).is_err());
+}
+
+#[test]
+fn test_push_keyset_page() {
+    let mut first = PsnClassPushTask::get_query_builder(QueryType::ByDate("2026-10-15".into()));
+    push_keyset_page::<PsnClassPushTask>(&mut first, None, 500);
+    assert!(first.sql().ends_with(" ORDER BY a.ID LIMIT ?"));
+
+    let mut next = PsnClassPushTask::get_query_builder(QueryType::ByDate("2026-10-15".into()));
+    push_keyset_page::<PsnClassPushTask>(&mut next, Some("cls-500".into()), 500);
+    assert!(next.sql().ends_with(" AND a.ID > ? ORDER BY a.ID LIMIT ?"));
+}
+
+#[test]
+fn test_push_train_status_filter() {
+    let mut push = PsnLecturerPushTask::get_query_builder(QueryType::ByDate("2026-10-15".into()));
+    push_train_status_filter::<PsnLecturerPushTask>(&mut push, "已取消");
+    assert!(push.sql().ends_with(
+        " AND NOT EXISTS (SELECT 1 FROM fz_train_trainstatus ts_cancelled WHERE ts_cancelled.`CODE` = T.trainstatus AND ts_cancelled.`NAME` = ?)"
+    ));
+
+    let mut delete = PsnDeletion::<PsnLecturerPushTask>::get_query_builder(QueryType::ByDate(
+        "2026-10-15".into(),
+    ));
+    push_train_status_filter::<PsnDeletion<PsnLecturerPushTask>>(&mut delete, "已取消");
+    assert!(delete.sql().ends_with(
+        " AND EXISTS (SELECT 1 FROM fz_train_trainstatus ts_cancelled WHERE ts_cancelled.`CODE` = T.trainstatus AND ts_cancelled.`NAME` = ?)"
+    ));
 }
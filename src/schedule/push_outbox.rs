@@ -0,0 +1,356 @@
+//! 推送发件箱：保证进程在推送过程中中断时，已发出的推送不会丢失状态。
+//! 每批记录推送前写入 mss_push_outbox（pending），写入失败时不推送；请求后更新为 acked / failed，
+//! trainNotifyMss 回写成功后删除。启动时处理上次遗留的行：pending 的重新推送（至少一次），
+//! acked / failed 的补写状态
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use sqlx::{MySql, MySqlPool, QueryBuilder, Row};
+use tracing::{error, info, warn};
+
+use crate::config::PushOutboxConfig;
+use crate::db::query_runner::QueryRunner;
+use crate::metrics::PUSH_OUTBOX_RECOVERED;
+use crate::models::task_run::current_run_id;
+use crate::schedule::BasePsnPushTask;
+use crate::schedule::push_executor::{
+    PushBatchOutcome, push_batch, push_retry_policy, write_back_statuses,
+};
+use crate::utils::mss_client::ThrottleStats;
+use crate::{DynamicPsnData, PsnDataKind, PsnRecord};
+
+/// 发件箱中一条记录的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutboxStatus {
+    /// 已写入，推送结果未知
+    Pending,
+    /// MSS 已接收，等待回写 trainNotifyMss
+    Acked,
+    /// 推送失败，等待回写 trainNotifyMss
+    Failed,
+}
+
+impl OutboxStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OutboxStatus::Pending => "pending",
+            OutboxStatus::Acked => "acked",
+            OutboxStatus::Failed => "failed",
+        }
+    }
+
+    fn from_str(s: &str) -> Option<Self> {
+        [
+            OutboxStatus::Pending,
+            OutboxStatus::Acked,
+            OutboxStatus::Failed,
+        ]
+        .into_iter()
+        .find(|status| status.as_str() == s)
+    }
+}
+
+impl fmt::Display for OutboxStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 启动时待恢复的一行
+struct OutboxRow {
+    id: u64,
+    kind: String,
+    record_id: String,
+    status: String,
+    record: String,
+    error: Option<String>,
+}
+
+pub struct PushOutbox {
+    mysql_pool: MySqlPool,
+    config: Arc<PushOutboxConfig>,
+}
+
+impl PushOutbox {
+    /// 未启用时返回 None
+    pub fn new(mysql_pool: MySqlPool, config: Arc<PushOutboxConfig>) -> Option<Self> {
+        config.enabled.then_some(PushOutbox { mysql_pool, config })
+    }
+
+    /// 推送前写入 pending 状态与记录内容，已存在的行（上次未完成或失败的推送）重新开始
+    pub async fn claim(&self, kind: PsnDataKind, records: &[DynamicPsnData]) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let run_id = current_run_id();
+        let mut rows = Vec::with_capacity(records.len());
+        for record in records {
+            let json =
+                serde_json::to_string(record).context("Failed to serialize outbox record")?;
+            rows.push((record.id(), json));
+        }
+        let mut query_builder: QueryBuilder<MySql> = QueryBuilder::new(
+            "INSERT INTO mss_push_outbox (psn_data_kind, record_id, status, record, run_id) ",
+        );
+        query_builder.push_values(rows, |mut row, (record_id, json)| {
+            row.push_bind(kind.as_name())
+                .push_bind(record_id)
+                .push_bind(OutboxStatus::Pending.as_str())
+                .push_bind(json)
+                .push_bind(run_id);
+        });
+        query_builder.push(
+            " ON DUPLICATE KEY UPDATE status = VALUES(status), record = VALUES(record), \
+             run_id = VALUES(run_id), error = NULL",
+        );
+        QueryRunner::new("push_outbox_claim")
+            .run(query_builder.build().execute(&self.mysql_pool))
+            .await
+            .context("Failed to claim records in mss_push_outbox")?;
+        Ok(())
+    }
+
+    /// 更新状态，items 为 (记录 ID, 失败原因)
+    pub async fn mark(
+        &self,
+        kind: PsnDataKind,
+        status: OutboxStatus,
+        items: &[(String, Option<String>)],
+    ) -> Result<()> {
+        if items.is_empty() {
+            return Ok(());
+        }
+        let mut query_builder: QueryBuilder<MySql> =
+            QueryBuilder::new("UPDATE mss_push_outbox SET status = ");
+        query_builder.push_bind(status.as_str());
+        if items.iter().any(|(_, error)| error.is_some()) {
+            query_builder.push(", error = CASE record_id");
+            for (record_id, error) in items {
+                query_builder.push(" WHEN ");
+                query_builder.push_bind(record_id.clone());
+                query_builder.push(" THEN ");
+                query_builder.push_bind(error.clone());
+            }
+            query_builder.push(" END");
+        }
+        query_builder.push(" WHERE psn_data_kind = ");
+        query_builder.push_bind(kind.as_name());
+        query_builder.push(" AND record_id IN (");
+        let mut separated = query_builder.separated(", ");
+        for (record_id, _) in items {
+            separated.push_bind(record_id.clone());
+        }
+        query_builder.push(")");
+        QueryRunner::new("push_outbox_mark")
+            .run(query_builder.build().execute(&self.mysql_pool))
+            .await
+            .with_context(|| format!("Failed to mark mss_push_outbox records as {status}"))?;
+        Ok(())
+    }
+
+    /// trainNotifyMss 回写后删除 acked / failed 的行，已被新一次推送重新写入的行保留
+    pub async fn settle(&self, kind: PsnDataKind, record_ids: &[String]) -> Result<()> {
+        self.delete(kind, record_ids, true).await
+    }
+
+    /// 交给重试队列的记录不再由发件箱跟踪，直接删除
+    pub async fn release(&self, kind: PsnDataKind, record_ids: &[String]) -> Result<()> {
+        self.delete(kind, record_ids, false).await
+    }
+
+    async fn delete(
+        &self,
+        kind: PsnDataKind,
+        record_ids: &[String],
+        finished_only: bool,
+    ) -> Result<()> {
+        if record_ids.is_empty() {
+            return Ok(());
+        }
+        let mut query_builder: QueryBuilder<MySql> =
+            QueryBuilder::new("DELETE FROM mss_push_outbox WHERE psn_data_kind = ");
+        query_builder.push_bind(kind.as_name());
+        if finished_only {
+            query_builder.push(" AND status IN (");
+            query_builder.push_bind(OutboxStatus::Acked.as_str());
+            query_builder.push(", ");
+            query_builder.push_bind(OutboxStatus::Failed.as_str());
+            query_builder.push(")");
+        }
+        query_builder.push(" AND record_id IN (");
+        let mut separated = query_builder.separated(", ");
+        for record_id in record_ids {
+            separated.push_bind(record_id.clone());
+        }
+        query_builder.push(")");
+        QueryRunner::new("push_outbox_delete")
+            .run(query_builder.build().execute(&self.mysql_pool))
+            .await
+            .context("Failed to delete records from mss_push_outbox")?;
+        Ok(())
+    }
+
+    /// 删除各类数据状态已回写的行，失败只记录日志，启动时恢复会再次补写
+    pub async fn settle_all(&self, settled: HashMap<PsnDataKind, Vec<String>>) {
+        for (kind, record_ids) in settled {
+            if let Err(e) = self.settle(kind, &record_ids).await {
+                error!(
+                    "Failed to settle {} {kind:?} outbox records: {e:?}",
+                    record_ids.len()
+                );
+            }
+        }
+    }
+
+    async fn stale_rows(&self) -> Result<Vec<OutboxRow>> {
+        let query = sqlx::query(
+            "SELECT id, psn_data_kind, record_id, status, record, error FROM mss_push_outbox \
+             WHERE updated_at < NOW() - INTERVAL ? SECOND ORDER BY id",
+        )
+        .bind(self.config.stale_after.as_secs());
+        let rows = QueryRunner::new("push_outbox_stale")
+            .run(query.fetch_all(&self.mysql_pool))
+            .await
+            .context("Failed to query mss_push_outbox")?;
+        rows.into_iter()
+            .map(|row| {
+                Ok(OutboxRow {
+                    id: row.try_get("id")?,
+                    kind: row.try_get("psn_data_kind")?,
+                    record_id: row.try_get("record_id")?,
+                    status: row.try_get("status")?,
+                    record: row.try_get("record")?,
+                    error: row.try_get("error")?,
+                })
+            })
+            .collect()
+    }
+
+    async fn discard(&self, ids: &[u64]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let mut query_builder: QueryBuilder<MySql> =
+            QueryBuilder::new("DELETE FROM mss_push_outbox WHERE id IN (");
+        let mut separated = query_builder.separated(", ");
+        for id in ids {
+            separated.push_bind(*id);
+        }
+        query_builder.push(")");
+        QueryRunner::new("push_outbox_discard")
+            .run(query_builder.build().execute(&self.mysql_pool))
+            .await
+            .context("Failed to discard rows from mss_push_outbox")?;
+        Ok(())
+    }
+}
+
+/// 一类数据待恢复的记录
+#[derive(Default)]
+struct KindRecovery {
+    unsent: Vec<DynamicPsnData>,
+    acked: Vec<String>,
+    failed: Vec<(String, Option<String>)>,
+}
+
+/// 启动时处理上次进程遗留在发件箱中的行：pending 的重新推送，acked / failed 的补写状态。
+/// 无法解析的行记录日志后删除
+pub async fn recover_outbox(base_task: &BasePsnPushTask) -> Result<()> {
+    let Some(outbox) = &base_task.push_outbox else {
+        return Ok(());
+    };
+    let rows = outbox.stale_rows().await?;
+    if rows.is_empty() {
+        info!("Push outbox is clean, nothing to recover.");
+        return Ok(());
+    }
+    warn!(
+        "Recovering {} unfinished rows from push outbox.",
+        rows.len()
+    );
+
+    let mut by_kind: HashMap<PsnDataKind, KindRecovery> = HashMap::new();
+    let mut discarded = Vec::new();
+    for row in rows {
+        let (Some(kind), Some(status)) = (
+            PsnDataKind::from_name(&row.kind),
+            OutboxStatus::from_str(&row.status),
+        ) else {
+            error!(
+                "Discarding outbox row {} with unknown kind {} or status {}.",
+                row.id, row.kind, row.status
+            );
+            discarded.push(row.id);
+            continue;
+        };
+        PUSH_OUTBOX_RECOVERED
+            .with_label_values(&[kind.as_name(), status.as_str()])
+            .inc();
+        let recovery = by_kind.entry(kind).or_default();
+        match status {
+            OutboxStatus::Acked => recovery.acked.push(row.record_id),
+            OutboxStatus::Failed => recovery.failed.push((row.record_id, row.error)),
+            OutboxStatus::Pending => {
+                let record = serde_json::from_str(&row.record)
+                    .and_then(|value| DynamicPsnData::from_value(kind, value));
+                match record {
+                    Ok(record) => recovery.unsent.push(record),
+                    Err(e) => {
+                        error!(
+                            "Discarding outbox row {} of {kind:?} record {}: {e}",
+                            row.id, row.record_id
+                        );
+                        discarded.push(row.id);
+                    }
+                }
+            }
+        }
+    }
+    outbox.discard(&discarded).await?;
+
+    let retry_policy = push_retry_policy(base_task);
+    let throttle = Arc::new(ThrottleStats::default());
+    let batch_size = base_task.mss_info_config.push_batch_size();
+    for (kind, recovery) in by_kind {
+        info!(
+            "Recovering {kind:?} from push outbox: {} to re-push, {} acked, {} failed.",
+            recovery.unsent.len(),
+            recovery.acked.len(),
+            recovery.failed.len()
+        );
+        if !recovery.acked.is_empty() || !recovery.failed.is_empty() {
+            write_back_statuses(base_task, kind, &recovery.acked, &recovery.failed).await;
+        }
+        let mut outcome = PushBatchOutcome::default();
+        for records in recovery.unsent.chunks(batch_size) {
+            push_batch(
+                base_task,
+                kind,
+                records,
+                &retry_policy,
+                &throttle,
+                &mut outcome,
+            )
+            .await?;
+        }
+        if !outcome.success_ids.is_empty() || !outcome.failed_ids.is_empty() {
+            write_back_statuses(base_task, kind, &outcome.success_ids, &outcome.failed_ids).await;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_outbox_status_roundtrip() {
+    for status in [
+        OutboxStatus::Pending,
+        OutboxStatus::Acked,
+        OutboxStatus::Failed,
+    ] {
+        assert_eq!(OutboxStatus::from_str(status.as_str()), Some(status));
+    }
+    assert_eq!(OutboxStatus::from_str("done"), None);
+}
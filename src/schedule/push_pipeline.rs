@@ -0,0 +1,383 @@
+//! 推送任务的查询与推送解耦：查询阶段把记录按 MSS 批量大小放入有界队列，
+//! 多个推送 worker 并发取出推送。队列可以是进程内的 channel，也可以是 Redis 列表，
+//! 两个阶段可以分别扩展，以后也可以把推送 worker 单独部署
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::{Mutex, Notify, mpsc};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::config::{PushPipelineConfig, PushQueueBackend};
+use crate::schedule::BasePsnPushTask;
+use crate::schedule::push_executor::{PushBatchOutcome, push_batch, push_retry_policy};
+use crate::utils::mss_client::ThrottleStats;
+use crate::utils::redis::RedisMgr;
+use crate::{DynamicPsnData, PsnDataKind};
+
+// worker 连续读取队列失败达到该次数后退出，未推送的记录保持原状态，下次运行再推
+const MAX_CONSECUTIVE_RECV_ERRORS: u32 = 5;
+
+/// 查询阶段与推送 worker 之间的有界队列
+#[async_trait]
+trait PushQueue: Send + Sync {
+    /// 放入一批记录，队列满时等待
+    async fn send(&self, records: Vec<DynamicPsnData>) -> Result<()>;
+    /// 查询阶段结束，workers 个 worker 取完剩余记录后收到 None
+    async fn close(&self, workers: usize) -> Result<()>;
+    /// 取出一批记录，队列关闭且已取完时返回 None
+    async fn recv(&self) -> Result<Option<Vec<DynamicPsnData>>>;
+    /// 执行结束后清理队列
+    async fn cleanup(&self) {}
+}
+
+struct MemoryQueue {
+    sender: std::sync::Mutex<Option<mpsc::Sender<Vec<DynamicPsnData>>>>,
+    receiver: Mutex<mpsc::Receiver<Vec<DynamicPsnData>>>,
+}
+
+impl MemoryQueue {
+    fn new(capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+        MemoryQueue {
+            sender: std::sync::Mutex::new(Some(sender)),
+            receiver: Mutex::new(receiver),
+        }
+    }
+}
+
+#[async_trait]
+impl PushQueue for MemoryQueue {
+    async fn send(&self, records: Vec<DynamicPsnData>) -> Result<()> {
+        let sender = self
+            .sender
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+            .context("Push queue is closed")?;
+        sender
+            .send(records)
+            .await
+            .map_err(|_| anyhow::anyhow!("All push workers have stopped"))
+    }
+
+    async fn close(&self, _workers: usize) -> Result<()> {
+        // 丢弃发送端后 channel 取完即结束
+        self.sender.lock().unwrap_or_else(|e| e.into_inner()).take();
+        Ok(())
+    }
+
+    async fn recv(&self) -> Result<Option<Vec<DynamicPsnData>>> {
+        Ok(self.receiver.lock().await.recv().await)
+    }
+}
+
+/// Redis 队列在 redis_idle_timeout 内一直为空或一直已满，另一端可能已异常退出
+#[derive(Debug, thiserror::Error)]
+#[error("Push queue {key} stayed {state} for {timeout:?}")]
+struct QueueIdleTimeout {
+    key: String,
+    state: &'static str,
+    timeout: Duration,
+}
+
+/// Redis 列表中的一项。查询阶段结束时为每个 worker 追加一个 End
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum QueueItem {
+    Batch {
+        kind: PsnDataKind,
+        records: Vec<Value>,
+    },
+    End,
+}
+
+struct RedisQueue {
+    redis_mgr: RedisMgr,
+    key: String,
+    kind: PsnDataKind,
+    config: Arc<PushPipelineConfig>,
+}
+
+#[async_trait]
+impl PushQueue for RedisQueue {
+    async fn send(&self, records: Vec<DynamicPsnData>) -> Result<()> {
+        let records = records
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<serde_json::Result<Vec<Value>>>()
+            .context("Failed to serialize push records")?;
+        let item = serde_json::to_string(&QueueItem::Batch {
+            kind: self.kind,
+            records,
+        })
+        .context("Failed to serialize push queue item")?;
+        let mut conn = self.redis_mgr.clone();
+        let deadline = self.idle_deadline();
+        loop {
+            let len: usize = conn.llen(&self.key).await.context("redis LLEN failed")?;
+            if len < self.config.queue_capacity.max(1) {
+                break;
+            }
+            self.wait_until(deadline, "full").await?;
+        }
+        self.push(vec![item]).await
+    }
+
+    async fn close(&self, workers: usize) -> Result<()> {
+        let end = serde_json::to_string(&QueueItem::End)?;
+        self.push(vec![end; workers]).await
+    }
+
+    async fn recv(&self) -> Result<Option<Vec<DynamicPsnData>>> {
+        let mut conn = self.redis_mgr.clone();
+        let deadline = self.idle_deadline();
+        loop {
+            let item: Option<String> = conn
+                .lpop(&self.key, None)
+                .await
+                .context("redis LPOP failed")?;
+            let Some(item) = item else {
+                self.wait_until(deadline, "empty").await?;
+                continue;
+            };
+            match serde_json::from_str(&item) {
+                Ok(QueueItem::Batch { kind, records }) => {
+                    let mut batch = Vec::with_capacity(records.len());
+                    for record in records {
+                        match DynamicPsnData::from_value(kind, record) {
+                            Ok(psn_data) => batch.push(psn_data),
+                            Err(e) => error!("Dropping malformed {kind:?} push record: {e}"),
+                        }
+                    }
+                    return Ok(Some(batch));
+                }
+                Ok(QueueItem::End) => return Ok(None),
+                Err(e) => error!("Dropping malformed push queue item: {e}. Item: {item}"),
+            }
+        }
+    }
+
+    async fn cleanup(&self) {
+        let mut conn = self.redis_mgr.clone();
+        let deleted: redis::RedisResult<()> = conn.del(&self.key).await;
+        if let Err(e) = deleted {
+            warn!("Failed to delete push queue {}: {e}", self.key);
+        }
+    }
+}
+
+impl RedisQueue {
+    /// 追加到列表尾部并重设过期时间，进程异常退出时队列不会一直留在 Redis 中
+    async fn push(&self, items: Vec<String>) -> Result<()> {
+        let ttl_secs =
+            i64::try_from(self.config.redis_key_ttl.as_secs().max(1)).unwrap_or(i64::MAX);
+        let mut conn = self.redis_mgr.clone();
+        let _: () = redis::pipe()
+            .atomic()
+            .rpush(&self.key, items)
+            .ignore()
+            .expire(&self.key, ttl_secs)
+            .ignore()
+            .query_async(&mut conn)
+            .await
+            .context("redis RPUSH/EXPIRE failed")?;
+        Ok(())
+    }
+
+    fn idle_deadline(&self) -> tokio::time::Instant {
+        tokio::time::Instant::now() + self.config.redis_idle_timeout
+    }
+
+    /// 等待一个轮询间隔，超过 deadline 时返回 QueueIdleTimeout
+    async fn wait_until(&self, deadline: tokio::time::Instant, state: &'static str) -> Result<()> {
+        if tokio::time::Instant::now() >= deadline {
+            return Err(QueueIdleTimeout {
+                key: self.key.clone(),
+                state,
+                timeout: self.config.redis_idle_timeout,
+            }
+            .into());
+        }
+        tokio::time::sleep_until(
+            deadline.min(tokio::time::Instant::now() + self.config.redis_poll_interval),
+        )
+        .await;
+        Ok(())
+    }
+}
+
+/// 查询与推送之间的队列，启用后推送任务查询出的记录交给它推送
+pub struct PushPipeline {
+    config: Arc<PushPipelineConfig>,
+    redis_mgr: RedisMgr,
+}
+
+impl PushPipeline {
+    /// 未启用时返回 None
+    pub fn new(config: Arc<PushPipelineConfig>, redis_mgr: RedisMgr) -> Option<Self> {
+        config.enabled.then_some(PushPipeline { config, redis_mgr })
+    }
+
+    fn queue(&self, kind: PsnDataKind, task_display_name: &str) -> Box<dyn PushQueue> {
+        match self.config.backend {
+            PushQueueBackend::Memory => Box::new(MemoryQueue::new(self.config.queue_capacity)),
+            PushQueueBackend::Redis => Box::new(RedisQueue {
+                redis_mgr: self.redis_mgr.clone(),
+                key: format!(
+                    "{}:{task_display_name}:{}",
+                    self.config.redis_key_prefix,
+                    Uuid::new_v4().simple()
+                ),
+                kind,
+                config: Arc::clone(&self.config),
+            }),
+        }
+    }
+
+    /// 查询阶段按批入队，同时由 workers 个 worker 并发推送，返回合并后的推送结果，不回写状态。
+    /// worker 与推送任务在同一个 tokio 任务中运行，组合任务的状态收集与执行记录对它们同样可见
+    pub async fn run(
+        &self,
+        base_task: &BasePsnPushTask,
+        psn_data_kind: PsnDataKind,
+        task_display_name: &str,
+        records: Vec<DynamicPsnData>,
+    ) -> PushBatchOutcome {
+        let workers = self.config.workers.max(1);
+        let batch_size = base_task.mss_info_config.push_batch_size();
+        let queue = self.queue(psn_data_kind, task_display_name);
+        let throttle = Arc::new(ThrottleStats::default());
+        let retry_policy = push_retry_policy(base_task);
+        let remaining_workers = AtomicUsize::new(workers);
+        let workers_stopped = Notify::new();
+        info!(
+            "Pushing {} {task_display_name} records through {:?} queue with {workers} workers.",
+            records.len(),
+            self.config.backend
+        );
+
+        let producer = async {
+            let total = records.len();
+            let mut queued = 0;
+            for batch in records.chunks(batch_size) {
+                // 关闭时不再入队，未入队的记录保持原状态，下次运行再推
+                if base_task.shutdown.is_shutting_down() {
+                    warn!(
+                        "Shutting down, stopped queueing {task_display_name} with {} records left.",
+                        total - queued
+                    );
+                    break;
+                }
+                tokio::select! {
+                    sent = queue.send(batch.to_vec()) => {
+                        if let Err(e) = sent {
+                            error!("Failed to queue {task_display_name} records, {} left unqueued: {e:?}", total - queued);
+                            break;
+                        }
+                    }
+                    _ = workers_stopped.notified() => {
+                        error!("All {task_display_name} push workers stopped, {} records left unqueued.", total - queued);
+                        break;
+                    }
+                }
+                queued += batch.len();
+            }
+            if let Err(e) = queue.close(workers).await {
+                error!("Failed to close {task_display_name} push queue: {e:?}");
+            }
+        };
+
+        let worker = |index: usize| {
+            let queue = &queue;
+            let throttle = &throttle;
+            let retry_policy = &retry_policy;
+            let remaining_workers = &remaining_workers;
+            let workers_stopped = &workers_stopped;
+            async move {
+                let mut outcome = PushBatchOutcome::default();
+                let mut consecutive_errors = 0;
+                loop {
+                    let records = match queue.recv().await {
+                        Ok(Some(records)) => records,
+                        Ok(None) => break,
+                        // 长时间没有新的记录，查询阶段可能已异常退出，不再等待
+                        Err(e) if e.is::<QueueIdleTimeout>() => {
+                            error!("Push worker {index} of {task_display_name} stopped: {e}");
+                            break;
+                        }
+                        Err(e) => {
+                            consecutive_errors += 1;
+                            error!(
+                                "Push worker {index} of {task_display_name} failed to read the queue ({consecutive_errors}/{MAX_CONSECUTIVE_RECV_ERRORS}): {e:?}"
+                            );
+                            if consecutive_errors >= MAX_CONSECUTIVE_RECV_ERRORS {
+                                break;
+                            }
+                            continue;
+                        }
+                    };
+                    consecutive_errors = 0;
+                    // 关闭时只取出不推送，让查询阶段尽快结束
+                    if base_task.shutdown.is_shutting_down() {
+                        continue;
+                    }
+                    if let Err(e) = push_batch(
+                        base_task,
+                        psn_data_kind,
+                        &records,
+                        retry_policy,
+                        throttle,
+                        &mut outcome,
+                    )
+                    .await
+                    {
+                        error!("Push worker {index} of {task_display_name} stopped: {e:?}");
+                        outcome.aborted = Some(e);
+                        break;
+                    }
+                }
+                if remaining_workers.fetch_sub(1, Ordering::AcqRel) == 1 {
+                    workers_stopped.notify_one();
+                }
+                outcome
+            }
+        };
+
+        let ((), outcomes) = futures::future::join(
+            producer,
+            futures::future::join_all((0..workers).map(worker)),
+        )
+        .await;
+        queue.cleanup().await;
+
+        let mut outcome = PushBatchOutcome::default();
+        for worker_outcome in outcomes {
+            outcome.merge(worker_outcome);
+        }
+        outcome.throttle_events = throttle.events();
+        outcome.throttle_wait_ms = throttle.wait_ms();
+        outcome
+    }
+}
+
+#[test]
+fn test_queue_item_roundtrip() {
+    let item = QueueItem::Batch {
+        kind: PsnDataKind::Lecturer,
+        records: vec![serde_json::json!({"id": "lec-1"})],
+    };
+    let encoded = serde_json::to_string(&item).unwrap();
+    assert!(encoded.starts_with(r#"{"type":"batch","kind":"Lecturer""#));
+    assert!(matches!(
+        serde_json::from_str(r#"{"type":"end"}"#).unwrap(),
+        QueueItem::End
+    ));
+}
@@ -0,0 +1,344 @@
+//! 推送结束后的核对：本次查询到的全部记录（按日期推送时即该日期的全部源数据）在 MySQL 与 ClickHouse 中
+//! trainNotifyMss 仍为 pending 或 NULL 的行数即残留，ClickHouse 各节点上与 MySQL 状态不一致的行数即不一致。
+//! 残留或不一致说明回写只执行了一部分（批次失败、mutation 未生效等），下次按日期推送时这些记录会被重复推送
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sqlx::{MySql, MySqlPool, QueryBuilder, Row};
+use tracing::{error, warn};
+
+use crate::config::{NotifyStatusConfig, PushVerificationConfig};
+use crate::db::query_runner::QueryRunner;
+use crate::metrics::{PUSH_STATUS_MISMATCH, PUSH_STATUS_RESIDUE, PUSH_STATUS_RESIDUE_EXCEEDED};
+use crate::schedule::status_updates::{NotifyStatus, StatusStore, StatusTarget};
+use crate::utils::ClickHouseClient;
+
+/// 一张表的核对结果
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusResidue {
+    pub store: StatusStore,
+    pub table: String,
+    /// 核对的 ID 数
+    pub checked: usize,
+    /// 仍为 pending / NULL 的行数，ClickHouse 取各节点中的最大值
+    pub residue: u64,
+    /// ClickHouse 中状态与 MySQL 不一致的行数，取各节点中的最大值；MySQL 表本身为 0
+    pub mismatched: u64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+impl fmt::Display for StatusResidue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}={}/{}",
+            self.store.as_str(),
+            self.table,
+            self.residue,
+            self.checked
+        )?;
+        if self.mismatched > 0 {
+            write!(f, " ({} mismatched)", self.mismatched)?;
+        }
+        if !self.errors.is_empty() {
+            write!(f, " ({} errors)", self.errors.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// 用于日志和执行汇总，如 "mysql:NU_trainSourceData_ztk=0/120, clickhouse:...=3/120"
+pub fn residue_summary(residues: &[StatusResidue]) -> String {
+    residues
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub struct PushVerifier {
+    mysql_pool: MySqlPool,
+    clickhouse_client: Arc<ClickHouseClient>,
+    statuses: Arc<NotifyStatusConfig>,
+    config: Arc<PushVerificationConfig>,
+    batch_size: usize,
+}
+
+impl PushVerifier {
+    /// 未启用时返回 None
+    pub fn new(
+        mysql_pool: MySqlPool,
+        clickhouse_client: Arc<ClickHouseClient>,
+        statuses: Arc<NotifyStatusConfig>,
+        config: Arc<PushVerificationConfig>,
+        batch_size: usize,
+    ) -> Option<Self> {
+        config.enabled.then(|| PushVerifier {
+            mysql_pool,
+            clickhouse_client,
+            statuses,
+            config,
+            batch_size: batch_size.max(1),
+        })
+    }
+
+    /// 核对回写后的残留与不一致，更新指标，超过阈值时记录错误日志与告警计数。
+    /// targets 为同一种数据的各状态表，ClickHouse 表逐条与其前面的 MySQL 表的状态比较
+    pub async fn verify(&self, targets: &[StatusTarget]) -> Vec<StatusResidue> {
+        self.settle(targets.iter()).await;
+        self.check(targets).await
+    }
+
+    /// 依次核对多种数据，ClickHouse 只等待一次
+    pub async fn verify_all(&self, groups: &[Vec<StatusTarget>]) -> Vec<Vec<StatusResidue>> {
+        self.settle(groups.iter().flatten()).await;
+        let mut residues = Vec::with_capacity(groups.len());
+        for targets in groups {
+            residues.push(self.check(targets).await);
+        }
+        residues
+    }
+
+    // ClickHouse 的 ALTER ... UPDATE 异步执行，核对前等待
+    async fn settle<'a>(&self, mut targets: impl Iterator<Item = &'a StatusTarget>) {
+        if targets.any(|target| target.store == StatusStore::Clickhouse) {
+            tokio::time::sleep(self.config.clickhouse_settle_delay).await;
+        }
+    }
+
+    async fn check(&self, targets: &[StatusTarget]) -> Vec<StatusResidue> {
+        let mut residues = Vec::with_capacity(targets.len());
+        // MySQL 中各 ID 的状态，作为 ClickHouse 的比较基准
+        let mut mysql_flags: Option<HashMap<String, String>> = None;
+        for target in targets {
+            let residue = match target.store {
+                StatusStore::Mysql => {
+                    let (residue, flags) = self.mysql_residue(target).await;
+                    mysql_flags = Some(flags);
+                    residue
+                }
+                StatusStore::Clickhouse => {
+                    self.clickhouse_residue(target, mysql_flags.as_ref()).await
+                }
+            };
+            self.report(&residue);
+            residues.push(residue);
+        }
+        residues
+    }
+
+    fn report(&self, residue: &StatusResidue) {
+        let labels = [residue.store.as_str(), residue.table.as_str()];
+        PUSH_STATUS_RESIDUE
+            .with_label_values(&labels)
+            .set(i64::try_from(residue.residue).unwrap_or(i64::MAX));
+        if residue.residue > self.config.residue_threshold {
+            PUSH_STATUS_RESIDUE_EXCEEDED
+                .with_label_values(&labels)
+                .inc();
+            error!(
+                "Status write-back left {} of {} rows pending in {} table {} (threshold {}).",
+                residue.residue,
+                residue.checked,
+                residue.store.as_str(),
+                residue.table,
+                self.config.residue_threshold
+            );
+        }
+        if residue.mismatched > 0 {
+            PUSH_STATUS_MISMATCH
+                .with_label_values(&[residue.table.as_str()])
+                .inc_by(residue.mismatched);
+            error!(
+                "{} of {} rows in ClickHouse table {} have a status different from MySQL.",
+                residue.mismatched, residue.checked, residue.table
+            );
+        }
+        for e in &residue.errors {
+            warn!(
+                "Status write-back verification of {} table {} failed: {e}",
+                residue.store.as_str(),
+                residue.table
+            );
+        }
+    }
+
+    /// MySQL 中的残留与各 ID 的状态，NULL 记为空字符串
+    async fn mysql_residue(
+        &self,
+        target: &StatusTarget,
+    ) -> (StatusResidue, HashMap<String, String>) {
+        let mut residue = StatusResidue::new(target);
+        let mut flags = HashMap::new();
+        let pending = self.statuses.value(NotifyStatus::Pending);
+        for chunk in target.ids.chunks(self.batch_size) {
+            match load_flags_mysql(&self.mysql_pool, target, chunk).await {
+                Ok(chunk_flags) => flags.extend(chunk_flags),
+                Err(e) => residue.errors.push(format!("{e:#}")),
+            }
+        }
+        residue.residue = flags
+            .values()
+            .filter(|flag| is_pending(flag, pending))
+            .count() as u64;
+        (residue, flags)
+    }
+
+    async fn clickhouse_residue(
+        &self,
+        target: &StatusTarget,
+        mysql_flags: Option<&HashMap<String, String>>,
+    ) -> StatusResidue {
+        let mut residue = StatusResidue::new(target);
+        let pending = self.statuses.value(NotifyStatus::Pending);
+        let mut by_node: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+        for chunk in target.ids.chunks(self.batch_size) {
+            for (node, result) in self
+                .clickhouse_client
+                .notify_flags_on_all_nodes(&target.table, target.id_column, chunk)
+                .await
+            {
+                match result {
+                    Ok(flags) => {
+                        let (pending_ids, mismatched_ids) = by_node.entry(node).or_default();
+                        *pending_ids += flags
+                            .values()
+                            .filter(|flags| flags.iter().any(|flag| is_pending(flag, pending)))
+                            .count() as u64;
+                        if let Some(mysql_flags) = mysql_flags {
+                            *mismatched_ids +=
+                                count_mismatched(mysql_flags, &flags, pending) as u64;
+                        }
+                    }
+                    Err(e) => residue.errors.push(format!("{node}: {e:#}")),
+                }
+            }
+        }
+        residue.residue = by_node
+            .values()
+            .map(|(pending, _)| *pending)
+            .max()
+            .unwrap_or_default();
+        residue.mismatched = by_node
+            .values()
+            .map(|(_, mismatched)| *mismatched)
+            .max()
+            .unwrap_or_default();
+        residue
+    }
+}
+
+impl StatusResidue {
+    fn new(target: &StatusTarget) -> Self {
+        StatusResidue {
+            store: target.store,
+            table: target.table.clone(),
+            checked: target.ids.len(),
+            residue: 0,
+            mismatched: 0,
+            errors: Vec::new(),
+        }
+    }
+}
+
+fn is_pending(flag: &str, pending: &str) -> bool {
+    flag.is_empty() || flag == pending
+}
+
+/// ClickHouse 节点上状态与 MySQL 不一致的 ID 数。pending 与 NULL 视为相同，任一方没有的 ID 不比较
+fn count_mismatched(
+    mysql_flags: &HashMap<String, String>,
+    clickhouse_flags: &HashMap<String, Vec<String>>,
+    pending: &str,
+) -> usize {
+    let normalize = |flag: &str| if is_pending(flag, pending) { "" } else { flag };
+    clickhouse_flags
+        .iter()
+        .filter(|(id, flags)| {
+            mysql_flags.get(id.as_str()).is_some_and(|mysql_flag| {
+                flags
+                    .iter()
+                    .any(|flag| normalize(flag) != normalize(mysql_flag))
+            })
+        })
+        .count()
+}
+
+async fn load_flags_mysql(
+    mysql_pool: &MySqlPool,
+    target: &StatusTarget,
+    ids: &[String],
+) -> Result<Vec<(String, String)>> {
+    let mut query_builder: QueryBuilder<MySql> = QueryBuilder::new(format!(
+        "SELECT CAST({id_column} AS CHAR) AS id, IFNULL(CAST(trainNotifyMss AS CHAR), '') AS flag \
+         FROM {table} WHERE {id_column} IN (",
+        id_column = target.id_column,
+        table = target.table
+    ));
+    let mut separated = query_builder.separated(", ");
+    for id in ids {
+        separated.push_bind(id.as_str());
+    }
+    separated.push_unseparated(")");
+    let rows = QueryRunner::new("push_status_flags")
+        .run(query_builder.build().fetch_all(mysql_pool))
+        .await
+        .with_context(|| format!("Failed to load push statuses from {}", target.table))?;
+    rows.iter()
+        .map(|row| Ok((row.try_get("id")?, row.try_get("flag")?)))
+        .collect()
+}
+
+#[test]
+fn test_residue_summary() {
+    assert!(is_pending("", "0"));
+    assert!(is_pending("0", "0"));
+    assert!(!is_pending("1", "0"));
+    let residues = vec![
+        StatusResidue {
+            store: StatusStore::Mysql,
+            table: "NU_trainSourceData_ztk".to_string(),
+            checked: 2,
+            residue: 0,
+            mismatched: 0,
+            errors: Vec::new(),
+        },
+        StatusResidue {
+            store: StatusStore::Clickhouse,
+            table: "DXXY_LOCAL.TRAIN_SOURCE_DATA_ZTK_ALL".to_string(),
+            checked: 2,
+            residue: 1,
+            mismatched: 1,
+            errors: vec!["timeout".to_string()],
+        },
+    ];
+    assert_eq!(
+        residue_summary(&residues),
+        "mysql:NU_trainSourceData_ztk=0/2, clickhouse:DXXY_LOCAL.TRAIN_SOURCE_DATA_ZTK_ALL=1/2 (1 mismatched) (1 errors)"
+    );
+}
+
+#[test]
+fn test_count_mismatched() {
+    let mysql_flags = HashMap::from([
+        ("a".to_string(), "1".to_string()),
+        ("b".to_string(), "".to_string()),
+        ("c".to_string(), "2".to_string()),
+    ]);
+    let clickhouse_flags = HashMap::from([
+        // 一致
+        ("a".to_string(), vec!["1".to_string()]),
+        // pending 与 NULL 视为相同
+        ("b".to_string(), vec!["0".to_string()]),
+        // 重复行中有一行不一致
+        ("c".to_string(), vec!["2".to_string(), "0".to_string()]),
+        // MySQL 中没有的 ID 不比较
+        ("d".to_string(), vec!["1".to_string()]),
+    ]);
+    assert_eq!(count_mismatched(&mysql_flags, &clickhouse_flags, "0"), 1);
+}
@@ -0,0 +1,246 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result, anyhow};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sqlx::MySqlPool;
+use tracing::info;
+
+use crate::PsnDataKind;
+use crate::config::{RunManifestConfig, SecretString};
+use crate::mappers::reply_store::{ReplyBodyStore, build_reply_store, sha256_hex};
+use crate::models::run_manifest::{KindManifest, RunManifest, record_run_manifest};
+use crate::models::task_run::current_run_id;
+use crate::utils::{Clock, timefmt};
+
+tokio::task_local! {
+    static CURRENT_MANIFEST: Arc<RunManifestCollector>;
+}
+
+/// 一类数据在本次执行中收集到的结果
+#[derive(Debug, Default)]
+struct KindRecord {
+    hit_dates: BTreeSet<String>,
+    fetched: u64,
+    pushed_ids: BTreeSet<String>,
+    failed: u64,
+}
+
+/// 组合任务一次执行内的清单收集器，子任务推送后登记各自的计数与已推送 ID
+#[derive(Debug, Default)]
+pub struct RunManifestCollector {
+    kinds: Mutex<BTreeMap<&'static str, KindRecord>>,
+}
+
+impl RunManifestCollector {
+    /// 当前组合任务执行的收集器，不在 `scope` 范围内时为 None
+    pub fn current() -> Option<Arc<RunManifestCollector>> {
+        CURRENT_MANIFEST.try_with(Arc::clone).ok()
+    }
+
+    pub async fn scope<F: Future>(self: Arc<Self>, fut: F) -> F::Output {
+        CURRENT_MANIFEST.scope(self, fut).await
+    }
+
+    pub fn record(
+        &self,
+        kind: PsnDataKind,
+        hit_date: Option<&str>,
+        fetched: usize,
+        pushed_ids: &[String],
+        failed: usize,
+    ) {
+        let mut kinds = self.kinds.lock().unwrap_or_else(|e| e.into_inner());
+        let record = kinds.entry(kind.to_task_display_name()).or_default();
+        record.hit_dates.extend(hit_date.map(str::to_string));
+        record.fetched += fetched as u64;
+        record.pushed_ids.extend(pushed_ids.iter().cloned());
+        record.failed += failed as u64;
+    }
+
+    /// 生成清单。所有子任务的 hit_date 相同时以它为清单日期，否则（按 ID 推送等）使用 today
+    fn build(&self, run_id: Option<u64>, task_name: &str, clock: &dyn Clock) -> RunManifest {
+        let kinds = self.kinds.lock().unwrap_or_else(|e| e.into_inner());
+        let hit_dates: BTreeSet<&String> = kinds
+            .values()
+            .flat_map(|record| record.hit_dates.iter())
+            .collect();
+        let manifest_date = match hit_dates.into_iter().collect::<Vec<_>>().as_slice() {
+            [hit_date] => timefmt::parse_business_date(hit_date).ok(),
+            _ => None,
+        }
+        .unwrap_or_else(|| clock.today());
+        let all_pushed: BTreeSet<&String> = kinds
+            .values()
+            .flat_map(|record| record.pushed_ids.iter())
+            .collect();
+        RunManifest {
+            run_id,
+            task_name: task_name.to_string(),
+            manifest_date,
+            generated_at: timefmt::datetime(clock.now_local()),
+            kinds: kinds
+                .iter()
+                .map(|(name, record)| {
+                    let manifest = KindManifest {
+                        hit_dates: record.hit_dates.iter().cloned().collect(),
+                        fetched: record.fetched,
+                        pushed: record.pushed_ids.len() as u64,
+                        failed: record.failed,
+                        pushed_ids_sha256: ids_checksum(&record.pushed_ids),
+                    };
+                    (name.to_string(), manifest)
+                })
+                .collect(),
+            pushed_ids_sha256: ids_checksum(all_pushed),
+        }
+    }
+}
+
+/// 去重排序后的 ID 按行拼接再计算 SHA-256，与推送顺序无关
+fn ids_checksum<'a>(ids: impl IntoIterator<Item = &'a String>) -> String {
+    let ids: BTreeSet<&String> = ids.into_iter().collect();
+    let joined = ids
+        .into_iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join("\n");
+    sha256_hex(joined.as_bytes())
+}
+
+fn sign(key: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// 把组合任务的清单签名后写入存储并记录到 composite_run_manifest
+pub struct RunManifestWriter {
+    mysql_pool: MySqlPool,
+    store: Option<Arc<dyn ReplyBodyStore>>,
+    signing_key: SecretString,
+    clock: Arc<dyn Clock>,
+}
+
+impl RunManifestWriter {
+    /// 未启用时返回 None，启用但未配置 signing_key 时返回错误
+    pub fn new(
+        mysql_pool: MySqlPool,
+        config: &RunManifestConfig,
+        http_client: reqwest::Client,
+        clock: Arc<dyn Clock>,
+    ) -> Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        if config.signing_key.is_empty() {
+            return Err(anyhow!(
+                "run_manifest.signing_key is required when run_manifest is enabled"
+            ));
+        }
+        let store = build_reply_store(&config.storage, http_client)
+            .context("Failed to initialize run manifest store")?;
+        Ok(Some(RunManifestWriter {
+            mysql_pool,
+            store,
+            signing_key: config.signing_key.clone(),
+            clock,
+        }))
+    }
+
+    /// 登记执行 run_id 使用的连接池，组合任务在 with_task_run 中执行并写入清单
+    pub fn mysql_pool(&self) -> &MySqlPool {
+        &self.mysql_pool
+    }
+
+    /// 生成并保存本次执行的清单，返回清单的 SHA-256。
+    /// 清单记录当前执行的 run_id，不在登记的执行范围内（或登记失败）时返回错误
+    pub async fn write(&self, task_name: &str, collector: &RunManifestCollector) -> Result<String> {
+        let run_id = current_run_id()
+            .ok_or_else(|| anyhow!("No run id for the run manifest of {task_name}"))?;
+        let manifest = collector.build(Some(run_id), task_name, self.clock.as_ref());
+        let body =
+            serde_json::to_vec_pretty(&manifest).context("Failed to serialize run manifest")?;
+        let sha256 = sha256_hex(&body);
+        let signature = sign(self.signing_key.expose(), &body);
+
+        let location = match &self.store {
+            Some(store) => {
+                let key = format!(
+                    "run_manifests/{}/{run_id}.json",
+                    timefmt::business_date(manifest.manifest_date)
+                );
+                Some(
+                    store
+                        .put(&key, &body)
+                        .await
+                        .context("Failed to store run manifest")?,
+                )
+            }
+            None => None,
+        };
+        record_run_manifest(
+            &self.mysql_pool,
+            &manifest,
+            &body,
+            location.as_deref(),
+            &sha256,
+            &signature,
+        )
+        .await?;
+        info!(
+            "Run manifest of '{task_name}' for {} written: sha256={sha256}, location={location:?}",
+            manifest.manifest_date
+        );
+        Ok(sha256)
+    }
+}
+
+#[test]
+fn test_manifest_build() {
+    use crate::utils::clock::FixedClock;
+    use chrono::NaiveDate;
+
+    let clock = FixedClock::at_local(
+        NaiveDate::from_ymd_opt(2026, 10, 16)
+            .unwrap()
+            .and_hms_opt(2, 0, 0)
+            .unwrap(),
+    );
+    let collector = RunManifestCollector::default();
+    collector.record(
+        PsnDataKind::Class,
+        Some("2026-10-15"),
+        3,
+        &["b".to_string(), "a".to_string()],
+        1,
+    );
+    collector.record(PsnDataKind::Training, Some("2026-10-15"), 0, &[], 0);
+    let manifest = collector.build(Some(7), "培训推送", &clock);
+    assert_eq!(
+        manifest.manifest_date,
+        NaiveDate::from_ymd_opt(2026, 10, 15).unwrap()
+    );
+    let class = &manifest.kinds["PsnClassPushTask"];
+    assert_eq!((class.fetched, class.pushed, class.failed), (3, 2, 1));
+    assert_eq!(
+        class.pushed_ids_sha256,
+        ids_checksum(&["a".to_string(), "b".to_string()])
+    );
+    assert_eq!(manifest.pushed_ids_sha256, class.pushed_ids_sha256);
+    assert_eq!(
+        manifest.kinds["PsnTrainingPushTask"].pushed_ids_sha256,
+        sha256_hex(b"")
+    );
+
+    // 按 ID 推送没有 hit_date，使用执行当天
+    let by_ids = RunManifestCollector::default();
+    by_ids.record(PsnDataKind::Archive, None, 1, &["x".to_string()], 0);
+    assert_eq!(
+        by_ids.build(None, "培训推送", &clock).manifest_date,
+        clock.today()
+    );
+}
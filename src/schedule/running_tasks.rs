@@ -0,0 +1,102 @@
+//! 正在运行的推送任务实例：任务开始时登记参数，注册表只保存弱引用，
+//! 任务结束后句柄释放即自动移出，用于诊断“现在到底在跑什么”
+
+use std::sync::{Arc, Mutex, Weak};
+
+use serde::Serialize;
+
+use crate::PsnDataKind;
+use crate::utils::timefmt;
+
+/// 一个正在运行的推送任务实例及其构造参数
+#[derive(Debug, Clone, Serialize)]
+pub struct RunningTask {
+    pub name: String,
+    pub kind: PsnDataKind,
+    /// main 或 sc（四川）
+    pub region: &'static str,
+    pub hit_date: Option<String>,
+    pub train_ids: Option<Vec<String>>,
+    pub run_id: Option<u64>,
+    pub started_at: String,
+}
+
+impl RunningTask {
+    pub fn new(
+        name: &str,
+        kind: PsnDataKind,
+        hit_date: Option<String>,
+        train_ids: Option<Vec<String>>,
+        run_id: Option<u64>,
+    ) -> Self {
+        RunningTask {
+            name: name.to_string(),
+            kind,
+            region: if kind.is_sc() { "sc" } else { "main" },
+            hit_date,
+            train_ids,
+            run_id,
+            started_at: timefmt::datetime(timefmt::now_local()),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RunningTaskRegistry {
+    tasks: Mutex<Vec<Weak<RunningTask>>>,
+}
+
+impl RunningTaskRegistry {
+    /// 登记任务，返回的句柄在任务运行期间持有，释放后任务不再出现在快照中
+    pub fn register(&self, task: RunningTask) -> Arc<RunningTask> {
+        let task = Arc::new(task);
+        let mut tasks = self.lock();
+        tasks.retain(|task| task.strong_count() > 0);
+        tasks.push(Arc::downgrade(&task));
+        task
+    }
+
+    /// 当前仍在运行的任务，按开始顺序
+    pub fn snapshot(&self) -> Vec<RunningTask> {
+        let mut tasks = self.lock();
+        tasks.retain(|task| task.strong_count() > 0);
+        tasks
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|task| task.as_ref().clone())
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Weak<RunningTask>>> {
+        self.tasks.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[test]
+fn test_running_task_registry() {
+    let registry = RunningTaskRegistry::default();
+    let class = registry.register(RunningTask::new(
+        "PsnClassPushTask",
+        PsnDataKind::Class,
+        Some("2026-10-15".to_string()),
+        None,
+        Some(1),
+    ));
+    let lecturer = registry.register(RunningTask::new(
+        "PsnLecturerScPushTask",
+        PsnDataKind::LecturerSc,
+        None,
+        Some(vec!["t-1".to_string()]),
+        Some(2),
+    ));
+    let snapshot = registry.snapshot();
+    assert_eq!(snapshot.len(), 2);
+    assert_eq!(snapshot[1].region, "sc");
+
+    drop(class);
+    let snapshot = registry.snapshot();
+    assert_eq!(snapshot.len(), 1);
+    assert_eq!(snapshot[0].name, "PsnLecturerScPushTask");
+    drop(lecturer);
+    assert!(registry.snapshot().is_empty());
+}
@@ -0,0 +1,255 @@
+//! 影子推送：MSS 新环境上线前，按记录 ID 抽样把推送的请求体另外发送到备用地址。
+//! 影子请求在后台执行、只发一次，结果记录在 mss_shadow_push_reply 与指标中，
+//! 不经过主推送的限流、重试、归档与状态回写
+
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::Instant;
+
+use anyhow::{Context, Result, bail};
+use itertools::Itertools;
+use siphasher::sip::SipHasher13;
+use sqlx::MySqlPool;
+use tokio::sync::Semaphore;
+use tracing::{error, info, warn};
+
+use crate::config::{MssInfoConfig, ShadowPushConfig};
+use crate::db::query_runner::QueryRunner;
+use crate::metrics::MSS_SHADOW_PUSH;
+use crate::models::task_run::current_run_id;
+use crate::parsers::mss_response::MssEnvelope;
+use crate::shutdown::ShutdownController;
+use crate::utils::InstrumentedClient;
+use crate::utils::mss_encoder::EncodedPayload;
+use crate::{DynamicPsnData, PsnRecord};
+
+// 同时进行的影子请求上限，超过时丢弃本批，避免影子环境变慢时请求堆积
+const MAX_IN_FLIGHT: usize = 16;
+
+// 抽样哈希的固定密钥，修改后抽中的记录会变化
+const SAMPLE_KEYS: (u64, u64) = (0x7368_6164_6f77_5f70, 0x7573_685f_7361_6d70);
+
+/// 记录是否被抽中，按 ID 的哈希决定，同一条记录在各实例、各版本中的结果相同
+fn is_sampled(id: &str, sample_percent: f64) -> bool {
+    let mut hasher = SipHasher13::new_with_keys(SAMPLE_KEYS.0, SAMPLE_KEYS.1);
+    id.hash(&mut hasher);
+    ((hasher.finish() % 10_000) as f64) < sample_percent * 100.0
+}
+
+/// 一次影子请求的结果
+struct ShadowReply {
+    outcome: &'static str,
+    http_status: Option<u16>,
+    response: String,
+}
+
+pub struct ShadowPusher {
+    http_client: Arc<InstrumentedClient>,
+    mysql_pool: MySqlPool,
+    mss_info_config: Arc<MssInfoConfig>,
+    config: Arc<ShadowPushConfig>,
+    in_flight: Arc<Semaphore>,
+    shutdown: Arc<ShutdownController>, // 后台的影子请求登记为运行中的任务，关闭时等待其写完结果
+}
+
+impl ShadowPusher {
+    /// 未启用时返回 None，启用但缺少 app_url 或抽样比例不在 0-100 时返回错误
+    pub fn new(
+        http_client: Arc<InstrumentedClient>,
+        mysql_pool: MySqlPool,
+        mss_info_config: Arc<MssInfoConfig>,
+        config: Arc<ShadowPushConfig>,
+        shutdown: Arc<ShutdownController>,
+    ) -> Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        if config.app_url.trim().is_empty() {
+            bail!("shadow_push.app_url is required when shadow push is enabled");
+        }
+        if !(0.0..=100.0).contains(&config.sample_percent) {
+            bail!(
+                "shadow_push.sample_percent must be between 0 and 100, got {}",
+                config.sample_percent
+            );
+        }
+        Ok(Some(ShadowPusher {
+            http_client,
+            mysql_pool,
+            mss_info_config,
+            config,
+            in_flight: Arc::new(Semaphore::new(MAX_IN_FLIGHT)),
+            shutdown,
+        }))
+    }
+
+    /// 把 records 中被抽中的记录在后台发送到影子环境，primary_ok 为同一批主推送的结果。
+    /// 不等待影子请求完成
+    pub fn mirror(self: &Arc<Self>, records: &[DynamicPsnData], primary_ok: bool) {
+        let sampled: Vec<DynamicPsnData> = records
+            .iter()
+            .filter(|record| is_sampled(record.id(), self.config.sample_percent))
+            .cloned()
+            .collect();
+        let Some(first) = sampled.first() else {
+            return;
+        };
+        let data_type = first.key_name();
+        let primary = if primary_ok { "success" } else { "failed" };
+        let Some(guard) = self.shutdown.track() else {
+            info!(
+                "Shutting down, skipped shadow push of {} {data_type} records.",
+                sampled.len()
+            );
+            MSS_SHADOW_PUSH
+                .with_label_values(&[data_type, "dropped", primary])
+                .inc();
+            return;
+        };
+        let Ok(permit) = Arc::clone(&self.in_flight).try_acquire_owned() else {
+            warn!(
+                "Too many shadow pushes in flight, dropped {} {data_type} records.",
+                sampled.len()
+            );
+            MSS_SHADOW_PUSH
+                .with_label_values(&[data_type, "dropped", primary])
+                .inc();
+            return;
+        };
+        let run_id = current_run_id();
+        let shadow_pusher = Arc::clone(self);
+        tokio::spawn(async move {
+            let _guard = guard;
+            let _permit = permit;
+            if let Err(e) = shadow_pusher.push(run_id, &sampled, primary_ok).await {
+                error!("Shadow push of {data_type} failed: {e:?}");
+            }
+        });
+    }
+
+    async fn push(
+        &self,
+        run_id: Option<u64>,
+        records: &[DynamicPsnData],
+        primary_ok: bool,
+    ) -> Result<()> {
+        let records: Vec<&DynamicPsnData> = records.iter().collect();
+        let data_type = records
+            .iter()
+            .map(|record| record.key_name())
+            .unique()
+            .join(",");
+        let payload = self.mss_info_config.encoding.encoder().encode(&records)?;
+
+        let started_at = Instant::now();
+        let reply = self.send(&payload).await;
+        let duration_ms = started_at.elapsed().as_millis() as i64;
+        info!(
+            "Shadow push of {} {data_type} records to {}: {} ({:?}) in {duration_ms}ms",
+            records.len(),
+            self.config.app_url,
+            reply.outcome,
+            reply.http_status
+        );
+        MSS_SHADOW_PUSH
+            .with_label_values(&[
+                &data_type,
+                reply.outcome,
+                if primary_ok { "success" } else { "failed" },
+            ])
+            .inc();
+
+        let record_ids = records.iter().map(|record| record.id()).join(",");
+        QueryRunner::new("mss_shadow_push_reply_insert")
+            .run(
+                sqlx::query(
+                    "INSERT INTO mss_shadow_push_reply (run_id, data_type, record_ids, endpoint, \
+                     outcome, primary_ok, http_status, duration_ms, request_body, response) \
+                     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                )
+                .bind(run_id)
+                .bind(&data_type)
+                .bind(record_ids)
+                .bind(&self.config.app_url)
+                .bind(reply.outcome)
+                .bind(primary_ok)
+                .bind(reply.http_status)
+                .bind(duration_ms)
+                .bind(&payload.request_json)
+                .bind(&reply.response)
+                .execute(&self.mysql_pool),
+            )
+            .await
+            .context("Failed to insert into mss_shadow_push_reply table")?;
+        Ok(())
+    }
+
+    /// 发送一次，不重试。HTTP 成功且 descCode 为成功时为 success，MSS 拒绝为 rejected，其余为 failed
+    async fn send(&self, payload: &EncodedPayload) -> ShadowReply {
+        let app_id = if self.config.app_id.is_empty() {
+            &self.mss_info_config.app_id
+        } else {
+            &self.config.app_id
+        };
+        let app_key = if self.config.app_key.is_empty() {
+            &self.mss_info_config.app_key
+        } else {
+            &self.config.app_key
+        };
+        let mut request = self
+            .http_client
+            .post(&self.config.app_url)
+            .header("X-APP-ID", app_id)
+            .header("X-APP-KEY", app_key.expose())
+            .header("Content-Type", payload.content_type)
+            .timeout(self.config.timeout)
+            .body(payload.body.clone());
+        if let Some(content_encoding) = payload.content_encoding {
+            request = request.header("Content-Encoding", content_encoding);
+        }
+
+        let response = match self.http_client.send(request).await {
+            Ok(response) => response,
+            Err(e) => {
+                return ShadowReply {
+                    outcome: "failed",
+                    http_status: None,
+                    response: format!("ERROR: {e:?}"),
+                };
+            }
+        };
+        let http_status = response.status();
+        let body = match self.http_client.read_text(response).await {
+            Ok(body) => body,
+            Err(e) => format!("ERROR: {e:?}"),
+        };
+        let outcome = if !http_status.is_success() {
+            "failed"
+        } else {
+            match serde_json::from_str::<MssEnvelope>(&body) {
+                Ok(envelope) if envelope.is_success() => "success",
+                Ok(_) => "rejected",
+                Err(_) => "failed",
+            }
+        };
+        ShadowReply {
+            outcome,
+            http_status: Some(http_status.as_u16()),
+            response: body,
+        }
+    }
+}
+
+#[test]
+fn test_is_sampled() {
+    let ids: Vec<String> = (0..1000).map(|i| format!("train-{i}")).collect();
+    assert!(ids.iter().all(|id| !is_sampled(id, 0.0)));
+    assert!(ids.iter().all(|id| is_sampled(id, 100.0)));
+    // 同一 ID 结果稳定，抽样数量接近比例
+    let sampled = ids.iter().filter(|id| is_sampled(id, 10.0)).count();
+    assert!((50..=150).contains(&sampled), "sampled {sampled}");
+    assert!(
+        ids.iter()
+            .all(|id| is_sampled(id, 10.0) == is_sampled(id, 10.0))
+    );
+}
@@ -0,0 +1,458 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use serde::Serialize;
+use sqlx::MySqlPool;
+use tracing::{error, info};
+
+use crate::config::NotifyStatusConfig;
+use crate::metrics::DB_BATCH_SIZE;
+use crate::schedule::clickhouse_replay::ClickHouseReplayQueue;
+use crate::schedule::push_executor::update_notify_mss_mysql;
+use crate::schedule::push_outbox::PushOutbox;
+use crate::schedule::push_verification::{PushVerifier, residue_summary};
+use crate::utils::ClickHouseClient;
+use crate::{AppContext, PsnDataKind};
+
+tokio::task_local! {
+    static CURRENT_COLLECTOR: Arc<StatusUpdateCollector>;
+}
+
+/// trainNotifyMss 的状态，写入数据库的值由 NotifyStatusConfig 决定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NotifyStatus {
+    /// 未推送，下次运行重新推送
+    Pending,
+    Success,
+    Failed,
+}
+
+impl NotifyStatus {
+    pub const ALL: [NotifyStatus; 3] = [
+        NotifyStatus::Pending,
+        NotifyStatus::Success,
+        NotifyStatus::Failed,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NotifyStatus::Pending => "pending",
+            NotifyStatus::Success => "success",
+            NotifyStatus::Failed => "failed",
+        }
+    }
+}
+
+impl fmt::Display for NotifyStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for NotifyStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NotifyStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| anyhow!("Unknown notify status: {s}"))
+    }
+}
+
+/// trainNotifyMss 所在的存储
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusStore {
+    Mysql,
+    Clickhouse,
+}
+
+impl StatusStore {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StatusStore::Mysql => "mysql",
+            StatusStore::Clickhouse => "clickhouse",
+        }
+    }
+}
+
+/// 一张表中要核对推送状态的 ID
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusTarget {
+    pub store: StatusStore,
+    pub table: String,
+    pub id_column: &'static str,
+    pub ids: Vec<String>,
+}
+
+/// 待回写的 trainNotifyMss 状态。同一张表的更新合并在一起，同一 ID 以最后一次写入的状态为准
+#[derive(Debug, Default)]
+pub struct StatusUpdates {
+    // (表, ID 字段) -> ID -> 状态
+    clickhouse: BTreeMap<(String, &'static str), BTreeMap<String, NotifyStatus>>,
+    // (表, ID 字段, 是否更新 trainNotifyMssMessage) -> ID -> (状态, 失败原因)
+    mysql: BTreeMap<
+        (&'static str, &'static str, bool),
+        BTreeMap<String, (NotifyStatus, Option<String>)>,
+    >,
+    // 回写后从推送发件箱删除的记录：数据种类 -> 回写的表与 ID
+    settled: HashMap<PsnDataKind, SettledRecords>,
+}
+
+/// 一类数据回写后可以从发件箱删除的记录，tables 中的表都回写成功的 ID 才删除
+#[derive(Debug, Default)]
+struct SettledRecords {
+    tables: BTreeSet<String>,
+    ids: Vec<String>,
+}
+
+impl StatusUpdates {
+    pub fn add_clickhouse<'a>(
+        &mut self,
+        table: &str,
+        id_column: &'static str,
+        status: NotifyStatus,
+        ids: impl IntoIterator<Item = &'a String>,
+    ) {
+        let entries = self
+            .clickhouse
+            .entry((table.to_string(), id_column))
+            .or_default();
+        for id in ids {
+            entries.insert(id.clone(), status);
+        }
+    }
+
+    pub fn add_mysql(
+        &mut self,
+        table: &'static str,
+        id_column: &'static str,
+        update_message_field: bool,
+        status: NotifyStatus,
+        items: &[(String, Option<String>)],
+    ) {
+        let entries = self
+            .mysql
+            .entry((table, id_column, update_message_field))
+            .or_default();
+        for (id, reason) in items {
+            entries.insert(id.clone(), (status, reason.clone()));
+        }
+    }
+
+    /// 这些记录在 tables 中的状态都回写成功后从推送发件箱删除
+    pub fn add_settled<'a>(
+        &mut self,
+        kind: PsnDataKind,
+        tables: &[&str],
+        ids: impl IntoIterator<Item = &'a String>,
+    ) {
+        let settled = self.settled.entry(kind).or_default();
+        settled
+            .tables
+            .extend(tables.iter().map(|table| table.to_string()));
+        settled.ids.extend(ids.into_iter().cloned());
+    }
+
+    pub fn merge(&mut self, other: StatusUpdates) {
+        for (key, entries) in other.clickhouse {
+            self.clickhouse.entry(key).or_default().extend(entries);
+        }
+        for (key, entries) in other.mysql {
+            self.mysql.entry(key).or_default().extend(entries);
+        }
+        for (kind, other) in other.settled {
+            let settled = self.settled.entry(kind).or_default();
+            settled.tables.extend(other.tables);
+            settled.ids.extend(other.ids);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clickhouse.values().all(BTreeMap::is_empty)
+            && self.mysql.values().all(BTreeMap::is_empty)
+            && self.settled.values().all(|settled| settled.ids.is_empty())
+    }
+
+    /// 按表和状态分组，每组每 `batch_size` 个 ID 执行一次更新，状态按 `statuses` 转换为写入的值。
+    /// ClickHouse 更新在部分节点失败时，启用了 replay_queue 则写入重放队列。
+    /// 返回各类数据中状态已回写（或已进入重放队列）、可以从推送发件箱删除的 ID
+    pub async fn flush(
+        self,
+        clickhouse_client: &ClickHouseClient,
+        mysql_pool: &MySqlPool,
+        batch_size: usize,
+        statuses: &NotifyStatusConfig,
+        replay_queue: Option<&ClickHouseReplayQueue>,
+    ) -> HashMap<PsnDataKind, Vec<String>> {
+        let batch_size = batch_size.max(1);
+        // 表 -> 回写失败的 ID
+        let mut unwritten: HashMap<String, HashSet<String>> = HashMap::new();
+        for ((table, id_column), entries) in self.clickhouse {
+            let mut by_status: BTreeMap<NotifyStatus, Vec<String>> = BTreeMap::new();
+            for (id, status) in entries {
+                by_status.entry(status).or_default().push(id);
+            }
+            for (status, ids) in by_status {
+                let value = statuses.value(status);
+                for chunk in ids.chunks(batch_size) {
+                    info!(
+                        "Attempting to update status {status} for {} IDs in ClickHouse table '{table}'.",
+                        chunk.len()
+                    );
+                    DB_BATCH_SIZE
+                        .with_label_values(&["clickhouse", table.as_str()])
+                        .observe(chunk.len() as f64);
+                    let written = match clickhouse_client
+                        .update_notify_status(&table, id_column, value, chunk)
+                        .await
+                    {
+                        Ok(execution) if execution.all_succeeded() => true,
+                        Ok(execution) => match replay_queue {
+                            Some(replay_queue) => replay_queue.enqueue_failures(&execution).await,
+                            None => false,
+                        },
+                        Err(e) => {
+                            error!("Failed to update status {status} in '{table}': {e:?}");
+                            false
+                        }
+                    };
+                    if !written {
+                        unwritten
+                            .entry(table.clone())
+                            .or_default()
+                            .extend(chunk.iter().cloned());
+                    }
+                }
+            }
+        }
+
+        for ((table, id_column, update_message_field), entries) in self.mysql {
+            let mut by_status: BTreeMap<NotifyStatus, Vec<(String, Option<String>)>> =
+                BTreeMap::new();
+            for (id, (status, reason)) in entries {
+                by_status.entry(status).or_default().push((id, reason));
+            }
+            for (status, items) in by_status {
+                for chunk in items.chunks(batch_size) {
+                    DB_BATCH_SIZE
+                        .with_label_values(&["mysql", table])
+                        .observe(chunk.len() as f64);
+                    if let Err(e) = update_notify_mss_mysql(
+                        mysql_pool,
+                        table,
+                        id_column,
+                        status,
+                        statuses,
+                        chunk,
+                        update_message_field,
+                    )
+                    .await
+                    {
+                        error!("Failed to update status {status} in '{table}': {e:?}");
+                        unwritten
+                            .entry(table.to_string())
+                            .or_default()
+                            .extend(chunk.iter().map(|(id, _)| id.clone()));
+                    }
+                }
+            }
+        }
+
+        written_ids(self.settled, &unwritten)
+    }
+}
+
+/// 在各自回写的表中都没有失败的 ID，失败的留在发件箱，启动时恢复再次补写
+fn written_ids(
+    settled: HashMap<PsnDataKind, SettledRecords>,
+    unwritten: &HashMap<String, HashSet<String>>,
+) -> HashMap<PsnDataKind, Vec<String>> {
+    let mut written = HashMap::new();
+    for (kind, settled) in settled {
+        let ids: Vec<String> = settled
+            .ids
+            .into_iter()
+            .filter(|id| {
+                !settled
+                    .tables
+                    .iter()
+                    .any(|table| unwritten.get(table).is_some_and(|ids| ids.contains(id)))
+            })
+            .collect();
+        if !ids.is_empty() {
+            written.insert(kind, ids);
+        }
+    }
+    written
+}
+
+/// 组合任务内共享的状态回写收集器：子任务的回写先合并，组合任务结束时统一执行一次，
+/// 减少对同一张 ClickHouse 表的 mutation 次数
+pub struct StatusUpdateCollector {
+    pending: Mutex<StatusUpdates>,
+    clickhouse_client: Arc<ClickHouseClient>,
+    mysql_pool: MySqlPool,
+    batch_size: usize,
+    statuses: Arc<NotifyStatusConfig>,
+    // 回写后核对残留，未启用时为 None
+    verifier: Option<Arc<PushVerifier>>,
+    // 各子任务查询到的记录，回写后按子任务分组核对
+    verifications: Mutex<Vec<Vec<StatusTarget>>>,
+    // 回写后删除发件箱中已完成的行，未启用时为 None
+    outbox: Option<Arc<PushOutbox>>,
+    // ClickHouse 节点失败时的重放队列，未启用时为 None
+    replay_queue: Option<Arc<ClickHouseReplayQueue>>,
+}
+
+impl StatusUpdateCollector {
+    pub fn new(app_context: &AppContext) -> Self {
+        StatusUpdateCollector {
+            pending: Mutex::new(StatusUpdates::default()),
+            clickhouse_client: Arc::clone(&app_context.clickhouse_client),
+            mysql_pool: app_context.mysql_pool.clone(),
+            batch_size: app_context.limits.push_update_batch_size,
+            statuses: Arc::clone(&app_context.notify_status),
+            verifier: app_context.push_verifier.clone(),
+            verifications: Mutex::new(Vec::new()),
+            outbox: app_context.push_outbox.clone(),
+            replay_queue: app_context.clickhouse_replay.clone(),
+        }
+    }
+
+    /// 当前组合任务的收集器，不在 `scope` 范围内时为 None
+    pub fn current() -> Option<Arc<StatusUpdateCollector>> {
+        CURRENT_COLLECTOR.try_with(Arc::clone).ok()
+    }
+
+    /// 在收集器范围内执行 `fut`，结束后回写收集到的全部状态
+    pub async fn scope<F: Future>(self: Arc<Self>, fut: F) -> F::Output {
+        let output = CURRENT_COLLECTOR.scope(Arc::clone(&self), fut).await;
+        self.flush().await;
+        output
+    }
+
+    pub fn add(&self, updates: StatusUpdates) {
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .merge(updates);
+    }
+
+    /// 子任务查询到的记录的核对目标，合并回写之后核对
+    pub fn verify_after_flush(&self, targets: Vec<StatusTarget>) {
+        if targets.is_empty() {
+            return;
+        }
+        self.verifications
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(targets);
+    }
+
+    pub async fn flush(&self) {
+        let mut updates =
+            std::mem::take(&mut *self.pending.lock().unwrap_or_else(|e| e.into_inner()));
+        if !updates.is_empty() {
+            info!("Flushing merged status updates of composite task.");
+            let written = updates
+                .flush(
+                    &self.clickhouse_client,
+                    &self.mysql_pool,
+                    self.batch_size,
+                    &self.statuses,
+                    self.replay_queue.as_deref(),
+                )
+                .await;
+            if let Some(outbox) = &self.outbox {
+                outbox.settle_all(written).await;
+            }
+        }
+        let verifications =
+            std::mem::take(&mut *self.verifications.lock().unwrap_or_else(|e| e.into_inner()));
+        if let Some(verifier) = &self.verifier {
+            for residues in verifier.verify_all(&verifications).await {
+                info!(
+                    "Merged status write-back verification: {}",
+                    residue_summary(&residues)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_status_updates_merge_last_status_wins() {
+    let mut first = StatusUpdates::default();
+    first.add_clickhouse(
+        "T",
+        "id",
+        NotifyStatus::Failed,
+        &["a".to_string(), "b".to_string()],
+    );
+    let mut second = StatusUpdates::default();
+    second.add_clickhouse(
+        "T",
+        "id",
+        NotifyStatus::Success,
+        &["b".to_string(), "c".to_string()],
+    );
+    second.add_mysql(
+        "M",
+        "id",
+        false,
+        NotifyStatus::Success,
+        &[("a".to_string(), None)],
+    );
+    first.merge(second);
+
+    let statuses = &first.clickhouse[&("T".to_string(), "id")];
+    assert_eq!(statuses.len(), 3);
+    assert_eq!(statuses["a"], NotifyStatus::Failed);
+    assert_eq!(statuses["b"], NotifyStatus::Success);
+    assert_eq!(first.mysql[&("M", "id", false)].len(), 1);
+}
+
+#[test]
+fn test_written_ids_keep_unwritten_in_outbox() {
+    let mut updates = StatusUpdates::default();
+    let ids = ["a".to_string(), "b".to_string(), "c".to_string()];
+    updates.add_settled(PsnDataKind::Class, &["CH", "M"], &ids);
+    updates.add_settled(PsnDataKind::Lecturer, &["L"], &ids[..1]);
+    let unwritten = HashMap::from([
+        ("M".to_string(), HashSet::from(["b".to_string()])),
+        ("CH".to_string(), HashSet::from(["c".to_string()])),
+        ("L".to_string(), HashSet::from(["a".to_string()])),
+    ]);
+    let written = written_ids(updates.settled, &unwritten);
+    assert_eq!(written[&PsnDataKind::Class], vec!["a".to_string()]);
+    assert!(!written.contains_key(&PsnDataKind::Lecturer));
+}
+
+#[test]
+fn test_notify_status_values() {
+    assert_eq!(
+        "Success".parse::<NotifyStatus>().unwrap(),
+        NotifyStatus::Success
+    );
+    assert!("done".parse::<NotifyStatus>().is_err());
+    assert_eq!(NotifyStatus::Failed.to_string(), "failed");
+
+    let default = NotifyStatusConfig::default();
+    assert_eq!(default.value(NotifyStatus::Failed), "2");
+    assert_eq!(default.parse("0"), Some(NotifyStatus::Pending));
+    let custom = NotifyStatusConfig {
+        success: "SUCCESS".to_string(),
+        failed: "FAIL".to_string(),
+        ..Default::default()
+    };
+    assert_eq!(custom.value(NotifyStatus::Success), "SUCCESS");
+    assert_eq!(custom.parse("FAIL"), Some(NotifyStatus::Failed));
+    assert_eq!(custom.parse("failed"), Some(NotifyStatus::Failed));
+    assert_eq!(custom.parse("2"), None);
+}
@@ -0,0 +1,466 @@
+//! 调度器中 cron 任务的注册表：记录每个任务的 cron 表达式与最近一次运行结果，
+//! 支持运行时手动触发、暂停、恢复和修改 cron 表达式。
+//! 修改只在内存中生效，重启后恢复为配置文件中的计划
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use tokio_cron_scheduler::{Job, JobScheduler};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::TaskExecutor;
+use crate::db::mysql_pool::is_pool_exhausted;
+use crate::metrics::{
+    SCHEDULER_JOB_LAST_SUCCESS, SCHEDULER_JOB_REGISTERED, TASK_RUN_DURATION, TASK_RUNS,
+};
+use crate::models::task_run_history::TaskRunRecorder;
+use crate::schedule::cron_calendar::{
+    CronSchedule, SCHEDULER_TIMEZONE, next_fire_time, validate_cron,
+};
+use crate::shutdown::ShutdownController;
+use crate::utils::timefmt;
+
+type SharedTask = Arc<dyn TaskExecutor + Send + Sync + 'static>;
+
+// 开始运行前连接池耗尽时等待多久后再检查，以及一次触发最多检查的次数
+const POOL_EXHAUSTED_RETRY_DELAY: Duration = Duration::from_secs(60);
+const POOL_EXHAUSTED_MAX_ATTEMPTS: u32 = 3;
+
+/// 任务最近一次运行的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunResult {
+    Success,
+    Failed,
+    /// 关闭过程中触发，未运行
+    Skipped,
+    /// 开始运行前 MySQL 连接池耗尽，等待后再运行
+    #[serde(rename = "retry_later")]
+    RetryLater,
+}
+
+/// 任务控制接口的错误，由 handler 转换为对应的 HTTP 状态码
+#[derive(Debug, thiserror::Error)]
+pub enum TaskControlError {
+    #[error("Task '{0}' is not registered")]
+    NotFound(String),
+    #[error("Task '{0}' is already paused")]
+    AlreadyPaused(String),
+    #[error("Task '{0}' is not paused")]
+    NotPaused(String),
+    #[error("Task '{0}' is already running")]
+    AlreadyRunning(String),
+    #[error("Shutting down, task '{0}' was not started")]
+    ShuttingDown(String),
+    #[error("{0:#}")]
+    InvalidSchedule(anyhow::Error),
+    #[error("{0:#}")]
+    Scheduler(anyhow::Error),
+}
+
+/// GET /tasks 返回的任务状态，时间为调度器时区
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskStatus {
+    pub name: String,
+    pub cron_schedule: String,
+    pub paused: bool,
+    pub running: bool,
+    pub last_run_at: Option<String>,
+    pub last_duration_ms: Option<u64>,
+    pub last_result: Option<RunResult>,
+    pub last_error: Option<String>,
+    /// 暂停时为 None
+    pub next_fire_at: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct RunState {
+    cron_schedule: String,
+    /// 调度器中的 job，暂停时为 None
+    job_id: Option<Uuid>,
+    /// 正在运行的次数，cron 触发与手动触发可能重叠
+    running: usize,
+    last_run_at: Option<NaiveDateTime>,
+    last_duration_ms: Option<u64>,
+    last_result: Option<RunResult>,
+    last_error: Option<String>,
+}
+
+struct RegisteredTask {
+    task: SharedTask,
+    dependents: Vec<SharedTask>,
+    recorder: TaskRunRecorder,
+    state: Mutex<RunState>,
+}
+
+impl RegisteredTask {
+    fn state(&self) -> std::sync::MutexGuard<'_, RunState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn begin(&self) {
+        self.state().running += 1;
+    }
+
+    /// 运行一次主任务，成功后依次运行依赖任务。调用前须先调用 begin。
+    /// 开始前连接池耗尽时等待后再运行，等待期间任务仍视为运行中，不会被重复触发。
+    /// 运行开始后不再重跑，组合任务中已完成的子任务不会被重复执行
+    async fn run_started(&self, shutdown: &ShutdownController, trigger: &str) {
+        let job_name = self.task.name().to_string();
+        let mut attempt = 1;
+        loop {
+            // 关闭过程中不再开始新的运行，guard 在任务与依赖任务结束后释放
+            let Some(guard) = shutdown.track() else {
+                info!("Shutting down, skipping job '{job_name}' ({trigger}).");
+                TASK_RUNS
+                    .with_label_values(&[job_name.as_str(), "skipped"])
+                    .inc();
+                self.finish(RunResult::Skipped, None, 0);
+                return;
+            };
+            // 只在开始运行前等待连接池恢复，最后一次检查仍失败时照常运行，由运行记录失败原因
+            let checked = Instant::now();
+            match self.recorder.check_pool().await {
+                Err(e) if is_pool_exhausted(&e) && attempt < POOL_EXHAUSTED_MAX_ATTEMPTS => {
+                    drop(guard);
+                    let duration_ms =
+                        u64::try_from(checked.elapsed().as_millis()).unwrap_or(u64::MAX);
+                    warn!(
+                        "Job '{job_name}' ({trigger}) hit MySQL pool exhaustion before starting, retrying in {POOL_EXHAUSTED_RETRY_DELAY:?} (attempt {attempt}/{POOL_EXHAUSTED_MAX_ATTEMPTS}): {e:#}"
+                    );
+                    TASK_RUNS
+                        .with_label_values(&[job_name.as_str(), "retry_later"])
+                        .inc();
+                    self.record_retry_later(format!("{e:#}"), duration_ms);
+                    tokio::select! {
+                        _ = tokio::time::sleep(POOL_EXHAUSTED_RETRY_DELAY) => {}
+                        _ = shutdown.cancelled() => {}
+                    }
+                    attempt += 1;
+                    continue;
+                }
+                Err(e) => warn!("Pool check before job '{job_name}' ({trigger}) failed: {e:#}"),
+                Ok(()) => {}
+            }
+            info!("Job '{job_name}' ({trigger}) is running.");
+            // --- 执行主任务 ---
+            let started = Instant::now();
+            let result = self.recorder.run(self.task.as_ref(), trigger).await;
+            let duration_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+            record_task_run(&job_name, started, result.is_ok());
+            self.complete(&job_name, trigger, result, duration_ms).await;
+            return;
+        }
+    }
+
+    async fn complete(&self, job_name: &str, trigger: &str, result: Result<()>, duration_ms: u64) {
+        match result {
+            Err(e) => {
+                error!("Error executing primary job '{job_name}' ({trigger}): {e:?}");
+                self.finish(RunResult::Failed, Some(format!("{e:#}")), duration_ms);
+            }
+            Ok(()) => {
+                info!("Primary job '{job_name}' ({trigger}) completed successfully.");
+                SCHEDULER_JOB_LAST_SUCCESS
+                    .with_label_values(&[job_name])
+                    .set(chrono::Utc::now().timestamp());
+                self.finish(RunResult::Success, None, duration_ms);
+                // --- 执行依赖任务 ---
+                execute_dependent_tasks(job_name, &self.dependents, &self.recorder).await;
+            }
+        }
+    }
+
+    /// 记录本次运行因连接池耗尽将重新运行，不减少运行计数
+    fn record_retry_later(&self, error: String, duration_ms: u64) {
+        let mut state = self.state();
+        state.last_run_at = Some(timefmt::now_local());
+        state.last_duration_ms = Some(duration_ms);
+        state.last_result = Some(RunResult::RetryLater);
+        state.last_error = Some(error);
+    }
+
+    fn finish(&self, result: RunResult, error: Option<String>, duration_ms: u64) {
+        let mut state = self.state();
+        state.running = state.running.saturating_sub(1);
+        state.last_run_at = Some(timefmt::now_local());
+        state.last_duration_ms = Some(duration_ms);
+        state.last_result = Some(result);
+        state.last_error = error;
+    }
+
+    fn status(&self) -> TaskStatus {
+        let state = self.state();
+        let paused = state.job_id.is_none();
+        let next_fire_at = if paused {
+            None
+        } else {
+            let now = chrono::Utc::now().with_timezone(&SCHEDULER_TIMEZONE);
+            next_fire_time(&state.cron_schedule, now)
+                .ok()
+                .flatten()
+                .map(|time| timefmt::datetime(time.naive_local()))
+        };
+        TaskStatus {
+            name: self.task.name().to_string(),
+            cron_schedule: state.cron_schedule.clone(),
+            paused,
+            running: state.running > 0,
+            last_run_at: state.last_run_at.map(timefmt::datetime),
+            last_duration_ms: state.last_duration_ms,
+            last_result: state.last_result,
+            last_error: state.last_error.clone(),
+            next_fire_at,
+        }
+    }
+}
+
+pub struct TaskRegistry {
+    scheduler: JobScheduler,
+    shutdown: Arc<ShutdownController>,
+    recorder: TaskRunRecorder,
+    tasks: Mutex<BTreeMap<String, Arc<RegisteredTask>>>,
+    // 暂停、恢复与修改计划串行执行，避免同一任务被重复加入调度器
+    control: tokio::sync::Mutex<()>,
+}
+
+impl TaskRegistry {
+    pub fn new(
+        scheduler: JobScheduler,
+        shutdown: Arc<ShutdownController>,
+        recorder: TaskRunRecorder,
+    ) -> Self {
+        Self {
+            scheduler,
+            shutdown,
+            recorder,
+            tasks: Mutex::new(BTreeMap::new()),
+            control: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// 把任务按 cron_schedule 加入调度器，主任务成功后依次运行 dependents
+    pub async fn register(
+        &self,
+        task: SharedTask,
+        cron_schedule: &str,
+        dependents: Vec<SharedTask>,
+    ) -> Result<()> {
+        let job_name = task.name().to_string();
+        if self.get(&job_name).is_some() {
+            bail!("Task '{job_name}' is registered more than once");
+        }
+        let entry = Arc::new(RegisteredTask {
+            task,
+            dependents,
+            recorder: self.recorder.clone(),
+            state: Mutex::new(RunState {
+                cron_schedule: cron_schedule.to_string(),
+                ..Default::default()
+            }),
+        });
+        let job_id = self.schedule(&entry, cron_schedule).await?;
+        entry.state().job_id = Some(job_id);
+        self.tasks().insert(job_name, entry);
+        Ok(())
+    }
+
+    /// 所有任务的状态，按名称排序
+    pub fn list(&self) -> Vec<TaskStatus> {
+        self.tasks().values().map(|entry| entry.status()).collect()
+    }
+
+    /// 未暂停任务当前的 cron 表达式
+    pub fn cron_schedules(&self) -> Vec<CronSchedule> {
+        self.tasks()
+            .values()
+            .filter_map(|entry| {
+                let state = entry.state();
+                state.job_id.map(|_| CronSchedule {
+                    task_name: entry.task.name().to_string(),
+                    cron_schedule: state.cron_schedule.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// 立即运行一次任务，返回的 future 由调用方 spawn。任务正在运行时拒绝，避免重复推送
+    pub fn trigger(
+        &self,
+        name: &str,
+    ) -> Result<impl Future<Output = ()> + Send + 'static, TaskControlError> {
+        let entry = self.find(name)?;
+        if self.shutdown.is_shutting_down() {
+            return Err(TaskControlError::ShuttingDown(name.to_string()));
+        }
+        {
+            let mut state = entry.state();
+            if state.running > 0 {
+                return Err(TaskControlError::AlreadyRunning(name.to_string()));
+            }
+            state.running += 1;
+        }
+        let shutdown = Arc::clone(&self.shutdown);
+        Ok(async move { entry.run_started(&shutdown, "manual").await })
+    }
+
+    /// 从调度器中移除任务，正在进行的运行不受影响
+    pub async fn pause(&self, name: &str) -> Result<TaskStatus, TaskControlError> {
+        let _control = self.control.lock().await;
+        let entry = self.find(name)?;
+        let Some(job_id) = entry.state().job_id else {
+            return Err(TaskControlError::AlreadyPaused(name.to_string()));
+        };
+        self.scheduler
+            .remove(&job_id)
+            .await
+            .with_context(|| format!("Failed to remove job '{name}' from scheduler"))
+            .map_err(TaskControlError::Scheduler)?;
+        entry.state().job_id = None;
+        SCHEDULER_JOB_REGISTERED.with_label_values(&[name]).set(0);
+        info!("Job '{name}' paused.");
+        Ok(entry.status())
+    }
+
+    /// 按当前的 cron 表达式重新加入调度器
+    pub async fn resume(&self, name: &str) -> Result<TaskStatus, TaskControlError> {
+        let _control = self.control.lock().await;
+        let entry = self.find(name)?;
+        let cron_schedule = {
+            let state = entry.state();
+            if state.job_id.is_some() {
+                return Err(TaskControlError::NotPaused(name.to_string()));
+            }
+            state.cron_schedule.clone()
+        };
+        let job_id = self
+            .schedule(&entry, &cron_schedule)
+            .await
+            .map_err(TaskControlError::Scheduler)?;
+        entry.state().job_id = Some(job_id);
+        info!("Job '{name}' resumed with '{cron_schedule}'.");
+        Ok(entry.status())
+    }
+
+    /// 修改 cron 表达式。先加入新 job 再移除旧 job，失败时保留原计划；暂停的任务只更新表达式
+    pub async fn reschedule(
+        &self,
+        name: &str,
+        cron_schedule: &str,
+    ) -> Result<TaskStatus, TaskControlError> {
+        let _control = self.control.lock().await;
+        let entry = self.find(name)?;
+        validate_cron(cron_schedule).map_err(TaskControlError::InvalidSchedule)?;
+        let old_job_id = entry.state().job_id;
+        if let Some(old_job_id) = old_job_id {
+            let job_id = self
+                .schedule(&entry, cron_schedule)
+                .await
+                .map_err(TaskControlError::Scheduler)?;
+            if let Err(e) = self.scheduler.remove(&old_job_id).await {
+                // 旧 job 移除失败时撤回新 job，保持只有一个计划
+                let _ = self.scheduler.remove(&job_id).await;
+                return Err(TaskControlError::Scheduler(anyhow::anyhow!(
+                    "Failed to remove previous job '{name}' from scheduler: {e:?}"
+                )));
+            }
+            entry.state().job_id = Some(job_id);
+        }
+        let previous =
+            std::mem::replace(&mut entry.state().cron_schedule, cron_schedule.to_string());
+        info!("Job '{name}' rescheduled from '{previous}' to '{cron_schedule}'.");
+        Ok(entry.status())
+    }
+
+    async fn schedule(&self, entry: &Arc<RegisteredTask>, cron_schedule: &str) -> Result<Uuid> {
+        let job_name = entry.task.name().to_string();
+        let job_entry = Arc::clone(entry);
+        let shutdown = Arc::clone(&self.shutdown);
+        let job = Job::new_async_tz(
+            cron_schedule,
+            SCHEDULER_TIMEZONE,
+            move |_uuid, _scheduler| {
+                let entry = Arc::clone(&job_entry);
+                let shutdown = Arc::clone(&shutdown);
+                Box::pin(async move {
+                    entry.begin();
+                    entry.run_started(&shutdown, "cron").await;
+                })
+            },
+        )
+        .context(format!("Failed to create cron job '{job_name}'"))?;
+
+        let job_id = self
+            .scheduler
+            .add(job)
+            .await
+            .context(format!("Failed to add job '{job_name}' to scheduler"))?;
+        SCHEDULER_JOB_REGISTERED
+            .with_label_values(&[job_name.as_str()])
+            .set(1);
+        info!("Job '{job_name}' added to scheduler.");
+        Ok(job_id)
+    }
+
+    fn get(&self, name: &str) -> Option<Arc<RegisteredTask>> {
+        self.tasks().get(name).cloned()
+    }
+
+    fn find(&self, name: &str) -> Result<Arc<RegisteredTask>, TaskControlError> {
+        self.get(name)
+            .ok_or_else(|| TaskControlError::NotFound(name.to_string()))
+    }
+
+    fn tasks(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Arc<RegisteredTask>>> {
+        self.tasks.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// 记录一次任务执行的结果与耗时
+pub(crate) fn record_task_run(task: &str, started: Instant, success: bool) {
+    let outcome = if success { "success" } else { "failed" };
+    TASK_RUNS.with_label_values(&[task, outcome]).inc();
+    TASK_RUN_DURATION
+        .with_label_values(&[task])
+        .observe(started.elapsed().as_secs_f64());
+}
+
+async fn execute_dependent_tasks(
+    primary_job_name: &str,
+    deps: &[SharedTask],
+    recorder: &TaskRunRecorder,
+) {
+    if deps.is_empty() {
+        info!("No dependent tasks to execute for '{primary_job_name}'.");
+        return;
+    }
+
+    info!(
+        "Starting {} dependent tasks for '{primary_job_name}'.",
+        deps.len()
+    );
+    // --- 遍历并执行所有依赖任务 ---
+    for (i, task) in deps.iter().enumerate() {
+        let task_num = i + 1;
+        info!("Executing dependent task #{task_num} for '{primary_job_name}'.");
+
+        match recorder.run(task.as_ref(), "dependent").await {
+            Ok(()) => {
+                info!(
+                    "Dependent task #{task_num} for '{primary_job_name}' completed successfully."
+                );
+            }
+            Err(e) => {
+                error!(
+                    "Error executing dependent task #{task_num} for '{primary_job_name}': {e:?}"
+                );
+            }
+        }
+    }
+}
@@ -1,38 +1,77 @@
-use crate::config::TasksConfig;
+use crate::alert_rules::{MonitoredTasks, generate_alert_rules};
+use crate::binlog::refresh::validate_refresh_queries;
+use crate::config::{BinlogPollingConfig, CompositeGroupConfig, TasksConfig};
+use crate::metrics::{SCHEDULER_JOB_LAST_SUCCESS, SCHEDULER_JOB_REGISTERED};
+use crate::models::task_run_history::TaskRunRecorder;
 use crate::schedule::binlog_sync::BinlogSyncTask;
+use crate::schedule::clickhouse_replay::spawn_replay_worker;
+use crate::schedule::mss_retry_queue::spawn_retry_worker;
+use crate::schedule::poll_interval::AdaptivePollInterval;
+use crate::schedule::push_executor::audit_push_queries;
+use crate::schedule::push_outbox::recover_outbox;
+use crate::schedule::status_updates::StatusUpdateCollector;
+use crate::schedule::task_registry::{TaskRegistry, record_task_run};
+use crate::shutdown::ShutdownController;
 use crate::{
+    AppContext, PsnDataKind, TaskExecutor,
     schedule::{
-        CompositeTask, PsnArchivePushTask, PsnArchiveScPushTask, PsnClassPushTask,
-        PsnClassScPushTask, PsnLecturerPushTask, PsnLecturerScPushTask, PsnTrainingPushTask,
-        PsnTrainingScPushTask,
-    }, AppContext,
-    TaskExecutor,
+        BasePsnPushTask, BinlogDigestTask, BinlogGapReplayTask, ClickhouseSchemaCheckTask,
+        CompositeTask, GatewayCacheWarmupTask, PsnArchivePushTask, PsnArchiveScPushTask,
+        PsnClassPushTask, PsnClassScPushTask, PsnDeletePushTask, PsnLecturerPushTask,
+        PsnLecturerScPushTask, PsnTrainingPushTask, PsnTrainingScPushTask,
+    },
 };
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
-use tokio_cron_scheduler::{Job, JobScheduler};
+use tokio_cron_scheduler::JobScheduler;
 use tracing::{error, info};
 
+// 推送任务的执行顺序
+const PUSH_KINDS: [PsnDataKind; 8] = [
+    PsnDataKind::Class,
+    PsnDataKind::Lecturer,
+    PsnDataKind::Archive,
+    PsnDataKind::Training,
+    PsnDataKind::ClassSc,
+    PsnDataKind::LecturerSc,
+    PsnDataKind::ArchiveSc,
+    PsnDataKind::TrainingSc,
+];
+
 pub struct TaskSchedulerManager {
     scheduler: JobScheduler,
+    registry: Arc<TaskRegistry>,
+    shutdown: Arc<ShutdownController>,
+    recorder: TaskRunRecorder,
 }
 
 impl TaskSchedulerManager {
-    pub async fn new() -> Result<Self> {
+    pub async fn new(shutdown: Arc<ShutdownController>, recorder: TaskRunRecorder) -> Result<Self> {
         // 初始化任务调度器
         // --- 使用 tokio-cron-scheduler 启动调度器 ---
         let scheduler = JobScheduler::new()
             .await
             .context("Failed to create scheduler")?;
         info!("Scheduler initialized.");
-        Ok(Self { scheduler })
+        let registry = Arc::new(TaskRegistry::new(
+            scheduler.clone(),
+            Arc::clone(&shutdown),
+            recorder.clone(),
+        ));
+        Ok(Self {
+            scheduler,
+            registry,
+            shutdown,
+            recorder,
+        })
     }
 
-    pub async fn start(self) {
+    pub async fn start(&self) {
+        let scheduler = self.scheduler.clone();
         tokio::spawn(async move {
-            if let Err(e) = self.scheduler.start().await {
+            if let Err(e) = scheduler.start().await {
                 error!("Failed to start scheduler in background: {e:?}");
             } else {
                 info!("Scheduler successfully started in background.");
@@ -40,184 +79,385 @@ impl TaskSchedulerManager {
         });
     }
 
+    /// 停止触发新的 cron 任务，已在运行的任务由 ShutdownController::drain 等待
+    pub async fn shutdown(&mut self) {
+        if let Err(e) = self.scheduler.shutdown().await {
+            error!("Failed to shut down scheduler: {e:?}");
+        } else {
+            info!("Scheduler stopped, no new jobs will be triggered.");
+        }
+    }
+
     pub async fn initialize_tasks(
         &self,
         app_context: Arc<AppContext>,
         tasks_config: &TasksConfig,
     ) -> Result<()> {
+        // 启动前校验推送任务的查询拼接，有问题直接启动失败
+        audit_push_queries(&app_context.mysql_pool)
+            .await
+            .context("Push task query audit failed")?;
+        // 启动时 EXPLAIN mc_* 表的刷新语句，不兼容只告警不阻止启动
+        validate_refresh_queries(&app_context.mysql_pool).await;
+        // 启动时、注册推送任务前恢复上次进程中断时发件箱中未完成的推送，失败只告警
+        if app_context.push_outbox.is_some() {
+            let base_task = BasePsnPushTask::new(Arc::clone(&app_context), None, None);
+            if let Err(e) = recover_outbox(&base_task).await {
+                error!("Startup push outbox recovery failed: {e:?}");
+            }
+        }
+
+        // 启动时巡检 ClickHouse 表结构，不一致只告警不阻止启动
+        let schema_check_name = tasks_config
+            .clickhouse_schema_check
+            .as_ref()
+            .map(|config| config.task_name.clone())
+            .unwrap_or_else(|| "ClickhouseSchemaCheckTask".to_string());
+        let schema_check_task = Arc::new(ClickhouseSchemaCheckTask::new(
+            Arc::clone(&app_context.clickhouse_client),
+            Arc::clone(&app_context.clickhouse_tables),
+            schema_check_name,
+        ));
+        if let Err(e) = self
+            .recorder
+            .run(schema_check_task.as_ref(), "startup")
+            .await
+        {
+            error!("Startup ClickHouse schema check failed: {e:?}");
+        }
+        let mut monitored = MonitoredTasks::default();
+        if let Some(config) = &tasks_config.clickhouse_schema_check {
+            self.registry
+                .register(schema_check_task, config.cron_schedule.as_str(), vec![])
+                .await?;
+            monitored.cron_jobs.push(config.task_name.clone());
+        }
+
+        // 启动 binlog 同步前预热网关缓存，失败只告警
+        let warmup_name = tasks_config
+            .gateway_cache_warmup
+            .as_ref()
+            .map(|config| config.task_name.clone())
+            .unwrap_or_else(|| "GatewayCacheWarmupTask".to_string());
+        let warmup_task = Arc::new(GatewayCacheWarmupTask::new(
+            Arc::clone(&app_context),
+            warmup_name,
+        ));
+        if let Err(e) = self.recorder.run(warmup_task.as_ref(), "startup").await {
+            error!("Startup gateway cache warmup failed: {e:?}");
+        }
+        if let Some(config) = &tasks_config.gateway_cache_warmup {
+            self.registry
+                .register(warmup_task, config.cron_schedule.as_str(), vec![])
+                .await?;
+            monitored.cron_jobs.push(config.task_name.clone());
+        }
+
+        // binlog 同步日报：汇总前一天的批次统计
+        if let Some(config) = &tasks_config.binlog_digest {
+            let digest_task = Arc::new(BinlogDigestTask::new(
+                app_context.mysql_pool.clone(),
+                config.task_name.clone(),
+                Arc::clone(&app_context.clock),
+            ));
+            self.registry
+                .register(digest_task, config.cron_schedule.as_str(), vec![])
+                .await?;
+            monitored.cron_jobs.push(config.task_name.clone());
+        }
+
+        // binlog 缺口补拉：重新处理检查点之前未完整处理的区间
+        if let Some(config) = &tasks_config.binlog_gap_replay {
+            let gap_task = Arc::new(BinlogGapReplayTask::new(
+                Arc::clone(&app_context),
+                config.task_name.clone(),
+            ));
+            self.registry
+                .register(gap_task, config.cron_schedule.as_str(), vec![])
+                .await?;
+            monitored.cron_jobs.push(config.task_name.clone());
+        }
+
         // 创建所有推送任务实例
-        let tasks = self.create_push_tasks(&app_context);
+        let tasks = self.create_push_tasks(&app_context, &PUSH_KINDS);
+        monitored.push_tasks = tasks.iter().map(|task| task.name().to_string()).collect();
 
         // 创建复合任务
-        let composite_task = Arc::new(CompositeTask::new(
-            tasks,
-            tasks_config.psn_push.task_name.clone(),
-        ));
+        let composite_task = Arc::new(
+            CompositeTask::new(tasks, tasks_config.psn_push.task_name.clone())
+                .with_status_batching(StatusUpdateCollector::new(&app_context))
+                .with_manifest(app_context.run_manifest_writer.clone()),
+        );
 
-        // 使用辅助函数创建并添加 CompositeTask 的 Cron Job
-        // 添加到调度器
-        self.create_schedule_job(
-            composite_task, // Arc<CompositeTask> 会自动转换为 Arc<dyn TaskExecutor>
-            tasks_config.psn_push.cron_schedule.as_str(),
-            vec![],
-        )
-        .await?;
+        // 将 CompositeTask 的 Cron Job 注册到调度器
+        self.registry
+            .register(
+                composite_task, // Arc<CompositeTask> 会自动转换为 Arc<dyn TaskExecutor>
+                tasks_config.psn_push.cron_schedule.as_str(),
+                vec![],
+            )
+            .await?;
+        monitored
+            .cron_jobs
+            .push(tasks_config.psn_push.task_name.clone());
+
+        // 配置中的其他推送组合任务
+        for group in &tasks_config.composite_groups {
+            let kinds = group_kinds(group)?;
+            info!(
+                "Registering composite group '{}' with kinds {kinds:?}.",
+                group.task_name
+            );
+            let group_task = Arc::new(
+                CompositeTask::new(
+                    self.create_push_tasks(&app_context, &kinds),
+                    group.task_name.clone(),
+                )
+                .with_status_batching(StatusUpdateCollector::new(&app_context))
+                .with_manifest(app_context.run_manifest_writer.clone()),
+            );
+            self.registry
+                .register(group_task, group.cron_schedule.as_str(), vec![])
+                .await?;
+            monitored.cron_jobs.push(group.task_name.clone());
+        }
+
+        // 延迟重试队列的 worker
+        if let Some(retry_queue) = &app_context.mss_retry_queue {
+            spawn_retry_worker(
+                Arc::clone(&app_context),
+                Arc::clone(retry_queue),
+                Arc::clone(&self.shutdown),
+            );
+        }
+        // ClickHouse 重放队列的 worker
+        if let Some(replay_queue) = &app_context.clickhouse_replay {
+            spawn_replay_worker(Arc::clone(replay_queue), Arc::clone(&self.shutdown));
+        }
 
         // --- 连续任务 ---
         // 1. 创建 BinlogSyncTask 实例
         let binlog_task = Arc::new(BinlogSyncTask::new(Arc::clone(&app_context)));
+        binlog_task.spawn_shard_heartbeat(Arc::clone(&self.shutdown));
 
         // 2. 将其作为连续任务启动，而不是 Cron Job
-        self.run_continuous_task(binlog_task).await;
+        // 连续多个空闲周期都没有成功视为停滞
+        monitored.continuous_jobs.push((
+            binlog_task.name().to_string(),
+            app_context.binlog_polling.max_interval * 10,
+        ));
+        self.run_continuous_task(binlog_task, Arc::clone(&app_context.binlog_polling))
+            .await;
+
+        // 根据实际注册的任务生成告警规则
+        monitored.binlog_data_types = app_context.processor_registry.data_types().collect();
+        let _ = app_context
+            .alert_rules
+            .set(generate_alert_rules(&monitored));
+        // 注册完成后开放任务查询与控制接口
+        let _ = app_context.task_registry.set(Arc::clone(&self.registry));
 
         Ok(())
     }
 
+    /// 按 kinds 的顺序创建推送任务，之后是这些数据的删除通知任务
     fn create_push_tasks(
         &self,
         app_context: &Arc<AppContext>,
+        kinds: &[PsnDataKind],
     ) -> Vec<Arc<dyn TaskExecutor + Send + Sync + 'static>> {
-        vec![
-            Arc::new(PsnClassPushTask::new(Arc::clone(app_context), None, None)),
-            Arc::new(PsnLecturerPushTask::new(
-                Arc::clone(app_context),
-                None,
-                None,
-            )),
-            Arc::new(PsnArchivePushTask::new(Arc::clone(app_context), None, None)),
-            Arc::new(PsnTrainingPushTask::new(
-                Arc::clone(app_context),
-                None,
-                None,
-            )),
-            Arc::new(PsnClassScPushTask::new(Arc::clone(app_context), None, None)),
-            Arc::new(PsnLecturerScPushTask::new(
-                Arc::clone(app_context),
-                None,
-                None,
-            )),
-            Arc::new(PsnArchiveScPushTask::new(
-                Arc::clone(app_context),
-                None,
-                None,
-            )),
-            Arc::new(PsnTrainingScPushTask::new(
-                Arc::clone(app_context),
-                None,
-                None,
-            )),
-        ]
-    }
-
-    // 辅助函数：创建并调度一个任务的 Cron Job
-    async fn create_schedule_job(
-        &self,
-        primary_task: Arc<dyn TaskExecutor + Send + Sync + 'static>, // 主任务
-        cron_schedule: &str,
-        dependent_tasks: Vec<Arc<dyn TaskExecutor + Send + Sync + 'static>>, // 依赖任务
-    ) -> Result<()> {
-        let primary_task_clone = Arc::clone(&primary_task);
-        let job_name = primary_task_clone.name().to_string();
-
-        let job = Job::new_async_tz(
-            cron_schedule,
-            chrono_tz::Asia::Shanghai,
-            move |uuid, _scheduler| {
-                let task = Arc::clone(&primary_task_clone);
-                let job_name_future = task.name().to_string();
-                let deps = dependent_tasks.clone();
-
-                Box::pin(async move {
-                    info!("Job '{job_name_future}' ({uuid:?}) is running.");
-                    // --- 执行主任务 ---
-                    if let Err(e) = task.execute().await {
-                        error!("Error executing primary job '{job_name_future}' {uuid:?}: {e:?}");
-                    } else {
-                        info!("Primary job '{job_name_future}' ({uuid:?}) completed successfully.");
-                        // --- 执行依赖任务 ---
-                        Self::execute_dependent_tasks(&job_name_future, deps).await;
-                    }
-                })
-            },
-        )
-        .context(format!("Failed to create cron job '{job_name}'"))?;
-
-        self.scheduler
-            .add(job)
-            .await
-            .context(format!("Failed to add job '{job_name}' to scheduler"))?;
-        info!("Job '{job_name}' added to scheduler.");
-
-        Ok(())
+        let mut tasks: Vec<Arc<dyn TaskExecutor + Send + Sync + 'static>> = kinds
+            .iter()
+            .map(|&kind| push_task(app_context, kind))
+            .collect();
+        // 推送任务排除已取消的培训班，它们的数据只由最后的删除通知推送
+        tasks.extend(
+            kinds
+                .iter()
+                .map(|&kind| delete_push_task(app_context, kind)),
+        );
+        tasks
     }
 
     /// 启动一个在后台持续运行的任务
-    async fn run_continuous_task(&self, task: Arc<BinlogSyncTask>) {
+    async fn run_continuous_task(
+        &self,
+        task: Arc<BinlogSyncTask>,
+        polling: Arc<BinlogPollingConfig>,
+    ) {
         let task_name = task.name().to_string();
         info!("Spawning continuous task '{task_name}' to run in the background.");
+        SCHEDULER_JOB_REGISTERED
+            .with_label_values(&[task_name.as_str()])
+            .set(1);
 
+        let shutdown = Arc::clone(&self.shutdown);
         tokio::spawn(async move {
-            let idle_sleep = Duration::from_secs(60); // 空闲时休眠60秒
-            let busy_sleep = Duration::from_secs(1); // 追赶时休眠1秒
-            let error_sleep = Duration::from_secs(10); // 出错时休眠10秒
+            // 根据每个周期的结果调整休眠时间：空闲时逐步放慢，繁忙时加速，出错时退避
+            let mut poll_interval = AdaptivePollInterval::new(polling);
 
             loop {
+                // 周期结束前不推进检查点，关闭时未完成的周期在下次启动后重新处理
+                let Some(guard) = shutdown.track() else {
+                    info!("Shutting down, continuous task '{task_name}' stopped.");
+                    return;
+                };
                 info!("Starting a new cycle for continuous task '{task_name}'.");
 
-                match task.sync_data().await {
-                    Ok(true) => {
-                        // binlog 日志追赶上系统时间后，休眠60s后再执行
-                        info!("System is caught up. Sleeping for {idle_sleep:?}.");
-                        sleep(idle_sleep).await;
-                    }
-                    Ok(false) => {
-                        //  成功后短暂休眠，避免对数据库或API造成过大压力
-                        info!("Continuous task '{task_name}' completed a cycle successfully.");
-                        info!("System is catching up. Sleeping for {busy_sleep:?}.");
-                        sleep(busy_sleep).await;
+                let started = Instant::now();
+                let result = task.sync_data().await;
+                drop(guard);
+                record_task_run(&task_name, started, result.is_ok());
+                if result.is_ok() {
+                    SCHEDULER_JOB_LAST_SUCCESS
+                        .with_label_values(&[task_name.as_str()])
+                        .set(chrono::Utc::now().timestamp());
+                }
+                match result {
+                    Ok(cycle) => {
+                        let next_sleep = poll_interval.on_success(cycle);
+                        info!(
+                            "Continuous task '{task_name}' completed a cycle successfully ({} records, caught up: {}). Sleeping for {next_sleep:?}.",
+                            cycle.records, cycle.caught_up
+                        );
+                        Self::sleep_unless_shutdown(&shutdown, next_sleep).await;
                     }
                     Err(e) => {
+                        let error_sleep = poll_interval.on_error();
                         error!(
-                            "Continuous task '{task_name}' failed: {e:?}. Waiting for 10 seconds before next cycle."
+                            "Continuous task '{task_name}' failed: {e:?}. Waiting for {error_sleep:?} before next cycle."
                         );
                         // 如果任务失败，等待一段时间再重试，避免因连续失败导致CPU空转或频繁攻击下游服务
-                        sleep(error_sleep).await;
+                        Self::sleep_unless_shutdown(&shutdown, error_sleep).await;
                     }
                 }
             }
         });
     }
 
-    async fn execute_dependent_tasks(
-        primary_job_name: &str,
-        deps: Vec<Arc<dyn TaskExecutor + Send + Sync + 'static>>,
-    ) {
-        if deps.is_empty() {
-            info!("No dependent tasks to execute for '{primary_job_name}'.");
-            return;
+    /// 休眠 duration，关闭被触发时提前返回
+    async fn sleep_unless_shutdown(shutdown: &ShutdownController, duration: Duration) {
+        tokio::select! {
+            _ = sleep(duration) => {}
+            _ = shutdown.cancelled() => {}
         }
+    }
+}
 
-        info!(
-            "Starting {} dependent tasks for '{primary_job_name}'.",
-            deps.len()
-        );
-        // --- 遍历并执行所有依赖任务 ---
-        for (i, task) in deps.iter().enumerate() {
-            let task_num = i + 1;
-            info!("Executing dependent task #{task_num} for '{primary_job_name}'.");
-
-            match task.execute().await {
-                Ok(()) => {
-                    info!(
-                        "Dependent task #{task_num} for '{primary_job_name}' completed successfully."
-                    );
-                }
-                Err(e) => {
-                    error!(
-                        "Error executing dependent task #{task_num} for '{primary_job_name}': {e:?}"
-                    );
-                }
-            }
-        }
+fn push_task(
+    app_context: &Arc<AppContext>,
+    kind: PsnDataKind,
+) -> Arc<dyn TaskExecutor + Send + Sync + 'static> {
+    let app_context = Arc::clone(app_context);
+    match kind {
+        PsnDataKind::Class => Arc::new(PsnClassPushTask::new(app_context, None, None)),
+        PsnDataKind::Lecturer => Arc::new(PsnLecturerPushTask::new(app_context, None, None)),
+        PsnDataKind::Archive => Arc::new(PsnArchivePushTask::new(app_context, None, None)),
+        PsnDataKind::Training => Arc::new(PsnTrainingPushTask::new(app_context, None, None)),
+        PsnDataKind::ClassSc => Arc::new(PsnClassScPushTask::new(app_context, None, None)),
+        PsnDataKind::LecturerSc => Arc::new(PsnLecturerScPushTask::new(app_context, None, None)),
+        PsnDataKind::ArchiveSc => Arc::new(PsnArchiveScPushTask::new(app_context, None, None)),
+        PsnDataKind::TrainingSc => Arc::new(PsnTrainingScPushTask::new(app_context, None, None)),
     }
 }
+
+fn delete_push_task(
+    app_context: &Arc<AppContext>,
+    kind: PsnDataKind,
+) -> Arc<dyn TaskExecutor + Send + Sync + 'static> {
+    let app_context = Arc::clone(app_context);
+    match kind {
+        PsnDataKind::Class => Arc::new(PsnDeletePushTask::<PsnClassPushTask>::new(
+            app_context,
+            None,
+            None,
+        )),
+        PsnDataKind::Lecturer => Arc::new(PsnDeletePushTask::<PsnLecturerPushTask>::new(
+            app_context,
+            None,
+            None,
+        )),
+        PsnDataKind::Archive => Arc::new(PsnDeletePushTask::<PsnArchivePushTask>::new(
+            app_context,
+            None,
+            None,
+        )),
+        PsnDataKind::Training => Arc::new(PsnDeletePushTask::<PsnTrainingPushTask>::new(
+            app_context,
+            None,
+            None,
+        )),
+        PsnDataKind::ClassSc => Arc::new(PsnDeletePushTask::<PsnClassScPushTask>::new(
+            app_context,
+            None,
+            None,
+        )),
+        PsnDataKind::LecturerSc => Arc::new(PsnDeletePushTask::<PsnLecturerScPushTask>::new(
+            app_context,
+            None,
+            None,
+        )),
+        PsnDataKind::ArchiveSc => Arc::new(PsnDeletePushTask::<PsnArchiveScPushTask>::new(
+            app_context,
+            None,
+            None,
+        )),
+        PsnDataKind::TrainingSc => Arc::new(PsnDeletePushTask::<PsnTrainingScPushTask>::new(
+            app_context,
+            None,
+            None,
+        )),
+    }
+}
+
+/// 组合任务组包含的数据种类，按 PUSH_KINDS 的顺序。种类名称写错或筛选后为空时启动失败
+fn group_kinds(group: &CompositeGroupConfig) -> Result<Vec<PsnDataKind>> {
+    let mut base_kinds = Vec::with_capacity(group.kinds.len());
+    for name in &group.kinds {
+        let Some(kind) = PsnDataKind::from_name(name).filter(|kind| !kind.is_sc()) else {
+            bail!(
+                "Unknown data kind '{name}' in composite group '{}', expected class, lecturer, training or archive",
+                group.task_name
+            );
+        };
+        base_kinds.push(kind);
+    }
+    let kinds: Vec<PsnDataKind> = PUSH_KINDS
+        .into_iter()
+        .filter(|kind| base_kinds.contains(&kind.base_kind()) && group.region.includes(*kind))
+        .collect();
+    if kinds.is_empty() {
+        bail!("Composite group '{}' has no data kinds", group.task_name);
+    }
+    Ok(kinds)
+}
+
+#[test]
+fn test_group_kinds() {
+    use crate::config::PushRegion;
+
+    let mut group = CompositeGroupConfig {
+        cron_schedule: "0 0 12 * * *".to_string(),
+        task_name: "讲师与档案".to_string(),
+        kinds: vec!["archive".to_string(), "Lecturer".to_string()],
+        region: PushRegion::All,
+    };
+    assert_eq!(
+        group_kinds(&group).unwrap(),
+        vec![
+            PsnDataKind::Lecturer,
+            PsnDataKind::Archive,
+            PsnDataKind::LecturerSc,
+            PsnDataKind::ArchiveSc,
+        ]
+    );
+    group.region = PushRegion::Sc;
+    assert_eq!(
+        group_kinds(&group).unwrap(),
+        vec![PsnDataKind::LecturerSc, PsnDataKind::ArchiveSc]
+    );
+    group.kinds = vec!["class_sc".to_string()];
+    assert!(group_kinds(&group).is_err());
+    group.kinds = vec![];
+    assert!(group_kinds(&group).is_err());
+}
@@ -0,0 +1,155 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use tokio::sync::{Notify, watch};
+use tracing::{info, warn};
+
+/// 进程级的关闭信号与运行中任务计数。
+/// 触发后不再开始新的任务，运行中的任务在检查点停止或自然结束，由 `drain` 等待
+pub struct ShutdownController {
+    sender: watch::Sender<bool>,
+    in_flight: Arc<InFlight>,
+}
+
+#[derive(Default)]
+struct InFlight {
+    count: AtomicUsize,
+    idle: Notify,
+}
+
+/// 运行中任务的登记，drop 时计数减一
+pub struct TaskGuard {
+    in_flight: Arc<InFlight>,
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        if self.in_flight.count.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.in_flight.idle.notify_waiters();
+        }
+    }
+}
+
+impl Default for ShutdownController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShutdownController {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+        Self {
+            sender,
+            in_flight: Arc::new(InFlight::default()),
+        }
+    }
+
+    /// 触发关闭，重复调用无副作用
+    pub fn trigger(&self) {
+        self.sender.send_if_modified(|shutting_down| {
+            let changed = !*shutting_down;
+            *shutting_down = true;
+            changed
+        });
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    /// 等待关闭被触发，用于与休眠等待一起 select
+    pub async fn cancelled(&self) {
+        let mut receiver = self.sender.subscribe();
+        // sender 由 self 持有，wait_for 不会因通道关闭返回错误
+        let _ = receiver.wait_for(|shutting_down| *shutting_down).await;
+    }
+
+    /// 登记一个开始运行的任务；已触发关闭时返回 None，调用方应跳过本次运行
+    pub fn track(&self) -> Option<TaskGuard> {
+        self.in_flight.count.fetch_add(1, Ordering::AcqRel);
+        let guard = TaskGuard {
+            in_flight: Arc::clone(&self.in_flight),
+        };
+        // 先计数再检查，drain 看到计数为零之后不会再有任务开始
+        if self.is_shutting_down() {
+            return None;
+        }
+        Some(guard)
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.count.load(Ordering::Acquire)
+    }
+
+    /// 等待运行中的任务结束，超时返回 false
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        let wait_idle = async {
+            loop {
+                let idle = self.in_flight.idle.notified();
+                if self.in_flight() == 0 {
+                    return;
+                }
+                idle.await;
+            }
+        };
+        match tokio::time::timeout(timeout, wait_idle).await {
+            Ok(()) => true,
+            Err(_) => {
+                warn!(
+                    "Shutdown drain timed out after {timeout:?} with {} tasks still running.",
+                    self.in_flight()
+                );
+                false
+            }
+        }
+    }
+}
+
+/// 等待 SIGINT 或 SIGTERM
+pub async fn wait_for_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for SIGINT: {e:?}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {e:?}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received SIGINT, shutting down."),
+        _ = terminate => info!("Received SIGTERM, shutting down."),
+    }
+}
+
+#[tokio::test]
+async fn test_drain_waits_for_tracked_tasks() {
+    let controller = Arc::new(ShutdownController::new());
+    let guard = controller.track().expect("not shutting down yet");
+    controller.trigger();
+    assert!(controller.track().is_none());
+    assert!(!controller.drain(Duration::from_millis(10)).await);
+
+    let release = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        drop(guard);
+    });
+    assert!(controller.drain(Duration::from_secs(1)).await);
+    release.await.unwrap();
+    controller.cancelled().await;
+}
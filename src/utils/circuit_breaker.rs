@@ -0,0 +1,223 @@
+//! 出站调用的熔断器：连续失败达到阈值后打开，冷却期内直接拒绝调用；
+//! 冷却结束后进入半开，放行少量探测调用，成功则关闭，失败则重新打开
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tracing::{info, warn};
+
+use crate::config::CircuitBreakerConfig;
+use crate::metrics::{CIRCUIT_BREAKER_STATE, CIRCUIT_BREAKER_TRANSITIONS};
+
+/// 熔断器打开期间被拒绝的调用返回的错误
+#[derive(Debug, thiserror::Error)]
+#[error("Circuit breaker for {name} is open, retry after {retry_after:?}")]
+pub struct CircuitOpen {
+    pub name: &'static str,
+    pub retry_after: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+impl CircuitState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CircuitState::Closed => "closed",
+            CircuitState::Open => "open",
+            CircuitState::HalfOpen => "half_open",
+        }
+    }
+
+    fn gauge_value(self) -> i64 {
+        match self {
+            CircuitState::Closed => 0,
+            CircuitState::Open => 1,
+            CircuitState::HalfOpen => 2,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: CircuitState,
+    /// 关闭状态下的连续失败次数
+    failures: u32,
+    /// 打开状态的结束时间
+    open_until: Instant,
+    /// 半开状态下已放行、尚未结束的探测调用
+    probes: u32,
+}
+
+pub struct CircuitBreaker {
+    name: &'static str,
+    config: CircuitBreakerConfig,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(name: &'static str, config: CircuitBreakerConfig) -> Self {
+        CIRCUIT_BREAKER_STATE
+            .with_label_values(&[name])
+            .set(CircuitState::Closed.gauge_value());
+        CircuitBreaker {
+            name,
+            config,
+            inner: Mutex::new(Inner {
+                state: CircuitState::Closed,
+                failures: 0,
+                open_until: Instant::now(),
+                probes: 0,
+            }),
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.lock().state
+    }
+
+    /// 调用前检查，打开状态或半开状态探测已满时返回 `CircuitOpen`
+    pub fn try_acquire(&self) -> Result<(), CircuitOpen> {
+        self.try_acquire_at(Instant::now())
+    }
+
+    /// 调用成功（下游可用）
+    pub fn on_success(&self) {
+        let mut inner = self.lock();
+        match inner.state {
+            CircuitState::Closed => inner.failures = 0,
+            CircuitState::HalfOpen => self.transition(&mut inner, CircuitState::Closed),
+            CircuitState::Open => {}
+        }
+    }
+
+    /// 调用失败（下游不可用）
+    pub fn on_failure(&self) {
+        self.on_failure_at(Instant::now());
+    }
+
+    /// 调用结束但结果不反映下游是否可用（如服务被配置关闭），只释放探测名额
+    pub fn on_ignored(&self) {
+        let mut inner = self.lock();
+        if inner.state == CircuitState::HalfOpen {
+            inner.probes = inner.probes.saturating_sub(1);
+        }
+    }
+
+    fn try_acquire_at(&self, now: Instant) -> Result<(), CircuitOpen> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        let mut inner = self.lock();
+        if inner.state == CircuitState::Open {
+            if now < inner.open_until {
+                return Err(CircuitOpen {
+                    name: self.name,
+                    retry_after: inner.open_until - now,
+                });
+            }
+            self.transition(&mut inner, CircuitState::HalfOpen);
+        }
+        if inner.state == CircuitState::HalfOpen {
+            if inner.probes >= self.config.half_open_max_calls.max(1) {
+                return Err(CircuitOpen {
+                    name: self.name,
+                    retry_after: Duration::ZERO,
+                });
+            }
+            inner.probes += 1;
+        }
+        Ok(())
+    }
+
+    fn on_failure_at(&self, now: Instant) {
+        if !self.config.enabled {
+            return;
+        }
+        let mut inner = self.lock();
+        match inner.state {
+            CircuitState::Closed => {
+                inner.failures += 1;
+                if inner.failures >= self.config.failure_threshold.max(1) {
+                    inner.open_until = now + self.config.cool_down;
+                    self.transition(&mut inner, CircuitState::Open);
+                }
+            }
+            CircuitState::HalfOpen => {
+                inner.open_until = now + self.config.cool_down;
+                self.transition(&mut inner, CircuitState::Open);
+            }
+            CircuitState::Open => {}
+        }
+    }
+
+    fn transition(&self, inner: &mut Inner, to: CircuitState) {
+        let from = inner.state;
+        inner.state = to;
+        inner.failures = 0;
+        inner.probes = 0;
+        match to {
+            CircuitState::Open => warn!(
+                "Circuit breaker for {} opened ({} -> open), rejecting calls for {:?}.",
+                self.name,
+                from.as_str(),
+                self.config.cool_down
+            ),
+            _ => info!(
+                "Circuit breaker for {}: {} -> {}.",
+                self.name,
+                from.as_str(),
+                to.as_str()
+            ),
+        }
+        CIRCUIT_BREAKER_STATE
+            .with_label_values(&[self.name])
+            .set(to.gauge_value());
+        CIRCUIT_BREAKER_TRANSITIONS
+            .with_label_values(&[self.name, to.as_str()])
+            .inc();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[test]
+fn test_circuit_breaker_transitions() {
+    let breaker = CircuitBreaker::new(
+        "test",
+        CircuitBreakerConfig {
+            enabled: true,
+            failure_threshold: 2,
+            cool_down: Duration::from_secs(30),
+            half_open_max_calls: 1,
+        },
+    );
+    let start = Instant::now();
+    assert!(breaker.try_acquire_at(start).is_ok());
+    breaker.on_failure_at(start);
+    assert_eq!(breaker.state(), CircuitState::Closed);
+    breaker.on_failure_at(start);
+    assert_eq!(breaker.state(), CircuitState::Open);
+    assert!(breaker.try_acquire_at(start).is_err());
+
+    // 冷却结束后只放行一个探测调用，探测失败重新打开
+    let later = start + Duration::from_secs(30);
+    assert!(breaker.try_acquire_at(later).is_ok());
+    assert_eq!(breaker.state(), CircuitState::HalfOpen);
+    assert!(breaker.try_acquire_at(later).is_err());
+    breaker.on_failure_at(later);
+    assert_eq!(breaker.state(), CircuitState::Open);
+
+    // 探测成功后关闭
+    let recovered = later + Duration::from_secs(30);
+    assert!(breaker.try_acquire_at(recovered).is_ok());
+    breaker.on_success();
+    assert_eq!(breaker.state(), CircuitState::Closed);
+    assert!(breaker.try_acquire_at(recovered).is_ok());
+}
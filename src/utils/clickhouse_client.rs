@@ -1,14 +1,15 @@
-use anyhow::Result;
-use std::sync::Arc;
-use tracing::{error, info};
+use anyhow::{Result, anyhow};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use tracing::{error, info, warn};
 
-use clickhouse_rs::Pool;
+use clickhouse_rs::{Block, Pool};
 
 use crate::ClickhouseConfig;
 /// 封装 ClickHouse 客户端，支持连接到多个节点和端口。
 pub struct ClickHouseClient {
-    // 存储多个 ClickHouse 客户端实例，每个实例对应一个 host:port 组合
-    clients: Vec<(String, Arc<Pool>)>,
+    // 存储多个 ClickHouse 客户端实例，每个实例对应一个 host:port 组合，close 后为空
+    clients: RwLock<Vec<(String, Arc<Pool>)>>,
 }
 
 impl ClickHouseClient {
@@ -20,12 +21,15 @@ impl ClickHouseClient {
                 let url = format!(
                     "tcp://{user}:{pass}@{host}:{port}/{db}?compression=lz4",
                     user = config.user,
-                    pass = config.password,
+                    pass = config.password.expose(),
                     host = host,
                     port = port,
                     db = config.database,
                 );
-                info!("Initializing ClickHouse client for: {url}");
+                info!(
+                    "Initializing ClickHouse client for: {host}:{port}/{}",
+                    config.database
+                );
                 let ck_pool = Pool::new(url);
                 clients.push((format!("{host}:{port}"), Arc::new(ck_pool)));
             }
@@ -35,46 +39,311 @@ impl ClickHouseClient {
             anyhow::bail!("No ClickHouse hosts or ports configured.");
         }
 
-        Ok(ClickHouseClient { clients })
+        Ok(ClickHouseClient {
+            clients: RwLock::new(clients),
+        })
     }
 
-    /// 在所有配置的 ClickHouse 节点上执行 SQL 查询。
-    /// 这里的实现会尝试在每个客户端上执行查询，如果某个客户端失败，会记录错误但继续尝试其他客户端。
-    pub async fn execute_on_all_nodes(&self, sql: &str) {
-        // 1. Create a vector of futures. Each future represents an async operation.
-        // 创建 Futures: self.clients.iter().map(|(addr, ck_pool)| async move { ... }).collect() 这一步会立即创建出一个 Vec，其中包含了所有节点的查询任务，但这些任务此时都还没有被执行。它们是被称为 "future" 的惰性异步任务。
-        let futures: Vec<_> = self
-            .clients
-            .iter()
-            .map(|(addr, ck_pool)| async move {
-                match ck_pool.get_handle().await {
-                    Ok(mut client) => {
-                        info!("Executing query on ClickHouse node: {addr}");
-                        if let Err(e) = client.execute(sql).await {
-                            error!("Failed to execute query on {addr}: {e:?}");
-                            false
-                        } else {
-                            info!("Query executed successfully on: {addr}");
-                            true
-                        }
-                    }
-                    Err(e) => {
-                        error!("Failed to get connection handle for {addr}: {e:?}");
-                        false
-                    }
-                }
-            })
-            .collect::<Vec<_>>();
-        // 2. Use a library function to await all the futures concurrently.
-        // 并发执行: futures::future::join_all(futures).await 会将这些 future 都提交给 Tokio 运行时并发执行。运行时会同时处理所有任务，当一个任务在等待 I/O（例如网络请求）时，运行时会切换到另一个任务，而不是闲置等待。
-        // 等待所有完成: join_all 会一直等待，直到所有 future 都执行完成并返回结果，然后将所有结果收集到一个 Vec 中。
-        let results: Vec<bool> = futures::future::join_all(futures).await;
-
-        // 3. Check if all results are true.
-        if results.iter().all(|&res| res) {
+    /// 当前的节点与连接池，执行期间持有连接池，不受 close 影响
+    fn nodes(&self) -> Vec<(String, Arc<Pool>)> {
+        self.clients
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// 进程关闭时释放各节点的连接池，正在执行的语句结束后连接随之关闭，之后的调用不再连接任何节点
+    pub fn close(&self) {
+        let closed = std::mem::take(&mut *self.clients.write().unwrap_or_else(|e| e.into_inner()));
+        info!("Closed {} ClickHouse connection pools.", closed.len());
+    }
+
+    /// 在所有配置的 ClickHouse 节点上并发执行 SQL，某个节点失败时记录错误并继续其他节点，
+    /// 返回每个节点的结果，调用方据此知道哪个节点没有执行成功
+    pub async fn execute_on_all_nodes(&self, sql: &str) -> NodeExecution {
+        let results = self.execute_on_each_node(sql).await;
+        for (addr, result) in &results {
+            match result {
+                Ok(()) => info!("Query executed successfully on: {addr}"),
+                Err(e) => error!("Failed to execute query on {addr}: {e:?}"),
+            }
+        }
+        let execution = NodeExecution {
+            statement: sql.to_string(),
+            results,
+        };
+        if execution.all_succeeded() {
             info!("All ClickHouse nodes executed the query successfully.");
         } else {
-            error!("Some ClickHouse nodes failed to execute the query.");
+            error!(
+                "ClickHouse nodes failed to execute the query: {:?}",
+                execution
+                    .failed_nodes()
+                    .map(|(node, _)| node)
+                    .collect::<Vec<_>>()
+            );
         }
+        execution
+    }
+
+    /// 在每个节点上查询表（`database.table`）的列名，返回 节点地址 -> 列名集合。
+    /// 表不存在时返回空集合，节点不可用时返回错误。
+    pub async fn column_names_on_all_nodes(
+        &self,
+        table: &str,
+    ) -> Vec<(String, Result<HashSet<String>>)> {
+        let Some((database, table_name)) = table.split_once('.') else {
+            return self
+                .nodes()
+                .iter()
+                .map(|(addr, _)| {
+                    (
+                        addr.clone(),
+                        Err(anyhow!("Table name must be database.table: {table}")),
+                    )
+                })
+                .collect();
+        };
+        let sql = format!(
+            "SELECT name FROM system.columns WHERE database = '{database}' AND table = '{table_name}'"
+        );
+        let nodes = self.nodes();
+        let futures = nodes.iter().map(|(addr, ck_pool)| {
+            let sql = &sql;
+            async move {
+                let result = async {
+                    let mut client = ck_pool.get_handle().await?;
+                    let block = client.query(sql.as_str()).fetch_all().await?;
+                    let mut names = HashSet::new();
+                    for row in block.rows() {
+                        let name: String = row.get("name")?;
+                        names.insert(name);
+                    }
+                    Ok::<_, anyhow::Error>(names)
+                }
+                .await;
+                (addr.clone(), result)
+            }
+        });
+        futures::future::join_all(futures).await
+    }
+
+    /// 在每个节点上查询 `id_column IN (ids)` 的记录的 trainNotifyMss，返回 节点地址 -> (ID -> 各行的状态)
+    pub async fn notify_flags_on_all_nodes(
+        &self,
+        table: &str,
+        id_column: &str,
+        ids: &[String],
+    ) -> Vec<(String, Result<HashMap<String, Vec<String>>>)> {
+        let ids_for_query = ids
+            .iter()
+            .map(|id| quote_literal(id))
+            .collect::<Vec<String>>()
+            .join(",");
+        let sql = format!(
+            "SELECT toString({id_column}) AS id, ifNull(toString(trainNotifyMss), '') AS flag \
+             FROM {table} WHERE {id_column} IN ({ids_for_query})"
+        );
+        let nodes = self.nodes();
+        let futures = nodes.iter().map(|(addr, ck_pool)| {
+            let sql = &sql;
+            async move {
+                let result = async {
+                    let mut client = ck_pool.get_handle().await?;
+                    let block = client.query(sql.as_str()).fetch_all().await?;
+                    let mut flags: HashMap<String, Vec<String>> = HashMap::new();
+                    for row in block.rows() {
+                        let id: String = row.get("id")?;
+                        let flag: String = row.get("flag")?;
+                        flags.entry(id).or_default().push(flag);
+                    }
+                    Ok::<_, anyhow::Error>(flags)
+                }
+                .await;
+                (addr.clone(), result)
+            }
+        });
+        futures::future::join_all(futures).await
+    }
+
+    /// 逐个节点获取连接并 ping，返回 节点地址 -> 结果
+    pub async fn ping_all_nodes(&self) -> Vec<(String, Result<()>)> {
+        let nodes = self.nodes();
+        let futures = nodes.iter().map(|(addr, ck_pool)| async move {
+            let result = async {
+                let mut client = ck_pool.get_handle().await?;
+                client.ping().await?;
+                Ok::<_, anyhow::Error>(())
+            }
+            .await;
+            (addr.clone(), result)
+        });
+        futures::future::join_all(futures).await
+    }
+
+    /// 在每个节点上执行 SQL，返回 节点地址 -> 结果
+    pub async fn execute_on_each_node(&self, sql: &str) -> Vec<(String, Result<()>)> {
+        let nodes = self.nodes();
+        let futures = nodes.iter().map(|(addr, ck_pool)| async move {
+            let result = async {
+                let mut client = ck_pool.get_handle().await?;
+                client.execute(sql).await?;
+                Ok::<_, anyhow::Error>(())
+            }
+            .await;
+            (addr.clone(), result)
+        });
+        futures::future::join_all(futures).await
+    }
+
+    /// 指定节点是否可用
+    pub async fn ping_node(&self, node: &str) -> Result<()> {
+        let mut client = self.node_pool(node)?.get_handle().await?;
+        client.ping().await?;
+        Ok(())
+    }
+
+    /// 只在指定节点上执行 SQL，用于修复单个节点上不一致的数据
+    pub async fn execute_on_node(&self, node: &str, sql: &str) -> Result<()> {
+        let mut client = self.node_pool(node)?.get_handle().await?;
+        client.execute(sql).await?;
+        Ok(())
+    }
+
+    fn node_pool(&self, node: &str) -> Result<Arc<Pool>> {
+        self.nodes()
+            .into_iter()
+            .find(|(addr, _)| addr == node)
+            .map(|(_, ck_pool)| ck_pool)
+            .ok_or_else(|| anyhow!("Unknown ClickHouse node: {node}"))
+    }
+
+    /// 在每个节点上把 `id_column IN (ids)` 的记录的 trainNotifyMss 设为 status，
+    /// 返回各节点的结果。表名、字段名不合法或 ids 为空时返回错误，不执行
+    pub async fn update_notify_status(
+        &self,
+        table: &str,
+        id_column: &str,
+        status: &str,
+        ids: &[String],
+    ) -> Result<NodeExecution> {
+        let sql = notify_status_update_sql(table, id_column, status, ids)?;
+        Ok(self.execute_on_all_nodes(&sql).await)
+    }
+
+    /// 同 update_notify_status，只在指定节点上执行
+    pub async fn update_notify_status_on_node(
+        &self,
+        node: &str,
+        table: &str,
+        id_column: &str,
+        status: &str,
+        ids: &[String],
+    ) -> Result<()> {
+        let sql = notify_status_update_sql(table, id_column, status, ids)?;
+        self.execute_on_node(node, &sql).await
+    }
+
+    /// 在所有节点上并发写入 block，列名与类型须与表一致。某个节点失败时记录警告并继续其他节点，
+    /// 返回每个节点的结果。表名不合法时返回错误，不写入
+    pub async fn insert_on_all_nodes(&self, table: &str, block: &Block) -> Result<NodeExecution> {
+        check_identifier(table)?;
+        let nodes = self.nodes();
+        let futures = nodes.iter().map(|(addr, ck_pool)| async move {
+            let result = async {
+                let mut client = ck_pool.get_handle().await?;
+                client.insert(table, block).await?;
+                Ok::<_, anyhow::Error>(())
+            }
+            .await;
+            if let Err(e) = &result {
+                warn!("Failed to insert into {table} on {addr}: {e:?}");
+            }
+            (addr.clone(), result)
+        });
+        Ok(NodeExecution {
+            statement: format!("INSERT INTO {table}"),
+            results: futures::future::join_all(futures).await,
+        })
+    }
+}
+
+/// 一条语句在各节点上的执行结果
+#[derive(Debug)]
+pub struct NodeExecution {
+    pub statement: String,
+    /// 节点地址 -> 结果
+    pub results: Vec<(String, Result<()>)>,
+}
+
+impl NodeExecution {
+    pub fn all_succeeded(&self) -> bool {
+        self.results.iter().all(|(_, result)| result.is_ok())
     }
+
+    /// 执行失败的节点及错误
+    pub fn failed_nodes(&self) -> impl Iterator<Item = (&str, &anyhow::Error)> {
+        self.results
+            .iter()
+            .filter_map(|(node, result)| result.as_ref().err().map(|e| (node.as_str(), e)))
+    }
+}
+
+/// 生成 `ALTER TABLE table UPDATE trainNotifyMss = status WHERE id_column IN (ids)`。
+/// 状态与 ID 转义为字符串字面量，表名与字段名只允许字母、数字、下划线和 `.`
+pub fn notify_status_update_sql(
+    table: &str,
+    id_column: &str,
+    status: &str,
+    ids: &[String],
+) -> Result<String> {
+    check_identifier(table)?;
+    check_identifier(id_column)?;
+    if ids.is_empty() {
+        anyhow::bail!("No IDs to update in {table}");
+    }
+    let ids_for_query = ids
+        .iter()
+        .map(|id| quote_literal(id))
+        .collect::<Vec<String>>()
+        .join(",");
+    Ok(format!(
+        "ALTER TABLE {table} UPDATE trainNotifyMss = {} WHERE {id_column} IN ({ids_for_query})",
+        quote_literal(status)
+    ))
+}
+
+fn check_identifier(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.split('.').all(|part| {
+            !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+    if !valid {
+        anyhow::bail!("Invalid ClickHouse identifier: {name:?}");
+    }
+    Ok(())
+}
+
+/// 转义为 ClickHouse 字符串字面量
+pub fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+#[test]
+fn test_quote_literal() {
+    assert_eq!(quote_literal("abc"), "'abc'");
+    assert_eq!(quote_literal("a'b\\c"), "'a\\'b\\\\c'");
+}
+
+#[test]
+fn test_notify_status_update_sql() {
+    let ids = vec!["a".to_string(), "b'); DROP TABLE t; --".to_string()];
+    assert_eq!(
+        notify_status_update_sql("db.t", "trainId", "1", &ids).unwrap(),
+        "ALTER TABLE db.t UPDATE trainNotifyMss = '1' WHERE trainId IN ('a','b\\'); DROP TABLE t; --')"
+    );
+    assert!(notify_status_update_sql("db.t; DROP", "trainId", "1", &ids).is_err());
+    assert!(notify_status_update_sql("db.t", "id OR 1=1", "1", &ids).is_err());
+    assert!(notify_status_update_sql("db..t", "trainId", "1", &ids).is_err());
+    assert!(notify_status_update_sql("db.t", "trainId", "1", &[]).is_err());
 }
@@ -0,0 +1,99 @@
+//! 可注入的时钟。"昨天"、hit_date、binlog 同步窗口等按当前时间计算的逻辑从 AppContext 的时钟取时间，
+//! 测试与补数据时可以替换为固定时间
+
+use std::sync::Mutex;
+
+use chrono::{DateTime, Days, Local, NaiveDate, NaiveDateTime, TimeDelta, Utc};
+
+pub trait Clock: Send + Sync {
+    /// 当前时间
+    fn now(&self) -> DateTime<Utc>;
+
+    /// 当前本地时间
+    fn now_local(&self) -> NaiveDateTime {
+        self.now().with_timezone(&Local).naive_local()
+    }
+
+    /// 本地时间的今天
+    fn today(&self) -> NaiveDate {
+        self.now_local().date()
+    }
+
+    /// 本地时间的昨天，按日期处理的任务默认处理昨天的数据
+    fn yesterday(&self) -> NaiveDate {
+        self.today() - Days::new(1)
+    }
+
+    /// 当前毫秒时间戳，时间戳全球统一不区分时区
+    fn timestamp_ms(&self) -> i64 {
+        self.now().timestamp_millis()
+    }
+}
+
+/// 系统时间
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// 固定时间，只在 set / advance 时变化
+#[derive(Debug)]
+pub struct FixedClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl FixedClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: Mutex::new(now),
+        }
+    }
+
+    /// 以本地时间构造
+    pub fn at_local(now: NaiveDateTime) -> Self {
+        let now = now
+            .and_local_timezone(Local)
+            .earliest()
+            .map_or_else(|| now.and_utc(), |local| local.with_timezone(&Utc));
+        Self::new(now)
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner()) = now;
+    }
+
+    pub fn advance(&self, delta: TimeDelta) {
+        let mut now = self.now.lock().unwrap_or_else(|e| e.into_inner());
+        *now += delta;
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[test]
+fn test_fixed_clock() {
+    let start = NaiveDate::from_ymd_opt(2026, 3, 1)
+        .unwrap()
+        .and_hms_opt(0, 30, 0)
+        .unwrap();
+    let clock = FixedClock::at_local(start);
+    assert_eq!(clock.now_local(), start);
+    assert_eq!(clock.today(), start.date());
+    assert_eq!(
+        clock.yesterday(),
+        NaiveDate::from_ymd_opt(2026, 2, 28).unwrap()
+    );
+
+    let millis = clock.timestamp_ms();
+    clock.advance(TimeDelta::hours(24));
+    assert_eq!(clock.timestamp_ms() - millis, 86_400_000);
+    assert_eq!(clock.yesterday(), start.date());
+}
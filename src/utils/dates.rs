@@ -0,0 +1,321 @@
+//! 按日期推送接口的日期范围解析。支持三种写法：
+//! - 标准日期 `2024-05-01` ~ `2024-05-31`
+//! - 整月 `2024-05` ~ `2024-06`，起始取月初，结束取月末，可与标准日期混用
+//! - 特殊月份 `2024-13-01` ~ `2024-13-05`：月份大于 12 的业务周期（非日历月），
+//!   起止必须为同一年同一特殊月，按日号展开，结果原样保留特殊月份
+
+use std::fmt;
+
+use chrono::{Datelike, NaiveDate};
+
+use crate::messages::Message;
+use crate::utils::timefmt;
+
+/// 特殊日期中日号的上限
+const SPECIAL_MAX_DAY: u32 = 31;
+
+/// 错误信息来自 messages 目录，带固定编码
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DateRangeError {
+    InvalidFormat(String),
+    BeginAfterEnd { begin: String, end: String },
+    SpecialMonthMismatch { begin: String, end: String },
+    MixedSpecialMonth { begin: String, end: String },
+}
+
+impl DateRangeError {
+    pub fn message(&self) -> Message {
+        match self.clone() {
+            DateRangeError::InvalidFormat(value) => Message::DateInvalidFormat { value },
+            DateRangeError::BeginAfterEnd { begin, end } => {
+                Message::DateBeginAfterEnd { begin, end }
+            }
+            DateRangeError::SpecialMonthMismatch { begin, end } => {
+                Message::DateSpecialMonthMismatch { begin, end }
+            }
+            DateRangeError::MixedSpecialMonth { begin, end } => {
+                Message::DateMixedSpecialMonth { begin, end }
+            }
+        }
+    }
+}
+
+impl fmt::Display for DateRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.message().fmt(f)
+    }
+}
+
+impl std::error::Error for DateRangeError {}
+
+// 单个日期字符串解析后的形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DateBound {
+    Day(NaiveDate),
+    Month { year: i32, month: u32 },
+    Special { year: i32, month: u32, day: u32 },
+}
+
+impl DateBound {
+    fn parse(s: &str) -> Result<Self, DateRangeError> {
+        let invalid = || DateRangeError::InvalidFormat(s.to_string());
+        if let Ok(date) = timefmt::parse_business_date(s) {
+            return Ok(DateBound::Day(date));
+        }
+        let parts: Vec<&str> = s.split('-').collect();
+        let number = |part: &str, len: usize| -> Result<u32, DateRangeError> {
+            if part.len() != len || !part.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            part.parse().map_err(|_| invalid())
+        };
+        match parts.as_slice() {
+            [year, month] => {
+                let (year, month) = (number(year, 4)? as i32, number(month, 2)?);
+                if !(1..=12).contains(&month) {
+                    return Err(invalid());
+                }
+                Ok(DateBound::Month { year, month })
+            }
+            [year, month, day] => {
+                let (year, month, day) =
+                    (number(year, 4)? as i32, number(month, 2)?, number(day, 2)?);
+                // 月份 1..=12 时 parse_business_date 已失败，说明日期不存在
+                if month <= 12 || !(1..=SPECIAL_MAX_DAY).contains(&day) {
+                    return Err(invalid());
+                }
+                Ok(DateBound::Special { year, month, day })
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    fn first_day(self) -> Option<NaiveDate> {
+        match self {
+            DateBound::Day(date) => Some(date),
+            DateBound::Month { year, month } => NaiveDate::from_ymd_opt(year, month, 1),
+            DateBound::Special { .. } => None,
+        }
+    }
+
+    fn last_day(self) -> Option<NaiveDate> {
+        match self {
+            DateBound::Day(date) => Some(date),
+            DateBound::Month { year, month } => {
+                let (next_year, next_month) = if month == 12 {
+                    (year + 1, 1)
+                } else {
+                    (year, month + 1)
+                };
+                NaiveDate::from_ymd_opt(next_year, next_month, 1)?.pred_opt()
+            }
+            DateBound::Special { .. } => None,
+        }
+    }
+}
+
+/// 解析起止日期（均包含），返回按日升序的业务日期列表
+pub fn parse_date_range(begin: &str, end: &str) -> Result<Vec<String>, DateRangeError> {
+    let begin_bound = DateBound::parse(begin)?;
+    let end_bound = DateBound::parse(end)?;
+    let begin_after_end = || DateRangeError::BeginAfterEnd {
+        begin: begin.to_string(),
+        end: end.to_string(),
+    };
+
+    match (begin_bound, end_bound) {
+        (
+            DateBound::Special {
+                year,
+                month,
+                day: begin_day,
+            },
+            DateBound::Special {
+                year: end_year,
+                month: end_month,
+                day: end_day,
+            },
+        ) => {
+            if (year, month) != (end_year, end_month) {
+                return Err(DateRangeError::SpecialMonthMismatch {
+                    begin: begin.to_string(),
+                    end: end.to_string(),
+                });
+            }
+            if begin_day > end_day {
+                return Err(begin_after_end());
+            }
+            Ok((begin_day..=end_day)
+                .map(|day| format!("{year:04}-{month:02}-{day:02}"))
+                .collect())
+        }
+        (DateBound::Special { .. }, _) | (_, DateBound::Special { .. }) => {
+            Err(DateRangeError::MixedSpecialMonth {
+                begin: begin.to_string(),
+                end: end.to_string(),
+            })
+        }
+        _ => {
+            let invalid = || DateRangeError::InvalidFormat(format!("{begin} ~ {end}"));
+            let first = begin_bound.first_day().ok_or_else(invalid)?;
+            let last = end_bound.last_day().ok_or_else(invalid)?;
+            if first > last {
+                return Err(begin_after_end());
+            }
+            Ok(first
+                .iter_days()
+                .take_while(|&d| d <= last)
+                .map(timefmt::business_date)
+                .collect())
+        }
+    }
+}
+
+#[test]
+fn test_parse_date_range_formats() {
+    assert_eq!(
+        parse_date_range("2024-02-28", "2024-03-01").unwrap(),
+        ["2024-02-28", "2024-02-29", "2024-03-01"]
+    );
+    assert_eq!(parse_date_range("2024-05", "2024-05").unwrap().len(), 31);
+    assert_eq!(
+        parse_date_range("2024-12", "2025-01-02")
+            .unwrap()
+            .last()
+            .unwrap(),
+        "2025-01-02"
+    );
+    assert_eq!(
+        parse_date_range("2024-13-01", "2024-13-03").unwrap(),
+        ["2024-13-01", "2024-13-02", "2024-13-03"]
+    );
+
+    assert!(matches!(
+        parse_date_range("2024-05-02", "2024-05-01"),
+        Err(DateRangeError::BeginAfterEnd { .. })
+    ));
+    assert!(matches!(
+        parse_date_range("2024-13-01", "2024-14-03"),
+        Err(DateRangeError::SpecialMonthMismatch { .. })
+    ));
+    assert!(matches!(
+        parse_date_range("2024-13-01", "2024-05-03"),
+        Err(DateRangeError::MixedSpecialMonth { .. })
+    ));
+    for invalid in [
+        "",
+        "2024",
+        "2024-5",
+        "2024-00",
+        "2024-02-30",
+        "2024-13-32",
+        "2024-13-00",
+        "abcd-01-01",
+        "2024-01-01-01",
+    ] {
+        assert_eq!(
+            parse_date_range(invalid, "2024-12-31"),
+            Err(DateRangeError::InvalidFormat(invalid.to_string())),
+            "{invalid}"
+        );
+    }
+}
+
+#[test]
+fn test_parse_date_range_properties() {
+    // 对一段日期内的所有起止组合检查：结果连续、升序、首尾与输入一致、长度等于天数
+    let start = NaiveDate::from_ymd_opt(2023, 12, 20).unwrap();
+    let days: Vec<NaiveDate> = start.iter_days().take(80).collect();
+    for (i, &begin) in days.iter().enumerate() {
+        for &end in &days[i..] {
+            let dates =
+                parse_date_range(&timefmt::business_date(begin), &timefmt::business_date(end))
+                    .unwrap();
+            assert_eq!(dates.len() as i64, (end - begin).num_days() + 1);
+            assert_eq!(dates.first().unwrap(), &timefmt::business_date(begin));
+            assert_eq!(dates.last().unwrap(), &timefmt::business_date(end));
+            assert!(dates.windows(2).all(|w| w[0] < w[1]));
+        }
+        if let Some(&end) = days[..i].last() {
+            assert!(
+                parse_date_range(&timefmt::business_date(begin), &timefmt::business_date(end))
+                    .is_err()
+            );
+        }
+    }
+
+    // 整月范围覆盖月份内的所有天，且只包含这些月份
+    for year in [2023, 2024, 2100] {
+        for month in 1..=12 {
+            let month_str = format!("{year}-{month:02}");
+            let dates = parse_date_range(&month_str, &month_str).unwrap();
+            let first = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+            assert!(dates.iter().all(|d| d.starts_with(&month_str)));
+            assert_eq!(
+                dates.len(),
+                first.iter_days().take_while(|d| d.month() == month).count()
+            );
+        }
+    }
+
+    // 特殊月份按日号展开
+    for begin_day in 1..=SPECIAL_MAX_DAY {
+        for end_day in begin_day..=SPECIAL_MAX_DAY {
+            let dates = parse_date_range(
+                &format!("2024-13-{begin_day:02}"),
+                &format!("2024-13-{end_day:02}"),
+            )
+            .unwrap();
+            assert_eq!(dates.len() as u32, end_day - begin_day + 1);
+        }
+    }
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    // 任意输入都不 panic，失败时返回带编码的错误
+    #[test]
+    fn test_parse_date_range_arbitrary_input(begin in "\\PC{0,12}", end in "\\PC{0,12}") {
+        if let Err(e) = parse_date_range(&begin, &end) {
+            proptest::prop_assert!(e.to_string().starts_with("DATE_"));
+        }
+    }
+
+    // 随机起止日期：begin <= end 时结果连续、升序、首尾与输入一致；否则返回 BeginAfterEnd
+    #[test]
+    fn test_parse_date_range_random_days(begin in 0i64..80_000, end in 0i64..80_000) {
+        let epoch = NaiveDate::from_ymd_opt(1900, 1, 1).unwrap();
+        let begin = epoch + chrono::Days::new(begin as u64);
+        let end = epoch + chrono::Days::new(end as u64);
+        let result = parse_date_range(&timefmt::business_date(begin), &timefmt::business_date(end));
+        if begin > end {
+            proptest::prop_assert!(
+                matches!(result, Err(DateRangeError::BeginAfterEnd { .. })),
+                "{:?}",
+                result
+            );
+        } else {
+            let dates = result.unwrap();
+            proptest::prop_assert_eq!(dates.len() as i64, (end - begin).num_days() + 1);
+            proptest::prop_assert_eq!(dates.first().unwrap(), &timefmt::business_date(begin));
+            proptest::prop_assert_eq!(dates.last().unwrap(), &timefmt::business_date(end));
+            proptest::prop_assert!(dates.windows(2).all(|w| w[0] < w[1]));
+        }
+    }
+
+    // 随机整月范围：首日为起始月月初，末日为结束月月末
+    #[test]
+    fn test_parse_date_range_random_months(year in 1900i32..2200, begin_month in 1u32..=12, span in 0u32..36) {
+        let end_index = begin_month - 1 + span;
+        let (end_year, end_month) = (year + (end_index / 12) as i32, end_index % 12 + 1);
+        let dates = parse_date_range(
+            &format!("{year}-{begin_month:02}"),
+            &format!("{end_year}-{end_month:02}"),
+        )
+        .unwrap();
+        proptest::prop_assert_eq!(dates.first().unwrap(), &format!("{year}-{begin_month:02}-01"));
+        let last = NaiveDate::parse_from_str(dates.last().unwrap(), "%Y-%m-%d").unwrap();
+        proptest::prop_assert_eq!((last.year(), last.month()), (end_year, end_month));
+        proptest::prop_assert_ne!(last.succ_opt().unwrap().month(), end_month);
+    }
+}
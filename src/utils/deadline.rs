@@ -0,0 +1,88 @@
+use std::future::Future;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+tokio::task_local! {
+    static CYCLE_DEADLINE: CycleDeadline;
+}
+
+/// 超过周期截止时间后不再发起的调用返回该错误
+#[derive(Debug, thiserror::Error)]
+#[error("Cycle deadline exceeded")]
+pub struct DeadlineExceeded;
+
+/// 一个同步周期的截止时间，通过 `with_cycle_deadline` 传递给周期内的处理器、网关调用与数据库写入
+#[derive(Debug, Clone, Copy)]
+pub struct CycleDeadline {
+    at: Instant,
+}
+
+impl CycleDeadline {
+    pub fn after(budget: Duration) -> Self {
+        Self {
+            at: Instant::now() + budget,
+        }
+    }
+
+    pub fn is_exceeded(&self) -> bool {
+        Instant::now() >= self.at
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.at.saturating_duration_since(Instant::now())
+    }
+}
+
+/// 在截止时间范围内执行 fut。同一个 tokio 任务内（包括 join_all 的子 future）都可见
+pub async fn with_cycle_deadline<F: Future>(deadline: CycleDeadline, fut: F) -> F::Output {
+    CYCLE_DEADLINE.scope(deadline, fut).await
+}
+
+/// 当前周期的截止时间，不在 `with_cycle_deadline` 范围内时为 None
+pub fn current_deadline() -> Option<CycleDeadline> {
+    CYCLE_DEADLINE.try_with(|deadline| *deadline).ok()
+}
+
+/// 当前周期是否已超过截止时间，没有截止时间时总是 false
+pub fn deadline_exceeded() -> bool {
+    current_deadline().is_some_and(|deadline| deadline.is_exceeded())
+}
+
+/// 已超过截止时间时返回错误，用于发起调用之前的检查
+pub fn check_deadline() -> Result<(), DeadlineExceeded> {
+    if deadline_exceeded() {
+        return Err(DeadlineExceeded);
+    }
+    Ok(())
+}
+
+/// 在剩余时间内等待 fut，超时返回 DeadlineExceeded；没有截止时间时直接等待
+pub async fn within_deadline<F: Future>(fut: F) -> Result<F::Output, DeadlineExceeded> {
+    match current_deadline() {
+        Some(deadline) => tokio::time::timeout_at(deadline.at, fut)
+            .await
+            .map_err(|_| DeadlineExceeded),
+        None => Ok(fut.await),
+    }
+}
+
+#[tokio::test]
+async fn test_cycle_deadline_scope() {
+    assert!(current_deadline().is_none());
+    assert!(check_deadline().is_ok());
+
+    with_cycle_deadline(CycleDeadline::after(Duration::ZERO), async {
+        assert!(deadline_exceeded());
+        assert!(check_deadline().is_err());
+        let pending = within_deadline(std::future::pending::<()>()).await;
+        assert!(pending.is_err());
+    })
+    .await;
+
+    with_cycle_deadline(CycleDeadline::after(Duration::from_secs(60)), async {
+        assert!(!deadline_exceeded());
+        assert_eq!(within_deadline(async { 1 }).await.unwrap(), 1);
+    })
+    .await;
+}
@@ -0,0 +1,160 @@
+use anyhow::{Context, Result};
+use serde::{Serialize, de::DeserializeOwned};
+use std::time::Duration;
+use tracing::warn;
+
+use crate::metrics::GATEWAY_CACHE_REQUESTS;
+use crate::utils::redis::{RedisMgr, del_kv, get_kv, set_kv, set_kv_nx};
+
+const KEY_PREFIX: &str = "gateway_cache";
+
+/// 网关服务响应的 Redis 缓存，键为 gateway_cache:{service}:{key}，值为 JSON。
+/// Redis 不可用时只告警并回落到网关，不影响调用结果
+pub struct GatewayCache {
+    redis_mgr: RedisMgr,
+    ttl: Duration,
+    /// org/user 按 cid 查询的过期时间，None 表示不缓存这类查询
+    lookup_ttl: Option<Duration>,
+}
+
+impl GatewayCache {
+    pub fn new(redis_mgr: RedisMgr, ttl: Duration) -> Self {
+        Self {
+            redis_mgr,
+            ttl,
+            lookup_ttl: None,
+        }
+    }
+
+    /// 同时缓存 org.loadbyid / user.loadbyid 等按 cid 的查询
+    pub fn with_lookups(mut self, lookup_ttl: Duration) -> Self {
+        self.lookup_ttl = Some(lookup_ttl);
+        self
+    }
+
+    pub fn lookups_enabled(&self) -> bool {
+        self.lookup_ttl.is_some()
+    }
+
+    fn key(service: &str, key: &str) -> String {
+        format!("{KEY_PREFIX}:{service}:{key}")
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, service: &str, key: &str) -> Option<T> {
+        let cached = get_kv(&self.redis_mgr, &Self::key(service, key))
+            .await
+            .and_then(|value| {
+                value
+                    .map(|json| serde_json::from_str::<T>(&json))
+                    .transpose()
+                    .context("failed to decode cached gateway reply")
+            });
+        let (result, value) = match cached {
+            Ok(Some(value)) => ("hit", Some(value)),
+            Ok(None) => ("miss", None),
+            Err(e) => {
+                warn!("Gateway cache read failed for {service} {key}: {e:?}");
+                ("error", None)
+            }
+        };
+        GATEWAY_CACHE_REQUESTS
+            .with_label_values(&[service, result])
+            .inc();
+        value
+    }
+
+    pub async fn put<T: Serialize>(&self, service: &str, key: &str, value: &T) {
+        if let Err(e) = self.put_with_ttl(service, key, value, self.ttl).await {
+            warn!("Gateway cache write failed for {service} {key}: {e:?}");
+        }
+    }
+
+    /// 写入按 cid 查询的结果，使用 lookup_ttl，未启用查询缓存时不写入
+    pub async fn put_lookup<T: Serialize>(&self, service: &str, key: &str, value: &T) {
+        let Some(ttl) = self.lookup_ttl else {
+            return;
+        };
+        if let Err(e) = self.put_with_ttl(service, key, value, ttl).await {
+            warn!("Gateway cache write failed for {service} {key}: {e:?}");
+        }
+    }
+
+    /// 预热时写入本地表中 age 之前保存的结果：只在条目不存在时写入，不覆盖网关调用回填的新值，
+    /// 过期时间扣除 age，条目不会比直接调用网关回填的更旧。已超过 ttl 或条目已存在时返回 false
+    pub async fn try_put_aged<T: Serialize>(
+        &self,
+        service: &str,
+        key: &str,
+        value: &T,
+        age: Duration,
+    ) -> Result<bool> {
+        let Some(ttl) = remaining_ttl(self.ttl, age) else {
+            return Ok(false);
+        };
+        let json = serde_json::to_string(value).context("failed to encode gateway reply")?;
+        set_kv_nx(
+            &self.redis_mgr,
+            &Self::key(service, key),
+            &json,
+            ttl.as_secs(),
+        )
+        .await
+    }
+
+    /// 删除缓存条目，如实体被删除后不再返回旧值
+    pub async fn invalidate(&self, service: &str, key: &str) {
+        let result = del_kv(&self.redis_mgr, &Self::key(service, key)).await;
+        let label = match result {
+            Ok(_) => "invalidated",
+            Err(e) => {
+                warn!("Gateway cache invalidation failed for {service} {key}: {e:?}");
+                "invalidate_error"
+            }
+        };
+        GATEWAY_CACHE_REQUESTS
+            .with_label_values(&[service, label])
+            .inc();
+    }
+
+    async fn put_with_ttl<T: Serialize>(
+        &self,
+        service: &str,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> Result<()> {
+        let json = serde_json::to_string(value).context("failed to encode gateway reply")?;
+        set_kv(
+            &self.redis_mgr,
+            &Self::key(service, key),
+            &json,
+            Some(ttl.as_secs().max(1)),
+        )
+        .await
+    }
+}
+
+// 保存了 age 的结果还能缓存多久，不足一秒时不缓存
+fn remaining_ttl(ttl: Duration, age: Duration) -> Option<Duration> {
+    ttl.checked_sub(age)
+        .filter(|remaining| remaining.as_secs() >= 1)
+}
+
+#[test]
+fn test_remaining_ttl() {
+    let ttl = Duration::from_secs(600);
+    assert_eq!(
+        remaining_ttl(ttl, Duration::from_secs(60)),
+        Some(Duration::from_secs(540))
+    );
+    assert_eq!(remaining_ttl(ttl, Duration::from_millis(599_500)), None);
+    assert_eq!(remaining_ttl(ttl, Duration::from_secs(900)), None);
+}
+
+#[test]
+fn test_gateway_cache_key() {
+    assert_eq!(
+        GatewayCache::key("mss.organization.translate", "10001"),
+        "gateway_cache:mss.organization.translate:10001"
+    );
+}
@@ -1,11 +1,23 @@
-use anyhow::{anyhow, Context, Ok, Result};
-use chrono::Utc;
-use reqwest::Client;
+use anyhow::{Context, Result, anyhow};
+use std::net::SocketAddr;
 use std::sync::Arc;
-use tracing::{error, info};
+use std::time::Instant;
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
-use crate::{config::TelecomConfig, schedule::binlog_sync::ResultSet};
+use crate::{
+    config::{ColumnPolicy, GatewayServiceConfig, GatewayTarget, TelecomConfig},
+    metrics::GATEWAY_CALL_DURATION,
+    schedule::binlog_sync::ResultSet,
+};
+
+use super::circuit_breaker::{CircuitBreaker, CircuitOpen, CircuitState};
+use super::deadline::{DeadlineExceeded, check_deadline, within_deadline};
+use super::gateway_cache::GatewayCache;
+use super::http_client::InstrumentedClient;
+use super::rate_limiter::RateLimiter;
+use super::retry::{RetryClass, RetryPolicy};
+use super::timefmt;
 
 // 导入我们定义的请求和响应结构
 use super::gateway_types::{
@@ -13,35 +25,297 @@ use super::gateway_types::{
 };
 use crate::binlog::{
     TelecomMssOrg, TelecomMssOrgMapping, TelecomMssUser, TelecomMssUserMapping, TelecomOrg,
-    TelecomOrgTree, TelecomUser,
+    TelecomOrgTree, TelecomStandardStation, TelecomUser, redact_excluded_columns,
 };
 use crate::schedule::binlog_sync::{DataType, Page};
-use serde_json::{json, Value};
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+
+/// 服务在网关服务目录中被关闭时返回的错误，调用方可据此降级
+#[derive(Debug, thiserror::Error)]
+#[error("Gateway service {service} is disabled")]
+pub struct GatewayServiceDisabled {
+    pub service: String,
+}
+
+/// 网关查询类服务的错误。`Transport` 中保留原始错误，服务关闭、熔断打开与周期截止时间可从中取出
+#[derive(Debug, thiserror::Error)]
+pub enum GatewayError {
+    #[error("Gateway request failed: {0:#}")]
+    Transport(anyhow::Error),
+    #[error("Gateway request timed out: {0}")]
+    Timeout(#[source] reqwest::Error),
+    #[error("Gateway returned HTTP status {code}")]
+    NonSuccessStatus { code: u16 },
+    #[error("Gateway reply message code {code}: {description}")]
+    BadMessageCode { code: i32, description: String },
+    #[error("Failed to deserialize gateway reply: {source}")]
+    Deserialize {
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("Gateway reply contains no data")]
+    NotFound,
+}
+
+impl From<anyhow::Error> for GatewayError {
+    fn from(e: anyhow::Error) -> Self {
+        let e = match e.downcast::<GatewayError>() {
+            Result::Ok(gateway_error) => return gateway_error,
+            Err(e) => e,
+        };
+        match e.downcast::<reqwest::Error>() {
+            Result::Ok(request_error) if request_error.is_timeout() => {
+                GatewayError::Timeout(request_error)
+            }
+            Result::Ok(request_error) => GatewayError::Transport(request_error.into()),
+            Err(e) => GatewayError::Transport(e),
+        }
+    }
+}
+
+/// 网关回复成功的 message_code
+const SUCCESS_MESSAGE_CODE: i32 = 10000;
+
+pub const MSS_ORG_TRANSLATE_SERVICE: &str = "mss.organization.translate";
+pub const MSS_ORG_QUERY_SERVICE: &str = "mss.organization.query";
+pub const ORG_LOAD_SERVICE: &str = "org.loadbyid";
+pub const USER_LOAD_SERVICE: &str = "user.loadbyid";
+/// 网关上的一个服务：服务名与默认的目标应用。服务目录中配置了 target 时以配置为准
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GatewayService {
+    pub name: &'static str,
+    pub target: GatewayTarget,
+}
+
+impl GatewayService {
+    pub const TRAIN_STATUS: Self = Self {
+        name: "bj.bjglinfo.gettrainstatusbyid",
+        target: GatewayTarget::Newtca,
+    };
+    pub const BINLOG_FIND: Self = Self {
+        name: "binlog.find",
+        target: GatewayTarget::Basedata,
+    };
+    pub const ORG_LOAD: Self = Self {
+        name: ORG_LOAD_SERVICE,
+        target: GatewayTarget::Basedata,
+    };
+    pub const ORG_TREE_LOAD: Self = Self {
+        name: "org.tree_loadbyid",
+        target: GatewayTarget::Basedata,
+    };
+    pub const MSS_ORG_TRANSLATE: Self = Self {
+        name: MSS_ORG_TRANSLATE_SERVICE,
+        target: GatewayTarget::Basedata,
+    };
+    pub const MSS_ORG_QUERY: Self = Self {
+        name: MSS_ORG_QUERY_SERVICE,
+        target: GatewayTarget::Mss,
+    };
+    pub const USER_LOAD: Self = Self {
+        name: USER_LOAD_SERVICE,
+        target: GatewayTarget::Basedata,
+    };
+    pub const MSS_USER_TRANSLATE: Self = Self {
+        name: "mss.user.translate",
+        target: GatewayTarget::Mss,
+    };
+    pub const MSS_USER_QUERY_ORDER: Self = Self {
+        name: "mss.user.queryorder",
+        target: GatewayTarget::Basedata,
+    };
+    pub const STANDARD_STATION_LOAD: Self = Self {
+        name: "standardstation.loadbyid",
+        target: GatewayTarget::Basedata,
+    };
+}
+
+// 熔断器名称，用作指标标签
+fn circuit_breaker_name(target: GatewayTarget) -> &'static str {
+    match target {
+        GatewayTarget::Newtca => "gateway.newtca",
+        GatewayTarget::Basedata => "gateway.basedata",
+        GatewayTarget::Mss => "gateway.mss",
+    }
+}
+
+// 只有传输错误、超时与 5xx 说明网关不可用，计入熔断
+fn is_outage(e: &anyhow::Error) -> bool {
+    match e.downcast_ref::<GatewayError>() {
+        Some(GatewayError::NonSuccessStatus { code }) => *code >= 500,
+        Some(GatewayError::Transport(_) | GatewayError::Timeout(_)) => true,
+        Some(_) => false,
+        None => e.downcast_ref::<reqwest::Error>().is_some(),
+    }
+}
+
+/// 网关 binlog.find 单页条数上限
+pub const BINLOG_FIND_MAX_PAGE_SIZE: u32 = 1000;
 
 /// 网关客户端，封装了与电信服务网关的 HTTP 通信。
 pub struct GatewayClient {
-    pub http_client: Client,
+    pub http_client: InstrumentedClient,
     pub telecom_config: Arc<TelecomConfig>,
+    pub cache: Option<GatewayCache>,
+    pub retry_policy: RetryPolicy,
+    pub rate_limiter: Arc<RateLimiter>,
+    /// 每个目标应用一个熔断器，按 `GatewayTarget::ALL` 的顺序，一个应用不可用不影响调用其他应用
+    pub circuit_breakers: [CircuitBreaker; 3],
+    /// d_telecom_user 的落库策略，缓存 user.loadbyid 结果前去掉被排除的字段
+    pub user_policy: Option<ColumnPolicy>,
 }
 
 impl GatewayClient {
-    pub fn new(http_client: Client, telecom_config: Arc<TelecomConfig>) -> Self {
+    pub fn new(
+        http_client: InstrumentedClient,
+        telecom_config: Arc<TelecomConfig>,
+        retry_policy: RetryPolicy,
+    ) -> Self {
+        let circuit_breakers = GatewayTarget::ALL.map(|target| {
+            CircuitBreaker::new(
+                circuit_breaker_name(target),
+                telecom_config.circuit_breaker.clone(),
+            )
+        });
         GatewayClient {
             http_client,
+            circuit_breakers,
             telecom_config,
+            cache: None,
+            retry_policy,
+            rate_limiter: Arc::new(RateLimiter::unlimited("gateway")),
+            user_policy: None,
+        }
+    }
+
+    /// 与其他使用网关的任务共用限流器
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// MSS 组织查询先读 Redis 缓存，未命中时调用网关并回填；
+    /// 缓存启用了查询缓存时 org/user 按 cid 的查询同样处理
+    pub fn with_cache(mut self, cache: GatewayCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// 落库策略排除的用户字段不写入缓存
+    pub fn with_user_policy(mut self, policy: Option<ColumnPolicy>) -> Self {
+        self.user_policy = policy;
+        self
+    }
+
+    fn circuit_breaker(&self, target: GatewayTarget) -> &CircuitBreaker {
+        &self.circuit_breakers[target as usize]
+    }
+
+    /// 各目标应用熔断器的当前状态
+    pub fn circuit_states(&self) -> Vec<(GatewayTarget, CircuitState)> {
+        GatewayTarget::ALL
+            .into_iter()
+            .map(|target| (target, self.circuit_breaker(target).state()))
+            .collect()
+    }
+
+    /// 服务目录中的配置，未配置的服务使用默认值
+    pub fn service_config(&self, service_name: &str) -> GatewayServiceConfig {
+        self.telecom_config
+            .services
+            .get(service_name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// 服务的重试策略，服务目录中配置了 retry 时覆盖次数
+    fn retry_policy(&self, service_config: &GatewayServiceConfig) -> RetryPolicy {
+        match service_config.retry {
+            Some(retry) => self.retry_policy.with_max_attempts(retry.saturating_add(1)),
+            None => self.retry_policy.clone(),
+        }
+    }
+
+    /// 解析网关地址的主机名，用于健康检查
+    pub async fn resolve_gateway_url(&self) -> Result<Vec<SocketAddr>> {
+        let url =
+            reqwest::Url::parse(&self.telecom_config.gateway_url).context("Invalid gateway_url")?;
+        let host = url
+            .host_str()
+            .ok_or_else(|| anyhow!("gateway_url has no host"))?;
+        let port = url
+            .port_or_known_default()
+            .ok_or_else(|| anyhow!("gateway_url has no port"))?;
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+            .await
+            .with_context(|| format!("Failed to resolve gateway host {host}"))?
+            .collect();
+        if addrs.is_empty() {
+            return Err(anyhow!("Gateway host {host} resolved to no addresses"));
         }
+        Ok(addrs)
     }
 
-    /// 调用网关上的特定服务。
+    /// 调用网关上的特定服务，目标应用由 `service.target` 从 telecom_config.targets 中解析。
     /// `payload_data`: 请求体 `body.payload` 数组中的内容。它是一个 `Vec<serde_json::Value>`，允许传递任意 JSON 数据
+    /// 服务在目录中被关闭时返回 `GatewayServiceDisabled`，不发送请求；
+    /// 超过同步周期截止时间时返回 `DeadlineExceeded`；服务所属目标应用的熔断器打开时返回 `CircuitOpen`，不发送请求
     pub async fn invoke_gateway_service(
         &self,
-        service_name: &str,
-        target_app_id: u32,
+        service: &GatewayService,
         payload_data: Vec<Value>, // 传入 payload 数组中的具体数据
     ) -> Result<ServiceMessageReplyBuffer> {
+        let started = Instant::now();
+        let circuit_breaker = self.circuit_breaker(service.target);
+        let result = match circuit_breaker.try_acquire() {
+            Result::Ok(()) => {
+                let result = self.call_gateway_service(service, payload_data).await;
+                match &result {
+                    Result::Ok(_) => circuit_breaker.on_success(),
+                    // 服务被关闭或超过周期截止时间不说明网关不可用
+                    Err(e) if e.is::<GatewayServiceDisabled>() || e.is::<DeadlineExceeded>() => {
+                        circuit_breaker.on_ignored()
+                    }
+                    Err(e) if is_outage(e) => circuit_breaker.on_failure(),
+                    // 4xx、应答无法解析等说明网关可达
+                    Err(_) => circuit_breaker.on_success(),
+                }
+                result
+            }
+            Err(open) => Err(open.into()),
+        };
+        let outcome = match &result {
+            Result::Ok(_) => "success",
+            Err(e) if e.is::<GatewayServiceDisabled>() => "disabled",
+            Err(e) if e.is::<CircuitOpen>() => "circuit_open",
+            Err(_) => "failed",
+        };
+        GATEWAY_CALL_DURATION
+            .with_label_values(&[service.name, outcome])
+            .observe(started.elapsed().as_secs_f64());
+        result
+    }
+
+    async fn call_gateway_service(
+        &self,
+        service: &GatewayService,
+        payload_data: Vec<Value>,
+    ) -> Result<ServiceMessageReplyBuffer> {
+        let service_name = service.name;
+        let service_config = self.service_config(service_name);
+        if !service_config.enabled {
+            return Err(GatewayServiceDisabled {
+                service: service_name.to_string(),
+            }
+            .into());
+        }
+        let target_app_id = service_config
+            .target
+            .unwrap_or_else(|| self.telecom_config.targets.app_id(service.target));
+
         let message_id = Uuid::new_v4().to_string(); // 生成新的 UUID
-        let timestamp = Utc::now().timestamp_millis(); // 获取当前毫秒时间戳
+        let timestamp = timefmt::timestamp_ms(); // 获取当前毫秒时间戳
 
         let destination = Destination {
             source: self.telecom_config.source_app_id,
@@ -68,30 +342,63 @@ impl GatewayClient {
             "Sending ServiceMessage to gateway: {gateway_url}. Service: {service_name}. ServiceMessage: {service_message:?}"
         );
 
-        let response = self
-            .http_client
-            .post(gateway_url) // 发送 POST 请求到网关 URL
-            .json(&service_message) // 自动将 `service_message` 序列化为 JSON 并设置 Content-Type: application/json
-            .send()
-            .await?;
-
-        let status = response.status();
+        let retry_policy = self.retry_policy(&service_config);
+        let mut attempt = 1;
+        let (status, response_text) = loop {
+            check_deadline()?;
+            let mut request = self
+                .http_client
+                .post(gateway_url) // 发送 POST 请求到网关 URL
+                .json(&service_message); // 自动将 `service_message` 序列化为 JSON 并设置 Content-Type: application/json
+            if let Some(timeout) = service_config.timeout {
+                request = request.timeout(timeout);
+            }
+            within_deadline(self.rate_limiter.acquire()).await?;
+            let (class, error) = match within_deadline(self.http_client.send(request)).await? {
+                Result::Ok(response) => {
+                    let status = response.status();
+                    let response_text = self
+                        .http_client
+                        .read_text(response)
+                        .await
+                        .context("Failed to read response body from gateway")?;
+                    if !(status.is_server_error()
+                        && retry_policy.should_retry(attempt, RetryClass::ServerError))
+                    {
+                        break (status, response_text);
+                    }
+                    (
+                        RetryClass::ServerError,
+                        anyhow!("Status={status}, Body={response_text}"),
+                    )
+                }
+                Err(e) => match RetryClass::of_request_error(&e) {
+                    Some(class) if retry_policy.should_retry(attempt, class) => (class, e.into()),
+                    _ => return Err(e.into()),
+                },
+            };
+            let backoff = retry_policy.backoff(attempt);
+            warn!(
+                "Gateway service {service_name} failed ({class:?}), retrying in {backoff:?} ({attempt}/{}): {error}",
+                retry_policy.max_attempts
+            );
+            within_deadline(tokio::time::sleep(backoff)).await?;
+            attempt += 1;
+        };
 
-        let response_text = response
-            .text()
-            .await
-            .context("Failed to read response body from gateway")?;
         if status.is_success() {
             info!("Gateway call successful with status: {status}.");
             // 尝试将 JSON 响应体反序列化为 ServiceMessageReplyBuffer
-            serde_json::from_str(&response_text).context(format!(
-                "Failed to parse successful gateway response JSON from '{response_text}'"
-            ))
+            serde_json::from_str(&response_text).map_err(|source| {
+                error!("Failed to parse successful gateway response JSON from '{response_text}'");
+                GatewayError::Deserialize { source }.into()
+            })
         } else {
             error!("Gateway call failed with status: {status} and body: {response_text}");
-            Err(anyhow!(
-                "Gateway call failed: Status={status}, Body={response_text}",
-            ))
+            Err(GatewayError::NonSuccessStatus {
+                code: status.as_u16(),
+            }
+            .into())
         }
     }
 
@@ -101,12 +408,8 @@ impl GatewayClient {
         training_status: Option<&str>,
     ) -> Result<ServiceMessageReplyBuffer> {
         let payload = vec![json!({training_id: training_status})];
-        self.invoke_gateway_service(
-            "bj.bjglinfo.gettrainstatusbyid",
-            self.telecom_config.targets.newtca,
-            payload,
-        )
-        .await
+        self.invoke_gateway_service(&GatewayService::TRAIN_STATUS, payload)
+            .await
     }
 
     pub async fn binlog_find(
@@ -114,10 +417,8 @@ impl GatewayClient {
         data_type: DataType,
         start_time: i64,
         end_time: i64,
-        current_page: Option<Page>,
-    ) -> Result<Option<ResultSet>> {
-        let page = current_page.unwrap_or_else(|| Page::new(1, 20));
-
+        page: Page,
+    ) -> Result<Option<ResultSet>, GatewayError> {
         let payload: Vec<Value> = vec![
             json!(1),
             json!("telecom"),
@@ -126,340 +427,268 @@ impl GatewayClient {
             json!(end_time),
             json!(page),
         ];
+        self.call_and_parse(&GatewayService::BINLOG_FIND, payload)
+            .await
+    }
 
-        let reply_buffer = self
-            .invoke_gateway_service("binlog.find", self.telecom_config.targets.basedata, payload)
-            .await?;
-
-        if reply_buffer.header.message_code != 10000 {
-            error!(
-                "Invalid message code: {}, description: {}",
-                reply_buffer.header.message_code, reply_buffer.header.description
-            );
-            return Ok(None);
-        }
-
-        // 解析响应
-        match &reply_buffer.body.payload {
-            Value::Object(payload_obj) => {
-                let parse_result =
-                    serde_json::from_value::<ResultSet>(Value::Object(payload_obj.clone()));
-                match parse_result {
-                    Result::Ok(result_set) => Ok(Some(result_set)),
-                    Err(e) => {
-                        error!("Failed to parse ResultSet from response: {e:?}");
-                        Ok(None)
-                    }
-                }
-            }
-            _ => {
-                error!(
-                    "Unexpected response payload format: {:?}",
-                    reply_buffer.body.payload
-                );
-                Ok(None)
-            }
+    pub async fn org_loadbyid(&self, cid: &str) -> Result<TelecomOrg, GatewayError> {
+        let Some(cache) = self.cache.as_ref().filter(|cache| cache.lookups_enabled()) else {
+            return self.fetch_org_loadbyid(cid).await;
+        };
+        if let Some(org) = cache.get(ORG_LOAD_SERVICE, cid).await {
+            return Ok(org);
         }
+        let org = self.fetch_org_loadbyid(cid).await?;
+        cache.put_lookup(ORG_LOAD_SERVICE, cid, &org).await;
+        Ok(org)
     }
 
-    pub async fn org_loadbyid(&self, cid: &str) -> Result<Option<TelecomOrg>> {
-        let payload: Vec<Value> = vec![json!("telecom"), json!(cid)];
-
-        let reply_buffer = self
-            .invoke_gateway_service(
-                "org.loadbyid",
-                self.telecom_config.targets.basedata,
-                payload,
-            )
-            .await?;
-
-        if reply_buffer.header.message_code != 10000 {
-            error!(
-                "Invalid message code: {}, description: {}",
-                reply_buffer.header.message_code, reply_buffer.header.description
-            );
-            return Ok(None);
+    /// 删除以组织 cid 为键的缓存：org.loadbyid、mss.organization.translate，
+    /// 以及缓存的映射所指向的 mss.organization.query。处理组织变更日志前调用
+    pub async fn invalidate_org(&self, cid: &str) {
+        let Some(cache) = &self.cache else {
+            return;
+        };
+        if cache.lookups_enabled() {
+            cache.invalidate(ORG_LOAD_SERVICE, cid).await;
         }
-
-        // 解析响应
-        match &reply_buffer.body.payload {
-            Value::Object(payload_obj) => {
-                let parse_result =
-                    serde_json::from_value::<TelecomOrg>(Value::Object(payload_obj.clone()));
-                match parse_result {
-                    Result::Ok(telecom_org) => Ok(Some(telecom_org)),
-                    Err(e) => {
-                        error!("Failed to parse TelecomOrg from response: {e:?}");
-                        Ok(None)
-                    }
-                }
-            }
-            _ => {
-                error!(
-                    "Unexpected response payload format: {:?}",
-                    reply_buffer.body.payload
-                );
-                Ok(None)
-            }
+        let mapping: Option<TelecomMssOrgMapping> = cache.get(MSS_ORG_TRANSLATE_SERVICE, cid).await;
+        cache.invalidate(MSS_ORG_TRANSLATE_SERVICE, cid).await;
+        if let Some(mss_code) = mapping.and_then(|mapping| mapping.mss_code) {
+            cache.invalidate(MSS_ORG_QUERY_SERVICE, &mss_code).await;
         }
     }
 
-    pub async fn org_tree_loadbyid(&self, cid: &str) -> Result<Option<TelecomOrgTree>> {
+    async fn fetch_org_loadbyid(&self, cid: &str) -> Result<TelecomOrg, GatewayError> {
         let payload: Vec<Value> = vec![json!("telecom"), json!(cid)];
+        self.invoke_typed(&GatewayService::ORG_LOAD, payload).await
+    }
 
-        let reply_buffer = self
-            .invoke_gateway_service(
-                "org.tree_loadbyid",
-                self.telecom_config.targets.basedata,
-                payload,
-            )
-            .await?;
+    pub async fn org_tree_loadbyid(&self, cid: &str) -> Result<TelecomOrgTree, GatewayError> {
+        let payload: Vec<Value> = vec![json!("telecom"), json!(cid)];
+        self.invoke_typed(&GatewayService::ORG_TREE_LOAD, payload)
+            .await
+    }
 
-        if reply_buffer.header.message_code != 10000 {
-            error!(
-                "Invalid message code: {}, description: {}",
-                reply_buffer.header.message_code, reply_buffer.header.description
-            );
-            return Ok(None);
+    pub async fn mss_organization_translate(
+        &self,
+        cid: &str,
+    ) -> Result<TelecomMssOrgMapping, GatewayError> {
+        let Some(cache) = &self.cache else {
+            return self.fetch_mss_organization_translate(cid).await;
+        };
+        if let Some(mapping) = cache.get(MSS_ORG_TRANSLATE_SERVICE, cid).await {
+            return Ok(mapping);
         }
-
-        match &reply_buffer.body.payload {
-            Value::Object(payload_obj) => {
-                let parse_result =
-                    serde_json::from_value::<TelecomOrgTree>(Value::Object(payload_obj.clone()));
-                match parse_result {
-                    Result::Ok(telecom_org_tree) => Ok(Some(telecom_org_tree)),
-                    Err(e) => {
-                        error!("Failed to parse TelecomOrgTree from response: {e:?}");
-                        Ok(None)
-                    }
-                }
-            }
-            _ => {
-                error!(
-                    "Unexpected response payload format: {:?}",
-                    reply_buffer.body.payload
-                );
-                Ok(None)
-            }
+        let mapping = self.fetch_mss_organization_translate(cid).await?;
+        // 没有映射的组织不缓存，映射建立后下一次查询即可取到
+        if mapping.mss_code.is_some() {
+            cache.put(MSS_ORG_TRANSLATE_SERVICE, cid, &mapping).await;
         }
+        Ok(mapping)
     }
 
-    pub async fn mss_organization_translate(
+    async fn fetch_mss_organization_translate(
         &self,
         cid: &str,
-    ) -> Result<Option<TelecomMssOrgMapping>> {
+    ) -> Result<TelecomMssOrgMapping, GatewayError> {
         let payload: Vec<Value> = vec![Value::Null, json!(cid)];
+        self.invoke_typed(&GatewayService::MSS_ORG_TRANSLATE, payload)
+            .await
+    }
 
-        let reply_buffer = self
-            .invoke_gateway_service(
-                "mss.organization.translate",
-                self.telecom_config.targets.basedata,
-                payload,
-            )
-            .await?;
-
-        if reply_buffer.header.message_code != 10000 {
-            error!(
-                "Invalid message code: {}, description: {}",
-                reply_buffer.header.message_code, reply_buffer.header.description
-            );
-            return Ok(None);
+    pub async fn mss_organization_query(
+        &self,
+        mss_code: &str,
+    ) -> Result<Vec<TelecomMssOrg>, GatewayError> {
+        let Some(cache) = &self.cache else {
+            return self.fetch_mss_organization_query(mss_code).await;
+        };
+        if let Some(orgs) = cache.get(MSS_ORG_QUERY_SERVICE, mss_code).await {
+            return Ok(orgs);
         }
-
-        match &reply_buffer.body.payload {
-            Value::Object(payload_obj) => {
-                let parse_result = serde_json::from_value::<TelecomMssOrgMapping>(Value::Object(
-                    payload_obj.clone(),
-                ));
-                match parse_result {
-                    Result::Ok(telecom_mss_org_mapping) => Ok(Some(telecom_mss_org_mapping)),
-                    Err(e) => {
-                        error!("Failed to parse TelecomMssOrgMapping from response: {e:?}");
-                        Ok(None)
-                    }
-                }
-            }
-            _ => {
-                error!(
-                    "Unexpected response payload format: {:?}",
-                    reply_buffer.body.payload
-                );
-                Ok(None)
-            }
+        let orgs = self.fetch_mss_organization_query(mss_code).await?;
+        if !orgs.is_empty() {
+            cache.put(MSS_ORG_QUERY_SERVICE, mss_code, &orgs).await;
         }
+        Ok(orgs)
     }
 
-    pub async fn mss_organization_query(
+    async fn fetch_mss_organization_query(
         &self,
         mss_code: &str,
-    ) -> Result<Option<Vec<TelecomMssOrg>>> {
+    ) -> Result<Vec<TelecomMssOrg>, GatewayError> {
         let payload: Vec<Value> = vec![
             json!(vec![json!(mss_code)]), // 嵌套数组
         ];
+        self.invoke_typed(&GatewayService::MSS_ORG_QUERY, payload)
+            .await
+    }
 
-        let reply_buffer = self
-            .invoke_gateway_service(
-                "mss.organization.query",
-                self.telecom_config.targets.mss,
-                payload,
-            )
-            .await?;
-
-        if reply_buffer.header.message_code != 10000 {
-            error!(
-                "Invalid message code: {}, description: {}",
-                reply_buffer.header.message_code, reply_buffer.header.description
-            );
-            return Ok(None);
+    pub async fn user_loadbyid(&self, cid: &str) -> Result<TelecomUser, GatewayError> {
+        let Some(cache) = self.cache.as_ref().filter(|cache| cache.lookups_enabled()) else {
+            return self.fetch_user_loadbyid(cid).await;
+        };
+        if let Some(user) = cache.get(USER_LOAD_SERVICE, cid).await {
+            return Ok(user);
         }
-
-        match &reply_buffer.body.payload {
-            Value::Array(arr) => {
-                let parse_result =
-                    serde_json::from_value::<Vec<TelecomMssOrg>>(Value::Array(arr.clone()));
-                match parse_result {
-                    Result::Ok(vec_telecom_mss_org) => Ok(Some(vec_telecom_mss_org)),
-                    Err(e) => {
-                        error!("Failed to parse TelecomMssOrg from response: {e:?}");
-                        Ok(None)
-                    }
-                }
-            }
-            _ => {
-                error!(
-                    "Unexpected response payload format: {:?}",
-                    reply_buffer.body.payload
-                );
-                Ok(None)
+        let user = self.fetch_user_loadbyid(cid).await?;
+        match (&self.user_policy, serde_json::to_value(&user)) {
+            (Some(policy), Ok(mut cached)) => {
+                redact_excluded_columns(policy, &mut cached);
+                cache.put_lookup(USER_LOAD_SERVICE, cid, &cached).await;
             }
+            _ => cache.put_lookup(USER_LOAD_SERVICE, cid, &user).await,
         }
+        Ok(user)
     }
 
-    pub async fn user_loadbyid(&self, cid: &str) -> Result<Option<TelecomUser>> {
-        let payload: Vec<Value> = vec![json!("telecom"), json!(cid)];
-
-        let reply_buffer = self
-            .invoke_gateway_service(
-                "user.loadbyid",
-                self.telecom_config.targets.basedata,
-                payload,
-            )
-            .await?;
-
-        if reply_buffer.header.message_code != 10000 {
-            error!(
-                "Invalid message code: {}, description: {}",
-                reply_buffer.header.message_code, reply_buffer.header.description
-            );
-            return Ok(None);
-        }
-
-        // 解析响应
-        match &reply_buffer.body.payload {
-            Value::Object(payload_obj) => {
-                let parse_result =
-                    serde_json::from_value::<TelecomUser>(Value::Object(payload_obj.clone()));
-                match parse_result {
-                    Result::Ok(telecom_user) => Ok(Some(telecom_user)),
-                    Err(e) => {
-                        error!("Failed to parse TelecomUser from response: {e:?}");
-                        Ok(None)
-                    }
-                }
-            }
-            _ => {
-                error!(
-                    "Unexpected response payload format: {:?}",
-                    reply_buffer.body.payload
-                );
-                Ok(None)
-            }
+    /// 删除 user.loadbyid 的缓存，处理用户变更日志前调用
+    pub async fn invalidate_user(&self, cid: &str) {
+        if let Some(cache) = self.cache.as_ref().filter(|cache| cache.lookups_enabled()) {
+            cache.invalidate(USER_LOAD_SERVICE, cid).await;
         }
     }
 
-    pub async fn mss_user_translate(&self, cid: &str) -> Result<Option<TelecomMssUserMapping>> {
-        let payload: Vec<Value> = vec![Value::Null, json!(cid)];
-
-        let reply_buffer = self
-            .invoke_gateway_service(
-                "mss.user.translate",
-                self.telecom_config.targets.mss,
-                payload,
-            )
-            .await?;
+    async fn fetch_user_loadbyid(&self, cid: &str) -> Result<TelecomUser, GatewayError> {
+        let payload: Vec<Value> = vec![json!("telecom"), json!(cid)];
+        self.invoke_typed(&GatewayService::USER_LOAD, payload).await
+    }
 
-        if reply_buffer.header.message_code != 10000 {
-            error!(
-                "Invalid message code: {}, description: {}",
-                reply_buffer.header.message_code, reply_buffer.header.description
-            );
-            return Ok(None);
-        }
+    pub async fn standard_station_loadbyid(
+        &self,
+        cid: &str,
+    ) -> Result<TelecomStandardStation, GatewayError> {
+        let payload: Vec<Value> = vec![json!("telecom"), json!(cid)];
+        self.invoke_typed(&GatewayService::STANDARD_STATION_LOAD, payload)
+            .await
+    }
 
-        match &reply_buffer.body.payload {
-            Value::Object(payload_obj) => {
-                let parse_result = serde_json::from_value::<TelecomMssUserMapping>(Value::Object(
-                    payload_obj.clone(),
-                ));
-                match parse_result {
-                    Result::Ok(mss_user_mapping) => Ok(Some(mss_user_mapping)),
-                    Err(e) => {
-                        error!("Failed to parse TelecomMssOrgMapping from response: {e:?}");
-                        Ok(None)
-                    }
-                }
-            }
-            _ => {
-                error!(
-                    "Unexpected response payload format: {:?}",
-                    reply_buffer.body.payload
-                );
-                Ok(None)
-            }
-        }
+    pub async fn mss_user_translate(
+        &self,
+        cid: &str,
+    ) -> Result<TelecomMssUserMapping, GatewayError> {
+        let payload: Vec<Value> = vec![Value::Null, json!(cid)];
+        self.invoke_typed(&GatewayService::MSS_USER_TRANSLATE, payload)
+            .await
     }
 
-    pub async fn mss_user_queryorder(&self, hr_code: &str) -> Result<Option<Vec<TelecomMssUser>>> {
+    pub async fn mss_user_queryorder(
+        &self,
+        hr_code: &str,
+    ) -> Result<Vec<TelecomMssUser>, GatewayError> {
         let payload: Vec<Value> = vec![
             json!(vec![json!(hr_code)]), // 嵌套数组
         ];
+        self.invoke_typed(&GatewayService::MSS_USER_QUERY_ORDER, payload)
+            .await
+    }
 
-        let reply_buffer = self
-            .invoke_gateway_service(
-                "mss.user.queryorder",
-                self.telecom_config.targets.basedata,
-                payload,
-            )
-            .await?;
+    /// 与 `invoke_typed` 相同，payload 为 null 时返回 `None` 而不是 `NotFound`。
+    /// 新增网关服务时在 `GatewayService` 中声明服务名与目标应用，再用它组装 payload 即可
+    pub async fn call_and_parse<T: DeserializeOwned>(
+        &self,
+        service: &GatewayService,
+        payload_data: Vec<Value>,
+    ) -> Result<Option<T>, GatewayError> {
+        match self.invoke_typed(service, payload_data).await {
+            Ok(value) => Ok(Some(value)),
+            Err(GatewayError::NotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
 
-        if reply_buffer.header.message_code != 10000 {
+    /// 调用服务，校验 message_code 并把回复的 payload 反序列化为 `T`
+    async fn invoke_typed<T: DeserializeOwned>(
+        &self,
+        service: &GatewayService,
+        payload_data: Vec<Value>,
+    ) -> Result<T, GatewayError> {
+        let reply_buffer = self.invoke_gateway_service(service, payload_data).await?;
+        parse_reply(reply_buffer).inspect_err(|e| {
             error!(
-                "Invalid message code: {}, description: {}",
-                reply_buffer.header.message_code, reply_buffer.header.description
-            );
-            return Ok(None);
-        }
+                "Gateway service {} returned an unusable reply: {e}",
+                service.name
+            )
+        })
+    }
+}
 
-        match &reply_buffer.body.payload {
-            Value::Array(arr) => {
-                let parse_result =
-                    serde_json::from_value::<Vec<TelecomMssUser>>(Value::Array(arr.clone()));
-                match parse_result {
-                    Result::Ok(vec_mss_user) => Ok(Some(vec_mss_user)),
-                    Err(e) => {
-                        error!("Failed to parse TelecomMssUser from response: {e:?}");
-                        Ok(None)
-                    }
-                }
-            }
-            _ => {
-                error!(
-                    "Unexpected mss_user_queryorder response payload format: {:?}",
-                    reply_buffer.body.payload
-                );
-                Ok(None)
-            }
+/// 校验回复的 message_code 并反序列化 payload，payload 为 null 时返回 `NotFound`
+fn parse_reply<T: DeserializeOwned>(
+    reply_buffer: ServiceMessageReplyBuffer,
+) -> Result<T, GatewayError> {
+    let header = reply_buffer.header;
+    if header.message_code != SUCCESS_MESSAGE_CODE {
+        return Err(GatewayError::BadMessageCode {
+            code: header.message_code,
+            description: header.description,
+        });
+    }
+    match reply_buffer.body.payload {
+        Value::Null => Err(GatewayError::NotFound),
+        payload => {
+            serde_json::from_value(payload).map_err(|source| GatewayError::Deserialize { source })
         }
     }
 }
+
+#[test]
+fn test_parse_reply_distinguishes_errors() {
+    fn reply(message_code: i32, payload: Value) -> ServiceMessageReplyBuffer {
+        serde_json::from_value(json!({
+            "header": {
+                "messageId": "m",
+                "op_code": 1,
+                "timestamp": 0,
+                "destination": {"source": 1, "target": 2, "service": "s", "mode": 0, "sync": true},
+                "message_code": message_code,
+                "description": "denied",
+            },
+            "body": {"payload": payload},
+        }))
+        .unwrap()
+    }
+
+    let codes: Vec<String> = parse_reply(reply(10000, json!(["a", "b"]))).unwrap();
+    assert_eq!(codes, vec!["a".to_string(), "b".to_string()]);
+    assert!(matches!(
+        parse_reply::<Vec<String>>(reply(20001, json!([]))),
+        Err(GatewayError::BadMessageCode { code: 20001, .. })
+    ));
+    assert!(matches!(
+        parse_reply::<Vec<String>>(reply(10000, Value::Null)),
+        Err(GatewayError::NotFound)
+    ));
+    assert!(matches!(
+        parse_reply::<Vec<String>>(reply(10000, json!({"a": 1}))),
+        Err(GatewayError::Deserialize { .. })
+    ));
+
+    let status: GatewayError =
+        anyhow::Error::from(GatewayError::NonSuccessStatus { code: 502 }).into();
+    assert!(matches!(
+        status,
+        GatewayError::NonSuccessStatus { code: 502 }
+    ));
+    let disabled: GatewayError = anyhow::Error::from(GatewayServiceDisabled {
+        service: ORG_LOAD_SERVICE.to_string(),
+    })
+    .into();
+    assert!(matches!(disabled, GatewayError::Transport(e) if e.is::<GatewayServiceDisabled>()));
+}
+
+#[test]
+fn test_outage_classification() {
+    let status = |code| anyhow::Error::from(GatewayError::NonSuccessStatus { code });
+    assert!(is_outage(&status(502)));
+    assert!(!is_outage(&status(404)));
+    assert!(!is_outage(&status(429)));
+    let deserialize = serde_json::from_str::<Value>("{").unwrap_err();
+    assert!(!is_outage(&anyhow::Error::from(
+        GatewayError::Deserialize {
+            source: deserialize,
+        }
+    )));
+    assert!(!is_outage(&anyhow!("Response body exceeds limit")));
+}
@@ -0,0 +1,143 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{Result, anyhow};
+use reqwest::{Client, RequestBuilder, Response};
+use tokio::sync::Mutex;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::metrics::{HTTP_CLIENT_DURATION, HTTP_CLIENT_REQUESTS};
+
+// 连接失败时的最大尝试次数（请求未发出，重试是安全的）
+const MAX_CONNECT_ATTEMPTS: u32 = 3;
+// 连接失败后的退避基数，第 n 次重试等待 n 倍
+const CONNECT_RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// 带观测能力的 HTTP 客户端，每个出站目标（MSS、网关）各持有一个实例。
+/// 统一负责：目标级别的请求间隔限制、连接失败重试、耗时指标和 traceparent 请求头。
+pub struct InstrumentedClient {
+    client: Client,
+    target: &'static str,
+    min_interval: Duration,
+    max_body_size: usize,
+    last_request: Mutex<Option<Instant>>,
+}
+
+impl InstrumentedClient {
+    /// `min_interval` 为同一目标两次请求之间的最小间隔，为 0 时不限制
+    pub fn new(client: Client, target: &'static str, min_interval: Duration) -> Self {
+        InstrumentedClient {
+            client,
+            target,
+            min_interval,
+            max_body_size: usize::MAX,
+            last_request: Mutex::new(None),
+        }
+    }
+
+    /// 限制 `read_text` 读取的响应体大小
+    pub fn with_max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    pub fn target(&self) -> &'static str {
+        self.target
+    }
+
+    pub fn post(&self, url: &str) -> RequestBuilder {
+        self.client.post(url)
+    }
+
+    /// 发送请求。只有连接失败会重试，已发出的请求不会重复发送；
+    /// HTTP 状态码与业务层面的重试仍由调用方判断。
+    pub async fn send(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        // 同一次调用的所有尝试共用一个 trace id
+        let trace_id = Uuid::new_v4().simple().to_string();
+        let mut attempt = 1;
+        while attempt < MAX_CONNECT_ATTEMPTS {
+            // body 为流时无法克隆，只能发送一次
+            let Some(current) = request.try_clone() else {
+                break;
+            };
+            match self.send_once(current, &trace_id).await {
+                Err(e) if e.is_connect() => {
+                    warn!(
+                        "Connect to {} failed (Attempt {attempt}): {e}. Retrying...",
+                        self.target
+                    );
+                    tokio::time::sleep(CONNECT_RETRY_BACKOFF * attempt).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+        self.send_once(request, &trace_id).await
+    }
+
+    /// 按块读取响应体，超过 max_body_size 时返回错误而不是继续缓冲
+    pub async fn read_text(&self, mut response: Response) -> Result<String> {
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > self.max_body_size {
+                return Err(anyhow!(
+                    "Response body from {} exceeds {} bytes",
+                    self.target,
+                    self.max_body_size
+                ));
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+
+    async fn send_once(
+        &self,
+        request: RequestBuilder,
+        trace_id: &str,
+    ) -> reqwest::Result<Response> {
+        self.wait_for_slot().await;
+        let span_id = Uuid::new_v4().simple().to_string();
+        let started_at = Instant::now();
+        let result = request
+            .header(
+                "traceparent",
+                format!("00-{trace_id}-{}-01", &span_id[..16]),
+            )
+            .send()
+            .await;
+        self.observe(&result, started_at.elapsed());
+        result
+    }
+
+    /// 保证同一目标的请求间隔不小于 min_interval
+    async fn wait_for_slot(&self) {
+        if self.min_interval.is_zero() {
+            return;
+        }
+        let mut last_request = self.last_request.lock().await;
+        if let Some(last) = *last_request {
+            let elapsed = last.elapsed();
+            if elapsed < self.min_interval {
+                tokio::time::sleep(self.min_interval - elapsed).await;
+            }
+        }
+        *last_request = Some(Instant::now());
+    }
+
+    fn observe(&self, result: &reqwest::Result<Response>, elapsed: Duration) {
+        let (status, outcome) = match result {
+            Ok(response) if response.status().is_success() => {
+                (response.status().as_u16().to_string(), "ok")
+            }
+            Ok(response) => (response.status().as_u16().to_string(), "http_error"),
+            Err(_) => ("none".to_string(), "transport_error"),
+        };
+        HTTP_CLIENT_DURATION
+            .with_label_values(&[self.target, status.as_str()])
+            .observe(elapsed.as_secs_f64());
+        HTTP_CLIENT_REQUESTS
+            .with_label_values(&[self.target, outcome])
+            .inc();
+    }
+}
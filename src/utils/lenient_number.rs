@@ -0,0 +1,158 @@
+use serde::{Deserialize, Deserializer};
+use serde_json::{Number, Value};
+use tracing::warn;
+
+use crate::metrics::GATEWAY_NUMERIC_COERCIONS;
+
+/// 可以从网关宽松解析的数值类型。超出范围时取最接近的边界值
+pub trait LenientNumber: Sized + Copy {
+    const TYPE: &'static str;
+
+    /// 返回转换结果与发生的修正（clamped / truncated）
+    fn from_i64(value: i64) -> (Self, Option<&'static str>);
+
+    fn from_f64(value: f64) -> (Self, Option<&'static str>);
+}
+
+// as 转换超出范围时取边界值，小数部分截断
+fn integer_fixup(value: f64, min: f64, max: f64) -> Option<&'static str> {
+    if value < min || value > max {
+        Some("clamped")
+    } else if value.fract() != 0.0 {
+        Some("truncated")
+    } else {
+        None
+    }
+}
+
+macro_rules! lenient_narrow_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl LenientNumber for $ty {
+                const TYPE: &'static str = stringify!($ty);
+
+                fn from_i64(value: i64) -> (Self, Option<&'static str>) {
+                    match <$ty>::try_from(value) {
+                        Ok(n) => (n, None),
+                        Err(_) if value < 0 => (<$ty>::MIN, Some("clamped")),
+                        Err(_) => (<$ty>::MAX, Some("clamped")),
+                    }
+                }
+
+                fn from_f64(value: f64) -> (Self, Option<&'static str>) {
+                    let fixup = integer_fixup(value, <$ty>::MIN as f64, <$ty>::MAX as f64);
+                    (value as $ty, fixup)
+                }
+            }
+        )*
+    };
+}
+
+lenient_narrow_integer!(u8, i32);
+
+impl LenientNumber for i64 {
+    const TYPE: &'static str = "i64";
+
+    fn from_i64(value: i64) -> (Self, Option<&'static str>) {
+        (value, None)
+    }
+
+    fn from_f64(value: f64) -> (Self, Option<&'static str>) {
+        let fixup = integer_fixup(value, i64::MIN as f64, i64::MAX as f64);
+        (value as i64, fixup)
+    }
+}
+
+impl LenientNumber for f32 {
+    const TYPE: &'static str = "f32";
+
+    fn from_i64(value: i64) -> (Self, Option<&'static str>) {
+        (value as f32, None)
+    }
+
+    fn from_f64(value: f64) -> (Self, Option<&'static str>) {
+        if value.abs() > f32::MAX as f64 {
+            (f32::MAX.copysign(value as f32), Some("clamped"))
+        } else {
+            (value as f32, None)
+        }
+    }
+}
+
+/// 网关的数值字段有时是字符串或超出范围，宽松解析而不是让整条记录反序列化失败：
+/// 数字字符串按数字解析，超出范围取边界值，无法解析的值记为 None。每次修正都计入指标
+pub fn lenient_number<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: LenientNumber,
+{
+    Ok(coerce(Option::<Value>::deserialize(deserializer)?))
+}
+
+fn coerce<T: LenientNumber>(value: Option<Value>) -> Option<T> {
+    let number = match value {
+        None | Some(Value::Null) => return None,
+        Some(Value::Number(n)) => n,
+        Some(Value::String(s)) => {
+            let trimmed = s.trim();
+            if trimmed.is_empty() {
+                record::<T>("empty");
+                return None;
+            }
+            match trimmed.parse::<Number>() {
+                Ok(n) => {
+                    record::<T>("string");
+                    n
+                }
+                Err(_) => {
+                    warn!("Ignoring non-numeric value {s:?} for {} field", T::TYPE);
+                    record::<T>("invalid");
+                    return None;
+                }
+            }
+        }
+        Some(other) => {
+            warn!("Ignoring non-numeric value {other} for {} field", T::TYPE);
+            record::<T>("invalid");
+            return None;
+        }
+    };
+
+    let (result, fixup) = match number.as_i64() {
+        Some(i) => T::from_i64(i),
+        None => T::from_f64(number.as_f64().unwrap_or_default()),
+    };
+    if let Some(kind) = fixup {
+        warn!("Numeric value {number} is {kind} to fit {} field", T::TYPE);
+        record::<T>(kind);
+    }
+    Some(result)
+}
+
+fn record<T: LenientNumber>(kind: &str) {
+    GATEWAY_NUMERIC_COERCIONS
+        .with_label_values(&[T::TYPE, kind])
+        .inc();
+}
+
+#[test]
+fn test_lenient_number() {
+    use serde_json::json;
+
+    assert_eq!(coerce::<i32>(Some(json!(7))), Some(7));
+    assert_eq!(coerce::<i32>(Some(json!(" 12 "))), Some(12));
+    assert_eq!(coerce::<i32>(Some(json!("abc"))), None);
+    assert_eq!(coerce::<i32>(Some(json!(""))), None);
+    assert_eq!(coerce::<i32>(Some(json!(true))), None);
+    assert_eq!(coerce::<i32>(Some(Value::Null)), None);
+    assert_eq!(
+        coerce::<i32>(Some(json!(3_000_000_000_i64))),
+        Some(i32::MAX)
+    );
+    assert_eq!(coerce::<i32>(Some(json!(2.9))), Some(2));
+    assert_eq!(coerce::<u8>(Some(json!(-1))), Some(0));
+    assert_eq!(coerce::<u8>(Some(json!("300"))), Some(u8::MAX));
+    assert_eq!(coerce::<i64>(Some(json!(u64::MAX))), Some(i64::MAX));
+    assert_eq!(coerce::<f32>(Some(json!("1.5"))), Some(1.5));
+    assert_eq!(coerce::<f32>(Some(json!(1e300))), Some(f32::MAX));
+}
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use prometheus::IntCounter;
+
+use crate::metrics::{LOOKUP_CACHE_ENTRIES, LOOKUP_CACHE_REQUESTS};
+
+/// 字符串到字符串的进程内查找表（省份编码等静态映射），保存完整映射，不做淘汰，
+/// 整体替换通过 `reload`。查找只加读锁并克隆 `Arc<str>`，不分配内存；
+/// 命中与未命中计入 lookup_cache_requests_total
+pub struct LookupCache {
+    name: &'static str,
+    hits: IntCounter,
+    misses: IntCounter,
+    entries: RwLock<HashMap<Arc<str>, Arc<str>>>,
+}
+
+impl LookupCache {
+    /// 以完整的静态映射创建，`name` 为指标中的 cache 标签
+    pub fn from_map(name: &'static str, map: &HashMap<String, String>) -> Self {
+        let cache = LookupCache {
+            name,
+            hits: LOOKUP_CACHE_REQUESTS.with_label_values(&[name, "hit"]),
+            misses: LOOKUP_CACHE_REQUESTS.with_label_values(&[name, "miss"]),
+            entries: RwLock::new(HashMap::new()),
+        };
+        cache.reload(map);
+        cache
+    }
+
+    pub fn get(&self, key: &str) -> Option<Arc<str>> {
+        let value = self
+            .entries
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(key)
+            .cloned();
+        match value {
+            Some(_) => self.hits.inc(),
+            None => self.misses.inc(),
+        }
+        value
+    }
+
+    /// 用新的映射整体替换缓存内容
+    pub fn reload(&self, map: &HashMap<String, String>) {
+        let entries: HashMap<Arc<str>, Arc<str>> = map
+            .iter()
+            .map(|(key, value)| (Arc::from(key.as_str()), Arc::from(value.as_str())))
+            .collect();
+        let len = entries.len();
+        *self.entries.write().unwrap_or_else(|e| e.into_inner()) = entries;
+        LOOKUP_CACHE_ENTRIES
+            .with_label_values(&[self.name])
+            .set(len as i64);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[test]
+fn test_lookup_cache_reload() {
+    let cache = LookupCache::from_map(
+        "test_lookup",
+        &HashMap::from([
+            ("32".to_string(), "江苏".to_string()),
+            ("51".to_string(), "四川".to_string()),
+        ]),
+    );
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.get("32").as_deref(), Some("江苏"));
+    // 命中返回同一份数据，不复制字符串
+    assert!(Arc::ptr_eq(
+        &cache.get("51").unwrap(),
+        &cache.get("51").unwrap()
+    ));
+    assert_eq!(cache.get("11"), None);
+
+    let map = HashMap::from([
+        ("31".to_string(), "上海".to_string()),
+        ("44".to_string(), "广东".to_string()),
+        ("50".to_string(), "重庆".to_string()),
+    ]);
+    cache.reload(&map);
+    assert_eq!(cache.len(), 3);
+    assert_eq!(cache.get("32"), None);
+    assert_eq!(cache.get("50").as_deref(), Some("重庆"));
+}
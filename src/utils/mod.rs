@@ -1,12 +1,29 @@
+pub mod circuit_breaker;
 pub mod clickhouse_client;
+pub mod clock;
+pub mod dates;
+pub mod deadline;
+pub mod gateway_cache;
 pub mod gateway_client;
 pub mod gateway_types;
+pub mod http_client;
+pub mod lenient_number;
+pub mod lookup_cache;
 pub mod mss_client;
+pub mod mss_encoder;
 pub mod mysql_client;
 mod process_error;
+pub mod rate_limiter;
 pub mod redis;
+pub mod retry;
+pub mod timefmt;
 
 pub use clickhouse_client::ClickHouseClient;
+pub use clock::{Clock, SystemClock};
+pub use gateway_cache::GatewayCache;
 pub use gateway_client::GatewayClient;
-pub use mss_client::psn_dos_push;
+pub use http_client::InstrumentedClient;
+pub use lookup_cache::LookupCache;
+pub use mss_client::{psn_dos_push, psn_dos_push_batch};
 pub use process_error::*;
+pub use rate_limiter::{RateLimiter, RateLimiters};
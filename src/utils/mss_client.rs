@@ -1,63 +1,172 @@
+use std::future::Future;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result, anyhow};
-use chrono::Local;
-use reqwest::Client;
-use serde_json::{Value, from_str, json};
+use itertools::Itertools;
+use serde_json::{Value, from_str};
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
-use crate::{ArchivingMssMapper, DynamicPsnData, MssInfoConfig, PushResultParser, RecordMssReply};
+use crate::models::push_result::PushTelemetry;
+use crate::schedule::job_tracker;
+use crate::utils::retry::{RetryClass, RetryPolicy};
+use crate::utils::{InstrumentedClient, RateLimiter, timefmt};
+use crate::{
+    ArchivingMssMapper, DynamicPsnData, MssInfoConfig, PsnRecord, PushResultParser, RecordMssReply,
+};
+
+tokio::task_local! {
+    static THROTTLE_STATS: Arc<ThrottleStats>;
+}
+
+/// MSS 返回需要休息（9019）的次数与累计等待时长
+#[derive(Debug, Default)]
+pub struct ThrottleStats {
+    events: AtomicU32,
+    wait_ms: AtomicU64,
+}
+
+impl ThrottleStats {
+    pub fn events(&self) -> u32 {
+        self.events.load(Ordering::Relaxed)
+    }
+
+    pub fn wait_ms(&self) -> u64 {
+        self.wait_ms.load(Ordering::Relaxed)
+    }
+
+    fn record(&self, wait: Duration) {
+        self.events.fetch_add(1, Ordering::Relaxed);
+        let wait_ms = u64::try_from(wait.as_millis()).unwrap_or(u64::MAX);
+        self.wait_ms.fetch_add(wait_ms, Ordering::Relaxed);
+    }
+}
+
+/// 在 fut 执行期间把 psn_dos_push 遇到的 9019 计入 stats
+pub async fn with_throttle_stats<F: Future>(stats: Arc<ThrottleStats>, fut: F) -> F::Output {
+    THROTTLE_STATS.scope(stats, fut).await
+}
+
+fn record_throttle(wait: Duration) {
+    let _ = THROTTLE_STATS.try_with(|stats| stats.record(wait));
+}
+
+// 按策略等待下一次请求，9019 的等待计入限流统计并标记到作业进度，同时暂停其他任务对 MSS 的请求
+async fn wait_before_retry(
+    retry_policy: &RetryPolicy,
+    rate_limiter: &RateLimiter,
+    attempt: u32,
+    class: RetryClass,
+) {
+    let backoff = retry_policy.backoff(attempt);
+    warn!("MSS request failed ({class:?}), retrying after {backoff:?}...");
+    let throttled = class == RetryClass::Throttled;
+    if throttled {
+        record_throttle(backoff);
+        rate_limiter.pause(backoff);
+        job_tracker::mark_throttled(backoff);
+    }
+    tokio::time::sleep(backoff).await;
+    if throttled {
+        job_tracker::mark_resumed();
+    }
+}
 
 /// 通用的 PSN DOS 推送方法。
 /// 接收所需的所有依赖（HTTP 客户端、配置、数据映射器和解析器）作为参数。
 // 将其设为 pub，以便其他模块可以调用
 pub async fn psn_dos_push(
-    http_client: &Client,                  // 引用类型，避免所有权转移
+    http_client: &InstrumentedClient,      // 引用类型，避免所有权转移
     mss_info_config: Arc<MssInfoConfig>,   // 引用类型
     archiving_mapper: &ArchivingMssMapper, // 引用类型
     push_result_parser: &PushResultParser, // 引用类型
     psn_data: &DynamicPsnData,             // 引用类型
+    retry_policy: &RetryPolicy, // 重试策略，max_attempts 为 1 时遇到 9019 不等待直接返回错误
+    rate_limiter: &RateLimiter, // 所有任务共用的 MSS 限流器
 ) -> Result<()> {
-    const MAX_RETRIES: u32 = 5;
-
-    let dynamic_key_name = psn_data.get_key_name();
+    psn_dos_push_batch(
+        http_client,
+        mss_info_config,
+        archiving_mapper,
+        push_result_parser,
+        &[psn_data],
+        retry_policy,
+        rate_limiter,
+    )
+    .await
+}
 
-    let request_json_data_value = json!({
-        dynamic_key_name: [psn_data]
-    });
+/// 一次请求推送多条记录，按 keyName 分组放入 classData / lecturerData 等数组。
+/// MSS 拒绝时返回的 PushRejection 中 record_errors 列出被拒绝的记录，按记录拆分结果由调用方处理
+pub async fn psn_dos_push_batch(
+    http_client: &InstrumentedClient,
+    mss_info_config: Arc<MssInfoConfig>,
+    archiving_mapper: &ArchivingMssMapper,
+    push_result_parser: &PushResultParser,
+    records: &[&DynamicPsnData],
+    retry_policy: &RetryPolicy,
+    rate_limiter: &RateLimiter,
+) -> Result<()> {
+    let dynamic_key_name = records
+        .iter()
+        .map(|record| record.key_name())
+        .unique()
+        .join(",");
+    let record_count = records.len();
 
     let app_url = &mss_info_config.app_url;
 
-    let request_json_data = serde_json::to_string(&request_json_data_value)
-        .context("Failed to serialize dynamic JSON payload")?;
+    // 传输格式由配置决定，归档与结果解析统一使用 request_json
+    let payload = mss_info_config.encoding.encoder().encode(records)?;
+    let request_json_data = payload.request_json.as_str();
+
+    // 记录本条数据的请求耗时、次数和最后一次的 HTTP 状态码
+    let started_at = Instant::now();
+    let mut attempt_count: u32 = 0;
+    let mut last_http_status: Option<u16> = None;
 
     // 引入一个 Result 来封装循环体内的逻辑，以便统一错误处理
     let result_of_send_loop: Result<String, anyhow::Error> = async {
-        for attempt in 1..=MAX_RETRIES {
+        let mut attempt = 1;
+        loop {
+            attempt_count = attempt;
             info!(
-                "Attempting to send data to {app_url} (Attempt {attempt}), key: {dynamic_key_name}"
+                "Attempting to send {record_count} records to {app_url} (Attempt {attempt}), key: {dynamic_key_name}"
             );
-            // 调用mss接口前先休眠20毫秒
-            tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
-            let request = http_client
+            let mut request = http_client
                 .post(app_url)
                 .header("X-APP-ID", &mss_info_config.app_id)
-                .header("X-APP-KEY", &mss_info_config.app_key)
-                .header("Content-Type", "application/json")
-                .body(request_json_data.clone());
+                .header("X-APP-KEY", mss_info_config.app_key.expose())
+                .header("Content-Type", payload.content_type)
+                .body(payload.body.clone());
+            if let Some(content_encoding) = payload.content_encoding {
+                request = request.header("Content-Encoding", content_encoding);
+            }
 
-            let response = match request.send().await {
+            // 每次请求（含重试）先从共享的限流器取令牌；
+            // 请求间隔、连接重试和耗时指标由 InstrumentedClient 统一处理
+            rate_limiter.acquire().await;
+            let response = match http_client.send(request).await {
                 Ok(r) => r,
                 Err(e) => {
                     // 发送请求失败 (网络不通, DNS 查找失败等)
                     error!("Failed to send HTTP request to {app_url}: {e:?}");
-                    return Err(anyhow!("Failed to send HTTP request to {app_url}: {e:?}"));
+                    match RetryClass::of_request_error(&e) {
+                        Some(class) if retry_policy.should_retry(attempt, class) => {
+                            wait_before_retry(retry_policy, rate_limiter, attempt, class).await;
+                            attempt += 1;
+                            continue;
+                        }
+                        _ => return Err(anyhow!("Failed to send HTTP request to {app_url}: {e:?}")),
+                    }
                 },
             };
 
             let http_status = response.status();
-            let http_body_str = match response.text().await {
+            last_http_status = Some(http_status.as_u16());
+            let http_body_str = match http_client.read_text(response).await {
                 Ok(body) => body,
                 Err(e) => {
                     error!("Failed to read response body for {app_url}: {e:?}"); 
@@ -67,40 +176,57 @@ pub async fn psn_dos_push(
 
             info!("Received response for {app_url} (Attempt {attempt}): Status={http_status}, Body={http_body_str}");
 
-            if http_status.is_success() {
-                if have_rest(&http_body_str) {
-                    warn!("Response indicates 'rest' required. Retrying after 1 minute...");
-                    tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
-                    continue; // 继续循环进行重试
-                } else {
+            let class = if http_status.is_success() {
+                if !have_rest(&http_body_str) {
                     info!("Request to {app_url} successful and no 'rest' required.");
                     return Ok(http_body_str); // 成功并退出重试循环
                 }
+                if !retry_policy.should_retry(attempt, RetryClass::Throttled) {
+                    // 最后一次请求不再等待
+                    warn!("Response indicates 'rest' required and no attempts left.");
+                    record_throttle(Duration::ZERO);
+                    return Err(anyhow!(
+                        "All {attempt} attempts failed for key {dynamic_key_name}"
+                    ));
+                }
+                RetryClass::Throttled
             } else {
                 // HTTP 状态码表示失败
                 error!(
                     "HTTP request to {app_url} failed with status: {http_status}. Body: {http_body_str}");
-                return  Err(anyhow!(
-                    "HTTP request failed with status: {http_status}. Body: {http_body_str}"
-                ));
-            }
+                if !(http_status.is_server_error()
+                    && retry_policy.should_retry(attempt, RetryClass::ServerError))
+                {
+                    return Err(anyhow!(
+                        "HTTP request failed with status: {http_status}. Body: {http_body_str}"
+                    ));
+                }
+                RetryClass::ServerError
+            };
+            wait_before_retry(retry_policy, rate_limiter, attempt, class).await;
+            attempt += 1;
         }
-        Err(anyhow!(
-            "All {MAX_RETRIES} attempts failed for key {dynamic_key_name}"
-        ))
     }
     .await;
 
+    let telemetry = PushTelemetry {
+        duration_ms: started_at.elapsed().as_millis() as i64,
+        http_status: last_http_status,
+        attempt_count,
+        endpoint: app_url.to_string(),
+    };
+
     // 统一的错误处理和记录逻辑
-    let current_time = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let current_time = timefmt::datetime(archiving_mapper.clock().now_local());
 
     match result_of_send_loop {
         Ok(http_body_str) => {
             // 请求成功，记录成功信息
             let record_reply = RecordMssReply {
                 id: Uuid::new_v4().to_string().replace("-", ""),
+                // 报文归档不保存 app_key 明文
                 datas: format!(
-                    "X-APP_ID{}|X-APP-KEY{}|DATA:{}",
+                    "X-APP_ID{}|X-APP-KEY{:?}|DATA:{}",
                     mss_info_config.app_id, mss_info_config.app_key, request_json_data
                 ),
                 send_time: current_time,
@@ -114,11 +240,11 @@ pub async fn psn_dos_push(
 
             // 只有成功时才调用 parser.parse
             let push_result = push_result_parser
-                .parse(&request_json_data, &http_body_str)
+                .parse(request_json_data, &http_body_str, &telemetry)
                 .await;
-            // 根据解析结果判断是否成功
-            if let Err(msg) = push_result {
-                return Err(anyhow::anyhow!(msg));
+            // 根据解析结果判断是否成功，保留 PushRejection 以便调用方按记录获取错误信息
+            if let Err(rejection) = push_result {
+                return Err(anyhow::Error::new(rejection));
             }
             Ok(()) // 主请求和记录都成功
         }
@@ -0,0 +1,138 @@
+use std::io::Write;
+
+use anyhow::{Context, Result, anyhow};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use serde_json::{Map, Value, json};
+
+use crate::config::MssEncoding;
+use crate::{DynamicPsnData, PsnRecord};
+
+/// 编码后的 MSS 请求
+pub struct EncodedPayload {
+    /// 实际发送的请求体
+    pub body: Vec<u8>,
+    pub content_type: &'static str,
+    pub content_encoding: Option<&'static str>,
+    /// `{ "<keyName>": [...] }` 形式的 JSON，用于归档和解析推送结果，与传输格式无关
+    pub request_json: String,
+}
+
+/// MSS 请求体的编码方式，由 mss_info_config.encoding 选择
+pub trait MssEncoder: Send + Sync {
+    fn encode(&self, records: &[&DynamicPsnData]) -> Result<EncodedPayload>;
+}
+
+impl MssEncoding {
+    pub fn encoder(&self) -> &'static dyn MssEncoder {
+        match self {
+            MssEncoding::JsonSingle => &JsonSingleEncoder,
+            MssEncoding::JsonBatch => &JsonBatchEncoder,
+            MssEncoding::NdjsonGzip => &NdjsonGzipEncoder,
+        }
+    }
+}
+
+/// 按 keyName 分组：`{ "classData": [..], "lecturerData": [..] }`
+fn envelope(records: &[&DynamicPsnData]) -> Result<String> {
+    let mut grouped = Map::new();
+    for record in records {
+        let value = serde_json::to_value(record).context("Failed to serialize MSS record")?;
+        match grouped
+            .entry(record.key_name())
+            .or_insert_with(|| Value::Array(Vec::new()))
+        {
+            Value::Array(items) => items.push(value),
+            _ => unreachable!("grouped values are always arrays"),
+        }
+    }
+    serde_json::to_string(&grouped).context("Failed to serialize dynamic JSON payload")
+}
+
+/// 每次请求一条记录：`{ "<keyName>": [record] }`
+pub struct JsonSingleEncoder;
+
+impl MssEncoder for JsonSingleEncoder {
+    fn encode(&self, records: &[&DynamicPsnData]) -> Result<EncodedPayload> {
+        let [record] = records else {
+            return Err(anyhow!(
+                "json-single encoding expects exactly one record, got {}",
+                records.len()
+            ));
+        };
+        let request_json = envelope(&[*record])?;
+        Ok(EncodedPayload {
+            body: request_json.clone().into_bytes(),
+            content_type: "application/json",
+            content_encoding: None,
+            request_json,
+        })
+    }
+}
+
+/// 一次请求多条记录，按 keyName 分组
+pub struct JsonBatchEncoder;
+
+impl MssEncoder for JsonBatchEncoder {
+    fn encode(&self, records: &[&DynamicPsnData]) -> Result<EncodedPayload> {
+        let request_json = envelope(records)?;
+        Ok(EncodedPayload {
+            body: request_json.clone().into_bytes(),
+            content_type: "application/json",
+            content_encoding: None,
+            request_json,
+        })
+    }
+}
+
+/// gzip 压缩的 NDJSON，每行一条 `{ "<keyName>": record }`
+pub struct NdjsonGzipEncoder;
+
+impl MssEncoder for NdjsonGzipEncoder {
+    fn encode(&self, records: &[&DynamicPsnData]) -> Result<EncodedPayload> {
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        for record in records {
+            let line = json!({ record.key_name(): record });
+            serde_json::to_writer(&mut gz, &line).context("Failed to serialize MSS record")?;
+            gz.write_all(b"\n")?;
+        }
+        let body = gz.finish().context("Failed to gzip NDJSON payload")?;
+        Ok(EncodedPayload {
+            body,
+            content_type: "application/x-ndjson",
+            content_encoding: Some("gzip"),
+            request_json: envelope(records)?,
+        })
+    }
+}
+
+#[test]
+fn test_encoders_share_request_json() {
+    use std::io::Read;
+
+    use crate::LecturerData;
+
+    let lecturer = |id: &str| {
+        DynamicPsnData::Lecturer(LecturerData {
+            id: id.to_string(),
+            ..Default::default()
+        })
+    };
+    let (first, second) = (lecturer("1"), lecturer("2"));
+
+    let single = JsonSingleEncoder.encode(&[&first]).unwrap();
+    assert_eq!(single.body, single.request_json.as_bytes());
+    assert!(JsonSingleEncoder.encode(&[&first, &second]).is_err());
+
+    let batch = JsonBatchEncoder.encode(&[&first, &second]).unwrap();
+    let batch_value: Value = serde_json::from_str(&batch.request_json).unwrap();
+    assert_eq!(batch_value["lecturerData"].as_array().unwrap().len(), 2);
+
+    let ndjson = NdjsonGzipEncoder.encode(&[&first, &second]).unwrap();
+    assert_eq!(ndjson.request_json, batch.request_json);
+    let mut lines = String::new();
+    flate2::read::GzDecoder::new(ndjson.body.as_slice())
+        .read_to_string(&mut lines)
+        .unwrap();
+    assert_eq!(lines.lines().count(), 2);
+}
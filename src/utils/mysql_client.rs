@@ -1,3 +1,4 @@
+use crate::db::query_runner::QueryRunner;
 use itertools::Itertools;
 use sqlx::{MySql, Transaction};
 use std::ops::DerefMut;
@@ -25,7 +26,10 @@ pub async fn batch_delete(
     for id in unique_ids {
         query = query.bind(id);
     }
-    let result = query.execute(tx.deref_mut()).await?;
+    let query_name = format!("batch_delete_{table_name}");
+    let result = QueryRunner::new(&query_name)
+        .run(query.execute(tx.deref_mut()))
+        .await?;
     info!(
         "Deleted {} records in table {}",
         result.rows_affected(),
@@ -2,12 +2,28 @@ use anyhow::Error as AnyhowError;
 use reqwest::Error as ReqwestError;
 use tracing::error;
 
+use super::circuit_breaker::CircuitOpen;
+use super::deadline::DeadlineExceeded;
+use super::gateway_client::{GatewayError, GatewayServiceDisabled};
+
 // 1. 自定义错误类型，用于区分可重试和不可重试的错误
 #[derive(Debug, thiserror::Error)] // 使用 thiserror 库可以方便地实现 Error trait
 pub enum ProcessError {
     #[error("Gateway request timeout, can be retried: {0}")]
     GatewayTimeout(String), // 专门用于 reqwest 的超时等网络错误
 
+    #[error("Gateway temporarily unavailable, can be retried: {0}")]
+    GatewayUnavailable(String), // 网关返回 5xx 或 429，稍后重试
+
+    #[error("Gateway service {0} is disabled, skipped")]
+    ServiceDisabled(String), // 服务在网关服务目录中被关闭，本轮不再调用
+
+    #[error("Cycle deadline exceeded, deferred to the next cycle")]
+    DeadlineExceeded, // 超过同步周期截止时间，留到下个周期处理
+
+    #[error("Gateway circuit breaker is open, deferred: {0}")]
+    CircuitOpen(String), // 网关熔断期间不发请求，与超时一样留到之后重试
+
     #[error("Permanent error, should not be retried: {0}")]
     Permanent(#[from] anyhow::Error), // 包含所有其他错误，如数据解析失败、逻辑错误等
 }
@@ -20,21 +36,91 @@ pub trait MapToProcessError<T> {
 // 2. 为所有 Result<T, anyhow::Error> 实现这个trait
 impl<T> MapToProcessError<T> for Result<T, AnyhowError> {
     fn map_gateway_err(self) -> Result<T, ProcessError> {
-        self.map_err(|e| {
-            if let Some(reqwest_err) = e.downcast_ref::<ReqwestError>()
-                && (reqwest_err.is_timeout()
-                    || reqwest_err.is_connect()
-                    || reqwest_err.is_request())
-            {
-                // is_timeout: 请求在指定时间内未完成
-                // is_connect: TCP连接被拒绝
-                // is_request: DNS解析失败、连接无法建立等在发送阶段发生的网络错误
-                error!("request can be retried, reqwest_err: {reqwest_err:?}");
-                return ProcessError::GatewayTimeout(e.to_string());
-            }
+        self.map_err(classify)
+    }
+}
 
-            error!("other error can not be retried: {e:?}");
-            ProcessError::Permanent(e)
+// 3. 网关查询的类型化错误：超时、5xx 与 429 可重试，其他 4xx、回复异常与查无数据不重试，
+// 传输错误按原始错误分类
+impl<T> MapToProcessError<T> for Result<T, GatewayError> {
+    fn map_gateway_err(self) -> Result<T, ProcessError> {
+        self.map_err(|e| match e {
+            GatewayError::Transport(e) => classify(e),
+            GatewayError::Timeout(e) => {
+                error!("request can be retried, reqwest_err: {e:?}");
+                ProcessError::GatewayTimeout(e.to_string())
+            }
+            GatewayError::NonSuccessStatus { code } if is_retryable_status(code) => {
+                error!("gateway status can be retried: {code}");
+                ProcessError::GatewayUnavailable(e.to_string())
+            }
+            e => {
+                error!("gateway error can not be retried: {e}");
+                ProcessError::Permanent(e.into())
+            }
         })
     }
 }
+
+/// 服务端错误与限流可以稍后重试，其他状态码重试也不会成功
+fn is_retryable_status(code: u16) -> bool {
+    code == 429 || (500..600).contains(&code)
+}
+
+fn classify(e: AnyhowError) -> ProcessError {
+    if let Some(disabled) = e.downcast_ref::<GatewayServiceDisabled>() {
+        return ProcessError::ServiceDisabled(disabled.service.clone());
+    }
+    if e.is::<DeadlineExceeded>() {
+        return ProcessError::DeadlineExceeded;
+    }
+    if e.is::<CircuitOpen>() {
+        return ProcessError::CircuitOpen(e.to_string());
+    }
+    if let Some(GatewayError::NonSuccessStatus { code }) = e.downcast_ref::<GatewayError>()
+        && is_retryable_status(*code)
+    {
+        error!("gateway status can be retried: {code}");
+        return ProcessError::GatewayUnavailable(e.to_string());
+    }
+    if let Some(reqwest_err) = e.downcast_ref::<ReqwestError>()
+        && (reqwest_err.is_timeout() || reqwest_err.is_connect() || reqwest_err.is_request())
+    {
+        // is_timeout: 请求在指定时间内未完成
+        // is_connect: TCP连接被拒绝
+        // is_request: DNS解析失败、连接无法建立等在发送阶段发生的网络错误
+        error!("request can be retried, reqwest_err: {reqwest_err:?}");
+        return ProcessError::GatewayTimeout(e.to_string());
+    }
+
+    error!("other error can not be retried: {e:?}");
+    ProcessError::Permanent(e)
+}
+
+#[test]
+fn test_gateway_status_classification() {
+    let status = |code| {
+        Err::<(), _>(GatewayError::NonSuccessStatus { code })
+            .map_gateway_err()
+            .unwrap_err()
+    };
+    assert!(matches!(status(500), ProcessError::GatewayUnavailable(_)));
+    assert!(matches!(status(503), ProcessError::GatewayUnavailable(_)));
+    assert!(matches!(status(429), ProcessError::GatewayUnavailable(_)));
+    assert!(matches!(status(400), ProcessError::Permanent(_)));
+    assert!(matches!(status(404), ProcessError::Permanent(_)));
+
+    // 经 anyhow 传递的状态码同样分类
+    let wrapped = Err::<(), _>(anyhow::Error::from(GatewayError::NonSuccessStatus {
+        code: 502,
+    }))
+    .map_gateway_err()
+    .unwrap_err();
+    assert!(matches!(wrapped, ProcessError::GatewayUnavailable(_)));
+    let wrapped = Err::<(), _>(anyhow::Error::from(GatewayError::NonSuccessStatus {
+        code: 403,
+    }))
+    .map_gateway_err()
+    .unwrap_err();
+    assert!(matches!(wrapped, ProcessError::Permanent(_)));
+}
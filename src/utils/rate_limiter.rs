@@ -0,0 +1,206 @@
+//! 出站调用的令牌桶限流。每个目标（MSS、网关）一个实例，通过 AppContext 在所有任务间共享，
+//! 并发的推送与 binlog 任务合计不超过配置的速率。速率可在运行中通过配置热加载修改
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::{debug, info};
+
+use crate::config::{RateLimitConfig, RateLimitsConfig};
+use crate::metrics::RATE_LIMIT_WAIT_SECONDS;
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    /// tokens 对应的时间点，暂停期间位于将来
+    updated: Instant,
+}
+
+impl Bucket {
+    /// 取走一个令牌，返回取到令牌前需要等待的时长
+    fn reserve(&mut self, now: Instant, rate: f64, burst: f64) -> Duration {
+        if now > self.updated {
+            let refill = (now - self.updated).as_secs_f64() * rate;
+            self.tokens = (self.tokens + refill).min(burst);
+            self.updated = now;
+        }
+        self.tokens -= 1.0;
+        let ahead = self.updated.saturating_duration_since(now);
+        if self.tokens >= 0.0 {
+            ahead
+        } else {
+            ahead + Duration::from_secs_f64(-self.tokens / rate)
+        }
+    }
+
+    /// 在 until 之前不再发放令牌
+    fn pause_until(&mut self, until: Instant) {
+        if until > self.updated {
+            self.updated = until;
+            self.tokens = self.tokens.min(0.0);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Limit {
+    /// 每秒补充的令牌数，0 表示不限流
+    rate: f64,
+    burst: f64,
+}
+
+impl Limit {
+    fn new(config: &RateLimitConfig) -> Self {
+        Limit {
+            rate: config.requests_per_second.max(0.0),
+            burst: f64::from(config.burst.max(1)),
+        }
+    }
+}
+
+pub struct RateLimiter {
+    target: &'static str,
+    limit: Mutex<Limit>,
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    pub fn new(target: &'static str, config: &RateLimitConfig) -> Self {
+        let limit = Limit::new(config);
+        RateLimiter {
+            target,
+            limit: Mutex::new(limit),
+            bucket: Mutex::new(Bucket {
+                tokens: limit.burst,
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    /// 不限流，未配置限流器的客户端使用
+    pub fn unlimited(target: &'static str) -> Self {
+        Self::new(target, &RateLimitConfig::default())
+    }
+
+    /// 修改速率与突发数，已取走的令牌不退回，桶中多出新突发数的令牌被丢弃
+    pub fn update(&self, config: &RateLimitConfig) {
+        let limit = Limit::new(config);
+        let previous = std::mem::replace(&mut *self.limit(), limit);
+        if previous == limit {
+            return;
+        }
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        bucket.tokens = bucket.tokens.min(limit.burst);
+        info!(
+            "Rate limit for {} changed from {}/s (burst {}) to {}/s (burst {}).",
+            self.target, previous.rate, previous.burst, limit.rate, limit.burst
+        );
+    }
+
+    /// 等待直到可以发出一个请求
+    pub async fn acquire(&self) {
+        let limit = *self.limit();
+        if limit.rate <= 0.0 {
+            return;
+        }
+        let wait = self
+            .bucket
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .reserve(Instant::now(), limit.rate, limit.burst);
+        if !wait.is_zero() {
+            debug!("Rate limited request to {}, waiting {wait:?}.", self.target);
+            RATE_LIMIT_WAIT_SECONDS
+                .with_label_values(&[self.target])
+                .observe(wait.as_secs_f64());
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// 下游要求休息（如 MSS 返回 9019）时暂停所有使用该限流器的请求
+    pub fn pause(&self, duration: Duration) {
+        if self.limit().rate <= 0.0 {
+            return;
+        }
+        self.bucket
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .pause_until(Instant::now() + duration);
+    }
+
+    fn limit(&self) -> std::sync::MutexGuard<'_, Limit> {
+        self.limit.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// 各出站目标的限流器
+pub struct RateLimiters {
+    pub mss: Arc<RateLimiter>,
+    pub gateway: Arc<RateLimiter>,
+}
+
+impl RateLimiters {
+    pub fn new(config: &RateLimitsConfig) -> Self {
+        RateLimiters {
+            mss: Arc::new(RateLimiter::new("mss", &config.mss)),
+            gateway: Arc::new(RateLimiter::new("gateway", &config.gateway)),
+        }
+    }
+
+    /// 按新的配置修改各限流器的速率，配置热加载时调用
+    pub fn update(&self, config: &RateLimitsConfig) {
+        self.mss.update(&config.mss);
+        self.gateway.update(&config.gateway);
+    }
+}
+
+#[test]
+fn test_token_bucket() {
+    let start = Instant::now();
+    let mut bucket = Bucket {
+        tokens: 2.0,
+        updated: start,
+    };
+    // 突发 2 个不等待，第 3 个按 10/s 等待 100ms
+    assert_eq!(bucket.reserve(start, 10.0, 2.0), Duration::ZERO);
+    assert_eq!(bucket.reserve(start, 10.0, 2.0), Duration::ZERO);
+    assert_eq!(bucket.reserve(start, 10.0, 2.0), Duration::from_millis(100));
+    // 空闲 1 秒后最多补满 burst
+    let later = start + Duration::from_secs(1);
+    assert_eq!(bucket.reserve(later, 10.0, 2.0), Duration::ZERO);
+    assert_eq!(bucket.reserve(later, 10.0, 2.0), Duration::ZERO);
+    assert!(bucket.reserve(later, 10.0, 2.0) > Duration::ZERO);
+
+    // 暂停期间的请求等到暂停结束之后
+    let mut bucket = Bucket {
+        tokens: 2.0,
+        updated: start,
+    };
+    bucket.pause_until(start + Duration::from_secs(60));
+    let wait = bucket.reserve(start, 10.0, 2.0);
+    assert_eq!(wait, Duration::from_secs(60) + Duration::from_millis(100));
+}
+
+#[test]
+fn test_rate_limiter_update() {
+    let limiter = RateLimiter::new(
+        "mss",
+        &RateLimitConfig {
+            requests_per_second: 10.0,
+            burst: 10,
+        },
+    );
+    limiter.update(&RateLimitConfig {
+        requests_per_second: 5.0,
+        burst: 2,
+    });
+    assert_eq!(
+        *limiter.limit(),
+        Limit {
+            rate: 5.0,
+            burst: 2.0
+        }
+    );
+    // 桶中的令牌不超过新的突发数
+    assert_eq!(limiter.bucket.lock().unwrap().tokens, 2.0);
+}
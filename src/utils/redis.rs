@@ -1,7 +1,8 @@
 use anyhow::{Context, Result};
-use redis::aio::ConnectionManager;
 use redis::AsyncCommands;
 use redis::Script;
+use redis::aio::ConnectionManager;
+use serde::Serialize;
 use std::time::Duration;
 use uuid::Uuid;
 
@@ -18,6 +19,17 @@ pub async fn init_redis(redis_url: &str) -> Result<RedisMgr> {
     Ok(mgr)
 }
 
+/// 进程关闭时关闭 Redis 连接。ConnectionManager 的各个克隆共用同一条连接且没有关闭方法，
+/// 发送 QUIT 由服务端关闭连接，之后没有命令时不会重连
+pub async fn close_redis(mgr: &RedisMgr) -> Result<()> {
+    let mut conn = mgr.clone();
+    let _: () = redis::cmd("QUIT")
+        .query_async(&mut conn)
+        .await
+        .context("redis QUIT failed")?;
+    Ok(())
+}
+
 pub async fn set_kv(mgr: &RedisMgr, key: &str, val: &str, ttl_sec: Option<u64>) -> Result<()> {
     let mut conn = mgr.clone();
     if let Some(sec) = ttl_sec {
@@ -35,6 +47,21 @@ pub async fn set_kv(mgr: &RedisMgr, key: &str, val: &str, ttl_sec: Option<u64>)
     Ok(())
 }
 
+/// SET NX EX：键不存在时才写入，返回是否写入
+pub async fn set_kv_nx(mgr: &RedisMgr, key: &str, val: &str, ttl_sec: u64) -> Result<bool> {
+    let mut conn = mgr.clone();
+    let resp: Option<String> = redis::cmd("SET")
+        .arg(key)
+        .arg(val)
+        .arg("EX")
+        .arg(ttl_sec)
+        .arg("NX")
+        .query_async(&mut conn)
+        .await
+        .context("redis SET with NX failed")?;
+    Ok(resp.is_some())
+}
+
 pub async fn get_kv(mgr: &RedisMgr, key: &str) -> Result<Option<String>> {
     let mut conn = mgr.clone();
     let v: Option<String> = conn.get(key).await?;
@@ -47,6 +74,23 @@ pub async fn del_kv(mgr: &RedisMgr, key: &str) -> Result<Option<usize>> {
     Ok(v)
 }
 
+/// PING，用于健康检查
+pub async fn ping(mgr: &RedisMgr) -> Result<()> {
+    let mut conn = mgr.clone();
+    let _pong: String = redis::cmd("PING")
+        .query_async(&mut conn)
+        .await
+        .context("redis PING failed")?;
+    Ok(())
+}
+
+/// 锁当前的持有状态
+#[derive(Debug, Clone, Serialize)]
+pub struct LockState {
+    pub token: String,
+    pub ttl_ms: Option<u64>,
+}
+
 /// 分布式锁的实现（返回 token，调用者持有 token 用于释放）
 pub struct RedisLock {
     pub key: String,
@@ -102,6 +146,27 @@ impl RedisLock {
 
     /// 安全释放：只有 token 匹配时才删除（用 Lua 原子脚本）
     pub async fn release(self, mgr: &RedisMgr) -> Result<bool> {
+        Self::release_token(mgr, &self.key, &self.token).await
+    }
+
+    /// 查看锁的持有 token 与剩余过期时间，未被持有时返回 None
+    pub async fn inspect(mgr: &RedisMgr, key: &str) -> Result<Option<LockState>> {
+        let mut conn = mgr.clone();
+        let (token, ttl_ms): (Option<String>, i64) = redis::pipe()
+            .get(key)
+            .pttl(key)
+            .query_async(&mut conn)
+            .await
+            .context("redis GET/PTTL failed")?;
+        Ok(token.map(|token| LockState {
+            token,
+            // PTTL 返回 -1 表示没有过期时间
+            ttl_ms: u64::try_from(ttl_ms).ok(),
+        }))
+    }
+
+    /// 按 token 释放他人持有的锁（运维清理崩溃节点遗留的锁），token 不匹配时不删除
+    pub async fn release_token(mgr: &RedisMgr, key: &str, token: &str) -> Result<bool> {
         // Lua 脚本（标准做法）：
         // if redis.call("get",KEYS[1]) == ARGV[1] then return redis.call("del",KEYS[1]) else return 0 end
         const RELEASE_SCRIPT: &str = r#"
@@ -115,11 +180,7 @@ impl RedisLock {
         let mut conn = mgr.clone();
         let script = Script::new(RELEASE_SCRIPT);
         // 返回值是删除的数量（1 成功；0 失败）
-        let deleted: i32 = script
-            .key(&self.key)
-            .arg(&self.token)
-            .invoke_async(&mut conn)
-            .await?;
+        let deleted: i32 = script.key(key).arg(token).invoke_async(&mut conn).await?;
         Ok(deleted == 1)
     }
 }
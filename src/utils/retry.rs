@@ -0,0 +1,191 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+/// 可重试的错误类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryClass {
+    /// 请求超时
+    Timeout,
+    /// 连接失败、DNS 解析失败等发送阶段的网络错误
+    Connect,
+    /// 对端返回 5xx
+    ServerError,
+    /// 对端要求限流等待，如 MSS 的 9019
+    Throttled,
+}
+
+impl RetryClass {
+    /// 从请求错误中识别类别，无法识别的错误不重试
+    pub fn of_request_error(e: &reqwest::Error) -> Option<Self> {
+        if e.is_timeout() {
+            Some(RetryClass::Timeout)
+        } else if e.is_connect() || e.is_request() {
+            Some(RetryClass::Connect)
+        } else {
+            None
+        }
+    }
+}
+
+/// 重试策略：第 n 次重试前等待 initial_backoff * multiplier^(n-1)，不超过 max_backoff，
+/// 再按 jitter 比例随机缩放，避免多个实例同时重试
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// 最大尝试次数（包含第一次），为 1 时不重试
+    pub max_attempts: u32,
+    #[serde(with = "humantime_serde")]
+    pub initial_backoff: Duration,
+    #[serde(with = "humantime_serde")]
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    /// 0 到 1，实际等待时间在 [backoff * (1 - jitter), backoff] 之间
+    pub jitter: f64,
+    /// 允许重试的错误类别
+    pub retry_on: Vec<RetryClass>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.2,
+            retry_on: vec![RetryClass::Timeout, RetryClass::Connect],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 覆盖最大尝试次数，其他参数不变
+    pub fn with_max_attempts(&self, max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            ..self.clone()
+        }
+    }
+
+    /// 检查取值，加载配置时调用
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_attempts == 0 {
+            return Err("max_attempts must be at least 1".to_string());
+        }
+        if !(self.multiplier.is_finite() && self.multiplier >= 1.0) {
+            return Err(format!(
+                "multiplier must be a finite number of at least 1, got {}",
+                self.multiplier
+            ));
+        }
+        // NaN 不在任何区间内
+        if !(0.0..=1.0).contains(&self.jitter) {
+            return Err(format!(
+                "jitter must be between 0 and 1, got {}",
+                self.jitter
+            ));
+        }
+        if self.initial_backoff > self.max_backoff {
+            return Err(format!(
+                "initial_backoff {:?} exceeds max_backoff {:?}",
+                self.initial_backoff, self.max_backoff
+            ));
+        }
+        Ok(())
+    }
+
+    /// 第 attempt 次尝试（从 1 开始）以 class 失败后是否还能重试
+    pub fn should_retry(&self, attempt: u32, class: RetryClass) -> bool {
+        attempt < self.max_attempts && self.retry_on.contains(&class)
+    }
+
+    /// 第 attempt 次尝试失败后、下一次尝试前的等待时间
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.backoff_with(attempt, random_unit())
+    }
+
+    // unit 为 [0, 1) 的随机数，测试时固定
+    fn backoff_with(&self, attempt: u32, unit: f64) -> Duration {
+        let exponent = i32::try_from(attempt.saturating_sub(1)).unwrap_or(i32::MAX);
+        let base = self.initial_backoff.as_secs_f64() * self.multiplier.max(1.0).powi(exponent);
+        let capped = base.min(self.max_backoff.as_secs_f64());
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        Duration::from_secs_f64(capped * (1.0 - jitter * unit))
+    }
+}
+
+// 不为抖动引入随机数依赖，用 RandomState 的随机种子生成
+fn random_unit() -> f64 {
+    let bits = RandomState::new().hash_one(std::time::Instant::now());
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[test]
+fn test_retry_policy_backoff() {
+    let policy = RetryPolicy {
+        max_attempts: 4,
+        initial_backoff: Duration::from_secs(1),
+        max_backoff: Duration::from_secs(5),
+        multiplier: 2.0,
+        jitter: 0.5,
+        retry_on: vec![RetryClass::Timeout],
+    };
+    assert_eq!(policy.backoff_with(1, 0.0), Duration::from_secs(1));
+    assert_eq!(policy.backoff_with(2, 0.0), Duration::from_secs(2));
+    assert_eq!(policy.backoff_with(3, 0.0), Duration::from_secs(4));
+    assert_eq!(policy.backoff_with(4, 0.0), Duration::from_secs(5));
+    assert_eq!(policy.backoff_with(40, 0.0), Duration::from_secs(5));
+    assert_eq!(policy.backoff_with(2, 1.0), Duration::from_secs(1));
+    for _ in 0..100 {
+        let backoff = policy.backoff(3);
+        assert!(backoff > Duration::from_secs(2) && backoff <= Duration::from_secs(4));
+    }
+
+    assert!(policy.should_retry(3, RetryClass::Timeout));
+    assert!(!policy.should_retry(4, RetryClass::Timeout));
+    assert!(!policy.should_retry(1, RetryClass::ServerError));
+    assert!(
+        !policy
+            .with_max_attempts(1)
+            .should_retry(1, RetryClass::Timeout)
+    );
+}
+
+#[test]
+fn test_retry_policy_validate() {
+    assert!(RetryPolicy::default().validate().is_ok());
+    let invalid = [
+        RetryPolicy {
+            max_attempts: 0,
+            ..RetryPolicy::default()
+        },
+        RetryPolicy {
+            jitter: f64::NAN,
+            ..RetryPolicy::default()
+        },
+        RetryPolicy {
+            jitter: -0.1,
+            ..RetryPolicy::default()
+        },
+        RetryPolicy {
+            multiplier: f64::NAN,
+            ..RetryPolicy::default()
+        },
+        RetryPolicy {
+            multiplier: -2.0,
+            ..RetryPolicy::default()
+        },
+        RetryPolicy {
+            initial_backoff: Duration::from_secs(60),
+            max_backoff: Duration::from_secs(5),
+            ..RetryPolicy::default()
+        },
+    ];
+    for policy in invalid {
+        assert!(policy.validate().is_err(), "{policy:?}");
+    }
+}
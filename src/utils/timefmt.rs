@@ -0,0 +1,65 @@
+//! 统一的时间格式。业务日期（hitdate 等）、日期时间与毫秒时间戳都从这里生成，
+//! 避免各处手写格式串导致同一字段出现不同格式
+
+use chrono::{Datelike, Days, Local, NaiveDate, NaiveDateTime, ParseResult, Utc};
+
+/// 业务日期，如 hitdate、报文归档目录
+pub const BUSINESS_DATE_FORMAT: &str = "%Y-%m-%d";
+/// 日期时间，精确到秒
+pub const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+/// 日志时间，精确到毫秒
+pub const LOG_DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+pub fn business_date(date: NaiveDate) -> String {
+    date.format(BUSINESS_DATE_FORMAT).to_string()
+}
+
+pub fn parse_business_date(s: &str) -> ParseResult<NaiveDate> {
+    NaiveDate::parse_from_str(s, BUSINESS_DATE_FORMAT)
+}
+
+pub fn datetime(dt: NaiveDateTime) -> String {
+    dt.format(DATETIME_FORMAT).to_string()
+}
+
+pub fn parse_datetime(s: &str) -> ParseResult<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(s, DATETIME_FORMAT)
+}
+
+/// 当前本地时间
+pub fn now_local() -> NaiveDateTime {
+    Local::now().naive_local()
+}
+
+/// 本地时间的今天
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// 本地时间的昨天，按日期处理的任务默认处理昨天的数据
+pub fn yesterday() -> NaiveDate {
+    today() - Days::new(1)
+}
+
+/// 年、月两个分区字段，月份补零到两位
+pub fn year_month(date: impl Datelike) -> (String, String) {
+    (date.year().to_string(), format!("{:02}", date.month()))
+}
+
+/// 当前毫秒时间戳，时间戳全球统一不区分时区
+pub fn timestamp_ms() -> i64 {
+    Utc::now().timestamp_millis()
+}
+
+#[test]
+fn test_timefmt_round_trip() {
+    let date = NaiveDate::from_ymd_opt(2026, 3, 5).unwrap();
+    assert_eq!(business_date(date), "2026-03-05");
+    assert_eq!(parse_business_date("2026-03-05").unwrap(), date);
+    assert!(parse_business_date("2026-03").is_err());
+
+    let dt = date.and_hms_opt(8, 9, 10).unwrap();
+    assert_eq!(datetime(dt), "2026-03-05 08:09:10");
+    assert_eq!(parse_datetime("2026-03-05 08:09:10").unwrap(), dt);
+    assert_eq!(year_month(dt), ("2026".to_string(), "03".to_string()));
+}
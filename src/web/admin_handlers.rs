@@ -0,0 +1,513 @@
+use std::sync::Arc;
+
+use crate::db::query_runner::{QueryRunner, query_stats};
+use crate::messages::Message;
+use crate::models::admin_audit::{list_admin_actions, record_admin_action};
+use crate::schedule::binlog_sync::BINLOG_SYNC_LOCK_KEY;
+use crate::schedule::clickhouse_reconcile::{
+    reconcile_clickhouse_statuses, set_clickhouse_statuses,
+};
+use crate::schedule::cron_calendar::{SCHEDULER_TIMEZONE, upcoming_fires};
+use crate::schedule::push_executor::clickhouse_status_table;
+use crate::utils::redis::{LockState, RedisLock};
+use crate::utils::timefmt;
+use crate::web::RouteRegistrar;
+use crate::web::auth::AuthorizedCaller;
+use crate::web::idempotency::IdempotencyKey;
+use crate::{AppContext, PsnDataKind, web::models::ApiResponse};
+use actix_web::{HttpResponse, Result, delete, get, http::StatusCode, post, web};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use tracing::{error, info, warn};
+
+#[derive(Debug, Serialize)]
+pub struct ColumnInfo {
+    pub name: String,
+    pub column_type: String,
+    pub nullable: bool,
+    pub key: String,
+    pub default: Option<String>,
+    pub comment: String,
+}
+
+/// 查询本服务写入的表的列定义，只允许查询 admin_config.schema_tables 中配置的表
+#[get("/admin/tables/{name}/columns")]
+pub async fn table_columns(
+    app_context: web::Data<Arc<AppContext>>, // 注入 AppContext
+    _caller: AuthorizedCaller,
+    path: web::Path<String>,
+) -> Result<HttpResponse> {
+    let name = path.into_inner();
+    // MySQL 表名大小写敏感，使用白名单中的写法查询
+    let Some(table_name) = app_context
+        .admin_config
+        .schema_tables
+        .iter()
+        .find(|table| table.eq_ignore_ascii_case(&name))
+    else {
+        return Ok(
+            HttpResponse::Forbidden().json(ApiResponse::<()>::from_message(
+                Message::SchemaTableNotAllowed { table: name },
+            )),
+        );
+    };
+
+    let query = sqlx::query(
+        "SELECT CAST(COLUMN_NAME AS CHAR) AS name, CAST(COLUMN_TYPE AS CHAR) AS column_type, \
+         CAST(IS_NULLABLE AS CHAR) AS nullable, CAST(COLUMN_KEY AS CHAR) AS column_key, \
+         CAST(COLUMN_DEFAULT AS CHAR) AS column_default, CAST(COLUMN_COMMENT AS CHAR) AS comment \
+         FROM information_schema.COLUMNS \
+         WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? ORDER BY ORDINAL_POSITION",
+    )
+    .bind(table_name);
+    let rows = QueryRunner::new("table_columns")
+        .run(query.fetch_all(&app_context.mysql_pool))
+        .await;
+
+    let rows =
+        match rows {
+            Ok(rows) => rows,
+            Err(e) => {
+                error!("Failed to query columns of table {table_name}: {e:?}");
+                return Ok(HttpResponse::InternalServerError().json(
+                    ApiResponse::<()>::from_message(Message::SchemaQueryFailed {
+                        table: table_name.to_string(),
+                    }),
+                ));
+            }
+        };
+    if rows.is_empty() {
+        return Ok(
+            HttpResponse::NotFound().json(ApiResponse::<()>::from_message(
+                Message::SchemaTableNotFound {
+                    table: table_name.to_string(),
+                },
+            )),
+        );
+    }
+
+    let columns: Vec<ColumnInfo> = rows
+        .iter()
+        .map(|row| ColumnInfo {
+            name: row.get("name"),
+            column_type: row.get("column_type"),
+            nullable: row.get::<String, _>("nullable") == "YES",
+            key: row.get("column_key"),
+            default: row.get("column_default"),
+            comment: row.get("comment"),
+        })
+        .collect();
+    Ok(HttpResponse::Ok().json(ApiResponse::success(columns)))
+}
+
+/// 根据已注册的任务生成的 Prometheus 告警规则（规则文件格式）
+#[get("/admin/alert-rules")]
+pub async fn alert_rules(
+    app_context: web::Data<Arc<AppContext>>,
+    _caller: AuthorizedCaller,
+) -> Result<HttpResponse> {
+    match app_context.alert_rules.get() {
+        Some(groups) => Ok(HttpResponse::Ok().json(ApiResponse::success(
+            serde_json::json!({ "groups": groups }),
+        ))),
+        None => Ok(HttpResponse::ServiceUnavailable()
+            .json(ApiResponse::<()>::from_message(Message::AlertRulesNotReady))),
+    }
+}
+
+// 展开触发时间的默认天数与上限
+const DEFAULT_UPCOMING_DAYS: u32 = 7;
+const MAX_UPCOMING_DAYS: u32 = 31;
+
+#[derive(Debug, Deserialize)]
+pub struct UpcomingParams {
+    pub days: Option<u32>,
+}
+
+/// 已注册的 cron 任务在未来若干天内的触发时间（调度器时区），修改配置后用于确认调度计划
+#[get("/scheduler/upcoming")]
+pub async fn scheduler_upcoming(
+    app_context: web::Data<Arc<AppContext>>,
+    query: web::Query<UpcomingParams>,
+) -> Result<HttpResponse> {
+    let Some(registry) = app_context.task_registry.get() else {
+        return Ok(
+            HttpResponse::ServiceUnavailable().json(ApiResponse::<()>::from_message(
+                Message::CronSchedulesNotReady,
+            )),
+        );
+    };
+    let days = query
+        .days
+        .unwrap_or(DEFAULT_UPCOMING_DAYS)
+        .clamp(1, MAX_UPCOMING_DAYS);
+    let from = chrono::Utc::now().with_timezone(&SCHEDULER_TIMEZONE);
+    match upcoming_fires(&registry.cron_schedules(), from, days) {
+        Ok(upcoming) => Ok(HttpResponse::Ok().json(ApiResponse::success(upcoming))),
+        Err(e) => {
+            error!("Failed to expand cron schedules: {e:?}");
+            Ok(
+                HttpResponse::InternalServerError().json(ApiResponse::<()>::from_message(
+                    Message::CronExpandFailed {
+                        error: e.to_string(),
+                    },
+                )),
+            )
+        }
+    }
+}
+
+// 审计记录每页默认条数与上限
+const DEFAULT_AUDIT_PAGE_SIZE: u32 = 20;
+const MAX_AUDIT_PAGE_SIZE: u32 = 200;
+
+#[derive(Debug, Deserialize)]
+pub struct AuditPageParams {
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+}
+
+/// 分页查询手动操作审计记录，按时间倒序
+#[get("/admin/audit")]
+pub async fn admin_audit(
+    app_context: web::Data<Arc<AppContext>>,
+    _caller: AuthorizedCaller,
+    query: web::Query<AuditPageParams>,
+) -> Result<HttpResponse> {
+    let page = query.page.unwrap_or(1).max(1);
+    let page_size = query
+        .page_size
+        .unwrap_or(DEFAULT_AUDIT_PAGE_SIZE)
+        .clamp(1, MAX_AUDIT_PAGE_SIZE);
+
+    match list_admin_actions(&app_context.mysql_pool, page, page_size).await {
+        Ok((items, total)) => Ok(HttpResponse::Ok().json(ApiResponse::success(
+            serde_json::json!({
+                "page": page,
+                "page_size": page_size,
+                "total": total,
+                "items": items,
+            }),
+        ))),
+        Err(e) => {
+            error!("Failed to query admin audit: {e:?}");
+            Ok(
+                HttpResponse::InternalServerError().json(ApiResponse::<()>::from_message(
+                    Message::AuditQueryFailed {
+                        error: e.to_string(),
+                    },
+                )),
+            )
+        }
+    }
+}
+
+// 本服务使用的 Redis 锁，只有这些锁可以通过管理接口查看和清理
+const SERVICE_LOCKS: [&str; 1] = [BINLOG_SYNC_LOCK_KEY];
+
+/// 各具名 MySQL 查询的累计执行次数、耗时与行数，按总耗时降序，用于定位热点查询
+#[get("/admin/queries")]
+pub async fn query_hotspots(_caller: AuthorizedCaller) -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(ApiResponse::success(query_stats())))
+}
+
+/// 正在运行的推送任务实例及其参数（hit_date、train_ids、数据种类、地区），用于确认当前在跑什么
+#[get("/admin/tasks")]
+pub async fn running_tasks(
+    app_context: web::Data<Arc<AppContext>>,
+    _caller: AuthorizedCaller,
+) -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(ApiResponse::success(app_context.running_tasks.snapshot())))
+}
+
+#[derive(Debug, Serialize)]
+pub struct LockInfo {
+    pub key: &'static str,
+    pub held: bool,
+    #[serde(flatten)]
+    pub state: Option<LockState>,
+}
+
+/// 查看本服务 Redis 锁的持有状态
+#[get("/admin/locks")]
+pub async fn list_locks(
+    app_context: web::Data<Arc<AppContext>>,
+    _caller: AuthorizedCaller,
+) -> Result<HttpResponse> {
+    let mut locks = Vec::with_capacity(SERVICE_LOCKS.len());
+    for key in SERVICE_LOCKS {
+        match RedisLock::inspect(&app_context.redis_mgr, key).await {
+            Ok(state) => locks.push(LockInfo {
+                key,
+                held: state.is_some(),
+                state,
+            }),
+            Err(e) => {
+                error!("Failed to inspect redis lock {key}: {e:?}");
+                return Ok(HttpResponse::InternalServerError().json(
+                    ApiResponse::<()>::from_message(Message::LockInspectFailed {
+                        key: key.to_string(),
+                        error: e.to_string(),
+                    }),
+                ));
+            }
+        }
+    }
+    Ok(HttpResponse::Ok().json(ApiResponse::success(locks)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClearLockParams {
+    /// GET /admin/locks 返回的 token，锁已被重新获取时不会误删
+    pub token: String,
+}
+
+/// 清理崩溃节点遗留的锁，必须带上当前持有的 token
+#[delete("/admin/locks/{key}")]
+pub async fn clear_lock(
+    app_context: web::Data<Arc<AppContext>>,
+    path: web::Path<String>,
+    query: web::Query<ClearLockParams>,
+    caller: AuthorizedCaller,
+) -> Result<HttpResponse> {
+    let key = path.into_inner();
+    let Some(key) = SERVICE_LOCKS.into_iter().find(|lock| *lock == key) else {
+        return Ok(
+            HttpResponse::Forbidden().json(ApiResponse::<()>::from_message(Message::LockUnknown {
+                key,
+            })),
+        );
+    };
+
+    let caller = caller.0;
+    // 审计失败不影响清理
+    if let Err(e) = record_admin_action(
+        &app_context.mysql_pool,
+        "clear_lock",
+        &caller.identity,
+        caller.source_ip.as_deref(),
+        &serde_json::json!({ "key": key, "token": query.token }),
+    )
+    .await
+    {
+        warn!("Failed to record admin audit for clear_lock: {e:?}");
+    }
+
+    match RedisLock::release_token(&app_context.redis_mgr, key, &query.token).await {
+        Ok(true) => {
+            info!("Redis lock {key} cleared by {}", caller.identity);
+            Ok(HttpResponse::Ok().json(ApiResponse::success(key)))
+        }
+        Ok(false) => Ok(
+            HttpResponse::Conflict().json(ApiResponse::<()>::from_message(Message::LockNotHeld {
+                key: key.to_string(),
+            })),
+        ),
+        Err(e) => {
+            error!("Failed to clear redis lock {key}: {e:?}");
+            Ok(
+                HttpResponse::InternalServerError().json(ApiResponse::<()>::from_message(
+                    Message::LockClearFailed {
+                        key: key.to_string(),
+                        error: e.to_string(),
+                    },
+                )),
+            )
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ReconcileRequest {
+    /// 推送日期，yyyy-MM-dd
+    pub date: String,
+    /// 数据种类，目前支持 class、lecturer
+    pub kind: String,
+}
+
+/// 按某天的推送结果修正 ClickHouse 各节点上不一致的 trainNotifyMss，返回每个节点修正的记录数
+#[post("/admin/clickhouse/reconcile")]
+pub async fn reconcile_clickhouse(
+    app_context: web::Data<Arc<AppContext>>,
+    request: web::Json<ReconcileRequest>,
+    caller: AuthorizedCaller,
+    idempotency: IdempotencyKey,
+) -> Result<HttpResponse> {
+    let request = request.into_inner();
+    let Ok(date) = timefmt::parse_business_date(&request.date) else {
+        return Ok(
+            HttpResponse::BadRequest().json(ApiResponse::<()>::from_message(
+                Message::ReconcileDateInvalid {
+                    value: request.date,
+                },
+            )),
+        );
+    };
+    let Some(kind) = PsnDataKind::from_name(&request.kind) else {
+        return Ok(
+            HttpResponse::BadRequest().json(ApiResponse::<()>::from_message(
+                Message::UnknownDataKind { kind: request.kind },
+            )),
+        );
+    };
+
+    // 重复提交直接返回首次的结果，不重复执行修正
+    if let Some(response) = idempotency.begin(&app_context).await {
+        return Ok(response);
+    }
+
+    let caller = caller.0;
+    // 审计失败不影响对账
+    if let Err(e) = record_admin_action(
+        &app_context.mysql_pool,
+        "clickhouse_reconcile",
+        &caller.identity,
+        caller.source_ip.as_deref(),
+        &request,
+    )
+    .await
+    {
+        warn!("Failed to record admin audit for clickhouse_reconcile: {e:?}");
+    }
+
+    match reconcile_clickhouse_statuses(&app_context, kind, date).await {
+        Ok(reports) => Ok(idempotency
+            .complete(&app_context, StatusCode::OK, ApiResponse::success(reports))
+            .await),
+        Err(e) => {
+            error!("Failed to reconcile ClickHouse statuses of {kind:?} on {date}: {e:?}");
+            idempotency.release(&app_context).await;
+            Ok(
+                HttpResponse::InternalServerError().json(ApiResponse::<()>::from_message(
+                    Message::ReconcileFailed {
+                        error: e.to_string(),
+                    },
+                )),
+            )
+        }
+    }
+}
+
+// 单次手动更新的 ID 数上限
+const MAX_NOTIFY_STATUS_IDS: usize = 10_000;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NotifyStatusRequest {
+    /// 数据种类，目前支持 class、lecturer、archive
+    pub kind: String,
+    pub ids: Vec<String>,
+    /// 配置中的状态值（默认 0 未推送，1 成功，2 失败）或状态名 pending / success / failed
+    pub status: String,
+}
+
+/// 带外修复后手动设置指定 ID 在 ClickHouse 各节点上的 trainNotifyMss，返回每个节点更新的记录数
+#[post("/admin/clickhouse/notify-status")]
+pub async fn set_notify_status(
+    app_context: web::Data<Arc<AppContext>>,
+    request: web::Json<NotifyStatusRequest>,
+    caller: AuthorizedCaller,
+    idempotency: IdempotencyKey,
+) -> Result<HttpResponse> {
+    let mut request = request.into_inner();
+    let Some(kind) = PsnDataKind::from_name(&request.kind) else {
+        return Ok(
+            HttpResponse::BadRequest().json(ApiResponse::<()>::from_message(
+                Message::UnknownDataKind { kind: request.kind },
+            )),
+        );
+    };
+    if !matches!(
+        clickhouse_status_table(&app_context.clickhouse_tables, kind),
+        Ok(Some(_))
+    ) {
+        return Ok(
+            HttpResponse::BadRequest().json(ApiResponse::<()>::from_message(
+                Message::NotifyStatusUnsupported { kind: request.kind },
+            )),
+        );
+    }
+    let Some(status) = app_context.notify_status.parse(&request.status) else {
+        let statuses = &app_context.notify_status;
+        return Ok(
+            HttpResponse::BadRequest().json(ApiResponse::<()>::from_message(
+                Message::NotifyStatusInvalid {
+                    value: request.status,
+                    expected: format!(
+                        "{:?}",
+                        [&statuses.pending, &statuses.success, &statuses.failed]
+                    ),
+                },
+            )),
+        );
+    };
+    request.ids.retain(|id| !id.trim().is_empty());
+    request.ids.sort();
+    request.ids.dedup();
+    if request.ids.is_empty() || request.ids.len() > MAX_NOTIFY_STATUS_IDS {
+        return Ok(
+            HttpResponse::BadRequest().json(ApiResponse::<()>::from_message(
+                Message::NotifyStatusIdsOutOfRange {
+                    max: MAX_NOTIFY_STATUS_IDS,
+                },
+            )),
+        );
+    }
+
+    // 重复提交直接返回首次的结果
+    if let Some(response) = idempotency.begin(&app_context).await {
+        return Ok(response);
+    }
+
+    let caller = caller.0;
+    // 审计失败不影响更新
+    if let Err(e) = record_admin_action(
+        &app_context.mysql_pool,
+        "clickhouse_notify_status",
+        &caller.identity,
+        caller.source_ip.as_deref(),
+        &request,
+    )
+    .await
+    {
+        warn!("Failed to record admin audit for clickhouse_notify_status: {e:?}");
+    }
+
+    match set_clickhouse_statuses(&app_context, kind, &request.ids, status).await {
+        Ok(reports) => Ok(idempotency
+            .complete(&app_context, StatusCode::OK, ApiResponse::success(reports))
+            .await),
+        Err(e) => {
+            error!("Failed to set ClickHouse notify status of {kind:?}: {e:?}");
+            idempotency.release(&app_context).await;
+            Ok(
+                HttpResponse::InternalServerError().json(ApiResponse::<()>::from_message(
+                    Message::NotifyStatusSetFailed {
+                        error: e.to_string(),
+                    },
+                )),
+            )
+        }
+    }
+}
+
+/// 运维管理接口
+pub struct AdminRoutes;
+
+impl RouteRegistrar for AdminRoutes {
+    fn name(&self) -> &'static str {
+        "admin"
+    }
+
+    fn register(&self, cfg: &mut web::ServiceConfig) {
+        cfg.service(table_columns)
+            .service(alert_rules)
+            .service(scheduler_upcoming)
+            .service(admin_audit)
+            .service(query_hotspots)
+            .service(running_tasks)
+            .service(list_locks)
+            .service(clear_lock)
+            .service(reconcile_clickhouse)
+            .service(set_notify_status);
+    }
+}
@@ -0,0 +1,133 @@
+use std::future::{Ready, ready};
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use actix_web::error::InternalError;
+use actix_web::{FromRequest, HttpRequest, HttpResponse, dev::Payload, web};
+use tracing::warn;
+
+use crate::AppContext;
+use crate::web::models::ApiResponse;
+
+pub use crate::web::models::API_KEY_HEADER;
+
+/// 请求的调用方身份，用于审计
+#[derive(Debug, Clone)]
+pub struct Caller {
+    /// 匹配到的 API key 名称；未携带为 anonymous，携带了未知 key 为 unknown-key
+    pub identity: String,
+    pub source_ip: Option<String>,
+}
+
+impl FromRequest for Caller {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(Ok(Caller::from_http(req).0))
+    }
+}
+
+impl Caller {
+    /// 解析调用方，第二个值表示是否携带了已配置的 key
+    fn from_http(req: &HttpRequest) -> (Caller, bool) {
+        let app_context = req.app_data::<web::Data<Arc<AppContext>>>();
+        let presented_key = req
+            .headers()
+            .get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok());
+        let matched = presented_key.and_then(|key| {
+            app_context.and_then(|app_context| {
+                app_context
+                    .admin_config
+                    .api_keys
+                    .iter()
+                    .find(|(_, configured)| configured.expose() == key)
+                    .map(|(name, _)| name.clone())
+            })
+        });
+        let authorized = matched.is_some();
+        let identity = match (presented_key, matched) {
+            (_, Some(name)) => name,
+            (None, None) => "anonymous".to_string(),
+            (Some(_), None) => "unknown-key".to_string(),
+        };
+        let trusted_proxies = app_context
+            .map(|app_context| app_context.admin_config.trusted_proxies.as_slice())
+            .unwrap_or_default();
+        let source_ip = source_ip(req, trusted_proxies);
+        (
+            Caller {
+                identity,
+                source_ip,
+            },
+            authorized,
+        )
+    }
+}
+
+/// 对端是受信任的代理时取代理转发的客户端地址，否则取对端地址，请求头可由调用方任意填写
+fn source_ip(req: &HttpRequest, trusted_proxies: &[IpAddr]) -> Option<String> {
+    let peer = req.peer_addr()?.ip();
+    if trusted_proxies.contains(&peer) {
+        return req
+            .connection_info()
+            .realip_remote_addr()
+            .map(str::to_string);
+    }
+    Some(peer.to_string())
+}
+
+/// 必须携带 admin_config.api_keys 中配置的 key 的调用方，否则返回 401
+#[derive(Debug, Clone)]
+pub struct AuthorizedCaller(pub Caller);
+
+impl FromRequest for AuthorizedCaller {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let (caller, authorized) = Caller::from_http(req);
+        if authorized {
+            return ready(Ok(AuthorizedCaller(caller)));
+        }
+        warn!(
+            "Rejected {} {} from {} ({:?})",
+            req.method(),
+            req.path(),
+            caller.identity,
+            caller.source_ip
+        );
+        let response = HttpResponse::Unauthorized().json(ApiResponse::<()>::error(format!(
+            "A valid {API_KEY_HEADER} header is required."
+        )));
+        ready(Err(
+            InternalError::from_response("unauthorized", response).into()
+        ))
+    }
+}
+
+#[test]
+fn test_source_ip_ignores_forwarded_header_from_untrusted_peer() {
+    use actix_web::test::TestRequest;
+
+    let request = |peer: &str| {
+        TestRequest::default()
+            .peer_addr(peer.parse().unwrap())
+            .insert_header(("X-Forwarded-For", "203.0.113.7"))
+            .to_http_request()
+    };
+    let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+    assert_eq!(
+        source_ip(&request("198.51.100.2:4000"), &[proxy]).as_deref(),
+        Some("198.51.100.2")
+    );
+    assert_eq!(
+        source_ip(&request("10.0.0.1:4000"), &[proxy]).as_deref(),
+        Some("203.0.113.7")
+    );
+    assert_eq!(
+        source_ip(&request("10.0.0.1:4000"), &[]).as_deref(),
+        Some("10.0.0.1")
+    );
+}
@@ -1,23 +1,80 @@
 use std::sync::Arc;
 
-use crate::binlog::processor::DataProcessorTrait;
-use crate::binlog::{OrgDataProcessor, UserDataProcessor};
-use crate::schedule::binlog_sync::{DataType, ModifyOperationLog};
-use crate::web::BinlogParams;
-use crate::{web::models::ApiResponse, AppContext};
-use actix_web::{post, web, HttpResponse, Result};
-use tracing::{error, info, warn};
+use crate::binlog::dead_letter::DeadLetterStore;
+use crate::messages::Message;
+use crate::models::admin_audit::record_admin_action;
+use crate::schedule::binlog_sync::{BINLOG_SYNC_LOCK_KEY, DataType, ModifyOperationLog};
+use crate::utils::redis::RedisLock;
+use crate::web::auth::{AuthorizedCaller, Caller};
+use crate::web::idempotency::IdempotencyKey;
+use crate::web::{BinlogParams, JobAccepted, RouteRegistrar};
+use crate::{AppContext, web::models::ApiResponse};
+use actix_web::{HttpResponse, Result, get, http::StatusCode, post, web};
+use serde::{Deserialize, Serialize};
+use tracing::{Instrument, error, info, info_span, warn};
 
+/// 原有的响应格式：data 为提示文字。需要作业编号时使用 /v2/binlog/sync
 #[post("/binlog/sync")]
 pub async fn binlog_sync(
     app_context: web::Data<Arc<AppContext>>, // 注入 AppContext
     body: web::Json<BinlogParams>,           // 接收 JSON 请求体
+    caller: Caller,
+    idempotency: IdempotencyKey,
 ) -> Result<HttpResponse> {
+    start_binlog_sync(app_context, body, caller, idempotency, |accepted| {
+        accepted.message
+    })
+    .await
+}
+
+/// 与 /binlog/sync 相同，data 为 `JobAccepted`，作业的日志中以 job_id 标识
+#[post("/v2/binlog/sync")]
+pub async fn binlog_sync_v2(
+    app_context: web::Data<Arc<AppContext>>,
+    body: web::Json<BinlogParams>,
+    caller: Caller,
+    idempotency: IdempotencyKey,
+) -> Result<HttpResponse> {
+    start_binlog_sync(app_context, body, caller, idempotency, |accepted| accepted).await
+}
+
+async fn start_binlog_sync<T: Serialize>(
+    app_context: web::Data<Arc<AppContext>>,
+    body: web::Json<BinlogParams>,
+    caller: Caller,
+    idempotency: IdempotencyKey,
+    respond: impl FnOnce(JobAccepted) -> T,
+) -> Result<HttpResponse> {
+    // 重复提交直接返回首次的响应
+    if let Some(response) = idempotency.begin(&app_context).await {
+        return Ok(response);
+    }
+    // 关闭期间不再开始后台作业，作业结束前关闭流程等待其完成
+    let Some(guard) = app_context.shutdown.track() else {
+        idempotency.release(&app_context).await;
+        return Ok(HttpResponse::ServiceUnavailable()
+            .json(ApiResponse::<()>::from_message(Message::ShuttingDown)));
+    };
+    // 审计失败不影响同步
+    if let Err(e) = record_admin_action(
+        &app_context.mysql_pool,
+        "binlog_sync",
+        &caller.identity,
+        caller.source_ip.as_deref(),
+        &*body,
+    )
+    .await
+    {
+        warn!("Failed to record admin audit for binlog_sync: {e:?}");
+    }
     // 克隆必要的配置和连接池，以便在异步任务中使用
-    let app_context = Arc::clone(&app_context);
+    let task_context = Arc::clone(&app_context);
+    let job_id = uuid::Uuid::new_v4().to_string();
     // 1. 获取 BinlogParams 的所有权
     let params = body.into_inner();
-    tokio::spawn(async move {
+    let job = async move {
+        let app_context = task_context;
+        let _guard = guard;
         info!("----------------binlog org sync begin----------------");
         // 2. 构造 logs
         let logs: Vec<ModifyOperationLog> = params
@@ -32,33 +89,234 @@ pub async fn binlog_sync(
             .collect();
 
         let data_type = params.data_type;
-        match data_type {
-            DataType::Org => {
-                let org_processor = OrgDataProcessor::new(Arc::clone(&app_context));
-                // 返回Result，让上层决定如何处理错误
-                if let Err(e) = org_processor.process(logs).await {
-                    error!("Error occurred while manual processing organization data: {e:?}");
-                } else {
-                    info!("Organization data manual processing completed.");
-                }
-            }
-            DataType::User => {
-                let user_processor = UserDataProcessor::new(Arc::clone(&app_context));
-                if let Err(e) = user_processor.process(logs).await {
-                    error!("Error occurred while manual processing user data: {e:?}");
+        match app_context
+            .processor_registry
+            .get(data_type, Arc::clone(&app_context))
+        {
+            Some(processor) => {
+                if let Err(e) = processor.process_logs(logs).await {
+                    error!("Error occurred while manual processing {data_type:?} data: {e:?}");
                 } else {
-                    info!("User data manual processing completed.");
+                    info!("{data_type:?} data manual processing completed.");
                 }
             }
-            _ => {
+            None => {
                 warn!("Unknown or unsupported DataType for processing: {data_type:?}");
             }
         };
         info!("----------------binlog org sync end----------------");
-    });
+    };
+    tokio::spawn(job.instrument(info_span!("manual_job", job_id = %job_id)));
 
     // 立即返回成功响应，因为处理是异步的
-    Ok(HttpResponse::Ok().json(ApiResponse::<String>::success(
-        "syncing, check logs for progress.".to_string(),
-    )))
+    let accepted = JobAccepted {
+        job_id,
+        message: "syncing, check logs for progress.".to_string(),
+    };
+    Ok(idempotency
+        .complete(
+            &app_context,
+            StatusCode::OK,
+            ApiResponse::success(respond(accepted)),
+        )
+        .await)
+}
+
+// 死信列表默认条数与上限
+const DEFAULT_DEAD_LETTER_LIMIT: u32 = 50;
+const MAX_DEAD_LETTER_LIMIT: u32 = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct DeadLetterParams {
+    pub status: Option<String>, // pending / requeued，不传时全部返回
+    pub data_type: Option<DataType>,
+    pub limit: Option<u32>,
+}
+
+/// 查询 binlog_dead_letter 中的死信，按时间倒序。死信包含原始日志，需要管理 key
+#[get("/binlog/dead-letters")]
+pub async fn list_dead_letters(
+    app_context: web::Data<Arc<AppContext>>,
+    query: web::Query<DeadLetterParams>,
+    _caller: AuthorizedCaller,
+) -> Result<HttpResponse> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_DEAD_LETTER_LIMIT)
+        .clamp(1, MAX_DEAD_LETTER_LIMIT);
+    let store = DeadLetterStore::new(app_context.mysql_pool.clone());
+    match store
+        .list(query.status.as_deref(), query.data_type, limit)
+        .await
+    {
+        Ok(records) => Ok(HttpResponse::Ok().json(ApiResponse::success(records))),
+        Err(e) => {
+            error!("Failed to query binlog dead letters: {e:?}");
+            Ok(
+                HttpResponse::InternalServerError().json(ApiResponse::<()>::from_message(
+                    Message::BinlogDeadLettersQueryFailed {
+                        error: e.to_string(),
+                    },
+                )),
+            )
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RequeueParams {
+    pub ids: Vec<u64>, // binlog_dead_letter.id
+}
+
+/// 把 pending 状态的死信重新入队，按数据类型交给对应的处理器异步处理。
+/// 处理期间持有 binlog 同步锁，与定时同步互斥；处理完成后才把死信标记为 requeued，
+/// 中途失败或进程中断时死信仍为 pending。再次失败的日志会写入新的死信，重试次数累加
+#[post("/binlog/dead-letters/requeue")]
+pub async fn requeue_dead_letters(
+    app_context: web::Data<Arc<AppContext>>,
+    body: web::Json<RequeueParams>,
+    caller: AuthorizedCaller,
+    idempotency: IdempotencyKey,
+) -> Result<HttpResponse> {
+    let AuthorizedCaller(caller) = caller;
+    if let Some(response) = idempotency.begin(&app_context).await {
+        return Ok(response);
+    }
+    // 关闭期间不再开始后台作业，作业结束前关闭流程等待其完成
+    let Some(guard) = app_context.shutdown.track() else {
+        idempotency.release(&app_context).await;
+        return Ok(HttpResponse::ServiceUnavailable()
+            .json(ApiResponse::<()>::from_message(Message::ShuttingDown)));
+    };
+    if let Err(e) = record_admin_action(
+        &app_context.mysql_pool,
+        "binlog_dead_letter_requeue",
+        &caller.identity,
+        caller.source_ip.as_deref(),
+        &*body,
+    )
+    .await
+    {
+        warn!("Failed to record admin audit for binlog_dead_letter_requeue: {e:?}");
+    }
+
+    let ttl_ms =
+        u64::try_from(app_context.timeouts.binlog_lock_ttl.as_millis()).unwrap_or(u64::MAX);
+    let lock =
+        match RedisLock::try_acquire(&app_context.redis_mgr, BINLOG_SYNC_LOCK_KEY, ttl_ms).await {
+            Ok(Some(lock)) => lock,
+            // 锁被占用或获取失败时释放幂等 key，调用方可以用同一个 key 稍后重试
+            Ok(None) => {
+                idempotency.release(&app_context).await;
+                return Ok(HttpResponse::Conflict()
+                    .json(ApiResponse::<()>::from_message(Message::BinlogSyncRunning)));
+            }
+            Err(e) => {
+                error!("Failed to acquire binlog sync lock for dead letter requeue: {e:?}");
+                idempotency.release(&app_context).await;
+                return Ok(HttpResponse::InternalServerError().json(
+                    ApiResponse::<()>::from_message(Message::BinlogLockFailed {
+                        error: e.to_string(),
+                    }),
+                ));
+            }
+        };
+
+    let store = DeadLetterStore::new(app_context.mysql_pool.clone());
+    let pending = match store.pending(&body.ids).await {
+        Ok(pending) => pending,
+        Err(e) => {
+            error!("Failed to load binlog dead letters for requeue: {e:?}");
+            if let Err(e) = lock.release(&app_context.redis_mgr).await {
+                error!("Failed to release redis lock after dead letter requeue: {e:?}");
+            }
+            return Ok(idempotency
+                .complete(
+                    &app_context,
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ApiResponse::<()>::from_message(Message::BinlogRequeueFailed {
+                        error: e.to_string(),
+                    }),
+                )
+                .await);
+        }
+    };
+    let count = pending.len();
+    let mut by_type: Vec<(DataType, Vec<(u64, ModifyOperationLog)>)> = Vec::new();
+    for (id, data_type, log) in pending {
+        match by_type
+            .iter_mut()
+            .find(|(registered, _)| *registered == data_type)
+        {
+            Some((_, logs)) => logs.push((id, log)),
+            None => by_type.push((data_type, vec![(id, log)])),
+        }
+    }
+
+    let task_context = Arc::clone(&app_context);
+    let job_id = uuid::Uuid::new_v4().to_string();
+    let job = async move {
+        let app_context = task_context;
+        let _guard = guard;
+        for (data_type, entries) in by_type {
+            let Some(processor) = app_context
+                .processor_registry
+                .get(data_type, Arc::clone(&app_context))
+            else {
+                warn!("No processor registered for requeued {data_type:?} dead letters");
+                continue;
+            };
+            let (ids, logs): (Vec<u64>, Vec<ModifyOperationLog>) = entries.into_iter().unzip();
+            let log_ids: Vec<String> = logs.iter().map(|log| log.id.clone()).collect();
+            let total = logs.len();
+            let outcome = match processor.process_logs(logs).await {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                    error!("Failed to reprocess requeued {data_type:?} dead letters: {e:?}");
+                    continue;
+                }
+            };
+            // 未处理的日志保持 pending，可以再次重新入队
+            let processed: Vec<u64> = ids
+                .into_iter()
+                .zip(log_ids)
+                .filter(|(_, log_id)| !outcome.deferred.iter().any(|log| log.id == *log_id))
+                .map(|(id, _)| id)
+                .collect();
+            match store.mark_requeued(&processed).await {
+                Ok(marked) => info!(
+                    "Reprocessed {total} requeued {data_type:?} dead letters, {marked} marked as requeued."
+                ),
+                Err(e) => error!("Failed to mark reprocessed {data_type:?} dead letters: {e:?}"),
+            }
+        }
+        if let Err(e) = lock.release(&app_context.redis_mgr).await {
+            error!("Failed to release redis lock after dead letter requeue: {e:?}");
+        }
+    };
+    tokio::spawn(job.instrument(info_span!("manual_job", job_id = %job_id)));
+
+    let accepted = JobAccepted {
+        job_id,
+        message: format!("{count} dead letters requeued, check logs for progress."),
+    };
+    Ok(idempotency
+        .complete(&app_context, StatusCode::OK, ApiResponse::success(accepted))
+        .await)
+}
+
+/// binlog 手动同步接口
+pub struct BinlogRoutes;
+
+impl RouteRegistrar for BinlogRoutes {
+    fn name(&self) -> &'static str {
+        "binlog"
+    }
+
+    fn register(&self, cfg: &mut web::ServiceConfig) {
+        cfg.service(binlog_sync)
+            .service(binlog_sync_v2)
+            .service(list_dead_letters)
+            .service(requeue_dead_letters);
+    }
 }
@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use crate::binlog::audit::load_history;
+use crate::schedule::binlog_sync::DataType;
+use crate::web::RouteRegistrar;
+use crate::{AppContext, web::models::ApiResponse};
+use actix_web::{HttpResponse, Result, get, web};
+use serde::Deserialize;
+use tracing::error;
+
+// 默认返回的历史记录条数
+const DEFAULT_HISTORY_LIMIT: u32 = 50;
+// 单次查询最多返回的历史记录条数
+const MAX_HISTORY_LIMIT: u32 = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryParams {
+    pub limit: Option<u32>,
+}
+
+/// 查询实体（机构/用户）经 binlog 同步落库的变更历史
+#[get("/entities/{data_type}/{id}/history")]
+pub async fn entity_history(
+    app_context: web::Data<Arc<AppContext>>, // 注入 AppContext
+    path: web::Path<(DataType, String)>,
+    query: web::Query<HistoryParams>,
+) -> Result<HttpResponse> {
+    let (data_type, entity_id) = path.into_inner();
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_HISTORY_LIMIT)
+        .clamp(1, MAX_HISTORY_LIMIT);
+
+    match load_history(&app_context.mysql_pool, data_type, &entity_id, limit).await {
+        Ok(history) => Ok(HttpResponse::Ok().json(ApiResponse::success(history))),
+        Err(e) => {
+            error!("Failed to load history for {data_type:?} {entity_id}: {e:?}");
+            Ok(
+                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!(
+                    "Failed to load history: {e}"
+                ))),
+            )
+        }
+    }
+}
+
+/// 实体变更历史接口
+pub struct EntityRoutes;
+
+impl RouteRegistrar for EntityRoutes {
+    fn name(&self) -> &'static str {
+        "entity"
+    }
+
+    fn register(&self, cfg: &mut web::ServiceConfig) {
+        cfg.service(entity_history);
+    }
+}
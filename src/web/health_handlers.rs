@@ -0,0 +1,243 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::AppContext;
+use crate::db::query_runner::QueryRunner;
+use crate::utils::circuit_breaker::CircuitState;
+use crate::utils::redis;
+use actix_web::{HttpResponse, Result, get, web};
+use serde::Serialize;
+use tracing::warn;
+
+// 单个依赖检查的超时，探针本身不能被卡住
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 依赖或整体的状态，按严重程度排序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Healthy,
+    /// 非关键依赖不可用，服务仍可工作
+    Degraded,
+    /// 关键依赖不可用
+    Unhealthy,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DependencyHealth {
+    pub name: String,
+    pub status: HealthStatus,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub shutting_down: bool,
+    pub dependencies: Vec<DependencyHealth>,
+}
+
+impl HealthReport {
+    fn new(dependencies: Vec<DependencyHealth>, shutting_down: bool) -> Self {
+        let status = dependencies
+            .iter()
+            .map(|dependency| dependency.status)
+            .max()
+            .unwrap_or(HealthStatus::Healthy);
+        Self {
+            status,
+            shutting_down,
+            dependencies,
+        }
+    }
+}
+
+/// 在超时内执行检查，失败时记为 failure_status
+async fn check<F>(name: impl Into<String>, failure_status: HealthStatus, fut: F) -> DependencyHealth
+where
+    F: Future<Output = anyhow::Result<()>>,
+{
+    let started = Instant::now();
+    let result = match tokio::time::timeout(CHECK_TIMEOUT, fut).await {
+        Ok(result) => result,
+        Err(_) => Err(anyhow::anyhow!("timed out after {CHECK_TIMEOUT:?}")),
+    };
+    dependency_health(name.into(), failure_status, started, result)
+}
+
+fn dependency_health(
+    name: String,
+    failure_status: HealthStatus,
+    started: Instant,
+    result: anyhow::Result<()>,
+) -> DependencyHealth {
+    let latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+    match result {
+        Ok(()) => DependencyHealth {
+            name,
+            status: HealthStatus::Healthy,
+            latency_ms,
+            error: None,
+        },
+        Err(e) => {
+            warn!("Health check of {name} failed: {e:#}");
+            DependencyHealth {
+                name,
+                status: failure_status,
+                latency_ms,
+                error: Some(format!("{e:#}")),
+            }
+        }
+    }
+}
+
+/// 并发检查所有依赖：MySQL 与 Redis 不可用为 unhealthy，网关地址解析失败或网关熔断为 degraded。
+/// ClickHouse 单个节点不可用为 degraded，全部节点不可用时为 unhealthy
+async fn check_dependencies(app_context: &AppContext) -> HealthReport {
+    let mysql = check("mysql", HealthStatus::Unhealthy, async {
+        QueryRunner::new("health_check")
+            .run(sqlx::query("SELECT 1").execute(&app_context.mysql_pool))
+            .await?;
+        Ok(())
+    });
+    let redis = check(
+        "redis",
+        HealthStatus::Unhealthy,
+        redis::ping(&app_context.redis_mgr),
+    );
+    let gateway = check("gateway", HealthStatus::Degraded, async {
+        let open: Vec<String> = app_context
+            .gateway_client
+            .circuit_states()
+            .into_iter()
+            .filter(|(_, state)| *state != CircuitState::Closed)
+            .map(|(target, state)| format!("{} {}", target.as_str(), state.as_str()))
+            .collect();
+        if !open.is_empty() {
+            anyhow::bail!("circuit breaker is {}", open.join(", "));
+        }
+        app_context.gateway_client.resolve_gateway_url().await?;
+        Ok(())
+    });
+    let clickhouse_started = Instant::now();
+    let clickhouse = tokio::time::timeout(
+        CHECK_TIMEOUT,
+        app_context.clickhouse_client.ping_all_nodes(),
+    );
+
+    let (mysql, redis, gateway, clickhouse) = tokio::join!(mysql, redis, gateway, clickhouse);
+    let mut clickhouse_nodes = match clickhouse {
+        Ok(nodes) => nodes
+            .into_iter()
+            .map(|(node, result)| {
+                dependency_health(
+                    format!("clickhouse:{node}"),
+                    HealthStatus::Degraded,
+                    clickhouse_started,
+                    result,
+                )
+            })
+            .collect(),
+        Err(_) => vec![dependency_health(
+            "clickhouse".to_string(),
+            HealthStatus::Degraded,
+            clickhouse_started,
+            Err(anyhow::anyhow!("timed out after {CHECK_TIMEOUT:?}")),
+        )],
+    };
+    require_any_node(&mut clickhouse_nodes);
+    let mut dependencies = vec![mysql, redis, gateway];
+    dependencies.extend(clickhouse_nodes);
+    HealthReport::new(dependencies, app_context.shutdown.is_shutting_down())
+}
+
+// 读写在 ClickHouse 节点间故障转移，有一个节点可用即可工作，全部不可用时升级为 unhealthy
+fn require_any_node(nodes: &mut [DependencyHealth]) {
+    if nodes
+        .iter()
+        .all(|node| node.status != HealthStatus::Healthy)
+    {
+        for node in nodes {
+            node.status = HealthStatus::Unhealthy;
+        }
+    }
+}
+
+/// 存活探针：只表示进程在运行并能处理请求，不检查依赖，依赖故障时重启 pod 无济于事
+#[get("/health")]
+pub async fn health() -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "status": HealthStatus::Healthy })))
+}
+
+/// 就绪探针：关键依赖不可用（含 ClickHouse 全部节点不可用）或正在关闭时返回 503，pod 从服务中摘除；
+/// 部分 ClickHouse 节点或网关异常时仍就绪
+#[get("/readiness")]
+pub async fn readiness(app_context: web::Data<Arc<AppContext>>) -> Result<HttpResponse> {
+    let report = check_dependencies(&app_context).await;
+    let response = if report.status != HealthStatus::Unhealthy && !report.shutting_down {
+        HttpResponse::Ok().json(report)
+    } else {
+        HttpResponse::ServiceUnavailable().json(report)
+    };
+    Ok(response)
+}
+
+#[test]
+fn test_report_takes_worst_status() {
+    let dependency = |name: &str, status| DependencyHealth {
+        name: name.to_string(),
+        status,
+        latency_ms: 1,
+        error: None,
+    };
+    let report = HealthReport::new(
+        vec![
+            dependency("mysql", HealthStatus::Healthy),
+            dependency("clickhouse:a:9000", HealthStatus::Degraded),
+        ],
+        false,
+    );
+    assert_eq!(report.status, HealthStatus::Degraded);
+
+    let report = HealthReport::new(
+        vec![
+            dependency("redis", HealthStatus::Unhealthy),
+            dependency("clickhouse:a:9000", HealthStatus::Degraded),
+        ],
+        false,
+    );
+    assert_eq!(report.status, HealthStatus::Unhealthy);
+    assert_eq!(
+        HealthReport::new(vec![], false).status,
+        HealthStatus::Healthy
+    );
+}
+
+#[test]
+fn test_clickhouse_needs_one_node() {
+    let node = |name: &str, status| DependencyHealth {
+        name: name.to_string(),
+        status,
+        latency_ms: 1,
+        error: None,
+    };
+    let mut nodes = vec![
+        node("clickhouse:a:9000", HealthStatus::Healthy),
+        node("clickhouse:b:9000", HealthStatus::Degraded),
+    ];
+    require_any_node(&mut nodes);
+    assert_eq!(nodes[1].status, HealthStatus::Degraded);
+
+    let mut nodes = vec![
+        node("clickhouse:a:9000", HealthStatus::Degraded),
+        node("clickhouse:b:9000", HealthStatus::Degraded),
+    ];
+    require_any_node(&mut nodes);
+    assert!(
+        nodes
+            .iter()
+            .all(|node| node.status == HealthStatus::Unhealthy)
+    );
+}
@@ -0,0 +1,162 @@
+use std::future::{Ready, ready};
+use std::time::Duration;
+
+use actix_web::error::InternalError;
+use actix_web::http::StatusCode;
+use actix_web::{FromRequest, HttpRequest, HttpResponse, dev::Payload};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::AppContext;
+use crate::utils::redis::{RedisMgr, del_kv, get_kv, set_kv};
+use crate::web::models::ApiResponse;
+
+pub use crate::web::models::IDEMPOTENCY_KEY_HEADER;
+// 返回保存的响应时附带该响应头
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "Idempotent-Replayed";
+const MAX_KEY_LEN: usize = 128;
+// 首次请求处理中的占位值
+const PENDING: &str = "pending";
+// 占位的有效期，处理中的进程退出后该 key 在此之后可以重新提交
+const PENDING_TTL: Duration = Duration::from_secs(600);
+
+/// 保存的首次响应
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredResponse {
+    status: u16,
+    body: serde_json::Value,
+}
+
+/// 请求携带的 Idempotency-Key，按接口路径区分。未携带时不做任何处理
+#[derive(Debug, Clone)]
+pub struct IdempotencyKey {
+    redis_key: Option<String>,
+}
+
+impl FromRequest for IdempotencyKey {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let Some(value) = req.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+            return ready(Ok(IdempotencyKey { redis_key: None }));
+        };
+        match value.to_str() {
+            Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LEN => ready(Ok(IdempotencyKey {
+                redis_key: Some(format!("http:idempotency:{}:{key}", req.path())),
+            })),
+            _ => {
+                let response = HttpResponse::BadRequest().json(ApiResponse::<()>::error(format!(
+                    "{IDEMPOTENCY_KEY_HEADER} must be 1-{MAX_KEY_LEN} visible ASCII characters."
+                )));
+                ready(Err(InternalError::from_response(
+                    "invalid idempotency key",
+                    response,
+                )
+                .into()))
+            }
+        }
+    }
+}
+
+impl IdempotencyKey {
+    /// 相同 key 已有保存的响应时返回该响应，首次请求仍在处理时返回 409；
+    /// 否则占用该 key 并返回 None，由调用方继续处理。Redis 不可用时不做去重
+    pub async fn begin(&self, app_context: &AppContext) -> Option<HttpResponse> {
+        let redis_key = self.redis_key.as_deref()?;
+        match claim(&app_context.redis_mgr, redis_key).await {
+            Ok(None) => None,
+            Ok(Some(stored)) => {
+                info!("Replaying stored response for {redis_key}");
+                Some(
+                    HttpResponse::build(
+                        StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK),
+                    )
+                    .insert_header((IDEMPOTENT_REPLAYED_HEADER, "true"))
+                    .json(stored.body),
+                )
+            }
+            Err(e) if e.is::<InProgress>() => Some(HttpResponse::Conflict().json(
+                ApiResponse::<()>::error(format!(
+                    "A request with the same {IDEMPOTENCY_KEY_HEADER} is still in progress."
+                )),
+            )),
+            Err(e) => {
+                warn!("Idempotency check for {redis_key} failed, processing without it: {e:?}");
+                None
+            }
+        }
+    }
+
+    /// 保存响应并返回，之后相同 key 的请求在 idempotency_ttl 内得到同样的响应
+    pub async fn complete<T: Serialize>(
+        &self,
+        app_context: &AppContext,
+        status: StatusCode,
+        body: ApiResponse<T>,
+    ) -> HttpResponse {
+        let Some(redis_key) = self.redis_key.as_deref() else {
+            return HttpResponse::build(status).json(body);
+        };
+        let ttl = app_context.timeouts.idempotency_ttl;
+        if let Err(e) = store(&app_context.redis_mgr, redis_key, status, &body, ttl).await {
+            warn!("Failed to store idempotent response for {redis_key}: {e:?}");
+        }
+        HttpResponse::build(status).json(body)
+    }
+
+    /// 处理失败时释放占用的 key，调用方可以用同一个 key 重试
+    pub async fn release(&self, app_context: &AppContext) {
+        let Some(redis_key) = self.redis_key.as_deref() else {
+            return;
+        };
+        if let Err(e) = del_kv(&app_context.redis_mgr, redis_key).await {
+            warn!("Failed to release idempotency key {redis_key}: {e:?}");
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Request with the same idempotency key is in progress")]
+struct InProgress;
+
+/// 占用 key：成功返回 None；已有保存的响应时返回该响应
+async fn claim(mgr: &RedisMgr, redis_key: &str) -> Result<Option<StoredResponse>> {
+    let mut conn = mgr.clone();
+    let claimed: Option<String> = redis::cmd("SET")
+        .arg(redis_key)
+        .arg(PENDING)
+        .arg("NX")
+        .arg("EX")
+        .arg(PENDING_TTL.as_secs())
+        .query_async(&mut conn)
+        .await
+        .context("redis SET NX for idempotency key failed")?;
+    if claimed.is_some() {
+        return Ok(None);
+    }
+    match get_kv(mgr, redis_key).await? {
+        Some(value) if value != PENDING => {
+            let stored = serde_json::from_str(&value)
+                .context("Failed to parse stored idempotent response")?;
+            Ok(Some(stored))
+        }
+        _ => Err(InProgress.into()),
+    }
+}
+
+async fn store<T: Serialize>(
+    mgr: &RedisMgr,
+    redis_key: &str,
+    status: StatusCode,
+    body: &ApiResponse<T>,
+    ttl: Duration,
+) -> Result<()> {
+    let stored = StoredResponse {
+        status: status.as_u16(),
+        body: serde_json::to_value(body).context("Failed to serialize response")?,
+    };
+    let value = serde_json::to_string(&stored).context("Failed to serialize response")?;
+    set_kv(mgr, redis_key, &value, Some(ttl.as_secs().max(1))).await
+}
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use actix_web::{HttpResponse, Result, get, put, web};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::logging::LogFilterHandle;
+use crate::models::admin_audit::record_admin_action;
+use crate::web::RouteRegistrar;
+use crate::web::auth::AuthorizedCaller;
+use crate::{AppContext, web::models::ApiResponse};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LogFilterRequest {
+    /// 控制台层的过滤规则（EnvFilter 语法，如 "info,sqlx=warn"），不传时不修改
+    pub console: Option<String>,
+    /// 文件层的过滤规则，不传时不修改
+    pub file: Option<String>,
+}
+
+/// 当前控制台与文件层的日志过滤规则
+#[get("/admin/logging/filter")]
+pub async fn log_filter(app_context: web::Data<Arc<AppContext>>) -> Result<HttpResponse> {
+    let handle = match filter_handle(&app_context) {
+        Ok(handle) => handle,
+        Err(response) => return Ok(response),
+    };
+    match handle.current() {
+        Ok(filters) => Ok(HttpResponse::Ok().json(ApiResponse::success(filters))),
+        Err(e) => {
+            error!("Failed to read log filters: {e:?}");
+            Ok(
+                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!(
+                    "Failed to read log filters: {e}"
+                ))),
+            )
+        }
+    }
+}
+
+/// 运行中修改日志过滤规则，只在内存中生效，重启后恢复为配置中的规则
+#[put("/admin/logging/filter")]
+pub async fn reload_log_filter(
+    app_context: web::Data<Arc<AppContext>>,
+    request: web::Json<LogFilterRequest>,
+    caller: AuthorizedCaller,
+) -> Result<HttpResponse> {
+    let handle = match filter_handle(&app_context) {
+        Ok(handle) => handle,
+        Err(response) => return Ok(response),
+    };
+    let request = request.into_inner();
+    if request.console.is_none() && request.file.is_none() {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+            "At least one of console and file is required.".to_string(),
+        )));
+    }
+
+    let caller = caller.0;
+    // 审计失败不影响修改
+    if let Err(e) = record_admin_action(
+        &app_context.mysql_pool,
+        "log_filter_reload",
+        &caller.identity,
+        caller.source_ip.as_deref(),
+        &request,
+    )
+    .await
+    {
+        warn!("Failed to record admin audit for log_filter_reload: {e:?}");
+    }
+
+    match handle.reload(request.console.as_deref(), request.file.as_deref()) {
+        Ok(filters) => {
+            info!(
+                "Log filters reloaded by {}: console '{}', file '{}'.",
+                caller.identity, filters.console, filters.file
+            );
+            Ok(HttpResponse::Ok().json(ApiResponse::success(filters)))
+        }
+        Err(e) => Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(format!("{e:#}")))),
+    }
+}
+
+fn filter_handle(app_context: &AppContext) -> Result<&LogFilterHandle, HttpResponse> {
+    app_context.log_filter.get().ok_or_else(|| {
+        HttpResponse::ServiceUnavailable().json(ApiResponse::<()>::error(
+            "Log filters are not reloadable in this process.".to_string(),
+        ))
+    })
+}
+
+/// 日志过滤规则查询与热更新接口
+pub struct LoggingRoutes;
+
+impl RouteRegistrar for LoggingRoutes {
+    fn name(&self) -> &'static str {
+        "logging"
+    }
+
+    fn register(&self, cfg: &mut web::ServiceConfig) {
+        cfg.service(log_filter).service(reload_log_filter);
+    }
+}
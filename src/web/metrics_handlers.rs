@@ -0,0 +1,17 @@
+use crate::metrics;
+use actix_web::{HttpResponse, Result, get};
+use tracing::error;
+
+/// Prometheus 抓取接口，挂在根路径下，不经过 /api
+#[get("/metrics")]
+pub async fn prometheus_metrics() -> Result<HttpResponse> {
+    match metrics::gather_text() {
+        Ok(body) => Ok(HttpResponse::Ok()
+            .content_type("text/plain; version=0.0.4; charset=utf-8")
+            .body(body)),
+        Err(e) => {
+            error!("Failed to encode metrics: {e:?}");
+            Ok(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
@@ -1,9 +1,53 @@
+//! HTTP 服务。未启用 server feature 时只包含请求与响应模型，供客户端使用
+
+#[cfg(feature = "server")]
+mod admin_handlers;
+#[cfg(feature = "server")]
+pub mod auth;
+#[cfg(feature = "server")]
 mod binlog_handlers;
+#[cfg(feature = "server")]
+mod entity_handlers;
+#[cfg(feature = "server")]
+mod health_handlers;
+#[cfg(feature = "server")]
+pub mod idempotency;
+#[cfg(feature = "server")]
+mod logging_handlers;
+#[cfg(feature = "server")]
+mod metrics_handlers;
 mod models;
+#[cfg(feature = "server")]
 mod mss_handlers;
+#[cfg(feature = "server")]
+mod routes;
+#[cfg(feature = "server")]
 mod server;
+#[cfg(feature = "server")]
+mod task_handlers;
+#[cfg(feature = "server")]
+mod version_handlers;
 
+#[cfg(feature = "server")]
+pub use admin_handlers::*;
+#[cfg(feature = "server")]
 pub use binlog_handlers::*;
+#[cfg(feature = "server")]
+pub use entity_handlers::*;
+#[cfg(feature = "server")]
+pub use health_handlers::*;
+#[cfg(feature = "server")]
+pub use logging_handlers::*;
+#[cfg(feature = "server")]
+pub use metrics_handlers::*;
 pub use models::*;
+#[cfg(feature = "server")]
 pub use mss_handlers::*;
+#[cfg(feature = "server")]
+pub use routes::{RouteRegistrar, default_registrars};
+#[cfg(feature = "server")]
 pub use server::WebServer;
+#[cfg(feature = "server")]
+pub use task_handlers::*;
+#[cfg(feature = "server")]
+pub use version_handlers::*;
@@ -1,5 +1,20 @@
-use crate::schedule::binlog_sync::DataType;
+//! HTTP 接口的请求与响应模型，服务端与客户端（client feature）共用，
+//! 不依赖 server feature 的依赖
+
+use crate::messages::Message;
+use chrono::{NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// 调用方通过该请求头携带 admin_config.api_keys 中配置的 key
+pub const API_KEY_HEADER: &str = "X-API-Key";
+// 调用方通过该请求头标识一次提交，重复提交返回首次的响应
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+// 培训 ID 允许的最大长度
+const MAX_TRAIN_ID_LEN: usize = 64;
+// 从 Excel 复制时夹带的不可见字符：BOM 与零宽字符
+const INVISIBLE_CHARS: [char; 5] = ['\u{feff}', '\u{200b}', '\u{200c}', '\u{200d}', '\u{2060}'];
 
 #[derive(Debug, Deserialize)]
 pub struct QueryParams {
@@ -9,7 +24,7 @@ pub struct QueryParams {
 }
 
 // 为新的 POST 接口定义请求参数结构体
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct PushDataParams {
     pub begin_date: Option<String>,     // 日期范围的开始日期
     pub end_date: Option<String>,       // 日期范围的结束日期
@@ -20,24 +35,17 @@ pub struct PushDataParams {
 
 impl PushDataParams {
     // 验证参数的互斥性
-    pub fn validate(&self) -> Result<(), String> {
+    pub fn validate(&self) -> Result<(), Message> {
         let has_dates = self.begin_date.is_some() || self.end_date.is_some();
         let has_ids = self.train_ids.is_some();
 
         match (has_dates, has_ids) {
-            (true, true) => Err(
-                "Cannot provide both date range (begin_date/end_date) and train_ids.".to_string(),
-            ),
-            (false, false) => Err(
-                "Must provide either a date range (begin_date/end_date) or train_ids.".to_string(),
-            ),
+            (true, true) => Err(Message::PushParamsConflict),
+            (false, false) => Err(Message::PushParamsMissing),
             (true, false) => {
                 // 如果提供了日期，确保 begin_date 和 end_date 都存在
                 if self.begin_date.is_none() || self.end_date.is_none() {
-                    Err(
-                        "Both begin_date and end_date must be provided if using date range."
-                            .to_string(),
-                    )
+                    Err(Message::PushDateRangeIncomplete)
                 } else {
                     Ok(())
                 }
@@ -45,19 +53,171 @@ impl PushDataParams {
             (false, true) => Ok(()), // 只提供了 trainIds，合理
         }
     }
+
+    /// 规范化 train_ids：去掉不可见字符、拆分粘贴在一起的多个 ID、去空白与重复，
+    /// 不符合格式的 ID 从列表中移除并返回。全部被拒绝时报错，避免查询为空却静默成功
+    pub fn normalize_train_ids(&mut self) -> Result<Vec<RejectedId>, Message> {
+        let Some(raw_ids) = self.train_ids.take() else {
+            return Ok(Vec::new());
+        };
+        let normalized = normalize_ids(&raw_ids);
+        if normalized.ids.is_empty() {
+            return Err(Message::NoValidTrainIds {
+                rejected: format!("{:?}", normalized.rejected),
+            });
+        }
+        self.train_ids = Some(normalized.ids);
+        Ok(normalized.rejected)
+    }
 }
 
-#[derive(Debug, Deserialize)]
+/// 被拒绝的 ID 及原因
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RejectedId {
+    pub id: String,
+    pub reason: String,
+}
+
+/// 规范化后的 ID 列表，保持首次出现的顺序
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct NormalizedIds {
+    pub ids: Vec<String>,
+    pub rejected: Vec<RejectedId>,
+}
+
+pub fn normalize_ids(raw_ids: &[String]) -> NormalizedIds {
+    let mut normalized = NormalizedIds::default();
+    let tokens = raw_ids.iter().flat_map(|raw| {
+        raw.split(|c: char| c == ',' || c == '，' || c == ';' || c.is_whitespace())
+            .map(|token| token.replace(INVISIBLE_CHARS, ""))
+            .filter(|token| !token.is_empty())
+            .collect::<Vec<_>>()
+    });
+    for id in tokens {
+        if let Some(reason) = invalid_id_reason(&id) {
+            normalized.rejected.push(RejectedId { id, reason });
+        } else if !normalized.ids.contains(&id) {
+            normalized.ids.push(id);
+        }
+    }
+    normalized
+}
+
+// 培训 ID 只包含字母、数字、下划线与连字符
+fn invalid_id_reason(id: &str) -> Option<String> {
+    if id.chars().count() > MAX_TRAIN_ID_LEN {
+        Some(format!("longer than {MAX_TRAIN_ID_LEN} characters"))
+    } else if let Some(c) = id
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && *c != '-' && *c != '_')
+    {
+        Some(format!("unexpected character {c:?}"))
+    } else {
+        None
+    }
+}
+
+// 定义binlog类型枚举
+/// 数据类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataType {
+    /// 基准岗位
+    StandardStation,
+    /// 机构
+    Org,
+    /// 用户
+    User,
+}
+
+impl DataType {
+    /// 指标标签使用的名称，与序列化名称一致
+    pub fn as_label(&self) -> &'static str {
+        match self {
+            DataType::StandardStation => "standardstation",
+            DataType::Org => "org",
+            DataType::User => "user",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct BinlogParams {
     pub ids: Vec<String>, // 用户uid或者组织id
     pub data_type: DataType,
 }
 
-#[derive(Debug, Serialize)]
+/// 异步执行的请求返回的任务编号，任务的日志中以 job_id 标识
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JobAccepted {
+    pub job_id: String,
+    pub message: String,
+}
+
+/// pushMss 的响应，附带规范化时被拒绝的 train_ids
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PushJobAccepted {
+    #[serde(flatten)]
+    pub job: JobAccepted,
+    pub rejected_ids: Vec<RejectedId>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PushResultParams {
+    pub begin_date: Option<NaiveDate>, // yyyy-MM-dd，包含
+    pub end_date: Option<NaiveDate>,   // yyyy-MM-dd，包含
+    pub data_type: Option<i32>,        // mss_push_result.type：1 班级 2 讲师 3 学员培训 4 学员档案
+    pub error_code: Option<String>,
+    pub train_id: Option<String>,
+    pub user_id: Option<String>,
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(sqlx::FromRow))]
+pub struct MssPushResult {
+    pub id: String, // 数据库中存储为 VARCHAR(36)
+    pub push_time: NaiveDateTime,
+    pub train_id: Option<String>,
+    pub course_id: Option<String>,
+    pub user_id: Option<String>,
+    pub data_type: Option<i32>, // `type` 是 SQL 关键字，我们使用 `data_type`
+    pub error_msg: Option<String>,
+    pub error_code: Option<String>,
+    pub duration_ms: Option<i64>, // 本条记录从首次请求到拿到响应的总耗时（含重试与休眠）
+    pub http_status: Option<i32>, // MSS 返回的 HTTP 状态码
+    pub attempt_count: Option<i32>, // 实际发送的请求次数
+    pub endpoint: Option<String>, // 推送的 MSS 地址
+    pub run_id: Option<u64>,      // 产生该结果的任务执行编号（task_run.id）
+}
+
+/// 某天的推送成功与失败条数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyPushSummary {
+    pub date: NaiveDate,
+    pub succeeded: i64,
+    pub failed: i64,
+}
+
+/// pushResults 的响应，summary 为满足条件的记录按天统计的成功与失败条数
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PushResultPage {
+    pub page: u32,
+    pub page_size: u32,
+    pub total: i64,
+    pub items: Vec<MssPushResult>,
+    pub summary: Vec<DailyPushSummary>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
     pub message: Option<String>,
+    /// 失败来自提示信息目录时为其编码，调用方按编码匹配而不是解析 message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
 }
 
 impl<T> ApiResponse<T> {
@@ -66,6 +226,7 @@ impl<T> ApiResponse<T> {
             success: true,
             data: Some(data),
             message: None,
+            code: None,
         }
     }
 
@@ -74,6 +235,109 @@ impl<T> ApiResponse<T> {
             success: false,
             data: None,
             message: Some(message),
+            code: None,
         }
     }
+
+    /// 以提示信息目录中的一条作为失败原因，message 为当前语言的描述
+    pub fn from_message(message: Message) -> Self {
+        Self {
+            success: false,
+            data: None,
+            message: Some(message.text()),
+            code: Some(message.code().to_string()),
+        }
+    }
+}
+
+/// 一次执行结束时的行数计数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunTotals {
+    pub fetched: u64,
+    pub pushed: u64,
+    pub failed: u64,
+}
+
+impl std::ops::AddAssign for RunTotals {
+    fn add_assign(&mut self, other: Self) {
+        self.fetched += other.fetched;
+        self.pushed += other.pushed;
+        self.failed += other.failed;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Pending,
+    Running,
+    /// MSS 要求休息，到 throttled_until 后继续
+    Throttled,
+    Succeeded,
+    Failed,
+}
+
+/// 作业中一项的状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobItemStatus {
+    pub state: JobState,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    /// 休息中时为预计恢复的时间
+    pub throttled_until: Option<String>,
+    pub totals: RunTotals,
+    pub error: Option<String>,
+    // 同一项中正在休息的请求数，推送 worker 并发时可能同时休息
+    #[serde(skip)]
+    pub(crate) throttled: u32,
+}
+
+/// 作业结束后的汇总
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobReport {
+    pub items: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub totals: RunTotals,
+    /// 失败的项
+    pub failed_items: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobStatus {
+    pub job_id: String,
+    pub kind: String,
+    pub state: JobState,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub items: BTreeMap<String, JobItemStatus>,
+    /// 结束后设置
+    pub report: Option<JobReport>,
+}
+
+#[test]
+fn test_normalize_ids() {
+    let raw = vec![
+        "\u{feff}TR-001 ".to_string(),
+        "TR-002\r\nTR-001".to_string(),
+        " ".to_string(),
+        "TR\u{200b}-003，TR-004".to_string(),
+        "培训班01".to_string(),
+    ];
+    let normalized = normalize_ids(&raw);
+    assert_eq!(normalized.ids, vec!["TR-001", "TR-002", "TR-003", "TR-004"]);
+    assert_eq!(normalized.rejected.len(), 1);
+    assert_eq!(normalized.rejected[0].id, "培训班01");
+}
+
+#[test]
+fn test_api_response_from_message() {
+    let body =
+        serde_json::to_value(ApiResponse::<()>::from_message(Message::ShuttingDown)).unwrap();
+    assert_eq!(body["success"], false);
+    assert_eq!(body["code"], "SHUTTING_DOWN");
+    assert_eq!(body["message"], "service is shutting down, try again later");
+    // 非目录中的失败不输出 code
+    let body = serde_json::to_value(ApiResponse::<()>::error("boom".to_string())).unwrap();
+    assert!(body.get("code").is_none());
 }
@@ -1,79 +1,324 @@
 use std::sync::Arc;
 
 use crate::{
+    AppContext, PsnDataKind, TaskExecutor,
+    messages::Message,
+    models::admin_audit::record_admin_action,
+    models::push_result::{PushResultFilter, PushResultService},
+    models::run_manifest::run_manifests_by_date,
+    models::task_run_history::{RunTotals, TaskRunRecorder},
+    schedule::job_tracker,
+    schedule::push_executor::preview_push_payload,
+    schedule::status_updates::StatusUpdateCollector,
     schedule::{
         CompositeTask, PsnArchivePushTask, PsnArchiveScPushTask, PsnClassPushTask,
         PsnClassScPushTask, PsnLecturerPushTask, PsnLecturerScPushTask, PsnTrainingPushTask,
         PsnTrainingScPushTask,
-    }, web::{models::ApiResponse, PushDataParams},
-    AppContext,
-    TaskExecutor,
+    },
+    utils::dates,
+    web::{
+        JobAccepted, PushDataParams, PushJobAccepted, PushResultPage, PushResultParams,
+        RouteRegistrar, auth::Caller, idempotency::IdempotencyKey, models::ApiResponse,
+    },
 };
-use actix_web::{post, web, HttpResponse, Result};
+use actix_web::{HttpResponse, Result, get, http::StatusCode, post, web};
 use chrono::NaiveDate;
-use tracing::{error, info, warn};
+use futures::StreamExt;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tracing::{Instrument, error, info, info_span, warn};
 
 #[post("/pxb/pushMss")]
 pub async fn push_mss(
     app_context: web::Data<Arc<AppContext>>, // 注入 AppContext
     body: web::Json<PushDataParams>,         // 接收 JSON 请求体
+    caller: Caller,
+    idempotency: IdempotencyKey,
 ) -> Result<HttpResponse> {
     // 验证请求参数
     if let Err(e) = body.validate() {
-        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)));
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::from_message(e)));
+    }
+    // 规范化 train_ids，格式不对的 ID 不参与推送，在响应中返回
+    let mut body = body.into_inner();
+    let rejected_ids = match body.normalize_train_ids() {
+        Ok(rejected_ids) => rejected_ids,
+        Err(e) => return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::from_message(e))),
+    };
+    if !rejected_ids.is_empty() {
+        warn!("Rejected train_ids for push_mss: {rejected_ids:?}");
+    }
+    // 日期范围无法解析时返回 400，不创建作业
+    let dates_to_process = match (&body.train_ids, &body.begin_date, &body.end_date) {
+        (None, Some(begin_date_str), Some(end_date_str)) => {
+            match dates::parse_date_range(begin_date_str, end_date_str) {
+                Ok(dates) => Some(dates),
+                Err(e) => {
+                    return Ok(HttpResponse::BadRequest()
+                        .json(ApiResponse::<()>::from_message(e.message())));
+                }
+            }
+        }
+        _ => None,
+    };
+    // 重复提交直接返回首次的响应
+    if let Some(response) = idempotency.begin(&app_context).await {
+        return Ok(response);
+    }
+    // 关闭期间不再开始后台作业，作业结束前关闭流程等待其完成
+    let Some(guard) = app_context.shutdown.track() else {
+        idempotency.release(&app_context).await;
+        return Ok(HttpResponse::ServiceUnavailable()
+            .json(ApiResponse::<()>::from_message(Message::ShuttingDown)));
+    };
+    // 审计失败不影响推送
+    if let Err(e) = record_admin_action(
+        &app_context.mysql_pool,
+        "push_mss",
+        &caller.identity,
+        caller.source_ip.as_deref(),
+        &body,
+    )
+    .await
+    {
+        warn!("Failed to record admin audit for push_mss: {e:?}");
     }
     // 克隆必要的配置和连接池，以便在异步任务中使用
-    let app_context = Arc::clone(&app_context);
+    let task_context = Arc::clone(&app_context);
+    let job_id = uuid::Uuid::new_v4().to_string();
 
-    tokio::spawn(async move {
-        info!("----------------pxb mss pushByDate begin----------------");
+    // 每个日期为作业中的一项，按 train_ids 推送时只有一项
+    let items: Vec<Option<String>> = match dates_to_process {
+        Some(dates_to_process) => {
+            info!("Parsed date range: {dates_to_process:?}");
+            if dates_to_process.is_empty() {
+                warn!("{}", Message::NoDatesToProcess);
+            }
+            dates_to_process.into_iter().map(Some).collect()
+        }
+        None if body.train_ids.is_some() => vec![None],
+        None => Vec::new(),
+    };
+    let item_names: Vec<String> = items.iter().map(job_item_name).collect();
+    app_context
+        .job_tracker
+        .start(&job_id, "push_mss", &item_names);
 
-        // 直接从 `body` 结构体中获取数据，不再需要额外的 `clone()`
-        let begin_date_opt = &body.begin_date;
-        let end_date_opt = &body.end_date;
-        let train_ids_opt = &body.train_ids;
-        let is_sichuan_data = &body.is_sichuan_data;
+    let tracked_job_id = job_id.clone();
+    let job = async move {
+        let app_context = task_context;
+        let job_id = tracked_job_id;
+        let _guard = guard;
+        info!("----------------pxb mss pushByDate begin----------------");
+        let train_ids = body.train_ids;
+        let is_sichuan_data = body.is_sichuan_data;
+        let concurrency = app_context.limits.push_date_concurrency.max(1);
 
-        if let Some(ids) = train_ids_opt {
-            // 情况 1: 提供了 train_ids
-            process_push_tasks(
-                Arc::clone(&app_context),
-                None,
-                Some(ids.to_vec()),
-                *is_sichuan_data,
-            )
+        // 各日期是独立的组合任务，同时处理 concurrency 个
+        futures::stream::iter(items)
+            .for_each_concurrent(concurrency, |hit_date| {
+                let app_context = Arc::clone(&app_context);
+                let train_ids = train_ids.clone();
+                let job_id = job_id.as_str();
+                async move {
+                    let item = job_item_name(&hit_date);
+                    info!("--------{item} started--------");
+                    app_context.job_tracker.item_started(job_id, &item);
+                    // 9019 休息期间该项显示为 throttled
+                    let (result, totals) = job_tracker::with_job_item(
+                        Arc::clone(&app_context.job_tracker),
+                        job_id,
+                        &item,
+                        process_push_tasks(
+                            Arc::clone(&app_context),
+                            hit_date,
+                            train_ids,
+                            is_sichuan_data,
+                        ),
+                    )
+                    .await;
+                    let error = result.err().map(|e| format!("{e:#}"));
+                    app_context
+                        .job_tracker
+                        .item_finished(job_id, &item, totals, error);
+                    info!("--------{item} finished--------");
+                }
+            })
             .await;
-        } else if let (Some(begin_date_str), Some(end_date_str)) = (begin_date_opt, end_date_opt) {
-            // 情况 2: 未提供 train_ids，根据日期处理
-            let dates_to_process: Vec<String> =
-                parse_date_range_strings(begin_date_str, end_date_str).unwrap_or_else(|e| {
-                    error!("日期解析错误: {e}");
-                    Vec::new()
-                });
-            info!("解析到的日期范围: {dates_to_process:?}");
-            if dates_to_process.is_empty() {
-                warn!("解析日期后没有要处理的日期。");
-            }
-            // 遍历需要处理的每个日期
-            for current_date in dates_to_process {
-                info!("--------{current_date} 开始处理--------");
-                process_push_tasks(
-                    Arc::clone(&app_context),
-                    Some(current_date.clone()),
-                    None,
-                    *is_sichuan_data,
-                )
-                .await;
-                info!("--------{current_date} 处理完成--------");
+
+        if let Some(report) = app_context.job_tracker.finish(&job_id) {
+            info!(
+                "pushMss job finished: {}/{} succeeded, fetched={}, pushed={}, failed={}",
+                report.succeeded,
+                report.items,
+                report.totals.fetched,
+                report.totals.pushed,
+                report.totals.failed
+            );
+            if report.failed > 0 {
+                warn!("pushMss job failed items: {:?}", report.failed_items);
             }
         }
         info!("----------------pxb mss pushByDate end----------------");
-    });
+    };
+    tokio::spawn(job.instrument(info_span!("manual_job", job_id = %job_id)));
 
     // 立即返回成功响应，因为处理是异步的
-    Ok(HttpResponse::Ok().json(ApiResponse::<String>::success(
-        "pushing, check logs for progress.".to_string(),
-    )))
+    let accepted = PushJobAccepted {
+        job: JobAccepted {
+            message: format!("pushing, check /pxb/jobs/{job_id} for progress."),
+            job_id,
+        },
+        rejected_ids,
+    };
+    Ok(idempotency
+        .complete(&app_context, StatusCode::OK, ApiResponse::success(accepted))
+        .await)
+}
+
+/// 预览某个培训班 ID 推送给 MSS 的请求（不发送），用于排查 MSS 反馈的数据问题。
+/// kind 取值：class、lecturer、training、archive 及对应的 *_sc
+#[get("/pxb/preview/{kind}/{id}")]
+pub async fn preview_push(
+    app_context: web::Data<Arc<AppContext>>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse> {
+    let (kind_name, id) = path.into_inner();
+    let Some(kind) = PsnDataKind::from_name(&kind_name) else {
+        return Ok(
+            HttpResponse::BadRequest().json(ApiResponse::<()>::from_message(
+                Message::UnknownDataKind { kind: kind_name },
+            )),
+        );
+    };
+
+    let encoder = app_context.mss_info_config.encoding.encoder();
+    let cancelled_status = &app_context.train_status.cancelled;
+    match preview_push_payload(
+        kind,
+        &app_context.mysql_pool,
+        encoder,
+        cancelled_status,
+        &id,
+    )
+    .await
+    {
+        Ok(payloads) if payloads.is_empty() => Ok(HttpResponse::NotFound().json(
+            ApiResponse::<()>::error(format!("No {kind_name} data found for id {id}")),
+        )),
+        Ok(payloads) => {
+            let requests: Vec<Value> = payloads
+                .into_iter()
+                .map(|payload| {
+                    json!({
+                        "content_type": payload.content_type,
+                        "content_encoding": payload.content_encoding,
+                        "body": serde_json::from_str::<Value>(&payload.request_json)
+                            .unwrap_or(Value::String(payload.request_json)),
+                    })
+                })
+                .collect();
+            Ok(HttpResponse::Ok().json(ApiResponse::success(requests)))
+        }
+        Err(e) => {
+            error!("Failed to preview {kind_name} payload for {id}: {e:?}");
+            Ok(
+                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!(
+                    "Failed to preview payload: {e}"
+                ))),
+            )
+        }
+    }
+}
+
+// 推送结果每页默认条数与上限
+const DEFAULT_PUSH_RESULT_PAGE_SIZE: u32 = 20;
+const MAX_PUSH_RESULT_PAGE_SIZE: u32 = 500;
+
+/// 分页查询 mss_push_result 中的推送结果，按推送时间倒序，
+/// summary 为满足条件的记录按天统计的成功与失败条数
+#[get("/pxb/pushResults")]
+pub async fn push_results(
+    app_context: web::Data<Arc<AppContext>>,
+    query: web::Query<PushResultParams>,
+) -> Result<HttpResponse> {
+    let params = query.into_inner();
+    if let (Some(begin), Some(end)) = (params.begin_date, params.end_date) {
+        if begin > end {
+            return Ok(
+                HttpResponse::BadRequest().json(ApiResponse::<()>::error(format!(
+                    "begin_date {begin} is after end_date {end}"
+                ))),
+            );
+        }
+    }
+    let page = params.page.unwrap_or(1).max(1);
+    let page_size = params
+        .page_size
+        .unwrap_or(DEFAULT_PUSH_RESULT_PAGE_SIZE)
+        .clamp(1, MAX_PUSH_RESULT_PAGE_SIZE);
+    let filter = PushResultFilter {
+        begin_date: params.begin_date,
+        end_date: params.end_date,
+        data_type: params.data_type,
+        error_code: params.error_code,
+        train_id: params.train_id,
+        user_id: params.user_id,
+    };
+
+    let service = PushResultService::new(app_context.mysql_pool.clone());
+    let result = tokio::try_join!(
+        service.query(&filter, page, page_size),
+        service.daily_summary(&filter),
+    );
+    match result {
+        Ok(((items, total), summary)) => Ok(HttpResponse::Ok().json(ApiResponse::success(
+            PushResultPage {
+                page,
+                page_size,
+                total,
+                items,
+                summary,
+            },
+        ))),
+        Err(e) => {
+            error!("Failed to query push results: {e:?}");
+            Ok(
+                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!(
+                    "Failed to query push results: {e}"
+                ))),
+            )
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunManifestParams {
+    pub date: NaiveDate, // yyyy-MM-dd，清单日期
+}
+
+/// 查询某天的组合推送归档清单，manifest 为签名原文，可据此验证 sha256 与 signature
+#[get("/pxb/runManifests")]
+pub async fn run_manifests(
+    app_context: web::Data<Arc<AppContext>>,
+    query: web::Query<RunManifestParams>,
+) -> Result<HttpResponse> {
+    match run_manifests_by_date(&app_context.mysql_pool, query.date).await {
+        Ok(manifests) => Ok(HttpResponse::Ok().json(ApiResponse::success(manifests))),
+        Err(e) => {
+            error!("Failed to query run manifests: {e:?}");
+            Ok(
+                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!(
+                    "Failed to query run manifests: {e}"
+                ))),
+            )
+        }
+    }
+}
+
+/// 作业中一项的名称：日期，按 train_ids 推送时为 train_ids
+fn job_item_name(hit_date: &Option<String>) -> String {
+    hit_date.clone().unwrap_or_else(|| "train_ids".to_string())
 }
 
 // --- 辅助函数：封装了创建和执行推送任务的逻辑 ---
@@ -82,7 +327,7 @@ async fn process_push_tasks(
     hit_date: Option<String>,
     train_ids: Option<Vec<String>>,
     is_sichuan_data: bool,
-) {
+) -> (anyhow::Result<()>, RunTotals) {
     let task_name_suffix = if train_ids.is_some() {
         "根据培训班ID"
     } else if hit_date.is_some() {
@@ -145,79 +390,46 @@ async fn process_push_tasks(
         ]
     };
     // 创建 CompositeTask 实例
-    let composite_task = Arc::new(CompositeTask::new(composite_tasks, composite_task_name));
+    let composite_task = Arc::new(
+        CompositeTask::new(composite_tasks, composite_task_name)
+            .with_status_batching(StatusUpdateCollector::new(&app_context))
+            .with_manifest(app_context.run_manifest_writer.clone()),
+    );
 
     // 执行 CompositeTask，错误会在 CompositeTask 内部日志记录
-    let _ = composite_task.execute().await;
+    TaskRunRecorder::new(app_context.mysql_pool.clone())
+        .with_analytics(app_context.analytics_exporter.clone())
+        .run_counted(composite_task.as_ref(), "manual")
+        .await
 }
 
-// --- 辅助函数：解析日期范围，包括特殊月份格式 ---
-fn parse_date_range_strings(
-    begin_date_str: &str,
-    end_date_str: &str,
-) -> anyhow::Result<Vec<String>, String> {
-    // 将整个 if-else 块作为表达式，直接返回其结果
-    if let (Ok(current_date), Ok(end_date)) = (
-        NaiveDate::parse_from_str(begin_date_str, "%Y-%m-%d"),
-        NaiveDate::parse_from_str(end_date_str, "%Y-%m-%d"),
-    ) {
-        // 情况 A: 均为标准有效日期
-        if current_date > end_date {
-            return Err(format!(
-                "起始日期 {begin_date_str} 晚于结束日期 {end_date_str}"
-            ));
-        }
-        // 使用迭代器和 collect 生成 Vec，取代可变变量和循环
-        let dates = current_date
-            .iter_days()
-            .take_while(|&d| d <= end_date)
-            .map(|d| d.format("%Y-%m-%d").to_string())
-            .collect();
-        Ok(dates)
-    } else {
-        // 情况 B: 至少有一个日期不是标准格式
-        let begin_splits: Vec<&str> = begin_date_str.split('-').collect();
-        let end_splits: Vec<&str> = end_date_str.split('-').collect();
-
-        if begin_splits.len() != 3 || end_splits.len() != 3 {
-            return Err(format!(
-                "日期格式不完整或无效：{begin_date_str} 或 {end_date_str}"
-            ));
-        }
-        let year_res = begin_splits[0].parse::<u32>();
-        let month_res = begin_splits[1].parse::<u32>();
-        let begin_day_res = begin_splits[2].parse::<u32>();
-        let end_day_res = end_splits[2].parse::<u32>();
-
-        if year_res.is_err() || month_res.is_err() || begin_day_res.is_err() || end_day_res.is_err()
-        {
-            return Err(format!(
-                "日期组件解析失败 (非数字)：{begin_date_str} 或 {end_date_str}"
-            ));
-        }
-
-        let (year, month, begin_day, end_day) = (
-            year_res.unwrap(),
-            month_res.unwrap(),
-            begin_day_res.unwrap(),
-            end_day_res.unwrap(),
-        );
+/// 查询手动推送作业的进度，结束后 report 为汇总结果
+#[get("/pxb/jobs/{job_id}")]
+pub async fn job_status(
+    app_context: web::Data<Arc<AppContext>>,
+    path: web::Path<String>,
+) -> Result<HttpResponse> {
+    let job_id = path.into_inner();
+    match app_context.job_tracker.get(&job_id) {
+        Some(status) => Ok(HttpResponse::Ok().json(ApiResponse::success(status))),
+        None => Ok(HttpResponse::NotFound()
+            .json(ApiResponse::<()>::error(format!("Unknown job: {job_id}")))),
+    }
+}
 
-        if month <= 12 {
-            return Err(format!(
-                "日期格式无效或解析失败：{begin_date_str} 或 {end_date_str}"
-            ));
-        }
+/// MSS 推送相关接口
+pub struct MssRoutes;
 
-        if begin_day > end_day {
-            return Err(format!(
-                "特殊日期范围中，起始日 {begin_day} 晚于结束日 {end_day}"
-            ));
-        }
+impl RouteRegistrar for MssRoutes {
+    fn name(&self) -> &'static str {
+        "mss"
+    }
 
-        let dates = (begin_day..=end_day)
-            .map(|i| format!("{year}-{month:02}-{i:02}"))
-            .collect();
-        Ok(dates)
+    fn register(&self, cfg: &mut web::ServiceConfig) {
+        cfg.service(push_mss)
+            .service(preview_push)
+            .service(push_results)
+            .service(run_manifests)
+            .service(job_status);
     }
 }
@@ -0,0 +1,29 @@
+use actix_web::web;
+
+use crate::AppContext;
+
+/// 一组 HTTP 路由，由各 handler 模块实现。WebServer 启动时把启用的路由组注册到 /api 下，
+/// 新增接口只需在对应模块的 register 中添加
+pub trait RouteRegistrar: Send + Sync + 'static {
+    /// 路由组名称，用于启动日志
+    fn name(&self) -> &'static str;
+
+    /// 返回 false 时整组路由不注册，可按配置关闭某组接口
+    fn enabled(&self, _app_context: &AppContext) -> bool {
+        true
+    }
+
+    fn register(&self, cfg: &mut web::ServiceConfig);
+}
+
+/// 默认注册的所有路由组
+pub fn default_registrars() -> Vec<Box<dyn RouteRegistrar>> {
+    vec![
+        Box::new(super::mss_handlers::MssRoutes),
+        Box::new(super::binlog_handlers::BinlogRoutes),
+        Box::new(super::entity_handlers::EntityRoutes),
+        Box::new(super::admin_handlers::AdminRoutes),
+        Box::new(super::task_handlers::TaskRoutes),
+        Box::new(super::logging_handlers::LoggingRoutes),
+    ]
+}
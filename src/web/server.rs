@@ -1,41 +1,98 @@
 use std::sync::Arc;
 
-use crate::{web::binlog_handlers, web::mss_handlers, AppContext};
-use actix_web::{middleware, web, App, HttpServer};
+use crate::{
+    AppContext,
+    web::health_handlers::{health, readiness},
+    web::metrics_handlers::prometheus_metrics,
+    web::routes::{RouteRegistrar, default_registrars},
+    web::version_handlers::version,
+};
+use actix_web::{App, HttpServer, middleware, web};
 use anyhow::{Context, Result};
 use tracing::info;
 
 pub struct WebServer {
+    host: String,
     port: u16,
     app_context: Arc<AppContext>,
+    registrars: Vec<Box<dyn RouteRegistrar>>,
 }
 
 impl WebServer {
-    pub fn new(port: u16, app_context: Arc<AppContext>) -> Self {
-        WebServer { port, app_context }
+    /// 监听 host:port，使用默认的路由组，按 RouteRegistrar::enabled 过滤
+    pub fn new(host: impl Into<String>, port: u16, app_context: Arc<AppContext>) -> Self {
+        WebServer {
+            host: host.into(),
+            port,
+            app_context,
+            registrars: Vec::new(),
+        }
+        .with_registrars(default_registrars())
     }
 
-    pub async fn start(&self) -> Result<()> {
-        info!("Starting web server on port {}", self.port);
+    /// 追加路由组，未启用的路由组被跳过
+    pub fn with_registrars(mut self, registrars: Vec<Box<dyn RouteRegistrar>>) -> Self {
+        for registrar in registrars {
+            if registrar.enabled(&self.app_context) {
+                self.registrars.push(registrar);
+            } else {
+                info!("Route group {} is disabled", registrar.name());
+            }
+        }
+        self
+    }
+
+    pub async fn start(self) -> Result<()> {
+        info!("Starting web server on {}:{}", self.host, self.port);
+        info!(
+            "Registered route groups: {:?}",
+            self.registrars
+                .iter()
+                .map(|registrar| registrar.name())
+                .collect::<Vec<_>>()
+        );
 
         let app_context = Arc::clone(&self.app_context);
+        let registrars: Arc<[Box<dyn RouteRegistrar>]> = self.registrars.into();
 
-        HttpServer::new(move || {
+        let server = HttpServer::new(move || {
+            let registrars = Arc::clone(&registrars);
             App::new()
                 .app_data(web::Data::new(Arc::clone(&app_context))) // 在每个 worker 线程中克隆一次
                 .wrap(middleware::Logger::default()) // 启用请求日志
                 .wrap(middleware::Compress::default()) // 启用响应压缩
+                .service(prometheus_metrics) // Prometheus 抓取 /metrics
+                .service(version) // 构建信息 /version
+                .service(health) // 探针不在 /api 下，不经过鉴权
+                .service(readiness)
                 .service(
                     web::scope("/api") // 创建一个 /api 范围
-                        .service(mss_handlers::push_mss) // 注册处理函数
-                        .service(binlog_handlers::binlog_sync),
+                        .configure(move |cfg| {
+                            for registrar in registrars.iter() {
+                                registrar.register(cfg);
+                            }
+                        }),
                 )
         })
-        .bind(("127.0.0.1", self.port))
-        .context(format!("Failed to bind web server to port {}", self.port))? // 添加上下文信息
-        .run()
-        .await
-        .context("Web server failed to run or shut down unexpectedly")?; // 添加上下文信息
+        .bind((self.host.as_str(), self.port))
+        .context(format!(
+            "Failed to bind web server to {}:{}",
+            self.host, self.port
+        ))? // 添加上下文信息
+        .disable_signals() // 信号由 ShutdownController 统一处理
+        .run();
+
+        // 关闭被触发后停止接收新连接，等待处理中的请求完成
+        let handle = server.handle();
+        let shutdown = Arc::clone(&self.app_context.shutdown);
+        tokio::spawn(async move {
+            shutdown.cancelled().await;
+            handle.stop(true).await;
+        });
+
+        server
+            .await
+            .context("Web server failed to run or shut down unexpectedly")?; // 添加上下文信息
         info!("Web server shut down cleanly.");
         Ok(()) // 返回 Ok(()) 表示服务器成功启动并完成（通常是外部信号关闭）
     }
@@ -0,0 +1,236 @@
+use std::sync::Arc;
+
+use actix_web::{HttpResponse, Result, get, http::StatusCode, post, put, web};
+use serde::{Deserialize, Serialize};
+use tracing::{Instrument, error, info, info_span, warn};
+
+use crate::models::admin_audit::record_admin_action;
+use crate::models::task_run_history::recent_runs;
+use crate::schedule::task_registry::{TaskControlError, TaskRegistry, TaskStatus};
+use crate::web::auth::{AuthorizedCaller, Caller};
+use crate::web::idempotency::IdempotencyKey;
+use crate::web::{JobAccepted, RouteRegistrar};
+use crate::{AppContext, web::models::ApiResponse};
+
+// 执行历史默认条数与上限
+const DEFAULT_RUN_HISTORY_LIMIT: u32 = 50;
+const MAX_RUN_HISTORY_LIMIT: u32 = 500;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ScheduleRequest {
+    pub cron_schedule: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TaskRunParams {
+    pub task_name: Option<String>,
+    pub status: Option<String>, // running / success / failed，不传时全部返回
+    pub limit: Option<u32>,
+}
+
+/// 调度器注册的 cron 任务：cron 表达式、是否暂停、最近一次运行的时间与结果、下一次触发时间
+#[get("/tasks")]
+pub async fn list_tasks(app_context: web::Data<Arc<AppContext>>) -> Result<HttpResponse> {
+    match registry(&app_context) {
+        Ok(registry) => Ok(HttpResponse::Ok().json(ApiResponse::success(registry.list()))),
+        Err(response) => Ok(response),
+    }
+}
+
+/// 查询 task_run 中最近的执行，按开始时间倒序
+#[get("/tasks/runs")]
+pub async fn list_task_runs(
+    app_context: web::Data<Arc<AppContext>>,
+    query: web::Query<TaskRunParams>,
+) -> Result<HttpResponse> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_RUN_HISTORY_LIMIT)
+        .clamp(1, MAX_RUN_HISTORY_LIMIT);
+    match recent_runs(
+        &app_context.mysql_pool,
+        query.task_name.as_deref(),
+        query.status.as_deref(),
+        limit,
+    )
+    .await
+    {
+        Ok(runs) => Ok(HttpResponse::Ok().json(ApiResponse::success(runs))),
+        Err(e) => {
+            error!("Failed to query task run history: {e:?}");
+            Ok(
+                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!(
+                    "Failed to query task run history: {e}"
+                ))),
+            )
+        }
+    }
+}
+
+/// 立即在后台运行一次任务，任务正在运行时返回 409
+#[post("/tasks/{name}/trigger")]
+pub async fn trigger_task(
+    app_context: web::Data<Arc<AppContext>>,
+    path: web::Path<String>,
+    caller: AuthorizedCaller,
+    idempotency: IdempotencyKey,
+) -> Result<HttpResponse> {
+    let name = path.into_inner();
+    let registry = match registry(&app_context) {
+        Ok(registry) => registry,
+        Err(response) => return Ok(response),
+    };
+    // 重复提交直接返回首次的响应，不重复运行
+    if let Some(response) = idempotency.begin(&app_context).await {
+        return Ok(response);
+    }
+    let run = match registry.trigger(&name) {
+        Ok(run) => run,
+        Err(e) => {
+            idempotency.release(&app_context).await;
+            return Ok(control_error(e));
+        }
+    };
+    audit(&app_context, "task_trigger", &caller.0, &name, None).await;
+
+    let job_id = uuid::Uuid::new_v4().to_string();
+    info!(
+        "Task '{name}' triggered by {} as job {job_id}",
+        caller.0.identity
+    );
+    tokio::spawn(run.instrument(info_span!("manual_job", job_id = %job_id)));
+
+    let accepted = JobAccepted {
+        job_id,
+        message: format!("Task '{name}' started, check logs for progress."),
+    };
+    Ok(idempotency
+        .complete(&app_context, StatusCode::OK, ApiResponse::success(accepted))
+        .await)
+}
+
+/// 暂停任务的定时触发，重启后恢复为配置中的计划
+#[post("/tasks/{name}/pause")]
+pub async fn pause_task(
+    app_context: web::Data<Arc<AppContext>>,
+    path: web::Path<String>,
+    caller: AuthorizedCaller,
+) -> Result<HttpResponse> {
+    let name = path.into_inner();
+    let registry = match registry(&app_context) {
+        Ok(registry) => registry,
+        Err(response) => return Ok(response),
+    };
+    audit(&app_context, "task_pause", &caller.0, &name, None).await;
+    Ok(status_response(registry.pause(&name).await))
+}
+
+/// 恢复已暂停任务的定时触发
+#[post("/tasks/{name}/resume")]
+pub async fn resume_task(
+    app_context: web::Data<Arc<AppContext>>,
+    path: web::Path<String>,
+    caller: AuthorizedCaller,
+) -> Result<HttpResponse> {
+    let name = path.into_inner();
+    let registry = match registry(&app_context) {
+        Ok(registry) => registry,
+        Err(response) => return Ok(response),
+    };
+    audit(&app_context, "task_resume", &caller.0, &name, None).await;
+    Ok(status_response(registry.resume(&name).await))
+}
+
+/// 修改任务的 cron 表达式（调度器时区），只在内存中生效，重启后恢复为配置中的计划
+#[put("/tasks/{name}/schedule")]
+pub async fn reschedule_task(
+    app_context: web::Data<Arc<AppContext>>,
+    path: web::Path<String>,
+    request: web::Json<ScheduleRequest>,
+    caller: AuthorizedCaller,
+) -> Result<HttpResponse> {
+    let name = path.into_inner();
+    let registry = match registry(&app_context) {
+        Ok(registry) => registry,
+        Err(response) => return Ok(response),
+    };
+    let cron_schedule = request.into_inner().cron_schedule;
+    audit(
+        &app_context,
+        "task_reschedule",
+        &caller.0,
+        &name,
+        Some(&cron_schedule),
+    )
+    .await;
+    Ok(status_response(
+        registry.reschedule(&name, &cron_schedule).await,
+    ))
+}
+
+fn registry(app_context: &AppContext) -> Result<&Arc<TaskRegistry>, HttpResponse> {
+    app_context.task_registry.get().ok_or_else(|| {
+        HttpResponse::ServiceUnavailable().json(ApiResponse::<()>::error(
+            "Tasks are available after the scheduler registers them.".to_string(),
+        ))
+    })
+}
+
+// 审计失败不影响任务控制
+async fn audit(
+    app_context: &AppContext,
+    action: &str,
+    caller: &Caller,
+    name: &str,
+    cron_schedule: Option<&str>,
+) {
+    if let Err(e) = record_admin_action(
+        &app_context.mysql_pool,
+        action,
+        &caller.identity,
+        caller.source_ip.as_deref(),
+        &serde_json::json!({ "task": name, "cron_schedule": cron_schedule }),
+    )
+    .await
+    {
+        warn!("Failed to record admin audit for {action}: {e:?}");
+    }
+}
+
+fn status_response(result: Result<TaskStatus, TaskControlError>) -> HttpResponse {
+    match result {
+        Ok(status) => HttpResponse::Ok().json(ApiResponse::success(status)),
+        Err(e) => control_error(e),
+    }
+}
+
+fn control_error(error: TaskControlError) -> HttpResponse {
+    let status = match &error {
+        TaskControlError::NotFound(_) => StatusCode::NOT_FOUND,
+        TaskControlError::AlreadyPaused(_)
+        | TaskControlError::NotPaused(_)
+        | TaskControlError::AlreadyRunning(_) => StatusCode::CONFLICT,
+        TaskControlError::ShuttingDown(_) => StatusCode::SERVICE_UNAVAILABLE,
+        TaskControlError::InvalidSchedule(_) => StatusCode::BAD_REQUEST,
+        TaskControlError::Scheduler(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    HttpResponse::build(status).json(ApiResponse::<()>::error(error.to_string()))
+}
+
+/// 任务查询与控制接口
+pub struct TaskRoutes;
+
+impl RouteRegistrar for TaskRoutes {
+    fn name(&self) -> &'static str {
+        "tasks"
+    }
+
+    fn register(&self, cfg: &mut web::ServiceConfig) {
+        cfg.service(list_tasks)
+            .service(list_task_runs)
+            .service(trigger_task)
+            .service(pause_task)
+            .service(resume_task)
+            .service(reschedule_task);
+    }
+}
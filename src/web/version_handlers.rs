@@ -0,0 +1,9 @@
+use crate::build_info::BuildInfo;
+use crate::web::ApiResponse;
+use actix_web::{HttpResponse, Result, get};
+
+/// 构建信息，挂在根路径下，便于确认线上运行的版本
+#[get("/version")]
+pub async fn version() -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(ApiResponse::success(BuildInfo::current())))
+}
@@ -0,0 +1,73 @@
+//! ServicekitClient 对本服务 WebServer 的集成测试，需要配置中的 MySQL 与 Redis 可用。
+//! 运行：cargo test --features client --test client_integration_test -- --ignored
+#![cfg(all(feature = "client", feature = "server"))]
+
+use anyhow::{Context, Result};
+use reqwest::StatusCode;
+use servicekit::client::{ClientError, ServicekitClient};
+use servicekit::web::{PushDataParams, PushResultParams};
+use servicekit::{AppConfig, AppContext, WebServer};
+use std::sync::Arc;
+use std::time::Duration;
+
+// 测试使用的端口，避免与本地运行的服务冲突
+const TEST_PORT: u16 = 18089;
+
+/// 在后台启动 WebServer，返回指向它的客户端
+async fn start_server() -> Result<(Arc<AppContext>, ServicekitClient)> {
+    let app_config = AppConfig::new().context("Failed to load application configuration")?;
+    let app_context = Arc::new(AppContext::new(&app_config).await?);
+    let server = WebServer::new("127.0.0.1", TEST_PORT, Arc::clone(&app_context));
+    tokio::spawn(server.start());
+    // 等待端口开始监听
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let client = ServicekitClient::new(&format!("http://127.0.0.1:{TEST_PORT}"))?;
+    Ok((app_context, client))
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_client_against_web_server() -> Result<()> {
+    let (app_context, client) = start_server().await?;
+
+    // 未知作业返回 None
+    assert!(client.job_status("no-such-job").await?.is_none());
+
+    // 参数校验失败映射为 ClientError::Api
+    let invalid = PushDataParams {
+        begin_date: None,
+        end_date: None,
+        train_ids: None,
+        is_sichuan_data: false,
+    };
+    match client.push_mss(&invalid, None).await {
+        Err(ClientError::Api { status, .. }) => assert_eq!(status, StatusCode::BAD_REQUEST),
+        other => panic!("Expected a 400 from pushMss, got {other:?}"),
+    }
+
+    // 推送后可以按返回的 job_id 查询进度
+    let params = PushDataParams {
+        begin_date: None,
+        end_date: None,
+        train_ids: Some(vec!["client-integration-test".to_string()]),
+        is_sichuan_data: false,
+    };
+    let accepted = client.push_mss(&params, None).await?;
+    let status = client
+        .job_status(&accepted.job.job_id)
+        .await?
+        .context("Job accepted by pushMss is not tracked")?;
+    assert_eq!(status.kind, "push_mss");
+
+    let page = client
+        .push_results(&PushResultParams {
+            page_size: Some(5),
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(page.page, 1);
+    assert!(page.items.len() <= 5);
+
+    app_context.shutdown.trigger();
+    Ok(())
+}
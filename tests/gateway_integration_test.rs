@@ -1,16 +1,18 @@
+#![cfg(feature = "server")]
+
 use anyhow::{Context, Result};
 use servicekit::{
-    logging::LocalTimer, schedule::binlog_sync::BinlogSyncTask, AppConfig, AppContext,
+    AppConfig, AppContext, logging::LocalTimer, schedule::binlog_sync::BinlogSyncTask,
 };
 
 use servicekit::context::RedisContext;
-use servicekit::utils::redis::{del_kv, get_kv, set_kv, RedisLock, RedisMgr};
 use servicekit::utils::MapToProcessError;
+use servicekit::utils::redis::{RedisLock, RedisMgr, del_kv, get_kv, set_kv};
 use std::sync::{Arc, Once};
 use std::time::Duration;
 use tokio::task::JoinHandle;
 use tracing::info;
-use tracing_subscriber::{self, fmt::TestWriter, FmtSubscriber};
+use tracing_subscriber::{self, FmtSubscriber, fmt::TestWriter};
 
 // 定义日志初始化函数
 // 使用 Once 确保日志订阅者只设置一次，避免重复初始化错误
@@ -46,15 +48,7 @@ async fn test_invoke_gateway_service_real_success() -> Result<()> {
     let app_config = AppConfig::new().context("Failed to load application configuration")?;
 
     // 3. 创建AppContext实例
-    let app_context = AppContext::new(
-        &app_config.database_url,
-        Arc::clone(&app_config.mss_info_config),
-        Arc::clone(&app_config.telecom_config),
-        Arc::clone(&app_config.clickhouse_config),
-        Arc::clone(&app_config.redis_config),
-        app_config.provinces,
-    )
-    .await?;
+    let app_context = AppContext::new(&app_config).await?;
     let app_context_arc = Arc::new(app_context);
 
     let binlog_sync_task = BinlogSyncTask::new(app_context_arc.clone());
@@ -92,15 +86,7 @@ async fn test_redislock_concurrent_acquire_and_release() -> Result<()> {
     let app_config = AppConfig::new().context("Failed to load application configuration")?;
     // let app_config_arc = Arc::new(app_config);
 
-    let app_context = AppContext::new(
-        &app_config.database_url,
-        Arc::clone(&app_config.mss_info_config),
-        Arc::clone(&app_config.telecom_config),
-        Arc::clone(&app_config.clickhouse_config),
-        Arc::clone(&app_config.redis_config),
-        app_config.provinces,
-    )
-    .await?;
+    let app_context = AppContext::new(&app_config).await?;
     let app_context_arc = Arc::new(app_context);
 
     let redis_mgr = app_context_arc.redis_mgr.clone();